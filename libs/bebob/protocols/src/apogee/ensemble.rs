@@ -88,6 +88,11 @@ use ta1394::ccm::{SignalAddr, SignalSubunitAddr, SignalUnitAddr};
 use ta1394::MUSIC_SUBUNIT_0;
 
 /// The protocol implementation for media and sampling clock of Ensemble FireWire.
+///
+/// NOTE: The status of lock to external source of sampling clock is not identified yet in the
+/// response of HwStatus command, while the unit keeps silent when selecting the source without
+/// signal. Verification of lock and rollback to the previous source after timeout, as the vendor
+/// application does, is postponed until the status is identified.
 #[derive(Default)]
 pub struct EnsembleClkProtocol;
 
@@ -219,10 +224,12 @@ impl From<&EnsembleInputParameters> for Vec<EnsembleCmd> {
 
 impl EnsembleParameterProtocol<EnsembleInputParameters> for BebobAvc {}
 
-/// The structure for parameters of analog/digital outputs.
+/// The structure for parameters of analog/digital outputs. The volumes are between VOL_MIN and
+/// VOL_MAX. They are also changed by hardware knob.
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
 pub struct EnsembleOutputParameters {
     pub vol: u8,
+    /// For headphone-1/2 and headphone-3/4.
     pub headphone_vols: [u8; 2],
     pub levels: [OutputNominalLevel; 8],
     pub opt_iface_mode: OptIfaceMode,
@@ -245,7 +252,7 @@ impl From<&EnsembleOutputParameters> for Vec<EnsembleCmd> {
             .for_each(|(i, &vol)| {
                 cmds.push(EnsembleCmd::OutVol(
                     i,
-                    EnsembleOutputParameters::VOL_MAX - vol,
+                    EnsembleOutputParameters::VOL_MAX - vol.min(EnsembleOutputParameters::VOL_MAX),
                 ))
             });
 
@@ -613,44 +620,48 @@ impl EnsembleMeterProtocol {
         let mut op = EnsembleOperation::new(cmd);
         avc.control(&AvcAddr::Unit, &mut op, timeout_ms).map(|_| {
             if let EnsembleCmd::HwStatusLong(frame) = &op.cmd {
-                let val = (frame[SELECT_POS] >> KNOB_IN_TARGET_SHIFT) & KNOB_IN_TARGET_MASK;
-                meter.knob_input_target = match val & 0x03 {
-                    3 => KnobInputTarget::Mic3,
-                    2 => KnobInputTarget::Mic2,
-                    1 => KnobInputTarget::Mic1,
-                    _ => KnobInputTarget::Mic0,
-                };
+                Self::parse_long_frame(meter, frame);
+            }
+        })
+    }
 
-                let val = (frame[SELECT_POS] >> KNOB_OUT_TARGET_SHIFT) & KNOB_OUT_TARGET_MASK;
-                meter.knob_output_target = match val {
-                    4 => KnobOutputTarget::HeadphonePair1,
-                    2 => KnobOutputTarget::HeadphonePair0,
-                    _ => KnobOutputTarget::AnalogOutputPair0,
-                };
+    fn parse_long_frame(meter: &mut EnsembleMeter, frame: &[u8]) {
+        let val = (frame[SELECT_POS] >> KNOB_IN_TARGET_SHIFT) & KNOB_IN_TARGET_MASK;
+        meter.knob_input_target = match val & 0x03 {
+            3 => KnobInputTarget::Mic3,
+            2 => KnobInputTarget::Mic2,
+            1 => KnobInputTarget::Mic1,
+            _ => KnobInputTarget::Mic0,
+        };
 
-                IN_GAIN_POS
-                    .iter()
-                    .zip(meter.knob_input_vals.iter_mut())
-                    .for_each(|(&i, m)| *m = frame[i]);
+        let val = (frame[SELECT_POS] >> KNOB_OUT_TARGET_SHIFT) & KNOB_OUT_TARGET_MASK;
+        meter.knob_output_target = match val {
+            4 => KnobOutputTarget::HeadphonePair1,
+            2 => KnobOutputTarget::HeadphonePair0,
+            _ => KnobOutputTarget::AnalogOutputPair0,
+        };
 
-                OUT_VOL_POS
-                    .iter()
-                    .zip(meter.knob_output_vals.iter_mut())
-                    .for_each(|(&i, m)| {
-                        *m = Self::OUT_KNOB_VAL_MAX - (frame[i] & Self::OUT_KNOB_VAL_MAX);
-                    });
+        IN_GAIN_POS
+            .iter()
+            .zip(meter.knob_input_vals.iter_mut())
+            .for_each(|(&i, m)| *m = frame[i]);
 
-                IN_METER_POS
-                    .iter()
-                    .zip(meter.phys_inputs.iter_mut())
-                    .for_each(|(&i, m)| *m = frame[i]);
+        OUT_VOL_POS
+            .iter()
+            .zip(meter.knob_output_vals.iter_mut())
+            .for_each(|(&i, m)| {
+                *m = Self::OUT_KNOB_VAL_MAX - (frame[i] & Self::OUT_KNOB_VAL_MAX);
+            });
 
-                OUT_METER_POS
-                    .iter()
-                    .zip(meter.phys_outputs.iter_mut())
-                    .for_each(|(&i, m)| *m = frame[i]);
-            }
-        })
+        IN_METER_POS
+            .iter()
+            .zip(meter.phys_inputs.iter_mut())
+            .for_each(|(&i, m)| *m = frame[i]);
+
+        OUT_METER_POS
+            .iter()
+            .zip(meter.phys_outputs.iter_mut())
+            .for_each(|(&i, m)| *m = frame[i]);
     }
 }
 
@@ -1367,4 +1378,34 @@ mod test {
             EnsembleCmd::from(Into::<Vec<u8>>::into(&cmd).as_slice())
         );
     }
+
+    #[test]
+    fn output_params_to_cmds() {
+        let params = EnsembleOutputParameters {
+            vol: 0x7f,
+            headphone_vols: [0x40, 0xff],
+            ..Default::default()
+        };
+        let cmds = Vec::<EnsembleCmd>::from(&params);
+        assert_eq!(cmds[0], EnsembleCmd::OutVol(0, 0x00));
+        assert_eq!(cmds[1], EnsembleCmd::OutVol(1, 0x3f));
+        assert_eq!(cmds[2], EnsembleCmd::OutVol(2, 0x00));
+
+        assert_eq!(Into::<Vec<u8>>::into(&cmds[1]), vec![0xf6, 0x01, 0x3f]);
+        assert_eq!(Into::<Vec<u8>>::into(&cmds[2]), vec![0xf6, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn meter_knob_output_vals() {
+        let mut frame = [0; METER_LONG_FRAME_SIZE];
+        frame[SELECT_POS] = 0x04;
+        frame[OUT_VOL_POS[0]] = 0x7f;
+        frame[OUT_VOL_POS[1]] = 0x20;
+        frame[OUT_VOL_POS[2]] = 0x6f;
+
+        let mut meter = EnsembleMeter::default();
+        EnsembleMeterProtocol::parse_long_frame(&mut meter, &frame);
+        assert_eq!(meter.knob_output_target, KnobOutputTarget::HeadphonePair1);
+        assert_eq!(meter.knob_output_vals, [0x00, 0x5f, 0x10]);
+    }
 }
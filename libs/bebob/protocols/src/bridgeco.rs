@@ -8,7 +8,9 @@
 
 use glib::{Error, FileError};
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::general::{PlugInfo, SubunitInfo};
 use ta1394::stream_format::{AmStream, StreamFormat, SupportStatus};
@@ -1546,7 +1548,8 @@ impl BcoBootloaderProtocol {
         let mut size = Self::SIZE_WITH_DEBUGGER;
 
         let mut buf = vec![0; size];
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             DM_BCO_BOOTLOADER_INFO_OFFSET,
@@ -1556,7 +1559,8 @@ impl BcoBootloaderProtocol {
         )
         .or_else(|_| {
             size = Self::SIZE_WITHOUT_DEBUGGER;
-            req.transaction_sync(
+            transaction_sync(
+                req,
                 node,
                 FwTcode::ReadBlockRequest,
                 DM_BCO_BOOTLOADER_INFO_OFFSET,
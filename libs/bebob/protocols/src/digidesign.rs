@@ -48,7 +48,9 @@
 //!   maximum size: 0x180000
 //! ```
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use super::*;
 
@@ -89,7 +91,8 @@ impl Mbox2proIoProtocol {
     pub fn init(req: &FwReq, node: &FwNode, timeout_ms: u32) -> Result<(), Error> {
         let mut frame = [0;12];
         frame[0] = 1;
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteBlockRequest,
             DM_APPL_PARAM_OFFSET,
@@ -37,7 +37,9 @@ pub mod saffireproio;
 
 use glib::Error;
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::{general::*, *};
 
@@ -473,7 +475,8 @@ pub fn saffire_read_quadlet(
 ) -> Result<(), Error> {
     assert_eq!(buf.len(), 4);
 
-    req.transaction_sync(
+    transaction_sync(
+        req,
         node,
         FwTcode::ReadQuadletRequest,
         READ_OFFSET + offset as u64,
@@ -515,7 +518,8 @@ pub fn saffire_read_quadlets(
         if count == 1 {
             saffire_read_quadlet(req, node, prev_offset, frame, timeout_ms)
         } else {
-            req.transaction_sync(
+            transaction_sync(
+                req,
                 node,
                 FwTcode::ReadBlockRequest,
                 READ_OFFSET + prev_offset as u64,
@@ -545,7 +549,8 @@ pub fn saffire_write_quadlet(
     assert_eq!(buf.len(), 4);
 
     let mut frame = buf.to_vec();
-    req.transaction_sync(
+    transaction_sync(
+        req,
         node,
         FwTcode::WriteQuadletRequest,
         WRITE_OFFSET + offset as u64,
@@ -579,7 +584,8 @@ pub fn saffire_write_quadlets(
             frame
         });
 
-    req.transaction_sync(
+    transaction_sync(
+        req,
         node,
         FwTcode::WriteBlockRequest,
         WRITE_OFFSET,
@@ -29,6 +29,9 @@ use ta1394::{amdtp::*, audio::*, ccm::*, general::*, *};
 
 use bridgeco::*;
 
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// The offset for specific purposes in DM1000/DM1100/DM1500 ASICs.
 pub const DM_APPL_OFFSET: u64 = 0xffc700000000;
 pub const DM_APPL_METER_OFFSET: u64 = DM_APPL_OFFSET + 0x00600000;
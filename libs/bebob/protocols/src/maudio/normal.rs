@@ -184,7 +184,9 @@
 //!   maximum size: 0x180000
 //! ```
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::ccm::{SignalAddr, SignalSubunitAddr, SignalUnitAddr};
 use ta1394::MUSIC_SUBUNIT_0;
@@ -842,7 +844,8 @@ pub trait MaudioNormalMeterProtocol {
         let pos = frame.len() - 4;
         bitmap.copy_from_slice(&frame[pos..]);
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             DM_APPL_METER_OFFSET,
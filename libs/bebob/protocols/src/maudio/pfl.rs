@@ -72,7 +72,9 @@
 //!   maximum size: 0x12fb24
 //! ```
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::ccm::{SignalAddr, SignalSubunitAddr, SignalUnitAddr};
 use ta1394::MUSIC_SUBUNIT_0;
@@ -173,7 +175,8 @@ impl PflMeterProtocol {
     ) -> Result<(), Error> {
         let frame = &mut meter.cache;
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             DM_APPL_METER_OFFSET,
@@ -244,7 +247,8 @@ impl PflInputParametersProtocol {
         let val = params.force_smux as u32;
         cache[20..24].copy_from_slice(&val.to_be_bytes());
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             hinawa::FwTcode::WriteBlockRequest,
             DM_APPL_PARAM_OFFSET,
@@ -62,7 +62,9 @@
 //!   maximum size: 0x180000
 //! ```
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use crate::*;
 
@@ -214,7 +216,8 @@ impl MaudioSpecialMeterProtocol {
         let mut bitmap1 = [0; 4];
         bitmap1.copy_from_slice(&frame[(METER_SIZE - 4)..]);
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             DM_APPL_METER_OFFSET,
@@ -331,7 +334,8 @@ impl MaudioSpecialStateCache {
         timeout_ms: u32,
     ) -> Result<(), Error> {
         (0..CACHE_SIZE).step_by(4).try_for_each(|pos| {
-            req.transaction_sync(
+            transaction_sync(
+                req,
                 node,
                 FwTcode::WriteQuadletRequest,
                 DM_APPL_PARAM_OFFSET + pos as u64,
@@ -776,7 +780,8 @@ pub trait MaudioSpecialParameterProtocol<T: MaudioSpecialParameterOperation + Co
         (0..CACHE_SIZE).step_by(4)
             .try_for_each(|pos| {
                 if new[pos..(pos + 4)] != cache.0[pos..(pos + 4)] {
-                    req.transaction_sync(
+                    transaction_sync(
+                        req,
                         node,
                         FwTcode::WriteQuadletRequest,
                         DM_APPL_PARAM_OFFSET + pos as u64,
@@ -46,7 +46,9 @@
 //!   maximum size: 0x180000
 //! ```
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::{*, general::*};
 
@@ -180,7 +182,8 @@ pub trait Inspire1394MeterOperation {
         timeout_ms: u32,
     ) -> Result<(), Error> {
         let frame = &mut meter.frame;
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             DM_APPL_METER_OFFSET,
@@ -883,6 +883,13 @@ fn output_nominal_level_to_str(level: &OutputNominalLevel) -> &str {
 #[derive(Default)]
 struct OutputCtl(EnsembleOutputParameters, Vec<ElemId>);
 
+fn clamp_vol(val: i32) -> u8 {
+    val.clamp(
+        EnsembleOutputParameters::VOL_MIN as i32,
+        EnsembleOutputParameters::VOL_MAX as i32,
+    ) as u8
+}
+
 const OUTPUT_LEVEL_NAME: &str = "output-level";
 const OUTPUT_VOL_NAME: &str = "output-volume";
 const HP_VOL_NAME: &str = "headphone-volume";
@@ -1018,7 +1025,7 @@ impl<'a> OutputCtl {
                 let mut vals = [0];
                 elem_value.get_int(&mut vals);
                 let mut params = self.0.clone();
-                params.vol = vals[0] as u8;
+                params.vol = clamp_vol(vals[0]);
                 avc.update_params(&params, &mut self.0, timeout_ms)
                     .map(|_| true)
             }
@@ -1028,7 +1035,7 @@ impl<'a> OutputCtl {
                 let mut params = self.0.clone();
                 params.headphone_vols.iter_mut()
                     .zip(vals.iter())
-                    .for_each(|(vol, &val)| *vol = val as u8);
+                    .for_each(|(vol, &val)| *vol = clamp_vol(val));
                 avc.update_params(&params, &mut self.0, timeout_ms)
                     .map(|_| true)
             }
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use glib::Error;
+
+/// The trait for source of monotonic time, to be replaced for testing.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The clock of system.
+#[derive(Default, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The trait to accumulate writes to the unit and flush them at once. It is used for burst of
+/// writes, e.g. when alsactl restores state of elements.
+pub trait BatchedWrite<O> {
+    /// Start accumulation of writes. The change by write is just cached.
+    fn begin_batch(&mut self);
+    /// Finish accumulation, then flush the accumulated changes to the unit.
+    fn flush_batch(&mut self, unit: &mut O) -> Result<(), Error>;
+}
+
+/// The detector of burst of writes to elements.
+#[derive(Debug)]
+pub struct BurstDetector<C: Clock> {
+    clock: C,
+    history: VecDeque<Instant>,
+    last: Option<Instant>,
+    active: bool,
+}
+
+impl Default for BurstDetector<SystemClock> {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl<C: Clock> BurstDetector<C> {
+    /// The window to count writes.
+    pub const WINDOW: Duration = Duration::from_millis(100);
+    /// The number of writes within the window to detect burst.
+    pub const THRESHOLD: usize = 8;
+    /// The burst is regarded as finished when no write is done within the period.
+    pub const QUIET: Duration = Duration::from_millis(200);
+    /// The interval to check whether the burst is finished.
+    pub const INTERVAL: Duration = Duration::from_millis(50);
+
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            history: VecDeque::with_capacity(Self::THRESHOLD),
+            last: None,
+            active: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Record a write. Return true when the write begins burst.
+    pub fn record_write(&mut self) -> bool {
+        let now = self.clock.now();
+        self.last = Some(now);
+
+        if self.active {
+            return false;
+        }
+
+        self.history.push_back(now);
+        while let Some(&time) = self.history.front() {
+            if now.duration_since(time) > Self::WINDOW {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if self.history.len() >= Self::THRESHOLD {
+            self.history.clear();
+            self.active = true;
+        }
+
+        self.active
+    }
+
+    /// Check whether the burst is finished. Return true just once when it is finished.
+    pub fn check_end(&mut self) -> bool {
+        if !self.active {
+            return false;
+        }
+
+        let now = self.clock.now();
+        match self.last {
+            Some(last) if now.duration_since(last) < Self::QUIET => false,
+            _ => {
+                self.active = false;
+                true
+            }
+        }
+    }
+
+    /// Flush the accumulated writes when the burst is finished.
+    pub fn flush_if_finished<O, T>(&mut self, unit: &mut O, model: &mut T) -> Result<(), Error>
+    where
+        T: BatchedWrite<O>,
+    {
+        if self.check_end() {
+            model.flush_batch(unit)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct TestClock {
+        base: Instant,
+        elapsed: Rc<Cell<Duration>>,
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Instant {
+            self.base + self.elapsed.get()
+        }
+    }
+
+    fn create_detector() -> (BurstDetector<TestClock>, Rc<Cell<Duration>>) {
+        let elapsed = Rc::new(Cell::new(Duration::default()));
+        let clock = TestClock {
+            base: Instant::now(),
+            elapsed: elapsed.clone(),
+        };
+        (BurstDetector::new(clock), elapsed)
+    }
+
+    fn advance(elapsed: &Rc<Cell<Duration>>, ms: u64) {
+        elapsed.set(elapsed.get() + Duration::from_millis(ms));
+    }
+
+    #[derive(Default)]
+    struct TestModel {
+        batched: bool,
+        flush_count: usize,
+    }
+
+    impl BatchedWrite<()> for TestModel {
+        fn begin_batch(&mut self) {
+            self.batched = true;
+        }
+
+        fn flush_batch(&mut self, _: &mut ()) -> Result<(), Error> {
+            self.batched = false;
+            self.flush_count += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sparse_writes() {
+        let (mut detector, elapsed) = create_detector();
+
+        (0..(BurstDetector::<TestClock>::THRESHOLD * 2)).for_each(|_| {
+            assert!(!detector.record_write());
+            advance(&elapsed, 20);
+        });
+        assert!(!detector.is_active());
+        assert!(!detector.check_end());
+    }
+
+    #[test]
+    fn test_burst() {
+        let (mut detector, elapsed) = create_detector();
+        let mut model = TestModel::default();
+
+        (0..(BurstDetector::<TestClock>::THRESHOLD - 1)).for_each(|_| {
+            assert!(!detector.record_write());
+            advance(&elapsed, 1);
+        });
+        assert!(detector.record_write());
+        model.begin_batch();
+
+        // The burst continues.
+        (0..100).for_each(|_| {
+            advance(&elapsed, 10);
+            assert!(!detector.record_write());
+            detector.flush_if_finished(&mut (), &mut model).unwrap();
+        });
+        assert!(detector.is_active());
+        assert!(model.batched);
+
+        advance(&elapsed, 199);
+        detector.flush_if_finished(&mut (), &mut model).unwrap();
+        assert_eq!(model.flush_count, 0);
+
+        advance(&elapsed, 1);
+        detector.flush_if_finished(&mut (), &mut model).unwrap();
+        assert_eq!(model.flush_count, 1);
+        assert!(!model.batched);
+        assert!(!detector.is_active());
+
+        // Just once.
+        advance(&elapsed, 1000);
+        detector.flush_if_finished(&mut (), &mut model).unwrap();
+        assert_eq!(model.flush_count, 1);
+    }
+
+    #[test]
+    fn test_burst_again() {
+        let (mut detector, elapsed) = create_detector();
+
+        (0..BurstDetector::<TestClock>::THRESHOLD).for_each(|_| {
+            let _ = detector.record_write();
+        });
+        assert!(detector.is_active());
+        advance(&elapsed, 200);
+        assert!(detector.check_end());
+
+        // The writes before the end of burst are not counted.
+        (0..(BurstDetector::<TestClock>::THRESHOLD - 1)).for_each(|_| {
+            assert!(!detector.record_write());
+        });
+        assert!(detector.record_write());
+    }
+}
@@ -2,11 +2,268 @@
 // Copyright (c) 2020 Takashi Sakamoto
 use alsactl::*;
 use glib::IsA;
-use glib::{Error, FileError};
+use glib::{error::ErrorDomain, Error, FileError, Quark};
 
-pub struct CardCntr {
-    pub card: alsactl::Card,
-    entries: Vec<alsactl::ElemValue>,
+use std::cell::RefCell;
+
+use super::burst::*;
+use super::log::*;
+use crate::{log_debug, log_info, log_warn};
+
+/// The trait for operations to the card, used by CardCntr. The trait is implemented for
+/// `alsactl::Card` to operate actual ALSA control device. Additionally, `MockCard` is available
+/// to test the logic of element dispatch without the device.
+pub trait CardBackend {
+    /// Retrieve the list of identifiers for elements in the card.
+    fn list_elems(&self) -> Result<Vec<ElemId>, Error>;
+
+    /// Retrieve the information of element, as well as the identifier filled by the card.
+    fn elem_info(&self, elem_id: &ElemId) -> Result<(ElemId, ElemInfo), Error>;
+
+    /// Add the set of elements, then retrieve the list of identifiers for them.
+    fn add_elem_set(
+        &self,
+        elem_id: &ElemId,
+        elem_count: usize,
+        elem_info: &ElemInfo,
+    ) -> Result<Vec<ElemId>, Error>;
+
+    /// Remove the set of elements including the element.
+    fn remove_elem_set(&self, elem_id: &ElemId) -> Result<(), Error>;
+
+    /// Lock or unlock the element against write operation by the other processes.
+    fn set_elem_lock(&self, elem_id: &ElemId, lock: bool) -> Result<(), Error>;
+
+    /// Read the value of element.
+    fn read_value(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<(), Error>;
+
+    /// Write the value of element. The event is emitted to applications for the change.
+    fn write_value(&self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<(), Error>;
+
+    /// Write the content of TLV data for the element.
+    fn write_tlv(&self, elem_id: &ElemId, cntr: &[u32]) -> Result<(), Error>;
+}
+
+impl CardBackend for alsactl::Card {
+    fn list_elems(&self) -> Result<Vec<ElemId>, Error> {
+        self.get_elem_id_list()
+    }
+
+    fn elem_info(&self, elem_id: &ElemId) -> Result<(ElemId, ElemInfo), Error> {
+        let elem_info = self.get_elem_info(elem_id)?;
+        match elem_info.get_property_elem_id() {
+            Some(elem_id) => Ok((elem_id, elem_info)),
+            None => {
+                let label = "Unexpected result to detect element id";
+                Err(Error::new(FileError::Io, label))
+            }
+        }
+    }
+
+    fn add_elem_set(
+        &self,
+        elem_id: &ElemId,
+        elem_count: usize,
+        elem_info: &ElemInfo,
+    ) -> Result<Vec<ElemId>, Error> {
+        self.add_elems(elem_id, elem_count as u32, elem_info)
+    }
+
+    fn remove_elem_set(&self, elem_id: &ElemId) -> Result<(), Error> {
+        self.remove_elems(elem_id)
+    }
+
+    fn set_elem_lock(&self, elem_id: &ElemId, lock: bool) -> Result<(), Error> {
+        self.lock_elem(elem_id, lock)
+    }
+
+    fn read_value(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<(), Error> {
+        self.read_elem_value(elem_id, elem_value)
+    }
+
+    fn write_value(&self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<(), Error> {
+        self.write_elem_value(elem_id, elem_value)
+    }
+
+    fn write_tlv(&self, elem_id: &ElemId, cntr: &[u32]) -> Result<(), Error> {
+        self.write_elem_tlv(elem_id, cntr)
+    }
+}
+
+#[derive(Debug)]
+struct MockElem {
+    elem_id: ElemId,
+    elem_info: ElemInfo,
+    elem_value: ElemValue,
+    tlv: Vec<u32>,
+    locked: bool,
+}
+
+/// The mock of card for tests. The elements are kept in memory, and the history of written
+/// element is kept in order as the events emitted to applications.
+#[derive(Default, Debug)]
+pub struct MockCard {
+    elems: RefCell<Vec<MockElem>>,
+    pub writes: RefCell<Vec<ElemId>>,
+}
+
+fn is_same_elem_set(lhs: &ElemId, rhs: &ElemId) -> bool {
+    lhs.get_name() == rhs.get_name()
+        && lhs.get_device_id() == rhs.get_device_id()
+        && lhs.get_subdevice_id() == rhs.get_subdevice_id()
+        && lhs.get_iface() == rhs.get_iface()
+}
+
+impl MockCard {
+    fn with_elem<F, R>(&self, elem_id: &ElemId, cb: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut MockElem) -> R,
+    {
+        self.elems
+            .borrow_mut()
+            .iter_mut()
+            .find(|elem| elem.elem_id.eq(elem_id))
+            .map(cb)
+            .ok_or_else(|| {
+                let msg = format!("{} is not found", elem_id.get_name());
+                Error::new(FileError::Noent, &msg)
+            })
+    }
+
+    /// Whether the element is locked against write operation by the other processes.
+    pub fn is_locked(&self, elem_id: &ElemId) -> Option<bool> {
+        self.with_elem(elem_id, |elem| elem.locked).ok()
+    }
+
+    /// Retrieve the content of TLV data for the element.
+    pub fn tlv(&self, elem_id: &ElemId) -> Option<Vec<u32>> {
+        self.with_elem(elem_id, |elem| elem.tlv.clone()).ok()
+    }
+
+    /// Take the history of written element.
+    pub fn take_writes(&self) -> Vec<ElemId> {
+        std::mem::take(&mut self.writes.borrow_mut())
+    }
+}
+
+impl CardBackend for MockCard {
+    fn list_elems(&self) -> Result<Vec<ElemId>, Error> {
+        let elem_id_list = self.elems.borrow().iter().map(|elem| elem.elem_id.clone()).collect();
+        Ok(elem_id_list)
+    }
+
+    fn elem_info(&self, elem_id: &ElemId) -> Result<(ElemId, ElemInfo), Error> {
+        self.with_elem(elem_id, |elem| (elem.elem_id.clone(), elem.elem_info.clone()))
+    }
+
+    fn add_elem_set(
+        &self,
+        elem_id: &ElemId,
+        elem_count: usize,
+        elem_info: &ElemInfo,
+    ) -> Result<Vec<ElemId>, Error> {
+        let mut elems = self.elems.borrow_mut();
+
+        if elems.iter().any(|elem| is_same_elem_set(&elem.elem_id, elem_id)) {
+            let msg = format!("{} is already added", elem_id.get_name());
+            Err(Error::new(FileError::Exist, &msg))?;
+        }
+
+        let elem_id_list: Vec<ElemId> = (0..elem_count)
+            .map(|i| {
+                ElemId::new_by_name(
+                    elem_id.get_iface(),
+                    elem_id.get_device_id(),
+                    elem_id.get_subdevice_id(),
+                    &elem_id.get_name(),
+                    elem_id.get_index() + i as u32,
+                )
+            })
+            .collect();
+
+        elem_id_list.iter().for_each(|elem_id| {
+            elems.push(MockElem {
+                elem_id: elem_id.clone(),
+                elem_info: elem_info.clone(),
+                elem_value: ElemValue::new(),
+                tlv: Vec::new(),
+                // The element is locked by the process to add it.
+                locked: true,
+            })
+        });
+
+        Ok(elem_id_list)
+    }
+
+    fn remove_elem_set(&self, elem_id: &ElemId) -> Result<(), Error> {
+        self.elems
+            .borrow_mut()
+            .retain(|elem| !is_same_elem_set(&elem.elem_id, elem_id));
+        Ok(())
+    }
+
+    fn set_elem_lock(&self, elem_id: &ElemId, lock: bool) -> Result<(), Error> {
+        self.with_elem(elem_id, |elem| elem.locked = lock)
+    }
+
+    fn read_value(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<(), Error> {
+        self.with_elem(elem_id, |elem| *elem_value = elem.elem_value.clone())
+    }
+
+    fn write_value(&self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<(), Error> {
+        self.with_elem(elem_id, |elem| elem.elem_value = elem_value.clone())
+            .map(|_| self.writes.borrow_mut().push(elem_id.clone()))
+    }
+
+    fn write_tlv(&self, elem_id: &ElemId, cntr: &[u32]) -> Result<(), Error> {
+        self.with_elem(elem_id, |elem| elem.tlv = cntr.to_vec())
+    }
+}
+
+/// The error domain for the operation of element which is not available at current state of
+/// device, like the rate of sampling clock. The failure of write operation with the domain is
+/// expected, thus the value of element is just back to the old one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElemError {
+    Unavailable,
+    Invalid(i32),
+}
+
+impl std::fmt::Display for ElemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let msg = match self {
+            ElemError::Unavailable => "unavailable",
+            ElemError::Invalid(_) => "invalid",
+        };
+
+        write!(f, "ElemError::{}", msg)
+    }
+}
+
+impl ErrorDomain for ElemError {
+    fn domain() -> Quark {
+        Quark::from_string("snd-firewire-ctl-services-elem-error-quark")
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            ElemError::Unavailable => 0,
+            ElemError::Invalid(v) => v,
+        }
+    }
+
+    fn from(code: i32) -> Option<Self> {
+        let enumeration = match code {
+            0 => ElemError::Unavailable,
+            _ => ElemError::Invalid(code),
+        };
+        Some(enumeration)
+    }
+}
+
+pub struct CardCntr<C: CardBackend = alsactl::Card> {
+    pub card: C,
+    entries: Vec<(alsactl::ElemId, alsactl::ElemValue)>,
 }
 
 pub trait CtlModel<O: IsA<hinawa::SndUnit>> {
@@ -26,12 +283,19 @@ pub trait CtlModel<O: IsA<hinawa::SndUnit>> {
     ) -> Result<bool, Error>;
 }
 
+/// The trait for model with elements measured by interval timer. The default implementation is
+/// for model without such elements.
 pub trait MeasureModel<O: IsA<hinawa::SndUnit>> {
-    fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<alsactl::ElemId>);
-    fn measure_states(&mut self, unit: &mut O) -> Result<(), Error>;
-    fn measure_elem(&mut self, unit: &O, elem_id: &alsactl::ElemId,
-                    elem_value: &mut alsactl::ElemValue)
-        -> Result<bool, Error>;
+    fn get_measure_elem_list(&mut self, _elem_id_list: &mut Vec<alsactl::ElemId>) {}
+    fn measure_states(&mut self, _unit: &mut O) -> Result<(), Error> {
+        Ok(())
+    }
+    fn measure_elem(&mut self, _unit: &O, _elem_id: &alsactl::ElemId,
+                    _elem_value: &mut alsactl::ElemValue)
+        -> Result<bool, Error>
+    {
+        Ok(false)
+    }
 }
 
 pub trait NotifyModel<O: IsA<hinawa::SndUnit>, N> {
@@ -42,18 +306,24 @@ pub trait NotifyModel<O: IsA<hinawa::SndUnit>, N> {
         -> Result<bool, Error>;
 }
 
-impl Drop for CardCntr {
+impl<C: CardBackend> Drop for CardCntr<C> {
     fn drop(&mut self) {
-        self.entries.iter().filter_map(|v| v.get_property_elem_id()).for_each(|elem_id| {
-            let _ = self.card.remove_elems(&elem_id);
+        self.entries.iter().for_each(|(elem_id, _)| {
+            let _ = self.card.remove_elem_set(elem_id);
         });
     }
 }
 
 impl CardCntr {
     pub fn new() -> Self {
+        Self::with_backend(alsactl::Card::new())
+    }
+}
+
+impl<C: CardBackend> CardCntr<C> {
+    pub fn with_backend(card: C) -> Self {
         CardCntr {
-            card: alsactl::Card::new(),
+            card,
             entries: Vec::new(),
         }
     }
@@ -123,6 +393,24 @@ impl CardCntr {
         self.register_elems(&elem_id, elem_count, &elem_info, tlv, unlock)
     }
 
+    /// Add bytes element for snapshot of device state. ALSA control core disallows write operation
+    /// to the element without write access even if it's done by the owner, thus the element is
+    /// kept locked so that it's read-only for applications and updated by the runtime only.
+    pub fn add_snapshot_elem(
+        &mut self,
+        elem_id: &alsactl::ElemId,
+    ) -> Result<Vec<alsactl::ElemId>, Error> {
+        let elem_info = alsactl::ElemInfo::new(ElemType::Bytes)?;
+        elem_info.set_property_value_count(super::snapshot::STATE_SNAPSHOT_BYTES_COUNT as u32);
+
+        let access = alsactl::ElemAccessFlag::READ
+            | alsactl::ElemAccessFlag::WRITE
+            | alsactl::ElemAccessFlag::VOLATILE;
+        elem_info.set_property_access(access);
+
+        self.register_elems(&elem_id, 1, &elem_info, None, false)
+    }
+
     pub fn add_int_elems(
         &mut self,
         elem_id: &alsactl::ElemId,
@@ -180,16 +468,15 @@ impl CardCntr {
     where
         P: IsA<alsactl::ElemInfo>,
     {
+        let elem_info = elem_info.as_ref();
+
         // If already registered, reuse them if possible.
-        let elem_id_list = self.card.get_elem_id_list()?;
+        let elem_id_list = self.card.list_elems()?;
         let elem_id_list = match elem_id_list.iter().position(|eid| eid.eq(elem_id)) {
             Some(_) => {
-                let elem_id_list: Vec::<alsactl::ElemId> = elem_id_list.into_iter().filter(|eid| {
-                    eid.get_name() == elem_id.get_name() &&
-                    eid.get_device_id() == elem_id.get_device_id() &&
-                    eid.get_subdevice_id() == elem_id.get_subdevice_id() &&
-                    eid.get_iface() == elem_id.get_iface()
-                }).collect();
+                let elem_id_list: Vec::<alsactl::ElemId> = elem_id_list.into_iter()
+                    .filter(|eid| is_same_elem_set(eid, elem_id))
+                    .collect();
 
                 if elem_id_list.len() != elem_count {
                     // The count of elements is unexpected.
@@ -198,7 +485,7 @@ impl CardCntr {
                 }
 
                 elem_id_list.iter().try_for_each(|elem_id| {
-                    let einfo = self.card.get_elem_info(elem_id)?;
+                    let (_, einfo) = self.card.elem_info(elem_id)?;
 
                     if einfo.get_property_access().contains(alsactl::ElemAccessFlag::OWNER) {
                         // Programming error.
@@ -221,7 +508,7 @@ impl CardCntr {
                 elem_id_list
             }
             None => {
-                self.card.add_elems(elem_id, elem_count as u32, elem_info)
+                self.card.add_elem_set(elem_id, elem_count, elem_info)
                     .map_err(|e| {
                         if let Some(CardError::Failed) = e.kind::<CardError>() {
                             if e.to_string() == "ioctl(ELEM_ADD) 12(Cannot allocate memory)" {
@@ -231,7 +518,7 @@ impl CardCntr {
                                 msg.push_str("or by using snd.ko pached to extend the capacity.\n");
                                 msg.push_str("The capacity is defined as 'MAX_USER_CONTROLS'");
                                 msg.push_str("located in 'sound/core/control.c'.");
-                                eprintln!("{}", msg);
+                                log_warn!(LOG_CARD, "{}", msg);
                             }
                         }
                         e
@@ -240,22 +527,15 @@ impl CardCntr {
         };
 
         elem_id_list.iter().try_for_each(|elem_id| {
-            match self.card.get_elem_info(&elem_id) {
-                Ok(elem_info) => match elem_info.get_property_elem_id() {
-                    Some(elem_id) => {
-                        let mut v = alsactl::ElemValue::new();
-                        self.card.read_elem_value(&elem_id, &mut v)?;
-                        self.entries.push(v);
-                        Ok(())
-                    }
-                    None => {
-                        let _ = self.card.remove_elems(&elem_id_list[0]);
-                        let label = "Unexpected result to detect element id";
-                        Err(Error::new(FileError::Io, label))
-                    }
-                },
+            match self.card.elem_info(&elem_id) {
+                Ok((elem_id, _)) => {
+                    let mut v = alsactl::ElemValue::new();
+                    self.card.read_value(&elem_id, &mut v)?;
+                    self.entries.push((elem_id, v));
+                    Ok(())
+                }
                 Err(err) => {
-                    let _ = self.card.remove_elems(&elem_id_list[0]);
+                    let _ = self.card.remove_elem_set(&elem_id_list[0]);
                     Err(err)
                 }
             }
@@ -263,14 +543,14 @@ impl CardCntr {
 
         if let Some(cntr) = tlv {
             elem_id_list.iter().try_for_each(|elem_id| {
-                self.card.write_elem_tlv(&elem_id, &cntr)
+                self.card.write_tlv(&elem_id, &cntr)
             })?;
         }
 
         if unlock {
             elem_id_list.iter().for_each(|elem_id|{
                 // Ignore any errors.
-                let _ = self.card.lock_elem(&elem_id, false);
+                let _ = self.card.set_elem_lock(&elem_id, false);
             });
         }
 
@@ -289,27 +569,19 @@ impl CardCntr {
         T: CtlModel<O>,
     {
         if events.contains(alsactl::ElemEventMask::REMOVE) {
-            self.entries.retain(|v| match v.get_property_elem_id() {
-                Some(e) => e != *elem_id,
-                None => true,
-            });
+            self.entries.retain(|(e, _)| *e != *elem_id);
             return Ok(());
         }
 
         if events.contains(alsactl::ElemEventMask::ADD) {
-            for v in &mut self.entries {
-                let e = match v.get_property_elem_id() {
-                    Some(e) => e,
-                    None => continue,
-                };
-
-                if e != *elem_id {
+            for (e, v) in &mut self.entries {
+                if *e != *elem_id {
                     continue;
                 }
 
                 let mut val = alsactl::ElemValue::new();
 
-                if let Ok(res) = ctl_model.read(unit, &e, &mut val) {
+                if let Ok(res) = ctl_model.read(unit, e, &mut val) {
                     if !res {
                         continue;
                     }
@@ -318,7 +590,7 @@ impl CardCntr {
                         continue;
                     }
 
-                    if let Err(_) = self.card.write_elem_value(&e, &val) {
+                    if let Err(_) = self.card.write_value(e, &val) {
                         continue;
                     }
 
@@ -328,18 +600,13 @@ impl CardCntr {
         }
 
         if events.contains(alsactl::ElemEventMask::VALUE) {
-            for v in &mut self.entries {
-                let e = match v.get_property_elem_id() {
-                    Some(e) => e,
-                    None => continue,
-                };
-
-                if e != *elem_id {
+            for (e, v) in &mut self.entries {
+                if *e != *elem_id {
                     continue;
                 }
 
                 let mut val = alsactl::ElemValue::new();
-                if self.card.read_elem_value(&e, &mut val).is_err() {
+                if self.card.read_value(e, &mut val).is_err() {
                     continue;
                 }
 
@@ -348,7 +615,9 @@ impl CardCntr {
                     continue;
                 }
 
-                match ctl_model.write(unit, &e, v, &val) {
+                log_debug!(LOG_CARD, "Write {}:{}", e.get_name(), e.get_index());
+
+                match ctl_model.write(unit, e, v, &val) {
                     Ok(res) => {
                         if res {
                             *v = val;
@@ -356,9 +625,27 @@ impl CardCntr {
                         }
                     }
                     Err(err) => {
+                        let unavailable = err.kind::<ElemError>() == Some(ElemError::Unavailable);
+                        if unavailable {
+                            log_info!(
+                                LOG_CARD,
+                                "Unavailable to write {}:{}: {}",
+                                e.get_name(),
+                                e.get_index(),
+                                err
+                            );
+                        } else {
+                            log_warn!(
+                                LOG_CARD,
+                                "Fail to write {}:{}: {}",
+                                e.get_name(),
+                                e.get_index(),
+                                err
+                            );
+                        }
                         // Back to old values.
-                        self.card.write_elem_value(&e, v)?;
-                        return Err(err);
+                        self.card.write_value(e, v)?;
+                        return if unavailable { Ok(()) } else { Err(err) };
                     }
                 }
             }
@@ -367,6 +654,28 @@ impl CardCntr {
         Ok(())
     }
 
+    /// Dispatch the event of element with detection of burst of writes. The model is switched to
+    /// accumulation mode when the burst begins.
+    pub fn dispatch_elem_event_with_burst<O, T, C>(
+        &mut self,
+        unit: &mut O,
+        elem_id: &alsactl::ElemId,
+        events: &alsactl::ElemEventMask,
+        ctl_model: &mut T,
+        detector: &mut BurstDetector<C>,
+    ) -> Result<(), Error>
+    where
+        O: IsA<hinawa::SndUnit>,
+        T: CtlModel<O> + BatchedWrite<O>,
+        C: Clock,
+    {
+        if events.contains(alsactl::ElemEventMask::VALUE) && detector.record_write() {
+            ctl_model.begin_batch();
+        }
+
+        self.dispatch_elem_event(unit, elem_id, events, ctl_model)
+    }
+
     pub fn measure_elems<O, T>(
         &mut self,
         unit: &mut O,
@@ -383,14 +692,9 @@ impl CardCntr {
         ctl_model.measure_states(unit)?;
 
         elem_id_list.iter().try_for_each(|elem_id| {
-            entries.iter_mut().filter(|elem_value| {
-                match elem_value.get_property_elem_id() {
-                    Some(eid) => eid == *elem_id,
-                    None => false,
-                }
-            }).try_for_each(|elem_value| {
+            entries.iter_mut().filter(|(eid, _)| *eid == *elem_id).try_for_each(|(_, elem_value)| {
                 if ctl_model.measure_elem(unit, elem_id, elem_value)? {
-                    card.write_elem_value(elem_id, elem_value)?;
+                    card.write_value(elem_id, elem_value)?;
                 }
 
                 Ok(())
@@ -415,14 +719,9 @@ impl CardCntr {
         ctl_model.parse_notification(unit, notification)?;
 
         elem_id_list.iter().try_for_each(|elem_id| {
-            entries.iter_mut().filter(|elem_value| {
-                match elem_value.get_property_elem_id() {
-                    Some(eid) => eid == *elem_id,
-                    None => false,
-                }
-            }).try_for_each(|elem_value| {
+            entries.iter_mut().filter(|(eid, _)| *eid == *elem_id).try_for_each(|(_, elem_value)| {
                 if ctl_model.read_notified_elem(unit, elem_id, elem_value)? {
-                    card.write_elem_value(elem_id, elem_value)?;
+                    card.write_value(elem_id, elem_value)?;
                 }
 
                 Ok(())
@@ -430,3 +729,229 @@ impl CardCntr {
         })
     }
 }
+
+/// Detect the error due to the capacity of sound card for user-defined elements.
+pub fn is_capacity_error(e: &Error) -> bool {
+    match e.kind::<CardError>() {
+        Some(CardError::Failed) => {
+            let msg = e.to_string();
+            // ENOMEM or ENOSPC.
+            msg.starts_with("ioctl(ELEM_ADD) 12(") || msg.starts_with("ioctl(ELEM_ADD) 28(")
+        }
+        _ => false,
+    }
+}
+
+/// The registration of optional groups of elements, which are registered after essential ones.
+/// Once the capacity of sound card is exhausted, the remaining groups are skipped instead of
+/// failure.
+#[derive(Default, Debug)]
+pub struct OptionalElemGroups {
+    exhausted: bool,
+    skipped: Vec<&'static str>,
+}
+
+impl OptionalElemGroups {
+    pub fn register<F>(&mut self, label: &'static str, cb: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> Result<(), Error>,
+    {
+        if self.exhausted {
+            self.skipped.push(label);
+            return Ok(());
+        }
+
+        match cb() {
+            Err(e) if is_capacity_error(&e) => {
+                log_warn!(LOG_CARD, "Skip {} and the later optional elements: {}", label, e);
+                self.exhausted = true;
+                self.skipped.push(label);
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    pub fn skipped(&self) -> &[&'static str] {
+        &self.skipped
+    }
+
+    /// Print the summary of skipped groups, if any.
+    pub fn report(&self) {
+        if !self.skipped.is_empty() {
+            log_warn!(
+                LOG_CARD,
+                "Elements for {} are not available due to the capacity of sound card.",
+                self.skipped.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_ELEM_NAME: &str = "test-elem";
+
+    #[derive(Default)]
+    struct TestModel {
+        vals: [i32; 2],
+        fail: bool,
+        unavailable: bool,
+    }
+
+    impl CtlModel<hinawa::SndUnit> for TestModel {
+        fn load(&mut self, _: &mut hinawa::SndUnit, _: &mut CardCntr) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn read(
+            &mut self,
+            _: &mut hinawa::SndUnit,
+            elem_id: &ElemId,
+            elem_value: &mut ElemValue,
+        ) -> Result<bool, Error> {
+            match elem_id.get_name().as_str() {
+                TEST_ELEM_NAME => {
+                    elem_value.set_int(&self.vals);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        fn write(
+            &mut self,
+            _: &mut hinawa::SndUnit,
+            elem_id: &ElemId,
+            _: &ElemValue,
+            new: &ElemValue,
+        ) -> Result<bool, Error> {
+            match elem_id.get_name().as_str() {
+                TEST_ELEM_NAME => {
+                    if self.fail {
+                        Err(Error::new(FileError::Io, "failed"))
+                    } else if self.unavailable {
+                        Err(Error::new(ElemError::Unavailable, "unavailable"))
+                    } else {
+                        new.get_int(&mut self.vals);
+                        Ok(true)
+                    }
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+
+    fn read_int(card_cntr: &CardCntr<MockCard>, elem_id: &ElemId) -> [i32; 2] {
+        let mut elem_value = ElemValue::new();
+        card_cntr.card.read_value(elem_id, &mut elem_value).unwrap();
+        let mut vals = [0; 2];
+        elem_value.get_int(&mut vals);
+        vals
+    }
+
+    fn write_int(card_cntr: &CardCntr<MockCard>, elem_id: &ElemId, vals: &[i32]) {
+        let elem_value = ElemValue::new();
+        elem_value.set_int(vals);
+        card_cntr.card.write_value(elem_id, &elem_value).unwrap();
+    }
+
+    #[test]
+    fn test_mock_card_registration() {
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TEST_ELEM_NAME, 0);
+        let elem_id_list = card_cntr
+            .add_int_elems(&elem_id, 2, -10, 10, 1, 2, Some(&[1, 2, 3, 4]), true)
+            .unwrap();
+        assert_eq!(elem_id_list.len(), 2);
+        assert_eq!(elem_id_list[1].get_index(), 1);
+        assert_eq!(card_cntr.card.is_locked(&elem_id_list[0]), Some(false));
+        assert_eq!(card_cntr.card.tlv(&elem_id_list[1]), Some(vec![1, 2, 3, 4]));
+
+        // The count of elements is unexpected.
+        let err = card_cntr.add_int_elems(&elem_id, 1, -10, 10, 1, 2, None, true).unwrap_err();
+        assert_eq!(err.kind::<FileError>(), Some(FileError::Inval));
+
+        // The type of elements is unexpected.
+        let err = card_cntr.add_bool_elems(&elem_id, 2, 2, true).unwrap_err();
+        assert_eq!(err.kind::<FileError>(), Some(FileError::Inval));
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, "locked-elem", 0);
+        let elem_id_list = card_cntr.add_bool_elems(&elem_id, 1, 1, false).unwrap();
+        assert_eq!(card_cntr.card.is_locked(&elem_id_list[0]), Some(true));
+    }
+
+    #[test]
+    fn test_mock_card_dispatch() {
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut unit = hinawa::SndUnit::new();
+        let mut model = TestModel::default();
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TEST_ELEM_NAME, 0);
+        let elem_id_list = card_cntr
+            .add_int_elems(&elem_id, 1, -10, 10, 1, 2, None, true)
+            .unwrap();
+        let elem_id = &elem_id_list[0];
+
+        // The initial value is read from the model.
+        model.vals = [3, -4];
+        card_cntr
+            .dispatch_elem_event(&mut unit, elem_id, &ElemEventMask::ADD, &mut model)
+            .unwrap();
+        assert_eq!(read_int(&card_cntr, elem_id), [3, -4]);
+        assert_eq!(card_cntr.card.take_writes(), vec![elem_id.clone()]);
+
+        // The value written by application is delivered to the model.
+        write_int(&card_cntr, elem_id, &[5, 6]);
+        card_cntr
+            .dispatch_elem_event(&mut unit, elem_id, &ElemEventMask::VALUE, &mut model)
+            .unwrap();
+        assert_eq!(model.vals, [5, 6]);
+
+        // The value is back to the old one at failure of the model.
+        model.fail = true;
+        write_int(&card_cntr, elem_id, &[7, 8]);
+        assert!(card_cntr
+            .dispatch_elem_event(&mut unit, elem_id, &ElemEventMask::VALUE, &mut model)
+            .is_err());
+        assert_eq!(model.vals, [5, 6]);
+        assert_eq!(read_int(&card_cntr, elem_id), [5, 6]);
+
+        // The value is back to the old one as well when the model is unavailable, without error.
+        model.fail = false;
+        model.unavailable = true;
+        write_int(&card_cntr, elem_id, &[9, 10]);
+        card_cntr
+            .dispatch_elem_event(&mut unit, elem_id, &ElemEventMask::VALUE, &mut model)
+            .unwrap();
+        assert_eq!(model.vals, [5, 6]);
+        assert_eq!(read_int(&card_cntr, elem_id), [5, 6]);
+    }
+
+    #[test]
+    fn test_optional_elem_groups() {
+        let mut groups = OptionalElemGroups::default();
+
+        assert!(groups.register("a", || Ok(())).is_ok());
+        assert!(groups
+            .register("b", || Err(Error::new(FileError::Inval, "not capacity")))
+            .is_err());
+        assert_eq!(groups.skipped().len(), 0);
+
+        let e = Error::new(CardError::Failed, "ioctl(ELEM_ADD) 28(No space left on device)");
+        assert!(groups.register("c", || Err(e)).is_ok());
+
+        let mut called = false;
+        assert!(groups
+            .register("d", || {
+                called = true;
+                Ok(())
+            })
+            .is_ok());
+        assert!(!called);
+        assert_eq!(groups.skipped(), &["c", "d"]);
+    }
+}
@@ -0,0 +1,319 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use glib::{Error, FileError};
+
+use alsactl::{CardExtManual, ElemId, ElemIfaceType, ElemValueExtManual};
+
+use std::path::{Path, PathBuf};
+
+use crate::card_cntr::CardCntr;
+
+pub const INPUT_NAMES_NAME: &str = "input-names";
+pub const OUTPUT_NAMES_NAME: &str = "output-names";
+
+/// The number of bytes for names of channels in an element, separated by newline and terminated
+/// or padded by nul. It is the maximum size of bytes element in ALSA control.
+pub const CHANNEL_NAMES_BYTES_COUNT: usize = 512;
+
+/// The maximum length of name for a channel in bytes.
+pub const CHANNEL_NAME_MAX_LENGTH: usize = 32;
+
+/// The number of names in an element, enough for the names in the maximum length. The names of
+/// channels are split into several elements by the number.
+pub const CHANNEL_NAMES_PER_ELEM: usize = CHANNEL_NAMES_BYTES_COUNT / (CHANNEL_NAME_MAX_LENGTH + 1);
+
+const CONFIG_DIR_NAME: &str = "snd-firewire-ctl-services";
+
+const INPUT_KEY: &str = "input";
+const OUTPUT_KEY: &str = "output";
+
+/// Get GUID of node from the content of configuration ROM.
+pub fn guid_from_config_rom(raw: &[u8]) -> Option<u64> {
+    // The bus information block follows the first quadlet. Its third and fourth quadlets are for
+    // node vendor ID and chip ID.
+    if raw.len() < 20 {
+        None
+    } else {
+        let mut octlet = [0; 8];
+        octlet.copy_from_slice(&raw[12..20]);
+        Some(u64::from_be_bytes(octlet))
+    }
+}
+
+/// Generate default names of channels from the list of label and the number of channels.
+pub fn numbered_channel_names(groups: &[(&str, usize)]) -> Vec<String> {
+    groups
+        .iter()
+        .flat_map(|&(label, count)| (0..count).map(move |i| format!("{}-{}", label, i + 1)))
+        .collect()
+}
+
+/// Build the value of bytes element for names of channels.
+pub fn build_channel_names(names: &[String]) -> Vec<u8> {
+    let mut raw = names.join("\n").into_bytes();
+    raw.truncate(CHANNEL_NAMES_BYTES_COUNT - 1);
+    raw.resize(CHANNEL_NAMES_BYTES_COUNT, 0);
+    raw
+}
+
+/// Parse the value of bytes element into names of channels. The number of names should be the
+/// same as the number of channels.
+pub fn parse_channel_names(raw: &[u8], count: usize) -> Result<Vec<String>, String> {
+    let len = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    let text = std::str::from_utf8(&raw[..len])
+        .map_err(|e| format!("Invalid UTF-8 sequence: {}", e))?;
+
+    let names: Vec<String> = text.split('\n').map(|name| name.to_string()).collect();
+    if names.len() != count {
+        Err(format!("{} names are expected but {} given", count, names.len()))?;
+    }
+
+    names.iter().try_for_each(|name| {
+        if name.len() > CHANNEL_NAME_MAX_LENGTH {
+            let msg = format!("Name longer than {} bytes: {}", CHANNEL_NAME_MAX_LENGTH, name);
+            Err(msg)
+        } else if name.chars().any(|c| c.is_control()) {
+            Err(format!("Name including control character: {:?}", name))
+        } else {
+            Ok(())
+        }
+    })?;
+
+    Ok(names)
+}
+
+/// Get the path of file to store the state with the extension for the unit with the GUID.
+pub fn unit_state_path(guid: u64, extension: &str) -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join(CONFIG_DIR_NAME).join(format!("{:016x}.{}", guid, extension)))
+}
+
+/// Get the path of file to store names of channels for the unit with the GUID.
+pub fn channel_names_path(guid: u64) -> Option<PathBuf> {
+    unit_state_path(guid, "names")
+}
+
+fn serialize_channel_names(inputs: &[String], outputs: &[String]) -> String {
+    inputs
+        .iter()
+        .map(|name| (INPUT_KEY, name))
+        .chain(outputs.iter().map(|name| (OUTPUT_KEY, name)))
+        .fold(String::new(), |text, (key, name)| text + key + "=" + name + "\n")
+}
+
+// The names not found in the file are left as is. The line unknown or longer than the channels is
+// just ignored.
+fn deserialize_channel_names(text: &str, inputs: &mut [String], outputs: &mut [String]) {
+    let mut input_iter = inputs.iter_mut();
+    let mut output_iter = outputs.iter_mut();
+
+    text.lines().for_each(|line| {
+        let mut fields = line.splitn(2, '=');
+        if let (Some(key), Some(name)) = (fields.next(), fields.next()) {
+            let entry = match key {
+                INPUT_KEY => input_iter.next(),
+                OUTPUT_KEY => output_iter.next(),
+                _ => None,
+            };
+            if let Some(n) = entry {
+                if name.len() <= CHANNEL_NAME_MAX_LENGTH {
+                    *n = name.to_string();
+                }
+            }
+        }
+    });
+}
+
+/// The structure for names of input and output channels given by user. The names are stored in
+/// the file per GUID of unit, and restored at load.
+#[derive(Default, Debug)]
+pub struct ChannelNameCtl {
+    path: Option<PathBuf>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl ChannelNameCtl {
+    /// Add elements for names of channels. The given names are used unless stored ones are
+    /// available.
+    pub fn load(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        guid: u64,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+    ) -> Result<(), Error> {
+        self.inputs = inputs;
+        self.outputs = outputs;
+        self.path = channel_names_path(guid);
+
+        // The file is not available until the names are changed at first.
+        if let Some(text) = self
+            .path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        {
+            deserialize_channel_names(&text, &mut self.inputs, &mut self.outputs);
+        }
+
+        [(INPUT_NAMES_NAME, &self.inputs), (OUTPUT_NAMES_NAME, &self.outputs)]
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .try_for_each(|&(name, names)| {
+                let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0);
+                let elem_count = (names.len() + CHANNEL_NAMES_PER_ELEM - 1) / CHANNEL_NAMES_PER_ELEM;
+                let elem_id_list = card_cntr.add_bytes_elems(
+                    &elem_id,
+                    elem_count,
+                    CHANNEL_NAMES_BYTES_COUNT,
+                    None,
+                    true,
+                )?;
+
+                elem_id_list
+                    .iter()
+                    .zip(names.chunks(CHANNEL_NAMES_PER_ELEM))
+                    .try_for_each(|(elem_id, chunk)| {
+                        let mut elem_value = alsactl::ElemValue::new();
+                        elem_value.set_bytes(&build_channel_names(chunk));
+                        card_cntr.card.write_elem_value(elem_id, &elem_value)
+                    })
+            })
+    }
+
+    /// Handle the change of element by the other process, then store the names. The invalid
+    /// names are reverted with notification. Return false when the element is not for names.
+    pub fn handle_elem_event(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        elem_id: &ElemId,
+    ) -> Result<bool, Error> {
+        let name = elem_id.get_name();
+        let is_input = match name.as_str() {
+            INPUT_NAMES_NAME => true,
+            OUTPUT_NAMES_NAME => false,
+            _ => return Ok(false),
+        };
+        let names = if is_input {
+            &mut self.inputs
+        } else {
+            &mut self.outputs
+        };
+        let chunk = names
+            .chunks_mut(CHANNEL_NAMES_PER_ELEM)
+            .nth(elem_id.get_index() as usize)
+            .ok_or_else(|| {
+                let msg = format!("Invalid index of element for {}: {}", name, elem_id.get_index());
+                Error::new(FileError::Inval, &msg)
+            })?;
+
+        let mut elem_value = alsactl::ElemValue::new();
+        card_cntr.card.read_elem_value(elem_id, &mut elem_value)?;
+        let mut raw = vec![0; CHANNEL_NAMES_BYTES_COUNT];
+        elem_value.get_bytes(&mut raw);
+
+        match parse_channel_names(&raw, chunk.len()) {
+            Ok(new) => {
+                // The other processes are already notified by the write.
+                if new != *chunk {
+                    chunk.clone_from_slice(&new);
+                    self.save()?;
+                }
+                Ok(true)
+            }
+            Err(cause) => {
+                elem_value.set_bytes(&build_channel_names(chunk));
+                card_cntr.card.write_elem_value(elem_id, &elem_value)?;
+                let msg = format!("Invalid names for {}: {}", name, cause);
+                Err(Error::new(FileError::Inval, &msg))
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        match &self.path {
+            Some(path) => {
+                let text = serialize_channel_names(&self.inputs, &self.outputs);
+                path.parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::write(path, text))
+                    .map_err(|e| {
+                        let msg = format!("Fail to store names to {}: {}", path.display(), e);
+                        Error::new(FileError::Io, &msg)
+                    })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_channel_names_bytes() {
+        let names = numbered_channel_names(&[("Analog", 2), ("ADAT", 1)]);
+        assert_eq!(names, vec!["Analog-1", "Analog-2", "ADAT-1"]);
+
+        let raw = build_channel_names(&names);
+        assert_eq!(raw.len(), CHANNEL_NAMES_BYTES_COUNT);
+        assert_eq!(parse_channel_names(&raw, 3), Ok(names));
+
+        let raw = build_channel_names(&["Kick".to_string(), "Snare".to_string()]);
+        assert_eq!(&raw[..11], "Kick\nSnare\0".as_bytes());
+        assert!(parse_channel_names(&raw, 3).is_err());
+
+        let raw = [0x4b, 0xff, 0xfe, 0x0a, 0x53, 0x00];
+        assert!(parse_channel_names(&raw, 2).is_err());
+
+        let raw = "a\tb\nc".as_bytes();
+        assert!(parse_channel_names(raw, 2).is_err());
+
+        let long = "x".repeat(CHANNEL_NAME_MAX_LENGTH + 1);
+        let raw = format!("Kick\n{}", long);
+        assert!(parse_channel_names(raw.as_bytes(), 2).is_err());
+
+        // The names in the maximum length are not truncated in an element.
+        let names = vec!["x".repeat(CHANNEL_NAME_MAX_LENGTH); CHANNEL_NAMES_PER_ELEM];
+        let raw = build_channel_names(&names);
+        assert_eq!(raw.len(), CHANNEL_NAMES_BYTES_COUNT);
+        assert_eq!(parse_channel_names(&raw, CHANNEL_NAMES_PER_ELEM), Ok(names));
+    }
+
+    #[test]
+    fn test_channel_names_file() {
+        let inputs = vec!["Kick".to_string(), "Snare=Top".to_string()];
+        let outputs = vec!["Main".to_string()];
+        let text = serialize_channel_names(&inputs, &outputs);
+        assert_eq!(text, "input=Kick\ninput=Snare=Top\noutput=Main\n");
+
+        let mut parsed_inputs = numbered_channel_names(&[("Analog", 3)]);
+        let mut parsed_outputs = numbered_channel_names(&[("Analog", 1)]);
+        deserialize_channel_names(&text, &mut parsed_inputs, &mut parsed_outputs);
+        assert_eq!(parsed_inputs, vec!["Kick", "Snare=Top", "Analog-3"]);
+        assert_eq!(parsed_outputs, outputs);
+
+        let mut parsed_inputs = numbered_channel_names(&[("Analog", 1)]);
+        let mut parsed_outputs = Vec::new();
+        deserialize_channel_names(
+            "unknown=x\ninput=Kick\ninput=Snare\noutput=Main\n",
+            &mut parsed_inputs,
+            &mut parsed_outputs,
+        );
+        assert_eq!(parsed_inputs, vec!["Kick"]);
+        assert!(parsed_outputs.is_empty());
+    }
+
+    #[test]
+    fn test_guid_from_config_rom() {
+        let raw = [
+            0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0xe0, 0x64, 0xa0, 0x02, 0x00, 0x0a,
+            0x35, 0x00, 0x12, 0x34, 0x56, 0x78,
+        ];
+        assert_eq!(guid_from_config_rom(&raw), Some(0x000a350012345678));
+        assert_eq!(guid_from_config_rom(&raw[..19]), None);
+    }
+}
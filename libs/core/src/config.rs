@@ -0,0 +1,556 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Configuration file for service programs, reloadable at runtime.
+//!
+//! The file consists of lines of key and value delimited by equal sign, like
+//! "log-level = warn,card=debug". The empty line and the line beginning with '#' are ignored. The
+//! path of file is given by the command line option or the environment variable. The runtime
+//! reloads the file when receiving SIGHUP. The change of the key which is not reloadable is
+//! reported to log, and takes effect after restart.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use glib::{source, Error};
+
+use nix::sys::signal::Signal;
+
+use once_cell::sync::Lazy;
+
+use crate::dispatcher::Dispatcher;
+use crate::log::*;
+use crate::{log_info, log_warn};
+
+/// The name of environment variable for the path of configuration file.
+pub const CONFIG_PATH_ENV_NAME: &str = "SND_FIREWIRE_CTL_SERVICES_CONFIG";
+
+/// The key for the specification of log level. Reloadable unless the level is given by command
+/// line option.
+pub const LOG_LEVEL_KEY: &str = "log-level";
+
+/// The key for the minimum timeout of transactions in milliseconds. Reloadable, and takes effect
+/// for subsequent transactions.
+pub const TRANSACTION_TIMEOUT_KEY: &str = "transaction-timeout-ms";
+
+/// The key for the interval of timer for metering in milliseconds. Reloadable, and the running
+/// timer is restarted.
+pub const METERING_INTERVAL_KEY: &str = "metering-interval-ms";
+
+/// The key for the directory of preset files. Not reloadable.
+pub const PRESET_DIR_KEY: &str = "preset-dir";
+
+/// The key for the model of command DSP to which the state of mixer in MOTU register DSP models is
+/// converted. Reloadable, and the preset file is written again.
+pub const REGISTER_DSP_EXPORT_TARGET_KEY: &str = "register-dsp-export-target";
+
+/// The key for the name of preset file in the directory of preset files, to which the converted
+/// state of mixer in MOTU register DSP models is written. Reloadable, and the preset file is
+/// written again.
+pub const REGISTER_DSP_EXPORT_FILE_KEY: &str = "register-dsp-export-file";
+
+/// The key for the order of units in cascade, like FE-8; 'command-line' or 'guid'. Not reloadable.
+pub const CASCADE_ORDER_KEY: &str = "cascade-order";
+
+/// The order of units in cascade.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CascadeOrder {
+    /// The order of units given in command line.
+    CommandLine,
+    /// The ascending order of GUID of node.
+    Guid,
+}
+
+impl Default for CascadeOrder {
+    fn default() -> Self {
+        Self::CommandLine
+    }
+}
+
+impl CascadeOrder {
+    fn parse(val: &str) -> Result<Self, String> {
+        match val {
+            "command-line" => Ok(Self::CommandLine),
+            "guid" => Ok(Self::Guid),
+            _ => Err(format!("The value should be one of 'command-line' and 'guid': {}", val)),
+        }
+    }
+}
+
+/// The key for the scale of value between the item of control surface and application, like
+/// '<control>:<input max>:<output min>:<output max>:<curve>'. The control is the number of control
+/// change in application side, and the curve is one of 'linear', 'log', and 'taper'. Repeatable
+/// for each control. Reloadable.
+pub const VALUE_SCALE_KEY: &str = "value-scale";
+
+/// The curve of value scale.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueCurve {
+    Linear,
+    Log,
+    Taper,
+}
+
+impl ValueCurve {
+    fn parse(val: &str) -> Result<Self, String> {
+        match val {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            "taper" => Ok(Self::Taper),
+            _ => Err(format!("The curve should be one of 'linear', 'log', and 'taper': {}", val)),
+        }
+    }
+}
+
+/// The scale of value between the item of control surface and application.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValueScale {
+    /// The number of control change in application side.
+    pub control: u32,
+    /// The maximum of value from the surface.
+    pub input_max: u16,
+    /// The minimum of value for application.
+    pub output_min: i32,
+    /// The maximum of value for application.
+    pub output_max: i32,
+    /// The curve between both ranges.
+    pub curve: ValueCurve,
+}
+
+impl ValueScale {
+    fn parse(val: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = val.split(':').map(|f| f.trim()).collect();
+        if fields.len() != 5 {
+            let msg = format!(
+                "The value should be '<control>:<input max>:<output min>:<output max>:<curve>': {}",
+                val
+            );
+            return Err(msg);
+        }
+
+        let control = fields[0]
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid number of control: {}, {}", fields[0], e))?;
+        let input_max = fields[1]
+            .parse::<u16>()
+            .map_err(|e| format!("Invalid maximum of input: {}, {}", fields[1], e))?;
+        let output_min = fields[2]
+            .parse::<i32>()
+            .map_err(|e| format!("Invalid minimum of output: {}, {}", fields[2], e))?;
+        let output_max = fields[3]
+            .parse::<i32>()
+            .map_err(|e| format!("Invalid maximum of output: {}, {}", fields[3], e))?;
+        let curve = ValueCurve::parse(fields[4])?;
+
+        if input_max == 0 || output_min >= output_max {
+            return Err(format!("The ranges should not be empty: {}", val));
+        }
+
+        Ok(Self {
+            control,
+            input_max,
+            output_min,
+            output_max,
+            curve,
+        })
+    }
+}
+
+/// The configuration of service programs.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceConfig {
+    /// The filter of log by level per subsystem.
+    pub log_filter: Option<LogFilter>,
+    /// The minimum timeout of transactions in milliseconds.
+    pub transaction_timeout_ms: Option<u32>,
+    /// The interval of timer for metering in milliseconds.
+    pub metering_interval_ms: Option<u32>,
+    /// The directory of preset files.
+    pub preset_dir: Option<PathBuf>,
+    /// The model of command DSP for conversion of mixer state in register DSP models.
+    pub register_dsp_export_target: Option<String>,
+    /// The name of preset file for conversion of mixer state in register DSP models.
+    pub register_dsp_export_file: Option<String>,
+    /// The order of units in cascade.
+    pub cascade_order: Option<CascadeOrder>,
+    /// The scales of value for controls in application side.
+    pub value_scales: Vec<ValueScale>,
+}
+
+fn parse_ms(val: &str) -> Result<u32, String> {
+    val.parse::<u32>()
+        .map_err(|e| format!("Invalid value for milliseconds: {}, {}", val, e))
+        .and_then(|ms| {
+            if ms > 0 {
+                Ok(ms)
+            } else {
+                Err(format!("The value should be greater than 0: {}", val))
+            }
+        })
+}
+
+impl ServiceConfig {
+    /// Parse the content of configuration file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .try_for_each(|(num, line)| {
+                let mut parts = line.splitn(2, '=');
+                match (parts.next().map(|k| k.trim()), parts.next().map(|v| v.trim())) {
+                    (Some(LOG_LEVEL_KEY), Some(val)) => {
+                        LogFilter::parse(val).map(|filter| config.log_filter = Some(filter))
+                    }
+                    (Some(TRANSACTION_TIMEOUT_KEY), Some(val)) => {
+                        parse_ms(val).map(|ms| config.transaction_timeout_ms = Some(ms))
+                    }
+                    (Some(METERING_INTERVAL_KEY), Some(val)) => {
+                        parse_ms(val).map(|ms| config.metering_interval_ms = Some(ms))
+                    }
+                    (Some(PRESET_DIR_KEY), Some(val)) => {
+                        config.preset_dir = Some(PathBuf::from(val));
+                        Ok(())
+                    }
+                    (Some(REGISTER_DSP_EXPORT_TARGET_KEY), Some(val)) => {
+                        config.register_dsp_export_target = Some(val.to_string());
+                        Ok(())
+                    }
+                    (Some(REGISTER_DSP_EXPORT_FILE_KEY), Some(val)) => {
+                        config.register_dsp_export_file = Some(val.to_string());
+                        Ok(())
+                    }
+                    (Some(CASCADE_ORDER_KEY), Some(val)) => {
+                        CascadeOrder::parse(val).map(|order| config.cascade_order = Some(order))
+                    }
+                    (Some(VALUE_SCALE_KEY), Some(val)) => ValueScale::parse(val).map(|scale| {
+                        // The later line for the same control takes precedence.
+                        config.value_scales.retain(|s| s.control != scale.control);
+                        config.value_scales.push(scale);
+                    }),
+                    (Some(key), Some(_)) => Err(format!("Unknown key: {}", key)),
+                    _ => Err(format!("The line should be 'key = value': {}", line)),
+                }
+                .map_err(|e| format!("line {}: {}", num, e))
+            })?;
+
+        Ok(config)
+    }
+
+    /// Read and parse the configuration file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("Fail to read {}: {}", path.display(), e))
+            .and_then(|text| Self::parse(&text))
+    }
+
+    /// The keys which differ from the other configuration but are not reloadable.
+    fn unreloadable_changes(&self, other: &Self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if self.preset_dir != other.preset_dir {
+            keys.push(PRESET_DIR_KEY);
+        }
+        if self.cascade_order != other.cascade_order {
+            keys.push(CASCADE_ORDER_KEY);
+        }
+        keys
+    }
+}
+
+/// The changes of configuration by reload, which the runtime should follow.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigChanges {
+    /// The interval of timer for metering is changed.
+    pub metering_interval: bool,
+}
+
+#[derive(Default)]
+struct ConfigState {
+    path: Option<PathBuf>,
+    // The filter of log is given by command line option, which takes precedence over the file.
+    cmdline_log_filter: bool,
+    current: ServiceConfig,
+}
+
+impl ConfigState {
+    fn apply(&mut self, config: ServiceConfig) -> ConfigChanges {
+        if !self.cmdline_log_filter {
+            if let Some(filter) = &config.log_filter {
+                set_log_filter(filter.clone());
+            }
+        }
+        fw_transaction::set_min_timeout_ms(config.transaction_timeout_ms.unwrap_or(0));
+
+        let changes = ConfigChanges {
+            metering_interval: self.current.metering_interval_ms != config.metering_interval_ms,
+        };
+        self.current = config;
+        changes
+    }
+}
+
+// The state is allocated at first use and never released.
+static STATE: Lazy<Mutex<ConfigState>> = Lazy::new(|| Mutex::new(ConfigState::default()));
+
+/// Configure the path of configuration file for the process.
+pub fn set_config_path(path: Option<PathBuf>) {
+    if let Ok(mut s) = STATE.lock() {
+        s.path = path;
+    }
+}
+
+/// The path of configuration file. The path given by command line option takes precedence over
+/// the environment variable.
+pub fn config_path() -> Option<PathBuf> {
+    STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.path.clone())
+        .or_else(|| std::env::var_os(CONFIG_PATH_ENV_NAME).map(PathBuf::from))
+}
+
+/// Configure the filter of log given by command line option. The level of log in configuration
+/// file is ignored after the call, including reload.
+pub fn set_cmdline_log_filter(filter: LogFilter) {
+    if let Ok(mut s) = STATE.lock() {
+        s.cmdline_log_filter = true;
+    }
+    set_log_filter(filter);
+}
+
+/// Load the configuration file if given.
+pub fn load_config() -> Result<Option<ServiceConfig>, String> {
+    config_path()
+        .map(|path| ServiceConfig::load(&path).map(Some))
+        .unwrap_or(Ok(None))
+}
+
+/// Apply the configuration to the process at startup.
+pub fn apply_config(config: ServiceConfig) {
+    if let Ok(mut s) = STATE.lock() {
+        s.apply(config);
+    }
+}
+
+/// Load and apply the configuration file again. The failure is just reported to log so that the
+/// runtime continues with current configuration. The change of key which is not reloadable is
+/// reported to log as well, and the current value is kept.
+pub fn reload_config() -> ConfigChanges {
+    match load_config() {
+        Ok(Some(mut config)) => {
+            let mut s = match STATE.lock() {
+                Ok(s) => s,
+                Err(_) => return ConfigChanges::default(),
+            };
+            config.unreloadable_changes(&s.current).iter().for_each(|key| {
+                log_warn!(LOG_RUNTIME, "The change of {} requires restart", key);
+            });
+            config.preset_dir = s.current.preset_dir.clone();
+            let changes = s.apply(config);
+            log_info!(LOG_RUNTIME, "Configuration is reloaded");
+            changes
+        }
+        Ok(None) => {
+            log_info!(LOG_RUNTIME, "No configuration file is given");
+            ConfigChanges::default()
+        }
+        Err(e) => {
+            log_warn!(LOG_RUNTIME, "Fail to reload configuration: {}", e);
+            ConfigChanges::default()
+        }
+    }
+}
+
+/// The interval of timer for metering. The configured value takes precedence over the default
+/// of runtime.
+pub fn metering_interval(default: Duration) -> Duration {
+    STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.current.metering_interval_ms)
+        .map(|ms| Duration::from_millis(ms as u64))
+        .unwrap_or(default)
+}
+
+/// The current configuration, including the options specific to the runtime.
+pub fn current_config() -> ServiceConfig {
+    STATE.lock().map(|s| s.current.clone()).unwrap_or_default()
+}
+
+/// The directory of preset files, configured at startup.
+pub fn preset_dir() -> Option<PathBuf> {
+    STATE.lock().ok().and_then(|s| s.current.preset_dir.clone())
+}
+
+fn join_preset_path(dir: Option<&Path>, name: &str) -> Result<PathBuf, String> {
+    let dir = dir.ok_or_else(|| {
+        format!("The directory of preset is not configured by {}", PRESET_DIR_KEY)
+    })?;
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(n)), None) if n.to_str() == Some(name) => Ok(dir.join(n)),
+        _ => Err(format!("The name of preset should be a plain file name: {}", name)),
+    }
+}
+
+/// The path of preset file with the name in the configured directory. The name should be a plain
+/// file name so that any file out of the directory is not accessed.
+pub fn preset_path(name: &str) -> Result<PathBuf, String> {
+    join_preset_path(preset_dir().as_ref().map(|dir| dir.as_path()), name)
+}
+
+/// Attach the handler of SIGHUP to the dispatcher. The callback is expected to queue an event so
+/// that the runtime calls `ConfigReloadOperation::reload()` in its event loop.
+pub fn attach_reload_handler<F>(dispatcher: &mut Dispatcher, cb: F)
+where
+    F: Fn() + Send + 'static,
+{
+    dispatcher.attach_signal_handler(Signal::SIGHUP, move || {
+        cb();
+        source::Continue(true)
+    });
+}
+
+/// The operation of runtime to follow reload of configuration file.
+pub trait ConfigReloadOperation {
+    /// Restart the timer for metering with the configured interval, if running. The runtime
+    /// without timer for metering has nothing to do.
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Apply the options specific to the runtime in current configuration. It is called at every
+    /// reload. The runtime without such options has nothing to do.
+    fn apply_runtime_options(&mut self) {}
+
+    /// Reload the configuration file, then apply the changes to the runtime.
+    fn reload(&mut self) {
+        let changes = reload_config();
+        if changes.metering_interval {
+            if let Err(e) = self.restart_metering_timer() {
+                log_warn!(LOG_RUNTIME, "Fail to restart timer for metering: {}", e);
+            }
+        }
+        self.apply_runtime_options();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_parse() {
+        let config = ServiceConfig::parse("").unwrap();
+        assert_eq!(config, ServiceConfig::default());
+
+        let text = "
+# comment
+  log-level =  warn,card=debug
+transaction-timeout-ms = 200
+metering-interval-ms=100
+preset-dir = /var/lib/snd-firewire-ctl-services
+register-dsp-export-target = 828mk3
+register-dsp-export-file = 828mk2.mcdp
+cascade-order = guid
+value-scale = 16:1023:0:127:log
+value-scale = 17 : 1023 : 0 : 16383 : linear
+value-scale = 16:1023:0:127:taper
+";
+        let config = ServiceConfig::parse(text).unwrap();
+        let filter = config.log_filter.unwrap();
+        assert_eq!(filter.level(LOG_CARD), LogLevel::Debug);
+        assert_eq!(filter.level(LOG_RUNTIME), LogLevel::Warn);
+        assert_eq!(config.transaction_timeout_ms, Some(200));
+        assert_eq!(config.metering_interval_ms, Some(100));
+        assert_eq!(config.preset_dir, Some(PathBuf::from("/var/lib/snd-firewire-ctl-services")));
+        assert_eq!(config.register_dsp_export_target, Some("828mk3".to_string()));
+        assert_eq!(config.register_dsp_export_file, Some("828mk2.mcdp".to_string()));
+        assert_eq!(config.cascade_order, Some(CascadeOrder::Guid));
+        assert_eq!(
+            config.value_scales,
+            vec![
+                ValueScale {
+                    control: 17,
+                    input_max: 1023,
+                    output_min: 0,
+                    output_max: 16383,
+                    curve: ValueCurve::Linear,
+                },
+                ValueScale {
+                    control: 16,
+                    input_max: 1023,
+                    output_min: 0,
+                    output_max: 127,
+                    curve: ValueCurve::Taper,
+                },
+            ]
+        );
+
+        // The later line takes precedence.
+        let config = ServiceConfig::parse("log-level=debug\nlog-level=error").unwrap();
+        assert_eq!(config.log_filter.unwrap().level(LOG_CARD), LogLevel::Error);
+
+        let err = ServiceConfig::parse("\nlog-level=verbose").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+        let err = ServiceConfig::parse("timeout=100").unwrap_err();
+        assert!(err.contains("Unknown key"), "{}", err);
+        let err = ServiceConfig::parse("log-level").unwrap_err();
+        assert!(err.contains("key = value"), "{}", err);
+        let err = ServiceConfig::parse("metering-interval-ms = 0").unwrap_err();
+        assert!(err.starts_with("line 1:"), "{}", err);
+        let err = ServiceConfig::parse("transaction-timeout-ms = fast").unwrap_err();
+        assert!(err.starts_with("line 1:"), "{}", err);
+        let err = ServiceConfig::parse("cascade-order = serial").unwrap_err();
+        assert!(err.starts_with("line 1:"), "{}", err);
+        [
+            "16:1023:0:127",
+            "16:1023:0:127:cubic",
+            "16:0:0:127:log",
+            "16:1023:127:0:log",
+            "x:1:0:1:log",
+        ]
+        .iter()
+        .for_each(|val| {
+            let text = format!("value-scale = {}", val);
+            let err = ServiceConfig::parse(&text).unwrap_err();
+            assert!(err.starts_with("line 1:"), "{}", err);
+        });
+    }
+
+    #[test]
+    fn test_preset_path() {
+        let dir = Path::new("/var/lib/presets");
+        assert_eq!(join_preset_path(Some(dir), "studio.mcdp"), Ok(dir.join("studio.mcdp")));
+
+        assert!(join_preset_path(None, "studio.mcdp").is_err());
+        ["", ".", "..", "../studio.mcdp", "/etc/passwd", "sub/studio.mcdp", "studio.mcdp/"]
+            .iter()
+            .for_each(|name| assert!(join_preset_path(Some(dir), name).is_err(), "{}", name));
+    }
+
+    #[test]
+    fn test_config_apply() {
+        let mut state = ConfigState::default();
+
+        let config = ServiceConfig {
+            metering_interval_ms: Some(100),
+            preset_dir: Some(PathBuf::from("/tmp")),
+            ..Default::default()
+        };
+        assert_eq!(state.apply(config.clone()), ConfigChanges { metering_interval: true });
+        assert_eq!(state.apply(config.clone()), ConfigChanges { metering_interval: false });
+
+        let other = ServiceConfig {
+            metering_interval_ms: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(other.unreloadable_changes(&state.current), vec![PRESET_DIR_KEY]);
+        assert!(config.unreloadable_changes(&state.current).is_empty());
+
+        assert_eq!(state.apply(ServiceConfig::default()), ConfigChanges { metering_interval: true });
+    }
+}
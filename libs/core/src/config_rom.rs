@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use glib::{error::ErrorDomain, Quark};
+
+/// The error domain for failure to identify the unit by content of configuration ROM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfigRomError {
+    UnreadableRom,
+    VendorMismatch,
+    UnknownModel,
+    MalformedEntry,
+    Invalid(i32),
+}
+
+impl std::fmt::Display for ConfigRomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let msg = match self {
+            ConfigRomError::UnreadableRom => "unreadable-rom",
+            ConfigRomError::VendorMismatch => "vendor-mismatch",
+            ConfigRomError::UnknownModel => "unknown-model",
+            ConfigRomError::MalformedEntry => "malformed-entry",
+            ConfigRomError::Invalid(_) => "invalid",
+        };
+
+        write!(f, "ConfigRomError::{}", msg)
+    }
+}
+
+impl ErrorDomain for ConfigRomError {
+    fn domain() -> Quark {
+        Quark::from_string("snd-firewire-ctl-services-config-rom-error-quark")
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            ConfigRomError::UnreadableRom => 0,
+            ConfigRomError::VendorMismatch => 1,
+            ConfigRomError::UnknownModel => 2,
+            ConfigRomError::MalformedEntry => 3,
+            ConfigRomError::Invalid(v) => v,
+        }
+    }
+
+    fn from(code: i32) -> Option<Self> {
+        let enumeration = match code {
+            0 => ConfigRomError::UnreadableRom,
+            1 => ConfigRomError::VendorMismatch,
+            2 => ConfigRomError::UnknownModel,
+            3 => ConfigRomError::MalformedEntry,
+            _ => ConfigRomError::Invalid(code),
+        };
+        Some(enumeration)
+    }
+}
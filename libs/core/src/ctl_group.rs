@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Group of elements to compose the model of control.
+//!
+//! The model registers the groups to `CtlGroupRegistry`, then delegates the operations for
+//! elements to them in the order of registration. The lists of identifiers for notified and
+//! measured elements are aggregated from the groups, thus adding one group never requires to
+//! touch the implementation of model for each operation.
+//!
+//! The type parameters are for the unit, the context shared by the groups such as the object of
+//! transaction, and the message of notification.
+
+use glib::Error;
+
+use alsactl::{ElemId, ElemValue};
+
+use super::card_cntr::{CardBackend, CardCntr};
+
+/// The trait for the group of elements.
+pub trait CtlGroup<O, C, N, B: CardBackend = alsactl::Card> {
+    /// Add the elements to the card, as well as cache the initial state.
+    fn load(&mut self, unit: &mut O, ctx: &mut C, card_cntr: &mut CardCntr<B>)
+        -> Result<(), Error>;
+
+    fn read(
+        &mut self,
+        unit: &mut O,
+        ctx: &mut C,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error>;
+
+    fn write(
+        &mut self,
+        unit: &mut O,
+        ctx: &mut C,
+        elem_id: &ElemId,
+        old: &ElemValue,
+        new: &ElemValue,
+    ) -> Result<bool, Error>;
+
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &[]
+    }
+
+    fn parse_notification(&mut self, _: &mut O, _: &mut C, _: &N) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn read_notified_elem(&mut self, _: &ElemId, _: &mut ElemValue) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn measured_elem_list(&self) -> &[ElemId] {
+        &[]
+    }
+
+    fn measure_states(&mut self, _: &mut O, _: &mut C) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn read_measured_elem(&mut self, _: &ElemId, _: &mut ElemValue) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// The registry of groups, which owns them and iterates them in the order of registration.
+pub struct CtlGroupRegistry<O, C, N, B: CardBackend = alsactl::Card> {
+    groups: Vec<Box<dyn CtlGroup<O, C, N, B>>>,
+}
+
+impl<O, C, N, B: CardBackend> Default for CtlGroupRegistry<O, C, N, B> {
+    fn default() -> Self {
+        Self { groups: Vec::new() }
+    }
+}
+
+impl<O, C, N, B: CardBackend> CtlGroupRegistry<O, C, N, B> {
+    pub fn register<G>(&mut self, group: G) -> &mut Self
+    where
+        G: CtlGroup<O, C, N, B> + 'static,
+    {
+        self.groups.push(Box::new(group));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn load(
+        &mut self,
+        unit: &mut O,
+        ctx: &mut C,
+        card_cntr: &mut CardCntr<B>,
+    ) -> Result<(), Error> {
+        self.groups
+            .iter_mut()
+            .try_for_each(|group| group.load(unit, ctx, card_cntr))
+    }
+
+    pub fn read(
+        &mut self,
+        unit: &mut O,
+        ctx: &mut C,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        for group in self.groups.iter_mut() {
+            if group.read(unit, ctx, elem_id, elem_value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn write(
+        &mut self,
+        unit: &mut O,
+        ctx: &mut C,
+        elem_id: &ElemId,
+        old: &ElemValue,
+        new: &ElemValue,
+    ) -> Result<bool, Error> {
+        for group in self.groups.iter_mut() {
+            if group.write(unit, ctx, elem_id, old, new)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn get_notified_elem_list(&self, elem_id_list: &mut Vec<ElemId>) {
+        self.groups
+            .iter()
+            .for_each(|group| elem_id_list.extend_from_slice(group.notified_elem_list()));
+    }
+
+    pub fn parse_notification(&mut self, unit: &mut O, ctx: &mut C, msg: &N) -> Result<(), Error> {
+        self.groups
+            .iter_mut()
+            .try_for_each(|group| group.parse_notification(unit, ctx, msg))
+    }
+
+    pub fn read_notified_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        for group in self.groups.iter_mut() {
+            if group.read_notified_elem(elem_id, elem_value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn get_measure_elem_list(&self, elem_id_list: &mut Vec<ElemId>) {
+        self.groups
+            .iter()
+            .for_each(|group| elem_id_list.extend_from_slice(group.measured_elem_list()));
+    }
+
+    pub fn measure_states(&mut self, unit: &mut O, ctx: &mut C) -> Result<(), Error> {
+        self.groups
+            .iter_mut()
+            .try_for_each(|group| group.measure_states(unit, ctx))
+    }
+
+    pub fn read_measured_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        for group in self.groups.iter_mut() {
+            if group.read_measured_elem(elem_id, elem_value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::card_cntr::MockCard;
+    use alsactl::*;
+
+    #[derive(Default)]
+    struct TestGroup {
+        name: &'static str,
+        val: i32,
+        notified: Vec<ElemId>,
+        measured: Vec<ElemId>,
+    }
+
+    impl TestGroup {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                ..Default::default()
+            }
+        }
+    }
+
+    // The context counts the calls.
+    impl CtlGroup<(), Vec<&'static str>, u32, MockCard> for TestGroup {
+        fn load(
+            &mut self,
+            _: &mut (),
+            ctx: &mut Vec<&'static str>,
+            card_cntr: &mut CardCntr<MockCard>,
+        ) -> Result<(), Error> {
+            ctx.push(self.name);
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, self.name, 0);
+            card_cntr
+                .add_int_elems(&elem_id, 1, 0, 100, 1, 1, None, true)
+                .map(|elem_id_list| {
+                    self.notified = elem_id_list.clone();
+                    self.measured = elem_id_list;
+                })
+        }
+
+        fn read(
+            &mut self,
+            _: &mut (),
+            _: &mut Vec<&'static str>,
+            elem_id: &ElemId,
+            elem_value: &mut ElemValue,
+        ) -> Result<bool, Error> {
+            self.read_measured_elem(elem_id, elem_value)
+        }
+
+        fn write(
+            &mut self,
+            _: &mut (),
+            ctx: &mut Vec<&'static str>,
+            elem_id: &ElemId,
+            _: &ElemValue,
+            new: &ElemValue,
+        ) -> Result<bool, Error> {
+            if elem_id.get_name().as_str() == self.name {
+                let mut vals = [0];
+                new.get_int(&mut vals);
+                self.val = vals[0];
+                ctx.push(self.name);
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+
+        fn notified_elem_list(&self) -> &[ElemId] {
+            &self.notified
+        }
+
+        fn parse_notification(
+            &mut self,
+            _: &mut (),
+            _: &mut Vec<&'static str>,
+            msg: &u32,
+        ) -> Result<(), Error> {
+            self.val += *msg as i32;
+            Ok(())
+        }
+
+        fn read_notified_elem(
+            &mut self,
+            elem_id: &ElemId,
+            elem_value: &mut ElemValue,
+        ) -> Result<bool, Error> {
+            self.read_measured_elem(elem_id, elem_value)
+        }
+
+        fn measured_elem_list(&self) -> &[ElemId] {
+            &self.measured
+        }
+
+        fn read_measured_elem(
+            &mut self,
+            elem_id: &ElemId,
+            elem_value: &mut ElemValue,
+        ) -> Result<bool, Error> {
+            if elem_id.get_name().as_str() == self.name {
+                elem_value.set_int(&[self.val]);
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+    }
+
+    #[test]
+    fn test_ctl_group_registry() {
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut registry = CtlGroupRegistry::<(), Vec<&'static str>, u32, MockCard>::default();
+        registry
+            .register(TestGroup::new("group-a"))
+            .register(TestGroup::new("group-b"));
+        assert_eq!(registry.len(), 2);
+
+        let mut ctx = Vec::new();
+        registry.load(&mut (), &mut ctx, &mut card_cntr).unwrap();
+        assert_eq!(ctx, vec!["group-a", "group-b"]);
+
+        // The lists are aggregated in the order of registration.
+        let mut elem_id_list = Vec::new();
+        registry.get_notified_elem_list(&mut elem_id_list);
+        let names: Vec<String> = elem_id_list
+            .iter()
+            .map(|e| e.get_name().to_string())
+            .collect();
+        assert_eq!(names, vec!["group-a", "group-b"]);
+
+        let mut measured_elem_id_list = Vec::new();
+        registry.get_measure_elem_list(&mut measured_elem_id_list);
+        assert_eq!(measured_elem_id_list, elem_id_list);
+
+        // The operation is delegated to the group for the element.
+        let mut ctx = Vec::new();
+        let elem_value = ElemValue::new();
+        elem_value.set_int(&[7]);
+        assert!(registry
+            .write(
+                &mut (),
+                &mut ctx,
+                &elem_id_list[1],
+                &elem_value,
+                &elem_value
+            )
+            .unwrap());
+        assert_eq!(ctx, vec!["group-b"]);
+
+        registry.parse_notification(&mut (), &mut ctx, &1).unwrap();
+
+        let mut elem_value = ElemValue::new();
+        assert!(registry
+            .read_notified_elem(&elem_id_list[1], &mut elem_value)
+            .unwrap());
+        let mut vals = [0];
+        elem_value.get_int(&mut vals);
+        assert_eq!(vals, [8]);
+
+        assert!(registry
+            .read(&mut (), &mut ctx, &elem_id_list[0], &mut elem_value)
+            .unwrap());
+        elem_value.get_int(&mut vals);
+        assert_eq!(vals, [1]);
+
+        // The element out of the groups.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, "group-c", 0);
+        assert!(!registry
+            .read(&mut (), &mut ctx, &elem_id, &mut elem_value)
+            .unwrap());
+        assert!(!registry
+            .read_measured_elem(&elem_id, &mut elem_value)
+            .unwrap());
+    }
+}
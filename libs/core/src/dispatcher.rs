@@ -1,10 +1,10 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2020 Takashi Sakamoto
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Duration;
 
-use glib::Error;
+use glib::{Error, FileError};
 use glib::{source, MainContext, MainLoop, Source};
 use glib::IsA;
 
@@ -15,25 +15,41 @@ use alsaseq::UserClientExt;
 use hinawa::SndUnitExt;
 use hinawa::FwNodeExt;
 
+use crate::log::*;
+use crate::log_error;
+
 pub struct Dispatcher {
     name: String,
     th: Option<thread::JoinHandle<()>>,
     ev_loop: Arc<MainLoop>,
+    finished: mpsc::Receiver<()>,
 }
 
 impl Drop for Dispatcher {
     fn drop(&mut self) {
-        self.ev_loop.quit();
-
-        if let Some(th) = self.th.take() {
-            if th.join().is_err() {
-                println!("Fail to join thread for {}.", self.name);
-            }
+        if let Err(e) = self.finish(Self::JOIN_TIMEOUT) {
+            log_error!(LOG_DISPATCHER, "{}", e);
         }
     }
 }
 
+/// Stop and join the dispatchers in the order. Any error is reported instead of panic since the
+/// function is expected to be called in teardown path of runtime.
+pub fn join_dispatchers<I>(dispatchers: I)
+where
+    I: IntoIterator<Item = Dispatcher>,
+{
+    dispatchers.into_iter().for_each(|dispatcher| {
+        if let Err(e) = dispatcher.stop_and_join(Dispatcher::JOIN_TIMEOUT) {
+            log_error!(LOG_DISPATCHER, "{}", e);
+        }
+    });
+}
+
 impl Dispatcher {
+    /// The timeout to wait for the thread to finish.
+    pub const JOIN_TIMEOUT: Duration = Duration::from_millis(500);
+
     pub fn run(name: String) -> Result<Dispatcher, Error> {
         // Use own context.
         let ctx = MainContext::new();
@@ -41,9 +57,10 @@ impl Dispatcher {
 
         // launch one thread to dispatch all events.
         let l = ev_loop.clone();
+        let (tx, finished) = mpsc::channel();
         let th = thread::spawn(move || {
             l.run();
-            ()
+            let _ = tx.send(());
         });
 
         // TODO: better mechanism to wait for the launch.
@@ -56,13 +73,42 @@ impl Dispatcher {
         }
 
         let th = Some(th);
-        Ok(Dispatcher{name, th, ev_loop})
+        Ok(Dispatcher{name, th, ev_loop, finished})
     }
 
     pub fn stop(&mut self) {
         self.ev_loop.quit();
     }
 
+    /// Stop the event loop, then wait for the thread to finish till the timeout. The thread is
+    /// left detached at timeout.
+    pub fn stop_and_join(mut self, timeout: Duration) -> Result<(), Error> {
+        self.finish(timeout)
+    }
+
+    fn finish(&mut self, timeout: Duration) -> Result<(), Error> {
+        self.ev_loop.quit();
+
+        let th = match self.th.take() {
+            Some(th) => th,
+            None => return Ok(()),
+        };
+
+        match self.finished.recv_timeout(timeout) {
+            Ok(_) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                th.join()
+                    .map_err(|_| {
+                        let msg = format!("Fail to join thread for {}.", self.name);
+                        Error::new(FileError::Failed, &msg)
+                    })
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let msg = format!("Timeout to join thread for {}.", self.name);
+                Err(Error::new(FileError::Again, &msg))
+            }
+        }
+    }
+
     fn attach_src_to_ctx(&mut self, src: &Source) {
         let ctx = self.ev_loop.get_context();
         src.attach(Some(&ctx));
@@ -0,0 +1,265 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, RecvError, TryIter};
+
+use alsactl::ElemId;
+
+/// The trait for event queued to the event loop of runtime, to be coalesced.
+pub trait CoalescedEvent {
+    /// The identifier of element when the event is just for the change of its value. The other
+    /// events for element, like addition and removal, are never coalesced.
+    fn value_changed_elem_id(&self) -> Option<&ElemId>;
+
+    /// Whether the event is a tick of interval timer.
+    fn is_interval_tick(&self) -> bool;
+}
+
+/// Coalesce the batch of events. For the change of value, the latest event is kept for each
+/// element, since the value is read from the card at dispatch. For the ticks of interval timer,
+/// the latest one is kept. The order of the kept events is preserved.
+pub fn coalesce_events<E: CoalescedEvent>(events: Vec<E>) -> Vec<E> {
+    let mut elem_id_list: Vec<ElemId> = Vec::new();
+    let mut ticked = false;
+
+    let mut kept: Vec<E> = events
+        .into_iter()
+        .rev()
+        .filter(|ev| {
+            if let Some(elem_id) = ev.value_changed_elem_id() {
+                if elem_id_list.iter().any(|e| e.eq(elem_id)) {
+                    false
+                } else {
+                    elem_id_list.push(elem_id.clone());
+                    true
+                }
+            } else if ev.is_interval_tick() {
+                !std::mem::replace(&mut ticked, true)
+            } else {
+                true
+            }
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
+/// The queue of events for the event loop of runtime. The events are drained from the channel in
+/// batch and coalesced, so that the burst of writes by applications, like sweep of fader, doesn't
+/// fill the channel and block the threads to dispatch events, e.g. for disconnection of node.
+#[derive(Debug)]
+pub struct EventQueue<E: CoalescedEvent> {
+    rx: Receiver<E>,
+    pending: VecDeque<E>,
+}
+
+impl<E: CoalescedEvent> EventQueue<E> {
+    /// The maximum number of events drained from the channel at once.
+    pub const BATCH_SIZE: usize = 64;
+
+    pub fn new(rx: Receiver<E>) -> Self {
+        Self {
+            rx,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Receive the event. The thread is blocked until any event arrives.
+    pub fn recv(&mut self) -> Result<E, RecvError> {
+        if self.pending.is_empty() {
+            let mut events = vec![self.rx.recv()?];
+            events.extend(self.rx.try_iter().take(Self::BATCH_SIZE - 1));
+            self.pending.extend(coalesce_events(events));
+        }
+        // The queue is not empty here.
+        self.pending.pop_front().ok_or(RecvError)
+    }
+
+    /// Drain the events in the channel without blocking. The pending events are discarded.
+    pub fn try_iter(&mut self) -> TryIter<'_, E> {
+        self.pending.clear();
+        self.rx.try_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::card_cntr::*;
+    use alsactl::*;
+    use glib::Error;
+    use std::sync::mpsc;
+
+    enum TestEvent {
+        Elem(ElemId, ElemEventMask),
+        Timer,
+        Disconnected,
+    }
+
+    impl CoalescedEvent for TestEvent {
+        fn value_changed_elem_id(&self) -> Option<&ElemId> {
+            match self {
+                TestEvent::Elem(elem_id, events) if *events == ElemEventMask::VALUE => {
+                    Some(elem_id)
+                }
+                _ => None,
+            }
+        }
+
+        fn is_interval_tick(&self) -> bool {
+            matches!(self, TestEvent::Timer)
+        }
+    }
+
+    const TEST_ELEM_NAME: &str = "fader";
+
+    #[derive(Default)]
+    struct CountingModel {
+        vals: Vec<i32>,
+        write_count: usize,
+    }
+
+    impl CtlModel<hinawa::SndUnit> for CountingModel {
+        fn load(&mut self, _: &mut hinawa::SndUnit, _: &mut CardCntr) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn read(
+            &mut self,
+            _: &mut hinawa::SndUnit,
+            elem_id: &ElemId,
+            elem_value: &mut ElemValue,
+        ) -> Result<bool, Error> {
+            match elem_id.get_name().as_str() {
+                TEST_ELEM_NAME => {
+                    elem_value.set_int(&[self.vals[elem_id.get_index() as usize]]);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        fn write(
+            &mut self,
+            _: &mut hinawa::SndUnit,
+            elem_id: &ElemId,
+            _: &ElemValue,
+            new: &ElemValue,
+        ) -> Result<bool, Error> {
+            match elem_id.get_name().as_str() {
+                TEST_ELEM_NAME => {
+                    let mut vals = [0];
+                    new.get_int(&mut vals);
+                    self.vals[elem_id.get_index() as usize] = vals[0];
+                    self.write_count += 1;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+
+    #[test]
+    fn test_coalesce_events() {
+        let a = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TEST_ELEM_NAME, 0);
+        let b = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TEST_ELEM_NAME, 1);
+
+        let events = vec![
+            TestEvent::Elem(a.clone(), ElemEventMask::ADD),
+            TestEvent::Timer,
+            TestEvent::Elem(a.clone(), ElemEventMask::VALUE),
+            TestEvent::Elem(b.clone(), ElemEventMask::VALUE),
+            TestEvent::Timer,
+            TestEvent::Elem(a.clone(), ElemEventMask::VALUE),
+            TestEvent::Disconnected,
+            TestEvent::Timer,
+        ];
+        let kept = coalesce_events(events);
+        assert_eq!(kept.len(), 5);
+        assert!(matches!(&kept[0], TestEvent::Elem(e, m) if *e == a && *m == ElemEventMask::ADD));
+        assert!(matches!(&kept[1], TestEvent::Elem(e, _) if *e == b));
+        assert!(matches!(&kept[2], TestEvent::Elem(e, m) if *e == a && *m == ElemEventMask::VALUE));
+        assert!(matches!(&kept[3], TestEvent::Disconnected));
+        assert!(matches!(&kept[4], TestEvent::Timer));
+    }
+
+    #[test]
+    fn test_event_queue_stress() {
+        const CAPACITY: usize = 32;
+        const ELEM_COUNT: usize = 4;
+        const ROUNDS: usize = 50;
+
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut unit = hinawa::SndUnit::new();
+        let mut model = CountingModel {
+            vals: vec![0; ELEM_COUNT],
+            write_count: 0,
+        };
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TEST_ELEM_NAME, 0);
+        let elem_id_list = card_cntr
+            .add_int_elems(&elem_id, ELEM_COUNT, 0, i32::MAX, 1, 1, None, true)
+            .unwrap();
+        elem_id_list.iter().for_each(|elem_id| {
+            card_cntr
+                .dispatch_elem_event(&mut unit, elem_id, &ElemEventMask::ADD, &mut model)
+                .unwrap();
+        });
+
+        let (tx, rx) = mpsc::sync_channel(CAPACITY);
+        let mut queue = EventQueue::new(rx);
+
+        let mut sent = 0;
+        let mut dispatched = 0;
+        let mut ticks = 0;
+
+        (0..ROUNDS).for_each(|round| {
+            // Sweep of faders by application fills the channel, with ticks of timer.
+            (0..CAPACITY).for_each(|i| {
+                let ev = if i % 8 == 7 {
+                    TestEvent::Timer
+                } else {
+                    let elem_id = &elem_id_list[i % ELEM_COUNT];
+                    let elem_value = ElemValue::new();
+                    elem_value.set_int(&[(round * CAPACITY + i) as i32]);
+                    card_cntr.card.write_value(elem_id, &elem_value).unwrap();
+                    sent += 1;
+                    TestEvent::Elem(elem_id.clone(), ElemEventMask::VALUE)
+                };
+                tx.try_send(ev).unwrap();
+            });
+            // The channel is full.
+            assert!(tx.try_send(TestEvent::Timer).is_err());
+
+            // The channel is available again for the other threads after receiving an event.
+            let mut ev = queue.recv().unwrap();
+            assert!(queue.rx.try_recv().is_err());
+
+            loop {
+                match ev {
+                    TestEvent::Elem(elem_id, events) => {
+                        card_cntr
+                            .dispatch_elem_event(&mut unit, &elem_id, &events, &mut model)
+                            .unwrap();
+                        dispatched += 1;
+                    }
+                    TestEvent::Timer => ticks += 1,
+                    TestEvent::Disconnected => unreachable!(),
+                }
+                if queue.pending.is_empty() {
+                    break;
+                }
+                ev = queue.recv().unwrap();
+            }
+        });
+
+        assert_eq!(dispatched, ROUNDS * ELEM_COUNT);
+        assert!(dispatched < sent / 4);
+        assert_eq!(model.write_count, dispatched);
+        assert_eq!(ticks, ROUNDS);
+
+        // The latest value is delivered to the model.
+        let last = ((ROUNDS - 1) * CAPACITY) as i32;
+        assert_eq!(model.vals, vec![last + 28, last + 29, last + 30, last + 27]);
+    }
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use std::sync::mpsc;
+use std::time::Duration;
+
+use glib::{source, Error};
+
+use alsactl::{CardExtManual, ElemId, ElemIfaceType, ElemValueExtManual};
+
+use crate::{card_cntr::CardCntr, dispatcher::Dispatcher};
+
+/// The trait to operate any indicator in the unit so that user can identify it.
+pub trait IdentifyModel<O> {
+    fn blink_indicator(&mut self, unit: &mut O, on: bool) -> Result<(), Error>;
+    fn restore_indicator(&mut self, unit: &mut O) -> Result<(), Error>;
+}
+
+/// The state of countdown for blinking the indicator.
+#[derive(Default, Debug)]
+pub struct IdentifyBlink {
+    remaining: usize,
+}
+
+impl IdentifyBlink {
+    /// The interval between toggles of the indicator.
+    pub const INTERVAL: Duration = Duration::from_millis(250);
+
+    // Around 5 seconds.
+    const COUNT: usize = 20;
+
+    /// Start countdown, or restart it when it is already running.
+    pub fn start(&mut self) {
+        self.remaining = Self::COUNT;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Step the countdown. Return the phase of indicator, or None when the countdown is finished.
+    pub fn tick(&mut self) -> Option<bool> {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+        }
+
+        if self.remaining > 0 {
+            Some(self.remaining % 2 > 0)
+        } else {
+            None
+        }
+    }
+}
+
+const IDENTIFY_NAME: &str = "identify";
+const IDENTIFY_DISPATCHER_NAME: &str = "identify timer dispatcher";
+
+/// The structure for the element to identify the unit by blinking the indicator for a while.
+#[derive(Default)]
+pub struct IdentifyCtl {
+    timer: Option<Dispatcher>,
+    blink: IdentifyBlink,
+}
+
+impl IdentifyCtl {
+    pub fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, IDENTIFY_NAME, 0);
+        card_cntr.add_bool_elems(&elem_id, 1, 1, true).map(|_| ())
+    }
+
+    pub fn is_identify_elem(elem_id: &ElemId) -> bool {
+        elem_id.get_name().as_str() == IDENTIFY_NAME
+    }
+
+    /// Start blinking when the element is enabled. The event is sent at the interval of blink.
+    /// The countdown is just restarted when the timer is already running.
+    pub fn handle_elem_event<E: Send + 'static>(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        elem_id: &ElemId,
+        tx: &mpsc::SyncSender<E>,
+        event: fn() -> E,
+    ) -> Result<(), Error> {
+        let mut elem_value = alsactl::ElemValue::new();
+        card_cntr.card.read_elem_value(elem_id, &mut elem_value)?;
+        let mut vals = [false];
+        elem_value.get_bool(&mut vals);
+        if !vals[0] {
+            return Ok(());
+        }
+
+        if self.timer.is_none() {
+            let mut dispatcher = Dispatcher::run(IDENTIFY_DISPATCHER_NAME.to_string())?;
+            let tx = tx.clone();
+            dispatcher.attach_interval_handler(IdentifyBlink::INTERVAL, move || {
+                let _ = tx.send(event());
+                source::Continue(true)
+            });
+            self.timer = Some(dispatcher);
+        }
+
+        self.blink.start();
+
+        Ok(())
+    }
+
+    /// Operate the indicator at the event of timer. When the countdown is finished, the timer is
+    /// stopped, the indicator is restored, and the element is disabled.
+    pub fn blink<O, T: IdentifyModel<O>>(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        unit: &mut O,
+        model: &mut T,
+    ) -> Result<(), Error> {
+        match self.blink.tick() {
+            Some(on) => model.blink_indicator(unit, on),
+            None => {
+                self.timer = None;
+                let res = model.restore_indicator(unit);
+
+                let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, IDENTIFY_NAME, 0);
+                let mut elem_value = alsactl::ElemValue::new();
+                elem_value.set_bool(&[false]);
+                card_cntr.card.write_elem_value(&elem_id, &elem_value)?;
+
+                res
+            }
+        }
+    }
+
+    pub fn timer_mut(&mut self) -> Option<&mut Dispatcher> {
+        self.timer.as_mut()
+    }
+
+    pub fn take_timer(&mut self) -> Option<Dispatcher> {
+        self.timer.take()
+    }
+}
@@ -2,7 +2,20 @@
 // Copyright (c) 2020 Takashi Sakamoto
 pub mod dispatcher;
 pub mod card_cntr;
+pub mod ctl_group;
 pub mod elem_value_accessor;
+pub mod identify;
+pub mod burst;
+pub mod watchdog;
+pub mod config_rom;
+pub mod version;
+pub mod channel_name;
+pub mod snapshot;
+pub mod event_queue;
+pub mod log;
+pub mod worker;
+pub mod config;
+pub mod monitor;
 
 use glib::Error;
 
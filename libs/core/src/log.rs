@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Logging with level per subsystem.
+//!
+//! The message is printed to standard error as a line of plain text, like
+//! "warn: card: Fail to write volume:0", thus journald records it as is when the service runs as
+//! systemd unit. The level is configured by the text of specification, like "warn,card=debug", in
+//! which the entry without name of subsystem is for the default level. The default level is
+//! `info`.
+//!
+//! The macros are available to print the message: `log_error!`, `log_warn!`, `log_info!`, and
+//! `log_debug!`. The first argument is the name of subsystem, and the rest is for `format!`.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+
+/// The name of environment variable for the specification of log level.
+pub const LOG_LEVEL_ENV_NAME: &str = "SND_FIREWIRE_CTL_SERVICES_LOG";
+
+/// The name of subsystem for event loop in runtime.
+pub const LOG_RUNTIME: &str = "runtime";
+/// The name of subsystem for operation of sound card.
+pub const LOG_CARD: &str = "card";
+/// The name of subsystem for dispatcher of events.
+pub const LOG_DISPATCHER: &str = "dispatcher";
+
+/// The level of log. The lower level is more verbose.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!(
+                "The level of log should be one of 'error', 'warn', 'info', and 'debug': {}",
+                s
+            )),
+        }
+    }
+}
+
+/// The filter of log by level per subsystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LogLevel,
+    subsystems: Vec<(String, LogLevel)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogFilter {
+    pub const fn new() -> Self {
+        Self {
+            default: LogLevel::Info,
+            subsystems: Vec::new(),
+        }
+    }
+
+    /// Parse the text of specification, the list of entries delimited by comma. The entry is
+    /// either the level, or the pair of subsystem and level delimited by equal sign.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self::new();
+        spec.split(',')
+            .map(|entry| entry.trim())
+            .filter(|entry| !entry.is_empty())
+            .try_for_each(|entry| -> Result<(), String> {
+                let mut parts = entry.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(level), None) => filter.default = level.parse()?,
+                    (Some(subsystem), Some(level)) => {
+                        let level = level.parse()?;
+                        filter.subsystems.retain(|(s, _)| s != subsystem);
+                        filter.subsystems.push((subsystem.to_string(), level));
+                    }
+                    _ => unreachable!(),
+                }
+                Ok(())
+            })?;
+        Ok(filter)
+    }
+
+    pub fn level(&self, subsystem: &str) -> LogLevel {
+        self.subsystems
+            .iter()
+            .find(|(s, _)| s == subsystem)
+            .map(|&(_, level)| level)
+            .unwrap_or(self.default)
+    }
+
+    pub fn is_enabled(&self, subsystem: &str, level: LogLevel) -> bool {
+        level <= self.level(subsystem)
+    }
+}
+
+// The filter is allocated at first use and never released.
+static FILTER: Lazy<RwLock<LogFilter>> = Lazy::new(|| RwLock::new(LogFilter::new()));
+
+/// Configure the filter of log for the process.
+pub fn set_log_filter(filter: LogFilter) {
+    if let Ok(mut f) = FILTER.write() {
+        *f = filter;
+    }
+}
+
+/// Configure the filter of log by the environment variable, if given.
+pub fn set_log_filter_from_env() -> Result<(), String> {
+    match std::env::var(LOG_LEVEL_ENV_NAME) {
+        Ok(spec) => LogFilter::parse(&spec).map(set_log_filter),
+        Err(_) => Ok(()),
+    }
+}
+
+pub fn log_enabled(subsystem: &str, level: LogLevel) -> bool {
+    FILTER
+        .read()
+        .map(|f| f.is_enabled(subsystem, level))
+        .unwrap_or(level <= LogLevel::Info)
+}
+
+fn format_log(subsystem: &str, level: LogLevel, args: fmt::Arguments) -> String {
+    format!("{}: {}: {}", level.as_str(), subsystem, args)
+}
+
+/// Print the message of log when the level is enabled for the subsystem. Use the macros instead.
+pub fn log(subsystem: &str, level: LogLevel, args: fmt::Arguments) {
+    if log_enabled(subsystem, level) {
+        eprintln!("{}", format_log(subsystem, level, args));
+    }
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($subsystem:expr, $($arg:tt)*) => {
+        $crate::log::log($subsystem, $crate::log::LogLevel::Error, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($subsystem:expr, $($arg:tt)*) => {
+        $crate::log::log($subsystem, $crate::log::LogLevel::Warn, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($subsystem:expr, $($arg:tt)*) => {
+        $crate::log::log($subsystem, $crate::log::LogLevel::Info, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($subsystem:expr, $($arg:tt)*) => {
+        $crate::log::log($subsystem, $crate::log::LogLevel::Debug, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        let filter = LogFilter::default();
+        assert!(filter.is_enabled(LOG_RUNTIME, LogLevel::Info));
+        assert!(!filter.is_enabled(LOG_CARD, LogLevel::Debug));
+
+        let filter = LogFilter::parse("warn, card=debug,runtime=error").unwrap();
+        assert_eq!(filter.level(LOG_CARD), LogLevel::Debug);
+        assert_eq!(filter.level(LOG_RUNTIME), LogLevel::Error);
+        assert_eq!(filter.level(LOG_DISPATCHER), LogLevel::Warn);
+        assert!(filter.is_enabled(LOG_DISPATCHER, LogLevel::Error));
+        assert!(!filter.is_enabled(LOG_DISPATCHER, LogLevel::Info));
+
+        // The later entry takes precedence.
+        let filter = LogFilter::parse("card=debug,card=info").unwrap();
+        assert_eq!(filter.level(LOG_CARD), LogLevel::Info);
+
+        assert!(LogFilter::parse("verbose").is_err());
+        assert!(LogFilter::parse("card=").is_err());
+    }
+
+    #[test]
+    fn test_log_format() {
+        let msg = format_log(LOG_CARD, LogLevel::Warn, format_args!("Fail to write {}:{}", "volume", 0));
+        assert_eq!(msg, "warn: card: Fail to write volume:0");
+    }
+}
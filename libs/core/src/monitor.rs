@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use glib::{Error, FileError};
+
+use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExtManual};
+
+use crate::card_cntr::CardCntr;
+
+pub const MONITOR_DIM_NAME: &str = "monitor-dim";
+pub const MONITOR_MUTE_NAME: &str = "monitor-mute";
+pub const MONITOR_DIM_LEVEL_NAME: &str = "monitor-dim-level";
+
+/// The state of monitor section to dim and mute main output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MonitorSectionState {
+    pub dim: bool,
+    pub mute: bool,
+    /// The attenuation in dB during dim.
+    pub dim_level: i32,
+}
+
+impl Default for MonitorSectionState {
+    fn default() -> Self {
+        Self {
+            dim: false,
+            mute: false,
+            dim_level: Self::DIM_LEVEL_DEFAULT,
+        }
+    }
+}
+
+impl MonitorSectionState {
+    pub const DIM_LEVEL_MIN: i32 = -60;
+    pub const DIM_LEVEL_MAX: i32 = 0;
+    pub const DIM_LEVEL_DEFAULT: i32 = -20;
+
+    /// Whether the main output is attenuated by dim or mute.
+    pub fn is_attenuated(&self) -> bool {
+        self.dim || self.mute
+    }
+
+    /// The linear factor to apply to the underlying volume of main output.
+    pub fn scale(&self) -> f64 {
+        if self.mute {
+            0.0
+        } else if self.dim {
+            10f64.powf(self.dim_level as f64 / 20.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// The trait for monitor section to dim and mute main output, with the same elements in any
+/// runtime.
+///
+/// The attenuation applies on top of the underlying volume of main output. The volume written
+/// directly during dim or mute is kept as the new underlying volume and attenuated as well, then
+/// the newest underlying volume is restored when both of dim and mute are released.
+pub trait MonitorSection<O> {
+    fn monitor_state(&self) -> &MonitorSectionState;
+
+    /// Write the underlying volume of main output with the attenuation of new state, then cache
+    /// the state.
+    fn write_monitor_state(&mut self, unit: &mut O, state: MonitorSectionState)
+        -> Result<(), Error>;
+
+    fn dim(&mut self, unit: &mut O, enable: bool) -> Result<(), Error> {
+        let mut state = *self.monitor_state();
+        state.dim = enable;
+        self.write_monitor_state(unit, state)
+    }
+
+    fn mute(&mut self, unit: &mut O, enable: bool) -> Result<(), Error> {
+        let mut state = *self.monitor_state();
+        state.mute = enable;
+        self.write_monitor_state(unit, state)
+    }
+
+    fn set_dim_level(&mut self, unit: &mut O, level: i32) -> Result<(), Error> {
+        if level < MonitorSectionState::DIM_LEVEL_MIN || level > MonitorSectionState::DIM_LEVEL_MAX {
+            let msg = format!("Invalid level of dim: {}", level);
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+        let mut state = *self.monitor_state();
+        state.dim_level = level;
+        self.write_monitor_state(unit, state)
+    }
+
+    fn load_monitor_section(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        [MONITOR_DIM_NAME, MONITOR_MUTE_NAME]
+            .iter()
+            .try_for_each(|name| {
+                let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, name, 0);
+                card_cntr.add_bool_elems(&elem_id, 1, 1, true).map(|_| ())
+            })?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, MONITOR_DIM_LEVEL_NAME, 0);
+        card_cntr
+            .add_int_elems(
+                &elem_id,
+                1,
+                MonitorSectionState::DIM_LEVEL_MIN,
+                MonitorSectionState::DIM_LEVEL_MAX,
+                1,
+                1,
+                None,
+                true,
+            )
+            .map(|_| ())
+    }
+
+    fn read_monitor_section(&self, elem_id: &ElemId, elem_value: &mut ElemValue)
+        -> Result<bool, Error>
+    {
+        match elem_id.get_name().as_str() {
+            MONITOR_DIM_NAME => {
+                elem_value.set_bool(&[self.monitor_state().dim]);
+                Ok(true)
+            }
+            MONITOR_MUTE_NAME => {
+                elem_value.set_bool(&[self.monitor_state().mute]);
+                Ok(true)
+            }
+            MONITOR_DIM_LEVEL_NAME => {
+                elem_value.set_int(&[self.monitor_state().dim_level]);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn write_monitor_section(&mut self, unit: &mut O, elem_id: &ElemId, elem_value: &ElemValue)
+        -> Result<bool, Error>
+    {
+        match elem_id.get_name().as_str() {
+            MONITOR_DIM_NAME => {
+                let mut vals = [false];
+                elem_value.get_bool(&mut vals);
+                self.dim(unit, vals[0]).map(|_| true)
+            }
+            MONITOR_MUTE_NAME => {
+                let mut vals = [false];
+                elem_value.get_bool(&mut vals);
+                self.mute(unit, vals[0]).map(|_| true)
+            }
+            MONITOR_DIM_LEVEL_NAME => {
+                let mut vals = [0];
+                elem_value.get_int(&mut vals);
+                self.set_dim_level(unit, vals[0]).map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestMonitor {
+        state: MonitorSectionState,
+        underlying: f64,
+        written: f64,
+    }
+
+    impl MonitorSection<()> for TestMonitor {
+        fn monitor_state(&self) -> &MonitorSectionState {
+            &self.state
+        }
+
+        fn write_monitor_state(&mut self, _: &mut (), state: MonitorSectionState)
+            -> Result<(), Error>
+        {
+            self.written = self.underlying * state.scale();
+            self.state = state;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_monitor_section_scale() {
+        let mut state = MonitorSectionState::default();
+        assert!(!state.is_attenuated());
+        assert_eq!(state.scale(), 1.0);
+
+        state.dim = true;
+        assert!(state.is_attenuated());
+        assert!((state.scale() - 0.1).abs() < 1e-9);
+
+        state.dim_level = MonitorSectionState::DIM_LEVEL_MAX;
+        assert_eq!(state.scale(), 1.0);
+
+        // The mute takes precedence over the dim.
+        state.mute = true;
+        assert_eq!(state.scale(), 0.0);
+    }
+
+    #[test]
+    fn test_monitor_section_ops() {
+        let mut monitor = TestMonitor {
+            underlying: 1.0,
+            ..Default::default()
+        };
+
+        monitor.set_dim_level(&mut (), -6).unwrap();
+        assert_eq!(monitor.written, 1.0);
+
+        monitor.dim(&mut (), true).unwrap();
+        assert!((monitor.written - 0.501187).abs() < 1e-6);
+
+        monitor.mute(&mut (), true).unwrap();
+        assert_eq!(monitor.written, 0.0);
+
+        monitor.mute(&mut (), false).unwrap();
+        monitor.dim(&mut (), false).unwrap();
+        assert_eq!(monitor.written, 1.0);
+
+        assert!(monitor.set_dim_level(&mut (), 1).is_err());
+        assert!(monitor.set_dim_level(&mut (), -61).is_err());
+        assert_eq!(monitor.monitor_state().dim_level, -6);
+    }
+}
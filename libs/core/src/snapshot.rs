@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use glib::Error;
+
+use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExtManual};
+
+use crate::card_cntr::CardCntr;
+
+pub const STATE_SNAPSHOT_NAME: &str = "state-snapshot";
+
+/// The number of bytes for the value of snapshot element, nul-terminated JSON text. It is the
+/// maximum size of bytes element in ALSA control core.
+pub const STATE_SNAPSHOT_BYTES_COUNT: usize = 512;
+
+/// The maximum number of entries in an array of snapshot. The rest of entries is truncated.
+pub const STATE_SNAPSHOT_ARRAY_MAX_LENGTH: usize = 32;
+
+const TRUNCATED_KEY: &str = "truncated";
+
+fn escape_json_string(text: &str) -> String {
+    text.chars().fold(String::from("\""), |mut s, c| {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            c if c.is_control() => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+        s
+    }) + "\""
+}
+
+/// The builder of JSON object for snapshot of device state. The object is flat; the name of key
+/// is expected to include prefix for grouping, like "clock.source". The arrays longer than
+/// `STATE_SNAPSHOT_ARRAY_MAX_LENGTH` and the trailing fields beyond `STATE_SNAPSHOT_BYTES_COUNT`
+/// are truncated, then "truncated" field is added.
+#[derive(Default, Debug)]
+pub struct StateSnapshotBuilder {
+    fields: Vec<String>,
+    truncated: bool,
+}
+
+impl StateSnapshotBuilder {
+    fn push(&mut self, key: &str, val: String) -> &mut Self {
+        self.fields.push(format!("{}:{}", escape_json_string(key), val));
+        self
+    }
+
+    fn push_array<T, F>(&mut self, key: &str, vals: &[T], cb: F) -> &mut Self
+    where
+        F: Fn(&T) -> String,
+    {
+        if vals.len() > STATE_SNAPSHOT_ARRAY_MAX_LENGTH {
+            self.truncated = true;
+        }
+        let entries: Vec<String> = vals
+            .iter()
+            .take(STATE_SNAPSHOT_ARRAY_MAX_LENGTH)
+            .map(cb)
+            .collect();
+        self.push(key, format!("[{}]", entries.join(",")))
+    }
+
+    pub fn bool(&mut self, key: &str, val: bool) -> &mut Self {
+        self.push(key, val.to_string())
+    }
+
+    pub fn int<T: Into<i64>>(&mut self, key: &str, val: T) -> &mut Self {
+        self.push(key, val.into().to_string())
+    }
+
+    pub fn string(&mut self, key: &str, val: &str) -> &mut Self {
+        self.push(key, escape_json_string(val))
+    }
+
+    /// The null is used for the absent value.
+    pub fn optional_string(&mut self, key: &str, val: Option<&str>) -> &mut Self {
+        let v = val.map(escape_json_string).unwrap_or_else(|| "null".to_string());
+        self.push(key, v)
+    }
+
+    pub fn bool_array(&mut self, key: &str, vals: &[bool]) -> &mut Self {
+        self.push_array(key, vals, |v| v.to_string())
+    }
+
+    pub fn int_array<T: Copy + Into<i64>>(&mut self, key: &str, vals: &[T]) -> &mut Self {
+        self.push_array(key, vals, |&v| v.into().to_string())
+    }
+
+    /// Build the value of bytes element. The text is terminated and padded by nul.
+    pub fn build(&self) -> Vec<u8> {
+        let mut count = self.fields.len();
+        let mut truncated = self.truncated;
+
+        let text = loop {
+            let mut fields = self.fields[..count].to_vec();
+            if truncated {
+                fields.push(format!("{}:true", escape_json_string(TRUNCATED_KEY)));
+            }
+            let text = format!("{{{}}}", fields.join(","));
+            // Leave one byte at least for nul terminator.
+            if text.len() < STATE_SNAPSHOT_BYTES_COUNT || count == 0 {
+                break text;
+            }
+            count -= 1;
+            truncated = true;
+        };
+
+        let mut raw = text.into_bytes();
+        raw.resize(STATE_SNAPSHOT_BYTES_COUNT, 0);
+        raw
+    }
+}
+
+/// The trait for model to provide snapshot of device state.
+pub trait StateSnapshotProvider {
+    fn build_snapshot(&self, builder: &mut StateSnapshotBuilder);
+}
+
+fn snapshot_bytes<T: StateSnapshotProvider>(provider: &T) -> Vec<u8> {
+    let mut builder = StateSnapshotBuilder::default();
+    provider.build_snapshot(&mut builder);
+    builder.build()
+}
+
+/// The structure for read-only element to expose snapshot of device state in JSON text.
+///
+/// ALSA control core doesn't deliver read operation by applications to the service program, thus
+/// the value of element is cached in kernel space. The snapshot is serialized again just when the
+/// model invalidates it at the change of state, and when the element is included in the list for
+/// notification or measurement of the model. No timer is dedicated for it.
+#[derive(Default, Debug)]
+pub struct StateSnapshotCtl {
+    pub elem_id_list: Vec<ElemId>,
+    stale: bool,
+}
+
+impl StateSnapshotCtl {
+    pub fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STATE_SNAPSHOT_NAME, 0);
+        card_cntr
+            .add_snapshot_elem(&elem_id)
+            .map(|mut elem_id_list| self.elem_id_list.append(&mut elem_id_list))
+    }
+
+    /// Mark the snapshot as stale so that it is serialized at next refresh.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn read<T: StateSnapshotProvider>(
+        &self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+        provider: &T,
+    ) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            STATE_SNAPSHOT_NAME => {
+                elem_value.set_bytes(&snapshot_bytes(provider));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Take the stale flag for the snapshot element. The model which builds the provider from
+    /// its own fields is expected to read the element just when it returns true.
+    pub fn take_stale(&mut self, elem_id: &ElemId) -> bool {
+        elem_id.get_name().as_str() == STATE_SNAPSHOT_NAME && std::mem::take(&mut self.stale)
+    }
+
+    /// Serialize the snapshot again only if it is stale. The returned value is false for the
+    /// fresh snapshot, so that the element is not written and applications are not notified.
+    pub fn refresh<T: StateSnapshotProvider>(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+        provider: &T,
+    ) -> Result<bool, Error> {
+        if self.take_stale(elem_id) {
+            self.read(elem_id, elem_value, provider)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(raw: &[u8]) -> &str {
+        let len = raw.iter().position(|&b| b == 0).unwrap();
+        std::str::from_utf8(&raw[..len]).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_builder() {
+        let mut builder = StateSnapshotBuilder::default();
+        builder
+            .string("clock.source", "S/PDIF \"coaxial\"")
+            .optional_string("clock.external-rate", None)
+            .bool("lock.adat", true)
+            .int("resource-usage", 123u32)
+            .int_array("volumes", &[-1i32, 0, 1]);
+        let raw = builder.build();
+        assert_eq!(raw.len(), STATE_SNAPSHOT_BYTES_COUNT);
+        assert_eq!(
+            text(&raw),
+            "{\"clock.source\":\"S/PDIF \\\"coaxial\\\"\",\"clock.external-rate\":null,\
+             \"lock.adat\":true,\"resource-usage\":123,\"volumes\":[-1,0,1]}"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_take_stale() {
+        let mut ctl = StateSnapshotCtl::default();
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STATE_SNAPSHOT_NAME, 0);
+        let other = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, "other", 0);
+
+        assert!(!ctl.take_stale(&elem_id));
+        ctl.invalidate();
+        // The flag is kept for the other element.
+        assert!(!ctl.take_stale(&other));
+        assert!(ctl.take_stale(&elem_id));
+        assert!(!ctl.take_stale(&elem_id));
+    }
+
+    #[test]
+    fn test_snapshot_truncation() {
+        let mut builder = StateSnapshotBuilder::default();
+        let vals = vec![false; STATE_SNAPSHOT_ARRAY_MAX_LENGTH + 1];
+        builder.bool_array("flags", &vals);
+        let raw = builder.build();
+        let expected = format!(
+            "{{\"flags\":[{}],\"truncated\":true}}",
+            vec!["false"; STATE_SNAPSHOT_ARRAY_MAX_LENGTH].join(",")
+        );
+        assert_eq!(text(&raw), expected);
+
+        let mut builder = StateSnapshotBuilder::default();
+        (0..100).for_each(|i| {
+            builder.int(&format!("field-{}", i), i);
+        });
+        let raw = builder.build();
+        assert_eq!(raw.len(), STATE_SNAPSHOT_BYTES_COUNT);
+        let t = text(&raw);
+        assert!(t.starts_with("{\"field-0\":0,"));
+        assert!(t.ends_with(",\"truncated\":true}"));
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use glib::Error;
+
+use alsactl::{CardExtManual, ElemId, ElemIfaceType, ElemValueExtManual};
+
+use crate::card_cntr::CardCntr;
+
+pub const SERVICE_VERSION_NAME: &str = "service-version";
+pub const PROTOCOL_CRATE_VERSION_NAME: &str = "protocol-crate-version";
+
+/// The number of bytes for version string, terminated or padded by nul.
+pub const VERSION_BYTES_COUNT: usize = 32;
+
+/// Build the value of bytes element for the version string. The string longer than the element
+/// is truncated.
+pub fn build_version_bytes(version: &str) -> [u8; VERSION_BYTES_COUNT] {
+    let mut vals = [0; VERSION_BYTES_COUNT];
+    version
+        .as_bytes()
+        .iter()
+        .take(VERSION_BYTES_COUNT - 1)
+        .zip(vals.iter_mut())
+        .for_each(|(&b, v)| *v = b);
+    vals
+}
+
+/// Parse the value of bytes element for the version string into major, minor, and patch numbers.
+pub fn parse_version_bytes(vals: &[u8]) -> Option<(u32, u32, u32)> {
+    let len = vals.iter().position(|&b| b == 0).unwrap_or(vals.len());
+    let version = std::str::from_utf8(&vals[..len]).ok()?;
+    // The pre-release and build metadata are not required.
+    let core = version.split(|c| c == '-' || c == '+').next()?;
+    let mut numbers = core.split('.').map(|n| n.parse::<u32>());
+    match (numbers.next(), numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// Add elements to expose version of the runtime crate and the protocol crate. The elements are
+/// kept locked by the runtime, thus read-only for the other processes.
+pub fn add_version_elems(
+    card_cntr: &mut CardCntr,
+    service_version: &str,
+    protocol_crate_version: &str,
+) -> Result<(), Error> {
+    [
+        (SERVICE_VERSION_NAME, service_version),
+        (PROTOCOL_CRATE_VERSION_NAME, protocol_crate_version),
+    ]
+    .iter()
+    .try_for_each(|&(name, version)| {
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0);
+        let mut elem_id_list =
+            card_cntr.add_bytes_elems(&elem_id, 1, VERSION_BYTES_COUNT, None, false)?;
+
+        let mut elem_value = alsactl::ElemValue::new();
+        elem_value.set_bytes(&build_version_bytes(version));
+        card_cntr.card.write_elem_value(&elem_id_list.remove(0), &elem_value)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_bytes() {
+        let vals = build_version_bytes(env!("CARGO_PKG_VERSION"));
+        assert_ne!(vals[0], 0);
+        assert!(parse_version_bytes(&vals).is_some());
+
+        let vals = build_version_bytes("1.22.333-rc1");
+        assert_eq!(parse_version_bytes(&vals), Some((1, 22, 333)));
+
+        let vals = build_version_bytes("");
+        assert_eq!(parse_version_bytes(&vals), None);
+
+        let vals = build_version_bytes("1.2");
+        assert_eq!(parse_version_bytes(&vals), None);
+
+        let long = "0.1.0+".to_string() + &"x".repeat(VERSION_BYTES_COUNT * 2);
+        let vals = build_version_bytes(&long);
+        assert_eq!(vals[VERSION_BYTES_COUNT - 1], 0);
+        assert_eq!(parse_version_bytes(&vals), Some((0, 1, 0)));
+    }
+}
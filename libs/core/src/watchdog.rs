@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+use std::time::{Duration, Instant};
+
+use crate::burst::{Clock, SystemClock};
+
+/// The watchdog to detect lack of events expected to arrive periodically.
+#[derive(Debug)]
+pub struct Watchdog<C: Clock> {
+    clock: C,
+    timeout: Duration,
+    last: Instant,
+}
+
+impl Default for Watchdog<SystemClock> {
+    fn default() -> Self {
+        Self::new(SystemClock, Self::DEFAULT_TIMEOUT)
+    }
+}
+
+impl<C: Clock> Watchdog<C> {
+    /// The default period without any event to regard as expired.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new(clock: C, timeout: Duration) -> Self {
+        let last = clock.now();
+        Self {
+            clock,
+            timeout,
+            last,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Change the period without any event to regard as expired. Zero disables the watchdog.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+        self.feed();
+    }
+
+    /// Record arrival of event.
+    pub fn feed(&mut self) {
+        self.last = self.clock.now();
+    }
+
+    /// Check whether no event arrives within the timeout. The countdown is restarted when
+    /// expired, thus it returns true once per the timeout.
+    pub fn check_expired(&mut self) -> bool {
+        if self.timeout == Duration::default() {
+            return false;
+        }
+
+        let now = self.clock.now();
+        if now.duration_since(self.last) < self.timeout {
+            false
+        } else {
+            self.last = now;
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct TestClock {
+        base: Instant,
+        elapsed: Rc<Cell<Duration>>,
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Instant {
+            self.base + self.elapsed.get()
+        }
+    }
+
+    fn create_watchdog(timeout_ms: u64) -> (Watchdog<TestClock>, Rc<Cell<Duration>>) {
+        let elapsed = Rc::new(Cell::new(Duration::default()));
+        let clock = TestClock {
+            base: Instant::now(),
+            elapsed: elapsed.clone(),
+        };
+        (Watchdog::new(clock, Duration::from_millis(timeout_ms)), elapsed)
+    }
+
+    fn advance(elapsed: &Rc<Cell<Duration>>, ms: u64) {
+        elapsed.set(elapsed.get() + Duration::from_millis(ms));
+    }
+
+    #[test]
+    fn test_fed() {
+        let (mut watchdog, elapsed) = create_watchdog(1000);
+
+        (0..10).for_each(|_| {
+            advance(&elapsed, 999);
+            assert!(!watchdog.check_expired());
+            watchdog.feed();
+        });
+    }
+
+    #[test]
+    fn test_expired() {
+        let (mut watchdog, elapsed) = create_watchdog(1000);
+
+        advance(&elapsed, 999);
+        assert!(!watchdog.check_expired());
+        advance(&elapsed, 1);
+        assert!(watchdog.check_expired());
+
+        // The countdown is restarted.
+        assert!(!watchdog.check_expired());
+        advance(&elapsed, 999);
+        assert!(!watchdog.check_expired());
+        advance(&elapsed, 1);
+        assert!(watchdog.check_expired());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let (mut watchdog, elapsed) = create_watchdog(1000);
+
+        watchdog.set_timeout(Duration::default());
+        advance(&elapsed, 10000);
+        assert!(!watchdog.check_expired());
+
+        watchdog.set_timeout(Duration::from_millis(500));
+        assert!(!watchdog.check_expired());
+        advance(&elapsed, 500);
+        assert!(watchdog.check_expired());
+    }
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Worker thread to execute long operation out of the event loop of runtime.
+//!
+//! The job is submitted with the key of logical resource. The jobs are executed one by one in the
+//! order of submission, thus the jobs for the same resource are serialized. When a job is
+//! submitted while the job for the same resource is still queued, the queued one is superseded
+//! by the new one, instead of piling up. The job in flight is not cancelled. The result of job is
+//! passed to the callback in the worker thread, which is expected to queue an event to the event
+//! loop of runtime so that the cache is updated and elements are notified.
+
+use glib::{Error, FileError};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::log::*;
+use crate::log_error;
+
+type Job<R> = Box<dyn FnOnce() -> R + Send>;
+
+struct JobQueue<K, R> {
+    jobs: VecDeque<(K, Job<R>)>,
+    closed: bool,
+}
+
+type SharedQueue<K, R> = Arc<(Mutex<JobQueue<K, R>>, Condvar)>;
+
+pub struct Worker<K, R> {
+    name: String,
+    queue: SharedQueue<K, R>,
+    th: Option<thread::JoinHandle<()>>,
+}
+
+impl<K, R> Drop for Worker<K, R> {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.queue;
+        if let Ok(queue) = &mut lock.lock() {
+            // The queued jobs are discarded.
+            queue.jobs.clear();
+            queue.closed = true;
+        }
+        cvar.notify_one();
+
+        if let Some(th) = self.th.take() {
+            if th.join().is_err() {
+                log_error!(LOG_RUNTIME, "Fail to join thread for {}.", self.name);
+            }
+        }
+    }
+}
+
+impl<K, R> Worker<K, R>
+where
+    K: PartialEq + Send + 'static,
+    R: Send + 'static,
+{
+    /// Launch the thread. The callback is called with the key and the result of each job.
+    pub fn run<F>(name: &str, complete: F) -> Result<Self, Error>
+    where
+        F: Fn(K, R) + Send + 'static,
+    {
+        let queue: SharedQueue<K, R> = Arc::new((
+            Mutex::new(JobQueue {
+                jobs: VecDeque::new(),
+                closed: false,
+            }),
+            Condvar::new(),
+        ));
+
+        let q = queue.clone();
+        let th = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                while let Some((key, job)) = Self::take_job(&q) {
+                    complete(key, job());
+                }
+            })
+            .map_err(|e| {
+                let msg = format!("Fail to launch thread for {}: {}", name, e);
+                Error::new(FileError::Failed, &msg)
+            })?;
+
+        Ok(Self {
+            name: name.to_string(),
+            queue,
+            th: Some(th),
+        })
+    }
+
+    // Block till any job is queued. None when the worker is closed.
+    fn take_job(queue: &SharedQueue<K, R>) -> Option<(K, Job<R>)> {
+        let (lock, cvar) = &**queue;
+        let mut guard = lock.lock().ok()?;
+        loop {
+            if guard.closed {
+                return None;
+            }
+            if let Some(entry) = guard.jobs.pop_front() {
+                return Some(entry);
+            }
+            guard = cvar.wait(guard).ok()?;
+        }
+    }
+
+    /// Submit the job for the resource. Return true when the job queued for the same resource is
+    /// superseded.
+    pub fn submit<F>(&self, key: K, job: F) -> bool
+    where
+        F: FnOnce() -> R + Send + 'static,
+    {
+        let (lock, cvar) = &*self.queue;
+        let superseded = match &mut lock.lock() {
+            Ok(queue) => match queue.jobs.iter_mut().find(|(k, _)| k.eq(&key)) {
+                Some(entry) => {
+                    entry.1 = Box::new(job);
+                    true
+                }
+                None => {
+                    queue.jobs.push_back((key, Box::new(job)));
+                    false
+                }
+            },
+            Err(_) => false,
+        };
+        cvar.notify_one();
+        superseded
+    }
+
+    /// The number of jobs queued, except for the job in flight.
+    pub fn queued_count(&self) -> usize {
+        let (lock, _) = &*self.queue;
+        lock.lock().map(|queue| queue.jobs.len()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_worker_supersede() {
+        let (tx, rx) = mpsc::channel();
+        let worker = Worker::run("test worker", move |key: &'static str, val: u32| {
+            let _ = tx.send((key, val));
+        })
+        .unwrap();
+
+        // Block the worker by the first job.
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        assert!(!worker.submit("clock", move || {
+            let _ = gate_rx.recv();
+            0
+        }));
+        while worker.queued_count() > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // The job in flight is not superseded, while the queued one is.
+        assert!(!worker.submit("mixer", || 1));
+        assert!(!worker.submit("clock", || 2));
+        assert!(worker.submit("mixer", || 3));
+        assert!(worker.submit("mixer", || 4));
+        assert_eq!(worker.queued_count(), 2);
+
+        gate_tx.send(()).unwrap();
+
+        let results: Vec<(&str, u32)> = (0..3)
+            .map(|_| rx.recv_timeout(Duration::from_secs(1)).unwrap())
+            .collect();
+        assert_eq!(results, vec![("clock", 0), ("mixer", 4), ("clock", 2)]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_worker_drop() {
+        let (tx, rx) = mpsc::channel();
+        let worker = Worker::run("test worker", move |_: u32, val: u32| {
+            let _ = tx.send(val);
+        })
+        .unwrap();
+
+        worker.submit(0, || 10);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), 10);
+
+        // The thread finishes.
+        drop(worker);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+}
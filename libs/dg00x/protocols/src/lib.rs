@@ -7,7 +7,12 @@
 
 use glib::{Error, FileError};
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq};
+
+use fw_transaction::FwTransaction;
+
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// The protocol implementation for Digi 002.
 #[derive(Default)]
@@ -42,17 +47,18 @@ fn read_quadlet(
     node: &mut FwNode,
     offset: u64,
     timeout_ms: u32,
+) -> Result<u32, Error> {
+    read_quadlet_by(&mut (&*req, &*node), offset, timeout_ms)
+}
+
+fn read_quadlet_by<T: FwTransaction>(
+    tx: &mut T,
+    offset: u64,
+    timeout_ms: u32,
 ) -> Result<u32, Error> {
     let mut quadlet = [0; 4];
-    req.transaction_sync(
-        node,
-        FwTcode::ReadQuadletRequest,
-        BASE_OFFSET + offset,
-        quadlet.len(),
-        &mut quadlet,
-        timeout_ms,
-    )
-    .map(|_| u32::from_be_bytes(quadlet))
+    tx.read(BASE_OFFSET + offset, &mut quadlet, timeout_ms)
+        .map(|_| u32::from_be_bytes(quadlet))
 }
 
 fn write_quadlet(
@@ -61,17 +67,19 @@ fn write_quadlet(
     offset: u64,
     val: u32,
     timeout_ms: u32,
+) -> Result<(), Error> {
+    write_quadlet_by(&mut (&*req, &*node), offset, val, timeout_ms)
+}
+
+fn write_quadlet_by<T: FwTransaction>(
+    tx: &mut T,
+    offset: u64,
+    val: u32,
+    timeout_ms: u32,
 ) -> Result<(), Error> {
     let mut quadlet = [0; 4];
     quadlet.copy_from_slice(&val.to_be_bytes());
-    req.transaction_sync(
-        node,
-        FwTcode::WriteQuadletRequest,
-        BASE_OFFSET + offset,
-        quadlet.len(),
-        &mut quadlet,
-        timeout_ms,
-    )
+    tx.write(BASE_OFFSET + offset, &mut quadlet, timeout_ms)
 }
 
 /// The enumeration for frequency of media clock.
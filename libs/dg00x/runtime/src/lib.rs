@@ -16,7 +16,12 @@ use alsactl::{CardExt, CardExtManual, ElemEventMask, ElemIfaceType, ElemId, Elem
 
 use core::dispatcher::*;
 use core::card_cntr::*;
+use core::version::*;
+use core::event_queue::*;
 use core::RuntimeOperation;
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 use ieee1212_config_rom::ConfigRom;
 use ta1394::config_rom::Ta1394ConfigRom;
@@ -25,6 +30,7 @@ use model::*;
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
@@ -32,6 +38,19 @@ enum Event {
     Timer,
 }
 
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => Some(elem_id),
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
+}
+
 enum Model {
     Digi002(Digi002Model),
     Digi003(Digi003Model),
@@ -41,7 +60,7 @@ pub struct Dg00xRuntime {
     unit: hinawa::SndDg00x,
     model: Model,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<Dispatcher>,
     notified_elems: Vec<ElemId>,
@@ -103,6 +122,7 @@ impl RuntimeOperation<u32> for Dg00xRuntime {
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         let dispatchers = Vec::new();
         let notified_elems = Vec::new();
@@ -146,6 +166,12 @@ impl RuntimeOperation<u32> for Dg00xRuntime {
             let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         }
 
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            dg00x_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -158,8 +184,9 @@ impl RuntimeOperation<u32> for Dg00xRuntime {
 
             match ev {
                 Event::Shutdown | Event::Disconnected => break,
+                Event::Reload => self.reload(),
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Elem((elem_id, events)) => {
                     if elem_id.get_name() != Self::TIMER_NAME {
@@ -227,6 +254,16 @@ impl RuntimeOperation<u32> for Dg00xRuntime {
     }
 }
 
+impl ConfigReloadOperation for Dg00xRuntime {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> Dg00xRuntime {
     const NODE_DISPATCHER_NAME: &'a str = "node event dispatcher";
     const SYSTEM_DISPATCHER_NAME: &'a str = "system event dispatcher";
@@ -269,6 +306,11 @@ impl<'a> Dg00xRuntime {
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr
@@ -297,7 +339,7 @@ impl<'a> Dg00xRuntime {
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = Dispatcher::run(Self::TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(Self::TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(Self::TIMER_INTERVAL), move || {
             let _ = tx.send(Event::Timer);
             source::Continue(true)
         });
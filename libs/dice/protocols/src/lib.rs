@@ -16,6 +16,9 @@ pub mod loud;
 pub mod focusrite;
 pub mod presonus;
 
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 const QUADLET_SIZE: usize = 4;
 
 /// The trait to represent utility for conversion between quadlet-aligned byte array and computed value.
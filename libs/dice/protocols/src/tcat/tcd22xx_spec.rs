@@ -213,15 +213,13 @@ pub trait Tcd22xxSpecOperation {
 }
 
 pub trait Tcd22xxRouterOperation: Tcd22xxSpecOperation {
-    fn update_router_entries(
-        node: &mut FwNode,
-        req: &mut FwReq,
-        sections: &ExtensionSections,
+    /// Refine the entries and return them when they differ from the cached entries, thus should
+    /// be written to the device.
+    fn compute_router_entries_for_update(
         caps: &ExtensionCaps,
-        state: &mut Tcd22xxState,
+        state: &Tcd22xxState,
         entries: Vec<RouterEntry>,
-        timeout_ms: u32
-    ) -> Result<(), Error> {
+    ) -> Result<Option<Vec<RouterEntry>>, Error> {
         let srcs: Vec<_> = state.real_blk_pair.0.iter()
             .chain(state.stream_blk_pair.0.iter())
             .chain(state.mixer_blk_pair.0.iter())
@@ -239,6 +237,22 @@ pub trait Tcd22xxRouterOperation: Tcd22xxSpecOperation {
         }
 
         if entries != state.router_entries {
+            Ok(Some(entries))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn update_router_entries(
+        node: &mut FwNode,
+        req: &mut FwReq,
+        sections: &ExtensionSections,
+        caps: &ExtensionCaps,
+        state: &mut Tcd22xxState,
+        entries: Vec<RouterEntry>,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        if let Some(entries) = Self::compute_router_entries_for_update(caps, state, entries)? {
             let rate_mode = state.rate_mode;
             RouterSectionProtocol::write_router_entries(
                 req,
@@ -270,6 +284,32 @@ pub trait Tcd22xxRouterOperation: Tcd22xxSpecOperation {
         state: &mut Tcd22xxState,
         timeout_ms: u32
     ) -> Result<(), Error> {
+        let entries = Self::read_router_entries(node, req, sections, caps, state, timeout_ms)?;
+        Self::update_router_entries(node, req, sections, caps, state, entries, timeout_ms)
+    }
+
+    /// Cache the router entries as is. The entries for fixed sources are not added, thus no write
+    /// transaction is initiated.
+    fn cache_router_entries_as_is(
+        node: &mut FwNode,
+        req: &mut FwReq,
+        sections: &ExtensionSections,
+        caps: &ExtensionCaps,
+        state: &mut Tcd22xxState,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        Self::read_router_entries(node, req, sections, caps, state, timeout_ms)
+            .map(|entries| state.router_entries = entries)
+    }
+
+    fn read_router_entries(
+        node: &mut FwNode,
+        req: &mut FwReq,
+        sections: &ExtensionSections,
+        caps: &ExtensionCaps,
+        state: &mut Tcd22xxState,
+        timeout_ms: u32
+    ) -> Result<Vec<RouterEntry>, Error> {
         let rate_mode = state.rate_mode;
         let real_blk_pair = Self::compute_avail_real_blk_pair(rate_mode);
 
@@ -290,15 +330,14 @@ pub trait Tcd22xxRouterOperation: Tcd22xxSpecOperation {
         state.stream_blk_pair = stream_blk_pair;
         state.mixer_blk_pair = mixer_blk_pair;
 
-        let entries = CurrentConfigSectionProtocol::read_current_router_entries(
+        CurrentConfigSectionProtocol::read_current_router_entries(
             req,
             node,
             sections,
             caps,
             rate_mode,
             timeout_ms
-        )?;
-        Self::update_router_entries(node, req, sections, caps, state, entries, timeout_ms)
+        )
     }
 }
 
@@ -387,6 +426,78 @@ pub trait Tcd22xxStateOperation: Tcd22xxRouterOperation +  Tcd22xxMixerOperation
         Self::cache_mixer_coefs(node, req, sections, caps, state, timeout_ms)?;
         Ok(())
     }
+
+    /// Cache the state without any write transaction, to keep the state configured in advance.
+    fn cache_as_is(
+        node: &mut FwNode,
+        req: &mut FwReq,
+        sections: &ExtensionSections,
+        caps: &ExtensionCaps,
+        state: &mut Tcd22xxState,
+        rate_mode: RateMode,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        state.rate_mode = rate_mode;
+        Self::cache_router_entries_as_is(node, req, sections, caps, state, timeout_ms)?;
+        Self::cache_mixer_coefs(node, req, sections, caps, state, timeout_ms)?;
+        Ok(())
+    }
 }
 
 impl<O: Tcd22xxRouterOperation + Tcd22xxMixerOperation> Tcd22xxStateOperation for O {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestProtocol;
+
+    impl Tcd22xxSpecOperation for TestProtocol {
+        const INPUTS: &'static [Input] = &[];
+        const OUTPUTS: &'static [Output] = &[];
+        const FIXED: &'static [SrcBlk] = &[
+            SrcBlk{id: SrcBlkId::Ins0, ch: 0},
+            SrcBlk{id: SrcBlkId::Ins0, ch: 1},
+        ];
+    }
+
+    #[test]
+    fn test_router_entries_for_update() {
+        let mut caps = ExtensionCaps::default();
+        caps.router.maximum_entry_count = 8;
+
+        let mut state = Tcd22xxState::default();
+        state.real_blk_pair = (
+            vec![SrcBlk{id: SrcBlkId::Ins0, ch: 0}, SrcBlk{id: SrcBlkId::Ins0, ch: 1}],
+            vec![DstBlk{id: DstBlkId::Ins0, ch: 0}, DstBlk{id: DstBlkId::Ins0, ch: 1}],
+        );
+
+        // The entries configured in advance, without any entry for the first fixed source.
+        let entries = vec![
+            RouterEntry{
+                dst: DstBlk{id: DstBlkId::Ins0, ch: 0},
+                src: SrcBlk{id: SrcBlkId::Ins0, ch: 1},
+                ..Default::default()
+            },
+        ];
+
+        // The entries cached as is require to be written for the fixed source.
+        state.router_entries = entries.clone();
+        let refined = TestProtocol::compute_router_entries_for_update(&caps, &state, entries.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(refined.len(), 2);
+        assert_eq!(refined[0].src, SrcBlk{id: SrcBlkId::Ins0, ch: 0});
+        assert_eq!(refined[0].dst, DstBlk{id: DstBlkId::Reserved(0xff), ch: 0xff});
+        assert_eq!(refined[1], entries[0]);
+
+        // No write is required for the refined entries.
+        state.router_entries = refined.clone();
+        let res = TestProtocol::compute_router_entries_for_update(&caps, &state, refined.clone());
+        assert!(res.unwrap().is_none());
+
+        caps.router.maximum_entry_count = 1;
+        let res = TestProtocol::compute_router_entries_for_update(&caps, &state, entries);
+        assert!(res.is_err());
+    }
+}
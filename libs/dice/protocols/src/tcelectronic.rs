@@ -27,6 +27,10 @@ use super::tcat::*;
 
 const BASE_OFFSET: usize = 0x00a01000;
 
+/// The mask of notification to refresh cache of all segments. The unit can reset a part of the
+/// segments by bus reset without any notification; e.g. assignment of knob.
+pub const TC_KONNEKT_SEGMENT_REFRESH_MASK: u32 = 0xffff0000;
+
 /// The trait to interpret data of segment.
 pub trait TcKonnektSegmentData: Default {
     fn build(&self, raw: &mut [u8]);
@@ -49,6 +53,31 @@ where
     raw: Vec<u8>,
 }
 
+impl<U> TcKonnektSegment<U>
+where
+    U: TcKonnektSegmentData,
+{
+    /// Replace the cache with the content of segment in the unit, then return whether the
+    /// content differs from the cache.
+    pub fn refresh(&mut self, raw: &[u8]) -> bool {
+        assert_eq!(raw.len(), self.raw.len());
+
+        let changed = self.raw[..] != raw[..];
+        self.raw.copy_from_slice(raw);
+        // The data can be modified by failed write.
+        self.data.parse(&self.raw);
+        changed
+    }
+}
+
+// The offsets of quadlets which differ between the cache and the content to write.
+fn changed_quadlets(cache: &[u8], raw: &[u8]) -> Vec<usize> {
+    (0..cache.len())
+        .step_by(4)
+        .filter(|&pos| raw[pos..(pos + 4)] != cache[pos..(pos + 4)])
+        .collect()
+}
+
 impl<U> Default for TcKonnektSegment<U>
 where
     U: TcKonnektSegmentData,
@@ -76,14 +105,17 @@ where
     ) -> Result<(), Error> {
         assert_eq!(segment.raw.len(), TcKonnektSegment::<T>::SIZE);
 
+        let mut raw = vec![0; TcKonnektSegment::<T>::SIZE];
         GeneralProtocol::read(
             req,
             node,
             BASE_OFFSET + TcKonnektSegment::<T>::OFFSET,
-            &mut segment.raw,
+            &mut raw,
             timeout_ms,
         )
-        .map(|_| segment.data.parse(&segment.raw))
+        .map(|_| {
+            segment.refresh(&raw);
+        })
     }
 
     fn write_segment(
@@ -97,21 +129,17 @@ where
         let mut raw = segment.raw.clone();
         segment.data.build(&mut raw);
 
-        (0..TcKonnektSegment::<T>::SIZE)
-            .step_by(4)
+        changed_quadlets(&segment.raw, &raw)
+            .into_iter()
             .try_for_each(|pos| {
-                if raw[pos..(pos + 4)] != segment.raw[pos..(pos + 4)] {
-                    GeneralProtocol::write(
-                        req,
-                        node,
-                        BASE_OFFSET + TcKonnektSegment::<T>::OFFSET + pos,
-                        &mut raw[pos..(pos + 4)],
-                        timeout_ms,
-                    )
-                    .map(|_| segment.raw[pos..(pos + 4)].copy_from_slice(&raw[pos..(pos + 4)]))
-                } else {
-                    Ok(())
-                }
+                GeneralProtocol::write(
+                    req,
+                    node,
+                    BASE_OFFSET + TcKonnektSegment::<T>::OFFSET + pos,
+                    &mut raw[pos..(pos + 4)],
+                    timeout_ms,
+                )
+                .map(|_| segment.raw[pos..(pos + 4)].copy_from_slice(&raw[pos..(pos + 4)]))
             })
     }
 }
@@ -124,3 +152,56 @@ pub trait TcKonnektNotifiedSegmentSpec {
         msg & Self::NOTIFY_FLAG > 0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default, Debug)]
+    struct TestData(u32, u32);
+
+    impl TcKonnektSegmentData for TestData {
+        fn build(&self, raw: &mut [u8]) {
+            raw[..4].copy_from_slice(&self.0.to_be_bytes());
+            raw[4..8].copy_from_slice(&self.1.to_be_bytes());
+        }
+
+        fn parse(&mut self, raw: &[u8]) {
+            let mut quadlet = [0; 4];
+            quadlet.copy_from_slice(&raw[..4]);
+            self.0 = u32::from_be_bytes(quadlet);
+            quadlet.copy_from_slice(&raw[4..8]);
+            self.1 = u32::from_be_bytes(quadlet);
+        }
+    }
+
+    impl TcKonnektSegmentSpec for TcKonnektSegment<TestData> {
+        const OFFSET: usize = 0;
+        const SIZE: usize = 8;
+    }
+
+    #[test]
+    fn test_segment_refresh_after_reset() {
+        let mut segment = TcKonnektSegment::<TestData>::default();
+        let cache = [0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x05];
+        assert!(segment.refresh(&cache));
+        assert!(!segment.refresh(&cache));
+
+        // The unit resets the first field by bus reset.
+        let device = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05];
+
+        // The write against stale cache is skipped even if the field differs in the unit.
+        let mut raw = segment.raw.clone();
+        segment.data.build(&mut raw);
+        assert!(changed_quadlets(&segment.raw, &raw).is_empty());
+
+        assert!(segment.refresh(&device));
+        assert_eq!(segment.data.0, 0);
+        assert_eq!(segment.data.1, 5);
+
+        segment.data.0 = 2;
+        let mut raw = segment.raw.clone();
+        segment.data.build(&mut raw);
+        assert_eq!(changed_quadlets(&segment.raw, &raw), vec![0]);
+    }
+}
@@ -171,6 +171,8 @@ pub struct LimitterState {
 }
 
 /// The structure to represent state entry of channel strip effect.
+///
+/// The solo of strip for auditioning is not identified in the segment yet.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
 pub struct ChStripState {
     pub src_type: ChStripSrcType,
@@ -544,6 +544,14 @@ impl From<ShellPhysOutSrc> for u32 {
     }
 }
 
+/// The trait to represent specification for source of physical output pairs. Each pair is
+/// connected either to the mixer or directly to the stream.
+pub trait ShellPhysOutSrcSpec {
+    /// The list of index for physical output pairs whose source is selectable, in the order of
+    /// fields in the segment.
+    const PHYS_OUT_PAIRS: &'static [usize];
+}
+
 /// The enumeration to represent format of optical input interface.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ShellOptInputIfaceFormat {
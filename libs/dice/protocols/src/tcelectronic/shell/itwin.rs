@@ -454,3 +454,38 @@ impl TcKonnektSegmentSpec for TcKonnektSegment<ItwinChStripMeters> {
     const OFFSET: usize = 0x10e0;
     const SIZE: usize = ChStripMeter::SIZE * SHELL_CH_STRIP_COUNT + 4;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_output_pair_src() {
+        let mut raw = [0; 168];
+        raw[28..32].copy_from_slice(&1u32.to_be_bytes());
+        raw[32..36].copy_from_slice(&1u32.to_be_bytes());
+        raw[120..124].copy_from_slice(&15u32.to_be_bytes());
+        raw[124..128].copy_from_slice(&8u32.to_be_bytes());
+        raw[144..148].copy_from_slice(&3u32.to_be_bytes());
+
+        let mut cfg = ItwinConfig::default();
+        cfg.parse(&raw);
+        assert_eq!(
+            cfg.output_pair_src,
+            [
+                ItwinOutputPairSrc::MixerSend01,
+                ItwinOutputPairSrc::Stream01,
+                ItwinOutputPairSrc::MixerOut01,
+                ItwinOutputPairSrc::MixerOut01,
+                ItwinOutputPairSrc::MixerOut01,
+                ItwinOutputPairSrc::MixerOut01,
+                ItwinOutputPairSrc::Spdif01,
+            ]
+        );
+        assert_eq!(cfg.standalone_src, ShellStandaloneClkSrc::Coaxial);
+
+        let mut built = [0; 168];
+        cfg.build(&mut built);
+        assert_eq!(&built[..], &raw[..]);
+    }
+}
@@ -95,11 +95,15 @@ impl TcKonnektNotifiedSegmentSpec for TcKonnektSegment<K24dKnob> {
 pub struct K24dConfig {
     pub opt: ShellOptIfaceConfig,
     pub coax_out_src: ShellCoaxOutPairSrc,
-    pub out_23_src: ShellPhysOutSrc,
+    pub phys_out_src: [ShellPhysOutSrc; 1],
     pub standalone_src: ShellStandaloneClkSrc,
     pub standalone_rate: TcKonnektStandaloneClkRate,
 }
 
+impl ShellPhysOutSrcSpec for K24dConfig {
+    const PHYS_OUT_PAIRS: &'static [usize] = &[1];
+}
+
 impl ShellStandaloneClkSpec for K24dConfig {
     const STANDALONE_CLOCK_SOURCES: &'static [ShellStandaloneClkSrc] = &[
         ShellStandaloneClkSrc::Optical,
@@ -112,7 +116,7 @@ impl TcKonnektSegmentData for K24dConfig {
     fn build(&self, raw: &mut [u8]) {
         self.opt.build(&mut raw[..12]);
         self.coax_out_src.0.build_quadlet(&mut raw[12..16]);
-        self.out_23_src.build_quadlet(&mut raw[16..20]);
+        self.phys_out_src.build_quadlet_block(&mut raw[16..20]);
         self.standalone_src.build_quadlet(&mut raw[20..24]);
         self.standalone_rate.build_quadlet(&mut raw[24..28]);
     }
@@ -120,7 +124,7 @@ impl TcKonnektSegmentData for K24dConfig {
     fn parse(&mut self, raw: &[u8]) {
         self.opt.parse(&raw[..12]);
         self.coax_out_src.0.parse_quadlet(&raw[12..16]);
-        self.out_23_src.parse_quadlet(&raw[16..20]);
+        self.phys_out_src.parse_quadlet_block(&raw[16..20]);
         self.standalone_src.parse_quadlet(&raw[20..24]);
         self.standalone_rate.parse_quadlet(&raw[24..28]);
     }
@@ -358,3 +362,27 @@ impl TcKonnektSegmentSpec for TcKonnektSegment<K24dChStripMeters> {
     const OFFSET: usize = 0x10d0;
     const SIZE: usize = ChStripMeter::SIZE * SHELL_CH_STRIP_COUNT + 4;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_phys_out_src() {
+        assert_eq!(K24dConfig::PHYS_OUT_PAIRS.len(), K24dConfig::default().phys_out_src.len());
+
+        let mut raw = [0; 76];
+        raw[16..20].copy_from_slice(&2u32.to_be_bytes());
+        raw[20..24].copy_from_slice(&1u32.to_be_bytes());
+        raw[24..28].copy_from_slice(&1u32.to_be_bytes());
+
+        let mut cfg = K24dConfig::default();
+        cfg.parse(&raw);
+        assert_eq!(cfg.phys_out_src, [ShellPhysOutSrc::MixerOut01]);
+        assert_eq!(cfg.standalone_src, ShellStandaloneClkSrc::Coaxial);
+
+        let mut built = [0; 76];
+        cfg.build(&mut built);
+        assert_eq!(built, raw);
+    }
+}
@@ -99,8 +99,7 @@ impl TcKonnektNotifiedSegmentSpec for TcKonnektSegment<KliveKnob> {
 pub struct KliveConfig {
     pub opt: ShellOptIfaceConfig,
     pub coax_out_src: ShellCoaxOutPairSrc,
-    pub out_01_src: ShellPhysOutSrc,
-    pub out_23_src: ShellPhysOutSrc,
+    pub phys_out_src: [ShellPhysOutSrc; 2],
     pub mixer_stream_src_pair: ShellMixerStreamSrcPair,
     pub standalone_src: ShellStandaloneClkSrc,
     pub standalone_rate: TcKonnektStandaloneClkRate,
@@ -111,6 +110,10 @@ impl ShellMixerStreamSrcPairSpec for KliveConfig {
     const MAXIMUM_STREAM_SRC_PAIR_COUNT: usize = 6;
 }
 
+impl ShellPhysOutSrcSpec for KliveConfig {
+    const PHYS_OUT_PAIRS: &'static [usize] = &[0, 1];
+}
+
 impl ShellStandaloneClkSpec for KliveConfig {
     const STANDALONE_CLOCK_SOURCES: &'static [ShellStandaloneClkSrc] = &[
         ShellStandaloneClkSrc::Optical,
@@ -123,8 +126,7 @@ impl TcKonnektSegmentData for KliveConfig {
     fn build(&self, raw: &mut [u8]) {
         self.opt.build(&mut raw[..12]);
         self.coax_out_src.0.build_quadlet(&mut raw[12..16]);
-        self.out_01_src.build_quadlet(&mut raw[16..20]);
-        self.out_23_src.build_quadlet(&mut raw[20..24]);
+        self.phys_out_src.build_quadlet_block(&mut raw[16..24]);
         self.mixer_stream_src_pair.build_quadlet(&mut raw[24..28]);
         self.standalone_src.build_quadlet(&mut raw[28..32]);
         self.standalone_rate.build_quadlet(&mut raw[32..36]);
@@ -134,8 +136,7 @@ impl TcKonnektSegmentData for KliveConfig {
     fn parse(&mut self, raw: &[u8]) {
         self.opt.parse(&raw[..12]);
         self.coax_out_src.0.parse_quadlet(&raw[12..16]);
-        self.out_01_src.parse_quadlet(&raw[16..20]);
-        self.out_23_src.parse_quadlet(&raw[20..24]);
+        self.phys_out_src.parse_quadlet_block(&raw[16..24]);
         self.mixer_stream_src_pair.parse_quadlet(&raw[24..28]);
         self.standalone_src.parse_quadlet(&raw[28..32]);
         self.standalone_rate.parse_quadlet(&raw[32..36]);
@@ -502,3 +503,31 @@ impl From<OutputImpedance> for u32 {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_phys_out_src() {
+        assert_eq!(KliveConfig::PHYS_OUT_PAIRS.len(), KliveConfig::default().phys_out_src.len());
+
+        let mut raw = [0; 132];
+        raw[16..20].copy_from_slice(&3u32.to_be_bytes());
+        raw[20..24].copy_from_slice(&1u32.to_be_bytes());
+        raw[24..28].copy_from_slice(&2u32.to_be_bytes());
+        raw[32..36].copy_from_slice(&1u32.to_be_bytes());
+
+        let mut cfg = KliveConfig::default();
+        cfg.parse(&raw);
+        assert_eq!(
+            cfg.phys_out_src,
+            [ShellPhysOutSrc::MixerSend01, ShellPhysOutSrc::Analog01]
+        );
+        assert_eq!(cfg.mixer_stream_src_pair, ShellMixerStreamSrcPair::Stream45);
+
+        let mut built = [0; 132];
+        cfg.build(&mut built);
+        assert_eq!(&built[..], &raw[..]);
+    }
+}
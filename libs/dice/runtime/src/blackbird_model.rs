@@ -22,7 +22,7 @@ pub struct BlackbirdModel {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: BlackbirdTcd22xxCtl,
+    pub(crate) tcd22xx_ctl: BlackbirdTcd22xxCtl,
 }
 
 const TIMEOUT_MS: u32 = 20;
@@ -142,7 +142,7 @@ impl MeasureModel<hinawa::SndDice> for BlackbirdModel {
 }
 
 #[derive(Default)]
-struct BlackbirdTcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct BlackbirdTcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<BlackbirdProtocol> for BlackbirdTcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -22,7 +22,7 @@ pub struct ExtensionModel{
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: ExtensionTcd22xxCtl,
+    pub(crate) tcd22xx_ctl: ExtensionTcd22xxCtl,
 }
 
 const TIMEOUT_MS: u32 = 20;
@@ -178,7 +178,7 @@ impl Tcd22xxSpecOperation for  ExtensionProtocol {
 }
 
 #[derive(Default)]
-struct ExtensionTcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct ExtensionTcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<ExtensionProtocol> for ExtensionTcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -25,7 +25,7 @@ pub struct LiquidS56Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: LiquidS56Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: LiquidS56Tcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
     specific_ctl: SpecificCtl,
 }
@@ -180,7 +180,7 @@ impl MeasureModel<SndDice> for LiquidS56Model {
 }
 
 #[derive(Default)]
-struct LiquidS56Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct LiquidS56Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<LiquidS56Protocol> for LiquidS56Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
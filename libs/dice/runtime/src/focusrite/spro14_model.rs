@@ -18,7 +18,7 @@ pub struct SPro14Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: SPro14Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: SPro14Tcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
     input_ctl: InputCtl,
 }
@@ -218,7 +218,7 @@ impl MeasureModel<SndDice> for SPro14Model {
 }
 
 #[derive(Default)]
-struct SPro14Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct SPro14Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<SPro14Protocol> for SPro14Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -18,7 +18,7 @@ pub struct SPro24Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: SPro24Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: SPro24Tcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
     input_ctl: InputCtl,
 }
@@ -234,7 +234,7 @@ impl MeasureModel<SndDice> for SPro24Model {
 }
 
 #[derive(Default)]
-struct SPro24Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct SPro24Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<SPro24Protocol> for SPro24Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
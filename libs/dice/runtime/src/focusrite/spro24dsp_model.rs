@@ -18,7 +18,7 @@ pub struct SPro24DspModel {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: SPro24DspTcd22xxCtl,
+    pub(crate) tcd22xx_ctl: SPro24DspTcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
     input_ctl: InputCtl,
     effect_ctl: EffectCtl,
@@ -249,7 +249,7 @@ impl MeasureModel<SndDice> for SPro24DspModel {
 }
 
 #[derive(Default)]
-struct SPro24DspTcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct SPro24DspTcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<SPro24DspProtocol> for SPro24DspTcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
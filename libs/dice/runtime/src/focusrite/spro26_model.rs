@@ -24,7 +24,7 @@ pub struct SPro26Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: SPro26Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: SPro26Tcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
 }
 
@@ -171,7 +171,7 @@ impl MeasureModel<SndDice> for SPro26Model {
 }
 
 #[derive(Default)]
-struct SPro26Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct SPro26Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<SPro26Protocol> for SPro26Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -24,7 +24,7 @@ pub struct SPro40Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: SPro40Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: SPro40Tcd22xxCtl,
     out_grp_ctl: OutGroupCtl,
     specific_ctl: SpecificCtl,
 }
@@ -179,7 +179,7 @@ impl MeasureModel<SndDice> for SPro40Model {
 }
 
 #[derive(Default)]
-struct SPro40Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct SPro40Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<SPro40Protocol> for SPro40Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
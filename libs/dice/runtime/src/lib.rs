@@ -31,11 +31,17 @@ use alsactl::{CardExt, CardExtManual, ElemValueExtManual};
 use core::RuntimeOperation;
 use core::dispatcher;
 use core::card_cntr;
+use core::version::*;
+use core::event_queue::*;
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 use model::DiceModel;
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem(alsactl::ElemId, alsactl::ElemEventMask),
@@ -43,29 +49,46 @@ enum Event {
     Timer,
 }
 
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&alsactl::ElemId> {
+        match self {
+            Event::Elem(elem_id, events) if *events == alsactl::ElemEventMask::VALUE => {
+                Some(elem_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
+}
+
 pub struct DiceRuntime{
     unit: SndDice,
     model: DiceModel,
     card_cntr: card_cntr::CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<dispatcher::Dispatcher>,
     timer: Option<dispatcher::Dispatcher>,
 }
 
-impl RuntimeOperation<u32> for DiceRuntime {
-    fn new(card_id: u32) -> Result<Self, Error> {
+// The second argument is to keep the state of device at loading.
+impl RuntimeOperation<(u32, bool)> for DiceRuntime {
+    fn new((card_id, non_destructive): (u32, bool)) -> Result<Self, Error> {
         let unit = SndDice::new();
         let path = format!("/dev/snd/hwC{}D0", card_id);
         unit.open(&path)?;
 
-        let model = DiceModel::new(&unit)?;
+        let model = DiceModel::new(&unit, non_destructive)?;
 
         let card_cntr = card_cntr::CardCntr::new();
         card_cntr.card.open(card_id, 0)?;
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         let dispatchers = Vec::new();
 
@@ -86,6 +109,12 @@ impl RuntimeOperation<u32> for DiceRuntime {
             let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         }
 
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            dice_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -94,9 +123,11 @@ impl RuntimeOperation<u32> for DiceRuntime {
             if let Ok(ev) = self.rx.recv() {
                 match ev {
                     Event::Shutdown => break,
+                    Event::Reload => self.reload(),
                     Event::Disconnected => break,
                     Event::BusReset(generation) => {
-                        println!("IEEE 1394 bus is updated: {}", generation);
+                        log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
+                        let _ = self.model.dispatch_bus_reset(&mut self.unit, &mut self.card_cntr);
                     }
                     Event::Elem(elem_id, events) => {
                         if elem_id.get_name() != Self::TIMER_NAME {
@@ -132,15 +163,28 @@ impl RuntimeOperation<u32> for DiceRuntime {
 impl Drop for DiceRuntime {
     fn drop(&mut self) {
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.timer.iter_mut()
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timers and the dispatcher for signal handler are
+        // finished at first, then the dispatcher for node is finished at last.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.dispatchers.drain(..).rev());
+        dispatcher::join_dispatchers(dispatchers);
+    }
+}
+
+impl ConfigReloadOperation for DiceRuntime {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
     }
 }
 
@@ -191,6 +235,11 @@ impl DiceRuntime {
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -206,7 +255,7 @@ impl DiceRuntime {
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = dispatcher::Dispatcher::run(Self::TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(Self::TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(Self::TIMER_INTERVAL), move || {
             let _ = tx.send(Event::Timer);
             source::Continue(true)
         });
@@ -217,9 +266,6 @@ impl DiceRuntime {
     }
 
     fn stop_interval_timer(&mut self) {
-        if let Some(dispatcher) = &self.timer {
-            drop(dispatcher);
-            self.timer = None;
-        }
+        dispatcher::join_dispatchers(self.timer.take());
     }
 }
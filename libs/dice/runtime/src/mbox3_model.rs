@@ -23,7 +23,7 @@ pub struct Mbox3Model{
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: Mbox3Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: Mbox3Tcd22xxCtl,
     standalone_ctl: StandaloneCtl,
     hw_ctl: HwCtl,
     reverb_ctl: ReverbCtl,
@@ -178,7 +178,7 @@ impl MeasureModel<SndDice> for Mbox3Model {
 }
 
 #[derive(Default)]
-struct Mbox3Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct Mbox3Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<Mbox3Protocol> for Mbox3Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -9,10 +9,12 @@ use core::card_cntr::*;
 
 use ieee1212_config_rom::*;
 use dice_protocols::tcat::{*, config_rom::*, extension::*};
+use dice_protocols::tcelectronic::TC_KONNEKT_SEGMENT_REFRESH_MASK;
 
 use std::convert::TryFrom;
 
 use super::minimal_model::MinimalModel;
+use super::tcd22xx_ctl::Tcd22xxCtlOperation;
 use super::tcelectronic::k24d_model::*;
 use super::tcelectronic::k8_model::*;
 use super::tcelectronic::studiok48_model::*;
@@ -63,12 +65,13 @@ enum Model {
 
 pub struct DiceModel{
     model: Model,
+    non_destructive: bool,
     notified_elem_list: Vec<alsactl::ElemId>,
     pub measured_elem_list: Vec<alsactl::ElemId>,
 }
 
 impl DiceModel {
-    pub fn new(unit: &SndDice) -> Result<DiceModel, Error> {
+    pub fn new(unit: &SndDice, non_destructive: bool) -> Result<DiceModel, Error> {
         let node = unit.get_node();
         let raw = node.get_config_rom()?;
         let config_rom = ConfigRom::try_from(&raw[..])
@@ -116,7 +119,7 @@ impl DiceModel {
         let notified_elem_list = Vec::new();
         let measured_elem_list = Vec::new();
 
-        Ok(DiceModel{model, notified_elem_list, measured_elem_list})
+        Ok(DiceModel{model, non_destructive, notified_elem_list, measured_elem_list})
     }
 
     pub fn load(&mut self, unit: &mut SndDice, card_cntr: &mut CardCntr)
@@ -138,6 +141,26 @@ impl DiceModel {
             }
         }
 
+        // MEMO: The models with TCD22xx write router entries at loading to add entries for fixed
+        // sources, unless in non-destructive mode.
+        let non_destructive = self.non_destructive;
+        match &mut self.model {
+            Model::Extension(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::MaudioPfire2626(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::MaudioPfire610(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::AvidMbox3(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::LoudBlackbird(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteSPro40(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteLiquidS56(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteSPro24(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteSPro24Dsp(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteSPro14(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::FocusriteSPro26(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::PresonusFStudioProject(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            Model::PresonusFStudioMobile(m) => m.tcd22xx_ctl.set_non_destructive(non_destructive),
+            _ => (),
+        }
+
         match &mut self.model {
             Model::Minimal(m) => m.load(unit, card_cntr),
             Model::TcK24d(m) => m.load(unit, card_cntr),
@@ -280,6 +303,23 @@ impl DiceModel {
         }
     }
 
+    // The models of TC Electronic can reset a part of segments by bus reset. The elements are
+    // notified to the other processes just for the changed values.
+    pub fn dispatch_bus_reset(&mut self, unit: &mut SndDice, card_cntr: &mut CardCntr)
+        -> Result<(), Error>
+    {
+        let msg = TC_KONNEKT_SEGMENT_REFRESH_MASK;
+        match &mut self.model {
+            Model::TcK24d(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            Model::TcK8(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            Model::TcStudiok48(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            Model::TcKlive(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            Model::TcDesktopk6(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            Model::TcItwin(m) => card_cntr.dispatch_notification(unit, &msg, &self.notified_elem_list, m),
+            _ => Ok(()),
+        }
+    }
+
     pub fn measure_elems(&mut self, unit: &mut hinawa::SndDice, card_cntr: &mut CardCntr)
         -> Result<(), Error>
     {
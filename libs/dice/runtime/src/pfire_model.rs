@@ -25,7 +25,7 @@ pub struct Pfire2626Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: Pfire2626Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: Pfire2626Tcd22xxCtl,
     specific_ctl: Pfire2626SpecificCtl,
 }
 
@@ -159,7 +159,7 @@ impl MeasureModel<SndDice> for Pfire2626Model {
 }
 
 #[derive(Default)]
-struct Pfire2626Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct Pfire2626Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<Pfire2626Protocol> for Pfire2626Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -177,7 +177,7 @@ pub struct Pfire610Model {
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: Pfire610Tcd22xxCtl,
+    pub(crate) tcd22xx_ctl: Pfire610Tcd22xxCtl,
     specific_ctl: Pfire610SpecificCtl,
 }
 
@@ -311,7 +311,7 @@ impl MeasureModel<SndDice> for Pfire610Model {
 }
 
 #[derive(Default)]
-struct Pfire610Tcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct Pfire610Tcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<Pfire610Protocol> for Pfire610Tcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -22,7 +22,7 @@ pub struct FStudioMobileModel{
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: FStudioMobileTcd22xxCtl,
+    pub(crate) tcd22xx_ctl: FStudioMobileTcd22xxCtl,
 }
 
 const TIMEOUT_MS: u32 = 20;
@@ -142,7 +142,7 @@ impl MeasureModel<SndDice> for FStudioMobileModel {
 }
 
 #[derive(Default)]
-struct FStudioMobileTcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct FStudioMobileTcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<FStudioMobileProtocol> for FStudioMobileTcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -22,7 +22,7 @@ pub struct FStudioProjectModel{
     sections: GeneralSections,
     extension_sections: ExtensionSections,
     ctl: CommonCtl,
-    tcd22xx_ctl: FStudioProjectTcd22xxCtl,
+    pub(crate) tcd22xx_ctl: FStudioProjectTcd22xxCtl,
 }
 
 const TIMEOUT_MS: u32 = 20;
@@ -142,7 +142,7 @@ impl MeasureModel<SndDice> for FStudioProjectModel {
 }
 
 #[derive(Default)]
-struct FStudioProjectTcd22xxCtl(Tcd22xxCtl);
+pub(crate) struct FStudioProjectTcd22xxCtl(Tcd22xxCtl);
 
 impl Tcd22xxCtlOperation<FStudioProjectProtocol> for FStudioProjectTcd22xxCtl {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl {
@@ -26,6 +26,7 @@ pub struct Tcd22xxCtl {
     router_ctl: RouterCtl,
     mixer_ctl: MixerCtl,
     standalone_ctl: StandaloneCtl,
+    non_destructive: bool,
 }
 
 pub trait Tcd22xxCtlOperation<T>
@@ -34,6 +35,12 @@ where
 {
     fn tcd22xx_ctl(&self) -> &Tcd22xxCtl;
     fn tcd22xx_ctl_mut(&mut self) -> &mut Tcd22xxCtl;
+
+    /// In non-destructive mode, the router entries are cached as is, thus the routing configured
+    /// in advance is kept until any element for routing is changed.
+    fn set_non_destructive(&mut self, enable: bool) {
+        self.tcd22xx_ctl_mut().non_destructive = enable;
+    }
 }
 
 #[derive(Default, Debug)]
@@ -1029,15 +1036,30 @@ where
         )?;
         let rate_mode = RateMode::from(config.rate);
 
+        // MEMO: In usual mode, the entries for fixed sources are added to router entries and
+        // written to the device when missing, even at loading. In non-destructive mode, they are
+        // cached as is.
         let ctls = self.tcd22xx_ctl_mut();
-        T::cache(
-            &mut node,
-            req,
-            extension_sections,
-            &ctls.caps,
-            &mut ctls.state,
-            rate_mode,
-        timeout_ms)
+        if ctls.non_destructive {
+            T::cache_as_is(
+                &mut node,
+                req,
+                extension_sections,
+                &ctls.caps,
+                &mut ctls.state,
+                rate_mode,
+                timeout_ms
+            )
+        } else {
+            T::cache(
+                &mut node,
+                req,
+                extension_sections,
+                &ctls.caps,
+                &mut ctls.state,
+                rate_mode,
+            timeout_ms)
+        }
     }
 
     fn read(
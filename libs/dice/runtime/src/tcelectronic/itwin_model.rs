@@ -478,14 +478,9 @@ impl ConfigCtl {
             .map(|s| itwin_phys_out_src_to_string(s))
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OUT_SRC_NAME, 0);
-        let _ = card_cntr.add_enum_elems(
-            &elem_id,
-            1,
-            ITWIN_PHYS_OUT_PAIR_COUNT,
-            &labels,
-            None,
-            true,
-        )?;
+        card_cntr
+            .add_enum_elems(&elem_id, 1, ITWIN_PHYS_OUT_PAIR_COUNT, &labels, None, true)
+            .map(|mut elem_id_list| self.1.append(&mut elem_id_list))?;
 
         Ok(())
     }
@@ -496,21 +491,25 @@ impl ConfigCtl {
         } else if self.read_standalone(elem_id, elem_value)? {
             Ok(true)
         } else {
-            match elem_id.get_name().as_str() {
-                OUT_SRC_NAME => ElemValueAccessor::<u32>::set_vals(
-                    elem_value,
-                    ITWIN_PHYS_OUT_PAIR_COUNT,
-                    |idx| {
-                        let pos = Self::OUT_SRCS
-                            .iter()
-                            .position(|s| self.0.data.output_pair_src[idx].eq(s))
-                            .unwrap();
-                        Ok(pos as u32)
-                    },
-                )
-                .map(|_| true),
-                _ => Ok(false),
-            }
+            self.read_out_src(elem_id, elem_value)
+        }
+    }
+
+    fn read_out_src(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            OUT_SRC_NAME => ElemValueAccessor::<u32>::set_vals(
+                elem_value,
+                ITWIN_PHYS_OUT_PAIR_COUNT,
+                |idx| {
+                    let pos = Self::OUT_SRCS
+                        .iter()
+                        .position(|s| self.0.data.output_pair_src[idx].eq(s))
+                        .unwrap();
+                    Ok(pos as u32)
+                },
+            )
+            .map(|_| true),
+            _ => Ok(false),
         }
     }
 
@@ -575,7 +574,7 @@ impl ConfigCtl {
         if self.read_mixer_stream_src(elem_id, elem_value)? {
             Ok(true)
         } else {
-            Ok(false)
+            self.read_out_src(elem_id, elem_value)
         }
     }
 }
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2020 Takashi Sakamoto
-use glib::{Error, FileError};
+use glib::Error;
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue};
 
@@ -427,6 +427,20 @@ impl ShellCoaxIfaceCtlOperation<K24dConfig, K24dProtocol> for ConfigCtl {
     }
 }
 
+impl ShellPhysOutCtlOperation<K24dConfig, K24dProtocol> for ConfigCtl {
+    fn segment_mut(&mut self) -> &mut K24dConfigSegment {
+        &mut self.0
+    }
+
+    fn phys_out_src(&self) -> &[ShellPhysOutSrc] {
+        &self.0.data.phys_out_src
+    }
+
+    fn phys_out_src_mut(&mut self) -> &mut [ShellPhysOutSrc] {
+        &mut self.0.data.phys_out_src
+    }
+}
+
 impl ShellOptIfaceCtl<K24dConfig, K24dProtocol> for ConfigCtl {
     fn segment_mut(&mut self) -> &mut K24dConfigSegment {
         &mut self.0
@@ -465,8 +479,6 @@ impl ShellStandaloneCtlOperation<K24dConfig, K24dProtocol> for ConfigCtl {
     }
 }
 
-const OUT_23_SRC_NAME: &str = "output-3/4-source";
-
 impl ConfigCtl {
     fn load(
         &mut self,
@@ -480,13 +492,8 @@ impl ConfigCtl {
         self.load_coax_out_src(card_cntr)?;
         self.load_opt_iface_config(card_cntr)?;
         self.load_standalone(card_cntr)?;
-
-        let labels: Vec<&str> = PHYS_OUT_SRCS
-            .iter()
-            .map(|s| phys_out_src_to_str(s))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OUT_23_SRC_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
+        self.load_phys_out_src(card_cntr)
+            .map(|mut elem_id_list| self.1.append(&mut elem_id_list))?;
 
         Ok(())
     }
@@ -498,18 +505,10 @@ impl ConfigCtl {
             Ok(true)
         } else if self.read_standalone(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_phys_out_src(elem_id, elem_value)? {
+            Ok(true)
         } else {
-            match elem_id.get_name().as_str() {
-                OUT_23_SRC_NAME => ElemValueAccessor::<u32>::set_val(elem_value, || {
-                    let pos = PHYS_OUT_SRCS
-                        .iter()
-                        .position(|s| self.0.data.out_23_src.eq(s))
-                        .unwrap();
-                    Ok(pos as u32)
-                })
-                .map(|_| true),
-                _ => Ok(false),
-            }
+            Ok(false)
         }
     }
 
@@ -528,24 +527,10 @@ impl ConfigCtl {
             Ok(true)
         } else if self.write_standalone(unit, req, elem_id, new, timeout_ms)? {
             Ok(true)
+        } else if self.write_phys_out_src(unit, req, elem_id, new, timeout_ms)? {
+            Ok(true)
         } else {
-            match elem_id.get_name().as_str() {
-                OUT_23_SRC_NAME => {
-                    ElemValueAccessor::<u32>::get_val(new, |val| {
-                        PHYS_OUT_SRCS
-                            .iter()
-                            .nth(val as usize)
-                            .ok_or_else(|| {
-                                let msg = format!("Invalid index of output source: {}", val);
-                                Error::new(FileError::Inval, &msg)
-                            })
-                            .map(|&s| self.0.data.out_23_src = s)
-                    })?;
-                    K24dProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
-                        .map(|_| true)
-                }
-                _ => Ok(false),
-            }
+            Ok(false)
         }
     }
 
@@ -572,6 +557,8 @@ impl ConfigCtl {
             Ok(true)
         } else if self.read_opt_iface_config(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_phys_out_src(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -8,6 +8,7 @@ use hinawa::FwReq;
 use hinawa::{SndDice, SndUnitExt};
 
 use core::card_cntr::*;
+use core::ctl_group::*;
 use core::elem_value_accessor::*;
 
 use dice_protocols::tcat::{global_section::*, *};
@@ -30,63 +31,26 @@ use crate::common_ctl::*;
 #[derive(Default)]
 pub struct KliveModel {
     req: FwReq,
-    sections: GeneralSections,
-    ctl: CommonCtl,
-    knob_ctl: KnobCtl,
-    config_ctl: ConfigCtl,
-    mixer_ctl: MixerCtl,
-    hw_state_ctl: HwStateCtl,
-    reverb_ctl: ReverbCtl,
-    ch_strip_ctl: ChStripCtl,
+    groups: CtlGroupRegistry<SndDice, FwReq, u32>,
 }
 
 const TIMEOUT_MS: u32 = 20;
 
 impl CtlModel<SndDice> for KliveModel {
     fn load(&mut self, unit: &mut SndDice, card_cntr: &mut CardCntr) -> Result<(), Error> {
-        let mut node = unit.get_node();
-
-        self.sections = GeneralProtocol::read_general_sections(
-            &mut self.req,
-            &mut node,
-            TIMEOUT_MS
-        )?;
-        let caps = GlobalSectionProtocol::read_clock_caps(
-            &mut self.req,
-            &mut node,
-            &self.sections,
-            TIMEOUT_MS
-        )?;
-        let src_labels = GlobalSectionProtocol::read_clock_source_labels(
-            &mut self.req,
-            &mut node,
-            &self.sections,
-            TIMEOUT_MS
-        )?;
-        self.ctl.load(card_cntr, &caps, &src_labels)?;
-
-        self.knob_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
-        self.config_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
-        self.mixer_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
-        self.hw_state_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
-        self.reverb_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
-            .map(|(notified_elem_id_list, measured_elem_id_list)| {
-                self.reverb_ctl.2 = notified_elem_id_list;
-                self.reverb_ctl.3 = measured_elem_id_list;
-            })?;
-        self.ch_strip_ctl
-            .load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
-            .map(|(notified_elem_id_list, measured_elem_id_list)| {
-                self.ch_strip_ctl.2 = notified_elem_id_list;
-                self.ch_strip_ctl.3 = measured_elem_id_list;
-            })?;
+        // The groups registered at former loading are dropped so that no group is registered
+        // twice.
+        self.groups = Default::default();
+        self.groups
+            .register(GeneralCtl::default())
+            .register(KnobCtl::default())
+            .register(ConfigCtl::default())
+            .register(MixerCtl::default())
+            .register(HwStateCtl::default())
+            .register(ReverbCtl::default())
+            .register(ChStripCtl::default());
 
-        Ok(())
+        self.groups.load(unit, &mut self.req, card_cntr)
     }
 
     fn read(
@@ -95,30 +59,7 @@ impl CtlModel<SndDice> for KliveModel {
         elem_id: &ElemId,
         elem_value: &mut ElemValue,
     ) -> Result<bool, Error> {
-        if self.ctl.read(
-            unit,
-            &mut self.req,
-            &self.sections,
-            elem_id,
-            elem_value,
-            TIMEOUT_MS,
-        )? {
-            Ok(true)
-        } else if self.knob_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.config_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.mixer_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.hw_state_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.reverb_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.ch_strip_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.groups.read(unit, &mut self.req, elem_id, elem_value)
     }
 
     fn write(
@@ -128,79 +69,17 @@ impl CtlModel<SndDice> for KliveModel {
         old: &ElemValue,
         new: &ElemValue,
     ) -> Result<bool, Error> {
-        if self.ctl.write(
-            unit,
-            &mut self.req,
-            &self.sections,
-            elem_id,
-            old,
-            new,
-            TIMEOUT_MS,
-        )? {
-            Ok(true)
-        } else if self
-            .knob_ctl
-            .write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else if self
-            .config_ctl
-            .write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else if self
-            .mixer_ctl
-            .write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else if self
-            .reverb_ctl
-            .write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else if self
-            .ch_strip_ctl
-            .write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else if self
-            .hw_state_ctl
-            .write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)?
-        {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.groups.write(unit, &mut self.req, elem_id, old, new)
     }
 }
 
 impl NotifyModel<SndDice, u32> for KliveModel {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
-        elem_id_list.extend_from_slice(&self.ctl.notified_elem_list);
-        elem_id_list.extend_from_slice(&self.knob_ctl.1);
-        elem_id_list.extend_from_slice(&self.config_ctl.1);
-        elem_id_list.extend_from_slice(&self.mixer_ctl.2);
-        elem_id_list.extend_from_slice(&self.hw_state_ctl.1);
-        elem_id_list.extend_from_slice(&self.reverb_ctl.2);
-        elem_id_list.extend_from_slice(&self.ch_strip_ctl.2);
+        self.groups.get_notified_elem_list(elem_id_list);
     }
 
     fn parse_notification(&mut self, unit: &mut SndDice, msg: &u32) -> Result<(), Error> {
-        self.ctl
-            .parse_notification(unit, &mut self.req, &self.sections, *msg, TIMEOUT_MS)?;
-        self.knob_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        self.config_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        self.mixer_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        self.hw_state_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        self.reverb_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        self.ch_strip_ctl
-            .parse_notification(unit, &mut self.req, *msg, TIMEOUT_MS)?;
-        Ok(())
+        self.groups.parse_notification(unit, &mut self.req, msg)
     }
 
     fn read_notified_elem(
@@ -209,44 +88,17 @@ impl NotifyModel<SndDice, u32> for KliveModel {
         elem_id: &ElemId,
         elem_value: &mut ElemValue,
     ) -> Result<bool, Error> {
-        if self.ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.knob_ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.config_ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.mixer_ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.hw_state_ctl.read(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.reverb_ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.ch_strip_ctl.read_notified_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.groups.read_notified_elem(elem_id, elem_value)
     }
 }
 
 impl MeasureModel<SndDice> for KliveModel {
     fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
-        elem_id_list.extend_from_slice(&self.ctl.measured_elem_list);
-        elem_id_list.extend_from_slice(&self.mixer_ctl.3);
-        elem_id_list.extend_from_slice(&self.reverb_ctl.3);
-        elem_id_list.extend_from_slice(&self.ch_strip_ctl.3);
+        self.groups.get_measure_elem_list(elem_id_list);
     }
 
     fn measure_states(&mut self, unit: &mut SndDice) -> Result<(), Error> {
-        self.ctl
-            .measure_states(unit, &mut self.req, &self.sections, TIMEOUT_MS)?;
-        self.mixer_ctl
-            .measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        self.reverb_ctl
-            .measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        self.ch_strip_ctl
-            .measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        Ok(())
+        self.groups.measure_states(unit, &mut self.req)
     }
 
     fn measure_elem(
@@ -255,17 +107,85 @@ impl MeasureModel<SndDice> for KliveModel {
         elem_id: &ElemId,
         elem_value: &mut ElemValue,
     ) -> Result<bool, Error> {
-        if self.ctl.measure_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.mixer_ctl.read_measured_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.reverb_ctl.read_measured_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else if self.ch_strip_ctl.read_measured_elem(elem_id, elem_value)? {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.groups.read_measured_elem(elem_id, elem_value)
+    }
+}
+
+#[derive(Default)]
+struct GeneralCtl(GeneralSections, CommonCtl);
+
+impl CtlGroup<SndDice, FwReq, u32> for GeneralCtl {
+    fn load(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        card_cntr: &mut CardCntr,
+    ) -> Result<(), Error> {
+        let mut node = unit.get_node();
+
+        self.0 = GeneralProtocol::read_general_sections(req, &mut node, TIMEOUT_MS)?;
+        let caps = GlobalSectionProtocol::read_clock_caps(req, &mut node, &self.0, TIMEOUT_MS)?;
+        let src_labels =
+            GlobalSectionProtocol::read_clock_source_labels(req, &mut node, &self.0, TIMEOUT_MS)?;
+        self.1.load(card_cntr, &caps, &src_labels)
+    }
+
+    fn read(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.1.read(unit, req, &self.0, elem_id, elem_value, TIMEOUT_MS)
+    }
+
+    fn write(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        old: &ElemValue,
+        new: &ElemValue,
+    ) -> Result<bool, Error> {
+        self.1.write(unit, req, &self.0, elem_id, old, new, TIMEOUT_MS)
+    }
+
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.1.notified_elem_list
+    }
+
+    fn parse_notification(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        msg: &u32,
+    ) -> Result<(), Error> {
+        self.1.parse_notification(unit, req, &self.0, *msg, TIMEOUT_MS)
+    }
+
+    fn read_notified_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.1.read_notified_elem(elem_id, elem_value)
+    }
+
+    fn measured_elem_list(&self) -> &[ElemId] {
+        &self.1.measured_elem_list
+    }
+
+    fn measure_states(&mut self, unit: &mut SndDice, req: &mut FwReq) -> Result<(), Error> {
+        self.1.measure_states(unit, req, &self.0, TIMEOUT_MS)
+    }
+
+    fn read_measured_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.1.measure_elem(elem_id, elem_value)
     }
 }
 
@@ -341,15 +261,7 @@ impl KnobCtl {
     const OUTPUT_IMPEDANCES: [OutputImpedance; 2] =
         [OutputImpedance::Unbalance, OutputImpedance::Balance];
 
-    fn load(
-        &mut self,
-        card_cntr: &mut CardCntr,
-        unit: &mut SndDice,
-        req: &mut FwReq,
-        timeout_ms: u32,
-    ) -> Result<(), Error> {
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)?;
-
+    fn load_elems<C: CardBackend>(&mut self, card_cntr: &mut CardCntr<C>) -> Result<(), Error> {
         self.load_knob_target(card_cntr)
             .map(|mut elem_id_list| self.1.append(&mut elem_id_list))?;
         self.load_knob2_target(card_cntr)
@@ -366,8 +278,26 @@ impl KnobCtl {
 
         Ok(())
     }
+}
+
+impl CtlGroup<SndDice, FwReq, u32> for KnobCtl {
+    fn load(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        card_cntr: &mut CardCntr,
+    ) -> Result<(), Error> {
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)?;
+        self.load_elems(card_cntr)
+    }
 
-    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
         if self.read_knob_target(elem_id, elem_value)? {
             Ok(true)
         } else if self.read_knob2_target(elem_id, elem_value)? {
@@ -396,13 +326,12 @@ impl KnobCtl {
         elem_id: &ElemId,
         old: &ElemValue,
         new: &ElemValue,
-        timeout_ms: u32,
     ) -> Result<bool, Error> {
-        if self.write_knob_target(unit, req, elem_id, new, timeout_ms)? {
+        if self.write_knob_target(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_knob2_target(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_knob2_target(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_prog(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_prog(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else {
             match elem_id.get_name().as_str() {
@@ -417,7 +346,7 @@ impl KnobCtl {
                             })
                             .map(|&i| self.0.data.out_impedance[idx] = i)
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 _ => Ok(false),
@@ -425,15 +354,18 @@ impl KnobCtl {
         }
     }
 
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.1
+    }
+
     fn parse_notification(
         &mut self,
         unit: &mut SndDice,
         req: &mut FwReq,
-        msg: u32,
-        timeout_ms: u32,
+        msg: &u32,
     ) -> Result<(), Error> {
-        if self.0.has_segment_change(msg) {
-            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+        if self.0.has_segment_change(*msg) {
+            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
         } else {
             Ok(())
         }
@@ -487,6 +419,20 @@ impl ShellCoaxIfaceCtlOperation<KliveConfig, KliveProtocol> for ConfigCtl {
     }
 }
 
+impl ShellPhysOutCtlOperation<KliveConfig, KliveProtocol> for ConfigCtl {
+    fn segment_mut(&mut self) -> &mut KliveConfigSegment {
+        &mut self.0
+    }
+
+    fn phys_out_src(&self) -> &[ShellPhysOutSrc] {
+        &self.0.data.phys_out_src
+    }
+
+    fn phys_out_src_mut(&mut self) -> &mut [ShellPhysOutSrc] {
+        &mut self.0.data.phys_out_src
+    }
+}
+
 impl ShellOptIfaceCtl<KliveConfig, KliveProtocol> for ConfigCtl {
     fn segment_mut(&mut self) -> &mut KliveConfigSegment {
         &mut self.0
@@ -539,39 +485,33 @@ impl MidiSendCtlOperation<KliveConfig, KliveProtocol> for ConfigCtl {
     }
 }
 
-const OUT_01_SRC_NAME: &str = "output-1/2-source";
-const OUT_23_SRC_NAME: &str = "output-3/4-source";
-
-impl ConfigCtl {
+impl CtlGroup<SndDice, FwReq, u32> for ConfigCtl {
     fn load(
         &mut self,
-        card_cntr: &mut CardCntr,
         unit: &mut SndDice,
         req: &mut FwReq,
-        timeout_ms: u32,
+        card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)?;
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)?;
 
         self.load_mixer_stream_src(card_cntr)?;
         self.load_coax_out_src(card_cntr)?;
         self.load_opt_iface_config(card_cntr)?;
         self.load_standalone(card_cntr)?;
         self.load_midi_sender(card_cntr)?;
-
-        let labels: Vec<&str> = PHYS_OUT_SRCS
-            .iter()
-            .map(|s| phys_out_src_to_str(s))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OUT_01_SRC_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OUT_23_SRC_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
+        self.load_phys_out_src(card_cntr)
+            .map(|mut elem_id_list| self.1.append(&mut elem_id_list))?;
 
         Ok(())
     }
 
-    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
         if self.read_mixer_stream_src(elem_id, elem_value)? {
             Ok(true)
         } else if self.read_coax_out_src(elem_id, elem_value)? {
@@ -582,6 +522,8 @@ impl ConfigCtl {
             Ok(true)
         } else if self.read_midi_sender(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_phys_out_src(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -594,62 +536,36 @@ impl ConfigCtl {
         elem_id: &ElemId,
         _: &ElemValue,
         new: &ElemValue,
-        timeout_ms: u32,
     ) -> Result<bool, Error> {
-        if self.write_mixer_stream_src(unit, req, elem_id, new, timeout_ms)? {
+        if self.write_mixer_stream_src(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_coax_out_src(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_coax_out_src(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_opt_iface_config(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_opt_iface_config(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_standalone(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_standalone(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_midi_sender(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_midi_sender(unit, req, elem_id, new, TIMEOUT_MS)? {
+            Ok(true)
+        } else if self.write_phys_out_src(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else {
-            match elem_id.get_name().as_str() {
-                OUT_01_SRC_NAME => {
-                    ElemValueAccessor::<u32>::get_val(new, |val| {
-                        PHYS_OUT_SRCS
-                            .iter()
-                            .nth(val as usize)
-                            .ok_or_else(|| {
-                                let msg = format!("Invalid index of output source: {}", val);
-                                Error::new(FileError::Inval, &msg)
-                            })
-                            .map(|&s| self.0.data.out_01_src = s)
-                    })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
-                        .map(|_| true)
-                }
-                OUT_23_SRC_NAME => {
-                    ElemValueAccessor::<u32>::get_val(new, |val| {
-                        PHYS_OUT_SRCS
-                            .iter()
-                            .nth(val as usize)
-                            .ok_or_else(|| {
-                                let msg = format!("Invalid index of output source: {}", val);
-                                Error::new(FileError::Inval, &msg)
-                            })
-                            .map(|&s| self.0.data.out_23_src = s)
-                    })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
-                        .map(|_| true)
-                }
-                _ => Ok(false),
-            }
+            Ok(false)
         }
     }
 
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.1
+    }
+
     fn parse_notification(
         &mut self,
         unit: &mut SndDice,
         req: &mut FwReq,
-        msg: u32,
-        timeout_ms: u32,
+        msg: &u32,
     ) -> Result<(), Error> {
-        if self.0.has_segment_change(msg) {
-            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+        if self.0.has_segment_change(*msg) {
+            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
         } else {
             Ok(())
         }
@@ -668,6 +584,8 @@ impl ConfigCtl {
             Ok(true)
         } else if self.read_midi_sender(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_phys_out_src(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -774,16 +692,17 @@ impl MixerCtl {
         ChStripMode::RIAA1964,
         ChStripMode::RIAA1987,
     ];
+}
 
+impl CtlGroup<SndDice, FwReq, u32> for MixerCtl {
     fn load(
         &mut self,
-        card_cntr: &mut CardCntr,
         unit: &mut SndDice,
         req: &mut FwReq,
-        timeout_ms: u32,
+        card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)?;
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.1, timeout_ms)?;
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)?;
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.1, TIMEOUT_MS)?;
 
         self.load_mixer(card_cntr)
             .map(|(notified_elem_id_list, measured_elem_id_list)| {
@@ -821,7 +740,13 @@ impl MixerCtl {
         Ok(())
     }
 
-    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
         if self.read_mixer(elem_id, elem_value)? {
             Ok(true)
         } else if self.read_reverb_return(elem_id, elem_value)? {
@@ -870,11 +795,10 @@ impl MixerCtl {
         elem_id: &ElemId,
         old: &ElemValue,
         new: &ElemValue,
-        timeout_ms: u32,
     ) -> Result<bool, Error> {
-        if self.write_mixer(unit, req, elem_id, old, new, timeout_ms)? {
+        if self.write_mixer(unit, req, elem_id, old, new, TIMEOUT_MS)? {
             Ok(true)
-        } else if self.write_reverb_return(unit, req, elem_id, new, timeout_ms)? {
+        } else if self.write_reverb_return(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else {
             match elem_id.get_name().as_str() {
@@ -883,7 +807,7 @@ impl MixerCtl {
                         self.0.data.enabled = val;
                         Ok(())
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 USE_CH_STRIP_AS_PLUGIN_NAME => {
@@ -891,7 +815,7 @@ impl MixerCtl {
                         self.0.data.use_ch_strip_as_plugin = val;
                         Ok(())
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 CH_STRIP_SRC_NAME => {
@@ -906,7 +830,7 @@ impl MixerCtl {
                             })
                             .map(|&s| self.0.data.ch_strip_src = s)
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 CH_STRIP_MODE_NAME => {
@@ -921,7 +845,7 @@ impl MixerCtl {
                             })
                             .map(|&m| self.0.data.ch_strip_mode = m)
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 USE_REVERB_AT_MID_RATE => {
@@ -929,7 +853,7 @@ impl MixerCtl {
                         self.0.data.use_reverb_at_mid_rate = val;
                         Ok(())
                     })?;
-                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+                    KliveProtocol::write_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
                         .map(|_| true)
                 }
                 _ => Ok(false),
@@ -937,15 +861,18 @@ impl MixerCtl {
         }
     }
 
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.2
+    }
+
     fn parse_notification(
         &mut self,
         unit: &mut SndDice,
         req: &mut FwReq,
-        msg: u32,
-        timeout_ms: u32,
+        msg: &u32,
     ) -> Result<(), Error> {
-        if self.0.has_segment_change(msg) {
-            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+        if self.0.has_segment_change(*msg) {
+            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
         } else {
             Ok(())
         }
@@ -963,13 +890,12 @@ impl MixerCtl {
         }
     }
 
-    fn measure_states(
-        &mut self,
-        unit: &mut SndDice,
-        req: &mut FwReq,
-        timeout_ms: u32,
-    ) -> Result<(), Error> {
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.1, timeout_ms)
+    fn measured_elem_list(&self) -> &[ElemId] {
+        &self.3
+    }
+
+    fn measure_states(&mut self, unit: &mut SndDice, req: &mut FwReq) -> Result<(), Error> {
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.1, TIMEOUT_MS)
     }
 
     fn read_measured_elem(
@@ -1014,15 +940,14 @@ impl ShellHwStateCtlOperation<KliveHwState, KliveProtocol> for HwStateCtl {
     }
 }
 
-impl HwStateCtl {
+impl CtlGroup<SndDice, FwReq, u32> for HwStateCtl {
     fn load(
         &mut self,
-        card_cntr: &mut CardCntr,
         unit: &mut SndDice,
         req: &mut FwReq,
-        timeout_ms: u32,
+        card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
-        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)?;
+        KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)?;
 
         self.load_hw_state(card_cntr)
             .map(|mut notified_elem_id_list| self.1.append(&mut notified_elem_id_list))?;
@@ -1030,7 +955,13 @@ impl HwStateCtl {
         Ok(())
     }
 
-    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
         if self.read_hw_state(elem_id, elem_value)? {
             Ok(true)
         } else {
@@ -1045,28 +976,38 @@ impl HwStateCtl {
         elem_id: &ElemId,
         _: &ElemValue,
         new: &ElemValue,
-        timeout_ms: u32,
     ) -> Result<bool, Error> {
-        if self.write_hw_state(unit, req, elem_id, new, timeout_ms)? {
+        if self.write_hw_state(unit, req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.1
+    }
+
     fn parse_notification(
         &mut self,
         unit: &mut SndDice,
         req: &mut FwReq,
-        msg: u32,
-        timeout_ms: u32,
+        msg: &u32,
     ) -> Result<(), Error> {
-        if self.0.has_segment_change(msg) {
-            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, timeout_ms)
+        if self.0.has_segment_change(*msg) {
+            KliveProtocol::read_segment(req, &mut unit.get_node(), &mut self.0, TIMEOUT_MS)
         } else {
             Ok(())
         }
     }
+
+    fn read_notified_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.read_hw_state(elem_id, elem_value)
+    }
 }
 
 #[derive(Default)]
@@ -1136,3 +1077,181 @@ impl ChStripCtlOperation<KliveChStripStates, KliveChStripMeters, KliveProtocol>
         &self.1.data.0
     }
 }
+
+impl CtlGroup<SndDice, FwReq, u32> for ReverbCtl {
+    fn load(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        card_cntr: &mut CardCntr,
+    ) -> Result<(), Error> {
+        ReverbCtlOperation::load(self, card_cntr, unit, req, TIMEOUT_MS).map(
+            |(notified_elem_id_list, measured_elem_id_list)| {
+                self.2 = notified_elem_id_list;
+                self.3 = measured_elem_id_list;
+            },
+        )
+    }
+
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ReverbCtlOperation::read(self, elem_id, elem_value)
+    }
+
+    fn write(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        _: &ElemValue,
+        new: &ElemValue,
+    ) -> Result<bool, Error> {
+        ReverbCtlOperation::write(self, unit, req, elem_id, new, TIMEOUT_MS)
+    }
+
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.2
+    }
+
+    fn parse_notification(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        msg: &u32,
+    ) -> Result<(), Error> {
+        ReverbCtlOperation::parse_notification(self, unit, req, *msg, TIMEOUT_MS)
+    }
+
+    fn read_notified_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ReverbCtlOperation::read_notified_elem(self, elem_id, elem_value)
+    }
+
+    fn measured_elem_list(&self) -> &[ElemId] {
+        &self.3
+    }
+
+    fn measure_states(&mut self, unit: &mut SndDice, req: &mut FwReq) -> Result<(), Error> {
+        ReverbCtlOperation::measure_states(self, unit, req, TIMEOUT_MS)
+    }
+
+    fn read_measured_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ReverbCtlOperation::read_measured_elem(self, elem_id, elem_value)
+    }
+}
+
+impl CtlGroup<SndDice, FwReq, u32> for ChStripCtl {
+    fn load(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        card_cntr: &mut CardCntr,
+    ) -> Result<(), Error> {
+        ChStripCtlOperation::load(self, card_cntr, unit, req, TIMEOUT_MS).map(
+            |(notified_elem_id_list, measured_elem_id_list)| {
+                self.2 = notified_elem_id_list;
+                self.3 = measured_elem_id_list;
+            },
+        )
+    }
+
+    fn read(
+        &mut self,
+        _: &mut SndDice,
+        _: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ChStripCtlOperation::read(self, elem_id, elem_value)
+    }
+
+    fn write(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        old: &ElemValue,
+        new: &ElemValue,
+    ) -> Result<bool, Error> {
+        ChStripCtlOperation::write(self, unit, req, elem_id, old, new, TIMEOUT_MS)
+    }
+
+    fn notified_elem_list(&self) -> &[ElemId] {
+        &self.2
+    }
+
+    fn parse_notification(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        msg: &u32,
+    ) -> Result<(), Error> {
+        ChStripCtlOperation::parse_notification(self, unit, req, *msg, TIMEOUT_MS)
+    }
+
+    fn read_notified_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ChStripCtlOperation::read_notified_elem(self, elem_id, elem_value)
+    }
+
+    fn measured_elem_list(&self) -> &[ElemId] {
+        &self.3
+    }
+
+    fn measure_states(&mut self, unit: &mut SndDice, req: &mut FwReq) -> Result<(), Error> {
+        ChStripCtlOperation::measure_states(self, unit, req, TIMEOUT_MS)
+    }
+
+    fn read_measured_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        ChStripCtlOperation::read_measured_elem(self, elem_id, elem_value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alsactl::ElemValueExtManual;
+
+    #[test]
+    fn test_knob_ctl_definition() {
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut ctl = KnobCtl::default();
+
+        ctl.load_elems(&mut card_cntr).unwrap();
+        assert_eq!(ctl.1.len(), 3);
+
+        ctl.0.data.out_impedance = [OutputImpedance::Balance, OutputImpedance::Unbalance];
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OUTPUT_IMPEDANCE_NAME, 0);
+        let mut elem_value = ElemValue::new();
+        let mut unit = SndDice::new();
+        let mut req = FwReq::default();
+        assert!(ctl.read(&mut unit, &mut req, &elem_id, &mut elem_value).unwrap());
+        card_cntr.card.write_value(&elem_id, &elem_value).unwrap();
+
+        let mut elem_value = ElemValue::new();
+        card_cntr.card.read_value(&elem_id, &mut elem_value).unwrap();
+        let mut vals = [0; 2];
+        elem_value.get_enum(&mut vals);
+        assert_eq!(vals, [1, 0]);
+    }
+}
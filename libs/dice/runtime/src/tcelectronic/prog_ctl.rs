@@ -27,7 +27,10 @@ where
     fn prog(&self) -> &TcKonnektLoadedProgram;
     fn prog_mut(&mut self) -> &mut TcKonnektLoadedProgram;
 
-    fn load_prog(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+    fn load_prog<C: CardBackend>(
+        &mut self,
+        card_cntr: &mut CardCntr<C>,
+    ) -> Result<Vec<ElemId>, Error> {
         let labels: Vec<String> = Self::PROG_LABELS.iter().map(|l| l.to_string()).collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, LOADED_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
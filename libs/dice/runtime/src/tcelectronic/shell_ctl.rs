@@ -979,6 +979,92 @@ pub const PHYS_OUT_SRCS: [ShellPhysOutSrc; 4] = [
     ShellPhysOutSrc::MixerSend01,
 ];
 
+fn phys_out_src_name(pair: usize) -> String {
+    format!("output-{}/{}-source", pair * 2 + 1, pair * 2 + 2)
+}
+
+pub trait ShellPhysOutCtlOperation<S, T>
+where
+    S: TcKonnektSegmentData + ShellPhysOutSrcSpec,
+    TcKonnektSegment<S>: TcKonnektSegmentSpec + TcKonnektNotifiedSegmentSpec,
+    T: SegmentOperation<S>,
+{
+    fn segment_mut(&mut self) -> &mut TcKonnektSegment<S>;
+    fn phys_out_src(&self) -> &[ShellPhysOutSrc];
+    fn phys_out_src_mut(&mut self) -> &mut [ShellPhysOutSrc];
+
+    fn load_phys_out_src(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+        let labels: Vec<&str> = PHYS_OUT_SRCS
+            .iter()
+            .map(|s| phys_out_src_to_str(s))
+            .collect();
+
+        let mut notified_elem_id_list = Vec::new();
+        S::PHYS_OUT_PAIRS.iter().try_for_each(|&pair| {
+            let elem_id =
+                ElemId::new_by_name(ElemIfaceType::Card, 0, 0, &phys_out_src_name(pair), 0);
+            card_cntr
+                .add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+                .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))
+        })?;
+
+        Ok(notified_elem_id_list)
+    }
+
+    fn read_phys_out_src(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &ElemValue,
+    ) -> Result<bool, Error> {
+        let name = elem_id.get_name();
+        match S::PHYS_OUT_PAIRS
+            .iter()
+            .position(|&pair| name.as_str() == phys_out_src_name(pair))
+        {
+            Some(i) => ElemValueAccessor::<u32>::set_val(elem_value, || {
+                let pos = PHYS_OUT_SRCS
+                    .iter()
+                    .position(|s| self.phys_out_src()[i].eq(s))
+                    .unwrap();
+                Ok(pos as u32)
+            })
+            .map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    fn write_phys_out_src(
+        &mut self,
+        unit: &mut SndDice,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &ElemValue,
+        timeout_ms: u32,
+    ) -> Result<bool, Error> {
+        let name = elem_id.get_name();
+        match S::PHYS_OUT_PAIRS
+            .iter()
+            .position(|&pair| name.as_str() == phys_out_src_name(pair))
+        {
+            Some(i) => {
+                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    PHYS_OUT_SRCS
+                        .iter()
+                        .nth(val as usize)
+                        .ok_or_else(|| {
+                            let msg = format!("Invalid index of output source: {}", val);
+                            Error::new(FileError::Inval, &msg)
+                        })
+                        .map(|&s| self.phys_out_src_mut()[i] = s)
+                })?;
+                T::write_segment(req, &mut unit.get_node(), self.segment_mut(), timeout_ms)
+                    .map(|_| true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
 const COAX_OUT_SRC_NAME: &str = "coaxial-output-source";
 
 pub trait ShellCoaxIfaceCtlOperation<S, T>
@@ -1214,7 +1300,10 @@ where
     fn knob_target(&self) -> &ShellKnobTarget;
     fn knob_target_mut(&mut self) -> &mut ShellKnobTarget;
 
-    fn load_knob_target(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+    fn load_knob_target<C: CardBackend>(
+        &mut self,
+        card_cntr: &mut CardCntr<C>,
+    ) -> Result<Vec<ElemId>, Error> {
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, TARGET_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &Self::TARGETS, None, true)
     }
@@ -1280,7 +1369,10 @@ where
 
     const TARGETS: &'static [&'static str];
 
-    fn load_knob2_target(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+    fn load_knob2_target<C: CardBackend>(
+        &mut self,
+        card_cntr: &mut CardCntr<C>,
+    ) -> Result<Vec<ElemId>, Error> {
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, KNOB2_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &Self::TARGETS, None, true)
     }
@@ -19,6 +19,11 @@ pub mod transport;
 
 use hinawa::SndEfwExtManual;
 
+use fw_transaction::{add_error_context, effective_timeout_ms};
+
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// The enumeration to express source of sampling clock.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ClkSrc {
@@ -118,6 +123,12 @@ impl<O: SndEfwExtManual> EfwProtocol for O {
         params: Option<&mut [u32]>,
         timeout_ms: u32,
     ) -> Result<(), glib::Error> {
-        O::transaction_sync(self, category, command, args, params, timeout_ms).map(|_| ())
+        let timeout_ms = effective_timeout_ms(timeout_ms);
+        O::transaction_sync(self, category, command, args, params, timeout_ms)
+            .map(|_| ())
+            .map_err(|e| {
+                let context = format!("category: {}, command: {}", category, command);
+                add_error_context(e, &context)
+            })
     }
 }
@@ -16,7 +16,7 @@ use {
     nix::sys::signal,
     std::{sync::mpsc, time, thread},
     hinawa::{FwNodeExt, FwNodeExtManual, SndEfw, SndEfwExt, SndUnitExt},
-    core::{card_cntr::*, dispatcher::*, RuntimeOperation},
+    core::{card_cntr::*, dispatcher::*, event_queue::*, version::*, RuntimeOperation},
     alsactl::{
         CardExt,
         CardExtManual,
@@ -27,9 +27,13 @@ use {
         ElemValueExtManual,
     },
 };
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Timer,
@@ -37,11 +41,24 @@ enum Event {
     StreamLock(bool),
 }
 
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => Some(elem_id),
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
+}
+
 pub struct EfwRuntime {
     unit: SndEfw,
     model: model::EfwModel,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<Dispatcher>,
     timer: Option<Dispatcher>,
@@ -78,6 +95,7 @@ impl RuntimeOperation<u32> for EfwRuntime {
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(EfwRuntime {
             unit,
@@ -105,6 +123,12 @@ impl RuntimeOperation<u32> for EfwRuntime {
 
         self.model.get_notified_elem_list(&mut self.notified_elem_id_list);
 
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            efw_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -117,8 +141,9 @@ impl RuntimeOperation<u32> for EfwRuntime {
 
             match ev {
                 Event::Shutdown | Event::Disconnected => break,
+                Event::Reload => self.reload(),
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Timer => {
                     let _ = self.card_cntr.measure_elems(
@@ -167,6 +192,16 @@ impl RuntimeOperation<u32> for EfwRuntime {
     }
 }
 
+impl ConfigReloadOperation for EfwRuntime {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
+    }
+}
+
 impl EfwRuntime {
     const NODE_DISPATCHER_NAME: &'static str = "node event dispatcher";
     const SYSTEM_DISPATCHER_NAME: &'static str = "system event dispatcher";
@@ -209,6 +244,11 @@ impl EfwRuntime {
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         let tx = self.tx.clone();
         self.card_cntr
@@ -238,7 +278,7 @@ impl EfwRuntime {
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = Dispatcher::run(Self::TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(Self::TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(Self::TIMER_INTERVAL), move || {
             let _ = tx.send(Event::Timer);
             source::Continue(true)
         });
@@ -6,12 +6,35 @@
 pub mod ff800;
 pub mod ff400;
 
-use glib::Error;
+use glib::{Error, FileError};
 
-use hinawa::{FwNode, FwTcode, FwReq, FwReqExtManual};
+use std::time::{Duration, Instant};
+
+use hinawa::{FwNode, FwTcode, FwReq};
+
+use fw_transaction::FwTransaction;
 
 use super::*;
 
+// The models use block request even if the frame is for one quadlet.
+fn read_block<T: FwTransaction>(
+    tx: &mut T,
+    offset: usize,
+    raw: &mut [u8],
+    timeout_ms: u32
+) -> Result<(), Error> {
+    tx.transaction(FwTcode::ReadBlockRequest, offset as u64, raw, timeout_ms)
+}
+
+fn write_block<T: FwTransaction>(
+    tx: &mut T,
+    offset: usize,
+    raw: &mut [u8],
+    timeout_ms: u32
+) -> Result<(), Error> {
+    tx.transaction(FwTcode::WriteBlockRequest, offset as u64, raw, timeout_ms)
+}
+
 /// The structure to represent state of hardware meter.
 ///
 /// Each value of 32 bit integer is between 0x00000000 and 0x7fffff00 to represent -90.03 and
@@ -25,6 +48,45 @@ pub struct FormerMeterState{
     pub analog_outputs: Vec<i32>,
     pub spdif_outputs: Vec<i32>,
     pub adat_outputs: Vec<i32>,
+    /// Whether to carry signal for each channel of ADAT interface at current sampling rate. The
+    /// level of inactive channel is always zero.
+    pub adat_actives: Vec<bool>,
+}
+
+// The number of channels in one ADAT interface at single rate.
+const ADAT_PORT_CHANNEL_COUNT: usize = 8;
+
+/// The number of active channels in one ADAT interface at the nominal rate. The ADAT interface
+/// carries half of channels at double rate and quarter of channels at quadruple rate by S/MUX.
+pub fn adat_port_active_channel_count(rate: ClkNominalRate) -> usize {
+    match rate {
+        ClkNominalRate::R32000 |
+        ClkNominalRate::R44100 |
+        ClkNominalRate::R48000 => ADAT_PORT_CHANNEL_COUNT,
+        ClkNominalRate::R64000 |
+        ClkNominalRate::R88200 |
+        ClkNominalRate::R96000 => ADAT_PORT_CHANNEL_COUNT / 2,
+        ClkNominalRate::R128000 |
+        ClkNominalRate::R176400 |
+        ClkNominalRate::R192000 => ADAT_PORT_CHANNEL_COUNT / 4,
+    }
+}
+
+// The models with two ADAT interfaces have channels for the first interface, then for the second.
+fn update_adat_actives(actives: &mut [bool], rate: ClkNominalRate) {
+    let count = adat_port_active_channel_count(rate);
+    actives.iter_mut()
+        .enumerate()
+        .for_each(|(i, active)| *active = i % ADAT_PORT_CHANNEL_COUNT < count);
+}
+
+fn clear_inactive_adat_meters(state: &mut FormerMeterState) {
+    let actives = &state.adat_actives;
+    state.adat_inputs.iter_mut()
+        .zip(actives.iter())
+        .chain(state.adat_outputs.iter_mut().zip(actives.iter()))
+        .filter(|(_, active)| !**active)
+        .for_each(|(level, _)| *level = 0);
 }
 
 /// The trait to represent meter protocol of Fireface 400.
@@ -58,9 +120,16 @@ pub trait RmeFfFormerMeterOperation {
             analog_outputs: vec![0; Self::ANALOG_OUTPUT_COUNT],
             spdif_outputs: vec![0; Self::SPDIF_OUTPUT_COUNT],
             adat_outputs: vec![0; Self::ADAT_OUTPUT_COUNT],
+            adat_actives: vec![true; Self::ADAT_INPUT_COUNT],
         }
     }
 
+    /// Update active channels of ADAT interface according to the nominal sampling rate.
+    fn update_meter_rate(state: &mut FormerMeterState, rate: ClkNominalRate) {
+        update_adat_actives(&mut state.adat_actives, rate);
+        clear_inactive_adat_meters(state);
+    }
+
     fn read_meter(
         req: &mut FwReq,
         node: &mut FwNode,
@@ -78,14 +147,7 @@ pub trait RmeFfFormerMeterOperation {
             8 * (Self::PHYS_INPUT_COUNT + Self::PHYS_OUTPUT_COUNT * 2) +
             4 * (Self::PHYS_INPUT_COUNT + Self::STREAM_INPUT_COUNT + Self::PHYS_OUTPUT_COUNT);
         let mut raw = vec![0; length];
-        req.transaction_sync(
-            node,
-            FwTcode::ReadBlockRequest,
-            Self::METER_OFFSET as u64,
-            raw.len(),
-            &mut raw,
-            timeout_ms
-        )
+        read_block(&mut (&*req, &*node), Self::METER_OFFSET, &mut raw, timeout_ms)
             .map(|_| {
                 // TODO: pick up overload.
                 let mut quadlet = [0; 4];
@@ -107,10 +169,138 @@ pub trait RmeFfFormerMeterOperation {
                                 offset += 4;
                             });
                     });
+
+                // The hardware reports meaningless value for the channels unused by S/MUX.
+                clear_inactive_adat_meters(state);
             })
     }
 }
 
+/// The structure for ballistics of hardware meter; peak hold and decay.
+///
+/// The hardware meter reports instantaneous level. The peak of level is held for the duration,
+/// then decays at the rate. Both parameters are zero by default to report the level as is.
+#[derive(Default, Debug)]
+pub struct FormerMeterBallistics{
+    /// The duration to hold peak, in millisecond. Zero means no hold.
+    pub hold_ms: u32,
+    /// The rate of decay after hold, in dB per second. Zero means immediate fall.
+    pub decay_db_per_sec: u32,
+    processed: FormerMeterState,
+    entries: Vec<MeterBallisticsEntry>,
+    origin: Option<Instant>,
+}
+
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+struct MeterBallisticsEntry{
+    level: i32,
+    held_at: Duration,
+    updated_at: Duration,
+}
+
+impl FormerMeterBallistics {
+    pub const HOLD_MS_MAX: u32 = 10000;
+    pub const DECAY_DB_PER_SEC_MAX: u32 = 120;
+
+    pub fn is_enabled(&self) -> bool {
+        self.hold_ms > 0 || self.decay_db_per_sec > 0
+    }
+
+    /// The meter state after processed.
+    pub fn processed(&self) -> &FormerMeterState {
+        &self.processed
+    }
+
+    /// Process the meter state at current time.
+    pub fn process_now(&mut self, raw: &FormerMeterState) {
+        let origin = *self.origin.get_or_insert_with(|| Instant::now());
+        self.process(raw, origin.elapsed());
+    }
+
+    /// Process the meter state at the time elapsed from arbitrary origin.
+    pub fn process(&mut self, raw: &FormerMeterState, now: Duration) {
+        if !self.is_enabled() {
+            self.processed = raw.clone();
+            self.entries.clear();
+            return;
+        }
+
+        if self.processed.analog_inputs.len() != raw.analog_inputs.len() ||
+           self.processed.spdif_inputs.len() != raw.spdif_inputs.len() ||
+           self.processed.adat_inputs.len() != raw.adat_inputs.len() ||
+           self.processed.stream_inputs.len() != raw.stream_inputs.len() ||
+           self.processed.analog_outputs.len() != raw.analog_outputs.len() ||
+           self.processed.spdif_outputs.len() != raw.spdif_outputs.len() ||
+           self.processed.adat_outputs.len() != raw.adat_outputs.len() {
+            self.processed = raw.clone();
+        }
+
+        let count = raw.analog_inputs.len() + raw.spdif_inputs.len() + raw.adat_inputs.len() +
+                    raw.stream_inputs.len() + raw.analog_outputs.len() +
+                    raw.spdif_outputs.len() + raw.adat_outputs.len();
+        if self.entries.len() != count {
+            self.entries = vec![Default::default(); count];
+        }
+
+        let hold = Duration::from_millis(self.hold_ms as u64);
+        let decay_db_per_sec = self.decay_db_per_sec;
+
+        let p = &mut self.processed;
+        raw.analog_inputs.iter()
+            .chain(raw.spdif_inputs.iter())
+            .chain(raw.adat_inputs.iter())
+            .chain(raw.stream_inputs.iter())
+            .chain(raw.analog_outputs.iter())
+            .chain(raw.spdif_outputs.iter())
+            .chain(raw.adat_outputs.iter())
+            .zip(p.analog_inputs.iter_mut()
+                .chain(p.spdif_inputs.iter_mut())
+                .chain(p.adat_inputs.iter_mut())
+                .chain(p.stream_inputs.iter_mut())
+                .chain(p.analog_outputs.iter_mut())
+                .chain(p.spdif_outputs.iter_mut())
+                .chain(p.adat_outputs.iter_mut()))
+            .zip(self.entries.iter_mut())
+            .for_each(|((&level, processed), entry)| {
+                *processed = process_meter_level(entry, level, hold, decay_db_per_sec, now);
+            });
+
+        // The held peak is not available for inactive channels.
+        if p.adat_actives != raw.adat_actives {
+            p.adat_actives = raw.adat_actives.clone();
+        }
+        clear_inactive_adat_meters(p);
+    }
+}
+
+fn process_meter_level(
+    entry: &mut MeterBallisticsEntry,
+    level: i32,
+    hold: Duration,
+    decay_db_per_sec: u32,
+    now: Duration,
+) -> i32 {
+    if level >= entry.level {
+        entry.level = level;
+        entry.held_at = now;
+    } else {
+        let released_at = entry.held_at + hold;
+        if now >= released_at {
+            entry.level = if decay_db_per_sec == 0 {
+                level
+            } else {
+                let begin = std::cmp::max(released_at, entry.updated_at);
+                let elapsed = (now - begin).as_secs_f64();
+                let ratio = 10f64.powf(-(decay_db_per_sec as f64) * elapsed / 20.0);
+                let decayed = ((entry.level as f64 * ratio) as i32) & 0x7fffff00;
+                std::cmp::max(level, decayed)
+            };
+        }
+    }
+    entry.updated_at = now;
+    entry.level
+}
+
 /// The structure for state of output volumes.
 ///
 /// The value for volume is between 0x00000000 and 0x00010000 through 0x00000001 and 0x00080000 to
@@ -185,9 +375,19 @@ pub struct FormerMixerSrc {
     pub stream_gains: Vec<i32>,
 }
 
-/// The structure for state of mixer.
+/// The structure for state of mixer. The gains are cached per logical source, and laid out to the
+/// columns of mixer according to the nominal rate of sampling clock.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
-pub struct FormerMixerState(pub Vec<FormerMixerSrc>);
+pub struct FormerMixerState(pub Vec<FormerMixerSrc>, pub ClkNominalRate);
+
+/// The enumeration to represent logical source of mixer with the index of channel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FormerMixerSrcPort {
+    Analog(usize),
+    Spdif(usize),
+    Adat(usize),
+    Stream(usize),
+}
 
 /// The trait to represent mixer protocol specific to former models of RME Fireface.
 pub trait RmeFormerMixerOperation {
@@ -211,6 +411,10 @@ pub trait RmeFormerMixerOperation {
     const GAIN_MAX: i32 = 0x00010000;
     const GAIN_STEP: i32 = 1;
 
+    /// The number of changed gains in one mixer, over which the whole row of the mixer is written
+    /// by one block transaction instead of one transaction per gain.
+    const MIXER_ROW_WRITE_THRESHOLD: usize = 4;
+
     fn create_mixer_state() -> FormerMixerState {
         FormerMixerState(
             vec![FormerMixerSrc{
@@ -218,9 +422,37 @@ pub trait RmeFormerMixerOperation {
                 spdif_gains: vec![0; Self::SPDIF_INPUT_COUNT],
                 adat_gains: vec![0; Self::ADAT_INPUT_COUNT],
                 stream_gains: vec![0; Self::STREAM_INPUT_COUNT],
-            }; Self::DST_COUNT]
+            }; Self::DST_COUNT],
+            Default::default(),
         )
     }
+
+    /// Translate the logical source to the index of column in the mixer at the nominal rate. At
+    /// double and quadruple rates, the active channels of ADAT interfaces are packed after S/PDIF
+    /// inputs by S/MUX, thus the inactive channels have no column.
+    fn mixer_src_column(src: FormerMixerSrcPort, rate: ClkNominalRate) -> Option<usize> {
+        match src {
+            FormerMixerSrcPort::Analog(ch) if ch < Self::ANALOG_INPUT_COUNT => Some(ch),
+            FormerMixerSrcPort::Spdif(ch) if ch < Self::SPDIF_INPUT_COUNT => {
+                Some(Self::ANALOG_INPUT_COUNT + ch)
+            }
+            FormerMixerSrcPort::Adat(ch) if ch < Self::ADAT_INPUT_COUNT => {
+                let count = adat_port_active_channel_count(rate);
+                let port = ch / ADAT_PORT_CHANNEL_COUNT;
+                let pos = ch % ADAT_PORT_CHANNEL_COUNT;
+                if pos < count {
+                    Some(Self::ANALOG_INPUT_COUNT + Self::SPDIF_INPUT_COUNT + port * count + pos)
+                } else {
+                    None
+                }
+            }
+            FormerMixerSrcPort::Stream(ch) if ch < Self::STREAM_INPUT_COUNT => {
+                Some(Self::AVAIL_COUNT + ch)
+            }
+            _ => None,
+        }
+    }
+
     fn write_mixer_src_gains(
         req: &mut FwReq,
         node: &mut FwNode,
@@ -238,32 +470,119 @@ pub trait RmeFormerMixerOperation {
             });
 
         let offset = ((Self::AVAIL_COUNT * mixer * 2) + src_offset) * 4;
-        req.transaction_sync(
-            node,
-            FwTcode::WriteBlockRequest,
-            (Self::MIXER_OFFSET + offset) as u64,
-            raw.len(),
-            &mut raw,
-            timeout_ms
-        )
+        write_block(&mut (&*req, &*node), Self::MIXER_OFFSET + offset, &mut raw, timeout_ms)
     }
 
-    fn init_mixer_src_gains(
+    /// The spans of hardware inputs and stream inputs in the row of one mixer. The quadlets between
+    /// them are never written.
+    fn mixer_row_spans() -> [(usize, usize); 2] {
+        let hw_count = Self::ANALOG_INPUT_COUNT + Self::SPDIF_INPUT_COUNT + Self::ADAT_INPUT_COUNT;
+        [
+            (0, hw_count),
+            (Self::AVAIL_COUNT, Self::AVAIL_COUNT + Self::STREAM_INPUT_COUNT),
+        ]
+    }
+
+    /// Build gains in one mixer as quadlets in the layout of the mixer region at the nominal rate.
+    /// The gains of hardware inputs are followed by the gains of stream inputs at the position of
+    /// AVAIL_COUNT. The unused quadlets are filled with minimum gain.
+    fn build_mixer_row(src: &FormerMixerSrc, rate: ClkNominalRate) -> Vec<i32> {
+        let mut quads = vec![Self::GAIN_MIN; Self::AVAIL_COUNT + Self::STREAM_INPUT_COUNT];
+        [
+            (&src.analog_gains, FormerMixerSrcPort::Analog as fn(usize) -> FormerMixerSrcPort),
+            (&src.spdif_gains, FormerMixerSrcPort::Spdif),
+            (&src.adat_gains, FormerMixerSrcPort::Adat),
+            (&src.stream_gains, FormerMixerSrcPort::Stream),
+        ].iter()
+            .for_each(|(gains, port)| {
+                gains.iter()
+                    .enumerate()
+                    .filter_map(|(ch, &gain)| {
+                        Self::mixer_src_column(port(ch), rate).map(|pos| (pos, gain))
+                    })
+                    .for_each(|(pos, gain)| quads[pos] = gain);
+            });
+        quads
+    }
+
+    /// Write all of gains in one mixer by one block transaction per span of hardware inputs and
+    /// stream inputs.
+    fn write_mixer_row(
         req: &mut FwReq,
         node: &mut FwNode,
         state: &mut FormerMixerState,
         mixer: usize,
+        src: &FormerMixerSrc,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        [
-            (&state.0[mixer].analog_gains, 0),
-            (&state.0[mixer].spdif_gains, Self::ANALOG_INPUT_COUNT),
-            (&state.0[mixer].adat_gains, Self::ANALOG_INPUT_COUNT + Self::SPDIF_INPUT_COUNT),
-            (&state.0[mixer].stream_gains, Self::AVAIL_COUNT)
-        ].iter()
-            .try_for_each(|(gains, src_offset)| {
-                Self::write_mixer_src_gains(req, node, mixer, *src_offset, gains, timeout_ms)
+        let quads = Self::build_mixer_row(src, state.1);
+        Self::mixer_row_spans()
+            .iter()
+            .try_for_each(|&(begin, end)| {
+                Self::write_mixer_src_gains(req, node, mixer, begin, &quads[begin..end], timeout_ms)
             })
+            .map(|_| state.0[mixer] = src.clone())
+    }
+
+    /// Write the changed gains in one mixer. When the number of changed gains is over
+    /// MIXER_ROW_WRITE_THRESHOLD, the whole row is written at once. Else each of changed gains is
+    /// written by one quadlet.
+    fn write_mixer_src(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        state: &mut FormerMixerState,
+        mixer: usize,
+        src: &FormerMixerSrc,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let old = Self::build_mixer_row(&state.0[mixer], state.1);
+        let new = Self::build_mixer_row(src, state.1);
+        let changed: Vec<usize> = old.iter()
+            .zip(new.iter())
+            .enumerate()
+            .filter(|(_, (o, n))| o != n)
+            .map(|(i, _)| i)
+            .collect();
+
+        if changed.len() > Self::MIXER_ROW_WRITE_THRESHOLD {
+            Self::write_mixer_row(req, node, state, mixer, src, timeout_ms)
+        } else {
+            changed.iter()
+                .try_for_each(|&pos| {
+                    Self::write_mixer_src_gains(req, node, mixer, pos, &new[pos..(pos + 1)], timeout_ms)
+                })
+                .map(|_| state.0[mixer] = src.clone())
+        }
+    }
+
+    fn init_mixer_src_gains(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        state: &mut FormerMixerState,
+        mixer: usize,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let src = state.0[mixer].clone();
+        Self::write_mixer_row(req, node, state, mixer, &src, timeout_ms)
+    }
+
+    /// Update the nominal rate for the layout of columns. When the layout changes, all of mixers
+    /// are written again so that the gains are applied to the same logical sources.
+    fn update_mixer_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        state: &mut FormerMixerState,
+        rate: ClkNominalRate,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let changed = adat_port_active_channel_count(state.1) != adat_port_active_channel_count(rate);
+        state.1 = rate;
+        if changed {
+            (0..state.0.len())
+                .try_for_each(|mixer| Self::init_mixer_src_gains(req, node, state, mixer, timeout_ms))
+        } else {
+            Ok(())
+        }
     }
 
     fn write_mixer_analog_gains(
@@ -274,8 +593,9 @@ pub trait RmeFormerMixerOperation {
         gains: &[i32],
         timeout_ms: u32
     ) -> Result<(), Error> {
-        Self::write_mixer_src_gains(req, node, mixer, 0, gains, timeout_ms)
-            .map(|_| state.0[mixer].analog_gains.copy_from_slice(&gains))
+        let mut src = state.0[mixer].clone();
+        src.analog_gains.copy_from_slice(&gains);
+        Self::write_mixer_src(req, node, state, mixer, &src, timeout_ms)
     }
 
     fn write_mixer_spdif_gains(
@@ -286,15 +606,9 @@ pub trait RmeFormerMixerOperation {
         gains: &[i32],
         timeout_ms: u32
     ) -> Result<(), Error> {
-        Self::write_mixer_src_gains(
-            req,
-            node,
-            mixer,
-            Self::ANALOG_INPUT_COUNT,
-            gains,
-            timeout_ms
-        )
-            .map(|_| state.0[mixer].spdif_gains.copy_from_slice(&gains))
+        let mut src = state.0[mixer].clone();
+        src.spdif_gains.copy_from_slice(&gains);
+        Self::write_mixer_src(req, node, state, mixer, &src, timeout_ms)
     }
 
     fn write_mixer_adat_gains(
@@ -305,15 +619,9 @@ pub trait RmeFormerMixerOperation {
         gains: &[i32],
         timeout_ms: u32
     ) -> Result<(), Error> {
-        Self::write_mixer_src_gains(
-            req,
-            node,
-            mixer,
-            Self::ANALOG_INPUT_COUNT + Self::SPDIF_INPUT_COUNT,
-            gains,
-            timeout_ms
-        )
-            .map(|_| state.0[mixer].adat_gains.copy_from_slice(&gains))
+        let mut src = state.0[mixer].clone();
+        src.adat_gains.copy_from_slice(&gains);
+        Self::write_mixer_src(req, node, state, mixer, &src, timeout_ms)
     }
 
     fn write_mixer_stream_gains(
@@ -324,8 +632,72 @@ pub trait RmeFormerMixerOperation {
         gains: &[i32],
         timeout_ms: u32
     ) -> Result<(), Error> {
-        Self::write_mixer_src_gains(req, node, mixer, Self::AVAIL_COUNT, gains, timeout_ms)
-            .map(|_| state.0[mixer].stream_gains.copy_from_slice(&gains))
+        let mut src = state.0[mixer].clone();
+        src.stream_gains.copy_from_slice(&gains);
+        Self::write_mixer_src(req, node, state, mixer, &src, timeout_ms)
+    }
+
+    /// Detect the source routed exclusively to the mixer at unity gain. The index of source is in
+    /// order of analog, S/PDIF, ADAT, and stream inputs.
+    fn exclusive_mixer_src(state: &FormerMixerState, mixer: usize) -> Option<usize> {
+        let src = &state.0[mixer];
+        let gains: Vec<i32> = src.analog_gains.iter()
+            .chain(src.spdif_gains.iter())
+            .chain(src.adat_gains.iter())
+            .chain(src.stream_gains.iter())
+            .copied()
+            .collect();
+
+        gains.iter()
+            .position(|&gain| gain == Self::GAIN_ZERO)
+            .filter(|&pos| {
+                gains.iter()
+                    .enumerate()
+                    .all(|(i, &gain)| i == pos || gain == Self::GAIN_MIN)
+            })
+    }
+
+    /// Route the source exclusively to the mixer at unity gain, and mute the other sources. The
+    /// index of source is in order of analog, S/PDIF, ADAT, and stream inputs.
+    fn write_mixer_exclusive_src(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        state: &mut FormerMixerState,
+        mixer: usize,
+        src: usize,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let cache = Self::build_mixer_exclusive_src(&state.0[mixer], src)?;
+        Self::write_mixer_src(req, node, state, mixer, &cache, timeout_ms)
+    }
+
+    /// Build gains in one mixer for the source routed exclusively at unity gain.
+    fn build_mixer_exclusive_src(curr: &FormerMixerSrc, src: usize) -> Result<FormerMixerSrc, Error> {
+        let src_count = Self::ANALOG_INPUT_COUNT + Self::SPDIF_INPUT_COUNT +
+                        Self::ADAT_INPUT_COUNT + Self::STREAM_INPUT_COUNT;
+        if src >= src_count {
+            let msg = format!("Invalid index of source for mixer: {}", src);
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+
+        let mut cache = curr.clone();
+        let mut pos = src;
+        [
+            &mut cache.analog_gains,
+            &mut cache.spdif_gains,
+            &mut cache.adat_gains,
+            &mut cache.stream_gains,
+        ].iter_mut()
+            .for_each(|gains| {
+                gains.iter_mut()
+                    .for_each(|gain| *gain = Self::GAIN_MIN);
+                if pos < gains.len() {
+                    gains[pos] = Self::GAIN_ZERO;
+                }
+                pos = pos.wrapping_sub(gains.len());
+            });
+
+        Ok(cache)
     }
 }
 
@@ -340,6 +712,69 @@ pub struct FormerSpdifOutput {
     pub non_audio: bool,
 }
 
+impl SpdifInput {
+    fn build_former<T: RmeFormerDigitalIfaceCfgLayout>(&self, quads: &mut [u32]) {
+        build_flag(quads, T::SPDIF_IN_IFACE_OPT, self.iface == SpdifIface::Optical);
+        build_flag(quads, T::SPDIF_IN_USE_PREEMBLE, self.use_preemble);
+    }
+
+    fn parse_former<T: RmeFormerDigitalIfaceCfgLayout>(&mut self, quads: &[u32]) {
+        self.iface = if parse_flag(quads, T::SPDIF_IN_IFACE_OPT) {
+            SpdifIface::Optical
+        } else {
+            SpdifIface::Coaxial
+        };
+        self.use_preemble = parse_flag(quads, T::SPDIF_IN_USE_PREEMBLE);
+    }
+}
+
+impl FormerSpdifOutput {
+    fn build<T: RmeFormerDigitalIfaceCfgLayout>(&self, quads: &mut [u32]) {
+        build_flag(quads, T::SPDIF_OUT_FMT_PRO, self.format == SpdifFormat::Professional);
+        build_flag(quads, T::SPDIF_OUT_EMPHASIS, self.emphasis);
+        build_flag(quads, T::SPDIF_OUT_NON_AUDIO, self.non_audio);
+    }
+
+    fn parse<T: RmeFormerDigitalIfaceCfgLayout>(&mut self, quads: &[u32]) {
+        self.format = if parse_flag(quads, T::SPDIF_OUT_FMT_PRO) {
+            SpdifFormat::Professional
+        } else {
+            SpdifFormat::Consumer
+        };
+        self.emphasis = parse_flag(quads, T::SPDIF_OUT_EMPHASIS);
+        self.non_audio = parse_flag(quads, T::SPDIF_OUT_NON_AUDIO);
+    }
+}
+
+// The position of flags for digital interfaces in configuration quadlets, expressed by the index
+// of quadlet and the mask. The position differs between models.
+trait RmeFormerDigitalIfaceCfgLayout {
+    const SPDIF_IN_IFACE_OPT: (usize, u32);
+    const SPDIF_IN_USE_PREEMBLE: (usize, u32);
+    const SPDIF_OUT_FMT_PRO: (usize, u32);
+    const SPDIF_OUT_EMPHASIS: (usize, u32);
+    const SPDIF_OUT_NON_AUDIO: (usize, u32);
+    const WORD_OUT_SINGLE_SPEED: (usize, u32);
+}
+
+fn build_word_out_single<T: RmeFormerDigitalIfaceCfgLayout>(quads: &mut [u32], enabled: bool) {
+    build_flag(quads, T::WORD_OUT_SINGLE_SPEED, enabled);
+}
+
+fn parse_word_out_single<T: RmeFormerDigitalIfaceCfgLayout>(quads: &[u32]) -> bool {
+    parse_flag(quads, T::WORD_OUT_SINGLE_SPEED)
+}
+
+fn build_flag(quads: &mut [u32], (pos, mask): (usize, u32), enabled: bool) {
+    if enabled {
+        quads[pos] |= mask;
+    }
+}
+
+fn parse_flag(quads: &[u32], (pos, mask): (usize, u32)) -> bool {
+    quads[pos] & mask > 0
+}
+
 /// The enumeration to represent nominal level of line inputs.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum FormerLineInNominalLevel {
@@ -355,3 +790,308 @@ impl Default for FormerLineInNominalLevel {
         Self::Low
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn meter_state(level: i32) -> FormerMeterState {
+        FormerMeterState{
+            analog_inputs: vec![level],
+            ..Default::default()
+        }
+    }
+
+    struct TestMixerProtocol;
+
+    impl RmeFormerMixerOperation for TestMixerProtocol {
+        const ANALOG_INPUT_COUNT: usize = 2;
+        const SPDIF_INPUT_COUNT: usize = 2;
+        const ADAT_INPUT_COUNT: usize = 2;
+        const STREAM_INPUT_COUNT: usize = 6;
+
+        const ANALOG_OUTPUT_COUNT: usize = 2;
+        const SPDIF_OUTPUT_COUNT: usize = 0;
+        const ADAT_OUTPUT_COUNT: usize = 0;
+
+        const MIXER_OFFSET: usize = 0;
+        const AVAIL_COUNT: usize = 6;
+    }
+
+    #[test]
+    fn test_exclusive_mixer_src() {
+        let mut state = TestMixerProtocol::create_mixer_state();
+        assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 0), None);
+
+        state.0[0].stream_gains[1] = TestMixerProtocol::GAIN_ZERO;
+        assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 0), Some(7));
+        assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 1), None);
+
+        state.0[0].spdif_gains[0] = 0x00001000;
+        assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 0), None);
+
+        state.0[1].analog_gains[0] = TestMixerProtocol::GAIN_ZERO;
+        state.0[1].analog_gains[1] = TestMixerProtocol::GAIN_ZERO;
+        assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 1), None);
+    }
+
+    #[test]
+    fn test_mixer_exclusive_src() {
+        let mut state = TestMixerProtocol::create_mixer_state();
+        state.0[0].analog_gains[0] = TestMixerProtocol::GAIN_ZERO;
+        state.0[0].spdif_gains[1] = 0x00001000;
+
+        // Each source in order of analog, S/PDIF, ADAT, and stream inputs.
+        (0..12).for_each(|src| {
+            let built = TestMixerProtocol::build_mixer_exclusive_src(&state.0[0], src).unwrap();
+            state.0[0] = built;
+            assert_eq!(TestMixerProtocol::exclusive_mixer_src(&state, 0), Some(src));
+        });
+        assert_eq!(state.0[0].stream_gains[5], TestMixerProtocol::GAIN_ZERO);
+        assert!(state.0[0].analog_gains.iter().all(|&gain| gain == TestMixerProtocol::GAIN_MIN));
+
+        assert!(TestMixerProtocol::build_mixer_exclusive_src(&state.0[0], 12).is_err());
+    }
+
+    #[test]
+    fn test_mixer_row() {
+        let mut state = TestMixerProtocol::create_mixer_state();
+        let src = &mut state.0[1];
+        src.analog_gains.iter_mut().enumerate().for_each(|(i, gain)| *gain = 0x100 + i as i32);
+        src.spdif_gains.iter_mut().enumerate().for_each(|(i, gain)| *gain = 0x200 + i as i32);
+        src.adat_gains.iter_mut().enumerate().for_each(|(i, gain)| *gain = 0x300 + i as i32);
+        src.stream_gains.iter_mut().enumerate().for_each(|(i, gain)| *gain = 0x400 + i as i32);
+
+        let quads = TestMixerProtocol::build_mixer_row(&state.0[1], state.1);
+        assert_eq!(quads.len(), TestMixerProtocol::AVAIL_COUNT + TestMixerProtocol::STREAM_INPUT_COUNT);
+
+        // The same position as the quadlet written for each gain.
+        let mut raw = vec![0u8; quads.len() * 4];
+        [
+            (&state.0[1].analog_gains, 0),
+            (&state.0[1].spdif_gains, TestMixerProtocol::ANALOG_INPUT_COUNT),
+            (&state.0[1].adat_gains,
+             TestMixerProtocol::ANALOG_INPUT_COUNT + TestMixerProtocol::SPDIF_INPUT_COUNT),
+            (&state.0[1].stream_gains, TestMixerProtocol::AVAIL_COUNT),
+        ].iter()
+            .for_each(|(gains, src_offset)| {
+                gains.iter()
+                    .enumerate()
+                    .for_each(|(i, gain)| {
+                        let pos = (src_offset + i) * 4;
+                        raw[pos..(pos + 4)].copy_from_slice(&gain.to_le_bytes());
+                    });
+            });
+
+        let built: Vec<u8> = quads.iter()
+            .flat_map(|quad| quad.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(built, raw);
+    }
+
+    #[test]
+    fn test_mixer_row_spans() {
+        assert_eq!(TestMixerProtocol::mixer_row_spans(), [(0, 6), (6, 12)]);
+        assert_eq!(ff400::Ff400Protocol::mixer_row_spans(), [(0, 18), (18, 36)]);
+        // The quadlets between hardware inputs and stream inputs are not written.
+        assert_eq!(ff800::Ff800Protocol::mixer_row_spans(), [(0, 28), (32, 60)]);
+    }
+
+    fn adat_columns<T: RmeFormerMixerOperation>(rate: ClkNominalRate) -> Vec<Option<usize>> {
+        (0..T::ADAT_INPUT_COUNT)
+            .map(|ch| T::mixer_src_column(FormerMixerSrcPort::Adat(ch), rate))
+            .collect()
+    }
+
+    #[test]
+    fn test_mixer_src_column_ff400() {
+        type T = ff400::Ff400Protocol;
+
+        [ClkNominalRate::R44100, ClkNominalRate::R96000, ClkNominalRate::R192000].iter()
+            .for_each(|&rate| {
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Analog(7), rate), Some(7));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Analog(8), rate), None);
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Spdif(1), rate), Some(9));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Stream(0), rate), Some(18));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Stream(18), rate), None);
+            });
+
+        let single: Vec<Option<usize>> = (10..18).map(Some).collect();
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R48000), single);
+
+        let double = vec![Some(10), Some(11), Some(12), Some(13), None, None, None, None];
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R88200), double);
+
+        let quad = vec![Some(10), Some(11), None, None, None, None, None, None];
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R176400), quad);
+    }
+
+    #[test]
+    fn test_mixer_src_column_ff800() {
+        type T = ff800::Ff800Protocol;
+
+        [ClkNominalRate::R44100, ClkNominalRate::R96000, ClkNominalRate::R192000].iter()
+            .for_each(|&rate| {
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Analog(9), rate), Some(9));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Spdif(0), rate), Some(10));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Spdif(2), rate), None);
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Stream(27), rate), Some(59));
+                assert_eq!(T::mixer_src_column(FormerMixerSrcPort::Adat(16), rate), None);
+            });
+
+        let single: Vec<Option<usize>> = (12..28).map(Some).collect();
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R44100), single);
+
+        // The active channels of the second interface follow the ones of the first interface.
+        let double = vec![
+            Some(12), Some(13), Some(14), Some(15), None, None, None, None,
+            Some(16), Some(17), Some(18), Some(19), None, None, None, None,
+        ];
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R96000), double);
+
+        let quad = vec![
+            Some(12), Some(13), None, None, None, None, None, None,
+            Some(14), Some(15), None, None, None, None, None, None,
+        ];
+        assert_eq!(adat_columns::<T>(ClkNominalRate::R192000), quad);
+    }
+
+    #[test]
+    fn test_mixer_row_double_rate() {
+        type T = ff800::Ff800Protocol;
+
+        let mut state = T::create_mixer_state();
+        state.0[0].adat_gains.iter_mut().enumerate().for_each(|(i, gain)| *gain = 0x300 + i as i32);
+
+        let quads = T::build_mixer_row(&state.0[0], ClkNominalRate::R88200);
+        assert_eq!(&quads[12..16], &[0x300, 0x301, 0x302, 0x303]);
+        assert_eq!(&quads[16..20], &[0x308, 0x309, 0x30a, 0x30b]);
+        assert!(quads[20..28].iter().all(|&gain| gain == T::GAIN_MIN));
+    }
+
+    struct TestMeterProtocol;
+
+    impl RmeFfFormerMeterOperation for TestMeterProtocol {
+        const METER_OFFSET: usize = 0;
+
+        const ANALOG_INPUT_COUNT: usize = 2;
+        const SPDIF_INPUT_COUNT: usize = 2;
+        const ADAT_INPUT_COUNT: usize = 16;
+        const STREAM_INPUT_COUNT: usize = 20;
+
+        const ANALOG_OUTPUT_COUNT: usize = 2;
+        const SPDIF_OUTPUT_COUNT: usize = 2;
+        const ADAT_OUTPUT_COUNT: usize = 16;
+    }
+
+    #[test]
+    fn test_adat_meter_single_rate() {
+        let mut state = TestMeterProtocol::create_meter_state();
+        state.adat_inputs.iter_mut().for_each(|v| *v = 0x1000);
+        [ClkNominalRate::R32000, ClkNominalRate::R44100, ClkNominalRate::R48000].iter()
+            .for_each(|&rate| {
+                assert_eq!(adat_port_active_channel_count(rate), 8);
+                TestMeterProtocol::update_meter_rate(&mut state, rate);
+                assert_eq!(state.adat_actives, vec![true; 16]);
+                assert_eq!(state.adat_inputs, vec![0x1000; 16]);
+            });
+    }
+
+    #[test]
+    fn test_adat_meter_double_rate() {
+        let expected: Vec<bool> = (0..16).map(|i| i % 8 < 4).collect();
+        [ClkNominalRate::R64000, ClkNominalRate::R88200, ClkNominalRate::R96000].iter()
+            .for_each(|&rate| {
+                let mut state = TestMeterProtocol::create_meter_state();
+                state.adat_inputs.iter_mut().for_each(|v| *v = 0x1000);
+                state.adat_outputs.iter_mut().for_each(|v| *v = 0x2000);
+                assert_eq!(adat_port_active_channel_count(rate), 4);
+                TestMeterProtocol::update_meter_rate(&mut state, rate);
+                assert_eq!(state.adat_actives, expected);
+                state.adat_inputs.iter()
+                    .zip(state.adat_outputs.iter())
+                    .zip(expected.iter())
+                    .for_each(|((&input, &output), &active)| {
+                        assert_eq!(input, if active { 0x1000 } else { 0 });
+                        assert_eq!(output, if active { 0x2000 } else { 0 });
+                    });
+            });
+    }
+
+    #[test]
+    fn test_adat_meter_quadruple_rate() {
+        let expected: Vec<bool> = (0..16).map(|i| i % 8 < 2).collect();
+        [ClkNominalRate::R128000, ClkNominalRate::R176400, ClkNominalRate::R192000].iter()
+            .for_each(|&rate| {
+                let mut state = TestMeterProtocol::create_meter_state();
+                state.adat_inputs.iter_mut().for_each(|v| *v = 0x1000);
+                assert_eq!(adat_port_active_channel_count(rate), 2);
+                TestMeterProtocol::update_meter_rate(&mut state, rate);
+                assert_eq!(state.adat_actives, expected);
+                assert_eq!(state.adat_inputs.iter().filter(|&&v| v > 0).count(), 4);
+            });
+
+        // Back to single rate.
+        let mut state = TestMeterProtocol::create_meter_state();
+        TestMeterProtocol::update_meter_rate(&mut state, ClkNominalRate::R192000);
+        TestMeterProtocol::update_meter_rate(&mut state, ClkNominalRate::R48000);
+        assert_eq!(state.adat_actives, vec![true; 16]);
+    }
+
+    #[test]
+    fn test_meter_ballistics_disabled() {
+        let mut ballistics = FormerMeterBallistics::default();
+        [0x7fffff00, 0x00001000, 0x00000000].iter().enumerate().for_each(|(i, &level)| {
+            let raw = meter_state(level);
+            ballistics.process(&raw, Duration::from_millis(50 * i as u64));
+            assert_eq!(ballistics.processed(), &raw);
+        });
+    }
+
+    #[test]
+    fn test_meter_ballistics_hold() {
+        let mut ballistics = FormerMeterBallistics::default();
+        ballistics.hold_ms = 100;
+
+        ballistics.process(&meter_state(0x40000000), Duration::from_millis(0));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x40000000);
+
+        // Held.
+        ballistics.process(&meter_state(0x00001000), Duration::from_millis(50));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x40000000);
+
+        // Higher level is held again.
+        ballistics.process(&meter_state(0x50000000), Duration::from_millis(80));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x50000000);
+
+        ballistics.process(&meter_state(0x00001000), Duration::from_millis(150));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x50000000);
+
+        // Released without decay.
+        ballistics.process(&meter_state(0x00001000), Duration::from_millis(180));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x00001000);
+    }
+
+    #[test]
+    fn test_meter_ballistics_decay() {
+        let mut ballistics = FormerMeterBallistics::default();
+        ballistics.hold_ms = 100;
+        ballistics.decay_db_per_sec = 20;
+
+        ballistics.process(&meter_state(0x7fffff00), Duration::from_millis(0));
+        ballistics.process(&meter_state(0x00000000), Duration::from_millis(100));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x7fffff00);
+
+        // -20 dB in one second after release.
+        ballistics.process(&meter_state(0x00000000), Duration::from_millis(600));
+        let level = ballistics.processed().analog_inputs[0];
+        assert!(level < 0x7fffff00 && level > 0x0ccccc00);
+        ballistics.process(&meter_state(0x00000000), Duration::from_millis(1100));
+        let level = ballistics.processed().analog_inputs[0];
+        assert!(level >= 0x0ccccb00 && level <= 0x0ccccd00);
+
+        // The level is not below the current one.
+        ballistics.process(&meter_state(0x08000000), Duration::from_millis(2100));
+        assert_eq!(ballistics.processed().analog_inputs[0], 0x08000000);
+    }
+}
@@ -5,7 +5,9 @@
 
 use glib::Error;
 
-use hinawa::{FwNode, FwTcode, FwReq, FwReqExtManual};
+use hinawa::{FwNode, FwTcode, FwReq};
+
+use fw_transaction::{transaction_sync, FwTransaction};
 
 use super::*;
 
@@ -61,7 +63,8 @@ impl Ff400Protocol {
         let cmd = ((ch as u32) << 16) | ((level as u32) & 0xff);
         let mut raw = [0; 4];
         raw.copy_from_slice(&cmd.to_le_bytes());
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteQuadletRequest,
             AMP_OFFSET as u64,
@@ -154,6 +157,29 @@ impl Ff400Protocol {
                     .map(|_| *o = *n)
             })
     }
+
+    /// Compute the value of mixer source gain to compensate the change of amplifier gain for mic
+    /// input, so that the sum of both gains is kept. The gain of amplifier is 1 dB per step, while
+    /// the value of mixer source gain is linear to the amplitude.
+    pub fn compensate_mixer_src_gain(gain: i32, old_amp: i8, new_amp: i8) -> i32 {
+        Self::saturate_mixer_src_gain(Self::compensate_mixer_src_gain_unsaturated(gain as i64,
+                                                                                  old_amp,
+                                                                                  new_amp))
+    }
+
+    /// Compute the value the same as `compensate_mixer_src_gain()` without saturation, so that
+    /// the compensation from the same base value is reversible.
+    pub fn compensate_mixer_src_gain_unsaturated(gain: i64, old_amp: i8, new_amp: i8) -> i64 {
+        let diff_db = new_amp as f64 - old_amp as f64;
+        (gain as f64 * 10f64.powf(-diff_db / 20.0)).round() as i64
+    }
+
+    /// Saturate the value of mixer source gain within the available range.
+    pub fn saturate_mixer_src_gain(val: i64) -> i32 {
+        let min = <Self as RmeFormerMixerOperation>::GAIN_MIN as i64;
+        let max = <Self as RmeFormerMixerOperation>::GAIN_MAX as i64;
+        val.max(min).min(max) as i32
+    }
 }
 
 impl RmeFormerOutputOperation for Ff400Protocol {
@@ -170,7 +196,8 @@ impl RmeFormerOutputOperation for Ff400Protocol {
     ) -> Result<(), Error> {
         let mut raw = [0; 4];
         raw.copy_from_slice(&vol.to_le_bytes());
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteBlockRequest,
             (OUTPUT_OFFSET + ch * 4) as u64,
@@ -325,6 +352,8 @@ pub struct Ff400Status {
     /// For status of locking to external clocks.
     pub lock: Ff400ClkLockStatus,
 
+    /// The rate of S/PDIF input. The quadlets have no bits for the channel status of S/PDIF
+    /// input, like professional format, non-audio data, and emphasis.
     pub spdif_rate: Option<ClkNominalRate>,
     pub active_clk_src: Ff400ClkSrc,
     pub external_clk_rate: Option<ClkNominalRate>,
@@ -427,16 +456,18 @@ impl Ff400Protocol {
         node: &mut FwNode,
         status: &mut Ff400Status,
         timeout_ms: u32
+    ) -> Result<(), Error> {
+        Self::read_status_by(&mut (&*req, &*node), status, timeout_ms)
+    }
+
+    /// Read status by any backend of transaction.
+    pub fn read_status_by<T: FwTransaction>(
+        tx: &mut T,
+        status: &mut Ff400Status,
+        timeout_ms: u32
     ) -> Result<(), Error> {
         let mut raw = [0; 8];
-        req.transaction_sync(
-            node,
-            FwTcode::ReadBlockRequest,
-            STATUS_OFFSET as u64,
-            raw.len(),
-            &mut raw,
-            timeout_ms
-        )
+        read_block(tx, STATUS_OFFSET, &mut raw, timeout_ms)
             .map(|_| {
                 let mut quadlet = [0; 4];
                 let mut quads = [0u32; 2];
@@ -683,6 +714,10 @@ impl Ff400MidiTxLowOffset {
     }
 }
 
+/// The structure to represent quadlets of configuration written at last.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Ff400CfgCache(Option<[u32; Ff400Config::QUADLET_COUNT]>);
+
 /// The structure to represent configurations.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Ff400Config{
@@ -770,29 +805,14 @@ impl Ff400Config {
             }
         }
 
-        if self.spdif_in.iface == SpdifIface::Optical {
-            quads[2] |= Q2_SPDIF_IN_IFACE_OPT_MASK;
-        }
-        if self.spdif_in.use_preemble {
-            quads[2] |= Q2_SPDIF_IN_USE_PREEMBLE;
-        }
+        self.spdif_in.build_former::<Self>(quads);
+        self.spdif_out.build::<Self>(quads);
 
         if self.opt_out_signal == OpticalOutputSignal::Spdif {
             quads[2] |= Q2_OPT_OUT_SIGNAL_MASK;
         }
-        if self.spdif_out.format == SpdifFormat::Professional {
-            quads[2] |= Q2_SPDIF_OUT_FMT_PRO_MASK;
-        }
-        if self.spdif_out.emphasis {
-            quads[2] |= Q2_SPDIF_OUT_EMPHASIS_MASK;
-        }
-        if self.spdif_out.non_audio {
-            quads[2] |= Q2_SPDIF_OUT_NON_AUDIO_MASK;
-        }
 
-        if self.word_out_single {
-            quads[2] |= Q2_WORD_OUT_SINGLE_SPEED_MASK;
-        }
+        build_word_out_single::<Self>(quads, self.word_out_single);
 
         if self.continue_at_errors {
             quads[2] |= Q2_CONTINUE_AT_ERRORS;
@@ -824,20 +844,8 @@ impl Ff400Config {
             _ => unreachable!(),
         };
 
-        self.spdif_in.iface = if quads[2] & Q2_SPDIF_IN_IFACE_OPT_MASK > 0 {
-            SpdifIface::Optical
-        } else {
-            SpdifIface::Coaxial
-        };
-        self.spdif_in.use_preemble = quads[2] & Q2_SPDIF_IN_USE_PREEMBLE > 0;
-
-        self.spdif_out.format = if quads[2] & Q2_SPDIF_OUT_FMT_PRO_MASK > 0 {
-            SpdifFormat::Professional
-        } else {
-            SpdifFormat::Consumer
-        };
-        self.spdif_out.emphasis = quads[2] & Q2_SPDIF_OUT_EMPHASIS_MASK > 0;
-        self.spdif_out.non_audio = quads[2] & Q2_SPDIF_OUT_NON_AUDIO_MASK > 0;
+        self.spdif_in.parse_former::<Self>(quads);
+        self.spdif_out.parse::<Self>(quads);
 
         self.opt_out_signal = if quads[2] & Q2_OPT_OUT_SIGNAL_MASK > 0 {
             OpticalOutputSignal::Spdif
@@ -845,7 +853,7 @@ impl Ff400Config {
             OpticalOutputSignal::Adat
         };
 
-        self.word_out_single = quads[2] & Q2_WORD_OUT_SINGLE_SPEED_MASK > 0;
+        self.word_out_single = parse_word_out_single::<Self>(quads);
         self.continue_at_errors = quads[2] & Q2_CONTINUE_AT_ERRORS > 0;
     }
 
@@ -860,6 +868,15 @@ impl Ff400Config {
     }
 }
 
+impl RmeFormerDigitalIfaceCfgLayout for Ff400Config {
+    const SPDIF_IN_IFACE_OPT: (usize, u32) = (2, Q2_SPDIF_IN_IFACE_OPT_MASK);
+    const SPDIF_IN_USE_PREEMBLE: (usize, u32) = (2, Q2_SPDIF_IN_USE_PREEMBLE);
+    const SPDIF_OUT_FMT_PRO: (usize, u32) = (2, Q2_SPDIF_OUT_FMT_PRO_MASK);
+    const SPDIF_OUT_EMPHASIS: (usize, u32) = (2, Q2_SPDIF_OUT_EMPHASIS_MASK);
+    const SPDIF_OUT_NON_AUDIO: (usize, u32) = (2, Q2_SPDIF_OUT_NON_AUDIO_MASK);
+    const WORD_OUT_SINGLE_SPEED: (usize, u32) = (2, Q2_WORD_OUT_SINGLE_SPEED_MASK);
+}
+
 impl Ff400Protocol {
     pub fn write_cfg(
         req: &mut FwReq,
@@ -867,23 +884,259 @@ impl Ff400Protocol {
         cfg: &Ff400Config,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        let mut quads = [0u32; 3];
+        Self::write_cfg_by(&mut (&*req, &*node), cfg, timeout_ms)
+    }
+
+    /// Write configuration unless the quadlets built from it are the same as the ones written at
+    /// last. Whole quadlets are written even if the change is just in the third quadlet, as the
+    /// hardware requires, while the first and second quadlets are kept byte-exact so that relays
+    /// for analog inputs are not switched again. Return whether the transaction is performed.
+    pub fn write_cfg_partial(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        cfg: &Ff400Config,
+        cache: &mut Ff400CfgCache,
+        timeout_ms: u32
+    ) -> Result<bool, Error> {
+        Self::write_cfg_partial_by(&mut (&*req, &*node), cfg, cache, timeout_ms)
+    }
+
+    fn write_cfg_by<T: FwTransaction>(
+        tx: &mut T,
+        cfg: &Ff400Config,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let mut quads = [0u32; Ff400Config::QUADLET_COUNT];
+        cfg.build(&mut quads);
+        Self::write_cfg_quads(tx, &quads, timeout_ms)
+    }
+
+    /// Write configuration by any backend of transaction, as `write_cfg_partial()` does.
+    pub fn write_cfg_partial_by<T: FwTransaction>(
+        tx: &mut T,
+        cfg: &Ff400Config,
+        cache: &mut Ff400CfgCache,
+        timeout_ms: u32
+    ) -> Result<bool, Error> {
+        // The build is deterministic, thus the first and second quadlets are byte-exact unless the
+        // corresponding configurations are changed.
+        let mut quads = [0u32; Ff400Config::QUADLET_COUNT];
         cfg.build(&mut quads);
 
-        let mut raw = [0; 12];
+        match &cache.0 {
+            Some(last) if last.eq(&quads) => Ok(false),
+            _ => {
+                Self::write_cfg_quads(tx, &quads, timeout_ms)
+                    .map(|_| {
+                        cache.0 = Some(quads);
+                        true
+                    })
+            }
+        }
+    }
+
+    fn write_cfg_quads<T: FwTransaction>(
+        tx: &mut T,
+        quads: &[u32; Ff400Config::QUADLET_COUNT],
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let mut raw = [0; Ff400Config::QUADLET_COUNT * 4];
         quads.iter()
             .enumerate()
             .for_each(|(i, quad)| {
                 let pos = i * 4;
                 raw[pos..(pos + 4)].copy_from_slice(&quad.to_le_bytes())
             });
-        req.transaction_sync(
-            node,
-            FwTcode::WriteBlockRequest,
-            CFG_OFFSET as u64,
-            raw.len(),
-            &mut raw,
-            timeout_ms
-        )
+        write_block(tx, CFG_OFFSET, &mut raw, timeout_ms)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fw_transaction::{replay::ReplayTransaction, MockTransaction};
+
+    #[test]
+    fn test_read_status() {
+        let mut tx = MockTransaction::default();
+
+        let quads = [
+            Q0_LOCK_ADAT_MASK | Q0_SYNC_SPDIF_MASK | Q0_SPDIF_RATE_48000_FLAG |
+                Q0_ACTIVE_CLK_SRC_INTERNAL_FLAG,
+            Q1_OPT_OUT_SIGNAL_MASK | Q1_CONF_CLK_SRC_SPDIF_FLAG | Q1_CONF_CLK_RATE_96000_FLAG,
+        ];
+        let mut raw = Vec::new();
+        quads.iter().for_each(|quad| raw.extend_from_slice(&quad.to_le_bytes()));
+        tx.regs.insert(STATUS_OFFSET as u64, raw);
+
+        let mut status = Ff400Status::default();
+        Ff400Protocol::read_status_by(&mut tx, &mut status, 100).unwrap();
+        assert_eq!(status.lock, Ff400ClkLockStatus{adat: true, spdif: false, word_clock: false});
+        assert_eq!(status.sync, Ff400ClkSyncStatus{adat: false, spdif: true, word_clock: false});
+        assert_eq!(status.spdif_rate, Some(ClkNominalRate::R48000));
+        assert_eq!(status.active_clk_src, Ff400ClkSrc::Internal);
+        assert_eq!(status.external_clk_rate, None);
+        assert_eq!(status.opt_out_signal, OpticalOutputSignal::Spdif);
+        assert_eq!(status.configured_clk_src, Ff400ClkSrc::Spdif);
+        assert_eq!(status.configured_clk_rate, ClkNominalRate::R96000);
     }
+
+    #[test]
+    fn test_write_cfg() {
+        let mut tx = MockTransaction::default();
+
+        let mut cfg = Ff400Config::default();
+        cfg.clk.primary_src = Ff400ClkSrc::WordClock;
+        cfg.analog_in.phantom_powering[1] = true;
+        cfg.analog_in.pad[0] = true;
+        cfg.line_out_level = LineOutNominalLevel::Consumer;
+        cfg.opt_out_signal = OpticalOutputSignal::Spdif;
+        Ff400Protocol::write_cfg_by(&mut tx, &cfg, 100).unwrap();
+
+        let raw = tx.regs.get(&(CFG_OFFSET as u64)).unwrap();
+        assert_eq!(raw.len(), 12);
+        let mut quads = [0u32; 3];
+        let mut quadlet = [0; 4];
+        quads.iter_mut().enumerate().for_each(|(i, quad)| {
+            let pos = i * 4;
+            quadlet.copy_from_slice(&raw[pos..(pos + 4)]);
+            *quad = u32::from_le_bytes(quadlet);
+        });
+
+        let mut target = Ff400Config::default();
+        target.parse(&quads);
+        assert_eq!(target, cfg);
+    }
+
+    fn cfg_frame(cfg: &Ff400Config) -> Vec<u8> {
+        let mut quads = [0u32; Ff400Config::QUADLET_COUNT];
+        cfg.build(&mut quads);
+        quads.iter().flat_map(|quad| quad.to_le_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut tx = ReplayTransaction::parse(include_str!("../../replay/ff400.map")).unwrap();
+
+        let mut status = Ff400Status::default();
+        Ff400Protocol::read_status_by(&mut tx, &mut status, 100).unwrap();
+        assert_eq!(status.active_clk_src, Ff400ClkSrc::Internal);
+        assert_eq!(status.configured_clk_src, Ff400ClkSrc::Internal);
+        assert_eq!(status.configured_clk_rate, ClkNominalRate::R48000);
+
+        // The sequence of element writes; phantom powering, the same value again, then the
+        // level of line output.
+        let mut cfg = Ff400Config::default();
+        let mut cache = Ff400CfgCache::default();
+        cfg.analog_in.phantom_powering[0] = true;
+        assert!(Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+        let first = cfg_frame(&cfg);
+        assert!(!Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+        cfg.line_out_level = LineOutNominalLevel::Consumer;
+        assert!(Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+        let second = cfg_frame(&cfg);
+
+        let expected = vec![(CFG_OFFSET as u64, first), (CFG_OFFSET as u64, second)];
+        assert_eq!(tx.log(), &expected[..]);
+
+        // The status is not changed by the writes.
+        let mut s = Ff400Status::default();
+        Ff400Protocol::read_status_by(&mut tx, &mut s, 100).unwrap();
+        assert_eq!(s, status);
+    }
+
+    #[test]
+    fn test_analog_in_switch_isolation() {
+        let cfg = Ff400Config::default();
+        let mut base = [0u32; 3];
+        cfg.build(&mut base);
+
+        let switches: [(fn(&mut Ff400AnalogInConfig), u32); 6] = [
+            (|a| a.phantom_powering[0] = true, Q0_INPUT_0_POWERING_MASK),
+            (|a| a.phantom_powering[1] = true, Q0_INPUT_1_POWERING_MASK),
+            (|a| a.insts[0] = true, Q0_INPUT_2_INST_MASK),
+            (|a| a.insts[1] = true, Q0_INPUT_3_INST_MASK),
+            (|a| a.pad[0] = true, Q0_INPUT_2_PAD_MASK),
+            (|a| a.pad[1] = true, Q0_INPUT_3_PAD_MASK),
+        ];
+
+        switches.iter().for_each(|(flip, mask)| {
+            let mut target = cfg;
+            flip(&mut target.analog_in);
+            let mut quads = [0u32; 3];
+            target.build(&mut quads);
+
+            // Just the bit for the switch differs.
+            assert_eq!(quads[0] ^ base[0], *mask);
+            assert_eq!(quads[1], base[1]);
+            assert_eq!(quads[2], base[2]);
+
+            let mut parsed = Ff400Config::default();
+            parsed.parse(&quads);
+            assert_eq!(parsed, target);
+        });
+    }
+
+    #[test]
+    fn test_compensate_mixer_src_gain() {
+        let zero = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_ZERO;
+        let max = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_MAX;
+
+        // -6 dB and +6 dB against 0 dB.
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(zero, 0, 6), 0x4027);
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(0x4027, 6, 0), zero);
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(zero, 6, 0), 0xff65);
+
+        // No change.
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(zero, 20, 20), zero);
+
+        // Saturated at maximum.
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(zero, 7, 0), max);
+
+        // Muted source is kept.
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(0, 0, 65), 0);
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain(0, 65, 0), 0);
+
+        // The unsaturated value is recovered from the same base value.
+        let val = Ff400Protocol::compensate_mixer_src_gain_unsaturated(zero as i64, 7, 0);
+        assert!(val > max as i64);
+        assert_eq!(Ff400Protocol::saturate_mixer_src_gain(val), max);
+        assert_eq!(Ff400Protocol::compensate_mixer_src_gain_unsaturated(zero as i64, 7, 7),
+                   zero as i64);
+    }
+
+    #[test]
+    fn test_write_cfg_partial() {
+        let mut tx = MockTransaction::default();
+        let mut cache = Ff400CfgCache::default();
+
+        let mut cfg = Ff400Config::default();
+        cfg.analog_in.phantom_powering[0] = true;
+        cfg.line_out_level = LineOutNominalLevel::High;
+        assert!(Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+        assert_eq!(tx.writes.len(), 1);
+
+        // No transaction for the same configuration.
+        assert!(!Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+        assert_eq!(tx.writes.len(), 1);
+
+        // Toggle options just in the third quadlet.
+        let toggles: [fn(&mut Ff400Config); 4] = [
+            |c| c.spdif_out.emphasis = !c.spdif_out.emphasis,
+            |c| c.spdif_out.non_audio = !c.spdif_out.non_audio,
+            |c| c.word_out_single = !c.word_out_single,
+            |c| c.clk.primary_src = Ff400ClkSrc::Spdif,
+        ];
+        toggles.iter().for_each(|toggle| {
+            let (_, prev) = tx.writes.last().unwrap().clone();
+            toggle(&mut cfg);
+            assert!(Ff400Protocol::write_cfg_partial_by(&mut tx, &cfg, &mut cache, 100).unwrap());
+            let (offset, curr) = tx.writes.last().unwrap();
+            assert_eq!(*offset, CFG_OFFSET as u64);
+            assert_eq!(curr.len(), 12);
+            assert_eq!(&curr[..8], &prev[..8]);
+            assert_ne!(&curr[8..], &prev[8..]);
+        });
+    }
+
 }
@@ -4,6 +4,8 @@
 //! Protocol defined by RME GmbH for Fireface 800.
 use hinawa::{FwNode, FwReq};
 
+use fw_transaction::transaction_sync;
+
 use super::*;
 use crate::*;
 
@@ -61,7 +63,8 @@ impl RmeFormerOutputOperation for Ff800Protocol {
     ) -> Result<(), Error> {
         let mut raw = [0; 4];
         raw.copy_from_slice(&vol.to_le_bytes());
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteBlockRequest,
             (OUTPUT_OFFSET + ch * 4) as u64,
@@ -329,7 +332,8 @@ impl Ff800Protocol {
         timeout_ms: u32
     ) -> Result<(), Error> {
         let mut raw = [0; 8];
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             STATUS_OFFSET as u64,
@@ -674,29 +678,14 @@ impl Ff800Config {
             }
         }
 
-        if self.spdif_in.iface == SpdifIface::Optical {
-            quads[2] |= Q2_SPDIF_IN_IFACE_OPT_MASK;
-        }
-        if self.spdif_in.use_preemble {
-            quads[2] |= Q2_SPDIF_IN_USE_PREEMBLE;
-        }
+        self.spdif_in.build_former::<Self>(quads);
+        self.spdif_out.build::<Self>(quads);
 
         if self.opt_out_signal == OpticalOutputSignal::Spdif {
             quads[2] |= Q2_OPT_OUT_SIGNAL_MASK;
         }
-        if self.spdif_out.format == SpdifFormat::Professional {
-            quads[2] |= Q2_SPDIF_OUT_FMT_PRO_MASK;
-        }
-        if self.spdif_out.emphasis {
-            quads[2] |= Q2_SPDIF_OUT_EMPHASIS_MASK;
-        }
-        if self.spdif_out.non_audio {
-            quads[2] |= Q2_SPDIF_OUT_NON_AUDIO_MASK;
-        }
 
-        if self.word_out_single {
-            quads[2] |= Q2_WORD_OUT_SINGLE_SPEED_MASK;
-        }
+        build_word_out_single::<Self>(quads, self.word_out_single);
 
         if self.continue_at_errors {
             quads[2] |= Q2_CONTINUE_AT_ERRORS;
@@ -718,20 +707,8 @@ impl Ff800Config {
             _ => unreachable!(),
         };
 
-        self.spdif_in.iface = if quads[2] & Q2_SPDIF_IN_IFACE_OPT_MASK > 0 {
-            SpdifIface::Optical
-        } else {
-            SpdifIface::Coaxial
-        };
-        self.spdif_in.use_preemble = quads[2] & Q2_SPDIF_IN_USE_PREEMBLE > 0;
-
-        self.spdif_out.format = if quads[2] & Q2_SPDIF_OUT_FMT_PRO_MASK > 0 {
-            SpdifFormat::Professional
-        } else {
-            SpdifFormat::Consumer
-        };
-        self.spdif_out.emphasis = quads[2] & Q2_SPDIF_OUT_EMPHASIS_MASK > 0;
-        self.spdif_out.non_audio = quads[2] & Q2_SPDIF_OUT_NON_AUDIO_MASK > 0;
+        self.spdif_in.parse_former::<Self>(quads);
+        self.spdif_out.parse::<Self>(quads);
 
         self.opt_out_signal = if quads[2] & Q2_OPT_OUT_SIGNAL_MASK > 0 {
             OpticalOutputSignal::Spdif
@@ -739,7 +716,7 @@ impl Ff800Config {
             OpticalOutputSignal::Adat
         };
 
-        self.word_out_single = quads[2] & Q2_WORD_OUT_SINGLE_SPEED_MASK > 0;
+        self.word_out_single = parse_word_out_single::<Self>(quads);
         self.continue_at_errors = quads[2] & Q2_CONTINUE_AT_ERRORS > 0;
     }
 
@@ -754,6 +731,15 @@ impl Ff800Config {
     }
 }
 
+impl RmeFormerDigitalIfaceCfgLayout for Ff800Config {
+    const SPDIF_IN_IFACE_OPT: (usize, u32) = (2, Q2_SPDIF_IN_IFACE_OPT_MASK);
+    const SPDIF_IN_USE_PREEMBLE: (usize, u32) = (2, Q2_SPDIF_IN_USE_PREEMBLE);
+    const SPDIF_OUT_FMT_PRO: (usize, u32) = (2, Q2_SPDIF_OUT_FMT_PRO_MASK);
+    const SPDIF_OUT_EMPHASIS: (usize, u32) = (2, Q2_SPDIF_OUT_EMPHASIS_MASK);
+    const SPDIF_OUT_NON_AUDIO: (usize, u32) = (2, Q2_SPDIF_OUT_NON_AUDIO_MASK);
+    const WORD_OUT_SINGLE_SPEED: (usize, u32) = (2, Q2_WORD_OUT_SINGLE_SPEED_MASK);
+}
+
 impl Ff800Protocol {
     pub fn write_cfg(
         req: &mut FwReq,
@@ -771,7 +757,8 @@ impl Ff800Protocol {
                 let pos = i * 4;
                 raw[pos..(pos + 4)].copy_from_slice(&quad.to_le_bytes())
             });
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteBlockRequest,
             CFG_OFFSET as u64,
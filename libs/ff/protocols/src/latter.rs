@@ -7,7 +7,9 @@ pub mod ucx;
 pub mod ff802;
 
 use glib::Error;
-use hinawa::{FwNode, FwTcode, FwReq, FwReqExtManual};
+use hinawa::{FwNode, FwTcode, FwReq};
+
+use fw_transaction::transaction_sync;
 
 use super::*;
 
@@ -81,7 +83,8 @@ pub trait RmeFfLatterConfigOperation<U: RmeFfLatterRegisterValueOperation> {
 
         let mut raw = [0; 4];
         raw.copy_from_slice(&quad.to_le_bytes());
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::WriteQuadletRequest,
             CFG_OFFSET as u64,
@@ -163,7 +166,8 @@ pub trait RmeFfLatterStatusOperation<U>
         timeout_ms: u32
     ) -> Result<(), Error> {
         let mut raw = [0; 4];
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadQuadletRequest,
             DSP_OFFSET as u64,
@@ -236,7 +240,8 @@ pub trait RmeFfLatterMeterOperation {
     ) -> Result<(), Error> {
         (0..5).try_for_each(|_| {
             let mut raw = vec![0;392];
-            req.transaction_sync(
+            transaction_sync(
+                req,
                 node,
                 FwTcode::ReadBlockRequest,
                 METER_OFFSET as u64,
@@ -362,7 +367,8 @@ fn write_dsp_cmd(
         cmd |= ODD_PARITY_FLAG;
     }
     let mut raw = cmd.to_le_bytes();
-    req.transaction_sync(
+    transaction_sync(
+        req,
         node,
         FwTcode::WriteQuadletRequest,
         DSP_OFFSET as u64,
@@ -731,6 +737,9 @@ impl From<LineOutNominalLevel> for i16 {
 }
 
 /// The structure to represent state of inputs.
+///
+/// NOTE: The command to mute each output is not identified yet. Neither the protocol nor the
+/// runtime emulates it by the volume, thus the volume is always kept as user configured.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
 pub struct FfLatterOutputState {
     /// The level of volume. Each value is between -650 (0xfd76) and 60 (0x003c) to represent
@@ -11,6 +11,9 @@ pub mod latter;
 
 use ieee1212_config_rom::{*, entry::*};
 
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 const RME_OUI: u32 = 0x00000a35;
 
 /// The trait to represent parser of configuration rom for RME Fireface series.
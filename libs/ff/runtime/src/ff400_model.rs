@@ -2,18 +2,24 @@
 // Copyright (c) 2021 Takashi Sakamoto
 use glib::{Error, FileError};
 
-use hinawa::FwReq;
+use hinawa::{FwNode, FwReq};
 use hinawa::{SndUnit, SndUnitExt};
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual};
 
 use alsa_ctl_tlv_codec::items::DbInterval;
 
-use core::card_cntr::*;
+use core::{burst::BatchedWrite, card_cntr::*};
 use core::elem_value_accessor::*;
+use core::snapshot::*;
+use core::monitor::*;
+use core::log::*;
+use core::log_warn;
 
 use ff_protocols::{*, former::{*, ff400::*}};
 
+use fw_transaction::FwTransaction;
+
 use super::former_ctls::*;
 
 use super::model::*;
@@ -24,9 +30,13 @@ pub struct Ff400Model{
     meter_ctl: MeterCtl,
     out_ctl: OutputCtl,
     input_gain_ctl: InputGainCtl,
+    linked_gain_ctl: LinkedGainCtl,
     mixer_ctl: MixerCtl,
+    output_src_ctl: OutputSourceCtl,
     status_ctl: StatusCtl,
     cfg_ctl: CfgCtl,
+    snapshot_ctl: StateSnapshotCtl,
+    rate_change: FormerRateChangeState,
 }
 
 const TIMEOUT_MS: u32 = 100;
@@ -36,10 +46,18 @@ impl CtlModel<SndUnit> for Ff400Model {
         self.meter_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)
             .map(|mut elem_id_list| self.meter_ctl.1.append(&mut elem_id_list))?;
         self.out_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
+        self.load_monitor_section(card_cntr)?;
         self.mixer_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
+        self.output_src_ctl.load(card_cntr)?;
         self.input_gain_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
-        self.status_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
-        self.cfg_ctl.load(unit, &mut self.req, &self.status_ctl.status, card_cntr, TIMEOUT_MS)?;
+        self.linked_gain_ctl.load(card_cntr)?;
+        self.status_ctl.load(&mut (&self.req, &unit.get_node()), card_cntr, TIMEOUT_MS)?;
+        let rate = self.status_ctl.status.configured_clk_rate;
+        self.mixer_ctl.update_rate(unit, &mut self.req, rate, TIMEOUT_MS)?;
+        self.meter_ctl.update_rate(rate);
+        self.cfg_ctl.load(&mut (&self.req, &unit.get_node()), &self.status_ctl.status, card_cntr,
+                          TIMEOUT_MS)?;
+        self.snapshot_ctl.load(card_cntr)?;
         Ok(())
     }
 
@@ -48,12 +66,22 @@ impl CtlModel<SndUnit> for Ff400Model {
     {
         if self.out_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_monitor_section(elem_id, elem_value)? {
+            Ok(true)
         } else if self.mixer_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.output_src_ctl.read(elem_id, elem_value, &self.mixer_ctl)? {
+            Ok(true)
         } else if self.input_gain_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.linked_gain_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else if self.cfg_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.meter_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.snapshot_ctl.read(elem_id, elem_value, &self.snapshot())? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -62,13 +90,46 @@ impl CtlModel<SndUnit> for Ff400Model {
     fn write(&mut self, unit: &mut SndUnit, elem_id: &ElemId, old: &ElemValue, new: &ElemValue)
         -> Result<bool, Error>
     {
+        let mic_gains = self.input_gain_ctl.status.mic;
+
+        // The status is read again for the parameters which depend on it, since the polling is
+        // not so frequent.
+        if CfgCtl::STATUS_DEPENDENT_ELEM_NAMES.contains(&elem_id.get_name().as_str()) {
+            self.measure_status(unit)?;
+            self.status_ctl.request_refresh();
+        }
+
         if self.out_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.snapshot_ctl.invalidate();
+            Ok(true)
+        } else if self.write_monitor_section(unit, elem_id, new)? {
             Ok(true)
         } else if self.mixer_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.output_src_ctl.follow_mixer();
+            Ok(true)
+        } else if self.output_src_ctl.write(unit, &mut self.req, elem_id, new, &mut self.mixer_ctl,
+                                            TIMEOUT_MS)? {
             Ok(true)
         } else if self.input_gain_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.linked_gain_ctl.follow(unit, &mut self.req, &mic_gains,
+                                        &self.input_gain_ctl.status.mic, &mut self.mixer_ctl,
+                                        TIMEOUT_MS)?;
+            if self.linked_gain_ctl.has_changed_mixers() {
+                self.output_src_ctl.follow_mixer();
+            }
+            Ok(true)
+        } else if self.linked_gain_ctl.write(elem_id, new)? {
             Ok(true)
-        } else if self.cfg_ctl.write(unit, &mut self.req, elem_id, old, new, TIMEOUT_MS)? {
+        } else if self.cfg_ctl.write(&mut (&self.req, &unit.get_node()), &self.status_ctl.status,
+                                     self.status_ctl.strict_clk_switch, elem_id, old, new,
+                                     TIMEOUT_MS)? {
+            // The status can be changed by the configuration.
+            self.status_ctl.request_refresh();
+            self.snapshot_ctl.invalidate();
+            Ok(true)
+        } else if self.status_ctl.write(elem_id, new)? {
+            Ok(true)
+        } else if self.meter_ctl.write(elem_id, new)? {
             Ok(true)
         } else {
             Ok(false)
@@ -79,13 +140,11 @@ impl CtlModel<SndUnit> for Ff400Model {
 impl MeasureModel<SndUnit> for Ff400Model {
     fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.meter_ctl.1);
-        elem_id_list.extend_from_slice(&self.status_ctl.measured_elem_list);
+        elem_id_list.extend_from_slice(&self.status_ctl.notified_elem_list);
     }
 
     fn measure_states(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
-        self.meter_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        self.status_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        Ok(())
+        self.meter_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)
     }
 
     fn measure_elem(&mut self, _: &SndUnit, elem_id: &ElemId, elem_value: &mut ElemValue)
@@ -101,8 +160,185 @@ impl MeasureModel<SndUnit> for Ff400Model {
     }
 }
 
+impl NotifyModel<SndUnit, bool> for Ff400Model {
+    fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.status_ctl.notified_elem_list);
+        elem_id_list.extend_from_slice(&self.cfg_ctl.1);
+        elem_id_list.extend_from_slice(&self.snapshot_ctl.elem_id_list);
+        elem_id_list.extend_from_slice(&self.input_gain_ctl.notified_elem_list);
+        elem_id_list.extend_from_slice(&self.linked_gain_ctl.mixer_elem_list);
+        elem_id_list.extend_from_slice(&self.output_src_ctl.notified_elem_list);
+    }
+
+    fn parse_notification(&mut self, unit: &mut SndUnit, &refresh: &bool) -> Result<(), Error> {
+        if refresh {
+            self.measure_status(unit)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_notified_elem(&mut self, _: &SndUnit, elem_id: &ElemId, elem_value: &mut ElemValue)
+        -> Result<bool, Error>
+    {
+        if self.status_ctl.measure_elem(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.cfg_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.refresh_snapshot(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.input_gain_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.linked_gain_ctl.read_changed_mixer(elem_id, elem_value, &mut self.mixer_ctl)? {
+            Ok(true)
+        } else if self.output_src_ctl.read_changed_elem(elem_id, elem_value, &mut self.mixer_ctl)? {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl Ff400Model {
+    // The status is read by the polling and the request. The configuration is rebuilt just when
+    // the rate is changed, and the snapshot is invalidated just when the status or the
+    // configuration is changed.
+    fn measure_status(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        self.update_status(unit, |ctl, req, node| {
+            ctl.measure_states(&mut (req, node), TIMEOUT_MS).map(|_| true)
+        })
+            .map(|_| ())
+    }
+
+    /// Read whole status at once for the request by the trigger element or the change of
+    /// configuration, then return whether the status is read.
+    pub fn refresh_status(&mut self, unit: &mut SndUnit) -> Result<bool, Error> {
+        self.update_status(unit, |ctl, req, node| ctl.refresh(&mut (req, node), TIMEOUT_MS))
+    }
+
+    fn update_status<F>(&mut self, unit: &mut SndUnit, read: F) -> Result<bool, Error>
+        where F: FnOnce(&mut StatusCtl, &FwReq, &FwNode) -> Result<bool, Error>
+    {
+        let status = self.status_ctl.status;
+        let cfg = self.cfg_ctl.0;
+        let rates = self.clk_rates();
+        let node = unit.get_node();
+        if !read(&mut self.status_ctl, &self.req, &node)? {
+            return Ok(false);
+        }
+        self.detect_rate_change(unit, &rates)?;
+        if status != self.status_ctl.status || cfg != self.cfg_ctl.0 {
+            self.snapshot_ctl.invalidate();
+        }
+        Ok(true)
+    }
+
+    fn snapshot(&self) -> Ff400Snapshot {
+        Ff400Snapshot {
+            status: &self.status_ctl.status,
+            cfg: &self.cfg_ctl.0,
+            vols: self.out_ctl.1.requested(),
+        }
+    }
+
+    fn refresh_snapshot(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue)
+        -> Result<bool, Error>
+    {
+        if self.snapshot_ctl.take_stale(elem_id) {
+            self.snapshot_ctl.read(elem_id, elem_value, &self.snapshot())
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Take the request to notify the input gains and the mixer source gains changed by the link,
+    /// and the elements changed by the selection of output source.
+    pub fn take_gain_notification(&mut self) -> bool {
+        self.input_gain_ctl.take_notification() || self.linked_gain_ctl.has_changed_mixers() ||
+            self.output_src_ctl.has_changed_elems()
+    }
+}
+
+impl FormerRateChangeOperation for Ff400Model {
+    const RATE_AFFECTED_ELEM_NAMES: &'static [&'static str] = CfgCtl::STATUS_DERIVED_ELEM_NAMES;
+
+    fn rate_change_state_mut(&mut self) -> &mut FormerRateChangeState {
+        &mut self.rate_change
+    }
+
+    fn clk_rates(&self) -> (Option<ClkNominalRate>, ClkNominalRate) {
+        (self.status_ctl.status.external_clk_rate, self.status_ctl.status.configured_clk_rate)
+    }
+
+    fn reconcile_rate(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        self.cfg_ctl.reinit(&mut (&self.req, &unit.get_node()), &self.status_ctl.status,
+                            TIMEOUT_MS)?;
+        let rate = self.status_ctl.status.configured_clk_rate;
+        self.mixer_ctl.update_rate(unit, &mut self.req, rate, TIMEOUT_MS)?;
+        self.meter_ctl.update_rate(rate);
+        self.snapshot_ctl.invalidate();
+        Ok(())
+    }
+}
+
+impl MonitorSection<SndUnit> for Ff400Model {
+    fn monitor_state(&self) -> &MonitorSectionState {
+        &self.out_ctl.1.state
+    }
+
+    fn write_monitor_state(&mut self, unit: &mut SndUnit, state: MonitorSectionState)
+        -> Result<(), Error>
+    {
+        self.out_ctl.write_monitor_state(unit, &mut self.req, state, TIMEOUT_MS)?;
+        self.snapshot_ctl.invalidate();
+        Ok(())
+    }
+}
+
+impl BatchedWrite<SndUnit> for Ff400Model {
+    fn begin_batch(&mut self) {
+        self.mixer_ctl.begin_batch();
+    }
+
+    fn flush_batch(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        self.mixer_ctl.flush_batch(unit, &mut self.req, TIMEOUT_MS)
+    }
+}
+
+struct Ff400Snapshot<'a> {
+    status: &'a Ff400Status,
+    cfg: &'a Ff400Config,
+    // The volumes requested by user, without the attenuation by monitor section.
+    vols: &'a [i32],
+}
+
+impl<'a> StateSnapshotProvider for Ff400Snapshot<'a> {
+    fn build_snapshot(&self, builder: &mut StateSnapshotBuilder) {
+        let status = self.status;
+        let cfg = self.cfg;
+        builder
+            .string("clock.primary-source", &clk_src_to_string(&cfg.clk.primary_src))
+            .string("clock.active-source", &clk_src_to_string(&status.active_clk_src))
+            .string("clock.rate", &clk_nominal_rate_to_string(&status.configured_clk_rate))
+            .string(
+                "clock.external-rate",
+                &optional_clk_nominal_rate_to_string(&status.external_clk_rate),
+            )
+            .string("clock.spdif-rate", &optional_clk_nominal_rate_to_string(&status.spdif_rate))
+            .bool_array("lock.spdif-adat-wc", &[status.lock.spdif, status.lock.adat,
+                                                 status.lock.word_clock])
+            .bool_array("sync.spdif-adat-wc", &[status.sync.spdif, status.sync.adat,
+                                                 status.sync.word_clock])
+            .string("line-output-level", &line_out_nominal_level_to_string(&cfg.line_out_level))
+            .string("headphone-output-level", &line_out_nominal_level_to_string(&cfg.hp_out_level))
+            .string("spdif-input-interface", &spdif_iface_to_string(&cfg.spdif_in.iface))
+            .string("optical-output-signal", &optical_output_signal_to_string(&cfg.opt_out_signal))
+            .int_array("output-volume", self.vols);
+    }
+}
+
 #[derive(Default, Debug)]
-struct MeterCtl(FormerMeterState, Vec<ElemId>);
+struct MeterCtl(FormerMeterState, Vec<ElemId>, FormerMeterBallistics);
 
 impl FormerMeterCtlOperation<Ff400Protocol> for MeterCtl {
     fn meter(&self) -> &FormerMeterState {
@@ -113,10 +349,17 @@ impl FormerMeterCtlOperation<Ff400Protocol> for MeterCtl {
         &mut self.0
     }
 
+    fn ballistics(&self) -> &FormerMeterBallistics {
+        &self.2
+    }
+
+    fn ballistics_mut(&mut self) -> &mut FormerMeterBallistics {
+        &mut self.2
+    }
 }
 
 #[derive(Default, Debug)]
-struct OutputCtl(FormerOutputVolumeState);
+struct OutputCtl(FormerOutputVolumeState, FormerOutputMonitor);
 
 impl FormerOutputCtlOperation<Ff400Protocol> for OutputCtl {
     fn state(&self) -> &FormerOutputVolumeState {
@@ -126,10 +369,18 @@ impl FormerOutputCtlOperation<Ff400Protocol> for OutputCtl {
     fn state_mut(&mut self) -> &mut FormerOutputVolumeState {
         &mut self.0
     }
+
+    fn monitor(&self) -> &FormerOutputMonitor {
+        &self.1
+    }
+
+    fn monitor_mut(&mut self) -> &mut FormerOutputMonitor {
+        &mut self.1
+    }
 }
 
 #[derive(Default, Debug)]
-struct MixerCtl(FormerMixerState);
+struct MixerCtl(FormerMixerState, FormerMixerBatch);
 
 impl FormerMixerCtlOperation<Ff400Protocol> for MixerCtl {
     fn state(&self) -> &FormerMixerState {
@@ -139,15 +390,48 @@ impl FormerMixerCtlOperation<Ff400Protocol> for MixerCtl {
     fn state_mut(&mut self) -> &mut FormerMixerState {
         &mut self.0
     }
+
+    fn batch(&self) -> &FormerMixerBatch {
+        &self.1
+    }
+
+    fn batch_mut(&mut self) -> &mut FormerMixerBatch {
+        &mut self.1
+    }
 }
 
 #[derive(Default, Debug)]
 struct InputGainCtl{
     status: Ff400InputGainStatus,
+    // Whether the gains of mic and line inputs are ganged.
+    ganged: [bool; 2],
+    notified_elem_list: Vec<ElemId>,
+    notify: bool,
 }
 
 const MIC_GAIN_NAME: &str = "mic-input-gain";
 const LINE_GAIN_NAME: &str = "line-input-gain";
+const MIC_GAIN_GANG_NAME: &str = "mic-input-stereo-gang";
+const LINE_GAIN_GANG_NAME: &str = "line-input-stereo-gang";
+
+// The gain of channel changed from the cache is applied to both channels. The first channel
+// takes precedence when both channels are changed at once.
+fn gang_gains(cache: &[i8], gains: &mut [i8]) {
+    if gains[0] != cache[0] {
+        gains[1] = gains[0];
+    } else if gains[1] != cache[1] {
+        gains[0] = gains[1];
+    }
+}
+
+// The second channel is snapped to the first one when the link is enabled with unequal gains.
+fn snap_gains(cache: &[i8]) -> Option<Vec<i8>> {
+    if cache[0] != cache[1] {
+        Some(vec![cache[0]; cache.len()])
+    } else {
+        None
+    }
+}
 
 impl InputGainCtl {
     const MIC_GAIN_MIN: i32 = 0;
@@ -177,9 +461,26 @@ impl InputGainCtl {
         card_cntr.add_int_elems(&elem_id, 1, Self::LINE_GAIN_MIN, Self::LINE_GAIN_MAX, Self::LINE_GAIN_STEP,
                                 2, Some(&Vec::<u32>::from(&Self::LINE_GAIN_TLV)), true)?;
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, MIC_GAIN_GANG_NAME, 0);
+        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, LINE_GAIN_GANG_NAME, 0);
+        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+        // The gains are changed by the link.
+        self.notified_elem_list = [MIC_GAIN_NAME, LINE_GAIN_NAME]
+            .iter()
+            .map(|name| ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0))
+            .collect();
+
         Ok(())
     }
 
+    // The request to notify the gains changed by the link.
+    fn take_notification(&mut self) -> bool {
+        std::mem::take(&mut self.notify)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MIC_GAIN_NAME => {
@@ -196,6 +497,14 @@ impl InputGainCtl {
                 elem_value.set_int(&vals);
                 Ok(true)
             }
+            MIC_GAIN_GANG_NAME => {
+                elem_value.set_bool(&[self.ganged[0]]);
+                Ok(true)
+            }
+            LINE_GAIN_GANG_NAME => {
+                elem_value.set_bool(&[self.ganged[1]]);
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
@@ -212,9 +521,13 @@ impl InputGainCtl {
             MIC_GAIN_NAME => {
                 let mut vals = [0;2];
                 elem_value.get_int(&mut vals);
-                let gains: Vec<i8> = vals.iter()
+                let mut gains: Vec<i8> = vals.iter()
                     .map(|&val| val as i8)
                     .collect();
+                if self.ganged[0] {
+                    gang_gains(&self.status.mic, &mut gains);
+                    self.notify |= gains.iter().zip(vals.iter()).any(|(&g, &v)| g as i32 != v);
+                }
                 Ff400Protocol::write_input_mic_gains(
                     req,
                     &mut unit.get_node(),
@@ -227,9 +540,13 @@ impl InputGainCtl {
             LINE_GAIN_NAME => {
                 let mut vals = [0;2];
                 elem_value.get_int(&mut vals);
-                let gains: Vec<i8> = vals.iter()
+                let mut gains: Vec<i8> = vals.iter()
                     .map(|&val| val as i8)
                     .collect();
+                if self.ganged[1] {
+                    gang_gains(&self.status.line, &mut gains);
+                    self.notify |= gains.iter().zip(vals.iter()).any(|(&g, &v)| g as i32 != v);
+                }
                 Ff400Protocol::write_input_line_gains(
                     req,
                     &mut unit.get_node(),
@@ -239,9 +556,355 @@ impl InputGainCtl {
                 )
                     .map(|_| true)
             }
+            MIC_GAIN_GANG_NAME => {
+                let mut vals = [false];
+                elem_value.get_bool(&mut vals);
+                if vals[0] {
+                    if let Some(gains) = snap_gains(&self.status.mic) {
+                        Ff400Protocol::write_input_mic_gains(
+                            req,
+                            &mut unit.get_node(),
+                            &mut self.status,
+                            &gains,
+                            timeout_ms
+                        )?;
+                        self.notify = true;
+                    }
+                }
+                self.ganged[0] = vals[0];
+                Ok(true)
+            }
+            LINE_GAIN_GANG_NAME => {
+                let mut vals = [false];
+                elem_value.get_bool(&mut vals);
+                if vals[0] {
+                    if let Some(gains) = snap_gains(&self.status.line) {
+                        Ff400Protocol::write_input_line_gains(
+                            req,
+                            &mut unit.get_node(),
+                            &mut self.status,
+                            &gains,
+                            timeout_ms
+                        )?;
+                        self.notify = true;
+                    }
+                }
+                self.ganged[1] = vals[0];
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+// The base of compensation for mixer source gain, kept until the gain is changed by the other
+// than the link.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+struct LinkedGainBase {
+    gain: i32,
+    amp: i8,
+    applied: i32,
+}
+
+#[derive(Default, Debug)]
+struct LinkedGainCtl{
+    linked: [bool; 2],
+    mixer_elem_list: Vec<ElemId>,
+    changed_mixers: Vec<usize>,
+    bases: Vec<[Option<LinkedGainBase>; 2]>,
+}
+
+const MIC_GAIN_LINKED_NAME: &str = "mic-input-gain-linked";
+
+impl LinkedGainCtl {
+    fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, MIC_GAIN_LINKED_NAME, 0);
+        let _ = card_cntr.add_bool_elems(&elem_id, 1, 2, true)?;
+
+        self.mixer_elem_list = (0..Ff400Protocol::DST_COUNT)
+            .map(|i| ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, ANALOG_SRC_GAIN_NAME, i as u32))
+            .collect();
+        self.bases = vec![Default::default(); Ff400Protocol::DST_COUNT];
+
+        Ok(())
+    }
+
+    fn read(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            MIC_GAIN_LINKED_NAME => {
+                elem_value.set_bool(&self.linked);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn write(&mut self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            MIC_GAIN_LINKED_NAME => {
+                // Both gains are left as they are.
+                elem_value.get_bool(&mut self.linked);
+                let linked = self.linked;
+                self.bases.iter_mut()
+                    .for_each(|bases| {
+                        bases.iter_mut()
+                            .zip(linked.iter())
+                            .filter(|(_, &l)| !l)
+                            .for_each(|(base, _)| *base = None);
+                    });
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Change source gains of mixers for linked mic inputs against the change of amplifier gains.
+    /// The gains are computed from the base without saturation so that the link is reversible.
+    fn follow(
+        &mut self,
+        unit: &mut SndUnit,
+        req: &mut FwReq,
+        old: &[i8],
+        new: &[i8],
+        mixer_ctl: &mut MixerCtl,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let linked = self.linked;
+        let state = mixer_ctl.state_mut();
+        let changed_mixers = &mut self.changed_mixers;
+        let bases = &mut self.bases;
+
+        (0..state.0.len())
+            .try_for_each(|i| {
+                let mut gains = state.0[i].analog_gains.clone();
+                linked.iter()
+                    .zip(old.iter().zip(new.iter()))
+                    .enumerate()
+                    .filter(|&(_, (&l, (o, n)))| l && o != n)
+                    .for_each(|(ch, (_, (&o, &n)))| {
+                        gains[ch] = compensate_linked_gain(&mut bases[i][ch], gains[ch], o, n);
+                    });
+
+                if gains == state.0[i].analog_gains {
+                    return Ok(());
+                }
+
+                Ff400Protocol::write_mixer_analog_gains(
+                    req,
+                    &mut unit.get_node(),
+                    state,
+                    i,
+                    &gains,
+                    timeout_ms
+                )
+                    .map(|_| {
+                        if !changed_mixers.contains(&i) {
+                            changed_mixers.push(i);
+                        }
+                    })
+            })
+    }
+
+    fn has_changed_mixers(&self) -> bool {
+        !self.changed_mixers.is_empty()
+    }
+
+    /// Report the mixer changed by the link.
+    fn read_changed_mixer(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+        mixer_ctl: &mut MixerCtl
+    ) -> Result<bool, Error> {
+        if elem_id.get_name().as_str() != ANALOG_SRC_GAIN_NAME {
+            return Ok(false);
+        }
+
+        let index = elem_id.get_index() as usize;
+        match self.changed_mixers.iter().position(|&i| i == index) {
+            Some(pos) => {
+                self.changed_mixers.remove(pos);
+                mixer_ctl.read(elem_id, elem_value)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+// The base is renewed when the current gain is not the one applied by the link, since it was
+// changed by the other.
+fn compensate_linked_gain(base: &mut Option<LinkedGainBase>, gain: i32, old: i8, new: i8) -> i32 {
+    let b = match *base {
+        Some(b) if b.applied == gain => b,
+        _ => LinkedGainBase { gain, amp: old, applied: gain },
+    };
+    let val = Ff400Protocol::compensate_mixer_src_gain_unsaturated(b.gain as i64, b.amp, new);
+    let applied = Ff400Protocol::saturate_mixer_src_gain(val);
+    *base = Some(LinkedGainBase { applied, ..b });
+    applied
+}
+
+#[derive(Default, Debug)]
+struct OutputSourceCtl{
+    notified_elem_list: Vec<ElemId>,
+    changed_elem_list: Vec<ElemId>,
+}
+
+const OUTPUT_SRC_NAME: &str = "output-source";
+
+impl OutputSourceCtl {
+    // The sources are the pairs of inputs to mixer and stream playback. Each output takes the
+    // channel in the pair according to its position, thus the source of mixer is in the pair at
+    // the same index.
+    fn src_labels() -> Vec<String> {
+        let mut labels = Vec::new();
+        [
+            ("Analog", Ff400Protocol::ANALOG_INPUT_COUNT),
+            ("S/PDIF", Ff400Protocol::SPDIF_INPUT_COUNT),
+            ("ADAT", Ff400Protocol::ADAT_INPUT_COUNT),
+            ("Stream", Ff400Protocol::STREAM_INPUT_COUNT),
+        ].iter()
+            .for_each(|&(name, count)| {
+                (0..count).step_by(2).for_each(|i| labels.push(format!("{}-{}/{}", name, i + 1, i + 2)));
+            });
+        labels.push("Custom".to_string());
+        labels
+    }
+
+    fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        let labels = Self::src_labels();
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, OUTPUT_SRC_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, Ff400Protocol::DST_COUNT, &labels, None, true)
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
+
+        // The gains of mixer changed by the selection are reported as well.
+        [
+            ANALOG_SRC_GAIN_NAME,
+            SPDIF_SRC_GAIN_NAME,
+            ADAT_SRC_GAIN_NAME,
+            STREAM_SRC_GAIN_NAME,
+        ].iter()
+            .for_each(|&name| {
+                (0..Ff400Protocol::DST_COUNT).for_each(|i| {
+                    let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, name, i as u32);
+                    self.notified_elem_list.push(elem_id);
+                });
+            });
+
+        Ok(())
+    }
+
+    /// The source is detected by the gains of mixer. The last entry is for the other combinations.
+    fn read(&self, elem_id: &ElemId, elem_value: &mut ElemValue, mixer_ctl: &MixerCtl)
+        -> Result<bool, Error>
+    {
+        match elem_id.get_name().as_str() {
+            OUTPUT_SRC_NAME => {
+                let vals: Vec<u32> = (0..Ff400Protocol::DST_COUNT)
+                    .map(|i| Self::detect_pair(mixer_ctl.state(), i) as u32)
+                    .collect();
+                elem_value.set_enum(&vals);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn detect_pair(state: &FormerMixerState, mixer: usize) -> usize {
+        let custom = Self::src_labels().len() - 1;
+        Ff400Protocol::exclusive_mixer_src(state, mixer)
+            .filter(|&src| src % 2 == mixer % 2)
+            .map(|src| src / 2)
+            .unwrap_or(custom)
+    }
+
+    fn write(
+        &mut self,
+        unit: &mut SndUnit,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &ElemValue,
+        mixer_ctl: &mut MixerCtl,
+        timeout_ms: u32
+    ) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            OUTPUT_SRC_NAME => {
+                let custom = Self::src_labels().len() - 1;
+                let mut vals = vec![0; Ff400Protocol::DST_COUNT];
+                elem_value.get_enum(&mut vals);
+
+                // The selection of custom leaves the gains as they are.
+                let selected: Vec<(usize, usize)> = vals.iter()
+                    .enumerate()
+                    .map(|(i, &val)| (i, val as usize))
+                    .filter(|&(i, pair)| {
+                        pair != custom && pair != Self::detect_pair(mixer_ctl.state(), i)
+                    })
+                    .collect();
+
+                let notified_elem_list = &self.notified_elem_list;
+                let changed_elem_list = &mut self.changed_elem_list;
+                selected.iter()
+                    .try_for_each(|&(i, pair)| {
+                        Ff400Protocol::write_mixer_exclusive_src(
+                            req,
+                            &mut unit.get_node(),
+                            mixer_ctl.state_mut(),
+                            i,
+                            pair * 2 + i % 2,
+                            timeout_ms
+                        )
+                            .map(|_| {
+                                notified_elem_list.iter()
+                                    .filter(|elem_id| {
+                                        elem_id.get_name().as_str() != OUTPUT_SRC_NAME &&
+                                        elem_id.get_index() as usize == i
+                                    })
+                                    .for_each(|elem_id| {
+                                        if !changed_elem_list.contains(elem_id) {
+                                            changed_elem_list.push(elem_id.clone());
+                                        }
+                                    });
+                            })
+                    })
+                    .map(|_| true)
+            }
             _ => Ok(false),
         }
     }
+
+    /// Request to report the source detected again, after the gains of mixer are changed by the
+    /// others.
+    fn follow_mixer(&mut self) {
+        let elem_id = &self.notified_elem_list[0];
+        if !self.changed_elem_list.contains(elem_id) {
+            self.changed_elem_list.push(elem_id.clone());
+        }
+    }
+
+    fn has_changed_elems(&self) -> bool {
+        !self.changed_elem_list.is_empty()
+    }
+
+    /// Report the source detected by the gains of mixer, and the gains changed by the selection.
+    fn read_changed_elem(
+        &mut self,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+        mixer_ctl: &mut MixerCtl
+    ) -> Result<bool, Error> {
+        match self.changed_elem_list.iter().position(|e| e == elem_id) {
+            Some(pos) => {
+                self.changed_elem_list.remove(pos);
+                if self.read(elem_id, elem_value, mixer_ctl)? {
+                    Ok(true)
+                } else {
+                    mixer_ctl.read(elem_id, elem_value)
+                }
+            }
+            None => Ok(false),
+        }
+    }
 }
 
 fn clk_src_to_string(src: &Ff400ClkSrc) -> String {
@@ -254,24 +917,29 @@ fn clk_src_to_string(src: &Ff400ClkSrc) -> String {
     }.to_string()
 }
 
-fn update_cfg<F>(
-    unit: &mut SndUnit,
-    req: &mut FwReq,
+fn update_cfg<T, F>(
+    tx: &mut T,
     cfg: &mut Ff400Config,
+    cfg_cache: &mut Ff400CfgCache,
     timeout_ms: u32,
     cb: F
 ) -> Result<(), Error>
-    where F: Fn(&mut Ff400Config) -> Result<(), Error>,
+    where T: FwTransaction,
+          F: Fn(&mut Ff400Config) -> Result<(), Error>,
 {
     let mut cache = cfg.clone();
     cb(&mut cache)?;
-    Ff400Protocol::write_cfg(req, &mut unit.get_node(), &cache, timeout_ms).map(|_| *cfg = cache)
+    Ff400Protocol::write_cfg_partial_by(tx, &cache, cfg_cache, timeout_ms)
+        .map(|_| *cfg = cache)
 }
 
 #[derive(Default, Debug)]
 struct StatusCtl{
     status: Ff400Status,
-    measured_elem_list: Vec<ElemId>,
+    // The elements are notified by the polling regardless of metering.
+    notified_elem_list: Vec<ElemId>,
+    refresh_requested: bool,
+    strict_clk_switch: bool,
 }
 
 const EXT_SRC_LOCK_NAME: &'static str = "external-source-lock";
@@ -279,6 +947,14 @@ const EXT_SRC_SYNC_NAME: &'static str = "external-source-sync";
 const SPDIF_SRC_RATE_NAME: &'static str = "spdif-source-rate";
 const EXT_SRC_RATE_NAME: &'static str = "external-source-rate";
 const ACTIVE_CLK_SRC_NAME: &'static str = "active-clock-source";
+const STATUS_REFRESH_NAME: &'static str = "status-refresh";
+const WORD_CLOCK_LOCK_NAME: &str = "wc-lock";
+const WORD_CLOCK_SYNC_NAME: &str = "wc-sync";
+const SPDIF_LOCK_NAME: &str = "spdif-lock";
+const SPDIF_SYNC_NAME: &str = "spdif-sync";
+const ADAT_LOCK_NAME: &str = "adat-lock";
+const ADAT_SYNC_NAME: &str = "adat-sync";
+const STRICT_CLK_SWITCH_NAME: &str = "strict-clock-switch";
 
 impl StatusCtl {
     const EXT_SRCS: [Ff400ClkSrc;4] = [
@@ -301,26 +977,47 @@ impl StatusCtl {
         Some(ClkNominalRate::R192000),
     ];
 
-    fn load(
+    fn load<T: FwTransaction, C: CardBackend>(
         &mut self,
-        unit: &mut SndUnit,
-        req: &mut FwReq,
-        card_cntr: &mut CardCntr,
+        tx: &mut T,
+        card_cntr: &mut CardCntr<C>,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        Ff400Protocol::read_status(req, &mut unit.get_node(), &mut self.status, timeout_ms)?;
+        self.measure_states(tx, timeout_ms)?;
 
         let labels: Vec<String> = CfgCtl::CLK_SRCS.iter()
             .map(|s| clk_src_to_string(s))
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, ACTIVE_CLK_SRC_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)?;
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STATUS_REFRESH_NAME, 0);
+        card_cntr.add_bool_elems(&elem_id, 1, 1, true)
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
+
+        [
+            WORD_CLOCK_LOCK_NAME,
+            WORD_CLOCK_SYNC_NAME,
+            SPDIF_LOCK_NAME,
+            SPDIF_SYNC_NAME,
+            ADAT_LOCK_NAME,
+            ADAT_SYNC_NAME,
+        ].iter()
+            .try_for_each(|name| {
+                let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0);
+                card_cntr.add_bool_elems(&elem_id, 1, 1, false)
+                    .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))
+            })?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STRICT_CLK_SWITCH_NAME, 0);
+        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
 
         [EXT_SRC_LOCK_NAME, EXT_SRC_SYNC_NAME].iter()
             .try_for_each(|name| {
                 let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0);
                 card_cntr.add_bool_elems(&elem_id, 1, Self::EXT_SRCS.len(), false)
-                    .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))
+                    .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))
             })?;
 
         let labels: Vec<String> = Self::EXT_SRC_RATES.iter()
@@ -328,22 +1025,59 @@ impl StatusCtl {
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_SRC_RATE_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)
-            .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))?;
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
 
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, EXT_SRC_RATE_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)
-            .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))?;
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
 
         Ok(())
     }
 
-    fn measure_states(
-        &mut self,
-        unit: &mut SndUnit,
-        req: &mut FwReq,
-        timeout_ms: u32
-    ) -> Result<(), Error> {
-        Ff400Protocol::read_status(req, &mut unit.get_node(), &mut self.status, timeout_ms)
+    fn measure_states<T: FwTransaction>(&mut self, tx: &mut T, timeout_ms: u32)
+        -> Result<(), Error>
+    {
+        Ff400Protocol::read_status_by(tx, &mut self.status, timeout_ms)
+    }
+
+    // Read status just when requested.
+    fn refresh<T: FwTransaction>(&mut self, tx: &mut T, timeout_ms: u32) -> Result<bool, Error> {
+        if self.take_refresh_request() {
+            self.measure_states(tx, timeout_ms).map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn request_refresh(&mut self) {
+        self.refresh_requested = true;
+    }
+
+    // The requests issued before reading status are coalesced into one.
+    fn take_refresh_request(&mut self) -> bool {
+        std::mem::take(&mut self.refresh_requested)
+    }
+
+    fn write(&mut self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            STATUS_REFRESH_NAME => {
+                ElemValueAccessor::<bool>::get_val(elem_value, |val| {
+                    if val {
+                        self.request_refresh();
+                    }
+                    Ok(())
+                })?;
+                Ok(true)
+            }
+            STRICT_CLK_SWITCH_NAME => {
+                ElemValueAccessor::<bool>::get_val(elem_value, |val| {
+                    self.strict_clk_switch = val;
+                    Ok(())
+                })?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 
     fn measure_elem(&self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<bool, Error> {
@@ -387,13 +1121,42 @@ impl StatusCtl {
                 elem_value.set_enum(&[pos as u32]);
                 Ok(true)
             }
+            WORD_CLOCK_LOCK_NAME => {
+                elem_value.set_bool(&[self.status.lock.word_clock]);
+                Ok(true)
+            }
+            WORD_CLOCK_SYNC_NAME => {
+                elem_value.set_bool(&[self.status.sync.word_clock]);
+                Ok(true)
+            }
+            SPDIF_LOCK_NAME => {
+                elem_value.set_bool(&[self.status.lock.spdif]);
+                Ok(true)
+            }
+            SPDIF_SYNC_NAME => {
+                elem_value.set_bool(&[self.status.sync.spdif]);
+                Ok(true)
+            }
+            ADAT_LOCK_NAME => {
+                elem_value.set_bool(&[self.status.lock.adat]);
+                Ok(true)
+            }
+            ADAT_SYNC_NAME => {
+                elem_value.set_bool(&[self.status.sync.adat]);
+                Ok(true)
+            }
+            // The trigger is released after reading status.
+            STATUS_REFRESH_NAME => {
+                elem_value.set_bool(&[false]);
+                Ok(true)
+            }
             _ => Ok(false)
         }
     }
 }
 
 #[derive(Default, Debug)]
-struct CfgCtl(Ff400Config);
+struct CfgCtl(Ff400Config, Vec<ElemId>, Ff400CfgCache);
 
 const PRIMARY_CLK_SRC_NAME: &str = "primary-clock-source";
 const LINE_INPUT_LEVEL_NAME: &str = "line-input-level";
@@ -446,16 +1209,36 @@ impl CfgCtl {
         OpticalOutputSignal::Spdif,
     ];
 
-    fn load(
+    // The elements for the parameters validated by status.
+    const STATUS_DEPENDENT_ELEM_NAMES: &'static [&'static str] = &[
+        PRIMARY_CLK_SRC_NAME,
+        OPT_OUTPUT_SIGNAL_NAME,
+    ];
+
+    // The elements for the parameters initialized by status.
+    const STATUS_DERIVED_ELEM_NAMES: &'static [&'static str] = &[
+        PRIMARY_CLK_SRC_NAME,
+        SPDIF_INPUT_IFACE_NAME,
+        SPDIF_INPUT_USE_PREEMBLE_NAME,
+        SPDIF_OUTPUT_FMT_NAME,
+        SPDIF_OUTPUT_EMPHASIS_NAME,
+        SPDIF_OUTPUT_NON_AUDIO_NAME,
+        OPT_OUTPUT_SIGNAL_NAME,
+        WORD_CLOCK_SINGLE_SPPED_NAME,
+    ];
+
+    fn load<T: FwTransaction, C: CardBackend>(
         &mut self,
-        unit: &mut SndUnit,
-        req: &mut FwReq,
+        tx: &mut T,
         status: &Ff400Status,
-        card_cntr: &mut CardCntr,
+        card_cntr: &mut CardCntr<C>,
         timeout_ms: u32
     ) -> Result<(), Error> {
         self.0.init(&status);
-        Ff400Protocol::write_cfg(req, &mut unit.get_node(), &self.0, timeout_ms)?;
+        if is_quadruple_rate(&status.configured_clk_rate) {
+            self.0.opt_out_signal = OpticalOutputSignal::Spdif;
+        }
+        Ff400Protocol::write_cfg_partial_by(tx, &self.0, &mut self.2, timeout_ms)?;
 
         let labels: Vec<String> = Self::CLK_SRCS.iter()
             .map(|s| clk_src_to_string(s))
@@ -512,37 +1295,8 @@ impl CfgCtl {
             .map(|f| optical_output_signal_to_string(f))
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OPT_OUTPUT_SIGNAL_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, WORD_CLOCK_SINGLE_SPPED_NAME, 0);
-        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
-
-        let labels: Vec<String> = Self::SPDIF_IFACES.iter()
-            .map(|i| spdif_iface_to_string(i))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_INPUT_IFACE_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_INPUT_USE_PREEMBLE_NAME, 0);
-        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
-
-        let labels: Vec<String> = Self::SPDIF_FMTS.iter()
-            .map(|f| spdif_format_to_string(f))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_OUTPUT_FMT_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_OUTPUT_EMPHASIS_NAME, 0);
-        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
-
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_OUTPUT_NON_AUDIO_NAME, 0);
-        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
-
-        let labels: Vec<String> = Self::OPT_OUT_SIGNALS.iter()
-            .map(|f| optical_output_signal_to_string(f))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, OPT_OUTPUT_SIGNAL_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut elem_id_list| self.1.append(&mut elem_id_list))?;
 
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, WORD_CLOCK_SINGLE_SPPED_NAME, 0);
         let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
@@ -643,10 +1397,11 @@ impl CfgCtl {
         }
     }
 
-    fn write(
+    fn write<T: FwTransaction>(
         &mut self,
-        unit: &mut SndUnit,
-        req: &mut FwReq,
+        tx: &mut T,
+        status: &Ff400Status,
+        strict_clk_switch: bool,
         elem_id: &ElemId,
         _: &ElemValue,
         new: &ElemValue,
@@ -662,13 +1417,14 @@ impl CfgCtl {
                             Error::new(FileError::Inval, &msg)
                         })
                         .and_then(|&src| {
-                            update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| Ok(cfg.clk.primary_src = src))
+                            check_clk_src_lock(&status.lock, src, strict_clk_switch)?;
+                            update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| Ok(cfg.clk.primary_src = src))
                         })
                 })
                 .map(|_| true)
             }
             LINE_INPUT_LEVEL_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::LINE_INPUT_LEVELS.iter()
                             .nth(val as usize)
@@ -682,25 +1438,25 @@ impl CfgCtl {
                 .map(|_| true)
             }
             MIC_POWER_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     Ok(new.get_bool(&mut cfg.analog_in.phantom_powering))
                 })
                 .map(|_| true)
             }
             LINE_INST_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     Ok(new.get_bool(&mut cfg.analog_in.insts))
                 })
                 .map(|_| true)
             }
             LINE_PAD_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     Ok(new.get_bool(&mut cfg.analog_in.pad))
                 })
                 .map(|_| true)
             }
             LINE_OUTPUT_LEVEL_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::LINE_OUTPUT_LEVELS.iter()
                             .nth(val as usize)
@@ -714,7 +1470,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             HP_OUTPUT_LEVEL_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::LINE_OUTPUT_LEVELS.iter()
                             .nth(val as usize)
@@ -728,7 +1484,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             SPDIF_INPUT_IFACE_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::SPDIF_IFACES.iter()
                             .nth(val as usize)
@@ -742,7 +1498,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             SPDIF_INPUT_USE_PREEMBLE_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<bool>::get_val(new, |val| {
                         cfg.spdif_in.use_preemble = val;
                         Ok(())
@@ -751,7 +1507,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             SPDIF_OUTPUT_FMT_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::SPDIF_FMTS.iter()
                             .nth(val as usize)
@@ -765,7 +1521,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             SPDIF_OUTPUT_EMPHASIS_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<bool>::get_val(new, |val| {
                         cfg.spdif_out.emphasis = val;
                         Ok(())
@@ -774,7 +1530,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             SPDIF_OUTPUT_NON_AUDIO_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<bool>::get_val(new, |val| {
                         cfg.spdif_out.non_audio = val;
                         Ok(())
@@ -783,7 +1539,7 @@ impl CfgCtl {
                 .map(|_| true)
             }
             OPT_OUTPUT_SIGNAL_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<u32>::get_val(new, |val| {
                         Self::OPT_OUT_SIGNALS.iter()
                             .nth(val as usize)
@@ -791,13 +1547,24 @@ impl CfgCtl {
                                 let msg = format!("Invalid value for index of optical output signal: {}", val);
                                 Error::new(FileError::Inval, &msg)
                             })
-                            .map(|&s| cfg.opt_out_signal = s)
+                            .and_then(|&s| {
+                                if s == OpticalOutputSignal::Adat &&
+                                   is_quadruple_rate(&status.configured_clk_rate) {
+                                    let label = clk_nominal_rate_to_string(&status.configured_clk_rate);
+                                    let msg = format!("ADAT signal is not available for optical output at {} Hz",
+                                                      label);
+                                    Err(Error::new(FileError::Inval, &msg))
+                                } else {
+                                    cfg.opt_out_signal = s;
+                                    Ok(())
+                                }
+                            })
                     })
                 })
                 .map(|_| true)
             }
             WORD_CLOCK_SINGLE_SPPED_NAME => {
-                update_cfg(unit, req, &mut self.0, timeout_ms, |cfg| {
+                update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
                     ElemValueAccessor::<bool>::get_val(new, |val| {
                         cfg.word_out_single = val;
                         Ok(())
@@ -808,4 +1575,390 @@ impl CfgCtl {
             _ => Ok(false),
         }
     }
+
+    // The unit reconfigures itself at the change of sampling rate, thus the cache is rebuilt by
+    // the status.
+    fn reinit<T: FwTransaction>(
+        &mut self,
+        tx: &mut T,
+        status: &Ff400Status,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        self.0.init(status);
+        self.update_opt_out_signal(tx, status, timeout_ms)
+    }
+
+    // MEMO: ADAT signal is not available for optical output interface at quadruple rates, thus
+    // S/PDIF signal is selected instead when the configured rate is changed.
+    fn update_opt_out_signal<T: FwTransaction>(
+        &mut self,
+        tx: &mut T,
+        status: &Ff400Status,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        if is_quadruple_rate(&status.configured_clk_rate) &&
+           self.0.opt_out_signal == OpticalOutputSignal::Adat {
+            update_cfg(tx, &mut self.0, &mut self.2, timeout_ms, |cfg| {
+                cfg.opt_out_signal = OpticalOutputSignal::Spdif;
+                Ok(())
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// MEMO: The unit mutes all of outputs when the primary clock source is not locked. The switch to
+// such source is rejected in strict mode, or just warned.
+fn check_clk_src_lock(
+    lock: &Ff400ClkLockStatus,
+    src: Ff400ClkSrc,
+    strict: bool
+) -> Result<(), Error> {
+    let locked = match src {
+        Ff400ClkSrc::WordClock => lock.word_clock,
+        Ff400ClkSrc::Adat => lock.adat,
+        Ff400ClkSrc::Spdif => lock.spdif,
+        // The status of lock is not available for LTC.
+        Ff400ClkSrc::Internal | Ff400ClkSrc::Ltc => true,
+    };
+
+    if locked {
+        Ok(())
+    } else {
+        let msg = format!("{} is not locked for the primary clock source", clk_src_to_string(&src));
+        if strict {
+            Err(Error::new(FileError::Inval, &msg))
+        } else {
+            log_warn!(LOG_RUNTIME, "{}", msg);
+            Ok(())
+        }
+    }
+}
+
+fn is_quadruple_rate(rate: &ClkNominalRate) -> bool {
+    *rate == ClkNominalRate::R176400 || *rate == ClkNominalRate::R192000
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fw_transaction::{replay::ReplayTransaction, MockTransaction};
+
+    // The registers of status from the replay map, with the read transactions counted.
+    struct StatusTransaction {
+        replay: ReplayTransaction,
+        reads: usize,
+    }
+
+    impl StatusTransaction {
+        // The address of status in the replay map.
+        const STATUS_ADDR: u64 = 0x0000801c0000;
+
+        fn new() -> Self {
+            let replay = ReplayTransaction::parse(include_str!("../../protocols/replay/ff400.map"))
+                .unwrap();
+            Self { replay, reads: 0 }
+        }
+
+        fn update_status_quadlet(&mut self, pos: usize, bits: u32) {
+            let addr = Self::STATUS_ADDR + pos as u64 * 4;
+            let mut frame = [0; 4];
+            self.replay.read(addr, &mut frame, TIMEOUT_MS).unwrap();
+            let quad = u32::from_le_bytes(frame) | bits;
+            let mut frame = quad.to_le_bytes();
+            self.replay.write(addr, &mut frame, TIMEOUT_MS).unwrap();
+        }
+    }
+
+    impl FwTransaction for StatusTransaction {
+        fn transaction(
+            &mut self,
+            tcode: hinawa::FwTcode,
+            addr: u64,
+            frame: &mut [u8],
+            timeout_ms: u32
+        ) -> Result<(), Error> {
+            if addr == Self::STATUS_ADDR {
+                self.reads += 1;
+            }
+            self.replay.transaction(tcode, addr, frame, timeout_ms)
+        }
+    }
+
+    // Read the notified elements as the dispatcher does, then return the name of elements whose
+    // value is changed, thus notified to applications.
+    fn notified_elems(ctl: &StatusCtl, card_cntr: &CardCntr<MockCard>) -> Vec<String> {
+        ctl.notified_elem_list.iter()
+            .filter(|elem_id| {
+                let mut old = ElemValue::new();
+                card_cntr.card.read_value(elem_id, &mut old).unwrap();
+                let new = ElemValue::new();
+                assert!(ctl.measure_elem(elem_id, &new).unwrap());
+                card_cntr.card.write_value(elem_id, &new).unwrap();
+                !new.equal(&old)
+            })
+            .map(|elem_id| elem_id.get_name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_status_refresh() {
+        let mut tx = StatusTransaction::new();
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut ctl = StatusCtl::default();
+        ctl.load(&mut tx, &mut card_cntr, TIMEOUT_MS).unwrap();
+        assert_eq!(tx.reads, 1);
+        let _ = notified_elems(&ctl, &card_cntr);
+
+        // Nothing is read without any request.
+        assert!(!ctl.refresh(&mut tx, TIMEOUT_MS).unwrap());
+        assert_eq!(tx.reads, 1);
+
+        // The word clock is locked and synchronized after cabling. The bits are in the layout of
+        // status in the protocol implementation.
+        assert!(!ctl.status.lock.word_clock);
+        assert!(!ctl.status.sync.word_clock);
+        tx.update_status_quadlet(0, 0x60000000);
+
+        // The trigger and the change of configuration before reading status result in one read.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STATUS_REFRESH_NAME, 0);
+        let mut elem_value = ElemValue::new();
+        elem_value.set_bool(&[true]);
+        assert!(ctl.write(&elem_id, &elem_value).unwrap());
+        ctl.request_refresh();
+        assert!(ctl.refresh(&mut tx, TIMEOUT_MS).unwrap());
+        assert_eq!(tx.reads, 2);
+        assert!(!ctl.refresh(&mut tx, TIMEOUT_MS).unwrap());
+        assert_eq!(tx.reads, 2);
+
+        assert!(ctl.status.lock.word_clock);
+        assert!(ctl.status.sync.word_clock);
+        assert_eq!(
+            notified_elems(&ctl, &card_cntr),
+            vec![
+                WORD_CLOCK_LOCK_NAME.to_string(),
+                WORD_CLOCK_SYNC_NAME.to_string(),
+                EXT_SRC_LOCK_NAME.to_string(),
+                EXT_SRC_SYNC_NAME.to_string(),
+            ]
+        );
+
+        // Nothing is notified by the same status.
+        ctl.request_refresh();
+        assert!(ctl.refresh(&mut tx, TIMEOUT_MS).unwrap());
+        assert_eq!(tx.reads, 3);
+        assert!(notified_elems(&ctl, &card_cntr).is_empty());
+    }
+
+    // The transaction to write the configuration at once.
+    fn cfg_write(cfg: &Ff400Config) -> (u64, Vec<u8>) {
+        let mut tx = MockTransaction::default();
+        let mut cache = Ff400CfgCache::default();
+        Ff400Protocol::write_cfg_partial_by(&mut tx, cfg, &mut cache, TIMEOUT_MS).unwrap();
+        tx.writes.remove(0)
+    }
+
+    // Write the value to the element as application does, then dispatch it to the control with
+    // the old value.
+    fn write_cfg_elem<T: FwTransaction>(
+        ctl: &mut CfgCtl,
+        tx: &mut T,
+        status: &Ff400Status,
+        card_cntr: &CardCntr<MockCard>,
+        elem_id: &ElemId,
+        new: &ElemValue,
+    ) -> Result<bool, Error> {
+        let mut old = ElemValue::new();
+        card_cntr.card.read_value(elem_id, &mut old).unwrap();
+        card_cntr.card.write_value(elem_id, new).unwrap();
+
+        let mut new = ElemValue::new();
+        card_cntr.card.read_value(elem_id, &mut new).unwrap();
+        ctl.write(tx, status, true, elem_id, &old, &new, TIMEOUT_MS)
+    }
+
+    #[test]
+    fn test_cfg_ctl_replay() {
+        let mut tx = ReplayTransaction::parse(include_str!("../../protocols/replay/ff400.map"))
+            .unwrap();
+        let mut status = Ff400Status::default();
+        Ff400Protocol::read_status_by(&mut tx, &mut status, TIMEOUT_MS).unwrap();
+
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut ctl = CfgCtl::default();
+        ctl.load(&mut tx, &status, &mut card_cntr, TIMEOUT_MS).unwrap();
+
+        let mut cfg = Ff400Config::default();
+        cfg.init(&status);
+        let mut expected = vec![cfg_write(&cfg)];
+        assert_eq!(tx.log(), &expected[..]);
+
+        // Phantom powering for the first microphone input.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, MIC_POWER_NAME, 0);
+        let mut elem_value = ElemValue::new();
+        elem_value.set_bool(&[true, false]);
+        assert!(write_cfg_elem(&mut ctl, &mut tx, &status, &card_cntr, &elem_id, &elem_value)
+            .unwrap());
+        cfg.analog_in.phantom_powering[0] = true;
+        expected.push(cfg_write(&cfg));
+        assert_eq!(tx.log(), &expected[..]);
+
+        // The same value again is not written.
+        assert!(write_cfg_elem(&mut ctl, &mut tx, &status, &card_cntr, &elem_id, &elem_value)
+            .unwrap());
+        assert_eq!(tx.log(), &expected[..]);
+
+        // The level of line output.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, LINE_OUTPUT_LEVEL_NAME, 0);
+        let mut elem_value = ElemValue::new();
+        elem_value.set_enum(&[1]);
+        assert!(write_cfg_elem(&mut ctl, &mut tx, &status, &card_cntr, &elem_id, &elem_value)
+            .unwrap());
+        cfg.line_out_level = LineOutNominalLevel::Consumer;
+        expected.push(cfg_write(&cfg));
+        assert_eq!(tx.log(), &expected[..]);
+
+        // The switch to unlocked source is rejected in strict mode, without any write.
+        assert!(!status.lock.word_clock);
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PRIMARY_CLK_SRC_NAME, 0);
+        let mut elem_value = ElemValue::new();
+        elem_value.set_enum(&[1]);
+        assert!(write_cfg_elem(&mut ctl, &mut tx, &status, &card_cntr, &elem_id, &elem_value)
+            .is_err());
+        assert_eq!(tx.log(), &expected[..]);
+        assert_eq!(ctl.0, cfg);
+    }
+
+    #[test]
+    fn test_output_ctl_definition() {
+        let mut card_cntr = CardCntr::with_backend(MockCard::default());
+        let mut ctl = OutputCtl::default();
+        *ctl.state_mut() = Ff400Protocol::create_output_volume_state();
+        ctl.state_mut().0.iter_mut().for_each(|vol| *vol = Ff400Protocol::VOL_ZERO);
+
+        ctl.load_vol(&mut card_cntr).unwrap();
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, "output-volume", 0);
+        assert_eq!(card_cntr.card.is_locked(&elem_id), Some(false));
+        assert!(!card_cntr.card.tlv(&elem_id).unwrap().is_empty());
+
+        let mut elem_value = ElemValue::new();
+        assert!(ctl.read(&elem_id, &mut elem_value).unwrap());
+        card_cntr.card.write_value(&elem_id, &elem_value).unwrap();
+
+        let mut elem_value = ElemValue::new();
+        card_cntr.card.read_value(&elem_id, &mut elem_value).unwrap();
+        let mut vals = vec![0; ctl.state().0.len()];
+        elem_value.get_int(&mut vals);
+        assert!(vals.iter().all(|&vol| vol == Ff400Protocol::VOL_ZERO));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let status = Ff400Status::default();
+        let cfg = Ff400Config::default();
+        let vols = Ff400Protocol::create_output_volume_state();
+        let snapshot = Ff400Snapshot {
+            status: &status,
+            cfg: &cfg,
+            vols: &vols.0,
+        };
+
+        let mut ctl = StateSnapshotCtl::default();
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, STATE_SNAPSHOT_NAME, 0);
+        let mut elem_value = ElemValue::new();
+
+        // Nothing to do until the snapshot is invalidated.
+        assert!(!ctl.refresh(&elem_id, &mut elem_value, &snapshot).unwrap());
+        ctl.invalidate();
+        assert!(ctl.refresh(&elem_id, &mut elem_value, &snapshot).unwrap());
+        assert!(!ctl.refresh(&elem_id, &mut elem_value, &snapshot).unwrap());
+
+        let mut raw = [0; STATE_SNAPSHOT_BYTES_COUNT];
+        elem_value.get_bytes(&mut raw);
+        let len = raw.iter().position(|&b| b == 0).unwrap();
+        let text = std::str::from_utf8(&raw[..len]).unwrap();
+        assert!(text.starts_with("{\"clock.primary-source\":\"Internal\","));
+        assert!(text.contains("\"lock.spdif-adat-wc\":[false,false,false]"));
+        assert!(text.ends_with(&format!("\"output-volume\":[{}]}}", vec!["0"; 18].join(","))));
+    }
+
+    #[test]
+    fn test_gang_gains() {
+        // The changed channel is applied to both channels.
+        let mut gains = [30, 10];
+        gang_gains(&[10, 10], &mut gains);
+        assert_eq!(gains, [30, 30]);
+
+        let mut gains = [10, 25];
+        gang_gains(&[10, 10], &mut gains);
+        assert_eq!(gains, [25, 25]);
+
+        // The first channel takes precedence.
+        let mut gains = [5, 6];
+        gang_gains(&[10, 10], &mut gains);
+        assert_eq!(gains, [5, 5]);
+
+        let mut gains = [10, 10];
+        gang_gains(&[10, 10], &mut gains);
+        assert_eq!(gains, [10, 10]);
+    }
+
+    #[test]
+    fn test_snap_gains() {
+        assert_eq!(snap_gains(&[40, 20]), Some(vec![40, 40]));
+        assert_eq!(snap_gains(&[20, 20]), None);
+    }
+
+    #[test]
+    fn test_compensate_linked_gain() {
+        let zero = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_ZERO;
+        let max = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_MAX;
+        let mut base = None;
+
+        // Saturated at maximum, then recovered by the reverse change.
+        let gain = compensate_linked_gain(&mut base, zero, 10, 0);
+        assert_eq!(gain, max);
+        let gain = compensate_linked_gain(&mut base, gain, 0, 10);
+        assert_eq!(gain, zero);
+
+        // The base is renewed by the gain changed by the other.
+        let gain = compensate_linked_gain(&mut base, 0x1000, 10, 16);
+        assert_eq!(base.unwrap().gain, 0x1000);
+        assert_eq!(compensate_linked_gain(&mut base, gain, 16, 10), 0x1000);
+    }
+
+    #[test]
+    fn test_output_src_pair() {
+        let labels = OutputSourceCtl::src_labels();
+        assert_eq!(labels[0], "Analog-1/2");
+        assert_eq!(labels[labels.len() - 2], "Stream-17/18");
+        let custom = labels.len() - 1;
+
+        let mut state = Ff400Protocol::create_mixer_state();
+        assert_eq!(OutputSourceCtl::detect_pair(&state, 0), custom);
+
+        // The channel in the pair at the same position as the output.
+        state.0[2].stream_gains[2] = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_ZERO;
+        state.0[3].stream_gains[2] = <Ff400Protocol as RmeFormerMixerOperation>::GAIN_ZERO;
+        assert_eq!(labels[OutputSourceCtl::detect_pair(&state, 2)], "Stream-3/4");
+        assert_eq!(OutputSourceCtl::detect_pair(&state, 3), custom);
+    }
+
+    #[test]
+    fn test_clk_src_lock() {
+        let lock = Ff400ClkLockStatus {
+            adat: true,
+            spdif: false,
+            word_clock: false,
+        };
+
+        assert!(check_clk_src_lock(&lock, Ff400ClkSrc::Internal, true).is_ok());
+        assert!(check_clk_src_lock(&lock, Ff400ClkSrc::Adat, true).is_ok());
+        assert!(check_clk_src_lock(&lock, Ff400ClkSrc::Spdif, false).is_ok());
+
+        let err = check_clk_src_lock(&lock, Ff400ClkSrc::WordClock, true).unwrap_err();
+        assert!(err.to_string().contains("Word-clock"));
+    }
 }
@@ -7,8 +7,9 @@ use hinawa::{SndUnit, SndUnitExt};
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual};
 
-use core::card_cntr::*;
+use core::{burst::BatchedWrite, card_cntr::*};
 use core::elem_value_accessor::*;
+use core::monitor::*;
 
 use super::model::*;
 
@@ -24,6 +25,7 @@ pub struct Ff800Model{
     out_ctl: OutputCtl,
     mixer_ctl: MixerCtl,
     meter_ctl: MeterCtl,
+    rate_change: FormerRateChangeState,
 }
 
 const TIMEOUT_MS: u32 = 100;
@@ -33,9 +35,13 @@ impl CtlModel<SndUnit> for Ff800Model {
         self.status_ctl.load(unit, &mut self.req, TIMEOUT_MS, card_cntr)?;
         self.cfg_ctl.load(unit, &mut self.req, &self.status_ctl.status, card_cntr, TIMEOUT_MS)?;
         self.out_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
+        self.load_monitor_section(card_cntr)?;
         self.mixer_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)?;
+        let rate = self.status_ctl.status.configured_clk_rate;
+        self.mixer_ctl.update_rate(unit, &mut self.req, rate, TIMEOUT_MS)?;
         self.meter_ctl.load(unit, &mut self.req, card_cntr, TIMEOUT_MS)
             .map(|mut elem_id_list| self.meter_ctl.1.append(&mut elem_id_list))?;
+        self.meter_ctl.update_rate(self.status_ctl.status.configured_clk_rate);
 
         Ok(())
     }
@@ -47,8 +53,12 @@ impl CtlModel<SndUnit> for Ff800Model {
             Ok(true)
         } else if self.out_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_monitor_section(elem_id, elem_value)? {
+            Ok(true)
         } else if self.mixer_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.meter_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -61,8 +71,12 @@ impl CtlModel<SndUnit> for Ff800Model {
             Ok(true)
         } else if self.out_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.write_monitor_section(unit, elem_id, new)? {
+            Ok(true)
         } else if self.mixer_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.meter_ctl.write(elem_id, new)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -71,31 +85,94 @@ impl CtlModel<SndUnit> for Ff800Model {
 
 impl MeasureModel<SndUnit> for Ff800Model {
     fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
-        elem_id_list.extend_from_slice(&self.status_ctl.measured_elem_list);
         elem_id_list.extend_from_slice(&self.meter_ctl.1);
     }
 
     fn measure_states(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
-        self.status_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        self.meter_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)?;
-        Ok(())
+        self.meter_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)
     }
 
     fn measure_elem(&mut self, _: &SndUnit, elem_id: &ElemId, elem_value: &mut ElemValue)
         -> Result<bool, Error>
+    {
+        self.meter_ctl.measure_elem(elem_id, elem_value)
+    }
+}
+
+// The status is read by the polling regardless of metering, so that the layout of mixer follows
+// the change of sampling rate.
+impl NotifyModel<SndUnit, bool> for Ff800Model {
+    fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.status_ctl.notified_elem_list);
+    }
+
+    fn parse_notification(&mut self, unit: &mut SndUnit, &refresh: &bool) -> Result<(), Error> {
+        if refresh {
+            let rates = self.clk_rates();
+            self.status_ctl.measure_states(unit, &mut self.req, TIMEOUT_MS)?;
+            self.detect_rate_change(unit, &rates)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_notified_elem(&mut self, _: &SndUnit, elem_id: &ElemId, elem_value: &mut ElemValue)
+        -> Result<bool, Error>
     {
         if self.status_ctl.measure_elem(elem_id, elem_value)? {
             Ok(true)
-        } else if self.meter_ctl.measure_elem(elem_id, elem_value)? {
-            Ok(true)
         } else {
-            Ok(false)
+            self.cfg_ctl.read(elem_id, elem_value)
         }
     }
 }
 
+impl FormerRateChangeOperation for Ff800Model {
+    const RATE_AFFECTED_ELEM_NAMES: &'static [&'static str] = CfgCtl::STATUS_DERIVED_ELEM_NAMES;
+
+    fn rate_change_state_mut(&mut self) -> &mut FormerRateChangeState {
+        &mut self.rate_change
+    }
+
+    fn clk_rates(&self) -> (Option<ClkNominalRate>, ClkNominalRate) {
+        (self.status_ctl.status.external_clk_rate, self.status_ctl.status.configured_clk_rate)
+    }
+
+    // The unit reconfigures itself at the change of sampling rate, thus the cache of
+    // configuration is rebuilt by the status.
+    fn reconcile_rate(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        self.cfg_ctl.0.init(&self.status_ctl.status);
+        let rate = self.status_ctl.status.configured_clk_rate;
+        self.mixer_ctl.update_rate(unit, &mut self.req, rate, TIMEOUT_MS)?;
+        self.meter_ctl.update_rate(rate);
+        Ok(())
+    }
+}
+
+impl MonitorSection<SndUnit> for Ff800Model {
+    fn monitor_state(&self) -> &MonitorSectionState {
+        &self.out_ctl.1.state
+    }
+
+    fn write_monitor_state(&mut self, unit: &mut SndUnit, state: MonitorSectionState)
+        -> Result<(), Error>
+    {
+        self.out_ctl.write_monitor_state(unit, &mut self.req, state, TIMEOUT_MS)
+    }
+}
+
+impl BatchedWrite<SndUnit> for Ff800Model {
+    fn begin_batch(&mut self) {
+        self.mixer_ctl.begin_batch();
+    }
+
+    fn flush_batch(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        self.mixer_ctl.flush_batch(unit, &mut self.req, TIMEOUT_MS)
+    }
+}
+
 #[derive(Default, Debug)]
-struct MeterCtl(FormerMeterState, Vec<ElemId>);
+struct MeterCtl(FormerMeterState, Vec<ElemId>, FormerMeterBallistics);
 
 impl FormerMeterCtlOperation<Ff800Protocol> for MeterCtl {
     fn meter(&self) -> &FormerMeterState{
@@ -106,10 +183,17 @@ impl FormerMeterCtlOperation<Ff800Protocol> for MeterCtl {
         &mut self.0
     }
 
+    fn ballistics(&self) -> &FormerMeterBallistics {
+        &self.2
+    }
+
+    fn ballistics_mut(&mut self) -> &mut FormerMeterBallistics {
+        &mut self.2
+    }
 }
 
 #[derive(Default, Debug)]
-struct OutputCtl(FormerOutputVolumeState);
+struct OutputCtl(FormerOutputVolumeState, FormerOutputMonitor);
 
 impl FormerOutputCtlOperation<Ff800Protocol> for OutputCtl {
     fn state(&self) -> &FormerOutputVolumeState {
@@ -119,10 +203,18 @@ impl FormerOutputCtlOperation<Ff800Protocol> for OutputCtl {
     fn state_mut(&mut self) -> &mut FormerOutputVolumeState {
         &mut self.0
     }
+
+    fn monitor(&self) -> &FormerOutputMonitor {
+        &self.1
+    }
+
+    fn monitor_mut(&mut self) -> &mut FormerOutputMonitor {
+        &mut self.1
+    }
 }
 
 #[derive(Default, Debug)]
-struct MixerCtl(FormerMixerState);
+struct MixerCtl(FormerMixerState, FormerMixerBatch);
 
 impl FormerMixerCtlOperation<Ff800Protocol> for MixerCtl {
     fn state(&self) -> &FormerMixerState {
@@ -132,6 +224,14 @@ impl FormerMixerCtlOperation<Ff800Protocol> for MixerCtl {
     fn state_mut(&mut self) -> &mut FormerMixerState {
         &mut self.0
     }
+
+    fn batch(&self) -> &FormerMixerBatch {
+        &self.1
+    }
+
+    fn batch_mut(&mut self) -> &mut FormerMixerBatch {
+        &mut self.1
+    }
 }
 
 fn update_cfg<F>(
@@ -171,7 +271,7 @@ fn line_in_jack_to_string(jack: &Ff800AnalogInputJack) -> String {
 #[derive(Default, Debug)]
 struct StatusCtl{
     status: Ff800Status,
-    measured_elem_list: Vec<ElemId>,
+    notified_elem_list: Vec<ElemId>,
 }
 
 const EXT_SRC_LOCK_NAME: &str = "external-source-lock";
@@ -221,7 +321,7 @@ impl StatusCtl {
             .try_for_each(|name| {
                 let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0);
                 card_cntr.add_bool_elems(&elem_id, 1, Self::EXT_SRCS.len(), false)
-                    .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))
+                    .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))
             })?;
 
         let labels: Vec<String> = Self::EXT_SRC_RATES.iter()
@@ -229,11 +329,11 @@ impl StatusCtl {
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SPDIF_SRC_RATE_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)
-            .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))?;
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
 
         let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, EXT_SRC_RATE_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)
-            .map(|mut elem_id_list| self.measured_elem_list.append(&mut elem_id_list))?;
+            .map(|mut elem_id_list| self.notified_elem_list.append(&mut elem_id_list))?;
 
         Ok(())
     }
@@ -372,6 +472,18 @@ impl CfgCtl {
         OpticalOutputSignal::Spdif,
     ];
 
+    // The elements for the parameters initialized by status.
+    const STATUS_DERIVED_ELEM_NAMES: &'static [&'static str] = &[
+        PRIMARY_CLK_SRC_NAME,
+        SPDIF_INPUT_IFACE_NAME,
+        SPDIF_INPUT_USE_PREEMBLE_NAME,
+        SPDIF_OUTPUT_FMT_NAME,
+        SPDIF_OUTPUT_EMPHASIS_NAME,
+        SPDIF_OUTPUT_NON_AUDIO_NAME,
+        OPT_OUTPUT_SIGNAL_NAME,
+        WORD_CLOCK_SINGLE_SPPED_NAME,
+    ];
+
     fn load(
         &mut self,
         unit: &mut SndUnit,
@@ -3,6 +3,7 @@
 use glib::Error;
 
 use hinawa::FwReq;
+use hinawa::FwNodeExtManual;
 use hinawa::{SndUnit, SndUnitExt};
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual};
@@ -10,17 +11,47 @@ use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual
 use alsa_ctl_tlv_codec::items::DbInterval;
 
 use core::card_cntr::*;
+use core::channel_name::{guid_from_config_rom, unit_state_path};
+use core::monitor::MonitorSectionState;
+use core::log::*;
+use core::log_warn;
 
-use ff_protocols::former::*;
+use ff_protocols::{*, former::*};
 
 const VOL_NAME: &str = "output-volume";
 
+// The extension of file to store the output volumes per GUID of unit.
+const VOL_STATE_EXTENSION: &str = "volumes";
+
+/// The state of monitor section on the main pair of outputs. The volumes requested by user are
+/// kept so that the attenuation applies on top of them.
+#[derive(Default, Debug)]
+pub struct FormerOutputMonitor {
+    pub state: MonitorSectionState,
+    requested: Vec<i32>,
+    // The registers for output volume are write-only, thus the requested volumes are stored to
+    // the file and restored at load instead of the default.
+    path: Option<std::path::PathBuf>,
+}
+
+impl FormerOutputMonitor {
+    /// The volumes requested by user, without the attenuation.
+    pub fn requested(&self) -> &[i32] {
+        &self.requested
+    }
+}
+
 pub trait FormerOutputCtlOperation<T: RmeFormerOutputOperation> {
     fn state(&self) -> &FormerOutputVolumeState;
     fn state_mut(&mut self) -> &mut FormerOutputVolumeState;
+    fn monitor(&self) -> &FormerOutputMonitor;
+    fn monitor_mut(&mut self) -> &mut FormerOutputMonitor;
 
     const VOL_TLV: DbInterval = DbInterval{min: -9000, max: 600, linear: false, mute_avail: false};
 
+    /// The outputs attenuated by monitor section.
+    const MONITOR_OUTPUTS: [usize; 2] = [0, 1];
+
     fn load(
         &mut self,
         unit: &mut SndUnit,
@@ -28,11 +59,87 @@ pub trait FormerOutputCtlOperation<T: RmeFormerOutputOperation> {
         card_cntr: &mut CardCntr,
         timeout_ms: u32
     ) -> Result<(), Error> {
+        let path = unit.get_node().get_config_rom()
+            .ok()
+            .and_then(|raw| guid_from_config_rom(&raw))
+            .and_then(|guid| unit_state_path(guid, VOL_STATE_EXTENSION));
+        let text = path.as_ref().and_then(|path| std::fs::read_to_string(path).ok());
+
+        // The cache is ready before the elements are added, thus the write by the other process
+        // is applied on top of the restored volumes.
         let mut state = T::create_output_volume_state();
-        state.0.iter_mut().for_each(|vol| *vol = T::VOL_ZERO);
+        state.0 = Self::initial_vols(text.as_ref().map(|t| t.as_str()), state.0.len());
         T::init_output_vols(req, &mut unit.get_node(), &mut state, timeout_ms)?;
+        self.monitor_mut().requested = state.0.clone();
+        self.monitor_mut().path = path;
         *self.state_mut() = state;
 
+        self.load_vol(card_cntr)
+    }
+
+    /// The stored volumes, or 0 dB for all outputs unless available.
+    fn initial_vols(text: Option<&str>, count: usize) -> Vec<i32> {
+        text.and_then(|t| Self::parse_vols(t, count))
+            .unwrap_or_else(|| vec![T::VOL_ZERO; count])
+    }
+
+    fn parse_vols(text: &str, count: usize) -> Option<Vec<i32>> {
+        let vols = text.lines()
+            .map(|line| line.trim().parse::<i32>().ok())
+            .collect::<Option<Vec<i32>>>()?;
+        if vols.len() == count && vols.iter().all(|&vol| vol >= T::VOL_MIN && vol <= T::VOL_MAX) {
+            Some(vols)
+        } else {
+            None
+        }
+    }
+
+    fn serialize_vols(vols: &[i32]) -> String {
+        vols.iter()
+            .fold(String::new(), |text, vol| text + &vol.to_string() + "\n")
+    }
+
+    // The failure to store is just reported since the volumes are already written to the unit.
+    fn store_vols(&self) {
+        if let Some(path) = &self.monitor().path {
+            let text = Self::serialize_vols(&self.monitor().requested);
+            let res = path.parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(path, text));
+            if let Err(e) = res {
+                log_warn!(LOG_RUNTIME, "Fail to store output volumes to {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn attenuate_vols(requested: &[i32], monitor: &MonitorSectionState) -> Vec<i32> {
+        let scale = monitor.scale();
+        requested.iter()
+            .enumerate()
+            .map(|(i, &vol)| {
+                if Self::MONITOR_OUTPUTS.contains(&i) {
+                    ((vol as f64 * scale).round() as i32).max(T::VOL_MIN)
+                } else {
+                    vol
+                }
+            })
+            .collect()
+    }
+
+    /// Write the requested volumes with the attenuation of new state of monitor section.
+    fn write_monitor_state(
+        &mut self,
+        unit: &mut SndUnit,
+        req: &mut FwReq,
+        monitor: MonitorSectionState,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let vols = Self::attenuate_vols(&self.monitor().requested, &monitor);
+        T::write_output_vols(req, &mut unit.get_node(), self.state_mut(), &vols, timeout_ms)
+            .map(|_| self.monitor_mut().state = monitor)
+    }
+
+    fn load_vol<C: CardBackend>(&self, card_cntr: &mut CardCntr<C>) -> Result<(), Error> {
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, VOL_NAME, 0);
         let _ = card_cntr.add_int_elems(
             &elem_id,
@@ -51,7 +158,7 @@ pub trait FormerOutputCtlOperation<T: RmeFormerOutputOperation> {
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             VOL_NAME => {
-                elem_value.set_int(&self.state().0);
+                elem_value.set_int(&self.monitor().requested);
                 Ok(true)
             },
             _ => Ok(false),
@@ -68,24 +175,38 @@ pub trait FormerOutputCtlOperation<T: RmeFormerOutputOperation> {
     ) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             VOL_NAME => {
-                let mut vals = self.state().0.to_vec();
+                let mut vals = self.monitor().requested.clone();
                 new.get_int(&mut vals);
-                T::write_output_vols(req, &mut unit.get_node(), self.state_mut(), &vals, timeout_ms)
-                    .map(|_| true)
+                let vols = Self::attenuate_vols(&vals, &self.monitor().state);
+                T::write_output_vols(req, &mut unit.get_node(), self.state_mut(), &vols, timeout_ms)
+                    .map(|_| {
+                        self.monitor_mut().requested = vals;
+                        self.store_vols();
+                        true
+                    })
             },
             _ => Ok(false),
         }
     }
 }
 
-const ANALOG_SRC_GAIN_NAME: &str = "mixer:analog-source-gain";
-const SPDIF_SRC_GAIN_NAME: &str = "mixer:spdif-source-gain";
-const ADAT_SRC_GAIN_NAME: &str = "mixer:adat-source-gain";
-const STREAM_SRC_GAIN_NAME: &str = "mixer:stream-source-gain";
+pub const ANALOG_SRC_GAIN_NAME: &str = "mixer:analog-source-gain";
+pub const SPDIF_SRC_GAIN_NAME: &str = "mixer:spdif-source-gain";
+pub const ADAT_SRC_GAIN_NAME: &str = "mixer:adat-source-gain";
+pub const STREAM_SRC_GAIN_NAME: &str = "mixer:stream-source-gain";
+
+/// The cache of mixers changed during batched write.
+#[derive(Default, Debug)]
+pub struct FormerMixerBatch {
+    enabled: bool,
+    dirty: Vec<usize>,
+}
 
 pub trait FormerMixerCtlOperation<T: RmeFormerMixerOperation> {
     fn state(&self) -> &FormerMixerState;
     fn state_mut(&mut self) -> &mut FormerMixerState;
+    fn batch(&self) -> &FormerMixerBatch;
+    fn batch_mut(&mut self) -> &mut FormerMixerBatch;
 
     const GAIN_TLV: DbInterval = DbInterval{min: -9000, max: 600, linear: false, mute_avail: false};
 
@@ -203,6 +324,10 @@ pub trait FormerMixerCtlOperation<T: RmeFormerMixerOperation> {
         new: &ElemValue,
         timeout_ms: u32
     ) -> Result<bool, Error> {
+        if self.batch().enabled {
+            return self.cache(elem_id, new);
+        }
+
         match elem_id.get_name().as_str() {
             ANALOG_SRC_GAIN_NAME => {
                 let index = elem_id.get_index() as usize;
@@ -263,6 +388,58 @@ pub trait FormerMixerCtlOperation<T: RmeFormerMixerOperation> {
             _ => Ok(false),
         }
     }
+
+    fn cache(&mut self, elem_id: &ElemId, new: &ElemValue) -> Result<bool, Error> {
+        let index = elem_id.get_index() as usize;
+        let mixer = &mut self.state_mut().0[index];
+        let gains = match elem_id.get_name().as_str() {
+            ANALOG_SRC_GAIN_NAME => &mut mixer.analog_gains,
+            SPDIF_SRC_GAIN_NAME => &mut mixer.spdif_gains,
+            ADAT_SRC_GAIN_NAME => &mut mixer.adat_gains,
+            STREAM_SRC_GAIN_NAME => &mut mixer.stream_gains,
+            _ => return Ok(false),
+        };
+        new.get_int(gains);
+
+        let dirty = &mut self.batch_mut().dirty;
+        if !dirty.contains(&index) {
+            dirty.push(index);
+        }
+
+        Ok(true)
+    }
+
+    /// Follow the nominal rate of sampling clock so that the gains are applied to the same
+    /// physical inputs.
+    fn update_rate(
+        &mut self,
+        unit: &SndUnit,
+        req: &mut FwReq,
+        rate: ClkNominalRate,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        T::update_mixer_rate(req, &mut unit.get_node(), self.state_mut(), rate, timeout_ms)
+    }
+
+    /// The change of gains is just cached till flushed.
+    fn begin_batch(&mut self) {
+        self.batch_mut().enabled = true;
+    }
+
+    /// Write all of gains in the changed mixers.
+    fn flush_batch(
+        &mut self,
+        unit: &SndUnit,
+        req: &mut FwReq,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        self.batch_mut().enabled = false;
+        let dirty = std::mem::take(&mut self.batch_mut().dirty);
+        dirty.iter()
+            .try_for_each(|&i| {
+                T::init_mixer_src_gains(req, &mut unit.get_node(), self.state_mut(), i, timeout_ms)
+            })
+    }
 }
 
 const ANALOG_INPUT_NAME: &str = "meter:analog-input";
@@ -273,11 +450,18 @@ const STREAM_INPUT_NAME: &str = "meter:stream-input";
 const ANALOG_OUTPUT_NAME: &str = "meter:analog-output";
 const SPDIF_OUTPUT_NAME: &str = "meter:spdif-output";
 const ADAT_OUTPUT_NAME: &str = "meter:adat-output";
+const ADAT_ACTIVE_NAME: &str = "meter:adat-active";
+
+const HOLD_MS_NAME: &str = "meter:peak-hold-ms";
+const DECAY_NAME: &str = "meter:decay-db-per-sec";
 
 pub trait FormerMeterCtlOperation<T: RmeFfFormerMeterOperation> {
     fn meter(&self) -> &FormerMeterState;
     fn meter_mut(&mut self) -> &mut FormerMeterState;
 
+    fn ballistics(&self) -> &FormerMeterBallistics;
+    fn ballistics_mut(&mut self) -> &mut FormerMeterBallistics;
+
     const LEVEL_TLV: DbInterval = DbInterval{min: -9003, max: 600, linear: false, mute_avail: false};
 
     fn load(
@@ -289,8 +473,33 @@ pub trait FormerMeterCtlOperation<T: RmeFfFormerMeterOperation> {
     ) -> Result<Vec<ElemId>, Error> {
         let mut meter = T::create_meter_state();
         T::read_meter(req, &mut unit.get_node(), &mut meter, timeout_ms)?;
+        self.ballistics_mut().process_now(&meter);
         *self.meter_mut() = meter;
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, HOLD_MS_NAME, 0);
+        let _ = card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            0,
+            FormerMeterBallistics::HOLD_MS_MAX as i32,
+            1,
+            1,
+            None,
+            true
+        )?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, DECAY_NAME, 0);
+        let _ = card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            0,
+            FormerMeterBallistics::DECAY_DB_PER_SEC_MAX as i32,
+            1,
+            1,
+            None,
+            true
+        )?;
+
         let mut measured_elem_id_list = Vec::new();
 
         [
@@ -315,8 +524,20 @@ pub trait FormerMeterCtlOperation<T: RmeFfFormerMeterOperation> {
                     false
                 )
                     .map(|mut elem_id_list| measured_elem_id_list.append(&mut elem_id_list))
-            })
-                .map(|_| measured_elem_id_list)
+            })?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, ADAT_ACTIVE_NAME, 0);
+        card_cntr.add_bool_elems(&elem_id, 1, T::ADAT_INPUT_COUNT, false)
+            .map(|mut elem_id_list| measured_elem_id_list.append(&mut elem_id_list))?;
+
+        Ok(measured_elem_id_list)
+    }
+
+    /// Update active channels of ADAT interface when the nominal sampling rate is changed.
+    fn update_rate(&mut self, rate: ClkNominalRate) {
+        T::update_meter_rate(self.meter_mut(), rate);
+        let meter = self.meter().clone();
+        self.ballistics_mut().process_now(&meter);
     }
 
     fn measure_states(
@@ -325,40 +546,152 @@ pub trait FormerMeterCtlOperation<T: RmeFfFormerMeterOperation> {
         req: &mut FwReq,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        T::read_meter(req, &mut unit.get_node(), self.meter_mut(), timeout_ms)
+        T::read_meter(req, &mut unit.get_node(), self.meter_mut(), timeout_ms)?;
+        let meter = self.meter().clone();
+        self.ballistics_mut().process_now(&meter);
+        Ok(())
     }
 
     fn measure_elem(&self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<bool, Error> {
+        // The raw state is still available by meter().
+        let meter = self.ballistics().processed();
         match elem_id.get_name().as_str() {
             ANALOG_INPUT_NAME => {
-                elem_value.set_int(&self.meter().analog_inputs);
+                elem_value.set_int(&meter.analog_inputs);
                 Ok(true)
             }
             SPDIF_INPUT_NAME => {
-                elem_value.set_int(&self.meter().spdif_inputs);
+                elem_value.set_int(&meter.spdif_inputs);
                 Ok(true)
             }
             ADAT_INPUT_NAME => {
-                elem_value.set_int(&self.meter().adat_inputs);
+                elem_value.set_int(&meter.adat_inputs);
                 Ok(true)
             }
             STREAM_INPUT_NAME => {
-                elem_value.set_int(&self.meter().stream_inputs);
+                elem_value.set_int(&meter.stream_inputs);
                 Ok(true)
             }
             ANALOG_OUTPUT_NAME => {
-                elem_value.set_int(&self.meter().analog_outputs);
+                elem_value.set_int(&meter.analog_outputs);
                 Ok(true)
             }
             SPDIF_OUTPUT_NAME => {
-                elem_value.set_int(&self.meter().spdif_outputs);
+                elem_value.set_int(&meter.spdif_outputs);
                 Ok(true)
             }
             ADAT_OUTPUT_NAME => {
-                elem_value.set_int(&self.meter().adat_outputs);
+                elem_value.set_int(&meter.adat_outputs);
+                Ok(true)
+            }
+            ADAT_ACTIVE_NAME => {
+                elem_value.set_bool(&self.meter().adat_actives);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn read(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            HOLD_MS_NAME => {
+                elem_value.set_int(&[self.ballistics().hold_ms as i32]);
+                Ok(true)
+            }
+            DECAY_NAME => {
+                elem_value.set_int(&[self.ballistics().decay_db_per_sec as i32]);
                 Ok(true)
             }
             _ => Ok(false),
         }
     }
+
+    fn write(&mut self, elem_id: &ElemId, elem_value: &ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            HOLD_MS_NAME => {
+                let mut vals = [0];
+                elem_value.get_int(&mut vals);
+                self.ballistics_mut().hold_ms = vals[0] as u32;
+                Ok(true)
+            }
+            DECAY_NAME => {
+                let mut vals = [0];
+                elem_value.get_int(&mut vals);
+                self.ballistics_mut().decay_db_per_sec = vals[0] as u32;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ff_protocols::former::ff400::Ff400Protocol;
+
+    #[derive(Default)]
+    struct TestOutputCtl(FormerOutputVolumeState, FormerOutputMonitor);
+
+    impl FormerOutputCtlOperation<Ff400Protocol> for TestOutputCtl {
+        fn state(&self) -> &FormerOutputVolumeState {
+            &self.0
+        }
+
+        fn state_mut(&mut self) -> &mut FormerOutputVolumeState {
+            &mut self.0
+        }
+
+        fn monitor(&self) -> &FormerOutputMonitor {
+            &self.1
+        }
+
+        fn monitor_mut(&mut self) -> &mut FormerOutputMonitor {
+            &mut self.1
+        }
+    }
+
+    #[test]
+    fn test_initial_vols() {
+        // The default without stored volumes.
+        let vols = TestOutputCtl::initial_vols(None, 3);
+        assert_eq!(vols, vec![Ff400Protocol::VOL_ZERO; 3]);
+
+        // The stored volumes are restored instead of the default.
+        let text = TestOutputCtl::serialize_vols(&[0x1000, Ff400Protocol::VOL_MIN, 0x3000]);
+        assert_eq!(TestOutputCtl::initial_vols(Some(&text), 3),
+                   vec![0x1000, Ff400Protocol::VOL_MIN, 0x3000]);
+
+        // The stored volumes are ignored for the different number of outputs or invalid value.
+        assert_eq!(TestOutputCtl::initial_vols(Some(&text), 4), vec![Ff400Protocol::VOL_ZERO; 4]);
+        let text = format!("0\n{}\n0\n", Ff400Protocol::VOL_MAX + 1);
+        assert_eq!(TestOutputCtl::initial_vols(Some(&text), 3), vec![Ff400Protocol::VOL_ZERO; 3]);
+        assert_eq!(TestOutputCtl::initial_vols(Some("0\nfoo\n0\n"), 3),
+                   vec![Ff400Protocol::VOL_ZERO; 3]);
+    }
+
+    #[test]
+    fn test_attenuate_vols() {
+        let requested = [0x1000, 0x2000, 0x3000];
+        let mut monitor = MonitorSectionState::default();
+
+        // The requested volumes as they are.
+        assert_eq!(TestOutputCtl::attenuate_vols(&requested, &monitor), requested.to_vec());
+
+        // Just the main pair is attenuated.
+        monitor.dim = true;
+        monitor.dim_level = -20;
+        assert_eq!(TestOutputCtl::attenuate_vols(&requested, &monitor),
+                   vec![0x019a, 0x0333, 0x3000]);
+
+        monitor.mute = true;
+        assert_eq!(TestOutputCtl::attenuate_vols(&requested, &monitor),
+                   vec![Ff400Protocol::VOL_MIN, Ff400Protocol::VOL_MIN, 0x3000]);
+
+        // The newest requested volumes are restored.
+        monitor.dim = false;
+        monitor.mute = false;
+        let requested = [0x4000, 0x2000, 0x3000];
+        assert_eq!(TestOutputCtl::attenuate_vols(&requested, &monitor), requested.to_vec());
+    }
 }
@@ -26,25 +26,53 @@ use alsactl::{CardExt, CardExtManual, ElemId, ElemIfaceType, ElemValueExtManual}
 use core::RuntimeOperation;
 use core::dispatcher;
 use core::card_cntr;
+use core::version::*;
+use core::channel_name::ChannelNameCtl;
+use core::burst::{BurstDetector, SystemClock};
+use core::event_queue::*;
+use core::config::*;
+use core::log::*;
+use core::{log_info, log_warn};
 
 use model::FfModel;
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem(alsactl::ElemId, alsactl::ElemEventMask),
     Timer,
+    StatusPoll,
+    Burst,
+}
+
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&alsactl::ElemId> {
+        match self {
+            Event::Elem(elem_id, events) if *events == alsactl::ElemEventMask::VALUE => {
+                Some(elem_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
 }
 
 pub struct FfRuntime{
     unit: SndUnit,
     model: FfModel,
     card_cntr: card_cntr::CardCntr,
-    rx: mpsc::Receiver<Event>,
+    channel_names: ChannelNameCtl,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<dispatcher::Dispatcher>,
     timer: Option<dispatcher::Dispatcher>,
+    status_timer: Option<dispatcher::Dispatcher>,
+    burst_timer: Option<dispatcher::Dispatcher>,
 }
 
 impl RuntimeOperation<u32> for FfRuntime {
@@ -60,12 +88,18 @@ impl RuntimeOperation<u32> for FfRuntime {
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         let dispatchers = Vec::new();
 
         let timer = None;
+        let status_timer = None;
+        let burst_timer = None;
+
+        let channel_names = Default::default();
 
-        Ok(FfRuntime{unit, model, card_cntr, rx, tx, dispatchers, timer})
+        Ok(FfRuntime{unit, model, card_cntr, channel_names, rx, tx, dispatchers, timer, status_timer,
+                     burst_timer})
     }
 
     fn listen(&mut self) -> Result<(), Error> {
@@ -74,11 +108,24 @@ impl RuntimeOperation<u32> for FfRuntime {
 
         self.model.load(&mut self.unit, &mut self.card_cntr)?;
 
+        if self.model.has_status_poll() {
+            self.start_status_timer()?;
+        }
+
         if self.model.measured_elem_list.len() > 0 {
             let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, Self::TIMER_NAME, 0);
             let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         }
 
+        let (inputs, outputs) = self.model.channel_names();
+        self.channel_names.load(&mut self.card_cntr, self.model.guid, inputs, outputs)?;
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            ff_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -87,14 +134,27 @@ impl RuntimeOperation<u32> for FfRuntime {
             if let Ok(ev) = self.rx.recv() {
                 match ev {
                     Event::Shutdown => break,
+                    Event::Reload => self.reload(),
                     Event::Disconnected => break,
                     Event::BusReset(generation) => {
-                        println!("IEEE 1394 bus is updated: {}", generation);
+                        log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                     }
                     Event::Elem(elem_id, events) => {
+                        match self.channel_names.handle_elem_event(&mut self.card_cntr, &elem_id) {
+                            Ok(true) => continue,
+                            Ok(false) => (),
+                            Err(e) => {
+                                log_warn!(LOG_RUNTIME, "{}", e);
+                                continue;
+                            }
+                        }
+
                         if elem_id.get_name() != Self::TIMER_NAME {
                             let _ = self.model.dispatch_elem_event(&mut self.unit, &mut self.card_cntr,
                                                                    &elem_id, &events);
+                            if self.model.is_burst_active() && self.burst_timer.is_none() {
+                                let _ = self.start_burst_timer();
+                            }
                         } else {
                             let mut elem_value = alsactl::ElemValue::new();
                             let _ = self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value)
@@ -112,6 +172,19 @@ impl RuntimeOperation<u32> for FfRuntime {
                     Event::Timer => {
                         let _ = self.model.measure_elems(&mut self.unit, &mut self.card_cntr);
                     }
+                    Event::StatusPoll => {
+                        if let Err(e) = self.model.poll_status(&mut self.unit, &mut self.card_cntr) {
+                            log_warn!(LOG_RUNTIME, "Fail to poll status: {}", e);
+                        }
+                    }
+                    Event::Burst => {
+                        if let Err(e) = self.model.flush_batch_if_finished(&mut self.unit) {
+                            log_warn!(LOG_RUNTIME, "Fail to flush batched writes: {}", e);
+                        }
+                        if !self.model.is_burst_active() {
+                            self.burst_timer = None;
+                        }
+                    }
                 }
             }
         }
@@ -122,15 +195,32 @@ impl RuntimeOperation<u32> for FfRuntime {
 impl Drop for FfRuntime {
     fn drop(&mut self) {
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.timer.iter_mut()
+            .chain(self.status_timer.iter_mut())
+            .chain(self.burst_timer.iter_mut())
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timers and the dispatcher for signal handler are
+        // finished at first, then the dispatcher for node is finished at last.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.status_timer.take())
+            .chain(self.burst_timer.take())
+            .chain(self.dispatchers.drain(..).rev());
+        dispatcher::join_dispatchers(dispatchers);
+    }
+}
+
+impl ConfigReloadOperation for FfRuntime {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
     }
 }
 
@@ -138,9 +228,14 @@ impl<'a> FfRuntime {
     const NODE_DISPATCHER_NAME: &'a str = "node event dispatcher";
     const SYSTEM_DISPATCHER_NAME: &'a str = "system event dispatcher";
     const TIMER_DISPATCHER_NAME: &'a str = "interval timer dispatcher";
+    const BURST_DISPATCHER_NAME: &'a str = "burst timer dispatcher";
+    const STATUS_DISPATCHER_NAME: &'a str = "status timer dispatcher";
 
     const TIMER_NAME: &'a str = "metering";
     const TIMER_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+    // The status is polled regardless of metering, to follow the change of sampling rate
+    // initiated by the unit itself.
+    const STATUS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
 
     fn launch_node_event_dispatcher(&mut self) -> Result<(), Error> {
         let name = Self::NODE_DISPATCHER_NAME.to_string();
@@ -176,6 +271,11 @@ impl<'a> FfRuntime {
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -191,7 +291,7 @@ impl<'a> FfRuntime {
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = dispatcher::Dispatcher::run(Self::TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(Self::TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(Self::TIMER_INTERVAL), move || {
             let _ = tx.send(Event::Timer);
             source::Continue(true)
         });
@@ -202,9 +302,32 @@ impl<'a> FfRuntime {
     }
 
     fn stop_interval_timer(&mut self) {
-        if let Some(dispatcher) = &self.timer {
-            drop(dispatcher);
-            self.timer = None;
-        }
+        dispatcher::join_dispatchers(self.timer.take());
+    }
+
+    fn start_status_timer(&mut self) -> Result<(), Error> {
+        let mut dispatcher = dispatcher::Dispatcher::run(Self::STATUS_DISPATCHER_NAME.to_string())?;
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(Self::STATUS_POLL_INTERVAL, move || {
+            let _ = tx.send(Event::StatusPoll);
+            source::Continue(true)
+        });
+
+        self.status_timer = Some(dispatcher);
+
+        Ok(())
+    }
+
+    fn start_burst_timer(&mut self) -> Result<(), Error> {
+        let mut dispatcher = dispatcher::Dispatcher::run(Self::BURST_DISPATCHER_NAME.to_string())?;
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(BurstDetector::<SystemClock>::INTERVAL, move || {
+            let _ = tx.send(Event::Burst);
+            source::Continue(true)
+        });
+
+        self.burst_timer = Some(dispatcher);
+
+        Ok(())
     }
 }
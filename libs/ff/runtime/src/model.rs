@@ -2,14 +2,17 @@
 // Copyright (c) 2021 Takashi Sakamoto
 use glib::{Error, FileError};
 
+use alsactl::{ElemId, ElemIfaceType};
+
 use hinawa::FwNodeExtManual;
 use hinawa::{SndUnit, SndUnitExt};
 
-use core::card_cntr::*;
+use core::{burst::*, card_cntr::*, channel_name::*};
 
 use ieee1212_config_rom::*;
 
-use ff_protocols::{*, former::*, latter::*};
+use ff_protocols::{*, former::{*, ff400::Ff400Protocol, ff800::Ff800Protocol}};
+use ff_protocols::latter::{*, ff802::Ff802Protocol, ucx::FfUcxProtocol};
 
 use super::ff800_model::*;
 use super::ff400_model::*;
@@ -28,6 +31,9 @@ pub enum Model {
 pub struct FfModel{
     model: Model,
     pub measured_elem_list: Vec<alsactl::ElemId>,
+    notified_elem_list: Vec<alsactl::ElemId>,
+    pub guid: u64,
+    burst: BurstDetector<SystemClock>,
 }
 
 impl FfModel {
@@ -41,6 +47,8 @@ impl FfModel {
             })?;
         let model_id = config_rom.get_model_id()
             .ok_or_else(|| Error::new(FileError::Nxio, "Unexpected format of configuration ROM"))?;
+        let guid = guid_from_config_rom(&raw)
+            .ok_or_else(|| Error::new(FileError::Nxio, "Unexpected format of configuration ROM"))?;
 
         let model = match model_id {
             0x00000001 => Model::Ff800(Ff800Model::default()),
@@ -51,8 +59,11 @@ impl FfModel {
         };
 
         let measured_elem_list = Vec::new();
+        let notified_elem_list = Vec::new();
+
+        let burst = Default::default();
 
-        Ok(FfModel{model, measured_elem_list})
+        Ok(FfModel{model, measured_elem_list, notified_elem_list, guid, burst})
     }
 
     pub fn load(&mut self, unit: &mut SndUnit, card_cntr: &mut CardCntr) -> Result<(), Error> {
@@ -70,6 +81,12 @@ impl FfModel {
             Model::Ff802(m) => m.get_measure_elem_list(&mut self.measured_elem_list),
         }
 
+        match &mut self.model {
+            Model::Ff800(m) => m.get_notified_elem_list(&mut self.notified_elem_list),
+            Model::Ff400(m) => m.get_notified_elem_list(&mut self.notified_elem_list),
+            Model::Ucx(_) | Model::Ff802(_) => (),
+        }
+
         Ok(())
     }
 
@@ -78,13 +95,51 @@ impl FfModel {
         -> Result<(), Error>
     {
         match &mut self.model {
-            Model::Ff800(m) => card_cntr.dispatch_elem_event(unit, &elem_id, &events, m),
-            Model::Ff400(m) => card_cntr.dispatch_elem_event(unit, &elem_id, &events, m),
+            Model::Ff800(m) => {
+                card_cntr.dispatch_elem_event_with_burst(unit, &elem_id, &events, m, &mut self.burst)
+            }
+            Model::Ff400(m) => {
+                card_cntr.dispatch_elem_event_with_burst(unit, &elem_id, &events, m, &mut self.burst)?;
+                // Read whole status at once for the request by the trigger element or the change
+                // of configuration, then notify the derived elements.
+                if m.refresh_status(unit)? {
+                    card_cntr.dispatch_notification(unit, &false, &self.notified_elem_list, m)?;
+                    notify_rate_change(unit, card_cntr, m)?;
+                }
+                // The gains changed by the link or the selection of output source are notified without
+                // reading status.
+                if m.take_gain_notification() {
+                    card_cntr.dispatch_notification(unit, &false, &self.notified_elem_list, m)?;
+                }
+                Ok(())
+            }
             Model::Ucx(m) => card_cntr.dispatch_elem_event(unit, &elem_id, &events, m),
             Model::Ff802(m) => card_cntr.dispatch_elem_event(unit, &elem_id, &events, m),
         }
     }
 
+    /// Default names of physical input and output channels.
+    pub fn channel_names(&self) -> (Vec<String>, Vec<String>) {
+        match &self.model {
+            Model::Ff800(_) => former_channel_names::<Ff800Protocol>(),
+            Model::Ff400(_) => former_channel_names::<Ff400Protocol>(),
+            Model::Ucx(_) => latter_channel_names::<FfUcxProtocol>(),
+            Model::Ff802(_) => latter_channel_names::<Ff802Protocol>(),
+        }
+    }
+
+    pub fn is_burst_active(&self) -> bool {
+        self.burst.is_active()
+    }
+
+    pub fn flush_batch_if_finished(&mut self, unit: &mut SndUnit) -> Result<(), Error> {
+        match &mut self.model {
+            Model::Ff800(m) => self.burst.flush_if_finished(unit, m),
+            Model::Ff400(m) => self.burst.flush_if_finished(unit, m),
+            Model::Ucx(_) | Model::Ff802(_) => Ok(()),
+        }
+    }
+
     pub fn measure_elems(&mut self, unit: &mut SndUnit, card_cntr: &mut CardCntr)
         -> Result<(), Error>
     {
@@ -95,6 +150,119 @@ impl FfModel {
             Model::Ff802(m) => card_cntr.measure_elems(unit, &self.measured_elem_list, m),
         }
     }
+
+    /// Whether the status should be polled regardless of metering.
+    pub fn has_status_poll(&self) -> bool {
+        matches!(&self.model, Model::Ff800(_) | Model::Ff400(_))
+    }
+
+    /// Read whole status, then notify the derived elements and the elements affected by the
+    /// change of sampling rate.
+    pub fn poll_status(&mut self, unit: &mut SndUnit, card_cntr: &mut CardCntr)
+        -> Result<(), Error>
+    {
+        match &mut self.model {
+            Model::Ff800(m) => {
+                card_cntr.dispatch_notification(unit, &true, &self.notified_elem_list, m)?;
+                notify_rate_change(unit, card_cntr, m)
+            }
+            Model::Ff400(m) => {
+                card_cntr.dispatch_notification(unit, &true, &self.notified_elem_list, m)?;
+                notify_rate_change(unit, card_cntr, m)
+            }
+            Model::Ucx(_) | Model::Ff802(_) => Ok(()),
+        }
+    }
+}
+
+/// The state to detect the change of sampling rate initiated by the unit itself, e.g. when it
+/// follows the rate of external clock source.
+#[derive(Default, Debug)]
+pub struct FormerRateChangeState {
+    pending: bool,
+}
+
+/// The trait to reconcile cached states when the unit changes sampling rate by itself. The
+/// change is detected by the polling of status, instead of the operation to any element.
+pub trait FormerRateChangeOperation {
+    /// The name of elements for configuration rebuilt from status, to be notified.
+    const RATE_AFFECTED_ELEM_NAMES: &'static [&'static str];
+
+    fn rate_change_state_mut(&mut self) -> &mut FormerRateChangeState;
+
+    /// The rate of external clock source and the configured rate in the cached status.
+    fn clk_rates(&self) -> (Option<ClkNominalRate>, ClkNominalRate);
+
+    /// Rebuild the cached configuration by the status just polled, then refresh the layout of
+    /// mixer and the channel counts of meter.
+    fn reconcile_rate(&mut self, unit: &mut SndUnit) -> Result<(), Error>;
+
+    /// Reconcile the cached states when the rates differ from the ones before the polling. The
+    /// polling runs regardless of metering.
+    fn detect_rate_change(
+        &mut self,
+        unit: &mut SndUnit,
+        rates: &(Option<ClkNominalRate>, ClkNominalRate),
+    ) -> Result<(), Error> {
+        if self.clk_rates().ne(rates) {
+            self.reconcile_rate(unit)?;
+            self.rate_change_state_mut().pending = true;
+        }
+        Ok(())
+    }
+
+    /// Take the request to notify the elements affected by the change of rate.
+    fn take_rate_change(&mut self) -> bool {
+        std::mem::take(&mut self.rate_change_state_mut().pending)
+    }
+
+    fn rate_affected_elem_list() -> Vec<ElemId> {
+        Self::RATE_AFFECTED_ELEM_NAMES
+            .iter()
+            .map(|name| ElemId::new_by_name(ElemIfaceType::Card, 0, 0, name, 0))
+            .collect()
+    }
+}
+
+fn notify_rate_change<T>(unit: &mut SndUnit, card_cntr: &mut CardCntr, model: &mut T)
+    -> Result<(), Error>
+    where T: FormerRateChangeOperation + NotifyModel<SndUnit, bool>,
+{
+    if model.take_rate_change() {
+        card_cntr.dispatch_notification(unit, &false, &T::rate_affected_elem_list(), model)
+    } else {
+        Ok(())
+    }
+}
+
+fn former_channel_names<T: RmeFfFormerMeterOperation>() -> (Vec<String>, Vec<String>) {
+    let inputs = numbered_channel_names(&[
+        ("Analog", T::ANALOG_INPUT_COUNT),
+        ("S/PDIF", T::SPDIF_INPUT_COUNT),
+        ("ADAT", T::ADAT_INPUT_COUNT),
+    ]);
+    let outputs = numbered_channel_names(&[
+        ("Analog", T::ANALOG_OUTPUT_COUNT),
+        ("S/PDIF", T::SPDIF_OUTPUT_COUNT),
+        ("ADAT", T::ADAT_OUTPUT_COUNT),
+    ]);
+    (inputs, outputs)
+}
+
+fn latter_channel_names<T: RmeFfLatterMeterOperation>() -> (Vec<String>, Vec<String>) {
+    let inputs = numbered_channel_names(&[
+        ("Line", T::LINE_INPUT_COUNT),
+        ("Mic", T::MIC_INPUT_COUNT),
+        ("S/PDIF", T::SPDIF_INPUT_COUNT),
+        ("ADAT", T::ADAT_INPUT_COUNT),
+    ]);
+    let outputs = numbered_channel_names(&[
+        ("Line", T::LINE_OUTPUT_COUNT),
+        ("Headphone", T::HP_OUTPUT_COUNT),
+        ("S/PDIF", T::SPDIF_OUTPUT_COUNT),
+        ("ADAT", T::ADAT_OUTPUT_COUNT),
+    ]);
+    (inputs, outputs)
 }
 
 pub fn spdif_iface_to_string(iface: &SpdifIface) -> String {
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Abstraction of asynchronous transaction in IEEE 1394 bus.
+//!
+//! The crate includes `FwTransaction` trait to abstract the transaction used by protocol
+//! implementation. The trait is implemented for the pair of `hinawa::FwReq` and `hinawa::FwNode`
+//! to communicate with actual hardware. Additionally, `MockTransaction` is available to test
+//! register-level logic of the protocol implementation without hardware, and `ReplayTransaction`
+//! is available with `replay` feature to serve recorded registers of actual hardware.
+
+use glib::translate::{FromGlibPtrFull, ToGlibPtr};
+use glib::{Error, FileError};
+
+use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(feature = "replay")]
+pub mod replay;
+
+/// The trait for asynchronous transaction to the node.
+pub trait FwTransaction {
+    /// Execute transaction with the code, then wait for response.
+    fn transaction(
+        &mut self,
+        tcode: FwTcode,
+        addr: u64,
+        frame: &mut [u8],
+        timeout_ms: u32,
+    ) -> Result<(), Error>;
+
+    /// Read content from the address. Quadlet request is used for the frame of 4 bytes, else
+    /// block request.
+    fn read(&mut self, addr: u64, frame: &mut [u8], timeout_ms: u32) -> Result<(), Error> {
+        let tcode = if frame.len() == 4 {
+            FwTcode::ReadQuadletRequest
+        } else {
+            FwTcode::ReadBlockRequest
+        };
+        self.transaction(tcode, addr, frame, timeout_ms)
+    }
+
+    /// Write content to the address. Quadlet request is used for the frame of 4 bytes, else
+    /// block request.
+    fn write(&mut self, addr: u64, frame: &mut [u8], timeout_ms: u32) -> Result<(), Error> {
+        let tcode = if frame.len() == 4 {
+            FwTcode::WriteQuadletRequest
+        } else {
+            FwTcode::WriteBlockRequest
+        };
+        self.transaction(tcode, addr, frame, timeout_ms)
+    }
+}
+
+impl<'a> FwTransaction for (&'a FwReq, &'a FwNode) {
+    fn transaction(
+        &mut self,
+        tcode: FwTcode,
+        addr: u64,
+        frame: &mut [u8],
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let len = frame.len();
+        transaction_sync(self.0, self.1, tcode, addr, len, frame, timeout_ms)
+    }
+}
+
+/// Execute transaction by the request to the node, then wait for response. The code of
+/// transaction, the offset, and the length of frame are added to the message of error. Any
+/// protocol implementation should use it instead of `FwReqExtManual::transaction_sync()`.
+pub fn transaction_sync(
+    req: &FwReq,
+    node: &FwNode,
+    tcode: FwTcode,
+    addr: u64,
+    len: usize,
+    frame: &mut [u8],
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    req.transaction_sync(node, tcode, addr, len, frame, effective_timeout_ms(timeout_ms))
+        .map_err(|e| add_transaction_context(e, tcode, addr, len))
+}
+
+static MIN_TIMEOUT_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Configure the minimum timeout in milliseconds for subsequent transactions of the process. The
+/// timeout given by protocol implementation is used when it is longer. Zero means no minimum.
+pub fn set_min_timeout_ms(timeout_ms: u32) {
+    MIN_TIMEOUT_MS.store(timeout_ms, Ordering::Relaxed);
+}
+
+/// The timeout in milliseconds actually used for transaction with the given timeout.
+pub fn effective_timeout_ms(timeout_ms: u32) -> u32 {
+    std::cmp::max(timeout_ms, MIN_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+fn add_transaction_context(e: Error, tcode: FwTcode, addr: u64, len: usize) -> Error {
+    let context = format!("tcode: {:?}, offset: 0x{:012x}, length: {}", tcode, addr, len);
+    add_error_context(e, &context)
+}
+
+/// Add the context to the message of error, so that the failure is distinguishable in log. The
+/// domain and the code of error are kept as is.
+pub fn add_error_context(e: Error, context: &str) -> Error {
+    let msg = format!("{} ({})", e, context);
+    let cmsg = CString::new(msg.replace('\0', "")).unwrap();
+    unsafe {
+        let ptr: *const glib_sys::GError = e.to_glib_none().0;
+        let raw = glib_sys::g_error_new_literal((*ptr).domain, (*ptr).code, cmsg.as_ptr());
+        Error::from_glib_full(raw)
+    }
+}
+
+/// The mock of transaction for tests. The content of frame is kept per address, and the history of
+/// write transaction is kept in order.
+#[derive(Default, Debug)]
+pub struct MockTransaction {
+    pub regs: HashMap<u64, Vec<u8>>,
+    pub writes: Vec<(u64, Vec<u8>)>,
+}
+
+impl MockTransaction {
+    pub fn set_quadlet_be(&mut self, addr: u64, val: u32) {
+        self.regs.insert(addr, val.to_be_bytes().to_vec());
+    }
+
+    pub fn get_quadlet_be(&self, addr: u64) -> Option<u32> {
+        self.regs
+            .get(&addr)
+            .filter(|frame| frame.len() == 4)
+            .map(|frame| {
+                let mut quadlet = [0; 4];
+                quadlet.copy_from_slice(frame);
+                u32::from_be_bytes(quadlet)
+            })
+    }
+}
+
+impl FwTransaction for MockTransaction {
+    fn transaction(
+        &mut self,
+        tcode: FwTcode,
+        addr: u64,
+        frame: &mut [u8],
+        _: u32,
+    ) -> Result<(), Error> {
+        match tcode {
+            FwTcode::ReadQuadletRequest | FwTcode::ReadBlockRequest => self
+                .regs
+                .get(&addr)
+                .filter(|content| content.len() == frame.len())
+                .map(|content| frame.copy_from_slice(content))
+                .ok_or_else(|| {
+                    let msg = format!("No content of {} bytes at {:012x}", frame.len(), addr);
+                    Error::new(FileError::Nxio, &msg)
+                }),
+            FwTcode::WriteQuadletRequest | FwTcode::WriteBlockRequest => {
+                self.regs.insert(addr, frame.to_vec());
+                self.writes.push((addr, frame.to_vec()));
+                Ok(())
+            }
+            _ => {
+                let msg = format!("Unsupported transaction code: {:?}", tcode);
+                Err(Error::new(FileError::Inval, &msg))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hinawa::FwNodeError;
+
+    #[test]
+    fn test_transaction_context() {
+        let e = Error::new(FwNodeError::Disconnected, "Node is disconnected");
+        let e = add_transaction_context(e, FwTcode::ReadQuadletRequest, 0xfffff0000904, 4);
+        assert_eq!(e.kind::<FwNodeError>(), Some(FwNodeError::Disconnected));
+        assert_eq!(
+            e.to_string(),
+            "Node is disconnected (tcode: ReadQuadletRequest, offset: 0xfffff0000904, length: 4)"
+        );
+
+        // The domain of error is kept as well.
+        let e = Error::new(FileError::Nxio, "Timeout");
+        let e = add_transaction_context(e, FwTcode::WriteBlockRequest, 0x100, 8);
+        assert_eq!(e.kind::<FileError>(), Some(FileError::Nxio));
+        assert_eq!(
+            e.to_string(),
+            "Timeout (tcode: WriteBlockRequest, offset: 0x000000000100, length: 8)"
+        );
+    }
+
+    #[test]
+    fn test_min_timeout() {
+        assert_eq!(effective_timeout_ms(20), 20);
+
+        set_min_timeout_ms(100);
+        assert_eq!(effective_timeout_ms(20), 100);
+        assert_eq!(effective_timeout_ms(200), 200);
+
+        set_min_timeout_ms(0);
+        assert_eq!(effective_timeout_ms(20), 20);
+    }
+
+    #[test]
+    fn test_mock() {
+        let mut tx = MockTransaction::default();
+
+        let mut frame = [0; 4];
+        assert!(tx.read(0x100, &mut frame, 100).is_err());
+
+        let mut frame = [0x01, 0x23, 0x45, 0x67];
+        tx.write(0x100, &mut frame, 100).unwrap();
+        assert_eq!(tx.get_quadlet_be(0x100), Some(0x01234567));
+
+        let mut frame = [0; 4];
+        tx.read(0x100, &mut frame, 100).unwrap();
+        assert_eq!(frame, [0x01, 0x23, 0x45, 0x67]);
+
+        let mut frame = [0; 8];
+        assert!(tx.read(0x100, &mut frame, 100).is_err());
+
+        let mut frame = [0x89, 0xab, 0xcd, 0xef];
+        tx.write(0x100, &mut frame, 100).unwrap();
+        assert_eq!(
+            tx.writes,
+            vec![
+                (0x100, vec![0x01, 0x23, 0x45, 0x67]),
+                (0x100, vec![0x89, 0xab, 0xcd, 0xef]),
+            ]
+        );
+    }
+}
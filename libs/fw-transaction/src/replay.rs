@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Replay of recorded registers.
+//!
+//! The module includes `ReplayTransaction` which serves read transaction from the map of
+//! registers, and appends the content of write transaction to log. It is available with `replay`
+//! feature for integration tests without hardware. The map is either recorded with actual hardware
+//! or synthesized from the layout of registers, and the comment in head of map tells which.
+//!
+//! The map is a text. Each line consists of address and content in hexadecimal, like:
+//!
+//! ```text
+//! # Comment.
+//! 0000801c0000: 00 00 00 02 00 00 00 00
+//! ```
+//!
+//! The log is a text as well. Each line consists of "write" keyword, address, and content in the
+//! same format as the map.
+
+use glib::{Error, FileError};
+
+use hinawa::FwTcode;
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use super::FwTransaction;
+
+fn format_entry(addr: u64, content: &[u8]) -> String {
+    let bytes: Vec<String> = content.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{:012x}: {}", addr, bytes.join(" "))
+}
+
+fn parse_entry(line: &str) -> Option<(u64, Vec<u8>)> {
+    let mut parts = line.splitn(2, ':');
+    let addr = u64::from_str_radix(parts.next()?.trim(), 16).ok()?;
+    let content = parts
+        .next()?
+        .split_whitespace()
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if content.is_empty() {
+        None
+    } else {
+        Some((addr, content))
+    }
+}
+
+/// The backend of transaction to replay recorded registers. The read transaction is served by the
+/// recorded content of register which covers the range of frame. The write transaction updates
+/// the content and is appended to log in order, as well as to the log file if given.
+#[derive(Default, Debug)]
+pub struct ReplayTransaction {
+    regs: BTreeMap<u64, Vec<u8>>,
+    log: Vec<(u64, Vec<u8>)>,
+    log_file: Option<File>,
+}
+
+impl ReplayTransaction {
+    /// Parse the text of recorded map.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut tx = Self::default();
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.splitn(2, '#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .try_for_each(|(i, line)| {
+                parse_entry(line)
+                    .map(|(addr, content)| {
+                        tx.regs.insert(addr, content);
+                    })
+                    .ok_or_else(|| {
+                        let msg = format!("Invalid entry of register map at line {}", i + 1);
+                        Error::new(FileError::Inval, &msg)
+                    })
+            })?;
+        Ok(tx)
+    }
+
+    /// Load the recorded map from the file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        std::fs::read_to_string(&path)
+            .map_err(|e| {
+                let msg = format!("Fail to read {}: {}", path.as_ref().display(), e);
+                Error::new(FileError::Io, &msg)
+            })
+            .and_then(|text| Self::parse(&text))
+    }
+
+    /// Append the content of write transaction to the file as well.
+    pub fn append_log_to<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map(|file| self.log_file = Some(file))
+            .map_err(|e| {
+                let msg = format!("Fail to open {}: {}", path.as_ref().display(), e);
+                Error::new(FileError::Io, &msg)
+            })
+    }
+
+    /// The history of write transaction.
+    pub fn log(&self) -> &[(u64, Vec<u8>)] {
+        &self.log
+    }
+
+    /// The history of write transaction in the same format as log file.
+    pub fn log_lines(&self) -> Vec<String> {
+        self.log
+            .iter()
+            .map(|(addr, content)| format!("write {}", format_entry(*addr, content)))
+            .collect()
+    }
+
+    // The recorded register which covers the range.
+    fn covering_mut(&mut self, addr: u64, len: usize) -> Option<&mut [u8]> {
+        self.regs
+            .range_mut(..=addr)
+            .next_back()
+            .and_then(|(&start, content)| {
+                let pos = (addr - start) as usize;
+                if pos + len <= content.len() {
+                    Some(&mut content[pos..(pos + len)])
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn record_write(&mut self, addr: u64, frame: &[u8]) -> Result<(), Error> {
+        match self.covering_mut(addr, frame.len()) {
+            Some(content) => content.copy_from_slice(frame),
+            None => {
+                self.regs.insert(addr, frame.to_vec());
+            }
+        }
+        self.log.push((addr, frame.to_vec()));
+
+        match &mut self.log_file {
+            Some(file) => writeln!(file, "write {}", format_entry(addr, frame)).map_err(|e| {
+                let msg = format!("Fail to write log: {}", e);
+                Error::new(FileError::Io, &msg)
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FwTransaction for ReplayTransaction {
+    fn transaction(
+        &mut self,
+        tcode: FwTcode,
+        addr: u64,
+        frame: &mut [u8],
+        _: u32,
+    ) -> Result<(), Error> {
+        match tcode {
+            FwTcode::ReadQuadletRequest | FwTcode::ReadBlockRequest => self
+                .covering_mut(addr, frame.len())
+                .map(|content| frame.copy_from_slice(content))
+                .ok_or_else(|| {
+                    let msg = format!("No record of {} bytes at {:012x}", frame.len(), addr);
+                    Error::new(FileError::Nxio, &msg)
+                }),
+            FwTcode::WriteQuadletRequest | FwTcode::WriteBlockRequest => {
+                self.record_write(addr, frame)
+            }
+            _ => {
+                let msg = format!("Unsupported transaction code: {:?}", tcode);
+                Err(Error::new(FileError::Inval, &msg))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let text = "# Test.\n\
+                    000000000100: 01 23 45 67 89 ab cd ef\n\
+                    \n\
+                    000000000200: fe dc ba 98 # Trailing comment.\n";
+        let mut tx = ReplayTransaction::parse(text).unwrap();
+
+        let mut frame = [0; 4];
+        tx.read(0x104, &mut frame, 100).unwrap();
+        assert_eq!(frame, [0x89, 0xab, 0xcd, 0xef]);
+
+        let mut frame = [0; 8];
+        assert!(tx.read(0x200, &mut frame, 100).is_err());
+
+        let mut frame = [0x00, 0x11, 0x22, 0x33];
+        tx.write(0x100, &mut frame, 100).unwrap();
+        let mut frame = [0x44, 0x55, 0x66, 0x77];
+        tx.write(0x300, &mut frame, 100).unwrap();
+
+        let mut frame = [0; 8];
+        tx.read(0x100, &mut frame, 100).unwrap();
+        assert_eq!(frame, [0x00, 0x11, 0x22, 0x33, 0x89, 0xab, 0xcd, 0xef]);
+        let mut frame = [0; 4];
+        tx.read(0x300, &mut frame, 100).unwrap();
+        assert_eq!(frame, [0x44, 0x55, 0x66, 0x77]);
+
+        assert_eq!(
+            tx.log_lines(),
+            vec![
+                "write 000000000100: 00 11 22 33",
+                "write 000000000300: 44 55 66 77",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_map() {
+        assert!(ReplayTransaction::parse("000000000100 01 23").is_err());
+        assert!(ReplayTransaction::parse("000000000100:").is_err());
+        assert!(ReplayTransaction::parse("000000000100: 0g").is_err());
+    }
+}
@@ -6,12 +6,17 @@
 //! The module includes structure, enumeration, and trait for hardware mixer function operated by
 //! command.
 
-use glib::Error;
+use glib::{Error, FileError};
 
-use hinawa::{FwNode, FwNodeExt, FwReq, FwReqExtManual, FwResp, FwRespExt, FwTcode};
+use hinawa::{FwNode, FwNodeExt, FwReq, FwResp, FwRespExt, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use crate::*;
 
+use super::register_dsp::{RegisterDspMixerMonauralSourceState, RegisterDspMixerOutputState};
+use super::trace::{record_result, record_transaction, TraceDirection};
+
 const DSP_CMD_OFFSET: u64 = 0xffff00010000;
 const DSP_MSG_DST_HIGH_OFFSET: u32 = 0x0b38;
 const DSP_MSG_DST_LOW_OFFSET: u32 = 0x0b3c;
@@ -30,6 +35,8 @@ const CMD_RESOURCE_LENGTH: usize = 6;
 const CMD_BYTE_SINGLE_LENGTH: usize = 6;
 const CMD_QUADLET_SINGLE_LENGTH: usize = 9;
 
+const CMD_RESOURCE_IDENTIFIER: u8 = 0x11;
+
 const MSG_DST_OFFSET_BEGIN: u64 = 0xffffe0000000;
 const MSG_DST_OFFSET_END: u64 = MSG_DST_OFFSET_BEGIN + 0x10000000;
 
@@ -377,7 +384,23 @@ fn to_usize(raw: &[u8]) -> usize {
 }
 
 fn to_i32(raw: &[u8]) -> i32 {
-    to_f32(raw) as i32
+    to_f32(raw).round() as i32
+}
+
+fn to_i32_saturated(raw: &[u8], min: i32, max: i32) -> i32 {
+    to_i32(raw).max(min).min(max)
+}
+
+fn to_input_gain(raw: &[u8]) -> i32 {
+    to_i32_saturated(raw, InputCmd::GAIN_MIN, InputCmd::GAIN_MAX)
+}
+
+fn to_comp_threshold(raw: &[u8]) -> i32 {
+    to_i32_saturated(raw, DynamicsParameter::THRESHOLD_MIN, DynamicsParameter::THRESHOLD_MAX)
+}
+
+fn to_shelf_filter_attenuation(raw: &[u8]) -> i32 {
+    to_i32_saturated(raw, ReverbCmd::SHELF_FILTER_ATTR_MIN, ReverbCmd::SHELF_FILTER_ATTR_MAX)
 }
 
 fn to_f32(raw: &[u8]) -> f32 {
@@ -508,6 +531,9 @@ pub enum InputCmd {
 }
 
 impl InputCmd {
+    pub const GAIN_MIN: i32 = -96;
+    pub const GAIN_MAX: i32 = 22;
+
     fn parse(identifier: &[u8], vals: &[u8]) -> Self {
         assert_eq!(identifier.len(), 4);
         assert!(vals.len() > 0);
@@ -517,7 +543,7 @@ impl InputCmd {
         match (identifier[3], identifier[2], identifier[1]) {
             (0x01, 0x00, 0x00) => InputCmd::Phase(ch,  to_bool(vals)),
             (0x01, 0x00, 0x01) => InputCmd::Pair(ch, to_bool(vals)),
-            (0x01, 0x00, 0x02) => InputCmd::Gain(ch, to_i32(vals)),
+            (0x01, 0x00, 0x02) => InputCmd::Gain(ch, to_input_gain(vals)),
             (0x01, 0x00, 0x03) => InputCmd::Swap(ch, to_bool(vals)),
             (0x01, 0x00, 0x04) => InputCmd::StereoMode(ch, InputStereoPairMode::from(vals[0])),
             (0x01, 0x00, 0x05) => InputCmd::Width(ch, to_f32(vals)),
@@ -570,7 +596,7 @@ impl InputCmd {
             (0x01, 0x09, 0x00) => InputCmd::Dynamics(ch, DynamicsParameter::Enable(to_bool(vals))),
 
             (0x01, 0x0a, 0x00) => InputCmd::Dynamics(ch, DynamicsParameter::CompEnable(to_bool(vals))),
-            (0x01, 0x0a, 0x01) => InputCmd::Dynamics(ch, DynamicsParameter::CompThreshold(to_i32(vals))),
+            (0x01, 0x0a, 0x01) => InputCmd::Dynamics(ch, DynamicsParameter::CompThreshold(to_comp_threshold(vals))),
             (0x01, 0x0a, 0x02) => InputCmd::Dynamics(ch, DynamicsParameter::CompRatio(to_f32(vals))),
             (0x01, 0x0a, 0x03) => InputCmd::Dynamics(ch, DynamicsParameter::CompAttack(to_u32(vals))),
             (0x01, 0x0a, 0x04) => InputCmd::Dynamics(ch, DynamicsParameter::CompRelease(to_u32(vals))),
@@ -833,7 +859,7 @@ impl OutputCmd {
             (0x03, 0x08, 0x00) => OutputCmd::Dynamics(ch, DynamicsParameter::Enable(to_bool(vals))),
 
             (0x03, 0x09, 0x00) => OutputCmd::Dynamics(ch, DynamicsParameter::CompEnable(to_bool(vals))),
-            (0x03, 0x09, 0x01) => OutputCmd::Dynamics(ch, DynamicsParameter::CompThreshold(to_i32(vals))),
+            (0x03, 0x09, 0x01) => OutputCmd::Dynamics(ch, DynamicsParameter::CompThreshold(to_comp_threshold(vals))),
             (0x03, 0x09, 0x02) => OutputCmd::Dynamics(ch, DynamicsParameter::CompRatio(to_f32(vals))),
             (0x03, 0x09, 0x03) => OutputCmd::Dynamics(ch, DynamicsParameter::CompAttack(to_u32(vals))),
             (0x03, 0x09, 0x04) => OutputCmd::Dynamics(ch, DynamicsParameter::CompRelease(to_u32(vals))),
@@ -1024,6 +1050,9 @@ pub enum ReverbCmd {
 }
 
 impl ReverbCmd {
+    pub const SHELF_FILTER_ATTR_MIN: i32 = -40;
+    pub const SHELF_FILTER_ATTR_MAX: i32 = 0;
+
     fn parse(identifier: &[u8], vals: &[u8]) -> Self {
         assert_eq!(identifier.len(), 4);
         assert!(vals.len() > 0);
@@ -1033,7 +1062,7 @@ impl ReverbCmd {
             (0x04, 0x00, 0x01) => ReverbCmd::Split(SplitPoint::from(vals[0])),
             (0x04, 0x00, 0x02) => ReverbCmd::PreDelay(to_u32(vals)),
             (0x04, 0x00, 0x03) => ReverbCmd::ShelfFilterFreq(to_u32(vals)),
-            (0x04, 0x00, 0x04) => ReverbCmd::ShelfFilterAttenuation(to_i32(vals)),
+            (0x04, 0x00, 0x04) => ReverbCmd::ShelfFilterAttenuation(to_shelf_filter_attenuation(vals)),
             (0x04, 0x00, 0x05) => ReverbCmd::DecayTime(to_u32(vals)),
             (0x04, 0x00, 0x06) => ReverbCmd::LowFreqTime(to_u32(vals)),
             (0x04, 0x00, 0x07) => ReverbCmd::MiddleFreqTime(to_u32(vals)),
@@ -1082,6 +1111,11 @@ impl ResourceCmd {
     pub const USAGE_MAX: f32 = 100.0;
 
     fn parse(raw: &[u8]) -> Self {
+        // The command with unknown identifier is kept as is to rebuild the same bytes.
+        if raw[5] != CMD_RESOURCE_IDENTIFIER {
+            return ResourceCmd::Reserved(raw[..CMD_RESOURCE_LENGTH].to_vec());
+        }
+
         let mut quadlet = [0; 4];
         quadlet.copy_from_slice(&raw[1..5]);
         ResourceCmd::Usage(f32::from_le_bytes(quadlet), raw[5])
@@ -1170,7 +1204,23 @@ impl DspCmd {
     // identifier[2]: second level; e.g. 0x03 for low frequency filter.
     // identifier[3]: 0x01: first level
     //
+    /// Parse the first command in the bytes and return the length of parsed bytes. The command
+    /// truncated or unknown is not parsed, then zero is returned.
     pub fn parse(raw: &[u8], cmds: &mut Vec<DspCmd>) -> usize {
+        let length = match raw.first() {
+            Some(&CMD_RESOURCE) => CMD_RESOURCE_LENGTH,
+            Some(&CMD_BYTE_MULTIPLE) if raw.len() > 1 => 6 + raw[1] as usize,
+            Some(&CMD_QUADLET_MULTIPLE) if raw.len() > 1 => 6 + raw[1] as usize * 4,
+            Some(&CMD_DRAIN) => 1,
+            Some(&CMD_END) => raw.len(),
+            Some(&CMD_BYTE_SINGLE) => CMD_BYTE_SINGLE_LENGTH,
+            Some(&CMD_QUADLET_SINGLE) => CMD_QUADLET_SINGLE_LENGTH,
+            _ => 0,
+        };
+        if length == 0 || raw.len() < length {
+            return 0;
+        }
+
         match raw[0] {
             CMD_RESOURCE => {
                 let r = &raw[..CMD_RESOURCE_LENGTH];
@@ -1181,7 +1231,6 @@ impl DspCmd {
             }
             CMD_BYTE_MULTIPLE => {
                 let count = raw[1] as usize;
-                let length = 6 + count;
 
                 let mut identifier = [0; 4];
                 identifier.copy_from_slice(&raw[2..6]);
@@ -1211,7 +1260,6 @@ impl DspCmd {
             }
             CMD_QUADLET_MULTIPLE => {
                 let count = raw[1] as usize;
-                let length = 6 + count * 4;
 
                 let mut identifier = [0; 4];
                 identifier.copy_from_slice(&raw[2..6]);
@@ -1237,7 +1285,7 @@ impl DspCmd {
                     cmds.push(cmd);
                 }
 
-                6 + count * 4
+                length
             }
             CMD_DRAIN => 1,
             CMD_END => raw.len(),
@@ -1356,14 +1404,18 @@ fn send_message(
             frame.push(0x00);
         }
 
-        req.transaction_sync(
+        let res = transaction_sync(
+            req,
             node,
             FwTcode::WriteBlockRequest,
             DSP_CMD_OFFSET,
             frame.len(),
             &mut frame,
             timeout_ms
-        )?;
+        );
+        record_transaction(TraceDirection::Message, DSP_CMD_OFFSET, &frame, &res);
+        record_result(&res);
+        res?;
 
         *sequence_number += 1;
         *sequence_number %= 0xff;
@@ -1374,6 +1426,11 @@ fn send_message(
     Ok(())
 }
 
+fn message_destination_address(resp: &FwResp, node: &FwNode) -> u64 {
+    let local_node_id = node.get_property_local_node_id() as u64;
+    (local_node_id << 48) | resp.get_property_offset()
+}
+
 /// The trait for operation of command DSP.
 pub trait CommandDspOperation {
     fn send_commands(
@@ -1403,8 +1460,7 @@ pub trait CommandDspOperation {
             )?;
         }
 
-        let local_node_id = node.get_property_local_node_id() as u64;
-        let addr = (local_node_id << 48) | resp.get_property_offset();
+        let addr = message_destination_address(resp, node);
 
         let high = (addr >> 32) as u32;
         write_quad(req, node, DSP_MSG_DST_HIGH_OFFSET, high, timeout_ms)?;
@@ -1415,6 +1471,28 @@ pub trait CommandDspOperation {
         Ok(())
     }
 
+    /// Check the destination address of message registered in the unit, then register it again
+    /// when it differs from the expected one. The unit clears it when rebooting its firmware, e.g.
+    /// at the change of sampling transfer frequency between 1x and 2x. Return true when registered
+    /// again.
+    fn ensure_message_destination_address(
+        resp: &mut FwResp,
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms:u32
+    ) -> Result<bool, Error> {
+        if resp.get_property_is_reserved() {
+            let high = read_quad(req, node, DSP_MSG_DST_HIGH_OFFSET, timeout_ms)?;
+            let low = read_quad(req, node, DSP_MSG_DST_LOW_OFFSET, timeout_ms)?;
+            let addr = ((high as u64) << 32) | (low as u64);
+            if addr == message_destination_address(resp, node) {
+                return Ok(false);
+            }
+        }
+
+        Self::register_message_destination_address(resp, req, node, timeout_ms).map(|_| true)
+    }
+
     fn begin_messaging(
         req: &mut FwReq,
         node: &mut FwNode,
@@ -1463,6 +1541,8 @@ pub struct CommandDspMessageHandler {
     state: ParserState,
     cache: Vec<u8>,
     seq_num: u8,
+    // The offset of truncated message in the cache.
+    msg_offset: usize,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -1470,6 +1550,8 @@ enum ParserState {
     Initialized,
     Prepared,
     InTruncatedMessage,
+    // Waiting for the end of message whose fragment is lost.
+    Resynchronizing,
 }
 
 impl Default for CommandDspMessageHandler {
@@ -1478,6 +1560,7 @@ impl Default for CommandDspMessageHandler {
             state: ParserState::Initialized,
             cache: Vec::with_capacity(MAXIMUM_DSP_FRAME_SIZE + 6),
             seq_num: 0,
+            msg_offset: 0,
         }
     }
 }
@@ -1497,7 +1580,7 @@ fn remove_padding(cache: &mut Vec<u8>) {
             CMD_BYTE_SINGLE => CMD_BYTE_SINGLE_LENGTH,
             _ => 0,
         };
-        if length == 0 {
+        if length == 0 || length > buf.len() {
             break;
         }
 
@@ -1508,6 +1591,10 @@ fn remove_padding(cache: &mut Vec<u8>) {
     let _ = cache.drain(count..);
 }
 
+fn is_active_sensing(frame: &[u8]) -> bool {
+    frame.len() <= 4 && frame.iter().skip(2).all(|&b| b == 0x00)
+}
+
 fn increment_seq_num(seq_num: u8) -> u8 {
     if seq_num == u8::MAX {
         0
@@ -1529,9 +1616,20 @@ impl CommandDspMessageHandler {
     // 1: sequence number, incremented within 1 byte.
     //
     // When message is split to several transactions due to maximum length of frame (248 bytes),
-    // Type 1 message is not delivered between subsequent transactions.
+    // Type 1 message is usually not delivered between subsequent transactions. However, it is
+    // occasionally delivered between them. Such message is just skipped. Its payload consists of
+    // padding bytes only, thus the last fragment of truncated message can not be distinguished
+    // from it when the fragment has two or less bytes of zero.
+    //
+    // When any fragment of truncated message is lost, the rest of message is discarded. The
+    // messages cached already are kept.
     //
     pub fn cache_dsp_messages(&mut self, frame: &[u8]) {
+        // The frame without prefixes is just ignored.
+        if frame.len() < 2 {
+            return;
+        }
+
         let seq_num = frame[1];
 
         if self.state == ParserState::Initialized {
@@ -1539,12 +1637,25 @@ impl CommandDspMessageHandler {
             self.state = ParserState::Prepared;
         }
 
-        if self.seq_num == seq_num {
-            self.seq_num = increment_seq_num(seq_num);
+        if (self.state == ParserState::InTruncatedMessage ||
+            self.state == ParserState::Resynchronizing) && is_active_sensing(frame) {
+            if self.seq_num == seq_num {
+                self.seq_num = increment_seq_num(seq_num);
+            }
+            return;
+        }
 
-            if self.state == ParserState::Prepared {
+        if self.seq_num != seq_num && self.state == ParserState::InTruncatedMessage {
+            self.cache.truncate(self.msg_offset);
+            self.state = ParserState::Resynchronizing;
+        }
+        self.seq_num = increment_seq_num(seq_num);
+
+        match self.state {
+            ParserState::Prepared => {
                 // Check the type of first command in the message.
                 if frame.len() > 4 && frame[2] != 0x00 {
+                    self.msg_offset = self.cache.len();
                     self.cache.extend_from_slice(&frame[2..]);
 
                     if frame.len() == MAXIMUM_DSP_FRAME_SIZE {
@@ -1553,7 +1664,8 @@ impl CommandDspMessageHandler {
                         remove_padding(&mut self.cache);
                     }
                 }
-            } else if self.state == ParserState::InTruncatedMessage {
+            }
+            ParserState::InTruncatedMessage => {
                 self.cache.extend_from_slice(&frame[2..]);
 
                 if frame.len() < MAXIMUM_DSP_FRAME_SIZE {
@@ -1561,15 +1673,18 @@ impl CommandDspMessageHandler {
                     self.state = ParserState::Prepared;
                 }
             }
-        } else {
-            self.cache.clear();
-            self.state = ParserState::Prepared;
+            ParserState::Resynchronizing => {
+                if frame.len() < MAXIMUM_DSP_FRAME_SIZE {
+                    self.state = ParserState::Prepared;
+                }
+            }
+            ParserState::Initialized => (),
         }
     }
 
 
     pub fn has_dsp_message(&self) -> bool {
-        self.cache.len() > 0 && (self.state == ParserState::Prepared)
+        self.cache.len() > 0 && (self.state != ParserState::InTruncatedMessage)
     }
 
     pub fn decode_messages(&mut self) -> Vec<DspCmd> {
@@ -1578,6 +1693,8 @@ impl CommandDspMessageHandler {
         while self.cache.len() > 0 {
             let consumed = DspCmd::parse(&self.cache, &mut cmds);
             if consumed == 0 {
+                // The malformed content is discarded to resynchronize at next message.
+                self.cache.clear();
                 break;
             }
 
@@ -1646,6 +1763,111 @@ fn parse_reverb_command(state: &mut CommandDspReverbState, cmd: &ReverbCmd) {
     }
 }
 
+fn diff_reverb_commands(
+    state: &CommandDspReverbState,
+    old: &CommandDspReverbState,
+) -> Vec<DspCmd> {
+    let mut new_cmds = create_reverb_command(state);
+    let old_cmds = create_reverb_command(old);
+    new_cmds.retain(|cmd| old_cmds.iter().find(|c| c.eq(&cmd)).is_none());
+    new_cmds
+}
+
+fn interpolate_u32(origin: u32, target: u32, step: usize, count: usize) -> u32 {
+    let diff = target as i64 - origin as i64;
+    (origin as i64 + diff * step as i64 / count as i64) as u32
+}
+
+fn interpolate_i32(origin: i32, target: i32, step: usize, count: usize) -> i32 {
+    let diff = target as i64 - origin as i64;
+    (origin as i64 + diff * step as i64 / count as i64) as i32
+}
+
+fn interpolate_f32(origin: f32, target: f32, step: usize, count: usize) -> f32 {
+    if step == count {
+        target
+    } else {
+        origin + (target - origin) * step as f32 / count as f32
+    }
+}
+
+/// The ramp to change parameters of reverb effect by several steps, to avoid discontinuity of
+/// sound. The parameters of integer and floating point number are interpolated linearly, while
+/// the parameters of boolean and enumeration are changed at the first step.
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct CommandDspReverbRamp {
+    origin: CommandDspReverbState,
+    target: CommandDspReverbState,
+    step_count: usize,
+    step: usize,
+}
+
+impl CommandDspReverbRamp {
+    /// Start ramp from the current state to the target state. The ramp in progress is retargeted
+    /// from the current state.
+    pub fn start(
+        &mut self,
+        current: &CommandDspReverbState,
+        target: &CommandDspReverbState,
+        step_count: usize,
+    ) {
+        self.origin = *current;
+        self.target = *target;
+        self.step_count = step_count.max(1);
+        self.step = 0;
+    }
+
+    /// Abort the ramp in progress.
+    pub fn cancel(&mut self) {
+        self.step = self.step_count;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step < self.step_count
+    }
+
+    pub fn target(&self) -> &CommandDspReverbState {
+        &self.target
+    }
+
+    /// Generate the state for the next step, or None when the ramp is finished.
+    pub fn next_state(&mut self) -> Option<CommandDspReverbState> {
+        if !self.is_active() {
+            return None;
+        }
+
+        self.step += 1;
+        let (o, t, step, count) = (&self.origin, &self.target, self.step, self.step_count);
+
+        let mut state = *t;
+        state.pre_delay = interpolate_u32(o.pre_delay, t.pre_delay, step, count);
+        state.shelf_filter_freq =
+            interpolate_u32(o.shelf_filter_freq, t.shelf_filter_freq, step, count);
+        state.shelf_filter_attenuation = interpolate_i32(
+            o.shelf_filter_attenuation,
+            t.shelf_filter_attenuation,
+            step,
+            count,
+        );
+        state.decay_time = interpolate_u32(o.decay_time, t.decay_time, step, count);
+        state
+            .freq_time
+            .iter_mut()
+            .zip(o.freq_time.iter().zip(t.freq_time.iter()))
+            .for_each(|(s, (&o, &t))| *s = interpolate_u32(o, t, step, count));
+        state
+            .freq_crossover
+            .iter_mut()
+            .zip(o.freq_crossover.iter().zip(t.freq_crossover.iter()))
+            .for_each(|(s, (&o, &t))| *s = interpolate_u32(o, t, step, count));
+        state.width = interpolate_f32(o.width, t.width, step, count);
+        state.reflection_size = interpolate_u32(o.reflection_size, t.reflection_size, step, count);
+        state.reflection_level = interpolate_f32(o.reflection_level, t.reflection_level, step, count);
+
+        Some(state)
+    }
+}
+
 /// The trait for operation of reverb effect.
 pub trait CommandDspReverbOperation : CommandDspOperation {
     const DECAY_TIME_MIN: u32 = 100;
@@ -1660,9 +1882,9 @@ pub trait CommandDspReverbOperation : CommandDspOperation {
     const SHELF_FILTER_FREQ_MAX: u32 = 20000;
     const SHELF_FILTER_FREQ_STEP: u32 = 1;
 
-    const SHELF_FILTER_ATTR_MIN: i32 = -40;
-    const SHELF_FILTER_ATTR_MAX: i32 = 0;
-    const SHELF_FILTER_ATTR_STEP: i32 = 0;
+    const SHELF_FILTER_ATTR_MIN: i32 = ReverbCmd::SHELF_FILTER_ATTR_MIN;
+    const SHELF_FILTER_ATTR_MAX: i32 = ReverbCmd::SHELF_FILTER_ATTR_MAX;
+    const SHELF_FILTER_ATTR_STEP: i32 = 1;
 
     const FREQ_TIME_COUNT: usize = 3;
     const FREQ_TIME_MIN: u32 = 0;
@@ -1705,9 +1927,7 @@ pub trait CommandDspReverbOperation : CommandDspOperation {
         old: &mut CommandDspReverbState,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        let mut new_cmds = create_reverb_command(&state);
-        let old_cmds = create_reverb_command(old);
-        new_cmds.retain(|cmd| old_cmds.iter().find(|c| c.eq(&cmd)).is_none());
+        let new_cmds = diff_reverb_commands(&state, old);
         Self::send_commands(req, node, sequence_number, &new_cmds, timeout_ms).map(|_| *old = state)
     }
 }
@@ -1725,6 +1945,40 @@ pub struct CommandDspMonitorState {
     pub assign_target: TargetPort,
 }
 
+impl CommandDspMonitorState {
+    /// The minimum of main volume in 0.01 dB, except for mute.
+    pub const MAIN_VOLUME_DB_MIN: i32 = -8000;
+    /// The maximum of main volume in 0.01 dB.
+    pub const MAIN_VOLUME_DB_MAX: i32 = 0;
+    /// The value in 0.01 dB to express mute of main volume.
+    pub const MAIN_VOLUME_DB_MUTE: i32 = Self::MAIN_VOLUME_DB_MIN - 1;
+
+    /// Convert linear value of main volume to 0.01 dB, by 2000 * log10(linear). The value less
+    /// than -80.0 dB is regarded as mute.
+    pub fn main_volume_to_db(volume: f32) -> i32 {
+        if volume <= 0.0 {
+            Self::MAIN_VOLUME_DB_MUTE
+        } else {
+            let db = (2000.0 * volume.log10()).round() as i32;
+            if db < Self::MAIN_VOLUME_DB_MIN {
+                Self::MAIN_VOLUME_DB_MUTE
+            } else {
+                db.min(Self::MAIN_VOLUME_DB_MAX)
+            }
+        }
+    }
+
+    /// Convert main volume in 0.01 dB to linear value, by 10 ^ (db / 2000). The value less than
+    /// -80.0 dB results in 0.0 (mute).
+    pub fn main_volume_from_db(db: i32) -> f32 {
+        if db < Self::MAIN_VOLUME_DB_MIN {
+            0.0
+        } else {
+            10f32.powf(db.min(Self::MAIN_VOLUME_DB_MAX) as f32 / 2000.0)
+        }
+    }
+}
+
 fn create_monitor_commands(
     state: &CommandDspMonitorState,
     target_ports: &[TargetPort]
@@ -1800,6 +2054,63 @@ pub trait CommandDspMonitorOperation : CommandDspOperation {
     }
 }
 
+/// The structure for state of main volume dimmed automatically during talkback.
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct CommandDspTalkbackDimState {
+    /// The attenuation of main volume during talkback in dB.
+    pub dim: u32,
+    /// The main volume to be restored when talkback ends, and the dimmed volume.
+    saved: Option<(f32, f32)>,
+}
+
+impl CommandDspTalkbackDimState {
+    pub const DIM_MIN: u32 = 0;
+    pub const DIM_MAX: u32 = 30;
+
+    fn dim_volume(&self, volume: f32) -> f32 {
+        volume * 10f32.powf(-(self.dim as f32) / 20.0)
+    }
+
+    /// Adjust main volume in new state of monitor function according to transition of talkback.
+    /// The new state is either requested by user or notified by the unit.
+    pub fn follow(&mut self, old: &CommandDspMonitorState, new: &mut CommandDspMonitorState) {
+        match (old.talkback_enable, new.talkback_enable) {
+            (false, true) => {
+                let volume = new.main_volume;
+                let dimmed = self.dim_volume(volume);
+                self.saved = Some((volume, dimmed));
+                new.main_volume = dimmed;
+            }
+            (true, false) => {
+                if let Some((volume, dimmed)) = self.saved.take() {
+                    // The newer volume is kept when changed at the same time.
+                    if new.main_volume == old.main_volume || new.main_volume == dimmed {
+                        new.main_volume = volume;
+                    }
+                }
+            }
+            (true, true) => {
+                if let Some((volume, dimmed)) = &mut self.saved {
+                    // The volume equals to the saved one is the echo of the value sent before.
+                    if new.main_volume != old.main_volume
+                        && new.main_volume != *dimmed
+                        && new.main_volume != *volume
+                    {
+                        *volume = new.main_volume;
+                        *dimmed = new.main_volume;
+                    }
+                }
+            }
+            (false, false) => (),
+        }
+    }
+
+    /// Whether main volume is dimmed for talkback.
+    pub fn is_dimmed(&self) -> bool {
+        self.saved.is_some()
+    }
+}
+
 /// The structure for state of entry of mixer function.
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct CommandDspMixerSourceState {
@@ -1860,34 +2171,93 @@ fn create_mixer_commands(
     cmds
 }
 
+// The destination of output is sent as the index of available ports, thus the port out of them
+// is rejected instead of being sent as the first port.
+fn validate_mixer_output_assign(
+    state: &CommandDspMixerState,
+    output_ports: &[TargetPort]
+) -> Result<(), Error> {
+    state.output_assign
+        .iter()
+        .enumerate()
+        .try_for_each(|(mixer, port)| {
+            if output_ports.iter().any(|p| port.eq(p)) {
+                Ok(())
+            } else {
+                let msg = format!(
+                    "Invalid output destination of mixer {}: {:?}, should be one of {} ports",
+                    mixer,
+                    port,
+                    output_ports.len()
+                );
+                Err(Error::new(FileError::Inval, &msg))
+            }
+        })
+}
+
+// The index in the command from the device is not necessarily within the range of state.
+fn update_value<T: Copy>(vals: &mut [T], idx: usize, val: T) -> bool {
+    vals.get_mut(idx).map(|v| *v = val).is_some()
+}
+
 fn parse_mixer_command(
     state: &mut CommandDspMixerState,
     cmd: &MixerCmd,
     output_ports: &[TargetPort]
-) {
+) -> bool {
     match cmd {
         MixerCmd::OutputAssign(mixer, val) => {
-            state.output_assign[*mixer] = output_ports
-                .iter()
-                .nth(*val)
-                .map(|&p| p)
-                .unwrap_or_else(|| output_ports[0]);
-        }
-        MixerCmd::OutputMute(mixer, val) => state.output_mute[*mixer] = *val,
-        MixerCmd::OutputVolume(mixer, val) => state.output_volume[*mixer] = *val,
-        MixerCmd::ReverbSend(mixer, val) => state.reverb_send[*mixer] = *val,
-        MixerCmd::ReverbReturn(mixer, val) => state.reverb_return[*mixer] = *val,
-        MixerCmd::SourceMute(mixer, src, val) => state.source[*mixer].mute[*src] = *val,
-        MixerCmd::SourceSolo(mixer, src, val) => state.source[*mixer].solo[*src] = *val,
-        MixerCmd::SourceGain(mixer, src, val) => state.source[*mixer].gain[*src] = *val,
-        MixerCmd::SourceMonauralLrBalance(mixer, src, val) => state.source[*mixer].pan[*src] = *val,
-        MixerCmd::SourceStereoMode(mixer, src, val) => state.source[*mixer].stereo_mode[*src] = *val,
-        MixerCmd::SourceStereoLrBalance(mixer, src, val) => state.source[*mixer].stereo_balance[*src] = *val,
-        MixerCmd::SourceStereoWidth(mixer, src, val) => state.source[*mixer].stereo_width[*src] = *val,
-        _ => (),
+            // The unknown index falls back to the first port, and is reported as well as the
+            // index out of range.
+            match output_ports.get(*val) {
+                Some(&port) => update_value(&mut state.output_assign, *mixer, port),
+                None => {
+                    update_value(&mut state.output_assign, *mixer, output_ports[0]);
+                    false
+                }
+            }
+        }
+        MixerCmd::OutputMute(mixer, val) => update_value(&mut state.output_mute, *mixer, *val),
+        MixerCmd::OutputVolume(mixer, val) => update_value(&mut state.output_volume, *mixer, *val),
+        MixerCmd::ReverbSend(mixer, val) => update_value(&mut state.reverb_send, *mixer, *val),
+        MixerCmd::ReverbReturn(mixer, val) => update_value(&mut state.reverb_return, *mixer, *val),
+        MixerCmd::SourceMute(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.mute, *src, *val))
+        }
+        MixerCmd::SourceSolo(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.solo, *src, *val))
+        }
+        MixerCmd::SourceGain(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.gain, *src, *val))
+        }
+        MixerCmd::SourceMonauralLrBalance(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.pan, *src, *val))
+        }
+        MixerCmd::SourceStereoMode(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.stereo_mode, *src, *val))
+        }
+        MixerCmd::SourceStereoLrBalance(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.stereo_balance, *src, *val))
+        }
+        MixerCmd::SourceStereoWidth(mixer, src, val) => {
+            state.source.get_mut(*mixer).map_or(false, |s| update_value(&mut s.stereo_width, *src, *val))
+        }
+        _ => true,
     }
 }
 
+const REGISTER_DSP_GAIN_MAX: u8 = 0x80;
+const REGISTER_DSP_PAN_CENTER: u8 = 0x40;
+
+fn register_dsp_gain_to_linear(val: u8) -> f32 {
+    val.min(REGISTER_DSP_GAIN_MAX) as f32 / REGISTER_DSP_GAIN_MAX as f32
+}
+
+fn register_dsp_pan_to_balance(val: u8) -> f32 {
+    let val = val.min(REGISTER_DSP_GAIN_MAX) as f32 - REGISTER_DSP_PAN_CENTER as f32;
+    val / REGISTER_DSP_PAN_CENTER as f32
+}
+
 /// The trait for operation of mixer.
 pub trait CommandDspMixerOperation : CommandDspOperation {
     const SOURCE_PORTS: &'static [TargetPort];
@@ -1922,17 +2292,75 @@ pub trait CommandDspMixerOperation : CommandDspOperation {
         state
     }
 
+    /// Convert the state of mixer in register DSP models into the state of mixer in command DSP
+    /// models, and return the number of source entries dropped since the port is not available.
+    ///
+    /// The gain and volume in register DSP are linear between 0x00 and 0x80 (0 dB), and the pan is
+    /// between 0x00 and 0x80 with the center at 0x40. The register DSP has four mixers, thus the
+    /// rest of mixers are left as is. The destination of output not available in the command DSP
+    /// falls back to the first output port. The register DSP has no function for stereo pair,
+    /// reverb, equalizer, and dynamics, thus the parameters for them are left as is.
+    fn convert_register_dsp_mixer_state(
+        state: &mut CommandDspMixerState,
+        output: &RegisterDspMixerOutputState,
+        source_ports: &[TargetPort],
+        sources: &RegisterDspMixerMonauralSourceState,
+    ) -> usize {
+        let mut dropped = 0;
+
+        output.volume
+            .iter()
+            .zip(output.mute.iter())
+            .zip(output.destination.iter())
+            .zip(sources.0.iter())
+            .enumerate()
+            .for_each(|(mixer, (((&volume, &mute), destination), entry))| {
+                state.output_assign[mixer] = Self::OUTPUT_PORTS
+                    .iter()
+                    .find(|p| destination.eq(p))
+                    .copied()
+                    .unwrap_or_else(|| Self::OUTPUT_PORTS[0]);
+                state.output_mute[mixer] = mute;
+                state.output_volume[mixer] = register_dsp_gain_to_linear(volume);
+
+                let src = &mut state.source[mixer];
+                source_ports
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, port)| {
+                        match Self::SOURCE_PORTS.iter().position(|p| port.eq(p)) {
+                            Some(ch) => {
+                                update_value(&mut src.gain, ch, register_dsp_gain_to_linear(entry.gain[i]));
+                                update_value(&mut src.pan, ch, register_dsp_pan_to_balance(entry.pan[i]));
+                                update_value(&mut src.mute, ch, entry.mute[i]);
+                                update_value(&mut src.solo, ch, entry.solo[i]);
+                            }
+                            None => dropped += 1,
+                        }
+                    });
+            });
+
+        dropped
+    }
+
+    /// Parse the commands and return the number of commands dropped due to index out of range.
+    /// The command to assign output with unknown index is counted as well, while the first port
+    /// is used for the destination.
     fn parse_mixer_commands(
         state: &mut CommandDspMixerState,
         cmds: &[DspCmd]
-    ) {
+    ) -> usize {
+        let mut dropped = 0;
         cmds
             .iter()
             .for_each(|cmd| {
                 if let DspCmd::Mixer(c) = cmd {
-                    parse_mixer_command(state, c, Self::OUTPUT_PORTS);
+                    if !parse_mixer_command(state, c, Self::OUTPUT_PORTS) {
+                        dropped += 1;
+                    }
                 }
             });
+        dropped
     }
 
     fn write_mixer_state(
@@ -1943,6 +2371,7 @@ pub trait CommandDspMixerOperation : CommandDspOperation {
         old: &mut CommandDspMixerState,
         timeout_ms: u32
     ) -> Result<(), Error> {
+        validate_mixer_output_assign(&state, Self::OUTPUT_PORTS)?;
         let mut new_cmds = create_mixer_commands(&state, Self::SOURCE_PORTS.len(), Self::OUTPUT_PORTS);
         let old_cmds = create_mixer_commands(old, Self::SOURCE_PORTS.len(), Self::OUTPUT_PORTS);
         new_cmds.retain(|cmd| old_cmds.iter().find(|c| c.eq(&cmd)).is_none());
@@ -1950,6 +2379,129 @@ pub trait CommandDspMixerOperation : CommandDspOperation {
     }
 }
 
+/// The enumeration for mode of solo in mixer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SoloMode {
+    /// The other sources are muted.
+    InPlace,
+    /// The solo of source is sent to the unit as is, without touching mute of the other sources.
+    AfterFaderListen,
+}
+
+impl Default for SoloMode {
+    fn default() -> Self {
+        Self::InPlace
+    }
+}
+
+/// The structure for state of solo mode, emulated by mute of mixer sources.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CommandDspSoloState {
+    pub mode: SoloMode,
+    /// The mute of sources to be restored when the last solo is cleared, per mixer.
+    saved_mute: [Option<Vec<bool>>; MIXER_COUNT],
+}
+
+impl CommandDspSoloState {
+    /// Whether any source is soloed in the state of mixer.
+    pub fn is_active(mixer: &CommandDspMixerState) -> bool {
+        mixer.source.iter().any(|src| src.solo.iter().any(|&solo| solo))
+    }
+
+    /// Adjust mute of sources in new state of mixer according to transition of solo, in
+    /// solo-in-place mode. The old state is expected to be the latest one parsed from the unit.
+    pub fn follow_mixer(&mut self, old: &CommandDspMixerState, new: &mut CommandDspMixerState) {
+        if self.mode != SoloMode::InPlace {
+            return;
+        }
+
+        old.source
+            .iter()
+            .zip(new.source.iter_mut())
+            .zip(self.saved_mute.iter_mut())
+            .for_each(|((o, n), saved_mute)| {
+                let active = n.solo.iter().any(|&solo| solo);
+                match saved_mute {
+                    None if active => {
+                        n.mute
+                            .iter_mut()
+                            .zip(&n.solo)
+                            .filter(|(_, &solo)| !solo)
+                            .for_each(|(mute, _)| *mute = true);
+                        *saved_mute = Some(o.mute.clone());
+                    }
+                    Some(saved) if active => {
+                        n.mute
+                            .iter_mut()
+                            .zip(o.solo.iter().zip(&n.solo))
+                            .zip(saved.iter())
+                            .filter(|((_, (prev, curr)), _)| prev != curr)
+                            .for_each(|((mute, (_, &solo)), &saved)| {
+                                *mute = if solo { saved } else { true };
+                            });
+                    }
+                    // The remembered mutes are restored even if changed by the unit meanwhile.
+                    Some(saved) => {
+                        n.mute.copy_from_slice(saved);
+                        *saved_mute = None;
+                    }
+                    None => (),
+                }
+            });
+    }
+
+    /// Build the minimum commands to clear solo of all sources, talkback, listenback, and mute of
+    /// mixer outputs assigned to main pair. The mute of sources saved for solo is restored as
+    /// well. No command is built when nothing is to be cleared, thus it is
+    /// safe to call repeatedly.
+    pub fn build_panic_commands(
+        &mut self,
+        mixer: &CommandDspMixerState,
+        monitor: &CommandDspMonitorState,
+    ) -> Vec<DspCmd> {
+        let mut cmds = Vec::new();
+
+        mixer.source
+            .iter()
+            .zip(self.saved_mute.iter_mut())
+            .enumerate()
+            .for_each(|(i, (src, saved_mute))| {
+                src.solo
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, solo)| **solo)
+                    .for_each(|(ch, _)| cmds.push(DspCmd::Mixer(MixerCmd::SourceSolo(i, ch, false))));
+
+                if let Some(saved) = saved_mute.take() {
+                    src.mute
+                        .iter()
+                        .zip(saved.iter())
+                        .enumerate()
+                        .filter(|(_, (curr, saved))| curr != saved)
+                        .for_each(|(ch, (_, &saved))| {
+                            cmds.push(DspCmd::Mixer(MixerCmd::SourceMute(i, ch, saved)));
+                        });
+                }
+            });
+
+        mixer.output_mute
+            .iter()
+            .zip(mixer.output_assign.iter())
+            .enumerate()
+            .filter(|(_, (mute, port))| **mute && **port == TargetPort::MainPair0)
+            .for_each(|(i, _)| cmds.push(DspCmd::Mixer(MixerCmd::OutputMute(i, false))));
+
+        if monitor.talkback_enable {
+            cmds.push(DspCmd::Monitor(MonitorCmd::TalkbackEnable(false)));
+        }
+        if monitor.listenback_enable {
+            cmds.push(DspCmd::Monitor(MonitorCmd::ListenbackEnable(false)));
+        }
+
+        cmds
+    }
+}
+
 /// The structure for state of equalizer.
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct CommandDspEqualizerState {
@@ -2047,47 +2599,47 @@ fn parse_equalizer_parameter(
     state: &mut CommandDspEqualizerState,
     param: &EqualizerParameter,
     ch: usize
-) {
+) -> bool {
     match param {
-        EqualizerParameter::Enable(val) => state.enable[ch] = *val,
-
-        EqualizerParameter::HpfEnable(val) => state.hpf_enable[ch] = *val,
-        EqualizerParameter::HpfSlope(val) => state.hpf_slope[ch] = *val,
-        EqualizerParameter::HpfFreq(val) => state.hpf_freq[ch] = *val,
-
-        EqualizerParameter::LpfEnable(val) => state.lpf_enable[ch] = *val,
-        EqualizerParameter::LpfSlope(val) => state.lpf_slope[ch] = *val,
-        EqualizerParameter::LpfFreq(val) => state.lpf_freq[ch] = *val,
-
-        EqualizerParameter::LfEnable(val) => state.lf_enable[ch] = *val,
-        EqualizerParameter::LfType(val) => state.lf_type[ch] = *val,
-        EqualizerParameter::LfFreq(val) => state.lf_freq[ch] = *val,
-        EqualizerParameter::LfGain(val) => state.lf_gain[ch] = *val,
-        EqualizerParameter::LfWidth(val) => state.lf_width[ch] = *val,
-
-        EqualizerParameter::LmfEnable(val) => state.lmf_enable[ch] = *val,
-        EqualizerParameter::LmfType(val) => state.lmf_type[ch] = *val,
-        EqualizerParameter::LmfFreq(val) => state.lmf_freq[ch] = *val,
-        EqualizerParameter::LmfGain(val) => state.lmf_gain[ch] = *val,
-        EqualizerParameter::LmfWidth(val) => state.lmf_width[ch] = *val,
-
-        EqualizerParameter::MfEnable(val) => state.mf_enable[ch] = *val,
-        EqualizerParameter::MfType(val) => state.mf_type[ch] = *val,
-        EqualizerParameter::MfFreq(val) => state.mf_freq[ch] = *val,
-        EqualizerParameter::MfGain(val) => state.mf_gain[ch] = *val,
-        EqualizerParameter::MfWidth(val) => state.mf_width[ch] = *val,
-
-        EqualizerParameter::HmfEnable(val) => state.hmf_enable[ch] = *val,
-        EqualizerParameter::HmfType(val) => state.hmf_type[ch] = *val,
-        EqualizerParameter::HmfFreq(val) => state.hmf_freq[ch] = *val,
-        EqualizerParameter::HmfGain(val) => state.hmf_gain[ch] = *val,
-        EqualizerParameter::HmfWidth(val) => state.hmf_width[ch] = *val,
-
-        EqualizerParameter::HfEnable(val) => state.hf_enable[ch] = *val,
-        EqualizerParameter::HfType(val) => state.hf_type[ch] = *val,
-        EqualizerParameter::HfFreq(val) => state.hf_freq[ch] = *val,
-        EqualizerParameter::HfGain(val) => state.hf_gain[ch] = *val,
-        EqualizerParameter::HfWidth(val) => state.hf_width[ch] = *val,
+        EqualizerParameter::Enable(val) => update_value(&mut state.enable, ch, *val),
+
+        EqualizerParameter::HpfEnable(val) => update_value(&mut state.hpf_enable, ch, *val),
+        EqualizerParameter::HpfSlope(val) => update_value(&mut state.hpf_slope, ch, *val),
+        EqualizerParameter::HpfFreq(val) => update_value(&mut state.hpf_freq, ch, *val),
+
+        EqualizerParameter::LpfEnable(val) => update_value(&mut state.lpf_enable, ch, *val),
+        EqualizerParameter::LpfSlope(val) => update_value(&mut state.lpf_slope, ch, *val),
+        EqualizerParameter::LpfFreq(val) => update_value(&mut state.lpf_freq, ch, *val),
+
+        EqualizerParameter::LfEnable(val) => update_value(&mut state.lf_enable, ch, *val),
+        EqualizerParameter::LfType(val) => update_value(&mut state.lf_type, ch, *val),
+        EqualizerParameter::LfFreq(val) => update_value(&mut state.lf_freq, ch, *val),
+        EqualizerParameter::LfGain(val) => update_value(&mut state.lf_gain, ch, *val),
+        EqualizerParameter::LfWidth(val) => update_value(&mut state.lf_width, ch, *val),
+
+        EqualizerParameter::LmfEnable(val) => update_value(&mut state.lmf_enable, ch, *val),
+        EqualizerParameter::LmfType(val) => update_value(&mut state.lmf_type, ch, *val),
+        EqualizerParameter::LmfFreq(val) => update_value(&mut state.lmf_freq, ch, *val),
+        EqualizerParameter::LmfGain(val) => update_value(&mut state.lmf_gain, ch, *val),
+        EqualizerParameter::LmfWidth(val) => update_value(&mut state.lmf_width, ch, *val),
+
+        EqualizerParameter::MfEnable(val) => update_value(&mut state.mf_enable, ch, *val),
+        EqualizerParameter::MfType(val) => update_value(&mut state.mf_type, ch, *val),
+        EqualizerParameter::MfFreq(val) => update_value(&mut state.mf_freq, ch, *val),
+        EqualizerParameter::MfGain(val) => update_value(&mut state.mf_gain, ch, *val),
+        EqualizerParameter::MfWidth(val) => update_value(&mut state.mf_width, ch, *val),
+
+        EqualizerParameter::HmfEnable(val) => update_value(&mut state.hmf_enable, ch, *val),
+        EqualizerParameter::HmfType(val) => update_value(&mut state.hmf_type, ch, *val),
+        EqualizerParameter::HmfFreq(val) => update_value(&mut state.hmf_freq, ch, *val),
+        EqualizerParameter::HmfGain(val) => update_value(&mut state.hmf_gain, ch, *val),
+        EqualizerParameter::HmfWidth(val) => update_value(&mut state.hmf_width, ch, *val),
+
+        EqualizerParameter::HfEnable(val) => update_value(&mut state.hf_enable, ch, *val),
+        EqualizerParameter::HfType(val) => update_value(&mut state.hf_type, ch, *val),
+        EqualizerParameter::HfFreq(val) => update_value(&mut state.hf_freq, ch, *val),
+        EqualizerParameter::HfGain(val) => update_value(&mut state.hf_gain, ch, *val),
+        EqualizerParameter::HfWidth(val) => update_value(&mut state.hf_width, ch, *val),
     }
 }
 
@@ -2138,22 +2690,22 @@ fn parse_dynamics_parameter(
     state: &mut CommandDspDynamicsState,
     param: &DynamicsParameter,
     ch: usize,
-) {
+) -> bool {
     match param {
-        DynamicsParameter::Enable(val) => state.enable[ch] = *val,
-
-        DynamicsParameter::CompEnable(val) => state.comp_enable[ch] = *val,
-        DynamicsParameter::CompDetectMode(val) => state.comp_detect_mode[ch] = *val,
-        DynamicsParameter::CompThreshold(val) => state.comp_threshold[ch] = *val,
-        DynamicsParameter::CompRatio(val) => state.comp_ratio[ch] = *val,
-        DynamicsParameter::CompAttack(val) => state.comp_attack[ch] = *val,
-        DynamicsParameter::CompRelease(val) => state.comp_release[ch] = *val,
-        DynamicsParameter::CompGain(val) => state.comp_gain[ch] = *val,
-
-        DynamicsParameter::LevelerEnable(val) => state.leveler_enable[ch] = *val,
-        DynamicsParameter::LevelerMode(val) => state.leveler_mode[ch] = *val,
-        DynamicsParameter::LevelerMakeup(val) => state.leveler_makeup[ch] = *val,
-        DynamicsParameter::LevelerReduce(val) => state.leveler_reduce[ch] = *val,
+        DynamicsParameter::Enable(val) => update_value(&mut state.enable, ch, *val),
+
+        DynamicsParameter::CompEnable(val) => update_value(&mut state.comp_enable, ch, *val),
+        DynamicsParameter::CompDetectMode(val) => update_value(&mut state.comp_detect_mode, ch, *val),
+        DynamicsParameter::CompThreshold(val) => update_value(&mut state.comp_threshold, ch, *val),
+        DynamicsParameter::CompRatio(val) => update_value(&mut state.comp_ratio, ch, *val),
+        DynamicsParameter::CompAttack(val) => update_value(&mut state.comp_attack, ch, *val),
+        DynamicsParameter::CompRelease(val) => update_value(&mut state.comp_release, ch, *val),
+        DynamicsParameter::CompGain(val) => update_value(&mut state.comp_gain, ch, *val),
+
+        DynamicsParameter::LevelerEnable(val) => update_value(&mut state.leveler_enable, ch, *val),
+        DynamicsParameter::LevelerMode(val) => update_value(&mut state.leveler_mode, ch, *val),
+        DynamicsParameter::LevelerMakeup(val) => update_value(&mut state.leveler_makeup, ch, *val),
+        DynamicsParameter::LevelerReduce(val) => update_value(&mut state.leveler_reduce, ch, *val),
     }
 }
 
@@ -2223,24 +2775,24 @@ fn create_input_commands(
 fn parse_input_command(
     state: &mut CommandDspInputState,
     cmd: &InputCmd
-) {
+) -> bool {
     match cmd {
-        InputCmd::Phase(ch, val) => state.phase[*ch] = *val,
-        InputCmd::Pair(ch, val) => state.pair[*ch] = *val,
-        InputCmd::Gain(ch, val) => state.gain[*ch] = *val,
-        InputCmd::Swap(ch, val) => state.swap[*ch] = *val,
-        InputCmd::StereoMode(ch, val) =>state.stereo_mode[*ch] = *val,
-        InputCmd::Width(ch, val) => state.width[*ch] = *val,
+        InputCmd::Phase(ch, val) => update_value(&mut state.phase, *ch, *val),
+        InputCmd::Pair(ch, val) => update_value(&mut state.pair, *ch, *val),
+        InputCmd::Gain(ch, val) => update_value(&mut state.gain, *ch, *val),
+        InputCmd::Swap(ch, val) => update_value(&mut state.swap, *ch, *val),
+        InputCmd::StereoMode(ch, val) => update_value(&mut state.stereo_mode, *ch, *val),
+        InputCmd::Width(ch, val) => update_value(&mut state.width, *ch, *val),
         InputCmd::Equalizer(ch, param) => parse_equalizer_parameter(&mut state.equalizer, param, *ch),
         InputCmd::Dynamics(ch, param) => parse_dynamics_parameter(&mut state.dynamics, param, *ch),
-        InputCmd::ReverbSend(ch, val) => state.reverb_send[*ch] = *val,
-        InputCmd::ReverbLrBalance(ch, val) => state.reverb_balance[*ch] = *val,
-        InputCmd::Pad(ch, val) => if *ch < state.pad.len() { state.pad[*ch] = *val },
-        InputCmd::Phantom(ch, val) => if *ch < state.pad.len() { state.phantom[*ch] = *val },
-        InputCmd::Limitter(ch, val) => if *ch < state.pad.len() { state.limitter[*ch] = *val },
-        InputCmd::Lookahead(ch, val) => if *ch < state.pad.len() { state.lookahead[*ch] = *val },
-        InputCmd::Softclip(ch, val) => if *ch < state.pad.len() { state.soft_clip[*ch] = *val },
-        _ => (),
+        InputCmd::ReverbSend(ch, val) => update_value(&mut state.reverb_send, *ch, *val),
+        InputCmd::ReverbLrBalance(ch, val) => update_value(&mut state.reverb_balance, *ch, *val),
+        InputCmd::Pad(ch, val) => update_value(&mut state.pad, *ch, *val),
+        InputCmd::Phantom(ch, val) => update_value(&mut state.phantom, *ch, *val),
+        InputCmd::Limitter(ch, val) => update_value(&mut state.limitter, *ch, *val),
+        InputCmd::Lookahead(ch, val) => update_value(&mut state.lookahead, *ch, *val),
+        InputCmd::Softclip(ch, val) => update_value(&mut state.soft_clip, *ch, *val),
+        _ => true,
     }
 }
 
@@ -2249,8 +2801,8 @@ pub trait CommandDspInputOperation : CommandDspOperation {
     const INPUT_PORTS: &'static [TargetPort];
     const MIC_COUNT: usize;
 
-    const GAIN_MIN: i32 = -96;
-    const GAIN_MAX: i32 = 22;
+    const GAIN_MIN: i32 = InputCmd::GAIN_MIN;
+    const GAIN_MAX: i32 = InputCmd::GAIN_MAX;
     const GAIN_STEP: i32 = 1;
 
     const WIDTH_MIN: f32 = 0.0;
@@ -2337,17 +2889,22 @@ pub trait CommandDspInputOperation : CommandDspOperation {
         }
     }
 
+    /// Parse the commands and return the number of commands dropped due to index out of range.
     fn parse_input_commands(
         state: &mut CommandDspInputState,
         cmds: &[DspCmd]
-    ) {
+    ) -> usize {
+        let mut dropped = 0;
         cmds
             .iter()
             .for_each(|cmd| {
                 if let DspCmd::Input(c) = cmd {
-                    parse_input_command(state, c);
+                    if !parse_input_command(state, c) {
+                        dropped += 1;
+                    }
                 }
             });
+        dropped
     }
 
     fn write_input_state(
@@ -2382,6 +2939,10 @@ pub struct CommandDspOutputState {
     pub reverb_send: Vec<f32>,
     pub reverb_return: Vec<f32>,
 
+    // MEMO: The membership of output in monitor, talkback, and listenback groups is transferred
+    // by command independent of output assignment of mixers. Whether firmware accepts the
+    // membership of output assigned to any mixer is not confirmed with actual units yet, thus the
+    // command is sent as is and the runtime doesn't restrict the combination.
     pub master_monitor: Vec<bool>,
     pub master_talkback: Vec<bool>,
     pub master_listenback: Vec<bool>,
@@ -2411,19 +2972,30 @@ fn create_output_commands(state: &CommandDspOutputState, output_count: usize) ->
     cmds
 }
 
+fn create_output_diff_commands(
+    state: &CommandDspOutputState,
+    old: &CommandDspOutputState,
+    output_count: usize
+) -> Vec<DspCmd> {
+    let mut new_cmds = create_output_commands(state, output_count);
+    let old_cmds = create_output_commands(old, output_count);
+    new_cmds.retain(|cmd| old_cmds.iter().find(|c| c.eq(&cmd)).is_none());
+    new_cmds
+}
+
 fn parse_output_command(
     state: &mut CommandDspOutputState,
     cmd: &OutputCmd
-) {
+) -> bool {
     match cmd {
         OutputCmd::Equalizer(ch, param) => parse_equalizer_parameter(&mut state.equalizer, param, *ch),
         OutputCmd::Dynamics(ch, param) => parse_dynamics_parameter(&mut state.dynamics, param, *ch),
-        OutputCmd::ReverbSend(ch, val) => state.reverb_send[*ch] = *val,
-        OutputCmd::ReverbReturn(ch, val) => state.reverb_return[*ch] = *val,
-        OutputCmd::MasterMonitor(ch, val) => state.master_monitor[*ch] = *val,
-        OutputCmd::MasterTalkback(ch, val) => state.master_talkback[*ch] = *val,
-        OutputCmd::MasterListenback(ch, val) => state.master_listenback[*ch] = *val,
-        _ => (),
+        OutputCmd::ReverbSend(ch, val) => update_value(&mut state.reverb_send, *ch, *val),
+        OutputCmd::ReverbReturn(ch, val) => update_value(&mut state.reverb_return, *ch, *val),
+        OutputCmd::MasterMonitor(ch, val) => update_value(&mut state.master_monitor, *ch, *val),
+        OutputCmd::MasterTalkback(ch, val) => update_value(&mut state.master_talkback, *ch, *val),
+        OutputCmd::MasterListenback(ch, val) => update_value(&mut state.master_listenback, *ch, *val),
+        _ => true,
     }
 }
 
@@ -2504,17 +3076,22 @@ pub trait CommandDspOutputOperation : CommandDspOperation {
         }
     }
 
+    /// Parse the commands and return the number of commands dropped due to index out of range.
     fn parse_output_commands(
         state: &mut CommandDspOutputState,
         cmds: &[DspCmd]
-    ) {
+    ) -> usize {
+        let mut dropped = 0;
         cmds
             .iter()
             .for_each(|cmd| {
                 if let DspCmd::Output(c) = cmd {
-                    parse_output_command(state, c);
+                    if !parse_output_command(state, c) {
+                        dropped += 1;
+                    }
                 }
             });
+        dropped
     }
 
     fn write_output_state(
@@ -2525,13 +3102,188 @@ pub trait CommandDspOutputOperation : CommandDspOperation {
         old: &mut CommandDspOutputState,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        let mut new_cmds = create_output_commands(&state, Self::OUTPUT_PORTS.len());
-        let old_cmds = create_output_commands(old, Self::OUTPUT_PORTS.len());
-        new_cmds.retain(|cmd| old_cmds.iter().find(|c| c.eq(&cmd)).is_none());
+        let new_cmds = create_output_diff_commands(&state, old, Self::OUTPUT_PORTS.len());
         Self::send_commands(req, node, sequence_number, &new_cmds, timeout_ms).map(|_| *old = state)
     }
 }
 
+/// The structure for preset of whole state of command DSP.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CommandDspPreset {
+    pub reverb: CommandDspReverbState,
+    pub monitor: CommandDspMonitorState,
+    pub mixer: CommandDspMixerState,
+    pub input: CommandDspInputState,
+    pub output: CommandDspOutputState,
+}
+
+// MEMO: The preset consists of 8 bytes header and the sequence of commands in the same format as
+// the one in transaction frame:
+//
+// preset[0..4]: magic bytes; 'M', 'C', 'D', 'P'
+// preset[4..8]: version of format aligned to big-endianness
+// preset[8..]: the sequence of commands
+//
+// The preset generated by the later version can include commands unknown to the version. They are
+// just ignored.
+//
+const PRESET_MAGIC: [u8; 4] = [b'M', b'C', b'D', b'P'];
+const PRESET_VERSION: u32 = 1;
+const PRESET_HEADER_SIZE: usize = 8;
+
+fn parse_preset_commands(raw: &[u8]) -> Result<Vec<DspCmd>, Error> {
+    let mut cmds = Vec::new();
+    let mut pos = 0;
+
+    while pos < raw.len() {
+        let r = &raw[pos..];
+        let length = match r[0] {
+            CMD_RESOURCE => CMD_RESOURCE_LENGTH,
+            CMD_BYTE_MULTIPLE if r.len() > 1 => 6 + r[1] as usize,
+            CMD_QUADLET_MULTIPLE if r.len() > 1 => 6 + r[1] as usize * 4,
+            CMD_DRAIN => 1,
+            CMD_BYTE_SINGLE => CMD_BYTE_SINGLE_LENGTH,
+            CMD_QUADLET_SINGLE => CMD_QUADLET_SINGLE_LENGTH,
+            _ => 0,
+        };
+        if length == 0 || r.len() < length {
+            let msg = format!("Invalid command at offset {} in preset", PRESET_HEADER_SIZE + pos);
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+
+        let _ = DspCmd::parse(&r[..length], &mut cmds);
+        pos += length;
+    }
+
+    Ok(cmds)
+}
+
+fn build_preset_raw(cmds: &[DspCmd]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&PRESET_MAGIC);
+    raw.extend_from_slice(&PRESET_VERSION.to_be_bytes());
+    cmds.iter().for_each(|cmd| cmd.build(&mut raw));
+    raw
+}
+
+fn is_reserved_command(cmd: &DspCmd) -> bool {
+    match cmd {
+        DspCmd::Monitor(MonitorCmd::Reserved(_, _)) |
+        DspCmd::Input(InputCmd::Reserved(_, _)) |
+        DspCmd::Mixer(MixerCmd::Reserved(_, _)) |
+        DspCmd::Output(OutputCmd::Reserved(_, _)) |
+        DspCmd::Reverb(ReverbCmd::Reserved(_, _)) |
+        DspCmd::Resource(_) |
+        DspCmd::Reserved(_) => true,
+        _ => false,
+    }
+}
+
+/// The trait for operation of preset.
+pub trait CommandDspPresetOperation:
+    CommandDspReverbOperation + CommandDspMonitorOperation + CommandDspMixerOperation +
+    CommandDspInputOperation + CommandDspOutputOperation
+{
+    fn build_preset(preset: &CommandDspPreset) -> Vec<u8> {
+        let mut cmds = create_reverb_command(&preset.reverb);
+        cmds.append(&mut create_monitor_commands(&preset.monitor, Self::RETURN_ASSIGN_TARGETS));
+        cmds.append(&mut create_mixer_commands(
+            &preset.mixer,
+            Self::SOURCE_PORTS.len(),
+            <Self as CommandDspMixerOperation>::OUTPUT_PORTS,
+        ));
+        cmds.append(&mut create_input_commands(
+            &preset.input,
+            Self::INPUT_PORTS.len(),
+            Self::MIC_COUNT,
+        ));
+        cmds.append(&mut create_output_commands(
+            &preset.output,
+            <Self as CommandDspOutputOperation>::OUTPUT_PORTS.len(),
+        ));
+
+        build_preset_raw(&cmds)
+    }
+
+    /// Convert the state of mixer in register DSP models, then build the preset just for the
+    /// converted parameters and return it with the number of source entries dropped since the
+    /// port is not available. The other parameters are left as is when the preset is loaded.
+    fn build_preset_from_register_dsp_mixer(
+        output: &RegisterDspMixerOutputState,
+        source_ports: &[TargetPort],
+        sources: &RegisterDspMixerMonauralSourceState,
+    ) -> (Vec<u8>, usize) {
+        let mut state = Self::create_mixer_state();
+        let dropped =
+            Self::convert_register_dsp_mixer_state(&mut state, output, source_ports, sources);
+
+        let output_ports = <Self as CommandDspMixerOperation>::OUTPUT_PORTS;
+        let channels: Vec<usize> = source_ports
+            .iter()
+            .filter_map(|port| Self::SOURCE_PORTS.iter().position(|p| port.eq(p)))
+            .collect();
+
+        let mut cmds = Vec::new();
+        (0..output.volume.len())
+            .for_each(|mixer| {
+                let pos = output_ports
+                    .iter()
+                    .position(|p| state.output_assign[mixer].eq(p))
+                    .unwrap_or_default();
+                cmds.push(DspCmd::Mixer(MixerCmd::OutputAssign(mixer, pos)));
+                cmds.push(DspCmd::Mixer(MixerCmd::OutputMute(mixer, state.output_mute[mixer])));
+                cmds.push(DspCmd::Mixer(MixerCmd::OutputVolume(mixer, state.output_volume[mixer])));
+
+                let src = &state.source[mixer];
+                channels
+                    .iter()
+                    .for_each(|&ch| {
+                        cmds.push(DspCmd::Mixer(MixerCmd::SourceMute(mixer, ch, src.mute[ch])));
+                        cmds.push(DspCmd::Mixer(MixerCmd::SourceSolo(mixer, ch, src.solo[ch])));
+                        cmds.push(DspCmd::Mixer(MixerCmd::SourceGain(mixer, ch, src.gain[ch])));
+                        cmds.push(DspCmd::Mixer(MixerCmd::SourceMonauralLrBalance(mixer, ch, src.pan[ch])));
+                    });
+            });
+
+        (build_preset_raw(&cmds), dropped)
+    }
+
+    /// Parse the preset and return the number of commands ignored since unknown or out of range.
+    /// The parameters not included in the preset are left as is.
+    fn parse_preset(preset: &mut CommandDspPreset, raw: &[u8]) -> Result<usize, Error> {
+        if raw.len() < PRESET_HEADER_SIZE || raw[..4] != PRESET_MAGIC {
+            Err(Error::new(FileError::Inval, "Invalid header of preset"))?;
+        }
+
+        let mut quadlet = [0; 4];
+        quadlet.copy_from_slice(&raw[4..8]);
+        let version = u32::from_be_bytes(quadlet);
+        if version == 0 {
+            let msg = format!("Invalid version of preset: {}", version);
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+
+        let cmds = parse_preset_commands(&raw[PRESET_HEADER_SIZE..])?;
+
+        let ignored = cmds.iter().filter(|cmd| is_reserved_command(cmd)).count();
+        Ok(ignored + Self::update_preset(preset, &cmds))
+    }
+
+    /// Parse the commands into the preset and return the number of commands dropped due to index
+    /// out of range. During batched write, the commands originated by the unit are parsed into the
+    /// preset for the state in the unit as well as the pending state, thus the pending write for
+    /// the same parameter is dropped and the value in the unit is kept, e.g. when the knob of
+    /// preamp is turned.
+    fn update_preset(preset: &mut CommandDspPreset, cmds: &[DspCmd]) -> usize {
+        Self::parse_reverb_commands(&mut preset.reverb, cmds);
+        Self::parse_monitor_commands(&mut preset.monitor, cmds);
+        let mut dropped = Self::parse_mixer_commands(&mut preset.mixer, cmds);
+        dropped += Self::parse_input_commands(&mut preset.input, cmds);
+        dropped += Self::parse_output_commands(&mut preset.output, cmds);
+        dropped
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -2619,9 +3371,9 @@ mod test {
         [
             DspCmd::Monitor(MonitorCmd::Focus(FocusTarget::Output(11))),
             DspCmd::Input(InputCmd::Gain(0xe4, 0x01)),
-            DspCmd::Input(InputCmd::Dynamics(0xb1, DynamicsParameter::CompThreshold(97531))),
-            DspCmd::Output(OutputCmd::Dynamics(0x45, DynamicsParameter::CompThreshold(86420))),
-            DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(98765)),
+            DspCmd::Input(InputCmd::Dynamics(0xb1, DynamicsParameter::CompThreshold(-31))),
+            DspCmd::Output(OutputCmd::Dynamics(0x45, DynamicsParameter::CompThreshold(-17))),
+            DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(-29)),
         ]
             .iter()
             .for_each(|cmd| {
@@ -2633,6 +3385,36 @@ mod test {
             });
     }
 
+    #[test]
+    fn test_i32_rounding() {
+        [
+            ([0x01, 0x00, 0x02], -0.5, DspCmd::Input(InputCmd::Gain(0x03, -1))),
+            ([0x01, 0x00, 0x02], -95.6, DspCmd::Input(InputCmd::Gain(0x03, -96))),
+            ([0x01, 0x00, 0x02], 22.4, DspCmd::Input(InputCmd::Gain(0x03, 22))),
+            ([0x01, 0x00, 0x02], -120.0, DspCmd::Input(InputCmd::Gain(0x03, -96))),
+            ([0x01, 0x0a, 0x01], -0.7, DspCmd::Input(InputCmd::Dynamics(0x03, DynamicsParameter::CompThreshold(-1)))),
+            ([0x03, 0x09, 0x01], 12.0, DspCmd::Output(OutputCmd::Dynamics(0x03, DynamicsParameter::CompThreshold(0)))),
+            ([0x04, 0x00, 0x04], -40.4, DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(-40))),
+        ]
+            .iter()
+            .for_each(|(levels, val, cmd)| {
+                let mut raw = vec![CMD_QUADLET_SINGLE, 0x03, levels[2], levels[1], levels[0]];
+                raw.extend_from_slice(&(*val as f32).to_le_bytes());
+                let mut c = Vec::new();
+                assert_eq!(DspCmd::parse(&raw, &mut c), CMD_QUADLET_SINGLE_LENGTH);
+                assert_eq!(&c[0], cmd);
+
+                // The value is stable at the second round trip.
+                let mut built = Vec::new();
+                c[0].build(&mut built);
+                let mut d = Vec::new();
+                assert_eq!(DspCmd::parse(&built, &mut d), CMD_QUADLET_SINGLE_LENGTH);
+                let mut rebuilt = Vec::new();
+                d[0].build(&mut rebuilt);
+                assert_eq!(built, rebuilt);
+            });
+    }
+
     #[test]
     fn test_u32_cmds() {
         [
@@ -2736,12 +3518,53 @@ mod test {
 
     #[test]
     fn test_resource() {
-        let cmd = DspCmd::Resource(ResourceCmd::Usage(99.99999, 0x17));
+        let cmd = DspCmd::Resource(ResourceCmd::Usage(99.99999, CMD_RESOURCE_IDENTIFIER));
         let mut raw = Vec::new();
         cmd.build(&mut raw);
         let mut c = Vec::new();
         assert_eq!(DspCmd::parse(&raw, &mut c), CMD_RESOURCE_LENGTH);
         assert_eq!(c[0], cmd);
+
+        let raw = [0x23, 0x00, 0x00, 0x80, 0x3f, 0x17];
+        let mut c = Vec::new();
+        assert_eq!(DspCmd::parse(&raw, &mut c), CMD_RESOURCE_LENGTH);
+        assert_eq!(c[0], DspCmd::Resource(ResourceCmd::Reserved(raw.to_vec())));
+        let mut built = Vec::new();
+        c[0].build(&mut built);
+        assert_eq!(&built[..], &raw[..]);
+    }
+
+    #[test]
+    fn resource_decode_test() {
+        let raw = [
+            0x23, 0x00, 0x00, 0x20, 0x41, 0x11,
+            0x66, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3f,
+            0x23, 0x00, 0x00, 0x20, 0x41, 0x17,
+            0x66, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3f,
+            0x23, 0x00, 0x00, 0x48, 0x42, 0x11,
+        ];
+        let mut cmds = Vec::new();
+        let mut pos = 0;
+        let mut offsets = Vec::new();
+        while pos < raw.len() {
+            let consumed = DspCmd::parse(&raw[pos..], &mut cmds);
+            assert_ne!(consumed, 0);
+            offsets.push(pos);
+            pos += consumed;
+        }
+        assert_eq!(offsets, vec![0, 6, 15, 21, 30]);
+        assert_eq!(pos, raw.len());
+        assert_eq!(cmds[0], DspCmd::Resource(ResourceCmd::Usage(10.0, 0x11)));
+        assert_eq!(cmds[1], DspCmd::Monitor(MonitorCmd::Volume(1.0)));
+        assert_eq!(cmds[2], DspCmd::Resource(ResourceCmd::Reserved(raw[15..21].to_vec())));
+        assert_eq!(cmds[3], DspCmd::Monitor(MonitorCmd::Volume(1.0)));
+        assert_eq!(cmds[4], DspCmd::Resource(ResourceCmd::Usage(50.0, 0x11)));
+        assert_eq!(cmds.len(), 5);
+
+        let mut handler = CommandDspMessageHandler::default();
+        handler.cache.extend_from_slice(&raw);
+        assert_eq!(handler.decode_messages(), cmds);
+        assert!(handler.cache.is_empty());
     }
 
     #[test]
@@ -2777,4 +3600,850 @@ mod test {
         assert_eq!(cmds[13], DspCmd::Input(InputCmd::Width(1, 0.0)));
         assert_eq!(cmds.len(), 14);
     }
+
+    struct TestProtocol;
+
+    impl CommandDspOperation for TestProtocol {}
+
+    impl CommandDspMixerOperation for TestProtocol {
+        const SOURCE_PORTS: &'static [TargetPort] = &[TargetPort::Analog0, TargetPort::Analog1];
+        const OUTPUT_PORTS: &'static [TargetPort] = &[TargetPort::MainPair0, TargetPort::AnalogPair0];
+    }
+
+    impl CommandDspInputOperation for TestProtocol {
+        const INPUT_PORTS: &'static [TargetPort] = &[TargetPort::Analog0, TargetPort::Analog1];
+        const MIC_COUNT: usize = 1;
+    }
+
+    impl CommandDspOutputOperation for TestProtocol {
+        const OUTPUT_PORTS: &'static [TargetPort] = &[TargetPort::MainPair0, TargetPort::AnalogPair0];
+    }
+
+    impl CommandDspReverbOperation for TestProtocol {}
+
+    impl CommandDspMonitorOperation for TestProtocol {
+        const RETURN_ASSIGN_TARGETS: &'static [TargetPort] = &[TargetPort::MainPair0, TargetPort::AnalogPair0];
+    }
+
+    impl CommandDspPresetOperation for TestProtocol {}
+
+    fn create_test_preset() -> CommandDspPreset {
+        CommandDspPreset {
+            reverb: Default::default(),
+            monitor: Default::default(),
+            mixer: TestProtocol::create_mixer_state(),
+            input: TestProtocol::create_input_state(),
+            output: TestProtocol::create_output_state(),
+        }
+    }
+
+    // Xorshift to generate channel bytes.
+    fn next_byte(seed: &mut u32) -> usize {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        (*seed & 0xff) as usize
+    }
+
+    #[test]
+    fn test_input_channel_range() {
+        let mut state = TestProtocol::create_input_state();
+        let mut seed = 0x2c8a5f13;
+
+        (0..1000).for_each(|_| {
+            let ch = next_byte(&mut seed);
+            let cmds = vec![
+                DspCmd::Input(InputCmd::Phase(ch, true)),
+                DspCmd::Input(InputCmd::Width(ch, 0.5)),
+                DspCmd::Input(InputCmd::Equalizer(ch, EqualizerParameter::HpfFreq(100))),
+                DspCmd::Input(InputCmd::Dynamics(ch, DynamicsParameter::CompEnable(true))),
+                DspCmd::Input(InputCmd::Phantom(ch, true)),
+                DspCmd::Monitor(MonitorCmd::Volume(0.5)),
+            ];
+            let mut expected = 0;
+            if ch >= TestProtocol::INPUT_PORTS.len() {
+                expected += 4;
+            }
+            if ch >= TestProtocol::MIC_COUNT {
+                expected += 1;
+            }
+            assert_eq!(TestProtocol::parse_input_commands(&mut state, &cmds), expected, "{}", ch);
+        });
+
+        let mut state = TestProtocol::create_input_state();
+        let cmds = vec![DspCmd::Input(InputCmd::Phase(0xff, true))];
+        assert_eq!(TestProtocol::parse_input_commands(&mut state, &cmds), 1);
+        let cmds = vec![DspCmd::Input(InputCmd::Phase(1, true))];
+        assert_eq!(TestProtocol::parse_input_commands(&mut state, &cmds), 0);
+        assert_eq!(state.phase, vec![false, true]);
+    }
+
+    #[test]
+    fn test_mixer_channel_range() {
+        let mut state = TestProtocol::create_mixer_state();
+        let mut seed = 0x7f4a7c15;
+
+        (0..1000).for_each(|_| {
+            let mixer = next_byte(&mut seed);
+            let src = next_byte(&mut seed);
+            let cmds = vec![
+                DspCmd::Mixer(MixerCmd::OutputAssign(mixer, 1)),
+                DspCmd::Mixer(MixerCmd::OutputMute(mixer, true)),
+                DspCmd::Mixer(MixerCmd::SourceGain(mixer, src, 0.5)),
+                DspCmd::Mixer(MixerCmd::SourceStereoMode(mixer, src, SourceStereoPairMode::Width)),
+            ];
+            let mut expected = 0;
+            if mixer >= TestProtocol::MIXER_COUNT {
+                expected += 4;
+            } else if src >= TestProtocol::SOURCE_PORTS.len() {
+                expected += 2;
+            }
+            assert_eq!(
+                TestProtocol::parse_mixer_commands(&mut state, &cmds),
+                expected,
+                "{} {}",
+                mixer,
+                src
+            );
+        });
+    }
+
+    #[test]
+    fn test_mixer_output_assign_range() {
+        let ports = <TestProtocol as CommandDspMixerOperation>::OUTPUT_PORTS;
+        let last = ports.len() - 1;
+
+        let mut state = TestProtocol::create_mixer_state();
+        state.output_assign = [ports[last]; MIXER_COUNT];
+        assert!(validate_mixer_output_assign(&state, ports).is_ok());
+        state.output_assign[3] = TargetPort::PhonePair0;
+        assert!(validate_mixer_output_assign(&state, ports).is_err());
+
+        let mut raw = Vec::new();
+        DspCmd::Mixer(MixerCmd::OutputAssign(1, last)).build(&mut raw);
+        DspCmd::Mixer(MixerCmd::OutputAssign(2, last + 1)).build(&mut raw);
+        let mut cmds = Vec::new();
+        let mut pos = 0;
+        while pos < raw.len() {
+            pos += DspCmd::parse(&raw[pos..], &mut cmds);
+        }
+
+        let mut state = TestProtocol::create_mixer_state();
+        state.output_assign = [ports[last]; MIXER_COUNT];
+        assert_eq!(TestProtocol::parse_mixer_commands(&mut state, &cmds), 1);
+        assert_eq!(state.output_assign[1], ports[last]);
+        assert_eq!(state.output_assign[2], ports[0]);
+    }
+
+    #[test]
+    fn test_output_channel_range() {
+        let mut state = TestProtocol::create_output_state();
+        let mut seed = 0x1b873593;
+
+        (0..1000).for_each(|_| {
+            let ch = next_byte(&mut seed);
+            let cmds = vec![
+                DspCmd::Output(OutputCmd::Equalizer(ch, EqualizerParameter::Enable(true))),
+                DspCmd::Output(OutputCmd::Dynamics(ch, DynamicsParameter::LevelerMakeup(10))),
+                DspCmd::Output(OutputCmd::ReverbReturn(ch, 0.5)),
+                DspCmd::Output(OutputCmd::MasterListenback(ch, true)),
+            ];
+            let output_count = <TestProtocol as CommandDspOutputOperation>::OUTPUT_PORTS.len();
+            let expected = if ch >= output_count { 4 } else { 0 };
+            assert_eq!(TestProtocol::parse_output_commands(&mut state, &cmds), expected, "{}", ch);
+        });
+    }
+
+    #[test]
+    fn test_output_membership() {
+        let output_count = <TestProtocol as CommandDspOutputOperation>::OUTPUT_PORTS.len();
+        let old = TestProtocol::create_output_state();
+
+        // The change of membership is sent alone.
+        let mut state = old.clone();
+        state.master_monitor[1] = true;
+        state.master_listenback[output_count - 1] = true;
+        let cmds = create_output_diff_commands(&state, &old, output_count);
+        assert_eq!(
+            cmds,
+            vec![
+                DspCmd::Output(OutputCmd::MasterMonitor(1, true)),
+                DspCmd::Output(OutputCmd::MasterListenback(output_count - 1, true)),
+            ]
+        );
+
+        // Leaving the group is sent as well.
+        let cmds = create_output_diff_commands(&old, &state, output_count);
+        assert_eq!(
+            cmds,
+            vec![
+                DspCmd::Output(OutputCmd::MasterMonitor(1, false)),
+                DspCmd::Output(OutputCmd::MasterListenback(output_count - 1, false)),
+            ]
+        );
+
+        // No command is sent without any change.
+        assert_eq!(create_output_diff_commands(&state, &state, output_count), Vec::new());
+    }
+
+    fn build_frames(seq_num: &mut u8, cmds: &[DspCmd]) -> Vec<Vec<u8>> {
+        let mut msg = Vec::new();
+        cmds.iter().for_each(|cmd| cmd.build(&mut msg));
+        msg.chunks(MAXIMUM_DSP_FRAME_SIZE - 2)
+            .map(|chunk| {
+                let mut frame = vec![0x02, *seq_num];
+                frame.extend_from_slice(chunk);
+                while frame.len() % 4 > 0 {
+                    frame.push(0x00);
+                }
+                *seq_num = increment_seq_num(*seq_num);
+                frame
+            })
+            .collect()
+    }
+
+    fn create_width_cmds(count: usize, offset: usize) -> Vec<DspCmd> {
+        (0..count)
+            .map(|i| DspCmd::Input(InputCmd::Width(i, (offset + i) as f32 / 1000.0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_truncated_message() {
+        let mut handler = CommandDspMessageHandler::default();
+        let mut seq_num = 0xfe;
+
+        let cmds = create_width_cmds(60, 0);
+        let frames = build_frames(&mut seq_num, &cmds);
+        assert_eq!(frames.len(), 3);
+
+        frames.iter().for_each(|frame| {
+            assert!(!handler.has_dsp_message());
+            handler.cache_dsp_messages(frame);
+        });
+        assert!(handler.has_dsp_message());
+        assert_eq!(handler.decode_messages(), cmds);
+    }
+
+    #[test]
+    fn test_malformed_command() {
+        let mut raw = Vec::new();
+        DspCmd::Input(InputCmd::Width(1, 0.5)).build(&mut raw);
+        DspCmd::Mixer(MixerCmd::SourceMute(0, 2, true)).build(&mut raw);
+        append_resource(&mut raw, 12.0, 0x11);
+        raw.extend_from_slice(&[CMD_BYTE_MULTIPLE, 0x03, 0x00, 0x01, 0x01, 0x00, 0x10, 0x20, 0x30]);
+        raw.extend_from_slice(&[
+            CMD_QUADLET_MULTIPLE, 0x02, 0x00, 0x01, 0x01, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3f,
+        ]);
+
+        // Any truncated command is not parsed.
+        let mut pos = 0;
+        while pos < raw.len() {
+            let mut cmds = Vec::new();
+            let length = DspCmd::parse(&raw[pos..], &mut cmds);
+            assert!(length > 0);
+            (0..length).for_each(|len| {
+                let mut cmds = Vec::new();
+                assert_eq!(DspCmd::parse(&raw[pos..(pos + len)], &mut cmds), 0);
+                assert_eq!(cmds.len(), 0);
+            });
+            pos += length;
+        }
+        assert_eq!(pos, raw.len());
+
+        // The count of coefficients over the length.
+        let mut cmds = Vec::new();
+        assert_eq!(DspCmd::parse(&[CMD_BYTE_MULTIPLE, 0xff, 0x00, 0x01, 0x01, 0x00, 0x00], &mut cmds), 0);
+        assert_eq!(DspCmd::parse(&[CMD_QUADLET_MULTIPLE, 0x01, 0x00, 0x01, 0x01, 0x00, 0x00], &mut cmds), 0);
+        assert_eq!(cmds.len(), 0);
+
+        // The message truncated at the end is discarded.
+        let mut handler = CommandDspMessageHandler::default();
+        handler.cache.extend_from_slice(&raw[..(raw.len() - 3)]);
+        let cmds = handler.decode_messages();
+        assert_eq!(cmds.len(), 6);
+        assert!(!handler.has_dsp_message());
+    }
+
+    #[test]
+    fn test_random_bytes() {
+        let mut seed = 0x12345678;
+
+        (0..1000).for_each(|_| {
+            let length = next_byte(&mut seed);
+            let raw: Vec<u8> = (0..length).map(|_| next_byte(&mut seed) as u8).collect();
+
+            let mut cmds = Vec::new();
+            assert!(DspCmd::parse(&raw, &mut cmds) <= raw.len());
+
+            let mut handler = CommandDspMessageHandler::default();
+            handler.cache.extend_from_slice(&raw);
+            let _ = handler.decode_messages();
+            assert!(!handler.has_dsp_message());
+
+            let mut handler = CommandDspMessageHandler::default();
+            handler.cache_dsp_messages(&raw);
+            handler.cache_dsp_messages(&raw[..(length / 2)]);
+            if handler.has_dsp_message() {
+                let _ = handler.decode_messages();
+            }
+        });
+    }
+
+    #[test]
+    fn test_interleaved_active_sensing() {
+        let mut handler = CommandDspMessageHandler::default();
+        let mut seq_num = 0;
+
+        // The active sensing message with sequence number.
+        let cmds = create_width_cmds(30, 0);
+        let mut frames = build_frames(&mut seq_num, &cmds);
+        assert_eq!(frames.len(), 2);
+        frames[1][1] = 2;
+        seq_num = 3;
+        handler.cache_dsp_messages(&frames[0]);
+        assert!(!handler.has_dsp_message());
+        handler.cache_dsp_messages(&[0x00, 0x01, 0x00, 0x00]);
+        assert!(!handler.has_dsp_message());
+        handler.cache_dsp_messages(&frames[1]);
+        assert_eq!(handler.decode_messages(), cmds);
+
+        // The active sensing message without sequence number.
+        let cmds = create_width_cmds(30, 100);
+        let frames = build_frames(&mut seq_num, &cmds);
+        handler.cache_dsp_messages(&frames[0]);
+        handler.cache_dsp_messages(&[0x00, 0xa0]);
+        handler.cache_dsp_messages(&frames[1]);
+        assert_eq!(handler.decode_messages(), cmds);
+    }
+
+    #[test]
+    fn test_lost_fragment() {
+        let mut handler = CommandDspMessageHandler::default();
+        let mut seq_num = 0;
+
+        let first_cmds = create_width_cmds(4, 0);
+        build_frames(&mut seq_num, &first_cmds)
+            .iter()
+            .for_each(|frame| handler.cache_dsp_messages(frame));
+
+        // The second fragment is lost.
+        let lost_cmds = create_width_cmds(60, 100);
+        build_frames(&mut seq_num, &lost_cmds)
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .for_each(|(_, frame)| handler.cache_dsp_messages(frame));
+
+        let last_cmds = create_width_cmds(30, 200);
+        build_frames(&mut seq_num, &last_cmds)
+            .iter()
+            .for_each(|frame| handler.cache_dsp_messages(frame));
+
+        assert!(handler.has_dsp_message());
+        let mut expected = first_cmds.clone();
+        expected.extend_from_slice(&last_cmds);
+        assert_eq!(handler.decode_messages(), expected);
+
+        // The last fragment is lost, then the message is dropped with the next message.
+        let lost_cmds = create_width_cmds(30, 300);
+        let frames = build_frames(&mut seq_num, &lost_cmds);
+        handler.cache_dsp_messages(&frames[0]);
+        let next_cmds = create_width_cmds(4, 400);
+        build_frames(&mut seq_num, &next_cmds)
+            .iter()
+            .for_each(|frame| handler.cache_dsp_messages(frame));
+        let cmds = create_width_cmds(4, 500);
+        build_frames(&mut seq_num, &cmds)
+            .iter()
+            .for_each(|frame| handler.cache_dsp_messages(frame));
+        assert_eq!(handler.decode_messages(), cmds);
+    }
+
+    #[test]
+    fn test_talkback_dim() {
+        let mut dim = CommandDspTalkbackDimState::default();
+        dim.dim = 20;
+
+        let mut old = CommandDspMonitorState::default();
+        old.main_volume = 0.5;
+
+        // Enable talkback.
+        let mut new = old;
+        new.talkback_enable = true;
+        dim.follow(&old, &mut new);
+        assert!(dim.is_dimmed());
+        assert!((new.main_volume - 0.05).abs() < 1e-6);
+        let dimmed = new.main_volume;
+
+        // The echo of dimmed volume and stale volume from the unit.
+        let old = new;
+        let mut new = old;
+        dim.follow(&old, &mut new);
+        assert_eq!(new.main_volume, dimmed);
+        new.main_volume = 0.5;
+        dim.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.5);
+
+        // Disable talkback.
+        let mut restored = old;
+        restored.talkback_enable = false;
+        let mut d = dim;
+        d.follow(&old, &mut restored);
+        assert!(!d.is_dimmed());
+        assert_eq!(restored.main_volume, 0.5);
+
+        // Change main volume during talkback, then disable talkback.
+        let mut new = old;
+        new.main_volume = 0.25;
+        dim.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.25);
+        let old = new;
+        let mut new = old;
+        new.talkback_enable = false;
+        dim.follow(&old, &mut new);
+        assert!(!dim.is_dimmed());
+        assert_eq!(new.main_volume, 0.25);
+
+        // Disable talkback with the other volume at the same time.
+        let mut old = new;
+        let mut new = old;
+        new.talkback_enable = true;
+        dim.follow(&old, &mut new);
+        old = new;
+        new.talkback_enable = false;
+        new.main_volume = 0.75;
+        dim.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.75);
+    }
+
+    fn create_test_mixer_state(source_count: usize) -> CommandDspMixerState {
+        let mut state = CommandDspMixerState::default();
+        state.source.iter_mut().for_each(|src| {
+            src.mute = vec![false; source_count];
+            src.solo = vec![false; source_count];
+        });
+        state
+    }
+
+    #[test]
+    fn test_solo_in_place() {
+        let mut solo = CommandDspSoloState::default();
+
+        let mut old = create_test_mixer_state(4);
+        old.source[1].mute[2] = true;
+        old.source[1].mute[3] = true;
+
+        // The first solo mutes the others.
+        let mut new = old.clone();
+        new.source[1].solo[3] = true;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[1].mute, vec![true, true, true, true]);
+        assert_eq!(new.source[0].mute, vec![false; 4]);
+
+        // The second solo unmutes the source unless it was muted before.
+        let old = new;
+        let mut new = old.clone();
+        new.source[1].solo[0] = true;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[1].mute, vec![false, true, true, true]);
+
+        // The source is muted again when its solo is cleared.
+        let old = new;
+        let mut new = old.clone();
+        new.source[1].solo[0] = false;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[1].mute, vec![true, true, true, true]);
+
+        // The unit changes mute meanwhile, then the last solo is cleared.
+        let mut old = new;
+        old.source[1].mute[1] = false;
+        let mut new = old.clone();
+        new.source[1].solo[3] = false;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[1].mute, vec![false, false, true, true]);
+
+        // Nothing happens without solo.
+        let old = new;
+        let mut new = old.clone();
+        new.source[1].mute[0] = true;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[1].mute, vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn test_solo_after_fader_listen() {
+        let mut solo = CommandDspSoloState::default();
+        solo.mode = SoloMode::AfterFaderListen;
+
+        let mut old = create_test_mixer_state(4);
+        old.source[2].mute[0] = true;
+
+        let mut new = old.clone();
+        new.source[2].solo[3] = true;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[2].mute, vec![true, false, false, false]);
+
+        let old = new;
+        let mut new = old.clone();
+        new.source[2].solo[1] = true;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[2].mute, vec![true, false, false, false]);
+
+        let old = new;
+        let mut new = old.clone();
+        new.source[2].solo[1] = false;
+        new.source[2].solo[3] = false;
+        solo.follow_mixer(&old, &mut new);
+        assert_eq!(new.source[2].mute, vec![true, false, false, false]);
+        assert!(!CommandDspSoloState::is_active(&new));
+
+        // Nothing to be restored.
+        let monitor = CommandDspMonitorState::default();
+        assert_eq!(solo.build_panic_commands(&new, &monitor), Vec::new());
+    }
+
+    #[test]
+    fn test_panic() {
+        let mut solo = CommandDspSoloState::default();
+        let mut monitor = CommandDspMonitorState::default();
+
+        let mut old = create_test_mixer_state(4);
+        old.source[1].mute[2] = true;
+        let mut mixer = old.clone();
+        mixer.source[1].solo[0] = true;
+        mixer.source[3].solo[1] = true;
+        solo.follow_mixer(&old, &mut mixer);
+        mixer.output_assign[2] = TargetPort::MainPair0;
+        mixer.output_mute[2] = true;
+        mixer.output_assign[4] = TargetPort::AnalogPair0;
+        mixer.output_mute[4] = true;
+        monitor.talkback_enable = true;
+
+        let cmds = solo.build_panic_commands(&mixer, &monitor);
+        assert_eq!(
+            cmds,
+            vec![
+                DspCmd::Mixer(MixerCmd::SourceSolo(1, 0, false)),
+                DspCmd::Mixer(MixerCmd::SourceMute(1, 1, false)),
+                DspCmd::Mixer(MixerCmd::SourceMute(1, 3, false)),
+                DspCmd::Mixer(MixerCmd::SourceSolo(3, 1, false)),
+                DspCmd::Mixer(MixerCmd::SourceMute(3, 0, false)),
+                DspCmd::Mixer(MixerCmd::SourceMute(3, 2, false)),
+                DspCmd::Mixer(MixerCmd::SourceMute(3, 3, false)),
+                DspCmd::Mixer(MixerCmd::OutputMute(2, false)),
+                DspCmd::Monitor(MonitorCmd::TalkbackEnable(false)),
+            ]
+        );
+
+        TestProtocol::parse_mixer_commands(&mut mixer, &cmds);
+        TestProtocol::parse_monitor_commands(&mut monitor, &cmds);
+        assert!(!CommandDspSoloState::is_active(&mixer));
+        assert_eq!(mixer.source[1].mute, vec![false, false, true, false]);
+        assert_eq!(mixer.output_mute[4], true);
+
+        // Nothing to be cleared.
+        assert_eq!(solo.build_panic_commands(&mixer, &monitor), Vec::new());
+    }
+
+    #[test]
+    fn test_main_volume_db() {
+        [
+            (-8000, 0.0001),
+            (-2000, 0.1),
+            (0, 1.0),
+        ]
+            .iter()
+            .for_each(|&(db, volume)| {
+                let val = CommandDspMonitorState::main_volume_from_db(db);
+                assert!((val - volume).abs() < volume * 1e-4, "{} {}", db, val);
+                assert_eq!(CommandDspMonitorState::main_volume_to_db(val), db);
+            });
+
+        let mute = CommandDspMonitorState::MAIN_VOLUME_DB_MUTE;
+        assert_eq!(CommandDspMonitorState::main_volume_from_db(mute), 0.0);
+        assert_eq!(CommandDspMonitorState::main_volume_to_db(0.0), mute);
+        assert_eq!(CommandDspMonitorState::main_volume_to_db(0.00005), mute);
+    }
+
+    #[test]
+    fn test_preset() {
+        let mut preset = create_test_preset();
+        preset.reverb.enable = true;
+        preset.reverb.pre_delay = 50;
+        preset.monitor.main_volume = 0.5;
+        preset.monitor.assign_target = TargetPort::AnalogPair0;
+        preset.mixer.output_mute[3] = true;
+        preset.mixer.source[1].gain[1] = 0.25;
+        preset.input.phase[1] = true;
+        preset.input.gain[0] = -12;
+        preset.output.master_monitor[1] = true;
+        let raw = TestProtocol::build_preset(&preset);
+
+        let mut parsed = create_test_preset();
+        assert_eq!(TestProtocol::parse_preset(&mut parsed, &raw).unwrap(), 0);
+        assert_eq!(parsed.reverb.enable, true);
+        assert_eq!(parsed.reverb.pre_delay, 50);
+        assert_eq!(parsed.monitor.main_volume, 0.5);
+        assert_eq!(parsed.monitor.assign_target, TargetPort::AnalogPair0);
+        assert_eq!(parsed.mixer.output_mute[3], true);
+        assert_eq!(parsed.mixer.source[1].gain[1], 0.25);
+        assert_eq!(parsed.input.phase[1], true);
+        assert_eq!(parsed.input.gain[0], -12);
+        assert_eq!(parsed.output.master_monitor[1], true);
+        assert_eq!(TestProtocol::build_preset(&parsed), raw);
+    }
+
+    #[test]
+    fn test_preset_unknown_command() {
+        let preset = create_test_preset();
+        let mut raw = TestProtocol::build_preset(&preset);
+        let mut expected = create_test_preset();
+        TestProtocol::parse_preset(&mut expected, &raw).unwrap();
+
+        // Later version with unknown command.
+        raw[7] = 2;
+        raw.extend_from_slice(&[0x66, 0x00, 0x07, 0x00, 0xff, 0x00, 0x00, 0x00, 0x01]);
+        let mut parsed = create_test_preset();
+        assert_eq!(TestProtocol::parse_preset(&mut parsed, &raw).unwrap(), 1);
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_preset_update_in_batch() {
+        // The state in the unit, and the pending state during batched write.
+        let mut sent = create_test_preset();
+        let mut pending = sent.clone();
+
+        // The knob is turned after the write is queued.
+        pending.input.gain[0] = -10;
+        pending.input.phase[0] = true;
+        let cmds = [DspCmd::Input(InputCmd::Gain(0, -5))];
+        TestProtocol::update_preset(&mut pending, &cmds);
+        TestProtocol::update_preset(&mut sent, &cmds);
+        assert_eq!(pending.input.gain[0], -5);
+        assert_eq!(sent.input.gain[0], pending.input.gain[0]);
+        // The pending write for the other parameter is kept.
+        assert_ne!(sent.input.phase[0], pending.input.phase[0]);
+
+        // The write is queued after the knob is turned.
+        let cmds = [DspCmd::Input(InputCmd::Gain(1, -3))];
+        TestProtocol::update_preset(&mut pending, &cmds);
+        TestProtocol::update_preset(&mut sent, &cmds);
+        pending.input.gain[1] = -20;
+        assert_eq!(sent.input.gain[1], -3);
+        assert_eq!(pending.input.gain[1], -20);
+
+        // The knob is turned again.
+        let cmds = [
+            DspCmd::Input(InputCmd::Gain(1, -2)),
+            DspCmd::Input(InputCmd::Gain(1, -1)),
+        ];
+        TestProtocol::update_preset(&mut pending, &cmds);
+        TestProtocol::update_preset(&mut sent, &cmds);
+        assert_eq!(pending.input.gain, vec![-5, -1]);
+        assert_eq!(sent.input.gain, pending.input.gain);
+
+        pending.input.phase[0] = false;
+        assert_eq!(sent, pending);
+    }
+
+    #[test]
+    fn test_reverb_ramp() {
+        let origin = CommandDspReverbState {
+            decay_time: 1000,
+            shelf_filter_attenuation: -40,
+            width: -1.0,
+            ..Default::default()
+        };
+        let target = CommandDspReverbState {
+            enable: true,
+            decay_time: 2000,
+            shelf_filter_attenuation: 0,
+            width: 1.0,
+            reflection_mode: RoomShape::C,
+            ..origin
+        };
+
+        let mut ramp = CommandDspReverbRamp::default();
+        assert_eq!(ramp.next_state(), None);
+
+        ramp.start(&origin, &target, 4);
+        let mut current = origin;
+        let mut seqs = Vec::new();
+        while let Some(state) = ramp.next_state() {
+            seqs.push(diff_reverb_commands(&state, &current));
+            current = state;
+        }
+        assert_eq!(current, target);
+        assert_eq!(
+            seqs,
+            vec![
+                vec![
+                    DspCmd::Reverb(ReverbCmd::Enable(true)),
+                    DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(-30)),
+                    DspCmd::Reverb(ReverbCmd::DecayTime(1250)),
+                    DspCmd::Reverb(ReverbCmd::Width(-0.5)),
+                    DspCmd::Reverb(ReverbCmd::ReflectionMode(RoomShape::C)),
+                ],
+                vec![
+                    DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(-20)),
+                    DspCmd::Reverb(ReverbCmd::DecayTime(1500)),
+                    DspCmd::Reverb(ReverbCmd::Width(0.0)),
+                ],
+                vec![
+                    DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(-10)),
+                    DspCmd::Reverb(ReverbCmd::DecayTime(1750)),
+                    DspCmd::Reverb(ReverbCmd::Width(0.5)),
+                ],
+                vec![
+                    DspCmd::Reverb(ReverbCmd::ShelfFilterAttenuation(0)),
+                    DspCmd::Reverb(ReverbCmd::DecayTime(2000)),
+                    DspCmd::Reverb(ReverbCmd::Width(1.0)),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverb_ramp_retarget() {
+        let origin = CommandDspReverbState {
+            decay_time: 1000,
+            ..Default::default()
+        };
+        let target = CommandDspReverbState {
+            decay_time: 2000,
+            ..origin
+        };
+
+        let mut ramp = CommandDspReverbRamp::default();
+        ramp.start(&origin, &target, 4);
+        let current = ramp.next_state().unwrap();
+        assert_eq!(current.decay_time, 1250);
+
+        // The new target is applied from the current state, instead of queueing.
+        let target = CommandDspReverbState {
+            decay_time: 250,
+            ..origin
+        };
+        ramp.start(&current, &target, 4);
+        assert_eq!(ramp.target(), &target);
+        let decay_times: Vec<u32> = std::iter::from_fn(|| ramp.next_state())
+            .map(|state| state.decay_time)
+            .collect();
+        assert_eq!(decay_times, vec![1000, 750, 500, 250]);
+        assert!(!ramp.is_active());
+    }
+
+    #[test]
+    fn test_register_dsp_mixer_conversion() {
+        let mut output = RegisterDspMixerOutputState::default();
+        output.volume = [0x80, 0x00, 0x40, 0x80];
+        output.mute = [false, true, false, false];
+        output.destination = [
+            TargetPort::AnalogPair0,
+            TargetPort::MainPair0,
+            TargetPort::PhonePair0,
+            TargetPort::MainPair0,
+        ];
+
+        let ports = [TargetPort::Analog1, TargetPort::Analog0, TargetPort::Spdif0];
+        let mut sources = RegisterDspMixerMonauralSourceState::default();
+        sources.0.iter_mut().for_each(|entry| {
+            entry.gain = vec![0x80, 0x00, 0x80];
+            entry.pan = vec![0x40, 0x00, 0x80];
+            entry.mute = vec![false, true, false];
+            entry.solo = vec![true, false, false];
+        });
+        // Approximately -20 dB.
+        sources.0[1].gain[0] = 13;
+
+        let mut state = TestProtocol::create_mixer_state();
+        let dropped = TestProtocol::convert_register_dsp_mixer_state(
+            &mut state,
+            &output,
+            &ports,
+            &sources,
+        );
+        assert_eq!(dropped, 4);
+
+        assert_eq!(state.output_assign[0], TargetPort::AnalogPair0);
+        assert_eq!(state.output_assign[2], TargetPort::MainPair0);
+        assert!(state.output_mute[1]);
+        assert_eq!(state.output_volume[0], 1.0);
+        assert_eq!(state.output_volume[1], 0.0);
+        assert_eq!(state.output_volume[2], 0.5);
+
+        // 0 dB.
+        assert_eq!(state.source[0].gain, vec![0.0, 1.0]);
+        assert_eq!(state.source[0].pan, vec![-1.0, 0.0]);
+        assert_eq!(state.source[0].solo, vec![false, true]);
+
+        // -20 dB within the resolution of register DSP.
+        let db = 20.0 * state.source[1].gain[1].log10();
+        assert!((db + 20.0).abs() < 0.2, "{}", db);
+
+        // Mute.
+        assert_eq!(state.source[0].mute, vec![true, false]);
+        assert_eq!(state.source[0].gain[0], 0.0);
+
+        // The rest of mixers are left as is.
+        assert_eq!(state.source[4], TestProtocol::create_mixer_state().source[4]);
+    }
+
+    #[test]
+    fn test_register_dsp_mixer_preset() {
+        let mut output = RegisterDspMixerOutputState::default();
+        output.volume = [0x40, 0x80, 0x80, 0x80];
+        output.destination = [TargetPort::AnalogPair0; 4];
+
+        let ports = [TargetPort::Analog1, TargetPort::Spdif0];
+        let mut sources = RegisterDspMixerMonauralSourceState::default();
+        sources.0.iter_mut().for_each(|entry| {
+            entry.gain = vec![0x80, 0x80];
+            entry.pan = vec![0x00, 0x40];
+            entry.mute = vec![false, false];
+            entry.solo = vec![false, false];
+        });
+
+        let (raw, dropped) =
+            TestProtocol::build_preset_from_register_dsp_mixer(&output, &ports, &sources);
+        assert_eq!(dropped, 4);
+
+        let mut preset = create_test_preset();
+        preset.reverb.enable = true;
+        preset.mixer.reverb_send[0] = 0.5;
+        preset.mixer.source[0].gain[0] = 0.25;
+        preset.mixer.output_volume[5] = 0.75;
+        let mut expected = preset.clone();
+
+        assert_eq!(TestProtocol::parse_preset(&mut preset, &raw).unwrap(), 0);
+
+        // The converted parameters.
+        expected.mixer.output_assign[..4]
+            .iter_mut()
+            .for_each(|port| *port = TargetPort::AnalogPair0);
+        expected.mixer.output_volume[..4].copy_from_slice(&[0.5, 1.0, 1.0, 1.0]);
+        expected.mixer.source[..4].iter_mut().for_each(|src| {
+            src.gain[1] = 1.0;
+            src.pan[1] = -1.0;
+        });
+        // The rest of parameters are left as is.
+        assert_eq!(preset, expected);
+    }
+
+    #[test]
+    fn test_preset_invalid() {
+        let preset = create_test_preset();
+        let raw = TestProtocol::build_preset(&preset);
+
+        let mut parsed = create_test_preset();
+        assert!(TestProtocol::parse_preset(&mut parsed, &raw[..raw.len() - 1]).is_err());
+        assert!(TestProtocol::parse_preset(&mut parsed, &raw[..4]).is_err());
+
+        let mut invalid = raw.clone();
+        invalid[0] = b'X';
+        assert!(TestProtocol::parse_preset(&mut parsed, &invalid).is_err());
+    }
 }
@@ -17,60 +17,157 @@ pub struct UnitData {
 
 const OUI_MOTU: u32 = 0x0001f2;
 
+const SUPPORTED_MODEL_IDS: [u32; 13] = [
+    0x000001, 0x000002, 0x000003, 0x000005, 0x000009, 0x00000d, 0x00000f, 0x000015, 0x000019,
+    0x000030, 0x000033, 0x000035, 0x000045,
+];
+
+/// The enumeration to represent error to identify the unit by Configuration ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MotuConfigRomError {
+    /// The content of Configuration ROM is not parsed.
+    UnreadableRom(ConfigRomParseError),
+    /// The vendor or specifier ID is not for Mark of the Unicorn.
+    VendorMismatch { found: u32 },
+    /// The model ID is not for supported models.
+    UnknownModel { model_id: u32 },
+    /// The entry is missing or has unexpected type.
+    MalformedEntry { key: KeyType },
+}
+
+impl std::fmt::Display for MotuConfigRomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnreadableRom(cause) => {
+                write!(f, "Malformed configuration ROM detected: {}", cause)
+            }
+            Self::VendorMismatch { found } => {
+                write!(
+                    f,
+                    "Unexpected vendor ID 0x{:06x}, not for Mark of the Unicorn",
+                    found
+                )
+            }
+            Self::UnknownModel { model_id } => {
+                write!(f, "Unsupported model ID: 0x{:06x}", model_id)
+            }
+            Self::MalformedEntry { key } => write!(f, "{:?} entry is missing or malformed", key),
+        }
+    }
+}
+
+impl From<ConfigRomParseError> for MotuConfigRomError {
+    fn from(cause: ConfigRomParseError) -> Self {
+        Self::UnreadableRom(cause)
+    }
+}
+
 /// The trait for parser of configuration ROM.
 pub trait MotuConfigRom {
-    fn get_unit_data(&self) -> Option<UnitData>;
+    fn get_unit_data(&self) -> Result<UnitData, MotuConfigRomError>;
+
+    /// Check the data in unit directory and detect unsupported models.
+    fn identify_model(&self) -> Result<UnitData, MotuConfigRomError> {
+        let unit_data = self.get_unit_data()?;
+        if SUPPORTED_MODEL_IDS.contains(&unit_data.model_id) {
+            Ok(unit_data)
+        } else {
+            Err(MotuConfigRomError::UnknownModel {
+                model_id: unit_data.model_id,
+            })
+        }
+    }
 }
 
 impl<'a> MotuConfigRom for ConfigRom<'a> {
-    fn get_unit_data(&self) -> Option<UnitData> {
-        self.root
+    fn get_unit_data(&self) -> Result<UnitData, MotuConfigRomError> {
+        let vendor_id = self
+            .root
             .iter()
-            .find_map(|entry| {
-                EntryDataAccess::<u32>::get(entry, KeyType::Vendor).and_then(|vendor_id| {
-                    if vendor_id == OUI_MOTU {
-                        Some(vendor_id)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .and_then(|_| {
-                self.root
-                    .iter()
-                    .find_map(|entry| EntryDataAccess::<&[Entry]>::get(entry, KeyType::Unit))
-                    .and_then(|entries| {
-                        entries
-                            .iter()
-                            .find_map(|entry| {
-                                EntryDataAccess::<u32>::get(entry, KeyType::SpecifierId).and_then(
-                                    |specifier_id| {
-                                        if specifier_id == OUI_MOTU {
-                                            Some(specifier_id)
-                                        } else {
-                                            None
-                                        }
-                                    },
-                                )
-                            })
-                            .and_then(|_| {
-                                // NOTE: It's odd but version field is used for model ID and model field is
-                                // used for version in MOTU case.
-                                entries
-                                    .iter()
-                                    .find_map(|entry| {
-                                        EntryDataAccess::<u32>::get(entry, KeyType::Version)
-                                    })
-                                    .and_then(|model_id| {
-                                        entries
-                                            .iter()
-                                            .find_map(|entry| {
-                                                EntryDataAccess::<u32>::get(entry, KeyType::Model)
-                                            })
-                                            .map(|version| UnitData { model_id, version })
-                                    })
-                            })
-                    })
-            })
+            .find_map(|entry| EntryDataAccess::<u32>::get(entry, KeyType::Vendor))
+            .ok_or(MotuConfigRomError::MalformedEntry {
+                key: KeyType::Vendor,
+            })?;
+        if vendor_id != OUI_MOTU {
+            Err(MotuConfigRomError::VendorMismatch { found: vendor_id })?;
+        }
+
+        let entries = self
+            .root
+            .iter()
+            .find_map(|entry| EntryDataAccess::<&[Entry]>::get(entry, KeyType::Unit))
+            .ok_or(MotuConfigRomError::MalformedEntry { key: KeyType::Unit })?;
+
+        let specifier_id = entries
+            .iter()
+            .find_map(|entry| EntryDataAccess::<u32>::get(entry, KeyType::SpecifierId))
+            .ok_or(MotuConfigRomError::MalformedEntry {
+                key: KeyType::SpecifierId,
+            })?;
+        if specifier_id != OUI_MOTU {
+            Err(MotuConfigRomError::VendorMismatch {
+                found: specifier_id,
+            })?;
+        }
+
+        // NOTE: It's odd but version field is used for model ID and model field is used for
+        // version in MOTU case.
+        let model_id = entries
+            .iter()
+            .find_map(|entry| EntryDataAccess::<u32>::get(entry, KeyType::Version))
+            .ok_or(MotuConfigRomError::MalformedEntry {
+                key: KeyType::Version,
+            })?;
+
+        let version = entries
+            .iter()
+            .find_map(|entry| EntryDataAccess::<u32>::get(entry, KeyType::Model))
+            .ok_or(MotuConfigRomError::MalformedEntry {
+                key: KeyType::Model,
+            })?;
+
+        Ok(UnitData { model_id, version })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    const F828MK2_CONFIG_ROM: [u8; 104] = [
+        0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0x00, 0xff, 0x50, 0x02, 0x00, 0x02, 0x2e,
+        0xff, 0x12, 0x34, 0x56, 0x78, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x01, 0xf2, 0x0c, 0x00,
+        0x83, 0xc0, 0xd1, 0x00, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x12, 0x00, 0x01, 0xf2, 0x13,
+        0x00, 0x00, 0x03, 0x17, 0x00, 0x01, 0x00, 0xd4, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00,
+        0x81, 0x00, 0x00, 0x02, 0x82, 0x00, 0x00, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x4d, 0x4f, 0x54, 0x55, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x32, 0x38, 0x6d, 0x6b, 0x49, 0x49, 0x00,
+    ];
+
+    #[test]
+    fn test_identify_model() {
+        let config_rom = ConfigRom::try_from(&F828MK2_CONFIG_ROM[..]).unwrap();
+        let unit_data = config_rom.identify_model().unwrap();
+        assert_eq!(unit_data.model_id, 0x000003);
+        assert_eq!(unit_data.version, 0x000100);
+
+        let mut raw = F828MK2_CONFIG_ROM;
+        // The version entry in unit directory.
+        raw[47] = 0x04;
+        let config_rom = ConfigRom::try_from(&raw[..]).unwrap();
+        assert_eq!(
+            config_rom.identify_model().unwrap_err(),
+            MotuConfigRomError::UnknownModel { model_id: 0x000004 }
+        );
+
+        let mut raw = F828MK2_CONFIG_ROM;
+        // The vendor entry in root directory.
+        raw[25..28].copy_from_slice(&[0x00, 0x02, 0x2e]);
+        let config_rom = ConfigRom::try_from(&raw[..]).unwrap();
+        assert_eq!(
+            config_rom.identify_model().unwrap_err(),
+            MotuConfigRomError::VendorMismatch { found: 0x00022e }
+        );
     }
 }
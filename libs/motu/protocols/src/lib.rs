@@ -11,12 +11,20 @@ pub mod version_2;
 pub mod version_3;
 pub mod register_dsp;
 pub mod command_dsp;
+pub mod trace;
 
-use glib::{Error, FileError};
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use glib::{error::ErrorDomain, Error, FileError, Quark};
+use hinawa::{FwNode, FwReq};
+
+use fw_transaction::FwTransaction;
+
+use trace::{record_result, record_transaction, TraceDirection};
 
 use std::{thread, time};
 
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 const BASE_OFFSET: u64 = 0xfffff0000000;
 const OFFSET_CLK: u32 = 0x0b14;
 const OFFSET_PORT: u32 = 0x0c04;
@@ -28,58 +36,101 @@ fn read_quad(
     offset: u32,
     timeout_ms: u32
 ) -> Result<u32, Error> {
+    read_quad_by(&mut (&*req, &*node), offset, timeout_ms)
+}
+
+fn read_quad_by<T: FwTransaction>(tx: &mut T, offset: u32, timeout_ms: u32) -> Result<u32, Error> {
+    let res = read_quad_once(tx, offset, timeout_ms);
+    record_result(&res);
+    res
+}
+
+fn read_quad_once<T: FwTransaction>(tx: &mut T, offset: u32, timeout_ms: u32) -> Result<u32, Error> {
     let mut frame = [0; 4];
-    req.transaction_sync(
-        node,
-        FwTcode::ReadQuadletRequest,
-        BASE_OFFSET + offset as u64,
-        4,
-        &mut frame,
-        timeout_ms,
-    )
-    .map(|_| u32::from_be_bytes(frame))
+    let res = tx.read(BASE_OFFSET + offset as u64, &mut frame, timeout_ms);
+    record_transaction(TraceDirection::Read, BASE_OFFSET + offset as u64, &frame, &res);
+    res.map(|_| u32::from_be_bytes(frame))
+}
+
+fn write_quad(
+    req: &FwReq,
+    node: &mut FwNode,
+    offset: u32,
+    quad: u32,
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    write_quad_by(&mut (&*req, &*node), offset, quad, timeout_ms)
 }
 
 // AudioExpress sometimes transfers response subaction with non-standard rcode. This causes
 // Linux firewire subsystem to report 'unsolicited response' error. In the case, send error
 // is reported to userspace applications. As a workaround, the change of register is ensured
 // by following read transaction in failure of write transaction.
-fn write_quad(
-    req: &FwReq,
-    node: &mut FwNode,
+fn write_quad_by<T: FwTransaction>(
+    tx: &mut T,
     offset: u32,
     quad: u32,
     timeout_ms: u32,
 ) -> Result<(), Error> {
     let mut frame = [0; 4];
     frame.copy_from_slice(&quad.to_be_bytes());
-    req.transaction_sync(
-        node,
-        FwTcode::WriteQuadletRequest,
-        BASE_OFFSET + offset as u64,
-        4,
-        &mut frame,
-        timeout_ms,
-    )
-    .or_else(|err| {
-        // For prevention of RCODE_BUSY.
-        thread::sleep(time::Duration::from_millis(BUSY_DURATION));
-        req.transaction_sync(
-            node,
-            FwTcode::WriteQuadletRequest,
-            BASE_OFFSET + offset as u64,
-            4,
-            &mut frame,
-            timeout_ms,
-        )
-        .and_then(|_| {
-            if u32::from_be_bytes(frame) == quad {
-                Ok(())
-            } else {
-                Err(err)
+    let res = write_quad_once(tx, offset, &mut frame, timeout_ms)
+        .or_else(|err| {
+            // For prevention of RCODE_BUSY.
+            thread::sleep(time::Duration::from_millis(BUSY_DURATION));
+            frame.copy_from_slice(&quad.to_be_bytes());
+            write_quad_once(tx, offset, &mut frame, timeout_ms)
+                .or_else(|_| verify_quad_by(tx, offset, quad, timeout_ms, err))
+        });
+    record_result(&res);
+    res
+}
+
+fn write_quad_once<T: FwTransaction>(
+    tx: &mut T,
+    offset: u32,
+    frame: &mut [u8; 4],
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    let data = *frame;
+    let res = tx.write(BASE_OFFSET + offset as u64, frame, timeout_ms);
+    record_transaction(TraceDirection::Write, BASE_OFFSET + offset as u64, &data, &res);
+    res
+}
+
+// The device applies the write a few milliseconds later than the response subaction in the case,
+// thus the read transaction can return the stale value.
+fn verify_quad_by<T: FwTransaction>(
+    tx: &mut T,
+    offset: u32,
+    quad: u32,
+    timeout_ms: u32,
+    err: Error,
+) -> Result<(), Error> {
+    let mut last = None;
+    for i in 0..READ_BACK_COUNT {
+        if i > 0 {
+            thread::sleep(time::Duration::from_millis(READ_BACK_INTERVAL));
+        }
+        if let Ok(val) = read_quad_once(tx, offset, timeout_ms) {
+            if val == quad {
+                return Ok(());
             }
-        })
-    })
+            last = Some(val);
+        }
+    }
+
+    match last {
+        Some(val) => {
+            let msg = format!(
+                "Read back 0x{:08x} instead of 0x{:08x} at 0x{:04x} after failure of write: {}",
+                val, quad, offset, err
+            );
+            Err(Error::new(MotuProtocolError::UnverifiedWrite, &msg))
+        }
+        // The register is not readable, or the node is not available anymore.
+        None => Err(err),
+    }
 }
 
 fn get_idx_from_val(
@@ -92,7 +143,19 @@ fn get_idx_from_val(
     vals: &[u8],
     timeout_ms: u32,
 ) -> Result<usize, Error> {
-    let quad = read_quad(req, node, offset, timeout_ms)?;
+    get_idx_from_val_by(offset, mask, shift, label, &mut (&*req, &*node), vals, timeout_ms)
+}
+
+fn get_idx_from_val_by<T: FwTransaction>(
+    offset: u32,
+    mask: u32,
+    shift: usize,
+    label: &str,
+    tx: &mut T,
+    vals: &[u8],
+    timeout_ms: u32,
+) -> Result<usize, Error> {
+    let quad = read_quad_by(tx, offset, timeout_ms)?;
     let val = ((quad & mask) >> shift) as u8;
     vals.iter().position(|&v| v == val).ok_or_else(|| {
         let label = format!("Detect invalid value for {}: {:02x}", label, val);
@@ -110,18 +173,73 @@ fn set_idx_to_val(
     vals: &[u8],
     idx: usize,
     timeout_ms: u32,
+) -> Result<(), Error> {
+    set_idx_to_val_by(offset, mask, shift, label, &mut (&*req, &*node), vals, idx, timeout_ms)
+}
+
+fn set_idx_to_val_by<T: FwTransaction>(
+    offset: u32,
+    mask: u32,
+    shift: usize,
+    label: &str,
+    tx: &mut T,
+    vals: &[u8],
+    idx: usize,
+    timeout_ms: u32,
 ) -> Result<(), Error> {
     if idx >= vals.len() {
         let label = format!("Invalid argument for {}: {} {}", label, vals.len(), idx);
         return Err(Error::new(FileError::Inval, &label));
     }
-    let mut quad = read_quad(req, node, offset, timeout_ms)?;
+    let mut quad = read_quad_by(tx, offset, timeout_ms)?;
     quad &= !mask;
     quad |= (vals[idx] as u32) << shift;
-    write_quad(req, node, offset, quad, timeout_ms)
+    write_quad_by(tx, offset, quad, timeout_ms)
+}
+
+/// The error domain for the operation specific to the protocols.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MotuProtocolError {
+    /// The write transaction failed and the value read back is different from the written one.
+    /// The register can still be changed by the device later.
+    UnverifiedWrite,
+    Invalid(i32),
+}
+
+impl std::fmt::Display for MotuProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let msg = match self {
+            MotuProtocolError::UnverifiedWrite => "unverified-write",
+            MotuProtocolError::Invalid(_) => "invalid",
+        };
+
+        write!(f, "MotuProtocolError::{}", msg)
+    }
+}
+
+impl ErrorDomain for MotuProtocolError {
+    fn domain() -> Quark {
+        Quark::from_string("motu-protocol-error-quark")
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            MotuProtocolError::UnverifiedWrite => 0,
+            MotuProtocolError::Invalid(v) => v,
+        }
+    }
+
+    fn from(code: i32) -> Option<Self> {
+        let enumeration = match code {
+            0 => MotuProtocolError::UnverifiedWrite,
+            _ => MotuProtocolError::Invalid(code),
+        };
+        Some(enumeration)
+    }
 }
 
 /// The enumeration to express rate of sampling clock.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ClkRate {
     /// 44.1 kHx.
     R44100,
@@ -138,7 +256,25 @@ pub enum ClkRate {
 }
 
 const BUSY_DURATION: u64 = 150;
-const DISPLAY_CHARS: usize = 4 * 4;
+
+// The number of read transactions to verify the write, and the interval between them.
+const READ_BACK_COUNT: usize = 4;
+const READ_BACK_INTERVAL: u64 = 5;
+
+/// The number of characters in display for sampling clock.
+pub const DISPLAY_CHARS: usize = 4 * 4;
+
+// The text longer than the display is truncated. The character out of printable ASCII is replaced
+// with space.
+fn build_clk_display_chars(text: &[u8]) -> [u8; DISPLAY_CHARS] {
+    let mut chars = [0x20; DISPLAY_CHARS];
+    chars
+        .iter_mut()
+        .zip(text)
+        .filter(|(_, t)| (0x20..0x7f).contains(*t))
+        .for_each(|(c, &t)| *c = t);
+    chars
+}
 
 fn update_clk_display(
     req: &FwReq,
@@ -146,22 +282,44 @@ fn update_clk_display(
     label: &str,
     timeout_ms: u32,
 ) -> Result<(), Error> {
-    let mut chars = [0x20; DISPLAY_CHARS];
-    chars
-        .iter_mut()
-        .zip(label.bytes())
-        .for_each(|(c, l)| *c = l);
+    update_clk_display_by(&mut (&*req, &*node), label.as_bytes(), timeout_ms)
+}
+
+fn update_clk_display_by<T: FwTransaction>(
+    tx: &mut T,
+    text: &[u8],
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    let chars = build_clk_display_chars(text);
 
     (0..(DISPLAY_CHARS / 4)).try_for_each(|i| {
         let mut frame = [0; 4];
         frame.copy_from_slice(&chars[(i * 4)..(i * 4 + 4)]);
-        frame.reverse();
-        let quad = u32::from_ne_bytes(frame);
+        let quad = u32::from_be_bytes(frame);
         let offset = OFFSET_CLK_DISPLAY + 4 * i as u32;
-        write_quad(req, node, offset, quad, timeout_ms)
+        write_quad_by(tx, offset, quad, timeout_ms)
     })
 }
 
+/// The trait for operation of arbitrary text in display for sampling clock. The register is
+/// write-only.
+pub trait ClkDisplayOperation {
+    /// Build the text actually displayed. The text longer than DISPLAY_CHARS is truncated, and
+    /// the character out of printable ASCII is replaced with space.
+    fn build_clk_display_text(text: &[u8]) -> [u8; DISPLAY_CHARS] {
+        build_clk_display_chars(text)
+    }
+
+    fn update_clk_display_text(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        text: &[u8],
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        update_clk_display_by(&mut (&*req, &*node), text, timeout_ms)
+    }
+}
+
 const PORT_PHONE_LABEL: &str = "phone-assign";
 const PORT_PHONE_MASK: u32 = 0x0000000f;
 const PORT_PHONE_SHIFT: usize = 0;
@@ -224,6 +382,25 @@ impl Default for WordClkSpeedMode {
     }
 }
 
+impl WordClkSpeedMode {
+    /// The rate of word clock output on BNC interface in Hz, derived from the rate of sampling
+    /// clock. In the mode to force low rate, the rate is divided into the base rate of family.
+    pub fn effective_rate(&self, rate: ClkRate) -> u32 {
+        match (self, rate) {
+            (Self::ForceLowRate, ClkRate::R44100) |
+            (Self::ForceLowRate, ClkRate::R88200) |
+            (Self::ForceLowRate, ClkRate::R176400) => 44100,
+            (Self::ForceLowRate, _) => 48000,
+            (Self::FollowSystemClk, ClkRate::R44100) => 44100,
+            (Self::FollowSystemClk, ClkRate::R48000) => 48000,
+            (Self::FollowSystemClk, ClkRate::R88200) => 88200,
+            (Self::FollowSystemClk, ClkRate::R96000) => 96000,
+            (Self::FollowSystemClk, ClkRate::R176400) => 176400,
+            (Self::FollowSystemClk, ClkRate::R192000) => 192000,
+        }
+    }
+}
+
 const WORD_OUT_LABEL: &str = "word-out";
 const WORD_OUT_MASK: u32 = 0x08000000;
 const WORD_OUT_SHIFT: usize = 27;
@@ -232,6 +409,13 @@ const WORD_OUT_VALS: [u8; 2] = [0x00, 0x01];
 
 /// The trait for word-clock protocol.
 pub trait WordClkOperation {
+    /// Retrieve current rate of sampling clock to compute the rate of word clock output.
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error>;
+
     fn get_word_out(
         req: &mut FwReq,
         node: &mut FwNode,
@@ -281,6 +465,7 @@ pub trait WordClkOperation {
 }
 
 /// The enumeration to express the mode of rate convert for AES/EBU input/output signals.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AesebuRateConvertMode {
     /// Not available.
     None,
@@ -292,6 +477,22 @@ pub enum AesebuRateConvertMode {
     OutputDoubleSystem,
 }
 
+impl Default for AesebuRateConvertMode {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// Return human-readable label for the mode of rate convert.
+pub fn aesebu_rate_convert_mode_to_str(mode: &AesebuRateConvertMode) -> &'static str {
+    match mode {
+        AesebuRateConvertMode::None => "None",
+        AesebuRateConvertMode::InputToSystem => "input-is-converted",
+        AesebuRateConvertMode::OutputDependsInput => "output-depends-on-input",
+        AesebuRateConvertMode::OutputDoubleSystem => "output-is-double",
+    }
+}
+
 const AESEBU_RATE_CONVERT_LABEL: &str = "aesebu-rate-convert";
 
 /// The trait for protocol of rate convert specific to AES/EBU input/output signals.
@@ -299,6 +500,7 @@ pub trait AesebuRateConvertOperation {
     const AESEBU_RATE_CONVERT_MASK: u32;
     const AESEBU_RATE_CONVERT_SHIFT: usize;
 
+    /// The value of register corresponding to each entry of AESEBU_RATE_CONVERT_MODES.
     const AESEBU_RATE_CONVERT_VALS: [u8; 4] = [0x00, 0x01, 0x02, 0x03];
 
     const AESEBU_RATE_CONVERT_MODES: [AesebuRateConvertMode; 4] = [
@@ -312,7 +514,7 @@ pub trait AesebuRateConvertOperation {
         req: &mut FwReq,
         node: &mut FwNode,
         timeout_ms: u32,
-    ) -> Result<usize, Error> {
+    ) -> Result<AesebuRateConvertMode, Error> {
         get_idx_from_val(
             OFFSET_CLK,
             Self::AESEBU_RATE_CONVERT_MASK,
@@ -323,14 +525,22 @@ pub trait AesebuRateConvertOperation {
             &Self::AESEBU_RATE_CONVERT_VALS,
             timeout_ms,
         )
+        .map(|idx| Self::AESEBU_RATE_CONVERT_MODES[idx])
     }
 
     fn set_aesebu_rate_convert_mode(
         req: &mut FwReq,
         node: &mut FwNode,
-        idx: usize,
+        mode: AesebuRateConvertMode,
         timeout_ms: u32,
     ) -> Result<(), Error> {
+        let idx = Self::AESEBU_RATE_CONVERT_MODES
+            .iter()
+            .position(|m| mode.eq(m))
+            .ok_or_else(|| {
+                let label = format!("Invalid argument for {}: {:?}", AESEBU_RATE_CONVERT_LABEL, mode);
+                Error::new(FileError::Inval, &label)
+            })?;
         set_idx_to_val(
             OFFSET_CLK,
             Self::AESEBU_RATE_CONVERT_MASK,
@@ -676,3 +886,154 @@ impl Default for NominalSignalLevel {
         Self::Consumer
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fw_transaction::MockTransaction;
+
+    const TEST_MASK: u32 = 0x00000700;
+    const TEST_SHIFT: usize = 8;
+    const TEST_VALS: [u8; 3] = [0x00, 0x02, 0x05];
+
+    #[test]
+    fn test_get_idx_from_val() {
+        let mut tx = MockTransaction::default();
+        let addr = BASE_OFFSET + OFFSET_CLK as u64;
+
+        tx.set_quadlet_be(addr, 0xfffff5ff);
+        let idx =
+            get_idx_from_val_by(OFFSET_CLK, TEST_MASK, TEST_SHIFT, "test", &mut tx, &TEST_VALS, 100)
+                .unwrap();
+        assert_eq!(idx, 2);
+
+        tx.set_quadlet_be(addr, 0x00000300);
+        assert!(
+            get_idx_from_val_by(OFFSET_CLK, TEST_MASK, TEST_SHIFT, "test", &mut tx, &TEST_VALS, 100)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_set_idx_to_val() {
+        let mut tx = MockTransaction::default();
+        let addr = BASE_OFFSET + OFFSET_CLK as u64;
+
+        // The bits out of mask are not changed.
+        tx.set_quadlet_be(addr, 0xffffffff);
+        set_idx_to_val_by(OFFSET_CLK, TEST_MASK, TEST_SHIFT, "test", &mut tx, &TEST_VALS, 1, 100)
+            .unwrap();
+        assert_eq!(tx.get_quadlet_be(addr), Some(0xfffffaff));
+
+        tx.set_quadlet_be(addr, 0x00000000);
+        set_idx_to_val_by(OFFSET_CLK, TEST_MASK, TEST_SHIFT, "test", &mut tx, &TEST_VALS, 2, 100)
+            .unwrap();
+        assert_eq!(tx.get_quadlet_be(addr), Some(0x00000500));
+
+        // The index out of range is rejected without any write.
+        assert!(
+            set_idx_to_val_by(OFFSET_CLK, TEST_MASK, TEST_SHIFT, "test", &mut tx, &TEST_VALS, 3, 100)
+                .is_err()
+        );
+        assert_eq!(tx.get_quadlet_be(addr), Some(0x00000500));
+    }
+
+    // The write transaction always fails, while the device applies the value after the given
+    // number of read transactions.
+    #[derive(Default)]
+    struct LateTransaction {
+        mock: MockTransaction,
+        pending: Option<(u64, Vec<u8>)>,
+        delay: usize,
+    }
+
+    impl FwTransaction for LateTransaction {
+        fn transaction(
+            &mut self,
+            tcode: hinawa::FwTcode,
+            addr: u64,
+            frame: &mut [u8],
+            timeout_ms: u32,
+        ) -> Result<(), Error> {
+            match tcode {
+                hinawa::FwTcode::WriteQuadletRequest => {
+                    self.pending = Some((addr, frame.to_vec()));
+                    Err(Error::new(FileError::Io, "unsolicited response"))
+                }
+                _ => {
+                    if self.delay > 0 {
+                        self.delay -= 1;
+                    } else if let Some((addr, content)) = self.pending.take() {
+                        self.mock.regs.insert(addr, content);
+                    }
+                    self.mock.transaction(tcode, addr, frame, timeout_ms)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_quad_read_back() {
+        let addr = BASE_OFFSET + OFFSET_PORT as u64;
+
+        // The value is read back after some stale responses.
+        let mut tx = LateTransaction {
+            delay: READ_BACK_COUNT - 1,
+            ..Default::default()
+        };
+        tx.mock.set_quadlet_be(addr, 0x00000000);
+        write_quad_by(&mut tx, OFFSET_PORT, 0x01234567, 100).unwrap();
+        assert_eq!(tx.mock.get_quadlet_be(addr), Some(0x01234567));
+
+        // The value is not applied within the retries.
+        let mut tx = LateTransaction {
+            delay: READ_BACK_COUNT,
+            ..Default::default()
+        };
+        tx.mock.set_quadlet_be(addr, 0x00000000);
+        let err = write_quad_by(&mut tx, OFFSET_PORT, 0x01234567, 100).unwrap_err();
+        assert_eq!(
+            err.kind::<MotuProtocolError>(),
+            Some(MotuProtocolError::UnverifiedWrite)
+        );
+
+        // The register is not readable.
+        let mut tx = LateTransaction {
+            delay: READ_BACK_COUNT,
+            ..Default::default()
+        };
+        let err = write_quad_by(&mut tx, OFFSET_PORT, 0x01234567, 100).unwrap_err();
+        assert_eq!(err.kind::<FileError>(), Some(FileError::Io));
+    }
+
+    #[test]
+    fn test_clk_display_text() {
+        assert_eq!(&build_clk_display_chars(b"Internal"), b"Internal        ");
+        assert_eq!(&build_clk_display_chars(b"0123456789abcdefXYZ"), b"0123456789abcdef");
+        assert_eq!(&build_clk_display_chars(b"Caf\xc3\xa9\t\x00!"), b"Caf    !        ");
+
+        let mut tx = MockTransaction::default();
+        update_clk_display_by(&mut tx, b"Song-1", 100).unwrap();
+        let addr = BASE_OFFSET + OFFSET_CLK_DISPLAY as u64;
+        assert_eq!(tx.get_quadlet_be(addr), Some(0x536f6e67));
+        assert_eq!(tx.get_quadlet_be(addr + 4), Some(0x2d312020));
+        assert_eq!(tx.get_quadlet_be(addr + 8), Some(0x20202020));
+        assert_eq!(tx.get_quadlet_be(addr + 12), Some(0x20202020));
+    }
+
+    #[test]
+    fn test_word_out_effective_rate() {
+        [
+            (ClkRate::R44100, 44100, 44100),
+            (ClkRate::R48000, 48000, 48000),
+            (ClkRate::R88200, 44100, 88200),
+            (ClkRate::R96000, 48000, 96000),
+            (ClkRate::R176400, 44100, 176400),
+            (ClkRate::R192000, 48000, 192000),
+        ].iter().for_each(|&(rate, low, follow)| {
+            assert_eq!(WordClkSpeedMode::ForceLowRate.effective_rate(rate), low, "{:?}", rate);
+            let effective = WordClkSpeedMode::FollowSystemClk.effective_rate(rate);
+            assert_eq!(effective, follow, "{:?}", rate);
+        });
+    }
+}
@@ -643,6 +643,33 @@ pub struct Traveler828mk2LineInputState {
 const TRAVELER_828MK2_LINE_INPUT_LEVEL_OFFSET: usize = 0x0c08;
 const TRAVELER_828MK2_LINE_INPUT_BOOST_OFFSET: usize = 0x0c14;
 
+// The registers for nominal level and boost have a bit per channel of line input, from the least
+// significant bit with the offset of channel.
+fn line_input_flag(ch: usize, ch_offset: usize) -> u32 {
+    1 << (ch + ch_offset)
+}
+
+fn build_line_input_levels(level: &[NominalSignalLevel], ch_offset: usize) -> u32 {
+    level
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| NominalSignalLevel::Professional.eq(l))
+        .fold(0u32, |val, (i, _)| val | line_input_flag(i, ch_offset))
+}
+
+fn parse_line_input_levels(level: &mut [NominalSignalLevel], ch_offset: usize, val: u32) {
+    level
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, l)| {
+            *l = if val & line_input_flag(i, ch_offset) > 0 {
+                NominalSignalLevel::Professional
+            } else {
+                NominalSignalLevel::Consumer
+            };
+        });
+}
+
 /// The trait for operation of line input in Traveler and 828mk2.
 pub trait Traveler828mk2LineInputOperation {
     const LINE_INPUT_COUNT: usize;
@@ -662,17 +689,7 @@ pub trait Traveler828mk2LineInputOperation {
         timeout_ms: u32,
     ) -> Result<(), Error> {
         read_quad(req, node, TRAVELER_828MK2_LINE_INPUT_LEVEL_OFFSET as u32, timeout_ms).map(|val| {
-            state.level
-                .iter_mut()
-                .enumerate()
-                .for_each(|(mut i, level)| {
-                    i += Self::CH_OFFSET;
-                    *level = if val & (1 << i) > 0 {
-                        NominalSignalLevel::Professional
-                    } else {
-                        NominalSignalLevel::Consumer
-                    };
-                });
+            parse_line_input_levels(&mut state.level, Self::CH_OFFSET, val)
         })?;
 
         read_quad(req, node, TRAVELER_828MK2_LINE_INPUT_BOOST_OFFSET as u32, timeout_ms).map(|val| {
@@ -695,15 +712,7 @@ pub trait Traveler828mk2LineInputOperation {
         state: &mut Traveler828mk2LineInputState,
         timeout_ms: u32,
     ) -> Result<(), Error> {
-        let val = level
-            .iter()
-            .enumerate()
-            .fold(0u32, |mut val, (i, l)| {
-                if NominalSignalLevel::Professional.eq(l) {
-                    val |= 1 << (i + Self::CH_OFFSET);
-                }
-                val
-            });
+        let val = build_line_input_levels(level, Self::CH_OFFSET);
 
         write_quad(
             req,
@@ -716,6 +725,33 @@ pub trait Traveler828mk2LineInputOperation {
         })
     }
 
+    /// The flag in registers for the channel of line input.
+    fn line_input_flag(ch: usize) -> u32 {
+        line_input_flag(ch, Self::CH_OFFSET)
+    }
+
+    /// Change nominal level of the channel. The level of the other channels is kept as the state.
+    fn write_line_input_channel_level(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        ch: usize,
+        level: NominalSignalLevel,
+        state: &mut Traveler828mk2LineInputState,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        if ch >= Self::LINE_INPUT_COUNT {
+            let msg = format!(
+                "Invalid channel of line input: {}, should be less than {}",
+                ch,
+                Self::LINE_INPUT_COUNT
+            );
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+        let mut levels = state.level.clone();
+        levels[ch] = level;
+        Self::write_line_input_level(req, node, &levels, state, timeout_ms)
+    }
+
     fn write_line_input_boost(
         req: &mut FwReq,
         node: &mut FwNode,
@@ -965,3 +1001,48 @@ pub trait Audioexpress4preInputOperation {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct F828mk2LineInput;
+
+    impl Traveler828mk2LineInputOperation for F828mk2LineInput {
+        const LINE_INPUT_COUNT: usize = 8;
+        const CH_OFFSET: usize = 0;
+    }
+
+    struct TravelerLineInput;
+
+    impl Traveler828mk2LineInputOperation for TravelerLineInput {
+        const LINE_INPUT_COUNT: usize = 4;
+        const CH_OFFSET: usize = 4;
+    }
+
+    #[test]
+    fn test_line_input_flag() {
+        (0..F828mk2LineInput::LINE_INPUT_COUNT).for_each(|ch| {
+            assert_eq!(F828mk2LineInput::line_input_flag(ch), 0x01 << ch);
+        });
+        (0..TravelerLineInput::LINE_INPUT_COUNT).for_each(|ch| {
+            assert_eq!(TravelerLineInput::line_input_flag(ch), 0x10 << ch);
+        });
+    }
+
+    #[test]
+    fn test_line_input_levels() {
+        let mut level = vec![NominalSignalLevel::Consumer; TravelerLineInput::LINE_INPUT_COUNT];
+        level[2] = NominalSignalLevel::Professional;
+        assert_eq!(build_line_input_levels(&level, TravelerLineInput::CH_OFFSET), 0x40);
+
+        // The change of channel 3 keeps channel 2.
+        level[3] = NominalSignalLevel::Professional;
+        assert_eq!(build_line_input_levels(&level, TravelerLineInput::CH_OFFSET), 0xc0);
+        assert_eq!(build_line_input_levels(&level, F828mk2LineInput::CH_OFFSET), 0x0c);
+
+        let mut parsed = vec![NominalSignalLevel::Consumer; TravelerLineInput::LINE_INPUT_COUNT];
+        parse_line_input_levels(&mut parsed, TravelerLineInput::CH_OFFSET, 0x0000000f | 0xc0);
+        assert_eq!(parsed, level);
+    }
+}
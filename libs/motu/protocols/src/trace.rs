@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Ring buffer to trace recent transactions.
+//!
+//! The module includes the ring buffer to record the last transactions at register level, so that
+//! the history is available in log when the unit stops responding. The recording is disabled by
+//! default and enabled at runtime.
+
+use glib::Error;
+
+use once_cell::sync::Lazy;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The maximum number of entries in the ring.
+pub const TRACE_RING_CAPACITY: usize = 256;
+
+/// The maximum number of bytes recorded per entry.
+pub const TRACE_DATA_SIZE: usize = 16;
+
+/// The direction of transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraceDirection {
+    Read,
+    Write,
+    /// The write block transaction for message to command DSP.
+    Message,
+}
+
+impl std::fmt::Display for TraceDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let label = match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Message => "message",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// The entry of transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The time since UNIX epoch.
+    pub timestamp: Duration,
+    pub direction: TraceDirection,
+    pub offset: u64,
+    /// The first bytes of frame, up to `TRACE_DATA_SIZE`.
+    pub data: Vec<u8>,
+    /// The message of error, or None for success.
+    pub error: Option<String>,
+}
+
+impl TraceEntry {
+    pub fn new(
+        timestamp: Duration,
+        direction: TraceDirection,
+        offset: u64,
+        frame: &[u8],
+        error: Option<String>,
+    ) -> Self {
+        let len = std::cmp::min(frame.len(), TRACE_DATA_SIZE);
+        Self {
+            timestamp,
+            direction,
+            offset,
+            data: frame[..len].to_vec(),
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let data: Vec<String> = self.data.iter().map(|b| format!("{:02x}", b)).collect();
+        write!(
+            f,
+            "{}.{:06} {} 0x{:012x} [{}] {}",
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.direction,
+            self.offset,
+            data.join(" "),
+            self.error.as_ref().map_or("ok", |e| e.as_str()),
+        )
+    }
+}
+
+/// The bounded ring of entries. The oldest entry is overwritten when the ring is full.
+#[derive(Debug)]
+pub struct TraceRing {
+    entries: Vec<TraceEntry>,
+    // The position to push next entry, equals to the oldest entry when the ring is full.
+    next: usize,
+    capacity: usize,
+}
+
+impl TraceRing {
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            next: 0,
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: TraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.next] = entry;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The entries from the oldest to the latest.
+    pub fn iter(&self) -> impl Iterator<Item = &TraceEntry> {
+        let pos = if self.entries.len() < self.capacity {
+            0
+        } else {
+            self.next
+        };
+        self.entries[pos..].iter().chain(self.entries[..pos].iter())
+    }
+
+    /// Format the entries from the oldest to the latest, one line per entry.
+    pub fn format(&self) -> String {
+        self.iter()
+            .map(|entry| entry.to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+static TRACE_ENABLED: AtomicBool = AtomicBool::new(false);
+static TRACE_FAILED: AtomicBool = AtomicBool::new(false);
+
+// The ring is allocated at first use and never released.
+static TRACE_RING: Lazy<Mutex<TraceRing>> =
+    Lazy::new(|| Mutex::new(TraceRing::new(TRACE_RING_CAPACITY)));
+
+/// Enable or disable the recording of transactions.
+pub fn set_trace_enabled(enable: bool) {
+    TRACE_ENABLED.store(enable, Ordering::Relaxed);
+}
+
+pub fn is_trace_enabled() -> bool {
+    TRACE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Format the recorded transactions from the oldest to the latest.
+pub fn format_trace() -> String {
+    TRACE_RING
+        .lock()
+        .map(|ring| ring.format())
+        .unwrap_or_default()
+}
+
+/// Take whether any failure of transaction is recorded since the last call.
+pub fn take_trace_failure() -> bool {
+    TRACE_FAILED.swap(false, Ordering::Relaxed)
+}
+
+// The failure is recorded just for the final result of operation, so that the failed attempt
+// recovered by retry is not reported.
+pub(crate) fn record_result<T>(res: &Result<T, Error>) {
+    if is_trace_enabled() && res.is_err() {
+        TRACE_FAILED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_transaction<T>(
+    direction: TraceDirection,
+    offset: u64,
+    frame: &[u8],
+    res: &Result<T, Error>,
+) {
+    if !is_trace_enabled() {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let error = res.as_ref().err().map(|e| e.to_string());
+    let entry = TraceEntry::new(timestamp, direction, offset, frame, error);
+    if let Ok(ring) = &mut TRACE_RING.lock() {
+        ring.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(secs: u64, offset: u64, error: Option<&str>) -> TraceEntry {
+        TraceEntry::new(
+            Duration::from_micros(secs * 1000000 + 42),
+            TraceDirection::Write,
+            offset,
+            &[0x01, 0x02, 0x03, 0x04],
+            error.map(|e| e.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_trace_entry_format() {
+        let frame: Vec<u8> = (0..20).collect();
+        let entry = TraceEntry::new(
+            Duration::from_micros(1000042),
+            TraceDirection::Message,
+            0xffff00010000,
+            &frame,
+            Some("Timeout".to_string()),
+        );
+        assert_eq!(entry.data.len(), TRACE_DATA_SIZE);
+        assert_eq!(
+            entry.to_string(),
+            "1.000042 message 0xffff00010000 \
+             [00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f] Timeout"
+        );
+    }
+
+    #[test]
+    fn test_trace_ring_wrap_around() {
+        let mut ring = TraceRing::new(3);
+        assert_eq!(ring.format(), "");
+
+        ring.push(entry(1, 0x10, None));
+        ring.push(entry(2, 0x20, None));
+        assert_eq!(ring.len(), 2);
+        assert_eq!(
+            ring.format(),
+            "1.000042 write 0x000000000010 [01 02 03 04] ok\n\
+             2.000042 write 0x000000000020 [01 02 03 04] ok"
+        );
+
+        // The oldest entries are overwritten.
+        ring.push(entry(3, 0x30, None));
+        ring.push(entry(4, 0x40, None));
+        ring.push(entry(5, 0x50, Some("Timeout")));
+        assert_eq!(ring.len(), 3);
+        assert_eq!(
+            ring.format(),
+            "3.000042 write 0x000000000030 [01 02 03 04] ok\n\
+             4.000042 write 0x000000000040 [01 02 03 04] ok\n\
+             5.000042 write 0x000000000050 [01 02 03 04] Timeout"
+        );
+
+        ring.clear();
+        assert_eq!(ring.len(), 0);
+        ring.push(entry(6, 0x60, None));
+        assert_eq!(ring.format(), "6.000042 write 0x000000000060 [01 02 03 04] ok");
+    }
+}
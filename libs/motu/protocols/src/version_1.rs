@@ -672,7 +672,15 @@ impl F896Protocol {
     pub const NOTIFY_FOOTSWITCH_MASK: u32 = 0x01000000;
 }
 
-impl WordClkOperation for F896Protocol {}
+impl WordClkOperation for F896Protocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATE_LABELS[idx])
+    }
+}
 
 impl V1ClkOperation for F896Protocol {
     const CLK_OFFSET: u32 = OFFSET_CLK;
@@ -245,7 +245,15 @@ impl AssignOperation for F828mk2Protocol {
     ];
 }
 
-impl WordClkOperation for F828mk2Protocol {}
+impl WordClkOperation for F828mk2Protocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATES[idx].0)
+    }
+}
 
 impl V2ClkOperation for F828mk2Protocol {
     const CLK_RATES: &'static [(ClkRate, u8)] = &[
@@ -266,6 +274,8 @@ impl V2ClkOperation for F828mk2Protocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for F828mk2Protocol {}
+
 impl V2OptIfaceOperation for F828mk2Protocol {
     const OPT_IFACE_MODES: &'static [(V2OptIfaceMode, u8)] = &[
         (V2OptIfaceMode::None, 0x00),
@@ -445,7 +455,15 @@ impl AssignOperation for TravelerProtocol {
     ];
 }
 
-impl WordClkOperation for TravelerProtocol {}
+impl WordClkOperation for TravelerProtocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATES[idx].0)
+    }
+}
 
 impl V2ClkOperation for TravelerProtocol {
     const CLK_RATES: &'static [(ClkRate, u8)] = &[
@@ -469,6 +487,8 @@ impl V2ClkOperation for TravelerProtocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for TravelerProtocol {}
+
 impl V2OptIfaceOperation for TravelerProtocol {
     const OPT_IFACE_MODES: &'static [(V2OptIfaceMode, u8)] = &[
         (V2OptIfaceMode::None, 0x00),
@@ -668,6 +688,8 @@ impl V2ClkOperation for UltraliteProtocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for UltraliteProtocol {}
+
 impl RegisterDspMixerOutputOperation for UltraliteProtocol {
     const OUTPUT_DESTINATIONS: &'static [TargetPort] = &[
         TargetPort::Disabled,
@@ -904,7 +926,15 @@ impl AssignOperation for F896hdProtocol {
     ];
 }
 
-impl WordClkOperation for F896hdProtocol {}
+impl WordClkOperation for F896hdProtocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATES[idx].0)
+    }
+}
 
 impl AesebuRateConvertOperation for F896hdProtocol {
     const AESEBU_RATE_CONVERT_MASK: u32 = 0x00000300;
@@ -997,3 +1027,37 @@ impl RegisterDspMixerMonauralSourceOperation for F896hdProtocol {
 }
 
 impl RegisterDspOutputOperation for F896hdProtocol {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fw_transaction::replay::ReplayTransaction;
+
+    #[test]
+    fn test_replay() {
+        let mut tx = ReplayTransaction::parse(include_str!("../replay/828mk2.map")).unwrap();
+        let addr = BASE_OFFSET + OFFSET_CLK as u64;
+
+        let rates: Vec<u8> = F828mk2Protocol::CLK_RATES.iter().map(|e| e.1).collect();
+        let srcs: Vec<u8> = F828mk2Protocol::CLK_SRCS.iter().map(|e| e.1).collect();
+
+        let idx = get_idx_from_val_by(OFFSET_CLK, CLK_RATE_MASK, CLK_RATE_SHIFT, CLK_RATE_LABEL,
+                                      &mut tx, &rates, 100).unwrap();
+        assert_eq!(idx, 1);
+        let idx = get_idx_from_val_by(OFFSET_CLK, CLK_SRC_MASK, CLK_SRC_SHIFT, CLK_SRC_LABEL,
+                                      &mut tx, &srcs, 100).unwrap();
+        assert_eq!(idx, 0);
+
+        // The sequence of element writes; rate of sampling clock, then source of sampling clock.
+        set_idx_to_val_by(OFFSET_CLK, CLK_RATE_MASK, CLK_RATE_SHIFT, CLK_RATE_LABEL, &mut tx,
+                          &rates, 3, 100).unwrap();
+        set_idx_to_val_by(OFFSET_CLK, CLK_SRC_MASK, CLK_SRC_SHIFT, CLK_SRC_LABEL, &mut tx,
+                          &srcs, 2, 100).unwrap();
+
+        let expected = vec![
+            (addr, vec![0x00, 0x00, 0x00, 0x18]),
+            (addr, vec![0x00, 0x00, 0x00, 0x1a]),
+        ];
+        assert_eq!(tx.log(), &expected[..]);
+    }
+}
@@ -350,6 +350,47 @@ pub trait V3OptIfaceOperation {
     }
 }
 
+fn opt_iface_target_of_port(port: &TargetPort) -> Option<V3OptIfaceTarget> {
+    match port {
+        TargetPort::OpticalAPair0
+        | TargetPort::OpticalAPair1
+        | TargetPort::OpticalAPair2
+        | TargetPort::OpticalAPair3
+        | TargetPort::OpticalA0
+        | TargetPort::OpticalA1
+        | TargetPort::OpticalA2
+        | TargetPort::OpticalA3
+        | TargetPort::OpticalA4
+        | TargetPort::OpticalA5
+        | TargetPort::OpticalA6
+        | TargetPort::OpticalA7 => Some(V3OptIfaceTarget::A),
+        TargetPort::OpticalBPair0
+        | TargetPort::OpticalBPair1
+        | TargetPort::OpticalBPair2
+        | TargetPort::OpticalBPair3
+        | TargetPort::OpticalB0
+        | TargetPort::OpticalB1
+        | TargetPort::OpticalB2
+        | TargetPort::OpticalB3
+        | TargetPort::OpticalB4
+        | TargetPort::OpticalB5
+        | TargetPort::OpticalB6
+        | TargetPort::OpticalB7 => Some(V3OptIfaceTarget::B),
+        _ => None,
+    }
+}
+
+/// Whether the port is available for the modes of optical interface A and B. The ports of
+/// optical interface carry the channels of ADAT only in ADAT mode. The other ports are always
+/// available.
+pub fn is_v3_opt_port_available(port: &TargetPort, modes: &[V3OptIfaceMode; 2]) -> bool {
+    match opt_iface_target_of_port(port) {
+        Some(V3OptIfaceTarget::A) => modes[0] == V3OptIfaceMode::Adat,
+        Some(V3OptIfaceTarget::B) => modes[1] == V3OptIfaceMode::Adat,
+        None => true,
+    }
+}
+
 /// The protocol implementation for Audio Express.
 #[derive(Default)]
 pub struct AudioExpressProtocol;
@@ -559,7 +600,15 @@ impl AssignOperation for F828mk3Protocol {
     const ASSIGN_PORTS: &'static [(TargetPort, u8)] = F828MK3_ASSIGN_PORTS;
 }
 
-impl WordClkOperation for F828mk3Protocol {}
+impl WordClkOperation for F828mk3Protocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATES[idx].0)
+    }
+}
 
 impl V3ClkOperation for F828mk3Protocol {
     const CLK_RATES: &'static [(ClkRate, u8)] = F828MK3_CLK_RATES;
@@ -567,6 +616,8 @@ impl V3ClkOperation for F828mk3Protocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for F828mk3Protocol {}
+
 impl V3PortAssignOperation for F828mk3Protocol {}
 
 impl V3OptIfaceOperation for F828mk3Protocol {}
@@ -593,6 +644,8 @@ impl CommandDspOutputOperation for F828mk3Protocol {
     const OUTPUT_PORTS: &'static [TargetPort] = F828MK3_OUTPUT_PORTS;
 }
 
+impl CommandDspPresetOperation for F828mk3Protocol {}
+
 impl F828mk3Protocol {
     /// Notification mask for main assignment, return assignment, and phone assignment. The change
     /// of phone assignment is also notified in command message.
@@ -610,7 +663,15 @@ impl AssignOperation for F828mk3HybridProtocol {
     const ASSIGN_PORTS: &'static [(TargetPort, u8)] = F828MK3_ASSIGN_PORTS;
 }
 
-impl WordClkOperation for F828mk3HybridProtocol {}
+impl WordClkOperation for F828mk3HybridProtocol {
+    fn get_word_out_clk_rate(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<ClkRate, Error> {
+        Self::get_clk_rate(req, node, timeout_ms).map(|idx| Self::CLK_RATES[idx].0)
+    }
+}
 
 impl V3ClkOperation for F828mk3HybridProtocol {
     const CLK_RATES: &'static [(ClkRate, u8)] = F828MK3_CLK_RATES;
@@ -618,6 +679,8 @@ impl V3ClkOperation for F828mk3HybridProtocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for F828mk3HybridProtocol {}
+
 impl V3PortAssignOperation for F828mk3HybridProtocol {}
 
 impl V3OptIfaceOperation for F828mk3HybridProtocol {}
@@ -645,6 +708,8 @@ impl CommandDspOutputOperation for F828mk3HybridProtocol {
     const OUTPUT_PORTS: &'static [TargetPort] = F828MK3_OUTPUT_PORTS;
 }
 
+impl CommandDspPresetOperation for F828mk3HybridProtocol {}
+
 impl F828mk3HybridProtocol {
     /// Notification mask for main assignment, return assignment, and phone assignment. The change
     /// of phone assignment is also notified in command message.
@@ -789,6 +854,8 @@ impl V3ClkOperation for UltraliteMk3Protocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for UltraliteMk3Protocol {}
+
 impl V3PortAssignOperation for UltraliteMk3Protocol {}
 
 impl CommandDspOperation for UltraliteMk3Protocol {}
@@ -814,6 +881,8 @@ impl CommandDspOutputOperation for UltraliteMk3Protocol {
     const OUTPUT_PORTS: &'static [TargetPort] = ULTRALITE_MK3_OUTPUT_PORTS;
 }
 
+impl CommandDspPresetOperation for UltraliteMk3Protocol {}
+
 impl UltraliteMk3Protocol {
     /// Notification mask for main assignment, return assignment, and phone assignment. The change
     /// of phone assignment is also notified in command message.
@@ -834,6 +903,8 @@ impl V3ClkOperation for UltraliteMk3HybridProtocol {
     const HAS_LCD: bool = true;
 }
 
+impl ClkDisplayOperation for UltraliteMk3HybridProtocol {}
+
 impl V3PortAssignOperation for UltraliteMk3HybridProtocol {}
 
 impl CommandDspOperation for UltraliteMk3HybridProtocol {}
@@ -858,8 +929,54 @@ impl CommandDspOutputOperation for UltraliteMk3HybridProtocol {
     const OUTPUT_PORTS: &'static [TargetPort] = ULTRALITE_MK3_OUTPUT_PORTS;
 }
 
+impl CommandDspPresetOperation for UltraliteMk3HybridProtocol {}
+
 impl UltraliteMk3HybridProtocol {
     /// Notification mask for main assignment, return assignment, and phone assignment. The change
     /// of phone assignment is also notified in command message.
     pub const NOTIFY_PORT_CHANGE: u32 = 0x40000000;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_opt_port_availability() {
+        let modes = [V3OptIfaceMode::Adat, V3OptIfaceMode::Spdif];
+        assert!(is_v3_opt_port_available(&TargetPort::MainPair0, &modes));
+        assert!(is_v3_opt_port_available(&TargetPort::OpticalA7, &modes));
+        assert!(!is_v3_opt_port_available(&TargetPort::OpticalB0, &modes));
+        assert!(!is_v3_opt_port_available(&TargetPort::OpticalBPair3, &modes));
+
+        let modes = [V3OptIfaceMode::Disabled, V3OptIfaceMode::Adat];
+        assert!(!is_v3_opt_port_available(&TargetPort::OpticalAPair0, &modes));
+        assert!(is_v3_opt_port_available(&TargetPort::OpticalB7, &modes));
+    }
+
+    fn assert_mixer_state_size<T: CommandDspMixerOperation>() {
+        let state = T::create_mixer_state();
+        state.source.iter().for_each(|src| {
+            assert_eq!(src.mute.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.solo.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.gain.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.pan.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.stereo_mode.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.stereo_balance.len(), T::SOURCE_PORTS.len());
+            assert_eq!(src.stereo_width.len(), T::SOURCE_PORTS.len());
+        });
+    }
+
+    #[test]
+    fn test_mixer_state_size() {
+        assert_mixer_state_size::<F828mk3Protocol>();
+        assert_mixer_state_size::<F828mk3HybridProtocol>();
+        assert_mixer_state_size::<UltraliteMk3Protocol>();
+        assert_mixer_state_size::<UltraliteMk3HybridProtocol>();
+
+        // The optical interface B is available as source of mixer.
+        assert!(F828mk3Protocol::SOURCE_PORTS.contains(&TargetPort::OpticalB7));
+        assert!(<F828mk3Protocol as CommandDspMixerOperation>::OUTPUT_PORTS
+            .contains(&TargetPort::OpticalBPair3));
+    }
+}
@@ -9,9 +9,10 @@ use alsactl::{ElemId, ElemValue};
 
 use core::card_cntr::*;
 
-use motu_protocols::{register_dsp::*, version_3::*};
+use motu_protocols::{register_dsp::*, version_3::*, TargetPort};
 
 use super::{common_ctls::*, register_dsp_ctls::*, v3_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -179,6 +180,9 @@ impl CtlModel<SndMotu> for AudioExpress {
     }
 }
 
+// No element is measured.
+impl MeasureModel<SndMotu> for AudioExpress {}
+
 impl NotifyModel<SndMotu, u32> for AudioExpress {
     fn get_notified_elem_list(&mut self, _: &mut Vec<ElemId>) {}
 
@@ -196,3 +200,36 @@ impl NotifyModel<SndMotu, u32> for AudioExpress {
         Ok(false)
     }
 }
+
+impl ReconcileModel for AudioExpress {
+    fn reconcile(&mut self, unit: &mut SndMotu, elem_id: &ElemId) -> Result<bool, Error> {
+        if self.mixer_output_ctl.1.iter().any(|e| e.eq(elem_id)) {
+            self.mixer_output_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else if self.mixer_return_ctl.1.iter().any(|e| e.eq(elem_id)) {
+            self.mixer_return_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else if self.mixer_source_ctl.1.iter().any(|e| e.eq(elem_id)) {
+            self.mixer_source_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else if self.output_ctl.1.iter().any(|e| e.eq(elem_id)) {
+            self.output_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else if self.input_ctl.1.iter().any(|e| e.eq(elem_id)) {
+            self.input_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else if elem_id.get_name().as_str() == PHONE_ASSIGN_NAME {
+            self.phone_assign_ctl.cache(unit, &mut self.req, TIMEOUT_MS).map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl ClkDisplayTextModel for AudioExpress {}
+
+impl ChannelPortModel for AudioExpress {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            &<AudioExpressProtocol as RegisterDspMixerStereoSourceOperation>::MIXER_SOURCES,
+            <AudioExpressProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for AudioExpress {}
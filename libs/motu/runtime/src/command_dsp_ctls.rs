@@ -3,15 +3,18 @@
 
 use glib::{Error, FileError};
 
-use hinawa::FwReq;
+use hinawa::{FwReq, FwResp};
 use hinawa::{SndMotu, SndUnitExt};
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual};
 
-//use alsa_ctl_tlv_codec::items::DbInterval;
+use alsa_ctl_tlv_codec::items::DbInterval;
 
 use core::card_cntr::*;
 use core::elem_value_accessor::*;
+use core::log::*;
+use core::log_warn;
+use core::monitor::MonitorSectionState;
 
 use motu_protocols::command_dsp::*;
 
@@ -29,6 +32,7 @@ const REVERB_WIDTH_NAME: &str = "reverb-width";
 const REVERB_REFLECTION_MODE_NAME: &str = "reverb-reflection-mode";
 const REVERB_REFLECTION_SIZE_NAME: &str = "reverb-reflection-size";
 const REVERB_REFLECTION_LEVEL_NAME: &str = "reverb-reflection-level";
+const REVERB_SMOOTHING_NAME: &str = "reverb-smoothing";
 
 fn reverb_split_point_to_str(point: &SplitPoint) -> &'static str {
     match point {
@@ -49,9 +53,23 @@ fn reverb_room_shape_to_str(shape: &RoomShape) -> &'static str {
     }
 }
 
+/// The state of smoothing for parameters of reverb effect.
+#[derive(Default, Debug)]
+pub struct CommandDspReverbSmoothing {
+    pub enable: bool,
+    pub ramp: CommandDspReverbRamp,
+}
+
 pub trait CommandDspReverbCtlOperation<T: CommandDspReverbOperation> {
     fn state(&self) -> &CommandDspReverbState;
     fn state_mut(&mut self) -> &mut CommandDspReverbState;
+    fn smoothing(&self) -> &CommandDspReverbSmoothing;
+    fn smoothing_mut(&mut self) -> &mut CommandDspReverbSmoothing;
+
+    /// Whether to cache the state without sending commands, during batched write.
+    fn batched(&self) -> bool {
+        false
+    }
 
     const SPLIT_POINTS: [SplitPoint; 2] = [
         SplitPoint::Output,
@@ -68,6 +86,9 @@ pub trait CommandDspReverbCtlOperation<T: CommandDspReverbOperation> {
 
     const F32_CONVERT_SCALE: f32 = 1000000.0;
 
+    /// The number of steps for smoothing. Each step is issued at each tick of the timer for ramp.
+    const SMOOTHING_STEP_COUNT: usize = 4;
+
     fn load(
         &mut self,
         card_cntr: &mut CardCntr,
@@ -211,68 +232,86 @@ pub trait CommandDspReverbCtlOperation<T: CommandDspReverbOperation> {
         )
             .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, REVERB_SMOOTHING_NAME, 0);
+        let _ = card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
         Ok(notified_elem_id_list)
     }
 
+    /// The state to which parameters are going to be changed.
+    fn target_state(&self) -> CommandDspReverbState {
+        let ramp = &self.smoothing().ramp;
+        if ramp.is_active() {
+            *ramp.target()
+        } else {
+            *self.state()
+        }
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        let state = self.target_state();
         match elem_id.get_name().as_str() {
+            REVERB_SMOOTHING_NAME => {
+                elem_value.set_bool(&[self.smoothing().enable]);
+                Ok(true)
+            }
             REVERB_ENABLE => {
-                elem_value.set_bool(&[self.state().enable]);
+                elem_value.set_bool(&[state.enable]);
                 Ok(true)
             }
             REVERB_SPLIT_POINT_NAME => {
                 let pos = Self::SPLIT_POINTS
                     .iter()
-                    .position(|p| self.state().split_point.eq(p))
+                    .position(|p| state.split_point.eq(p))
                     .unwrap();
                 elem_value.set_enum(&[pos as u32]);
                 Ok(true)
             }
             REVERB_PRE_DELAY_NAME => {
-                elem_value.set_int(&[self.state().pre_delay as i32]);
+                elem_value.set_int(&[state.pre_delay as i32]);
                 Ok(true)
             }
             REVERB_SHELF_FILTER_FREQ_NAME => {
-                elem_value.set_int(&[self.state().shelf_filter_freq as i32]);
+                elem_value.set_int(&[state.shelf_filter_freq as i32]);
                 Ok(true)
             }
             REVERB_SHELF_FILTER_ATTR_NAME => {
-                elem_value.set_int(&[self.state().shelf_filter_attenuation]);
+                elem_value.set_int(&[state.shelf_filter_attenuation]);
                 Ok(true)
             }
             REVERB_DECAY_TIME_NAME => {
-                elem_value.set_int(&[self.state().decay_time as i32]);
+                elem_value.set_int(&[state.decay_time as i32]);
                 Ok(true)
             }
             REVERB_FREQ_TIME_NAME => {
-                let vals: Vec<i32> = self.state().freq_time.iter().map(|&val| val as i32).collect();
+                let vals: Vec<i32> = state.freq_time.iter().map(|&val| val as i32).collect();
                 elem_value.set_int(&vals);
                 Ok(true)
             }
             REVERB_FREQ_CROSSOVER_NAME => {
-                let vals: Vec<i32> = self.state().freq_crossover.iter().map(|&val| val as i32).collect();
+                let vals: Vec<i32> = state.freq_crossover.iter().map(|&val| val as i32).collect();
                 elem_value.set_int(&vals);
                 Ok(true)
             }
             REVERB_WIDTH_NAME => {
-                let val = (self.state().width * Self::F32_CONVERT_SCALE) as i32;
+                let val = (state.width * Self::F32_CONVERT_SCALE) as i32;
                 elem_value.set_int(&[val]);
                 Ok(true)
             }
             REVERB_REFLECTION_MODE_NAME => {
                 let pos = Self::ROOM_SHAPES
                     .iter()
-                    .position(|m| self.state().reflection_mode.eq(m))
+                    .position(|m| state.reflection_mode.eq(m))
                     .unwrap();
                 elem_value.set_enum(&[pos as u32]);
                 Ok(true)
             }
             REVERB_REFLECTION_SIZE_NAME => {
-                elem_value.set_int(&[self.state().reflection_size as i32]);
+                elem_value.set_int(&[state.reflection_size as i32]);
                 Ok(true)
             }
             REVERB_REFLECTION_LEVEL_NAME => {
-                let val = (self.state().reflection_level * Self::F32_CONVERT_SCALE) as i32;
+                let val = (state.reflection_level * Self::F32_CONVERT_SCALE) as i32;
                 elem_value.set_int(&[val]);
                 Ok(true)
             }
@@ -290,6 +329,29 @@ pub trait CommandDspReverbCtlOperation<T: CommandDspReverbOperation> {
         timeout_ms: u32,
     ) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
+            REVERB_SMOOTHING_NAME => {
+                let mut vals = [false];
+                elem_value.get_bool(&mut vals);
+                self.smoothing_mut().enable = vals[0];
+                // Finish the ramp in progress at once.
+                if !vals[0] && self.smoothing().ramp.is_active() {
+                    let state = *self.smoothing().ramp.target();
+                    self.smoothing_mut().ramp.cancel();
+                    if self.batched() {
+                        *self.state_mut() = state;
+                    } else {
+                        T::write_reverb_state(
+                            req,
+                            &mut unit.get_node(),
+                            sequence_number,
+                            state,
+                            self.state_mut(),
+                            timeout_ms
+                        )?;
+                    }
+                }
+                Ok(true)
+            }
             REVERB_ENABLE => {
                 let mut vals = [false];
                 elem_value.get_bool(&mut vals);
@@ -418,17 +480,52 @@ pub trait CommandDspReverbCtlOperation<T: CommandDspReverbOperation> {
     ) -> Result<bool, Error>
         where F: Fn(&mut CommandDspReverbState) -> Result<(), Error>,
     {
-        let mut state = self.state().clone();
+        let mut state = self.target_state();
         func(&mut state)?;
-        T::write_reverb_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            self.smoothing_mut().ramp.cancel();
+            *self.state_mut() = state;
+            Ok(true)
+        } else if self.smoothing().enable {
+            // The ramp in progress is retargeted.
+            let current = *self.state();
+            self.smoothing_mut().ramp.start(&current, &state, Self::SMOOTHING_STEP_COUNT);
+            self.step_ramp(sequence_number, unit, req, timeout_ms)
+                .map(|_| true)
+        } else {
+            T::write_reverb_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
+    }
+
+    /// Change parameters by one step of ramp in progress.
+    fn step_ramp(
+        &mut self,
+        sequence_number: &mut u8,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        match self.smoothing_mut().ramp.next_state() {
+            Some(state) if state != *self.state() => {
+                T::write_reverb_state(
+                    req,
+                    &mut unit.get_node(),
+                    sequence_number,
+                    state,
+                    self.state_mut(),
+                    timeout_ms
+                )
+            }
+            _ => Ok(()),
+        }
     }
 
     fn parse_commands(&mut self, cmds: &[DspCmd]) {
@@ -440,22 +537,135 @@ const MAIN_VOLUME_NAME: &str = "main-volume";
 const TALKBACK_ENABLE_NAME: &str = "talkback-enable";
 const LISTENBACK_ENABLE_NAME: &str = "listenback-enable";
 const TALKBACK_VOLUME_NAME: &str = "talkback-volume";
+const TALKBACK_DIM_NAME: &str = "talkback-dim";
 const LISTENBACK_VOLUME_NAME: &str = "listenback-volume";
+const FOCUS_TARGET_DIRECTION_NAME: &str = "focus-target-direction";
+const FOCUS_TARGET_CHANNEL_NAME: &str = "focus-target-channel";
+
+const FOCUS_TARGET_DIRECTION_LABELS: &[&str] = &["output", "input", "unknown"];
+const FOCUS_TARGET_UNKNOWN_LABEL: &str = "unknown";
+
+// The number of channels is indexed by the direction of focus target. The target reserved for
+// future firmware and the channel out of range are expressed by the last labels.
+fn focus_target_to_pos(target: &FocusTarget, channel_counts: &[usize; 2]) -> (usize, usize) {
+    let (dir, ch) = match target {
+        FocusTarget::Output(ch) => (0, *ch),
+        FocusTarget::Input(ch) => (1, *ch),
+        FocusTarget::Reserved(_, ch) => (FOCUS_TARGET_DIRECTION_LABELS.len() - 1, *ch),
+    };
+    let max = channel_counts.iter().max().copied().unwrap_or_default();
+    let count = channel_counts.get(dir).copied().unwrap_or(max);
+    (dir, if ch < count { ch } else { max })
+}
+
+fn pos_to_focus_target(
+    dir: usize,
+    ch: usize,
+    channel_counts: &[usize; 2],
+) -> Result<FocusTarget, Error> {
+    let count = channel_counts.get(dir).copied().ok_or_else(|| {
+        let msg = format!("Invalid index for direction of focus target: {}", dir);
+        Error::new(FileError::Inval, &msg)
+    })?;
+    if ch >= count {
+        let msg = format!("Invalid index for channel of focus target: {}", ch);
+        Err(Error::new(FileError::Inval, &msg))
+    } else if dir == 0 {
+        Ok(FocusTarget::Output(ch))
+    } else {
+        Ok(FocusTarget::Input(ch))
+    }
+}
+
+/// The state of monitor section on main volume. The volume requested by user or notified by the
+/// unit is kept so that the attenuation applies on top of it.
+#[derive(Default, Debug)]
+pub struct CommandDspMonitorSection {
+    pub state: MonitorSectionState,
+    // The underlying main volume to be restored, and the attenuated volume.
+    saved: Option<(f32, f32)>,
+    // The main volume adjusted for the change in the unit, to be written out of notification.
+    pending: Option<f32>,
+}
+
+impl CommandDspMonitorSection {
+    fn attenuate(state: &MonitorSectionState, volume: f32) -> Option<(f32, f32)> {
+        if state.is_attenuated() {
+            Some((volume, (volume as f64 * state.scale()) as f32))
+        } else {
+            None
+        }
+    }
+
+    /// The underlying main volume.
+    fn main_volume(&self, monitor: &CommandDspMonitorState) -> f32 {
+        self.saved.map(|(volume, _)| volume).unwrap_or(monitor.main_volume)
+    }
+
+    /// Keep the main volume requested by user, then return the volume to be written.
+    fn request(&mut self, volume: f32) -> f32 {
+        if self.saved.is_some() {
+            self.saved = Self::attenuate(&self.state, volume);
+        }
+        self.saved.map(|(_, attenuated)| attenuated).unwrap_or(volume)
+    }
+
+    /// Keep the main volume notified by the unit, then adjust it in new state.
+    fn follow(&mut self, old: &CommandDspMonitorState, new: &mut CommandDspMonitorState) {
+        if let Some((_, attenuated)) = self.saved {
+            // The volume equals to the attenuated one is the echo of the value sent before.
+            if new.main_volume != old.main_volume && new.main_volume != attenuated {
+                new.main_volume = self.request(new.main_volume);
+            }
+        }
+    }
+}
 
 pub trait CommandDspMonitorCtlOperation<T: CommandDspMonitorOperation> {
     fn state(&self) -> &CommandDspMonitorState;
     fn state_mut(&mut self) -> &mut CommandDspMonitorState;
+    fn talkback_dim(&self) -> &CommandDspTalkbackDimState;
+    fn talkback_dim_mut(&mut self) -> &mut CommandDspTalkbackDimState;
+    fn monitor_section(&self) -> &CommandDspMonitorSection;
+    fn monitor_section_mut(&mut self) -> &mut CommandDspMonitorSection;
+
+    /// Whether to cache the state without sending commands, during batched write.
+    fn batched(&self) -> bool {
+        false
+    }
 
     const F32_CONVERT_SCALE: f32 = 1000000.0;
 
+    /// The number of output and input channels available for focus target.
+    const FOCUS_CHANNEL_COUNTS: [usize; 2];
+
+    const MAIN_VOLUME_TLV: DbInterval = DbInterval {
+        min: CommandDspMonitorState::MAIN_VOLUME_DB_MUTE,
+        max: CommandDspMonitorState::MAIN_VOLUME_DB_MAX,
+        linear: false,
+        mute_avail: true,
+    };
+
     fn load(
         &mut self,
         card_cntr: &mut CardCntr,
     ) -> Result<Vec<ElemId>, Error> {
         let mut notified_elem_id_list = Vec::new();
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, MAIN_VOLUME_NAME, 0);
+        card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            CommandDspMonitorState::MAIN_VOLUME_DB_MUTE,
+            CommandDspMonitorState::MAIN_VOLUME_DB_MAX,
+            1,
+            1,
+            Some(&Into::<Vec<u32>>::into(Self::MAIN_VOLUME_TLV)),
+            true,
+        )
+            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
+
         [
-            MAIN_VOLUME_NAME,
             TALKBACK_VOLUME_NAME,
             LISTENBACK_VOLUME_NAME,
         ]
@@ -486,13 +696,40 @@ pub trait CommandDspMonitorCtlOperation<T: CommandDspMonitorOperation> {
                     .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))
             })?;
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TALKBACK_DIM_NAME, 0);
+        card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            CommandDspTalkbackDimState::DIM_MIN as i32,
+            CommandDspTalkbackDimState::DIM_MAX as i32,
+            1,
+            1,
+            None,
+            true,
+        )
+            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, FOCUS_TARGET_DIRECTION_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, FOCUS_TARGET_DIRECTION_LABELS, None, true)
+            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
+
+        let count = Self::FOCUS_CHANNEL_COUNTS.iter().max().copied().unwrap_or_default();
+        let mut labels: Vec<String> = (0..count)
+            .map(|i| (i + 1).to_string())
+            .collect();
+        labels.push(FOCUS_TARGET_UNKNOWN_LABEL.to_string());
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, FOCUS_TARGET_CHANNEL_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
+
         Ok(notified_elem_id_list)
     }
 
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MAIN_VOLUME_NAME => {
-                let val = (self.state().main_volume * Self::F32_CONVERT_SCALE) as i32;
+                let volume = self.monitor_section().main_volume(self.state());
+                let val = CommandDspMonitorState::main_volume_to_db(volume);
                 elem_value.set_int(&[val]);
                 Ok(true)
             }
@@ -514,6 +751,22 @@ pub trait CommandDspMonitorCtlOperation<T: CommandDspMonitorOperation> {
                 elem_value.set_int(&[val]);
                 Ok(true)
             }
+            TALKBACK_DIM_NAME => {
+                elem_value.set_int(&[self.talkback_dim().dim as i32]);
+                Ok(true)
+            }
+            FOCUS_TARGET_DIRECTION_NAME => {
+                let counts = &Self::FOCUS_CHANNEL_COUNTS;
+                let (dir, _) = focus_target_to_pos(&self.state().focus, counts);
+                elem_value.set_enum(&[dir as u32]);
+                Ok(true)
+            }
+            FOCUS_TARGET_CHANNEL_NAME => {
+                let counts = &Self::FOCUS_CHANNEL_COUNTS;
+                let (_, ch) = focus_target_to_pos(&self.state().focus, counts);
+                elem_value.set_enum(&[ch as u32]);
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
@@ -531,86 +784,171 @@ pub trait CommandDspMonitorCtlOperation<T: CommandDspMonitorOperation> {
             MAIN_VOLUME_NAME => {
                 let mut vals = [0];
                 elem_value.get_int(&mut vals);
-                let val = (vals[0] as f32) / Self::F32_CONVERT_SCALE;
+                let val = CommandDspMonitorState::main_volume_from_db(vals[0]);
                 let mut state = self.state().clone();
-                state.main_volume = val;
-                T::write_monitor_state(
-                    req,
-                    &mut unit.get_node(),
-                    sequence_number,
-                    state,
-                    self.state_mut(),
-                    timeout_ms
-                )
-                    .map(|_| true)
+                state.main_volume = self.monitor_section_mut().request(val);
+                let old = self.state().clone();
+                self.talkback_dim_mut().follow(&old, &mut state);
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
             }
             TALKBACK_ENABLE_NAME => {
                 let mut vals = [false];
                 elem_value.get_bool(&mut vals);
                 let mut state = self.state().clone();
                 state.talkback_enable = vals[0];
-                T::write_monitor_state(
-                    req,
-                    &mut unit.get_node(),
-                    sequence_number,
-                    state,
-                    self.state_mut(),
-                    timeout_ms
-                )
-                    .map(|_| true)
+                let old = self.state().clone();
+                self.talkback_dim_mut().follow(&old, &mut state);
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
             }
             LISTENBACK_ENABLE_NAME => {
                 let mut vals = [false];
                 elem_value.get_bool(&mut vals);
                 let mut state = self.state().clone();
                 state.listenback_enable = vals[0];
-                T::write_monitor_state(
-                    req,
-                    &mut unit.get_node(),
-                    sequence_number,
-                    state,
-                    self.state_mut(),
-                    timeout_ms
-                )
-                    .map(|_| true)
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
             }
             TALKBACK_VOLUME_NAME => {
                 let mut vals = [0];
                 elem_value.get_int(&mut vals);
                 let mut state = self.state().clone();
                 state.talkback_volume = (vals[0] as f32) / Self::F32_CONVERT_SCALE;
-                T::write_monitor_state(
-                    req,
-                    &mut unit.get_node(),
-                    sequence_number,
-                    state,
-                    self.state_mut(),
-                    timeout_ms
-                )
-                    .map(|_| true)
-                
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
             }
             LISTENBACK_VOLUME_NAME => {
                 let mut vals = [0];
                 elem_value.get_int(&mut vals);
                 let mut state = self.state().clone();
                 state.listenback_volume = (vals[0] as f32) / Self::F32_CONVERT_SCALE;
-                T::write_monitor_state(
-                    req,
-                    &mut unit.get_node(),
-                    sequence_number,
-                    state,
-                    self.state_mut(),
-                    timeout_ms
-                )
-                    .map(|_| true)
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
+            }
+            TALKBACK_DIM_NAME => {
+                let mut vals = [0];
+                elem_value.get_int(&mut vals);
+                if vals[0] < CommandDspTalkbackDimState::DIM_MIN as i32 ||
+                   vals[0] > CommandDspTalkbackDimState::DIM_MAX as i32 {
+                    let msg = format!("Invalid value for dim of talkback: {}", vals[0]);
+                    Err(Error::new(FileError::Inval, &msg))
+                } else {
+                    self.talkback_dim_mut().dim = vals[0] as u32;
+                    Ok(true)
+                }
+            }
+            FOCUS_TARGET_DIRECTION_NAME => {
+                let mut vals = [0];
+                elem_value.get_enum(&mut vals);
+                let counts = &Self::FOCUS_CHANNEL_COUNTS;
+                let (_, ch) = focus_target_to_pos(&self.state().focus, counts);
+                let mut state = self.state().clone();
+                state.focus = pos_to_focus_target(vals[0] as usize, ch, counts)?;
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
+            }
+            FOCUS_TARGET_CHANNEL_NAME => {
+                let mut vals = [0];
+                elem_value.get_enum(&mut vals);
+                let counts = &Self::FOCUS_CHANNEL_COUNTS;
+                let (dir, _) = focus_target_to_pos(&self.state().focus, counts);
+                let mut state = self.state().clone();
+                state.focus = pos_to_focus_target(dir, vals[0] as usize, counts)?;
+                self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
             }
             _ => Ok(false),
         }
     }
 
+    fn write_state<F>(
+        &mut self,
+        sequence_number: &mut u8,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32,
+        func: F,
+    ) -> Result<bool, Error>
+        where F: Fn(&mut CommandDspMonitorState) -> Result<(), Error>,
+    {
+        let mut state = self.state().clone();
+        func(&mut state)?;
+        self.write_whole_state(sequence_number, unit, req, state, timeout_ms)
+    }
+
+    /// Cache the whole state during batched write, or send commands for the differences.
+    fn write_whole_state(
+        &mut self,
+        sequence_number: &mut u8,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        state: CommandDspMonitorState,
+        timeout_ms: u32,
+    ) -> Result<bool, Error> {
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_monitor_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
+    }
+
+    /// Write main volume with the attenuation of new state of monitor section.
+    fn write_monitor_section_state(
+        &mut self,
+        sequence_number: &mut u8,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        section: MonitorSectionState,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let volume = self.monitor_section().main_volume(self.state());
+        let saved = CommandDspMonitorSection::attenuate(&section, volume);
+        let main_volume = saved.map(|(_, attenuated)| attenuated).unwrap_or(volume);
+        self.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            state.main_volume = main_volume;
+            Ok(())
+        })?;
+        let monitor = self.monitor_section_mut();
+        monitor.state = section;
+        monitor.saved = saved;
+        Ok(())
+    }
+
+    /// Parse commands from the unit. The main volume is adjusted later when talkback is switched
+    /// in the unit, or when main volume is changed in the unit during dim or mute of monitor
+    /// section.
     fn parse_commands(&mut self, cmds: &[DspCmd]) {
+        let old = self.state().clone();
         T::parse_monitor_commands(self.state_mut(), cmds);
+        let mut state = self.state().clone();
+        self.talkback_dim_mut().follow(&old, &mut state);
+        self.monitor_section_mut().follow(&old, &mut state);
+        if state.main_volume != self.state().main_volume {
+            self.monitor_section_mut().pending = Some(state.main_volume);
+        }
+    }
+
+    /// Write main volume adjusted at parsing commands, then return the commands sent to the unit.
+    fn write_pending_main_volume(
+        &mut self,
+        sequence_number: &mut u8,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32,
+    ) -> Result<Vec<DspCmd>, Error> {
+        match self.monitor_section_mut().pending.take() {
+            Some(volume) => {
+                self.write_state(sequence_number, unit, req, timeout_ms, |state| {
+                    state.main_volume = volume;
+                    Ok(())
+                })?;
+                Ok(vec![DspCmd::Monitor(MonitorCmd::Volume(volume))])
+            }
+            None => Ok(Vec::new()),
+        }
     }
 }
 
@@ -628,23 +966,70 @@ const MIXER_OUTPUT_VOLUME_NAME: &str = "mixer-output-volume";
 const MIXER_REVERB_SEND_NAME: &str = "mixer-reverb-send";
 const MIXER_REVERB_RETURN_NAME: &str = "mixer-reverb-return";
 
-const MIXER_SOURCE_MUTE_NAME: &str = "mixer-soruce-mute";
-const MIXER_SOURCE_SOLO_NAME: &str = "mixer-source-solo";
+pub const MIXER_SOURCE_MUTE_NAME: &str = "mixer-soruce-mute";
+pub const MIXER_SOURCE_SOLO_NAME: &str = "mixer-source-solo";
 const MIXER_SOURCE_GAIN_NAME: &str = "mixer-source-gain";
 const MIXER_SOURCE_PAN_NAME: &str = "mixer-source-pan";
 const MIXER_SOURCE_STEREO_PAIR_MODE_NAME: &str = "mixer-source-stereo-mode";
 const MIXER_SOURCE_STEREO_BALANCE_NAME: &str = "mixer-source-stereo-balance";
 const MIXER_SOURCE_STEREO_WIDTH_NAME: &str = "mixer-source-stereo-width";
 
+// The mode of solo is emulated by the runtime.
+const SOLO_MODE_NAME: &str = "solo-mode";
+
+fn solo_mode_to_str(mode: &SoloMode) -> &'static str {
+    match mode {
+        SoloMode::InPlace => "solo-in-place",
+        SoloMode::AfterFaderListen => "after-fader-listen",
+    }
+}
+
 pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
     fn state(&self) -> &CommandDspMixerState;
     fn state_mut(&mut self) -> &mut CommandDspMixerState;
+    fn solo(&self) -> &CommandDspSoloState;
+    fn solo_mut(&mut self) -> &mut CommandDspSoloState;
+
+    /// Whether to cache the state without sending commands, during batched write.
+    fn batched(&self) -> bool {
+        false
+    }
+
+    /// Whether the port is available as output destination of mixer in current configuration.
+    fn is_output_available(&self, _port: &TargetPort) -> bool {
+        true
+    }
+
+    /// Whether the port is available as source of mixer in current configuration.
+    fn is_source_available(&self, _port: &TargetPort) -> bool {
+        true
+    }
+
+    // The current parameters of unavailable source are kept as is.
+    fn check_source_available<V: PartialEq>(&self, curr: &[V], vals: &[V]) -> Result<(), Error> {
+        T::SOURCE_PORTS
+            .iter()
+            .zip(curr.iter().zip(vals.iter()))
+            .try_for_each(|(port, (c, v))| {
+                if c != v && !self.is_source_available(port) {
+                    let msg = format!(
+                        "Unavailable port for source of mixer: {}",
+                        target_port_to_str(port)
+                    );
+                    Err(Error::new(FileError::Inval, &msg))
+                } else {
+                    Ok(())
+                }
+            })
+    }
 
     const SOURCE_STEREO_PAIR_MODES: [SourceStereoPairMode; 2] = [
         SourceStereoPairMode::Width,
         SourceStereoPairMode::LrBalance,
     ];
 
+    const SOLO_MODES: [SoloMode; 2] = [SoloMode::InPlace, SoloMode::AfterFaderListen];
+
     const F32_CONVERT_SCALE: f32 = 1000000.0;
 
     fn load(
@@ -782,6 +1167,13 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
         )
             .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
 
+        let labels: Vec<&str> = Self::SOLO_MODES
+            .iter()
+            .map(|m| solo_mode_to_str(m))
+            .collect();
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, SOLO_MODE_NAME, 0);
+        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
+
         Ok(notified_elem_id_list)
     }
 
@@ -860,6 +1252,14 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
                 let mixer = elem_id.get_index() as usize;
                 Self::read_f32_values(elem_value, &self.state().source[mixer].stereo_width)
             }
+            SOLO_MODE_NAME => {
+                let pos = Self::SOLO_MODES
+                    .iter()
+                    .position(|m| self.solo().mode.eq(m))
+                    .unwrap();
+                elem_value.set_enum(&[pos as u32]);
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
@@ -886,15 +1286,25 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
                 let mut dsts = Vec::new();
                 vals
                     .iter()
-                    .try_for_each(|&val| {
-                        T::OUTPUT_PORTS
+                    .enumerate()
+                    .try_for_each(|(i, &val)| {
+                        let &p = T::OUTPUT_PORTS
                             .iter()
                             .nth(val as usize)
                             .ok_or_else(|| {
                                 let msg = format!("Invalid index of output destinations: {}", val);
                                 Error::new(FileError::Inval, &msg)
-                            })
-                            .map(|&p| dsts.push(p))
+                            })?;
+                        // The current destination is kept even if unavailable.
+                        if p != self.state().output_assign[i] && !self.is_output_available(&p) {
+                            let msg = format!(
+                                "Unavailable port for output destination: {}",
+                                target_port_to_str(&p)
+                            );
+                            Err(Error::new(FileError::Inval, &msg))?;
+                        }
+                        dsts.push(p);
+                        Ok(())
                     })?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.output_assign.copy_from_slice(&dsts);
@@ -934,6 +1344,7 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
                 let mut vals = vec![false; T::SOURCE_PORTS.len()];
                 elem_value.get_bool(&mut vals);
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].mute, &vals)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].mute.copy_from_slice(&vals);
                     Ok(())
@@ -943,14 +1354,21 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
                 let mut vals = vec![false; T::SOURCE_PORTS.len()];
                 elem_value.get_bool(&mut vals);
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].solo, &vals)?;
+                // The latest state parsed from the unit is the base to restore mutes.
+                let old = self.state().clone();
+                let mut new = old.clone();
+                new.source[mixer].solo.copy_from_slice(&vals);
+                self.solo_mut().follow_mixer(&old, &mut new);
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
-                    state.source[mixer].solo.copy_from_slice(&vals);
+                    *state = new.clone();
                     Ok(())
                 })
             }
             MIXER_SOURCE_PAN_NAME => {
                 let vals = Self::f32_array_from_i32_values(elem_value, T::SOURCE_PORTS.len());
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].pan, &vals)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].pan.copy_from_slice(&vals);
                     Ok(())
@@ -959,6 +1377,7 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
             MIXER_SOURCE_GAIN_NAME => {
                 let vals = Self::f32_array_from_i32_values(elem_value, T::SOURCE_PORTS.len());
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].gain, &vals)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].gain.copy_from_slice(&vals);
                     Ok(())
@@ -981,6 +1400,7 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
                             .map(|&mode| stereo_modes.push(mode))
                     })?;
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].stereo_mode, &stereo_modes)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].stereo_mode.copy_from_slice(&stereo_modes);
                     Ok(())
@@ -989,6 +1409,7 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
             MIXER_SOURCE_STEREO_BALANCE_NAME => {
                 let vals = Self::f32_array_from_i32_values(elem_value, T::SOURCE_PORTS.len());
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].stereo_balance, &vals)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].stereo_balance.copy_from_slice(&vals);
                     Ok(())
@@ -997,17 +1418,48 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
             MIXER_SOURCE_STEREO_WIDTH_NAME=> {
                 let vals = Self::f32_array_from_i32_values(elem_value, T::SOURCE_PORTS.len());
                 let mixer = elem_id.get_index() as usize;
+                self.check_source_available(&self.state().source[mixer].stereo_width, &vals)?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.source[mixer].stereo_width.copy_from_slice(&vals);
                     Ok(())
                 })
             }
+            SOLO_MODE_NAME => {
+                let mut vals = [0];
+                elem_value.get_enum(&mut vals);
+                let mode = Self::SOLO_MODES
+                    .iter()
+                    .nth(vals[0] as usize)
+                    .ok_or_else(|| {
+                        let msg = format!("Invalid index of solo modes: {}", vals[0]);
+                        Error::new(FileError::Inval, &msg)
+                    })?;
+                if *mode != self.solo().mode && CommandDspSoloState::is_active(self.state()) {
+                    let msg = "Solo mode can not be changed while any source is soloed";
+                    Err(Error::new(FileError::Inval, msg))
+                } else {
+                    self.solo_mut().mode = *mode;
+                    Ok(true)
+                }
+            }
             _ => Ok(false),
         }
     }
 
-    fn parse_commands(&mut self, cmds: &[DspCmd]) {
-        T::parse_mixer_commands(self.state_mut(), cmds);
+    fn parse_commands(&mut self, cmds: &[DspCmd]) -> usize {
+        cmds.iter().for_each(|cmd| {
+            if let DspCmd::Mixer(MixerCmd::OutputAssign(mixer, val)) = cmd {
+                if *val >= T::OUTPUT_PORTS.len() {
+                    log_warn!(
+                        LOG_RUNTIME,
+                        "Unknown output destination of mixer {}: {}, fall back to the first port",
+                        mixer,
+                        val
+                    );
+                }
+            }
+        });
+        T::parse_mixer_commands(self.state_mut(), cmds)
     }
 
     fn write_state<F>(
@@ -1022,15 +1474,20 @@ pub trait CommandDspMixerCtlOperation<T: CommandDspMixerOperation> {
     {
         let mut state = self.state().clone();
         func(&mut state)?;
-        T::write_mixer_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_mixer_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -2344,6 +2801,11 @@ pub trait CommandDspInputCtlOperation<T: CommandDspInputOperation> {
     fn state(&self) -> &CommandDspInputState;
     fn state_mut(&mut self) -> &mut CommandDspInputState;
 
+    /// Whether to cache the state without sending commands, during batched write.
+    fn batched(&self) -> bool {
+        false
+    }
+
     const STEREO_PAIR_MODES: [InputStereoPairMode; 2] = [
         InputStereoPairMode::LeftRight,
         InputStereoPairMode::MonauralStereo,
@@ -2585,15 +3047,25 @@ pub trait CommandDspInputCtlOperation<T: CommandDspInputOperation> {
                 let mut modes = Vec::new();
                 vals
                     .iter()
-                    .try_for_each(|&val| {
-                        Self::STEREO_PAIR_MODES
+                    .enumerate()
+                    .try_for_each(|(ch, &val)| {
+                        let &mode = Self::STEREO_PAIR_MODES
                             .iter()
                             .nth(val as usize)
                             .ok_or_else(|| {
                                 let msg = format!("Invalid index of stereo pair modes: {}", val);
                                 Error::new(FileError::Inval, &msg)
-                            })
-                            .map(|&m| modes.push(m))
+                            })?;
+                        // The mode is meaningful just for the paired channels.
+                        if mode != self.state().stereo_mode[ch] && !self.state().pair[ch] {
+                            let msg = format!(
+                                "Stereo pair mode is not available for unpaired input {}",
+                                target_port_to_str(&T::INPUT_PORTS[ch])
+                            );
+                            Err(Error::new(FileError::Inval, &msg))?;
+                        }
+                        modes.push(mode);
+                        Ok(())
                     })?;
                 self.write_state(sequence_number, unit, req, timeout_ms, |state| {
                     state.stereo_mode.copy_from_slice(&modes);
@@ -2665,8 +3137,8 @@ pub trait CommandDspInputCtlOperation<T: CommandDspInputOperation> {
         }
     }
 
-    fn parse_commands(&mut self, cmds: &[DspCmd]) {
-        T::parse_input_commands(self.state_mut(), cmds);
+    fn parse_commands(&mut self, cmds: &[DspCmd]) -> usize {
+        T::parse_input_commands(self.state_mut(), cmds)
     }
 
     fn write_state<F>(
@@ -2681,15 +3153,20 @@ pub trait CommandDspInputCtlOperation<T: CommandDspInputOperation> {
     {
         let mut state = self.state().clone();
         func(&mut state)?;
-        T::write_input_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_input_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -2756,15 +3233,20 @@ impl<O, T> CommandDspEqualizerCtlOperation<T, CommandDspInputState> for O
     {
         let mut state = self.state().clone();
         func(&mut state.equalizer)?;
-        T::write_input_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_input_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -2806,15 +3288,20 @@ impl<O, T> CommandDspDynamicsCtlOperation<T, CommandDspInputState> for O
     {
         let mut state = self.state().clone();
         func(&mut state.dynamics)?;
-        T::write_input_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_input_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -2828,6 +3315,11 @@ pub trait CommandDspOutputCtlOperation<T: CommandDspOutputOperation> {
     fn state(&self) -> &CommandDspOutputState;
     fn state_mut(&mut self) -> &mut CommandDspOutputState;
 
+    /// Whether to cache the state without sending commands, during batched write.
+    fn batched(&self) -> bool {
+        false
+    }
+
     const F32_CONVERT_SCALE: f32 = 1000000.0;
 
     fn load(
@@ -3018,8 +3510,8 @@ pub trait CommandDspOutputCtlOperation<T: CommandDspOutputOperation> {
         }
     }
 
-    fn parse_commands(&mut self, cmds: &[DspCmd]) {
-        T::parse_output_commands(self.state_mut(), cmds);
+    fn parse_commands(&mut self, cmds: &[DspCmd]) -> usize {
+        T::parse_output_commands(self.state_mut(), cmds)
     }
 
     fn write_state<F>(
@@ -3034,15 +3526,20 @@ pub trait CommandDspOutputCtlOperation<T: CommandDspOutputOperation> {
     {
         let mut state = self.state().clone();
         func(&mut state)?;
-        T::write_output_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_output_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -3109,15 +3606,20 @@ impl<O, T> CommandDspEqualizerCtlOperation<T, CommandDspOutputState> for O
     {
         let mut state = self.state().clone();
         func(&mut state.equalizer)?;
-        T::write_output_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_output_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -3159,15 +3661,20 @@ impl<O, T> CommandDspDynamicsCtlOperation<T, CommandDspOutputState> for O
     {
         let mut state = self.state().clone();
         func(&mut state.dynamics)?;
-        T::write_output_state(
-            req,
-            &mut unit.get_node(),
-            sequence_number,
-            state,
-            self.state_mut(),
-            timeout_ms
-        )
-            .map(|_| true)
+        if self.batched() {
+            *self.state_mut() = state;
+            Ok(true)
+        } else {
+            T::write_output_state(
+                req,
+                &mut unit.get_node(),
+                sequence_number,
+                state,
+                self.state_mut(),
+                timeout_ms
+            )
+                .map(|_| true)
+        }
     }
 }
 
@@ -3228,3 +3735,303 @@ pub trait CommandDspResourcebCtlOperation {
             });
     }
 }
+
+const DROPPED_COMMAND_COUNT_NAME: &str = "dropped-command-count";
+
+pub trait CommandDspDiagnosticsCtlOperation {
+    fn state(&self) -> &u32;
+    fn state_mut(&mut self) -> &mut u32;
+
+    fn load(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, DROPPED_COMMAND_COUNT_NAME, 0);
+        card_cntr.add_int_elems(&elem_id, 1, 0, i32::MAX, 1, 1, None, false)
+    }
+
+    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            DROPPED_COMMAND_COUNT_NAME => {
+                elem_value.set_int(&[*self.state() as i32]);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // The count saturates at the maximum of element value.
+    fn count_dropped_commands(&mut self, count: usize) {
+        let state = self.state_mut();
+        *state = std::cmp::min(*state as usize + count, i32::MAX as usize) as u32;
+    }
+}
+
+/// The trait for the whole state of command DSP in the model, operated at once for preset,
+/// batched write, and panic.
+pub trait CommandDspPresetCtlOperation<T: CommandDspPresetOperation> {
+    type Reverb: CommandDspReverbCtlOperation<T>;
+    type Monitor: CommandDspMonitorCtlOperation<T>;
+    type Mixer: CommandDspMixerCtlOperation<T>;
+    type Input: CommandDspInputCtlOperation<T>;
+    type Output: CommandDspOutputCtlOperation<T>;
+
+    fn preset_ctls(
+        &self,
+    ) -> (&Self::Reverb, &Self::Monitor, &Self::Mixer, &Self::Input, &Self::Output);
+
+    /// The controls with the sequence number and the request for transaction.
+    fn preset_ctls_mut(
+        &mut self,
+    ) -> (
+        &mut u8,
+        &mut FwReq,
+        &mut Self::Reverb,
+        &mut Self::Monitor,
+        &mut Self::Mixer,
+        &mut Self::Input,
+        &mut Self::Output,
+    );
+
+    fn preset(&self) -> CommandDspPreset {
+        let (reverb, monitor, mixer, input, output) = self.preset_ctls();
+        CommandDspPreset {
+            reverb: *reverb.state(),
+            monitor: *monitor.state(),
+            mixer: mixer.state().clone(),
+            input: input.state().clone(),
+            output: output.state().clone(),
+        }
+    }
+
+    /// The preset to be saved, with the underlying main volume instead of the one attenuated by
+    /// monitor section.
+    fn saved_preset(&self) -> CommandDspPreset {
+        let mut preset = self.preset();
+        let (_, monitor, _, _, _) = self.preset_ctls();
+        preset.monitor.main_volume = monitor.monitor_section().main_volume(monitor.state());
+        preset
+    }
+
+    /// Set the state without any transaction.
+    fn set_preset(&mut self, preset: &CommandDspPreset) {
+        let (_, _, reverb, monitor, mixer, input, output) = self.preset_ctls_mut();
+        *reverb.state_mut() = preset.reverb;
+        *monitor.state_mut() = preset.monitor;
+        *mixer.state_mut() = preset.mixer.clone();
+        *input.state_mut() = preset.input.clone();
+        *output.state_mut() = preset.output.clone();
+    }
+
+    /// Send the differences between the state and the preset.
+    fn write_preset(
+        &mut self,
+        unit: &mut SndMotu,
+        preset: &CommandDspPreset,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let (sequence_number, req, reverb, monitor, mixer, input, output) = self.preset_ctls_mut();
+        reverb.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            *state = preset.reverb;
+            Ok(())
+        })?;
+        monitor.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            *state = preset.monitor;
+            Ok(())
+        })?;
+        mixer.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            *state = preset.mixer.clone();
+            Ok(())
+        })?;
+        input.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            *state = preset.input.clone();
+            Ok(())
+        })?;
+        output.write_state(sequence_number, unit, req, timeout_ms, |state| {
+            *state = preset.output.clone();
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Parse the preset and send the differences, then return the number of ignored commands.
+    /// The main volume in the preset is the new underlying volume for monitor section.
+    fn parse_and_write_preset(
+        &mut self,
+        unit: &mut SndMotu,
+        raw: &[u8],
+        timeout_ms: u32,
+    ) -> Result<usize, Error> {
+        let mut preset = self.saved_preset();
+        let ignored = T::parse_preset(&mut preset, raw)?;
+        let (_, _, _, monitor, _, _, _) = self.preset_ctls_mut();
+        preset.monitor.main_volume =
+            monitor.monitor_section_mut().request(preset.monitor.main_volume);
+        self.write_preset(unit, &preset, timeout_ms)?;
+        Ok(ignored)
+    }
+
+    /// The state of command DSP in the unit during batched write.
+    fn batch_mut(&mut self) -> &mut Option<CommandDspPreset>;
+
+    /// Switch the controls between batched and immediate write.
+    fn set_batched(&mut self, batched: bool);
+
+    /// Begin batched write with the current state as the one in the unit.
+    fn begin_batched_write(&mut self) {
+        if self.batch_mut().is_none() {
+            let preset = self.preset();
+            *self.batch_mut() = Some(preset);
+            self.set_batched(true);
+        }
+    }
+
+    /// Apply the notified commands to the state in the unit during batched write, so that the
+    /// change in the unit is not overwritten by the pending write.
+    fn update_batch(&mut self, cmds: &[DspCmd]) {
+        if let Some(sent) = self.batch_mut() {
+            T::update_preset(sent, cmds);
+        }
+    }
+
+    /// Send the differences from the state in the unit at once.
+    fn flush_batched_write(&mut self, unit: &mut SndMotu, timeout_ms: u32) -> Result<(), Error> {
+        match self.batch_mut().take() {
+            Some(sent) => {
+                self.set_batched(false);
+                let preset = self.preset();
+                self.set_preset(&sent);
+                self.write_preset(unit, &preset, timeout_ms)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Clear solo of all sources, talkback, listenback, and mute of main output at once, then
+    /// return the commands sent to the unit.
+    fn write_panic(&mut self, unit: &mut SndMotu, timeout_ms: u32) -> Result<Vec<DspCmd>, Error> {
+        let (sequence_number, req, _, monitor, mixer, _, _) = self.preset_ctls_mut();
+        let mut solo = mixer.solo().clone();
+        let cmds = solo.build_panic_commands(mixer.state(), monitor.state());
+        if !cmds.is_empty() {
+            T::send_commands(req, &mut unit.get_node(), sequence_number, &cmds, timeout_ms)?;
+        }
+        *mixer.solo_mut() = solo;
+        Ok(cmds)
+    }
+
+    /// Write main volume adjusted at parsing notified commands, then return the commands sent to
+    /// the unit.
+    fn flush_pending_main_volume(
+        &mut self,
+        unit: &mut SndMotu,
+        timeout_ms: u32,
+    ) -> Result<Vec<DspCmd>, Error> {
+        let (sequence_number, req, _, monitor, _, _, _) = self.preset_ctls_mut();
+        monitor.write_pending_main_volume(sequence_number, unit, req, timeout_ms)
+    }
+
+    fn reverb_ramp_active(&self) -> bool {
+        let (reverb, _, _, _, _) = self.preset_ctls();
+        reverb.smoothing().ramp.is_active()
+    }
+
+    fn write_reverb_ramp_step(&mut self, unit: &mut SndMotu, timeout_ms: u32) -> Result<(), Error> {
+        let (sequence_number, req, reverb, _, _, _, _) = self.preset_ctls_mut();
+        reverb.step_ramp(sequence_number, unit, req, timeout_ms)
+    }
+}
+
+/// The trait for messaging from the unit.
+pub trait CommandDspMessageCtlOperation<T: CommandDspOperation> {
+    /// The sequence number, the request, and the response for messaging.
+    fn message_ctls_mut(&mut self) -> (&mut u8, &mut FwReq, &mut FwResp);
+
+    /// Register the destination address of message and begin messaging again when the unit lost
+    /// the address. Return true when recovered.
+    fn recover_messaging(&mut self, unit: &mut SndMotu, timeout_ms: u32) -> Result<bool, Error> {
+        let (sequence_number, req, resp) = self.message_ctls_mut();
+        let mut node = unit.get_node();
+        let recovered = T::ensure_message_destination_address(resp, req, &mut node, timeout_ms)?;
+        if recovered {
+            T::begin_messaging(req, &mut node, sequence_number, timeout_ms)?;
+        }
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_focus_target_pos() {
+        let counts = [4, 2];
+
+        assert_eq!(focus_target_to_pos(&FocusTarget::Output(3), &counts), (0, 3));
+        assert_eq!(focus_target_to_pos(&FocusTarget::Input(1), &counts), (1, 1));
+        // The channel out of range for the direction is expressed by the last label.
+        assert_eq!(focus_target_to_pos(&FocusTarget::Input(2), &counts), (1, 4));
+        assert_eq!(focus_target_to_pos(&FocusTarget::Output(4), &counts), (0, 4));
+        assert_eq!(focus_target_to_pos(&FocusTarget::Reserved(2, 1), &counts), (2, 1));
+
+        assert_eq!(pos_to_focus_target(0, 3, &counts).unwrap(), FocusTarget::Output(3));
+        assert_eq!(pos_to_focus_target(1, 1, &counts).unwrap(), FocusTarget::Input(1));
+        assert!(pos_to_focus_target(1, 2, &counts).is_err());
+        assert!(pos_to_focus_target(0, 4, &counts).is_err());
+        assert!(pos_to_focus_target(2, 0, &counts).is_err());
+    }
+
+    #[test]
+    fn test_monitor_section_request() {
+        let mut section = CommandDspMonitorSection::default();
+
+        // The requested volume as it is without attenuation.
+        assert_eq!(section.request(0.5), 0.5);
+        assert_eq!(section.saved, None);
+
+        section.state.dim = true;
+        section.state.dim_level = -20;
+        section.saved = CommandDspMonitorSection::attenuate(&section.state, 0.5);
+        let (volume, attenuated) = section.saved.unwrap();
+        assert_eq!(volume, 0.5);
+        assert!((attenuated - 0.05).abs() < 1e-6);
+
+        // The newest requested volume is kept as underlying one, then attenuated.
+        let attenuated = section.request(0.8);
+        assert!((attenuated - 0.08).abs() < 1e-6);
+        assert_eq!(section.saved, Some((0.8, attenuated)));
+
+        let monitor = CommandDspMonitorState::default();
+        assert_eq!(section.main_volume(&monitor), 0.8);
+    }
+
+    #[test]
+    fn test_monitor_section_follow() {
+        let mut section = CommandDspMonitorSection::default();
+        section.state.mute = true;
+        section.saved = CommandDspMonitorSection::attenuate(&section.state, 0.5);
+
+        let old = CommandDspMonitorState {
+            main_volume: 0.0,
+            ..Default::default()
+        };
+
+        // The echo of the attenuated volume.
+        let mut new = old;
+        section.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.0);
+        assert_eq!(section.saved, Some((0.5, 0.0)));
+
+        // The volume changed by the knob in the unit is the new underlying one.
+        new.main_volume = 0.7;
+        section.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.0);
+        assert_eq!(section.saved, Some((0.7, 0.0)));
+
+        // Nothing to do without attenuation.
+        let mut section = CommandDspMonitorSection::default();
+        let mut new = old;
+        new.main_volume = 0.7;
+        section.follow(&old, &mut new);
+        assert_eq!(new.main_volume, 0.7);
+        assert_eq!(section.saved, None);
+    }
+}
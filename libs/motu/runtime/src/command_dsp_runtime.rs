@@ -9,16 +9,25 @@ use nix::sys::signal::Signal;
 use glib::{Error, FileError};
 use glib::source;
 
-use hinawa::{FwNodeExt, FwResp, FwRcode, FwTcode};
+use hinawa::{FwNodeExt, FwNodeExtManual, FwResp, FwRcode, FwTcode};
 use hinawa::{SndMotu, SndMotuExt, SndUnitExt};
 
-use alsactl::{CardExt, ElemId, ElemEventMask};
+use alsactl::{CardExt, CardExtManual, ElemEventMask, ElemId, ElemIfaceType, ElemValue};
+use alsactl::ElemValueExtManual;
 
-use core::{card_cntr::*, dispatcher::*};
+use core::{burst::*, card_cntr::*, channel_name::*, dispatcher::*, identify::*, version::*};
+use core::{event_queue::*, watchdog::*, worker::*};
+use core::config::*;
+use core::log::*;
+use core::{log_info, log_warn};
 
-use motu_protocols::command_dsp::*;
+use motu_protocols::{command_dsp::*, trace::*, TargetPort};
 
 use crate::{f828mk3::*, f828mk3_hybrid::*, ultralite_mk3::*, ultralite_mk3_hybrid::*};
+use crate::target_port_to_str;
+use crate::command_dsp_ctls::{MIXER_SOURCE_MUTE_NAME, MIXER_SOURCE_SOLO_NAME};
+use crate::{common_ctls::PhoneFollowMainCtl, v3_ctls::V3_PHONE_FOLLOW_MAIN_PAIRS};
+use crate::common_ctls::{clk_display_text_elem_id, ClkDisplayTextModel, ClkDisplayTextRequest};
 
 pub type UltraliteMk3Runtime = Version3Runtime<UltraLiteMk3>;
 pub type UltraliteMk3HybridRuntime = Version3Runtime<UltraliteMk3Hybrid>;
@@ -27,59 +36,148 @@ pub type F828mk3HybridRuntime = Version3Runtime<F828mk3Hybrid>;
 
 pub struct Version3Runtime<T>
 where
-    for<'a> T: Default + CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + NotifyModel<SndMotu, &'a [DspCmd]> +
-               CommandDspModel<'a>,
+    for<'a> T: Default + CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> +
+               NotifyModel<SndMotu, &'a [DspCmd]> + CommandDspModel<'a> + IdentifyModel<SndMotu> +
+               BatchedWrite<SndMotu> + ClkDisplayTextModel,
 {
     unit: SndMotu,
     model: T,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<Event>,
+    channel_names: ChannelNameCtl,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<Dispatcher>,
+    timer: Option<Dispatcher>,
     #[allow(dead_code)]
     version: u32,
     notified_elem_id_list: Vec<ElemId>,
     msg_handler: Arc<Mutex<CommandDspMessageHandler>>,
+    msg_watchdog: Arc<Mutex<Watchdog<SystemClock>>>,
     cmd_notified_elem_id_list: Vec<ElemId>,
+    measured_elem_id_list: Vec<ElemId>,
+    identify_ctl: IdentifyCtl,
+    burst_timer: Option<Dispatcher>,
+    burst: BurstDetector<SystemClock>,
+    ramp_timer: Option<Dispatcher>,
+    phone_follow_main: PhoneFollowMainCtl,
+    clk_display_writer: Option<Worker<ElemId, (ClkDisplayTextRequest, Result<(), Error>)>>,
 }
 
 impl<T>  Drop for Version3Runtime<T>
 where
-    for<'a> T: Default + CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + NotifyModel<SndMotu, &'a [DspCmd]> +
-               CommandDspModel<'a>,
+    for<'a> T: Default + CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> +
+               NotifyModel<SndMotu, &'a [DspCmd]> + CommandDspModel<'a> + IdentifyModel<SndMotu> +
+               BatchedWrite<SndMotu> + ClkDisplayTextModel,
 {
     fn drop(&mut self) {
         let _ = self.model.release_message_handler(&mut self.unit);
 
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.identify_ctl.timer_mut().into_iter()
+            .chain(self.burst_timer.iter_mut())
+            .chain(self.ramp_timer.iter_mut())
+            .chain(self.timer.iter_mut())
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timers and the dispatcher for signal handler are
+        // finished at first, then the dispatcher for node is finished at last.
+        let dispatchers = self.identify_ctl.take_timer().into_iter()
+            .chain(self.burst_timer.take())
+            .chain(self.ramp_timer.take())
+            .chain(self.timer.take())
+            .chain(self.dispatchers.drain(..).rev());
+        join_dispatchers(dispatchers);
     }
 }
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
     Notify(u32),
     DspMsg,
+    Timer,
+    Identify,
+    Burst,
+    Ramp,
+    ClkDisplayText((ElemId, ClkDisplayTextRequest, Result<(), Error>)),
+}
+
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => Some(elem_id),
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
 }
 
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
 const SYSTEM_DISPATCHER_NAME: &str = "system event dispatcher";
+const TIMER_DISPATCHER_NAME: &str = "interval timer dispatcher";
+const BURST_DISPATCHER_NAME: &str = "burst timer dispatcher";
+const RAMP_DISPATCHER_NAME: &str = "ramp timer dispatcher";
+const CLK_DISPLAY_WRITER_NAME: &str = "clock display writer";
+
+// Some parameters are not configured by command DSP, thus their change is not notified by any
+// message. They are polled in the interval. The arrival of message is checked in the interval as
+// well.
+const TIMER_INTERVAL: Duration = Duration::from_millis(500);
+
+// The ramp of reverb parameters is stepped in the interval, independently of the interval for
+// metering.
+const RAMP_INTERVAL: Duration = Duration::from_millis(50);
+
+// The unit transfers message at least for active sensing while messaging is active. When no message
+// arrives within the timeout in second, the destination address of message is checked and
+// registered again. Zero disables the check.
+const MSG_WATCHDOG_TIMEOUT_NAME: &str = "message-watchdog-timeout";
+const MSG_WATCHDOG_TIMEOUT_MAX: i32 = 60;
+
+// The name of file is written to the element, then the preset is saved to or loaded from the file
+// in the directory configured by the configuration file. Any other path is rejected.
+const PRESET_SAVE_NAME: &str = "preset-save";
+const PRESET_LOAD_NAME: &str = "preset-load";
+const PRESET_PATH_MAX_SIZE: usize = 256;
+
+// Writing true to the element clears solo of all sources, talkback, listenback, and mute of main
+// output at once. The element is cleared by the runtime after the operation.
+const PANIC_NAME: &str = "panic";
+
+// Enable to record recent transactions. The record is dumped to log when any transaction fails or
+// the message watchdog fails to recover messaging.
+const TRANSACTION_TRACE_NAME: &str = "transaction-trace";
+
+impl<T> ConfigReloadOperation for Version3Runtime<T>
+where
+    for<'a> T: Default + CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> +
+               NotifyModel<SndMotu, &'a [DspCmd]> + CommandDspModel<'a> + IdentifyModel<SndMotu> +
+               BatchedWrite<SndMotu> + ClkDisplayTextModel,
+{
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            join_dispatchers(self.timer.take());
+            self.launch_interval_timer_dispatcher()?;
+        }
+        Ok(())
+    }
+}
 
 impl<T> Version3Runtime<T>
 where
-    for<'a> T: Default + CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + NotifyModel<SndMotu, &'a [DspCmd]> +
-               CommandDspModel<'a>,
+    for<'a> T: Default + CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> +
+               NotifyModel<SndMotu, &'a [DspCmd]> + CommandDspModel<'a> + IdentifyModel<SndMotu> +
+               BatchedWrite<SndMotu> + ClkDisplayTextModel,
 {
     pub fn new(unit: SndMotu, card_id: u32, version: u32) -> Result<Self, Error> {
         let card_cntr = CardCntr::new();
@@ -88,18 +186,29 @@ where
         // Use uni-directional channel for communication to child threads. Use large number of
         // queue to avoid task blocking in node message handling.
         let (tx, rx) = mpsc::sync_channel(256);
+        let rx = EventQueue::new(rx);
 
         Ok(Self{
             unit,
             model: Default::default(),
             card_cntr,
+            channel_names: Default::default(),
             rx,
             tx,
             dispatchers: Default::default(),
+            timer: Default::default(),
             version,
             notified_elem_id_list: Default::default(),
             msg_handler: Default::default(),
+            msg_watchdog: Default::default(),
             cmd_notified_elem_id_list: Default::default(),
+            measured_elem_id_list: Default::default(),
+            identify_ctl: Default::default(),
+            burst_timer: Default::default(),
+            burst: Default::default(),
+            ramp_timer: Default::default(),
+            phone_follow_main: Default::default(),
+            clk_display_writer: None,
         })
     }
 
@@ -110,6 +219,7 @@ where
         let node = self.unit.get_node();
         let tx = self.tx.clone();
         let handler = self.msg_handler.clone();
+        let watchdog = self.msg_watchdog.clone();
         // TODO: bus reset can cause change of node ID by updating bus topology.
         let peer_node_id = node.get_property_node_id();
         self.model.prepare_message_handler(&mut self.unit, move |_, tcode, _, src, _, _, _, frame| {
@@ -118,6 +228,9 @@ where
             } else if tcode != FwTcode::WriteQuadletRequest && tcode != FwTcode::WriteBlockRequest {
                 FwRcode::TypeError
             } else {
+                if let Ok(watchdog) = &mut watchdog.lock() {
+                    watchdog.feed();
+                }
                 let notify = if let Ok(handler) = &mut handler.lock() {
                     handler.cache_dsp_messages(frame);
                     if handler.has_dsp_message() {
@@ -163,6 +276,70 @@ where
             &mut self.model,
             &mut self.cmd_notified_elem_id_list
         );
+        MeasureModel::<SndMotu>::get_measure_elem_list(
+            &mut self.model,
+            &mut self.measured_elem_id_list
+        );
+
+        self.launch_interval_timer_dispatcher()?;
+        self.launch_clk_display_writer()?;
+
+        self.phone_follow_main.load(&mut self.card_cntr, V3_PHONE_FOLLOW_MAIN_PAIRS)?;
+
+        self.identify_ctl.load(&mut self.card_cntr)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PANIC_NAME, 0);
+        let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, TRANSACTION_TRACE_NAME, 0);
+        let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+        let mut elem_value = ElemValue::new();
+        elem_value.set_bool(&[is_trace_enabled()]);
+        self.card_cntr.card.write_elem_value(&elem_id, &elem_value)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PRESET_SAVE_NAME, 0);
+        let _ = self.card_cntr.add_bytes_elems(&elem_id, 1, PRESET_PATH_MAX_SIZE, None, true)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PRESET_LOAD_NAME, 0);
+        let _ = self.card_cntr.add_bytes_elems(&elem_id, 1, PRESET_PATH_MAX_SIZE, None, true)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, MSG_WATCHDOG_TIMEOUT_NAME, 0);
+        let _ = self.card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            0,
+            MSG_WATCHDOG_TIMEOUT_MAX,
+            1,
+            1,
+            None,
+            true,
+        )?;
+        let mut elem_value = ElemValue::new();
+        elem_value.set_int(&[Watchdog::<SystemClock>::DEFAULT_TIMEOUT.as_secs() as i32]);
+        self.card_cntr.card.write_elem_value(&elem_id, &elem_value)?;
+
+        let raw = self.unit.get_node().get_config_rom()?;
+        let guid = guid_from_config_rom(&raw)
+            .ok_or_else(|| Error::new(FileError::Nxio, "Unexpected format of configuration ROM"))?;
+        let (input_ports, output_ports) = self.model.channel_ports();
+        let port_names = |ports: &[TargetPort]| {
+            ports
+                .iter()
+                .map(|port| target_port_to_str(port).to_string())
+                .collect::<Vec<String>>()
+        };
+        self.channel_names.load(
+            &mut self.card_cntr,
+            guid,
+            port_names(input_ports),
+            port_names(output_ports),
+        )?;
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            motu_protocols::CRATE_VERSION,
+        )?;
 
         Ok(())
     }
@@ -176,16 +353,97 @@ where
 
             match ev {
                 Event::Shutdown | Event::Disconnected => break,
+                Event::Reload => self.reload(),
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Elem((elem_id, events)) => {
-                    let _ = self.card_cntr.dispatch_elem_event(
-                        &mut self.unit,
-                        &elem_id,
-                        &events,
-                        &mut self.model,
-                    );
+                    let name = elem_id.get_name();
+                    if IdentifyCtl::is_identify_elem(&elem_id) {
+                        let _ = self.identify_ctl.handle_elem_event(
+                            &mut self.card_cntr,
+                            &elem_id,
+                            &self.tx,
+                            || Event::Identify,
+                        );
+                    } else if name == INPUT_NAMES_NAME || name == OUTPUT_NAMES_NAME {
+                        if let Err(e) =
+                            self.channel_names.handle_elem_event(&mut self.card_cntr, &elem_id)
+                        {
+                            log_warn!(LOG_RUNTIME, "Fail to update names of channels: {}", e);
+                        }
+                    } else if name == PANIC_NAME {
+                        if let Err(e) = self.operate_panic(&elem_id) {
+                            log_warn!(LOG_RUNTIME, "Fail to operate panic: {}", e);
+                        }
+                    } else if name == PRESET_SAVE_NAME || name == PRESET_LOAD_NAME {
+                        if let Err(e) = self.operate_preset(&elem_id) {
+                            log_warn!(LOG_RUNTIME, "Fail to operate preset: {}", e);
+                        }
+                    } else if name == MSG_WATCHDOG_TIMEOUT_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [0];
+                            elem_value.get_int(&mut vals);
+                            if let Ok(watchdog) = &mut self.msg_watchdog.lock() {
+                                watchdog.set_timeout(Duration::from_secs(vals[0] as u64));
+                            }
+                        }
+                    } else if name == TRANSACTION_TRACE_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            set_trace_enabled(vals[0]);
+                        }
+                    } else {
+                        // When alsactl restores the state of elements, many writes arrive in
+                        // short period. They are batched to reduce transactions.
+                        let unit = &mut self.unit;
+                        let model = &mut self.model;
+                        let burst = &mut self.burst;
+                        let res = self.phone_follow_main.dispatch_elem_event(
+                            &mut self.card_cntr,
+                            &elem_id,
+                            &events,
+                            |card_cntr| {
+                                card_cntr.dispatch_elem_event_with_burst(
+                                    unit,
+                                    &elem_id,
+                                    &events,
+                                    model,
+                                    burst,
+                                )
+                            },
+                        );
+                        if let Err(e) = res {
+                            log_warn!(LOG_RUNTIME, "{}", e);
+                        }
+                        self.submit_clk_display_text();
+                        if self.burst.is_active() && self.burst_timer.is_none() {
+                            let _ = self.start_burst_timer();
+                        }
+                        if self.model.is_reverb_ramp_active() && self.ramp_timer.is_none() {
+                            let _ = self.start_ramp_timer();
+                        }
+                        // The mute of sources follows solo in solo-in-place mode.
+                        if name == MIXER_SOURCE_SOLO_NAME {
+                            let elem_id_list: Vec<ElemId> = self
+                                .cmd_notified_elem_id_list
+                                .iter()
+                                .filter(|e| e.get_name() == MIXER_SOURCE_MUTE_NAME)
+                                .cloned()
+                                .collect();
+                            elem_id_list.iter().for_each(|elem_id| {
+                                let _ = self.card_cntr.dispatch_elem_event(
+                                    &mut self.unit,
+                                    elem_id,
+                                    &ElemEventMask::ADD,
+                                    &mut self.model,
+                                );
+                            });
+                        }
+                    }
                 }
                 Event::Notify(msg) => {
                     let _ = self.card_cntr.dispatch_notification(
@@ -205,13 +463,59 @@ where
                     } else {
                         Default::default()
                     };
-                    let _ = self.card_cntr.dispatch_notification(
+                    let _ = self.dispatch_commands(&cmds);
+                }
+                Event::Timer => {
+                    let _ = self.card_cntr.measure_elems(
+                        &mut self.unit,
+                        &self.measured_elem_id_list,
+                        &mut self.model,
+                    );
+                    self.check_messaging();
+                }
+                Event::Identify => {
+                    let _ = self.identify_ctl.blink(
+                        &mut self.card_cntr,
                         &mut self.unit,
-                        &&cmds[..],
-                        &self.cmd_notified_elem_id_list,
                         &mut self.model,
                     );
                 }
+                Event::Burst => {
+                    if let Err(e) = self.burst.flush_if_finished(&mut self.unit, &mut self.model) {
+                        log_warn!(LOG_RUNTIME, "Fail to flush batched writes: {}", e);
+                    }
+                    if !self.burst.is_active() {
+                        self.burst_timer = None;
+                    }
+                }
+                Event::Ramp => {
+                    if let Err(e) = self.model.step_reverb_ramp(&mut self.unit) {
+                        log_warn!(LOG_RUNTIME, "Fail to change reverb parameters: {}", e);
+                    }
+                    if !self.model.is_reverb_ramp_active() {
+                        join_dispatchers(self.ramp_timer.take());
+                    }
+                }
+                Event::ClkDisplayText((elem_id, request, res)) => {
+                    if let Err(e) = &res {
+                        log_warn!(LOG_RUNTIME, "Fail to update text in clock display: {}", e);
+                    }
+                    // The element is notified when the text is restored.
+                    if self.model.complete_clk_display_text_request(&request, &res) {
+                        let _ = self.card_cntr.dispatch_elem_event(
+                            &mut self.unit,
+                            &elem_id,
+                            &ElemEventMask::ADD,
+                            &mut self.model,
+                        );
+                    }
+                }
+            }
+
+            // The failure of transaction is not always reported by the handlers of event, e.g.
+            // send_commands() in batched writes.
+            if take_trace_failure() {
+                dump_transaction_trace();
             }
         }
         Ok(())
@@ -256,6 +560,11 @@ where
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -266,6 +575,205 @@ where
 
         Ok(())
     }
+
+    fn launch_interval_timer_dispatcher(&mut self) -> Result<(), Error> {
+        let name = TIMER_DISPATCHER_NAME.to_string();
+        let mut dispatcher = Dispatcher::run(name)?;
+
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(metering_interval(TIMER_INTERVAL), move || {
+            let _ = tx.send(Event::Timer);
+            source::Continue(true)
+        });
+
+        self.timer = Some(dispatcher);
+
+        Ok(())
+    }
+
+    // The unit clears the destination address of message when rebooting its firmware, then no
+    // message arrives silently.
+    fn check_messaging(&mut self) {
+        let expired = match &mut self.msg_watchdog.lock() {
+            Ok(watchdog) => watchdog.check_expired(),
+            Err(_) => false,
+        };
+        if expired {
+            match self.model.ensure_messaging(&mut self.unit) {
+                Ok(true) => log_info!(
+                    LOG_RUNTIME,
+                    "Destination address of message was lost, then registered again."
+                ),
+                Ok(false) => (),
+                Err(e) => {
+                    log_warn!(
+                        LOG_RUNTIME,
+                        "Fail to check destination address of message: {}",
+                        e
+                    );
+                    let _ = take_trace_failure();
+                    dump_transaction_trace();
+                }
+            }
+        }
+    }
+
+    fn start_burst_timer(&mut self) -> Result<(), Error> {
+        let mut dispatcher = Dispatcher::run(BURST_DISPATCHER_NAME.to_string())?;
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(BurstDetector::<SystemClock>::INTERVAL, move || {
+            let _ = tx.send(Event::Burst);
+            source::Continue(true)
+        });
+
+        self.burst_timer = Some(dispatcher);
+
+        Ok(())
+    }
+
+    fn start_ramp_timer(&mut self) -> Result<(), Error> {
+        let mut dispatcher = Dispatcher::run(RAMP_DISPATCHER_NAME.to_string())?;
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(RAMP_INTERVAL, move || {
+            let _ = tx.send(Event::Ramp);
+            source::Continue(true)
+        });
+
+        self.ramp_timer = Some(dispatcher);
+
+        Ok(())
+    }
+
+    fn launch_clk_display_writer(&mut self) -> Result<(), Error> {
+        let tx = self.tx.clone();
+        let worker = Worker::run(CLK_DISPLAY_WRITER_NAME, move |elem_id, (request, res)| {
+            // Never block the worker thread at shutdown.
+            let _ = tx.try_send(Event::ClkDisplayText((elem_id, request, res)));
+        })?;
+        self.clk_display_writer = Some(worker);
+
+        Ok(())
+    }
+
+    // The request queued in the worker is superseded by the new one.
+    fn submit_clk_display_text(&mut self) {
+        if let Some(request) = self.model.take_clk_display_text_request() {
+            if let Some(worker) = &self.clk_display_writer {
+                let mut node = self.unit.get_node();
+                worker.submit(clk_display_text_elem_id(), move || {
+                    let res = request.execute(&mut node);
+                    (request, res)
+                });
+            }
+        }
+    }
+
+    fn operate_panic(&mut self, elem_id: &ElemId) -> Result<(), Error> {
+        let mut elem_value = ElemValue::new();
+        self.card_cntr.card.read_elem_value(elem_id, &mut elem_value)?;
+        let mut vals = [false];
+        elem_value.get_bool(&mut vals);
+        if !vals[0] {
+            return Ok(());
+        }
+
+        // Clear the element so that the operation can be triggered again.
+        elem_value.set_bool(&[false]);
+        self.card_cntr.card.write_elem_value(elem_id, &elem_value)?;
+
+        // The commands are parsed as well as message from the unit to update the cache, then
+        // the change is notified to elements.
+        let cmds = self.model.panic(&mut self.unit)?;
+        if cmds.is_empty() {
+            return Ok(());
+        }
+        log_info!(LOG_RUNTIME, "Panic: {} commands are sent.", cmds.len());
+        self.dispatch_commands(&cmds)
+    }
+
+    // The main volume adjusted at parsing the commands is written to the unit after the
+    // notification, then the change is notified to elements as well.
+    fn dispatch_commands(&mut self, cmds: &[DspCmd]) -> Result<(), Error> {
+        self.card_cntr.dispatch_notification(
+            &mut self.unit,
+            &cmds,
+            &self.cmd_notified_elem_id_list,
+            &mut self.model,
+        )?;
+        let cmds = self.model.write_pending_main_volume(&mut self.unit)?;
+        if cmds.is_empty() {
+            return Ok(());
+        }
+        self.card_cntr.dispatch_notification(
+            &mut self.unit,
+            &&cmds[..],
+            &self.cmd_notified_elem_id_list,
+            &mut self.model,
+        )
+    }
+
+    fn operate_preset(&mut self, elem_id: &ElemId) -> Result<(), Error> {
+        let mut elem_value = ElemValue::new();
+        self.card_cntr.card.read_elem_value(elem_id, &mut elem_value)?;
+        let mut vals = vec![0; PRESET_PATH_MAX_SIZE];
+        elem_value.get_bytes(&mut vals);
+
+        // The element is cleared by the runtime after the operation.
+        let len = vals.iter().position(|&b| b == 0).unwrap_or(vals.len());
+        if len == 0 {
+            return Ok(());
+        }
+
+        let name = std::str::from_utf8(&vals[..len])
+            .map(|name| name.to_string())
+            .map_err(|e| {
+                let msg = format!("Invalid bytes for name of preset: {}", e);
+                Error::new(FileError::Inval, &msg)
+            })?;
+
+        // Clear the name so that the same name can be written again.
+        elem_value.set_bytes(&vec![0; PRESET_PATH_MAX_SIZE]);
+        self.card_cntr.card.write_elem_value(elem_id, &elem_value)?;
+
+        let path = preset_path(&name).map_err(|msg| Error::new(FileError::Acces, &msg))?;
+
+        if elem_id.get_name() == PRESET_SAVE_NAME {
+            let raw = self.model.build_preset();
+            std::fs::write(&path, &raw).map_err(|e| {
+                let msg = format!("Fail to write preset to {}: {}", path.display(), e);
+                Error::new(FileError::Io, &msg)
+            })
+        } else {
+            let raw = std::fs::read(&path).map_err(|e| {
+                let msg = format!("Fail to read preset from {}: {}", path.display(), e);
+                Error::new(FileError::Io, &msg)
+            })?;
+            let ignored = self.model.load_preset(&mut self.unit, &raw)?;
+            if ignored > 0 {
+                log_warn!(
+                    LOG_RUNTIME,
+                    "{} commands in preset {} are ignored.",
+                    ignored,
+                    path.display()
+                );
+            }
+
+            // Notify the change of state to elements.
+            let cmds: Vec<DspCmd> = Vec::new();
+            self.card_cntr.dispatch_notification(
+                &mut self.unit,
+                &&cmds[..],
+                &self.cmd_notified_elem_id_list,
+                &mut self.model,
+            )
+        }
+    }
+}
+
+fn dump_transaction_trace() {
+    if is_trace_enabled() {
+        log_warn!(LOG_RUNTIME, "Recent transactions:\n{}", format_trace());
+    }
 }
 
 pub trait CommandDspModel<'a> : NotifyModel<SndMotu, &'a [DspCmd]> {
@@ -274,4 +782,22 @@ pub trait CommandDspModel<'a> : NotifyModel<SndMotu, &'a [DspCmd]> {
             F: Fn(&FwResp, FwTcode, u64, u32, u32, u32, u32, &[u8]) -> FwRcode + 'static;
     fn begin_messaging(&mut self, unit: &mut SndMotu) -> Result<(), Error>;
     fn release_message_handler(&mut self, unit: &mut SndMotu) -> Result<(), Error>;
+    /// Register the destination address of message and begin messaging again when the unit lost
+    /// the address. Return true when recovered.
+    fn ensure_messaging(&mut self, unit: &mut SndMotu) -> Result<bool, Error>;
+    fn build_preset(&self) -> Vec<u8>;
+    /// Load the preset and return the number of ignored commands.
+    fn load_preset(&mut self, unit: &mut SndMotu, raw: &[u8]) -> Result<usize, Error>;
+    /// Clear solo of all sources, talkback, listenback, and mute of main output at once, then
+    /// return the commands sent to the unit.
+    fn panic(&mut self, unit: &mut SndMotu) -> Result<Vec<DspCmd>, Error>;
+    /// Write main volume adjusted at parsing notified commands, then return the commands sent to
+    /// the unit.
+    fn write_pending_main_volume(&mut self, unit: &mut SndMotu) -> Result<Vec<DspCmd>, Error>;
+    /// Whether the ramp of reverb parameters is in progress.
+    fn is_reverb_ramp_active(&self) -> bool;
+    /// Change reverb parameters by one step of the ramp in progress.
+    fn step_reverb_ramp(&mut self, unit: &mut SndMotu) -> Result<(), Error>;
+    /// The ports for input and output channels, to which names are given.
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]);
 }
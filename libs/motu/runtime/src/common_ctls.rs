@@ -3,19 +3,22 @@
 
 use glib::{Error, FileError};
 
-use hinawa::FwReq;
+use hinawa::{FwNode, FwReq};
 use hinawa::{SndMotu, SndUnitExt};
 
-use alsactl::{ElemId, ElemIfaceType, ElemValue};
+use alsactl::{CardExtManual, ElemEventMask, ElemId, ElemIfaceType, ElemValue, ElemValueExt};
+use alsactl::ElemValueExtManual;
 
 use core::card_cntr::CardCntr;
 use core::elem_value_accessor::ElemValueAccessor;
+use core::log::*;
+use core::log_warn;
 
 use motu_protocols::*;
 
 use super::*;
 
-const PHONE_ASSIGN_NAME: &str = "phone-assign";
+pub const PHONE_ASSIGN_NAME: &str = "phone-assign";
 
 pub trait PhoneAssignCtlOperation<T: AssignOperation> {
     fn state(&self) -> &usize;
@@ -77,6 +80,105 @@ pub trait PhoneAssignCtlOperation<T: AssignOperation> {
     }
 }
 
+const PHONE_FOLLOW_MAIN_NAME: &str = "phones-follow-main";
+
+/// The structure for link of phone bus to main output. While the link is enabled, the change of
+/// value in the element for main output is mirrored to the element for phone bus, and direct write
+/// to the element for phone bus is rejected. The last mirrored value is left as is when disabled.
+#[derive(Default, Debug)]
+pub struct PhoneFollowMainCtl {
+    /// The pairs of name for element of main output and element of phone bus.
+    pairs: &'static [(&'static str, &'static str)],
+    enabled: bool,
+}
+
+fn mixer_elem_id(name: &str) -> ElemId {
+    ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, name, 0)
+}
+
+impl PhoneFollowMainCtl {
+    pub fn load(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        pairs: &'static [(&'static str, &'static str)],
+    ) -> Result<(), Error> {
+        self.pairs = pairs;
+
+        let elem_id = mixer_elem_id(PHONE_FOLLOW_MAIN_NAME);
+        card_cntr.add_bool_elems(&elem_id, 1, 1, true).map(|_| ())
+    }
+
+    fn mirror(card_cntr: &mut CardCntr, main: &str, phone: &str) -> Result<(), Error> {
+        let mut elem_value = ElemValue::new();
+        card_cntr.card.read_elem_value(&mixer_elem_id(main), &mut elem_value)?;
+        card_cntr.card.write_elem_value(&mixer_elem_id(phone), &elem_value)
+    }
+
+    /// Dispatch the event of element. The write to the element of link is handled without the
+    /// model. While the link is enabled, the write to the element of phone bus is dispatched just
+    /// for the value mirrored from main output, and the write to the element of main output is
+    /// mirrored to the element of phone bus after dispatched.
+    pub fn dispatch_elem_event<F>(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        elem_id: &ElemId,
+        events: &ElemEventMask,
+        dispatch: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut CardCntr) -> Result<(), Error>,
+    {
+        if !events.contains(ElemEventMask::VALUE) {
+            return dispatch(card_cntr);
+        }
+
+        let name = elem_id.get_name();
+
+        if name.as_str() == PHONE_FOLLOW_MAIN_NAME {
+            let mut elem_value = ElemValue::new();
+            card_cntr.card.read_elem_value(elem_id, &mut elem_value)?;
+            let mut vals = [false];
+            elem_value.get_bool(&mut vals);
+            self.enabled = vals[0];
+            if self.enabled {
+                self.pairs
+                    .iter()
+                    .try_for_each(|&(main, phone)| Self::mirror(card_cntr, main, phone))?;
+            }
+            return Ok(());
+        }
+
+        if self.enabled {
+            if let Some(&(main, phone)) = self.pairs.iter().find(|(_, p)| name.as_str() == *p) {
+                let mut main_value = ElemValue::new();
+                card_cntr.card.read_elem_value(&mixer_elem_id(main), &mut main_value)?;
+                let mut phone_value = ElemValue::new();
+                card_cntr.card.read_elem_value(elem_id, &mut phone_value)?;
+                if !phone_value.equal(&main_value) {
+                    // Back to the mirrored value.
+                    card_cntr.card.write_elem_value(elem_id, &main_value)?;
+                    let msg = format!(
+                        "{} follows {}, disable {} to change it",
+                        phone, main, PHONE_FOLLOW_MAIN_NAME
+                    );
+                    log_warn!(LOG_RUNTIME, "{}", msg);
+                    Err(Error::new(FileError::Inval, &msg))?;
+                }
+            }
+        }
+
+        dispatch(card_cntr)?;
+
+        if self.enabled {
+            if let Some(&(main, phone)) = self.pairs.iter().find(|(m, _)| name.as_str() == *m) {
+                Self::mirror(card_cntr, main, phone)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 fn word_clk_speed_mode_to_str(mode: &WordClkSpeedMode) -> &'static str {
     match mode {
         WordClkSpeedMode::ForceLowRate => "Force 44.1/48.0 kHz",
@@ -85,15 +187,30 @@ fn word_clk_speed_mode_to_str(mode: &WordClkSpeedMode) -> &'static str {
 }
 
 const WORD_OUT_MODE_NAME: &str = "word-out-mode";
+const WORD_OUT_RATE_NAME: &str = "word-out-rate";
 
 const WORD_OUT_MODES: [WordClkSpeedMode; 2] = [
     WordClkSpeedMode::ForceLowRate,
     WordClkSpeedMode::FollowSystemClk,
 ];
 
+const WORD_OUT_RATE_MAX: i32 = 192000;
+
+/// The cache of word clock output.
+#[derive(Default, Debug)]
+pub struct WordClkState {
+    pub mode: WordClkSpeedMode,
+    /// The rate of sampling clock, from which the rate of word clock output is derived.
+    pub rate: Option<ClkRate>,
+    /// The elements for mode, changed by notification.
+    pub notified_elem_list: Vec<ElemId>,
+    /// The elements for effective rate, refreshed by polling the rate of sampling clock.
+    pub measured_elem_list: Vec<ElemId>,
+}
+
 pub trait WordClkCtlOperation<T: WordClkOperation> {
-    fn state(&self) -> &WordClkSpeedMode;
-    fn state_mut(&mut self) -> &mut WordClkSpeedMode;
+    fn state(&self) -> &WordClkState;
+    fn state_mut(&mut self) -> &mut WordClkState;
 
     fn load(
         &mut self,
@@ -101,7 +218,7 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
         unit: &mut SndMotu,
         req: &mut FwReq,
         timeout_ms: u32
-    ) -> Result<Vec<ElemId>, Error> {
+    ) -> Result<(), Error> {
         self.cache(unit, req, timeout_ms)?;
 
         let labels: Vec<&str> = WORD_OUT_MODES
@@ -110,6 +227,12 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, WORD_OUT_MODE_NAME, 0);
         card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut elem_id_list| self.state_mut().notified_elem_list.append(&mut elem_id_list))?;
+
+        // The value is 0 when the rate of sampling clock is not retrieved yet.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, WORD_OUT_RATE_NAME, 0);
+        card_cntr.add_int_elems(&elem_id, 1, 0, WORD_OUT_RATE_MAX, 1, 1, None, false)
+            .map(|mut elem_id_list| self.state_mut().measured_elem_list.append(&mut elem_id_list))
     }
 
     fn cache(
@@ -118,7 +241,20 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
         req: &mut FwReq,
         timeout_ms: u32
     ) -> Result<(), Error> {
-        T::get_word_out(req, &mut unit.get_node(), timeout_ms).map(|mode| *self.state_mut() = mode)
+        T::get_word_out(req, &mut unit.get_node(), timeout_ms)
+            .map(|mode| self.state_mut().mode = mode)?;
+        self.measure(unit, req, timeout_ms)
+    }
+
+    /// Retrieve the rate of sampling clock, since the unit doesn't notify the change of it.
+    fn measure(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        T::get_word_out_clk_rate(req, &mut unit.get_node(), timeout_ms)
+            .map(|rate| self.state_mut().rate = Some(rate))
     }
 
     fn read(
@@ -131,12 +267,22 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
                 ElemValueAccessor::<u32>::set_val(elem_value, || {
                     let pos = WORD_OUT_MODES
                         .iter()
-                        .position(|m| self.state().eq(m))
+                        .position(|m| self.state().mode.eq(m))
                         .unwrap();
                     Ok(pos as u32)
                 })
                     .map(|_| true)
             }
+            WORD_OUT_RATE_NAME => {
+                ElemValueAccessor::<i32>::set_val(elem_value, || {
+                    let state = self.state();
+                    let rate = state.rate
+                        .map(|rate| state.mode.effective_rate(rate) as i32)
+                        .unwrap_or(0);
+                    Ok(rate)
+                })
+                    .map(|_| true)
+            }
             _ => Ok(false),
         }
     }
@@ -160,7 +306,7 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
                             Error::new(FileError::Inval, &msg)
                         })?;
                     T::set_word_out(req, &mut unit.get_node(), mode, timeout_ms)
-                        .map(|_| *self.state_mut() = mode)
+                        .map(|_| self.state_mut().mode = mode)
                 })
                 .map(|_| true)
             }
@@ -169,15 +315,6 @@ pub trait WordClkCtlOperation<T: WordClkOperation> {
     }
 }
 
-fn aesebu_rate_convert_mode_to_str(mode: &AesebuRateConvertMode) -> &'static str{
-    match mode {
-        AesebuRateConvertMode::None => "None",
-        AesebuRateConvertMode::InputToSystem => "input-is-converted",
-        AesebuRateConvertMode::OutputDependsInput => "output-depends-on-input",
-        AesebuRateConvertMode::OutputDoubleSystem => "output-is-double",
-    }
-}
-
 const AESEBU_RATE_CONVERT_MODE_NAME: &str = "AES/EBU-rate-convert";
 
 pub trait AesebuRateConvertCtlOperation<T: AesebuRateConvertOperation> {
@@ -209,8 +346,16 @@ pub trait AesebuRateConvertCtlOperation<T: AesebuRateConvertOperation> {
         match elem_id.get_name().as_str() {
             AESEBU_RATE_CONVERT_MODE_NAME => {
                 ElemValueAccessor::<u32>::set_val(elem_value, || {
-                    T::get_aesebu_rate_convert_mode(req, &mut unit.get_node(), timeout_ms)
-                        .map(|val| val as u32)
+                    let mode =
+                        T::get_aesebu_rate_convert_mode(req, &mut unit.get_node(), timeout_ms)?;
+                    T::AESEBU_RATE_CONVERT_MODES
+                        .iter()
+                        .position(|m| mode.eq(m))
+                        .map(|pos| pos as u32)
+                        .ok_or_else(|| {
+                            let msg = format!("Unexpected mode of AES/EBU rate convert: {:?}", mode);
+                            Error::new(FileError::Nxio, &msg)
+                        })
                 })
                 .map(|_| true)
             }
@@ -229,7 +374,15 @@ pub trait AesebuRateConvertCtlOperation<T: AesebuRateConvertOperation> {
         match elem_id.get_name().as_str() {
             AESEBU_RATE_CONVERT_MODE_NAME => {
                 ElemValueAccessor::<u32>::get_val(elem_value, |val| {
-                    T::set_aesebu_rate_convert_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
+                    let &mode = T::AESEBU_RATE_CONVERT_MODES
+                        .iter()
+                        .nth(val as usize)
+                        .ok_or_else(|| {
+                            let msg =
+                                format!("Invalid argument for index of AES/EBU rate convert: {}", val);
+                            Error::new(FileError::Inval, &msg)
+                        })?;
+                    T::set_aesebu_rate_convert_mode(req, &mut unit.get_node(), mode, timeout_ms)
                 })
                 .map(|_| true)
             }
@@ -238,183 +391,128 @@ pub trait AesebuRateConvertCtlOperation<T: AesebuRateConvertOperation> {
     }
 }
 
-fn level_meters_hold_time_mode_to_string(mode: &LevelMetersHoldTimeMode) -> &'static str {
-    match mode {
-        LevelMetersHoldTimeMode::Off => "off",
-        LevelMetersHoldTimeMode::Sec2 => "2sec",
-        LevelMetersHoldTimeMode::Sec4 => "4sec",
-        LevelMetersHoldTimeMode::Sec10 => "10sec",
-        LevelMetersHoldTimeMode::Sec60 => "1min",
-        LevelMetersHoldTimeMode::Sec300 => "5min",
-        LevelMetersHoldTimeMode::Sec480 => "8min",
-        LevelMetersHoldTimeMode::Infinite => "infinite",
-    }
+const CLK_DISPLAY_TEXT_NAME: &str = "clock-display-text";
+
+// The register for the display is write-only, thus the text is cached.
+#[derive(Default)]
+pub struct ClkDisplayTextState {
+    text: [u8; DISPLAY_CHARS],
+    written: [u8; DISPLAY_CHARS],
+    request: Option<ClkDisplayTextRequest>,
 }
 
-fn level_meters_aesebu_mode_to_string(mode: &LevelMetersAesebuMode) -> &'static str {
-    match mode {
-        LevelMetersAesebuMode::Output => "output",
-        LevelMetersAesebuMode::Input => "input",
-    }
+/// The request to update text in clock display. The request is executed in the worker thread of
+/// runtime with own transaction object, so that the event loop is not blocked by the transactions.
+pub struct ClkDisplayTextRequest {
+    text: [u8; DISPLAY_CHARS],
+    update: fn(&mut FwReq, &mut FwNode, &[u8], u32) -> Result<(), Error>,
+    timeout_ms: u32,
 }
 
-fn level_meters_programmable_mode_to_string(mode: &LevelMetersProgrammableMode) -> &'static str {
-    match mode {
-        LevelMetersProgrammableMode::AnalogOutput => "analog-output",
-        LevelMetersProgrammableMode::AdatInput => "ADAT-input",
-        LevelMetersProgrammableMode::AdatOutput => "ADAT-output",
+impl ClkDisplayTextRequest {
+    pub fn execute(&self, node: &mut FwNode) -> Result<(), Error> {
+        let mut req = FwReq::new();
+        (self.update)(&mut req, node, &self.text, self.timeout_ms)
     }
 }
 
-const PEAK_HOLD_TIME_MODE_NAME: &str = "meter-peak-hold-time";
-const CLIP_HOLD_TIME_MODE_NAME: &str = "meter-clip-hold-time";
-const AESEBU_MODE_NAME: &str = "AES/EBU-meter";
-const PROGRAMMABLE_MODE_NAME: &str = "programmable-meter";
-
-#[derive(Default)]
-pub struct LevelMeterState(usize, usize);
-
-pub trait LevelMetersCtlOperation<T: LevelMetersOperation> {
-    fn state(&self) -> &LevelMeterState;
-    fn state_mut(&mut self) -> &mut LevelMeterState;
+/// The trait for the model to update text in clock display out of the event loop.
+pub trait ClkDisplayTextModel {
+    /// Take the request queued by write operation for the element.
+    fn take_clk_display_text_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        None
+    }
 
-    fn load(
+    /// Finalize the cache by the result of request. Return true when the element should be
+    /// notified.
+    fn complete_clk_display_text_request(
         &mut self,
-        card_cntr: &mut CardCntr,
-        unit: &mut SndMotu,
-        req: &mut FwReq,
-        timeout_ms: u32
-    ) -> Result<Vec<ElemId>, Error> {
-        self.cache(unit, req, timeout_ms)?;
-
-        let mut notified_elem_id_list = Vec::new();
-
-        let labels: Vec<&str> = T::LEVEL_METERS_HOLD_TIME_MODES
-            .iter()
-            .map(|l| level_meters_hold_time_mode_to_string(&l))
-            .collect();
-        let elem_id =
-            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PEAK_HOLD_TIME_MODE_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let elem_id =
-            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, CLIP_HOLD_TIME_MODE_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)?;
-
-        let labels: Vec<&str> = T::LEVEL_METERS_AESEBU_MODES
-            .iter()
-            .map(|l| level_meters_aesebu_mode_to_string(&l))
-            .collect();
-        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, AESEBU_MODE_NAME, 0);
-        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
-            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
-
-        let labels: Vec<&str> = T::LEVEL_METERS_PROGRAMMABLE_MODES
-            .iter()
-            .map(|l| level_meters_programmable_mode_to_string(&l))
-            .collect();
-        let elem_id =
-            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PROGRAMMABLE_MODE_NAME, 0);
-        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
-            .map(|mut elem_id_list| notified_elem_id_list.append(&mut elem_id_list))?;
-
-        Ok(notified_elem_id_list)
+        _: &ClkDisplayTextRequest,
+        _: &Result<(), Error>,
+    ) -> bool {
+        false
     }
+}
 
-    fn cache(
-        &mut self,
-        unit: &mut SndMotu,
-        req: &mut FwReq,
-        timeout_ms: u32
-    ) -> Result<(), Error> {
-        T::get_level_meters_aesebu_mode(req, &mut unit.get_node(), timeout_ms).map(|idx| {
-            self.state_mut().0 = idx;
-        })?;
+pub fn clk_display_text_elem_id() -> ElemId {
+    ElemId::new_by_name(ElemIfaceType::Card, 0, 0, CLK_DISPLAY_TEXT_NAME, 0)
+}
 
-        T::get_level_meters_programmable_mode(req, &mut unit.get_node(), timeout_ms).map(|idx| {
-            self.state_mut().1 = idx;
-        })?;
+pub trait ClkDisplayTextCtlOperation<T: ClkDisplayOperation> {
+    fn state(&self) -> &ClkDisplayTextState;
+    fn state_mut(&mut self) -> &mut ClkDisplayTextState;
 
-        Ok(())
-    }
+    fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+        let text = T::build_clk_display_text(&[]);
+        let state = self.state_mut();
+        state.text = text;
+        state.written = text;
 
-    fn read(
-        &mut self,
-        unit: &mut SndMotu,
-        req: &mut FwReq,
-        elem_id: &ElemId,
-        elem_value: &mut ElemValue,
-        timeout_ms: u32,
-    ) -> Result<bool, Error> {
-        match elem_id.get_name().as_str() {
-            PEAK_HOLD_TIME_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_val(elem_value, || {
-                    T::get_level_meters_peak_hold_time_mode(req, &mut unit.get_node(), timeout_ms)
-                        .map(|val| val as u32)
-                })
-                .map(|_| true)
-            }
-            CLIP_HOLD_TIME_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_val(elem_value, || {
-                    T::get_level_meters_clip_hold_time_mode(req, &mut unit.get_node(), timeout_ms)
-                        .map(|val| val as u32)
-                })
-                .map(|_| true)
-            }
-            _ => self.refer(elem_id, elem_value),
-        }
+        card_cntr.add_bytes_elems(&clk_display_text_elem_id(), 1, DISPLAY_CHARS, None, true)
+            .map(|_| ())
     }
 
-    fn refer(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
-            AESEBU_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_val(elem_value, || Ok(self.state().0 as u32))
-                .map(|_| true)
-            }
-            PROGRAMMABLE_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_val(elem_value, || Ok(self.state().1 as u32))
-                .map(|_| true)
+            CLK_DISPLAY_TEXT_NAME => {
+                elem_value.set_bytes(&self.state().text);
+                Ok(true)
             }
             _ => Ok(false),
         }
     }
 
+    // The transactions are not initiated here. The request supersedes the one still queued in
+    // the worker thread, thus frequent update by client doesn't hammer the bus.
     fn write(
         &mut self,
-        unit: &mut SndMotu,
-        req: &mut FwReq,
         elem_id: &ElemId,
         elem_value: &ElemValue,
         timeout_ms: u32,
     ) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
-            PEAK_HOLD_TIME_MODE_NAME => {
-                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
-                    T::set_level_meters_peak_hold_time_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
-                })
-                .map(|_| true)
+            CLK_DISPLAY_TEXT_NAME => {
+                let mut vals = [0; DISPLAY_CHARS];
+                elem_value.get_bytes(&mut vals);
+                let text = T::build_clk_display_text(&vals);
+
+                let state = self.state_mut();
+                state.text = text;
+                state.request = Some(ClkDisplayTextRequest {
+                    text,
+                    update: T::update_clk_display_text,
+                    timeout_ms,
+                });
+                Ok(true)
             }
-            CLIP_HOLD_TIME_MODE_NAME => {
-                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
-                    T::set_level_meters_clip_hold_time_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
-                })
-                .map(|_| true)
-            }
-            AESEBU_MODE_NAME => {
-                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
-                    T::set_level_meters_aesebu_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
-                        .map(|_| self.state_mut().0 = val as usize)
-                })
-                .map(|_| true)
+            _ => Ok(false),
+        }
+    }
+
+    fn take_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        self.state_mut().request.take()
+    }
+
+    fn complete_request(
+        &mut self,
+        request: &ClkDisplayTextRequest,
+        res: &Result<(), Error>,
+    ) -> bool {
+        let state = self.state_mut();
+        match res {
+            Ok(_) => {
+                state.written = request.text;
+                false
             }
-            PROGRAMMABLE_MODE_NAME => {
-                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
-                    T::set_level_meters_programmable_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
-                        .map(|_| self.state_mut().1 = val as usize)
-                })
-                .map(|_| true)
+            // The text in the element is restored unless newer text is requested.
+            Err(_) => {
+                if state.text == request.text && state.text != state.written {
+                    state.text = state.written;
+                    true
+                } else {
+                    false
+                }
             }
-            _ => Ok(false),
         }
     }
 }
@@ -96,6 +96,18 @@ impl CtlModel<SndMotu> for F828 {
     }
 }
 
+impl MeasureModel<SndMotu> for F828 {
+    fn get_measure_elem_list(&mut self, _: &mut Vec<ElemId>) {}
+
+    fn measure_states(&mut self, _: &mut SndMotu) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn measure_elem(&mut self, _: &SndMotu, _: &ElemId, _: &mut ElemValue) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for F828 {
     fn get_notified_elem_list(&mut self, _: &mut Vec<ElemId>) {
     }
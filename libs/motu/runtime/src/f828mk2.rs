@@ -7,11 +7,12 @@ use hinawa::SndMotu;
 
 use alsactl::{ElemId, ElemValue};
 
-use core::card_cntr::{CardCntr, CtlModel, NotifyModel};
+use core::card_cntr::{CardCntr, CtlModel, MeasureModel, NotifyModel};
 
 use motu_protocols::{register_dsp::*, version_2::*, *};
 
 use super::{common_ctls::*, register_dsp_ctls::*, v2_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -19,6 +20,7 @@ const TIMEOUT_MS: u32 = 100;
 pub struct F828mk2{
     req: FwReq,
     clk_ctls: ClkCtl,
+    clk_display_ctl: ClkDisplayTextCtl,
     opt_iface_ctl: OptIfaceCtl,
     phone_assign_ctl: PhoneAssignCtl,
     word_clk_ctl: WordClkCtl,
@@ -43,14 +45,14 @@ impl PhoneAssignCtlOperation<F828mk2Protocol> for PhoneAssignCtl {
 }
 
 #[derive(Default)]
-struct WordClkCtl(WordClkSpeedMode, Vec<ElemId>);
+struct WordClkCtl(WordClkState);
 
 impl WordClkCtlOperation<F828mk2Protocol> for WordClkCtl {
-    fn state(&self) -> &WordClkSpeedMode {
+    fn state(&self) -> &WordClkState {
         &self.0
     }
 
-    fn state_mut(&mut self) -> &mut WordClkSpeedMode {
+    fn state_mut(&mut self) -> &mut WordClkState {
         &mut self.0
     }
 }
@@ -60,6 +62,19 @@ struct ClkCtl;
 
 impl V2ClkCtlOperation<F828mk2Protocol> for ClkCtl {}
 
+#[derive(Default)]
+struct ClkDisplayTextCtl(ClkDisplayTextState);
+
+impl ClkDisplayTextCtlOperation<F828mk2Protocol> for ClkDisplayTextCtl {
+    fn state(&self) -> &ClkDisplayTextState {
+        &self.0
+    }
+
+    fn state_mut(&mut self) -> &mut ClkDisplayTextState {
+        &mut self.0
+    }
+}
+
 #[derive(Default)]
 struct OptIfaceCtl((usize, usize), Vec<ElemId>);
 
@@ -141,12 +156,12 @@ impl Traveler828mk2LineInputCtlOperation<F828mk2Protocol> for LineInputCtl {
 impl CtlModel<SndMotu> for F828mk2 {
     fn load(&mut self, unit: &mut SndMotu, card_cntr: &mut CardCntr) -> Result<(), Error> {
         self.clk_ctls.load(card_cntr)?;
+        self.clk_display_ctl.load(card_cntr)?;
         self.opt_iface_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.opt_iface_ctl.1.append(&mut elem_id_list))?;
         self.phone_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.phone_assign_ctl.1.append(&mut elem_id_list))?;
-        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
-            .map(|mut elem_id_list| self.word_clk_ctl.1.append(&mut elem_id_list))?;
+        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
         self.mixer_output_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|elem_id_list| self.mixer_output_ctl.1 = elem_id_list)?;
         self.mixer_return_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
@@ -168,6 +183,8 @@ impl CtlModel<SndMotu> for F828mk2 {
     ) -> Result<bool, Error> {
         if self.clk_ctls.read(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else if self.opt_iface_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.phone_assign_ctl.read(elem_id, elem_value)? {
@@ -198,6 +215,8 @@ impl CtlModel<SndMotu> for F828mk2 {
     ) -> Result<bool, Error> {
         if self.clk_ctls.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.write(elem_id, new, TIMEOUT_MS)? {
+            Ok(true)
         } else if self.opt_iface_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else if self.phone_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
@@ -220,9 +239,28 @@ impl CtlModel<SndMotu> for F828mk2 {
     }
 }
 
+impl MeasureModel<SndMotu> for F828mk2 {
+    fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.measured_elem_list);
+    }
+
+    fn measure_states(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.word_clk_ctl.measure(unit, &mut self.req, TIMEOUT_MS)
+    }
+
+    fn measure_elem(
+        &mut self,
+        _: &SndMotu,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue
+    ) -> Result<bool, Error> {
+        self.word_clk_ctl.read(elem_id, elem_value)
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for F828mk2 {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
-        elem_id_list.extend_from_slice(&self.word_clk_ctl.1);
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.notified_elem_list);
     }
 
     fn parse_notification(&mut self, unit: &mut SndMotu, msg: &u32) -> Result<(), Error> {
@@ -246,3 +284,41 @@ impl NotifyModel<SndMotu, u32> for F828mk2 {
         }
     }
 }
+
+impl ClkDisplayTextModel for F828mk2 {
+    fn take_clk_display_text_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        self.clk_display_ctl.take_request()
+    }
+
+    fn complete_clk_display_text_request(
+        &mut self,
+        request: &ClkDisplayTextRequest,
+        res: &Result<(), Error>,
+    ) -> bool {
+        self.clk_display_ctl.complete_request(request, res)
+    }
+}
+
+impl ReconcileModel for F828mk2 {}
+
+impl ChannelPortModel for F828mk2 {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            <F828mk2Protocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            <F828mk2Protocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for F828mk2 {
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        Some((
+            &self.mixer_output_ctl.0,
+            <F828mk2Protocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            &self.mixer_source_ctl.0,
+        ))
+    }
+}
@@ -12,6 +12,7 @@ use core::card_cntr::*;
 use motu_protocols::{version_1::*, *};
 
 use super::common_ctls::*;
+use super::level_meters_ctl::*;
 use super::v1_ctls::*;
 
 const TIMEOUT_MS: u32 = 100;
@@ -27,14 +28,14 @@ pub struct F896 {
 }
 
 #[derive(Default)]
-struct WordClkCtl(WordClkSpeedMode);
+struct WordClkCtl(WordClkState);
 
 impl WordClkCtlOperation<F896Protocol> for WordClkCtl {
-    fn state(&self) -> &WordClkSpeedMode {
+    fn state(&self) -> &WordClkState {
         &self.0
     }
 
-    fn state_mut(&mut self) -> &mut WordClkSpeedMode {
+    fn state_mut(&mut self) -> &mut WordClkState {
         &mut self.0
     }
 }
@@ -45,7 +46,7 @@ struct AesebuRateConvertCtl;
 impl AesebuRateConvertCtlOperation<F896Protocol> for AesebuRateConvertCtl {}
 
 #[derive(Default)]
-struct LevelMetersCtl(LevelMeterState);
+struct LevelMetersCtl(LevelMeterState, Vec<ElemId>);
 
 impl LevelMetersCtlOperation<F896Protocol> for LevelMetersCtl {
     fn state(&self) -> &LevelMeterState {
@@ -71,9 +72,10 @@ impl CtlModel<SndMotu> for F896 {
     fn load(&mut self, unit: &mut SndMotu, card_cntr: &mut CardCntr) -> Result<(), Error> {
         self.clk_ctls.load(card_cntr)?;
         self.monitor_input_ctl.load(card_cntr)?;
-        let _ = self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
+        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
         self.aesebu_rate_convert_ctl.load(card_cntr)?;
-        let _ = self.level_meters_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
+        self.level_meters_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
+            .map(|mut elem_id_list| self.level_meters_ctl.1.append(&mut elem_id_list))?;
         Ok(())
     }
 
@@ -103,10 +105,7 @@ impl CtlModel<SndMotu> for F896 {
             TIMEOUT_MS,
         )? {
             Ok(true)
-        } else if self
-            .level_meters_ctl
-            .read(unit, &mut self.req,  elem_id, elem_value, TIMEOUT_MS)?
-        {
+        } else if self.level_meters_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else {
             Ok(false)
@@ -154,6 +153,33 @@ impl CtlModel<SndMotu> for F896 {
     }
 }
 
+impl MeasureModel<SndMotu> for F896 {
+    fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.level_meters_ctl.1);
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.measured_elem_list);
+    }
+
+    fn measure_states(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.level_meters_ctl.cache(unit, &mut self.req, TIMEOUT_MS)?;
+        self.word_clk_ctl.measure(unit, &mut self.req, TIMEOUT_MS)
+    }
+
+    fn measure_elem(
+        &mut self,
+        _: &SndMotu,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue
+    ) -> Result<bool, Error> {
+        if self.level_meters_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.word_clk_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for F896 {
     fn get_notified_elem_list(&mut self, _: &mut Vec<ElemId>) {
     }
@@ -11,7 +11,8 @@ use core::card_cntr::*;
 
 use motu_protocols::{register_dsp::*, version_2::*, *};
 
-use super::{common_ctls::*, register_dsp_ctls::*, v2_ctls::*};
+use super::{common_ctls::*, level_meters_ctl::*, register_dsp_ctls::*, v2_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -30,14 +31,14 @@ pub struct F896hd {
 }
 
 #[derive(Default)]
-struct WordClkCtl(WordClkSpeedMode, Vec<ElemId>);
+struct WordClkCtl(WordClkState);
 
 impl WordClkCtlOperation<F896hdProtocol> for WordClkCtl {
-    fn state(&self) -> &WordClkSpeedMode {
+    fn state(&self) -> &WordClkState {
         &self.0
     }
 
-    fn state_mut(&mut self) -> &mut WordClkSpeedMode {
+    fn state_mut(&mut self) -> &mut WordClkState {
         &mut self.0
     }
 }
@@ -135,8 +136,7 @@ impl CtlModel<SndMotu> for F896hd {
         self.clk_ctls.load(card_cntr)?;
         self.opt_iface_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.opt_iface_ctl.1.append(&mut elem_id_list))?;
-        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
-            .map(|mut elem_id_list| self.word_clk_ctl.1.append(&mut elem_id_list))?;
+        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
         self.aesebu_rate_convert_ctl.load(card_cntr)?;
         self.level_meters_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.level_meters_ctl.1.append(&mut elem_id_list))?;
@@ -174,10 +174,7 @@ impl CtlModel<SndMotu> for F896hd {
             TIMEOUT_MS,
         )? {
             Ok(true)
-        } else if self
-            .level_meters_ctl
-            .read(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)?
-        {
+        } else if self.level_meters_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.mixer_output_ctl.read(elem_id, elem_value)? {
             Ok(true)
@@ -241,6 +238,33 @@ impl CtlModel<SndMotu> for F896hd {
     }
 }
 
+impl MeasureModel<SndMotu> for F896hd {
+    fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.level_meters_ctl.1);
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.measured_elem_list);
+    }
+
+    fn measure_states(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.level_meters_ctl.cache(unit, &mut self.req, TIMEOUT_MS)?;
+        self.word_clk_ctl.measure(unit, &mut self.req, TIMEOUT_MS)
+    }
+
+    fn measure_elem(
+        &mut self,
+        _: &SndMotu,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue
+    ) -> Result<bool, Error> {
+        if self.level_meters_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else if self.word_clk_ctl.read(elem_id, elem_value)? {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for F896hd {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.level_meters_ctl.1);
@@ -260,10 +284,32 @@ impl NotifyModel<SndMotu, u32> for F896hd {
         elem_id: &ElemId,
         elem_value: &mut ElemValue
     ) -> Result<bool, Error> {
-        if self.level_meters_ctl.refer(elem_id, elem_value)? {
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.level_meters_ctl.read(elem_id, elem_value)
+    }
+}
+
+impl ReconcileModel for F896hd {}
+
+impl ClkDisplayTextModel for F896hd {}
+
+impl ChannelPortModel for F896hd {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            <F896hdProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            <F896hdProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for F896hd {
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        Some((
+            &self.mixer_output_ctl.0,
+            <F896hdProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            &self.mixer_source_ctl.0,
+        ))
     }
 }
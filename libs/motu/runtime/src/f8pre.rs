@@ -9,9 +9,10 @@ use alsactl::{ElemId, ElemValue};
 
 use core::card_cntr::*;
 
-use motu_protocols::{register_dsp::*, version_2::*};
+use motu_protocols::{register_dsp::*, version_2::*, TargetPort};
 
 use super::{common_ctls::*, register_dsp_ctls::*, v2_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -180,6 +181,9 @@ impl CtlModel<SndMotu> for F8pre {
     }
 }
 
+// No element is measured.
+impl MeasureModel<SndMotu> for F8pre {}
+
 impl NotifyModel<SndMotu, u32> for F8pre {
     fn get_notified_elem_list(&mut self, _: &mut Vec<ElemId>) {}
 
@@ -196,3 +200,29 @@ impl NotifyModel<SndMotu, u32> for F8pre {
         Ok(false)
     }
 }
+
+impl ReconcileModel for F8pre {}
+
+impl ClkDisplayTextModel for F8pre {}
+
+impl ChannelPortModel for F8pre {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            <F8preProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            <F8preProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for F8pre {
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        Some((
+            &self.mixer_output_ctl.0,
+            <F8preProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            &self.mixer_source_ctl.0,
+        ))
+    }
+}
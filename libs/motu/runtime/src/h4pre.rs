@@ -9,9 +9,10 @@ use alsactl::{ElemId, ElemValue};
 
 use core::card_cntr::*;
 
-use motu_protocols::{register_dsp::*, version_3::*};
+use motu_protocols::{register_dsp::*, version_3::*, TargetPort};
 
 use super::{common_ctls::*, register_dsp_ctls::*, v3_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -180,6 +181,9 @@ impl CtlModel<SndMotu> for H4pre {
     }
 }
 
+// No element is measured.
+impl MeasureModel<SndMotu> for H4pre {}
+
 impl NotifyModel<SndMotu, u32> for H4pre {
     fn get_notified_elem_list(&mut self, _: &mut Vec<ElemId>) {}
 
@@ -196,3 +200,18 @@ impl NotifyModel<SndMotu, u32> for H4pre {
         Ok(false)
     }
 }
+
+impl ReconcileModel for H4pre {}
+
+impl ClkDisplayTextModel for H4pre {}
+
+impl ChannelPortModel for H4pre {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            &<H4preProtocol as RegisterDspMixerStereoSourceOperation>::MIXER_SOURCES,
+            <H4preProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for H4pre {}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+use glib::Error;
+
+use hinawa::FwReq;
+use hinawa::{SndMotu, SndUnitExt};
+
+use alsactl::{ElemId, ElemIfaceType, ElemValue};
+
+use core::card_cntr::CardCntr;
+use core::elem_value_accessor::ElemValueAccessor;
+
+use motu_protocols::*;
+
+fn level_meters_hold_time_mode_to_string(mode: &LevelMetersHoldTimeMode) -> &'static str {
+    match mode {
+        LevelMetersHoldTimeMode::Off => "off",
+        LevelMetersHoldTimeMode::Sec2 => "2sec",
+        LevelMetersHoldTimeMode::Sec4 => "4sec",
+        LevelMetersHoldTimeMode::Sec10 => "10sec",
+        LevelMetersHoldTimeMode::Sec60 => "1min",
+        LevelMetersHoldTimeMode::Sec300 => "5min",
+        LevelMetersHoldTimeMode::Sec480 => "8min",
+        LevelMetersHoldTimeMode::Infinite => "infinite",
+    }
+}
+
+fn level_meters_aesebu_mode_to_string(mode: &LevelMetersAesebuMode) -> &'static str {
+    match mode {
+        LevelMetersAesebuMode::Output => "output",
+        LevelMetersAesebuMode::Input => "input",
+    }
+}
+
+fn level_meters_programmable_mode_to_string(mode: &LevelMetersProgrammableMode) -> &'static str {
+    match mode {
+        LevelMetersProgrammableMode::AnalogOutput => "analog-output",
+        LevelMetersProgrammableMode::AdatInput => "ADAT-input",
+        LevelMetersProgrammableMode::AdatOutput => "ADAT-output",
+    }
+}
+
+const PEAK_HOLD_TIME_MODE_NAME: &str = "meter-peak-hold-time";
+const CLIP_HOLD_TIME_MODE_NAME: &str = "meter-clip-hold-time";
+const AESEBU_MODE_NAME: &str = "AES/EBU-meter";
+const PROGRAMMABLE_MODE_NAME: &str = "programmable-meter";
+
+/// The cache of index for the modes of level meters. The modes can be changed by the buttons in
+/// front panel as well.
+#[derive(Default)]
+pub struct LevelMeterState {
+    peak_hold_time: usize,
+    clip_hold_time: usize,
+    aesebu: usize,
+    programmable: usize,
+}
+
+pub trait LevelMetersCtlOperation<T: LevelMetersOperation> {
+    fn state(&self) -> &LevelMeterState;
+    fn state_mut(&mut self) -> &mut LevelMeterState;
+
+    fn load(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32
+    ) -> Result<Vec<ElemId>, Error> {
+        self.cache(unit, req, timeout_ms)?;
+
+        let mut elem_id_list = Vec::new();
+
+        let labels: Vec<&str> = T::LEVEL_METERS_HOLD_TIME_MODES
+            .iter()
+            .map(|l| level_meters_hold_time_mode_to_string(&l))
+            .collect();
+        let elem_id =
+            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PEAK_HOLD_TIME_MODE_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut list| elem_id_list.append(&mut list))?;
+
+        let elem_id =
+            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, CLIP_HOLD_TIME_MODE_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut list| elem_id_list.append(&mut list))?;
+
+        let labels: Vec<&str> = T::LEVEL_METERS_AESEBU_MODES
+            .iter()
+            .map(|l| level_meters_aesebu_mode_to_string(&l))
+            .collect();
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, AESEBU_MODE_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut list| elem_id_list.append(&mut list))?;
+
+        let labels: Vec<&str> = T::LEVEL_METERS_PROGRAMMABLE_MODES
+            .iter()
+            .map(|l| level_meters_programmable_mode_to_string(&l))
+            .collect();
+        let elem_id =
+            ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PROGRAMMABLE_MODE_NAME, 0);
+        card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, true)
+            .map(|mut list| elem_id_list.append(&mut list))?;
+
+        Ok(elem_id_list)
+    }
+
+    // The elements are written at timer event as well as notification, since the change of hold
+    // time is not notified.
+    fn cache(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32
+    ) -> Result<(), Error> {
+        let mut node = unit.get_node();
+
+        let peak_hold_time = T::get_level_meters_peak_hold_time_mode(req, &mut node, timeout_ms)?;
+        let clip_hold_time = T::get_level_meters_clip_hold_time_mode(req, &mut node, timeout_ms)?;
+        let aesebu = T::get_level_meters_aesebu_mode(req, &mut node, timeout_ms)?;
+        let programmable = T::get_level_meters_programmable_mode(req, &mut node, timeout_ms)?;
+
+        *self.state_mut() = LevelMeterState {
+            peak_hold_time,
+            clip_hold_time,
+            aesebu,
+            programmable,
+        };
+
+        Ok(())
+    }
+
+    fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
+        let state = self.state();
+        let idx = match elem_id.get_name().as_str() {
+            PEAK_HOLD_TIME_MODE_NAME => state.peak_hold_time,
+            CLIP_HOLD_TIME_MODE_NAME => state.clip_hold_time,
+            AESEBU_MODE_NAME => state.aesebu,
+            PROGRAMMABLE_MODE_NAME => state.programmable,
+            _ => return Ok(false),
+        };
+        ElemValueAccessor::<u32>::set_val(elem_value, || Ok(idx as u32))
+            .map(|_| true)
+    }
+
+    fn write(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        elem_id: &ElemId,
+        elem_value: &ElemValue,
+        timeout_ms: u32,
+    ) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            PEAK_HOLD_TIME_MODE_NAME => {
+                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    T::set_level_meters_peak_hold_time_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
+                        .map(|_| self.state_mut().peak_hold_time = val as usize)
+                })
+                .map(|_| true)
+            }
+            CLIP_HOLD_TIME_MODE_NAME => {
+                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    T::set_level_meters_clip_hold_time_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
+                        .map(|_| self.state_mut().clip_hold_time = val as usize)
+                })
+                .map(|_| true)
+            }
+            AESEBU_MODE_NAME => {
+                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    T::set_level_meters_aesebu_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
+                        .map(|_| self.state_mut().aesebu = val as usize)
+                })
+                .map(|_| true)
+            }
+            PROGRAMMABLE_MODE_NAME => {
+                ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    T::set_level_meters_programmable_mode(req, &mut unit.get_node(), val as usize, timeout_ms)
+                        .map(|_| self.state_mut().programmable = val as usize)
+                })
+                .map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
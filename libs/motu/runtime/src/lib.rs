@@ -21,18 +21,19 @@ mod ultralite_mk3;
 mod ultralite_mk3_hybrid;
 
 mod common_ctls;
+mod level_meters_ctl;
 mod v1_ctls;
 mod v2_ctls;
 mod v3_ctls;
 mod register_dsp_ctls;
 mod command_dsp_ctls;
 
-use glib::{Error, FileError};
+use glib::Error;
 use std::convert::TryFrom;
 
 use hinawa::{FwNodeExtManual, SndUnitExt, SndMotuExt};
 
-use core::RuntimeOperation;
+use core::{config_rom::ConfigRomError, RuntimeOperation};
 
 use ieee1212_config_rom::*;
 use motu_protocols::{config_rom::*, *};
@@ -63,15 +64,19 @@ impl RuntimeOperation<u32> for MotuRuntime {
         let node = unit.get_node();
         let data = node.get_config_rom()?;
         let unit_data = ConfigRom::try_from(data)
+            .map_err(MotuConfigRomError::from)
+            .and_then(|config_rom| config_rom.identify_model())
             .map_err(|e| {
-                let msg = format!("Malformed configuration ROM detected: {}", e);
-                Error::new(FileError::Nxio, &msg)
-            })
-            .and_then(|config_rom| {
-                config_rom.get_unit_data()
-                    .ok_or_else(|| {
-                        Error::new(FileError::Nxio, "Unexpected content of configuration ROM.")
-                    })
+                let (code, msg) = match e {
+                    MotuConfigRomError::UnreadableRom(_) => (ConfigRomError::UnreadableRom, e.to_string()),
+                    MotuConfigRomError::VendorMismatch{..} => (ConfigRomError::VendorMismatch, e.to_string()),
+                    MotuConfigRomError::UnknownModel{model_id} => {
+                        let msg = format!("device is a MOTU model 0x{:06x}, not supported", model_id);
+                        (ConfigRomError::UnknownModel, msg)
+                    }
+                    MotuConfigRomError::MalformedEntry{..} => (ConfigRomError::MalformedEntry, e.to_string()),
+                };
+                Error::new(code, &msg)
             })?;
 
         let version = unit_data.version;
@@ -92,7 +97,7 @@ impl RuntimeOperation<u32> for MotuRuntime {
             0x000045 => Ok(Self::H4pre(H4preRuntime::new(unit, card_id, version)?)),
             _ => {
                 let label = format!("Unsupported model ID: 0x{:06x}", unit_data.model_id);
-                Err(Error::new(FileError::Noent, &label))
+                Err(Error::new(ConfigRomError::UnknownModel, &label))
             }
         }
     }
@@ -77,6 +77,10 @@ pub trait RegisterDspMixerOutputCtlOperation<T: RegisterDspMixerOutputOperation>
         Ok(notified_elem_id_list)
     }
 
+    fn cache(&mut self, unit: &mut SndMotu, req: &mut FwReq, timeout_ms: u32) -> Result<(), Error> {
+        T::read_mixer_output_state(req, &mut unit.get_node(), self.state_mut(), timeout_ms)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MIXER_OUTPUT_VOLUME_NAME => {
@@ -199,6 +203,10 @@ pub trait RegisterDspMixerReturnCtlOperation<T: RegisterDspMixerReturnOperation>
         Ok(notified_elem_id_list)
     }
 
+    fn cache(&mut self, unit: &mut SndMotu, req: &mut FwReq, timeout_ms: u32) -> Result<(), Error> {
+        T::read_mixer_return_state(req, &mut unit.get_node(), self.state_mut(), timeout_ms)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MIXER_RETURN_SOURCE_NAME => {
@@ -482,6 +490,10 @@ pub trait RegisterDspMixerStereoSourceCtlOperation<T: RegisterDspMixerStereoSour
         Ok(notified_elem_id_list)
     }
 
+    fn cache(&mut self, unit: &mut SndMotu, req: &mut FwReq, timeout_ms: u32) -> Result<(), Error> {
+        T::read_mixer_stereo_source_state(req, &mut unit.get_node(), self.state_mut(), timeout_ms)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MIXER_SOURCE_PAIRED_NAME => {
@@ -579,6 +591,10 @@ pub trait RegisterDspMixerStereoSourceCtlOperation<T: RegisterDspMixerStereoSour
 const MASTER_OUTPUT_VOLUME_NAME: &str = "master-output-volume";
 const PHONE_VOLUME_NAME: &str = "headphone-volume";
 
+/// The pair of elements for link of phone bus to main output.
+pub const REGISTER_DSP_PHONE_FOLLOW_MAIN_PAIRS: &[(&str, &str)] =
+    &[(MASTER_OUTPUT_VOLUME_NAME, PHONE_VOLUME_NAME)];
+
 pub trait RegisterDspOutputCtlOperation<T: RegisterDspOutputOperation> {
     fn state(&self) -> &RegisterDspOutputState;
     fn state_mut(&mut self) -> &mut RegisterDspOutputState;
@@ -630,6 +646,10 @@ pub trait RegisterDspOutputCtlOperation<T: RegisterDspOutputOperation> {
         Ok(notified_elem_id_list)
     }
 
+    fn cache(&mut self, unit: &mut SndMotu, req: &mut FwReq, timeout_ms: u32) -> Result<(), Error> {
+        T::read_output_state(req, &mut unit.get_node(), self.state_mut(), timeout_ms)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             MASTER_OUTPUT_VOLUME_NAME => {
@@ -767,13 +787,24 @@ pub trait Traveler828mk2LineInputCtlOperation<T: Traveler828mk2LineInputOperatio
                             })
                             .map(|&l| level.push(l))
                     })?;
-                T::write_line_input_level(
-                    req,
-                    &mut unit.get_node(),
-                    &level,
-                    self.state_mut(),
-                    timeout_ms
-                )
+                let changed: Vec<(usize, NominalSignalLevel)> = level
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, l)| !self.state().level[*i].eq(l))
+                    .map(|(i, &l)| (i, l))
+                    .collect();
+                changed
+                    .iter()
+                    .try_for_each(|&(ch, l)| {
+                        T::write_line_input_channel_level(
+                            req,
+                            &mut unit.get_node(),
+                            ch,
+                            l,
+                            self.state_mut(),
+                            timeout_ms
+                        )
+                    })
                     .map(|_| true)
             }
             INPUT_BOOST_NAME => {
@@ -850,6 +881,10 @@ pub trait Audioexpress4preInputCtlOperation<T: Audioexpress4preInputOperation> {
         Ok(notified_elem_id_list)
     }
 
+    fn cache(&mut self, unit: &mut SndMotu, req: &mut FwReq, timeout_ms: u32) -> Result<(), Error> {
+        T::read_input_state(req, &mut unit.get_node(), self.state_mut(), timeout_ms)
+    }
+
     fn read(&mut self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             INPUT_GAIN_NAME => {
@@ -1,20 +1,31 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2021 Takashi Sakamoto
 use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
 use nix::sys::signal::Signal;
 
-use glib::Error;
+use glib::{Error, FileError};
 use glib::source;
 
-use hinawa::FwNodeExt;
+use hinawa::{FwNodeExt, FwNodeExtManual};
 use hinawa::{SndMotu, SndMotuExt, SndUnitExt};
 
 use alsactl::{CardExt, ElemId, ElemEventMask};
 
-use core::{card_cntr::*, dispatcher::*};
+use core::{card_cntr::*, channel_name::*, dispatcher::*, event_queue::*, version::*, worker::*};
+use core::config::*;
+use core::log::*;
+use core::{log_info, log_warn};
+
+use motu_protocols::{command_dsp::CommandDspPresetOperation, register_dsp::*, version_3::*};
+use motu_protocols::{MotuProtocolError, TargetPort};
 
 use crate::{audioexpress::*, f828mk2::*, f896hd::*, f8pre::*, h4pre::*, traveler::*, ultralite::*};
+use crate::{common_ctls::PhoneFollowMainCtl, register_dsp_ctls::REGISTER_DSP_PHONE_FOLLOW_MAIN_PAIRS};
+use crate::common_ctls::{clk_display_text_elem_id, ClkDisplayTextModel, ClkDisplayTextRequest};
+use crate::target_port_to_str;
 
 pub type F828mk2Runtime = RegisterDspRuntime<F828mk2>;
 pub type F896hdRuntime = RegisterDspRuntime<F896hd>;
@@ -25,53 +36,202 @@ pub type UltraliteRuntime = RegisterDspRuntime<UltraLite>;
 pub type AudioExpressRuntime = RegisterDspRuntime<AudioExpress>;
 pub type H4preRuntime = RegisterDspRuntime<H4pre>;
 
+/// The trait for the model to reconcile the cache with the registers, when the write operation
+/// for the element is not verified. The device can apply the write later.
+pub trait ReconcileModel {
+    /// Cache the state of registers for the element again. Return true when the element is
+    /// handled.
+    fn reconcile(&mut self, _: &mut SndMotu, _: &ElemId) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// The trait for the model with ports of input and output channels, to which names are given.
+pub trait ChannelPortModel {
+    /// The ports for input and output channels. The disabled port is not given any name.
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]);
+}
+
+/// The trait for the model to export the state of mixer into the preset file of command DSP
+/// models.
+pub trait MixerExportModel {
+    /// The state of mixer outputs and monaural sources with the ports of sources. The model
+    /// without monaural sources has nothing to export.
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        None
+    }
+}
+
+// The names of command DSP models for the target of export.
+const EXPORT_TARGETS: &[&str] = &["828mk3", "828mk3-hybrid", "ultralite-mk3", "ultralite-mk3-hybrid"];
+
+fn build_export_preset(
+    target: &str,
+    output: &RegisterDspMixerOutputState,
+    source_ports: &[TargetPort],
+    sources: &RegisterDspMixerMonauralSourceState,
+) -> Result<(Vec<u8>, usize), String> {
+    match target {
+        "828mk3" => {
+            Ok(F828mk3Protocol::build_preset_from_register_dsp_mixer(output, source_ports, sources))
+        }
+        "828mk3-hybrid" => Ok(F828mk3HybridProtocol::build_preset_from_register_dsp_mixer(
+            output,
+            source_ports,
+            sources,
+        )),
+        "ultralite-mk3" => Ok(UltraliteMk3Protocol::build_preset_from_register_dsp_mixer(
+            output,
+            source_ports,
+            sources,
+        )),
+        "ultralite-mk3-hybrid" => Ok(
+            UltraliteMk3HybridProtocol::build_preset_from_register_dsp_mixer(
+                output,
+                source_ports,
+                sources,
+            ),
+        ),
+        _ => Err(format!(
+            "Unknown target of {}: {}, expected one of {}",
+            REGISTER_DSP_EXPORT_TARGET_KEY,
+            target,
+            EXPORT_TARGETS.join(", ")
+        )),
+    }
+}
+
 pub struct RegisterDspRuntime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu>
+        + MeasureModel<SndMotu>
+        + NotifyModel<SndMotu, u32>
+        + ReconcileModel
+        + MixerExportModel
+        + ClkDisplayTextModel
+        + ChannelPortModel
+        + Default,
 {
     unit: SndMotu,
     model: T,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<Dispatcher>,
+    timer: Option<Dispatcher>,
     #[allow(dead_code)]
     version: u32,
     notified_elem_id_list: Vec<ElemId>,
+    measured_elem_id_list: Vec<ElemId>,
+    phone_follow_main: PhoneFollowMainCtl,
+    channel_names: ChannelNameCtl,
+    reconciler: Option<Worker<ElemId, ()>>,
+    clk_display_writer: Option<Worker<ElemId, (ClkDisplayTextRequest, Result<(), Error>)>>,
 }
 
 impl<T>  Drop for RegisterDspRuntime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu>
+        + MeasureModel<SndMotu>
+        + NotifyModel<SndMotu, u32>
+        + ReconcileModel
+        + MixerExportModel
+        + ClkDisplayTextModel
+        + ChannelPortModel
+        + Default,
 {
     fn drop(&mut self) {
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.timer.iter_mut()
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timer is finished at first, then the rest in reverse
+        // order of launch, thus the dispatcher for signal handler is finished before the
+        // dispatcher for node.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.dispatchers.drain(..).rev());
+        join_dispatchers(dispatchers);
     }
 }
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
     Notify(u32),
+    Timer,
+    Reconcile(ElemId),
+    ClkDisplayText((ElemId, ClkDisplayTextRequest, Result<(), Error>)),
+}
+
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => Some(elem_id),
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
 }
 
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
 const SYSTEM_DISPATCHER_NAME: &str = "system event dispatcher";
+const TIMER_DISPATCHER_NAME: &str = "interval timer dispatcher";
+
+const TIMER_INTERVAL: Duration = Duration::from_millis(500);
+
+const RECONCILER_NAME: &str = "reconciler";
+const CLK_DISPLAY_WRITER_NAME: &str = "clock display writer";
+
+// The delay to read registers again after the write operation which is not verified.
+const RECONCILE_DELAY: Duration = Duration::from_millis(200);
+
+impl<T> ConfigReloadOperation for RegisterDspRuntime<T>
+where
+    T: CtlModel<SndMotu>
+        + MeasureModel<SndMotu>
+        + NotifyModel<SndMotu, u32>
+        + ReconcileModel
+        + MixerExportModel
+        + ClkDisplayTextModel
+        + ChannelPortModel
+        + Default,
+{
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            join_dispatchers(self.timer.take());
+            self.launch_interval_timer_dispatcher()?;
+        }
+        Ok(())
+    }
+
+    fn apply_runtime_options(&mut self) {
+        self.export_mixer();
+    }
+}
 
 impl<T> RegisterDspRuntime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu>
+        + MeasureModel<SndMotu>
+        + NotifyModel<SndMotu, u32>
+        + ReconcileModel
+        + MixerExportModel
+        + ClkDisplayTextModel
+        + ChannelPortModel
+        + Default,
 {
     pub fn new(unit: SndMotu, card_id: u32, version: u32) -> Result<Self, Error> {
         let card_cntr = CardCntr::new();
@@ -79,6 +239,7 @@ where
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(Self{
             unit,
@@ -87,8 +248,14 @@ where
             rx,
             tx,
             dispatchers: Default::default(),
+            timer: Default::default(),
             version,
             notified_elem_id_list: Default::default(),
+            measured_elem_id_list: Default::default(),
+            phone_follow_main: Default::default(),
+            channel_names: Default::default(),
+            reconciler: None,
+            clk_display_writer: None,
         })
     }
 
@@ -98,6 +265,42 @@ where
 
         self.model.load(&mut self.unit, &mut self.card_cntr)?;
         self.model.get_notified_elem_list(&mut self.notified_elem_id_list);
+        self.model.get_measure_elem_list(&mut self.measured_elem_id_list);
+
+        self.phone_follow_main.load(&mut self.card_cntr, REGISTER_DSP_PHONE_FOLLOW_MAIN_PAIRS)?;
+
+        if self.measured_elem_id_list.len() > 0 {
+            self.launch_interval_timer_dispatcher()?;
+        }
+
+        self.launch_reconciler()?;
+        self.launch_clk_display_writer()?;
+
+        self.export_mixer();
+
+        let raw = self.unit.get_node().get_config_rom()?;
+        let guid = guid_from_config_rom(&raw)
+            .ok_or_else(|| Error::new(FileError::Nxio, "Unexpected format of configuration ROM"))?;
+        let (input_ports, output_ports) = self.model.channel_ports();
+        let port_names = |ports: &[TargetPort]| {
+            ports
+                .iter()
+                .filter(|&port| *port != TargetPort::Disabled)
+                .map(|port| target_port_to_str(port).to_string())
+                .collect::<Vec<String>>()
+        };
+        self.channel_names.load(
+            &mut self.card_cntr,
+            guid,
+            port_names(input_ports),
+            port_names(output_ports),
+        )?;
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            motu_protocols::CRATE_VERSION,
+        )?;
 
         Ok(())
     }
@@ -111,16 +314,37 @@ where
 
             match ev {
                 Event::Shutdown | Event::Disconnected => break,
+                Event::Reload => self.reload(),
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Elem((elem_id, events)) => {
-                    let _ = self.card_cntr.dispatch_elem_event(
-                        &mut self.unit,
+                    let name = elem_id.get_name();
+                    if name == INPUT_NAMES_NAME || name == OUTPUT_NAMES_NAME {
+                        if let Err(e) =
+                            self.channel_names.handle_elem_event(&mut self.card_cntr, &elem_id)
+                        {
+                            log_warn!(LOG_RUNTIME, "Fail to update names of channels: {}", e);
+                        }
+                        continue;
+                    }
+
+                    let unit = &mut self.unit;
+                    let model = &mut self.model;
+                    let res = self.phone_follow_main.dispatch_elem_event(
+                        &mut self.card_cntr,
                         &elem_id,
                         &events,
-                        &mut self.model,
+                        |card_cntr| card_cntr.dispatch_elem_event(unit, &elem_id, &events, model),
                     );
+                    if let Err(err) = res {
+                        log_warn!(LOG_RUNTIME, "{}", err);
+                        let kind = err.kind::<MotuProtocolError>();
+                        if kind == Some(MotuProtocolError::UnverifiedWrite) {
+                            self.schedule_reconcile(elem_id);
+                        }
+                    }
+                    self.submit_clk_display_text();
                 }
                 Event::Notify(msg) => {
                     let _ = self.card_cntr.dispatch_notification(
@@ -130,11 +354,105 @@ where
                         &mut self.model,
                     );
                 }
+                Event::Timer => {
+                    let _ = self.card_cntr.measure_elems(
+                        &mut self.unit,
+                        &self.measured_elem_id_list,
+                        &mut self.model,
+                    );
+                }
+                Event::Reconcile(elem_id) => {
+                    match self.model.reconcile(&mut self.unit, &elem_id) {
+                        // The cached value is written to the element when it differs.
+                        Ok(true) => {
+                            let _ = self.card_cntr.dispatch_elem_event(
+                                &mut self.unit,
+                                &elem_id,
+                                &ElemEventMask::ADD,
+                                &mut self.model,
+                            );
+                        }
+                        Ok(false) => (),
+                        Err(err) => {
+                            log_warn!(
+                                LOG_RUNTIME,
+                                "Fail to reconcile {}:{}: {}",
+                                elem_id.get_name(),
+                                elem_id.get_index(),
+                                err
+                            );
+                        }
+                    }
+                }
+                Event::ClkDisplayText((elem_id, request, res)) => {
+                    if let Err(err) = &res {
+                        log_warn!(LOG_RUNTIME, "Fail to update text in clock display: {}", err);
+                    }
+                    // The element is notified when the text is restored.
+                    if self.model.complete_clk_display_text_request(&request, &res) {
+                        let _ = self.card_cntr.dispatch_elem_event(
+                            &mut self.unit,
+                            &elem_id,
+                            &ElemEventMask::ADD,
+                            &mut self.model,
+                        );
+                    }
+                }
             }
         }
         Ok(())
     }
 
+    // The state of mixer is converted and written to the preset file of command DSP models, when
+    // both of the target model and the name of file are configured.
+    fn export_mixer(&self) {
+        let config = current_config();
+        let (target, name) = match (
+            config.register_dsp_export_target,
+            config.register_dsp_export_file,
+        ) {
+            (None, None) => return,
+            (Some(target), Some(name)) => (target, name),
+            _ => {
+                log_warn!(
+                    LOG_RUNTIME,
+                    "Both of {} and {} are required to export mixer",
+                    REGISTER_DSP_EXPORT_TARGET_KEY,
+                    REGISTER_DSP_EXPORT_FILE_KEY
+                );
+                return;
+            }
+        };
+
+        let res = self
+            .model
+            .mixer_export_state()
+            .ok_or_else(|| "The model has no monaural source of mixer to export".to_string())
+            .and_then(|(output, source_ports, sources)| {
+                build_export_preset(&target, output, source_ports, sources)
+            })
+            .and_then(|(raw, dropped)| {
+                let path = preset_path(&name)?;
+                std::fs::write(&path, &raw)
+                    .map_err(|e| format!("Fail to write {}: {}", path.display(), e))
+                    .map(|_| (path, dropped))
+            });
+        match res {
+            Ok((path, dropped)) => {
+                log_info!(LOG_RUNTIME, "Mixer is exported to {} for {}", path.display(), target);
+                if dropped > 0 {
+                    log_warn!(
+                        LOG_RUNTIME,
+                        "{} source entries are dropped since unavailable in {}",
+                        dropped,
+                        target
+                    );
+                }
+            }
+            Err(e) => log_warn!(LOG_RUNTIME, "Fail to export mixer: {}", e),
+        }
+    }
+
     fn launch_node_event_dispatcher(&mut self) -> Result<(), Error> {
         let name = NODE_DISPATCHER_NAME.to_string();
         let mut dispatcher = Dispatcher::run(name)?;
@@ -174,6 +492,11 @@ where
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -184,4 +507,67 @@ where
 
         Ok(())
     }
+
+    fn launch_reconciler(&mut self) -> Result<(), Error> {
+        let tx = self.tx.clone();
+        let worker = Worker::run(RECONCILER_NAME, move |elem_id, _| {
+            // Never block the worker thread at shutdown.
+            let _ = tx.try_send(Event::Reconcile(elem_id));
+        })?;
+        self.reconciler = Some(worker);
+
+        Ok(())
+    }
+
+    // The request for the same element is merged while it is queued.
+    fn schedule_reconcile(&mut self, elem_id: ElemId) {
+        if let Some(worker) = &self.reconciler {
+            log_info!(
+                LOG_RUNTIME,
+                "Schedule to reconcile {}:{}",
+                elem_id.get_name(),
+                elem_id.get_index()
+            );
+            worker.submit(elem_id, || thread::sleep(RECONCILE_DELAY));
+        }
+    }
+
+    fn launch_clk_display_writer(&mut self) -> Result<(), Error> {
+        let tx = self.tx.clone();
+        let worker = Worker::run(CLK_DISPLAY_WRITER_NAME, move |elem_id, (request, res)| {
+            // Never block the worker thread at shutdown.
+            let _ = tx.try_send(Event::ClkDisplayText((elem_id, request, res)));
+        })?;
+        self.clk_display_writer = Some(worker);
+
+        Ok(())
+    }
+
+    // The request queued in the worker is superseded by the new one.
+    fn submit_clk_display_text(&mut self) {
+        if let Some(request) = self.model.take_clk_display_text_request() {
+            if let Some(worker) = &self.clk_display_writer {
+                let mut node = self.unit.get_node();
+                worker.submit(clk_display_text_elem_id(), move || {
+                    let res = request.execute(&mut node);
+                    (request, res)
+                });
+            }
+        }
+    }
+
+    fn launch_interval_timer_dispatcher(&mut self) -> Result<(), Error> {
+        let name = TIMER_DISPATCHER_NAME.to_string();
+        let mut dispatcher = Dispatcher::run(name)?;
+
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(metering_interval(TIMER_INTERVAL), move || {
+            let _ = tx.send(Event::Timer);
+            source::Continue(true)
+        });
+
+        self.timer = Some(dispatcher);
+
+        Ok(())
+    }
 }
@@ -14,6 +14,7 @@ use core::card_cntr::*;
 use motu_protocols::{register_dsp::*, version_2::*, *};
 
 use super::{common_ctls::*, register_dsp_ctls::*, v2_ctls::*};
+use super::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -21,6 +22,7 @@ const TIMEOUT_MS: u32 = 100;
 pub struct Traveler {
     req: FwReq,
     clk_ctls: ClkCtl,
+    clk_display_ctl: ClkDisplayTextCtl,
     opt_iface_ctl: OptIfaceCtl,
     phone_assign_ctl: PhoneAssignCtl,
     word_clk_ctl: WordClkCtl,
@@ -46,14 +48,14 @@ impl PhoneAssignCtlOperation<TravelerProtocol> for PhoneAssignCtl {
 }
 
 #[derive(Default)]
-struct WordClkCtl(WordClkSpeedMode, Vec<ElemId>);
+struct WordClkCtl(WordClkState);
 
 impl WordClkCtlOperation<TravelerProtocol> for WordClkCtl {
-    fn state(&self) -> &WordClkSpeedMode {
+    fn state(&self) -> &WordClkState {
         &self.0
     }
 
-    fn state_mut(&mut self) -> &mut WordClkSpeedMode {
+    fn state_mut(&mut self) -> &mut WordClkState {
         &mut self.0
     }
 }
@@ -63,6 +65,19 @@ struct ClkCtl;
 
 impl V2ClkCtlOperation<TravelerProtocol> for ClkCtl {}
 
+#[derive(Default)]
+struct ClkDisplayTextCtl(ClkDisplayTextState);
+
+impl ClkDisplayTextCtlOperation<TravelerProtocol> for ClkDisplayTextCtl {
+    fn state(&self) -> &ClkDisplayTextState {
+        &self.0
+    }
+
+    fn state_mut(&mut self) -> &mut ClkDisplayTextState {
+        &mut self.0
+    }
+}
+
 #[derive(Default)]
 struct OptIfaceCtl((usize, usize), Vec<ElemId>);
 
@@ -147,12 +162,12 @@ struct MicInputCtl(TravelerMicInputState, Vec<ElemId>);
 impl CtlModel<SndMotu> for Traveler {
     fn load(&mut self, unit: &mut SndMotu, card_cntr: &mut CardCntr) -> Result<(), Error> {
         self.clk_ctls.load(card_cntr)?;
+        self.clk_display_ctl.load(card_cntr)?;
         self.opt_iface_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.opt_iface_ctl.1.append(&mut elem_id_list))?;
         self.phone_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.phone_assign_ctl.1.append(&mut elem_id_list))?;
-        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
-            .map(|mut elem_id_list| self.word_clk_ctl.1.append(&mut elem_id_list))?;
+        self.word_clk_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
         self.mixer_output_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|elem_id_list| self.mixer_output_ctl.1 = elem_id_list)?;
         self.mixer_return_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
@@ -176,6 +191,8 @@ impl CtlModel<SndMotu> for Traveler {
     ) -> Result<bool, Error> {
         if self.clk_ctls.read(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else if self.opt_iface_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.phone_assign_ctl.read(elem_id, elem_value)? {
@@ -208,6 +225,8 @@ impl CtlModel<SndMotu> for Traveler {
     ) -> Result<bool, Error> {
         if self.clk_ctls.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.write(elem_id, new, TIMEOUT_MS)? {
+            Ok(true)
         } else if self.opt_iface_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else if self.phone_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
@@ -232,11 +251,30 @@ impl CtlModel<SndMotu> for Traveler {
     }
 }
 
+impl MeasureModel<SndMotu> for Traveler {
+    fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.measured_elem_list);
+    }
+
+    fn measure_states(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.word_clk_ctl.measure(unit, &mut self.req, TIMEOUT_MS)
+    }
+
+    fn measure_elem(
+        &mut self,
+        _: &SndMotu,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue
+    ) -> Result<bool, Error> {
+        self.word_clk_ctl.read(elem_id, elem_value)
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for Traveler {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.mic_input_ctl.1);
         elem_id_list.extend_from_slice(&self.phone_assign_ctl.1);
-        elem_id_list.extend_from_slice(&self.word_clk_ctl.1);
+        elem_id_list.extend_from_slice(&self.word_clk_ctl.0.notified_elem_list);
         elem_id_list.extend_from_slice(&self.opt_iface_ctl.1);
     }
 
@@ -379,3 +417,41 @@ impl MicInputCtl {
         }
     }
 }
+
+impl ClkDisplayTextModel for Traveler {
+    fn take_clk_display_text_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        self.clk_display_ctl.take_request()
+    }
+
+    fn complete_clk_display_text_request(
+        &mut self,
+        request: &ClkDisplayTextRequest,
+        res: &Result<(), Error>,
+    ) -> bool {
+        self.clk_display_ctl.complete_request(request, res)
+    }
+}
+
+impl ReconcileModel for Traveler {}
+
+impl ChannelPortModel for Traveler {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            <TravelerProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            <TravelerProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for Traveler {
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        Some((
+            &self.mixer_output_ctl.0,
+            <TravelerProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            &self.mixer_source_ctl.0,
+        ))
+    }
+}
@@ -11,9 +11,10 @@ use alsa_ctl_tlv_codec::items::DbInterval;
 
 use core::{card_cntr::*, elem_value_accessor::*};
 
-use motu_protocols::{register_dsp::*, version_2::*};
+use motu_protocols::{register_dsp::*, version_2::*, TargetPort};
 
 use crate::{common_ctls::*, register_dsp_ctls::*, v2_ctls::*, *};
+use crate::register_dsp_runtime::{ChannelPortModel, MixerExportModel, ReconcileModel};
 
 const TIMEOUT_MS: u32 = 100;
 
@@ -21,6 +22,7 @@ const TIMEOUT_MS: u32 = 100;
 pub struct UltraLite{
     req: FwReq,
     clk_ctls: ClkCtl,
+    clk_display_ctl: ClkDisplayTextCtl,
     main_assign_ctl: MainAssignCtl,
     phone_assign_ctl: PhoneAssignCtl,
     mixer_output_ctl: MixerOutputCtl,
@@ -48,6 +50,19 @@ struct ClkCtl;
 
 impl V2ClkCtlOperation<UltraliteProtocol> for ClkCtl {}
 
+#[derive(Default)]
+struct ClkDisplayTextCtl(ClkDisplayTextState);
+
+impl ClkDisplayTextCtlOperation<UltraliteProtocol> for ClkDisplayTextCtl {
+    fn state(&self) -> &ClkDisplayTextState {
+        &self.0
+    }
+
+    fn state_mut(&mut self) -> &mut ClkDisplayTextState {
+        &mut self.0
+    }
+}
+
 #[derive(Default)]
 struct MainAssignCtl(usize, Vec<ElemId>);
 
@@ -109,6 +124,7 @@ struct InputCtl(UltraliteInputState, Vec<ElemId>);
 impl CtlModel<SndMotu> for UltraLite {
     fn load(&mut self, unit: &mut SndMotu, card_cntr: &mut CardCntr) -> Result<(), Error> {
         self.clk_ctls.load(card_cntr)?;
+        self.clk_display_ctl.load(card_cntr)?;
         self.main_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
         self.phone_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.phone_assign_ctl.1.append(&mut elem_id_list))?;
@@ -133,6 +149,8 @@ impl CtlModel<SndMotu> for UltraLite {
     ) -> Result<bool, Error> {
         if self.clk_ctls.read(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else if self.main_assign_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.phone_assign_ctl.read(elem_id, elem_value)? {
@@ -161,6 +179,8 @@ impl CtlModel<SndMotu> for UltraLite {
     ) -> Result<bool, Error> {
         if self.clk_ctls.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.write(elem_id, new, TIMEOUT_MS)? {
+            Ok(true)
         } else if self.main_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else if self.phone_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
@@ -181,6 +201,9 @@ impl CtlModel<SndMotu> for UltraLite {
     }
 }
 
+// No element is measured.
+impl MeasureModel<SndMotu> for UltraLite {}
+
 impl NotifyModel<SndMotu, u32> for UltraLite {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.main_assign_ctl.1);
@@ -379,3 +402,41 @@ impl InputCtl {
         }
     }
 }
+
+impl ClkDisplayTextModel for UltraLite {
+    fn take_clk_display_text_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        self.clk_display_ctl.take_request()
+    }
+
+    fn complete_clk_display_text_request(
+        &mut self,
+        request: &ClkDisplayTextRequest,
+        res: &Result<(), Error>,
+    ) -> bool {
+        self.clk_display_ctl.complete_request(request, res)
+    }
+}
+
+impl ReconcileModel for UltraLite {}
+
+impl ChannelPortModel for UltraLite {
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            <UltraliteProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            <UltraliteProtocol as RegisterDspMixerOutputOperation>::OUTPUT_DESTINATIONS,
+        )
+    }
+}
+
+impl MixerExportModel for UltraLite {
+    fn mixer_export_state(
+        &self,
+    ) -> Option<(&RegisterDspMixerOutputState, &[TargetPort], &RegisterDspMixerMonauralSourceState)>
+    {
+        Some((
+            &self.mixer_output_ctl.0,
+            <UltraliteProtocol as RegisterDspMixerMonauralSourceOperation>::MIXER_SOURCES,
+            &self.mixer_source_ctl.0,
+        ))
+    }
+}
@@ -7,7 +7,8 @@ use hinawa::{SndMotu, SndUnitExt};
 
 use alsactl::{ElemId, ElemValue};
 
-use core::card_cntr::{CardCntr, CtlModel, NotifyModel};
+use core::card_cntr::{CardCntr, CtlModel, MeasureModel, NotifyModel, OptionalElemGroups};
+use core::{burst::BatchedWrite, identify::IdentifyModel, monitor::*};
 
 use motu_protocols::{command_dsp::*, version_3::*};
 
@@ -21,6 +22,7 @@ pub struct UltraliteMk3Hybrid {
     req: FwReq,
     resp: FwResp,
     clk_ctls: ClkCtl,
+    clk_display_ctl: ClkDisplayTextCtl,
     port_assign_ctl: PortAssignCtl,
     phone_assign_ctl: PhoneAssignCtl,
     sequence_number: u8,
@@ -30,6 +32,9 @@ pub struct UltraliteMk3Hybrid {
     input_ctl: InputCtl,
     output_ctl: OutputCtl,
     resource_ctl: ResourceCtl,
+    diagnostics_ctl: DiagnosticsCtl,
+    // The state of command DSP in the unit during batched write.
+    batch: Option<CommandDspPreset>,
 }
 
 #[derive(Default)]
@@ -50,6 +55,19 @@ struct ClkCtl;
 
 impl V3ClkCtlOperation<UltraliteMk3HybridProtocol> for ClkCtl {}
 
+#[derive(Default)]
+struct ClkDisplayTextCtl(ClkDisplayTextState);
+
+impl ClkDisplayTextCtlOperation<UltraliteMk3HybridProtocol> for ClkDisplayTextCtl {
+    fn state(&self) -> &ClkDisplayTextState {
+        &self.0
+    }
+
+    fn state_mut(&mut self) -> &mut ClkDisplayTextState {
+        &mut self.0
+    }
+}
+
 #[derive(Default)]
 struct PortAssignCtl(V3PortAssignState, Vec<ElemId>);
 
@@ -64,7 +82,7 @@ impl V3PortAssignCtlOperation<UltraliteMk3HybridProtocol> for PortAssignCtl {
 }
 
 #[derive(Default)]
-struct ReverbCtl(CommandDspReverbState, Vec<ElemId>);
+pub struct ReverbCtl(CommandDspReverbState, Vec<ElemId>, bool, CommandDspReverbSmoothing);
 
 impl CommandDspReverbCtlOperation<UltraliteMk3HybridProtocol> for ReverbCtl {
     fn state(&self) -> &CommandDspReverbState {
@@ -74,12 +92,35 @@ impl CommandDspReverbCtlOperation<UltraliteMk3HybridProtocol> for ReverbCtl {
     fn state_mut(&mut self) -> &mut CommandDspReverbState {
         &mut self.0
     }
+
+    fn smoothing(&self) -> &CommandDspReverbSmoothing {
+        &self.3
+    }
+
+    fn smoothing_mut(&mut self) -> &mut CommandDspReverbSmoothing {
+        &mut self.3
+    }
+
+    fn batched(&self) -> bool {
+        self.2
+    }
 }
 
 #[derive(Default)]
-struct MonitorCtl(CommandDspMonitorState, Vec<ElemId>);
+pub struct MonitorCtl(
+    CommandDspMonitorState,
+    Vec<ElemId>,
+    bool,
+    CommandDspTalkbackDimState,
+    CommandDspMonitorSection,
+);
 
 impl CommandDspMonitorCtlOperation<UltraliteMk3HybridProtocol> for MonitorCtl {
+    const FOCUS_CHANNEL_COUNTS: [usize; 2] = [
+        <UltraliteMk3HybridProtocol as CommandDspOutputOperation>::OUTPUT_PORTS.len(),
+        UltraliteMk3HybridProtocol::INPUT_PORTS.len(),
+    ];
+
     fn state(&self) -> &CommandDspMonitorState {
         &self.0
     }
@@ -87,10 +128,30 @@ impl CommandDspMonitorCtlOperation<UltraliteMk3HybridProtocol> for MonitorCtl {
     fn state_mut(&mut self) -> &mut CommandDspMonitorState {
         &mut self.0
     }
+
+    fn talkback_dim(&self) -> &CommandDspTalkbackDimState {
+        &self.3
+    }
+
+    fn talkback_dim_mut(&mut self) -> &mut CommandDspTalkbackDimState {
+        &mut self.3
+    }
+
+    fn monitor_section(&self) -> &CommandDspMonitorSection {
+        &self.4
+    }
+
+    fn monitor_section_mut(&mut self) -> &mut CommandDspMonitorSection {
+        &mut self.4
+    }
+
+    fn batched(&self) -> bool {
+        self.2
+    }
 }
 
 #[derive(Default)]
-struct MixerCtl(CommandDspMixerState, Vec<ElemId>);
+pub struct MixerCtl(CommandDspMixerState, Vec<ElemId>, bool, CommandDspSoloState);
 
 impl CommandDspMixerCtlOperation<UltraliteMk3HybridProtocol> for MixerCtl {
     fn state(&self) -> &CommandDspMixerState {
@@ -100,10 +161,22 @@ impl CommandDspMixerCtlOperation<UltraliteMk3HybridProtocol> for MixerCtl {
     fn state_mut(&mut self) -> &mut CommandDspMixerState {
         &mut self.0
     }
+
+    fn solo(&self) -> &CommandDspSoloState {
+        &self.3
+    }
+
+    fn solo_mut(&mut self) -> &mut CommandDspSoloState {
+        &mut self.3
+    }
+
+    fn batched(&self) -> bool {
+        self.2
+    }
 }
 
 #[derive(Default)]
-struct InputCtl(CommandDspInputState, Vec<ElemId>);
+pub struct InputCtl(CommandDspInputState, Vec<ElemId>, bool);
 
 impl CommandDspInputCtlOperation<UltraliteMk3HybridProtocol> for InputCtl {
     fn state(&self) -> &CommandDspInputState {
@@ -113,10 +186,14 @@ impl CommandDspInputCtlOperation<UltraliteMk3HybridProtocol> for InputCtl {
     fn state_mut(&mut self) -> &mut CommandDspInputState {
         &mut self.0
     }
+
+    fn batched(&self) -> bool {
+        self.2
+    }
 }
 
 #[derive(Default)]
-struct OutputCtl(CommandDspOutputState, Vec<ElemId>);
+pub struct OutputCtl(CommandDspOutputState, Vec<ElemId>, bool);
 
 impl CommandDspOutputCtlOperation<UltraliteMk3HybridProtocol> for OutputCtl {
     fn state(&self) -> &CommandDspOutputState {
@@ -126,6 +203,10 @@ impl CommandDspOutputCtlOperation<UltraliteMk3HybridProtocol> for OutputCtl {
     fn state_mut(&mut self) -> &mut CommandDspOutputState {
         &mut self.0
     }
+
+    fn batched(&self) -> bool {
+        self.2
+    }
 }
 
 #[derive(Default)]
@@ -141,33 +222,66 @@ impl CommandDspResourcebCtlOperation for ResourceCtl {
     }
 }
 
+#[derive(Default)]
+struct DiagnosticsCtl(u32, Vec<ElemId>);
+
+impl CommandDspDiagnosticsCtlOperation for DiagnosticsCtl {
+    fn state(&self) -> &u32 {
+        &self.0
+    }
+
+    fn state_mut(&mut self) -> &mut u32 {
+        &mut self.0
+    }
+}
+
 impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
     fn load(&mut self, unit: &mut SndMotu, card_cntr: &mut CardCntr) -> Result<(), Error> {
         self.clk_ctls.load(card_cntr)?;
+        self.clk_display_ctl.load(card_cntr)?;
         self.port_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.port_assign_ctl.1.append(&mut elem_id_list))?;
         self.phone_assign_ctl.load(card_cntr, unit, &mut self.req, TIMEOUT_MS)
             .map(|mut elem_id_list| self.phone_assign_ctl.1.append(&mut elem_id_list))?;
-        self.reverb_ctl.load(card_cntr)
-            .map(|mut elem_id_list| self.reverb_ctl.1.append(&mut elem_id_list))?;
         self.monitor_ctl.load(card_cntr)
             .map(|mut elem_id_list| self.monitor_ctl.1.append(&mut elem_id_list))?;
+        self.load_monitor_section(card_cntr)?;
         self.mixer_ctl.load(card_cntr)
             .map(|mut elem_id_list| self.mixer_ctl.1.append(&mut elem_id_list))?;
         self.input_ctl.load(card_cntr)
             .map(|mut elem_id_list| self.input_ctl.1.append(&mut elem_id_list))?;
-        self.input_ctl.load_equalizer(card_cntr)
-            .map(|mut elem_id_list| self.input_ctl.1.append(&mut elem_id_list))?;
-        self.input_ctl.load_dynamics(card_cntr)
-            .map(|mut elem_id_list| self.input_ctl.1.append(&mut elem_id_list))?;
         self.output_ctl.load(card_cntr)
             .map(|mut elem_id_list| self.output_ctl.1.append(&mut elem_id_list))?;
-        self.output_ctl.load_equalizer(card_cntr)
-            .map(|mut elem_id_list| self.output_ctl.1.append(&mut elem_id_list))?;
-        self.output_ctl.load_dynamics(card_cntr)
-            .map(|mut elem_id_list| self.output_ctl.1.append(&mut elem_id_list))?;
         self.resource_ctl.load(card_cntr)
             .map(|mut elem_id_list| self.resource_ctl.1.append(&mut elem_id_list))?;
+        self.diagnostics_ctl.load(card_cntr)
+            .map(|mut elem_id_list| self.diagnostics_ctl.1.append(&mut elem_id_list))?;
+
+        // The elements for DSP effects are numerous. They are registered at last and skipped when
+        // the capacity of sound card is exhausted, so that the essential ones are available.
+        let mut groups = OptionalElemGroups::default();
+        groups.register("reverb", || {
+            self.reverb_ctl.load(card_cntr)
+                .map(|mut elem_id_list| self.reverb_ctl.1.append(&mut elem_id_list))
+        })?;
+        groups.register("input-equalizer", || {
+            self.input_ctl.load_equalizer(card_cntr)
+                .map(|mut elem_id_list| self.input_ctl.1.append(&mut elem_id_list))
+        })?;
+        groups.register("output-equalizer", || {
+            self.output_ctl.load_equalizer(card_cntr)
+                .map(|mut elem_id_list| self.output_ctl.1.append(&mut elem_id_list))
+        })?;
+        groups.register("input-dynamics", || {
+            self.input_ctl.load_dynamics(card_cntr)
+                .map(|mut elem_id_list| self.input_ctl.1.append(&mut elem_id_list))
+        })?;
+        groups.register("output-dynamics", || {
+            self.output_ctl.load_dynamics(card_cntr)
+                .map(|mut elem_id_list| self.output_ctl.1.append(&mut elem_id_list))
+        })?;
+        groups.report();
+
         Ok(())
     }
 
@@ -179,6 +293,8 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
     ) -> Result<bool, Error> {
         if self.clk_ctls.read(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else if self.port_assign_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.phone_assign_ctl.read(elem_id, elem_value)? {
@@ -187,6 +303,8 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
             Ok(true)
         } else if self.monitor_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.read_monitor_section(elem_id, elem_value)? {
+            Ok(true)
         } else if self.mixer_ctl.read(elem_id, elem_value)? {
             Ok(true)
         } else if self.input_ctl.read(elem_id, elem_value)? {
@@ -203,6 +321,8 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
             Ok(true)
         } else if self.resource_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.diagnostics_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -217,6 +337,8 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
     ) -> Result<bool, Error> {
         if self.clk_ctls.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.clk_display_ctl.write(elem_id, new, TIMEOUT_MS)? {
+            Ok(true)
         } else if self.port_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
         } else if self.phone_assign_ctl.write(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
@@ -230,6 +352,8 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
             TIMEOUT_MS
         )? {
             Ok(true)
+        } else if self.write_monitor_section(unit, elem_id, new)? {
+            Ok(true)
         } else if self.monitor_ctl.write(
             &mut self.sequence_number,
             unit,
@@ -308,6 +432,42 @@ impl CtlModel<SndMotu> for UltraliteMk3Hybrid {
     }
 }
 
+impl ClkDisplayTextModel for UltraliteMk3Hybrid {
+    fn take_clk_display_text_request(&mut self) -> Option<ClkDisplayTextRequest> {
+        self.clk_display_ctl.take_request()
+    }
+
+    fn complete_clk_display_text_request(
+        &mut self,
+        request: &ClkDisplayTextRequest,
+        res: &Result<(), Error>,
+    ) -> bool {
+        self.clk_display_ctl.complete_request(request, res)
+    }
+}
+
+impl IdentifyModel<SndMotu> for UltraliteMk3Hybrid {
+    fn blink_indicator(&mut self, unit: &mut SndMotu, on: bool) -> Result<(), Error> {
+        self.clk_ctls.blink_clk_display(unit, &mut self.req, on, TIMEOUT_MS)
+    }
+
+    fn restore_indicator(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.clk_ctls.restore_clk_display(unit, &mut self.req, TIMEOUT_MS)
+    }
+}
+
+impl MeasureModel<SndMotu> for UltraliteMk3Hybrid {
+    fn get_measure_elem_list(&mut self, _: &mut Vec<ElemId>) {}
+
+    fn measure_states(&mut self, _: &mut SndMotu) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn measure_elem(&mut self, _: &SndMotu, _: &ElemId, _: &mut ElemValue) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
 impl NotifyModel<SndMotu, u32> for UltraliteMk3Hybrid {
     fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.port_assign_ctl.1);
@@ -346,15 +506,18 @@ impl NotifyModel<SndMotu, &[DspCmd]> for UltraliteMk3Hybrid {
         elem_id_list.extend_from_slice(&self.input_ctl.1);
         elem_id_list.extend_from_slice(&self.output_ctl.1);
         elem_id_list.extend_from_slice(&self.resource_ctl.1);
+        elem_id_list.extend_from_slice(&self.diagnostics_ctl.1);
     }
 
     fn parse_notification(&mut self, _: &mut SndMotu, cmds: &&[DspCmd]) -> Result<(), Error> {
+        self.update_batch(*cmds);
         self.reverb_ctl.parse_commands(*cmds);
         self.monitor_ctl.parse_commands(*cmds);
-        self.mixer_ctl.parse_commands(*cmds);
-        self.input_ctl.parse_commands(*cmds);
-        self.output_ctl.parse_commands(*cmds);
+        let mut dropped = self.mixer_ctl.parse_commands(*cmds);
+        dropped += self.input_ctl.parse_commands(*cmds);
+        dropped += self.output_ctl.parse_commands(*cmds);
         self.resource_ctl.parse_commands(*cmds);
+        self.diagnostics_ctl.count_dropped_commands(dropped);
         Ok(())
     }
 
@@ -384,12 +547,94 @@ impl NotifyModel<SndMotu, &[DspCmd]> for UltraliteMk3Hybrid {
             Ok(true)
         } else if self.resource_ctl.read(elem_id, elem_value)? {
             Ok(true)
+        } else if self.diagnostics_ctl.read(elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
     }
 }
 
+impl CommandDspPresetCtlOperation<UltraliteMk3HybridProtocol> for UltraliteMk3Hybrid {
+    type Reverb = ReverbCtl;
+    type Monitor = MonitorCtl;
+    type Mixer = MixerCtl;
+    type Input = InputCtl;
+    type Output = OutputCtl;
+
+    fn preset_ctls(&self) -> (&ReverbCtl, &MonitorCtl, &MixerCtl, &InputCtl, &OutputCtl) {
+        (&self.reverb_ctl, &self.monitor_ctl, &self.mixer_ctl, &self.input_ctl, &self.output_ctl)
+    }
+
+    fn preset_ctls_mut(
+        &mut self,
+    ) -> (
+        &mut u8,
+        &mut FwReq,
+        &mut ReverbCtl,
+        &mut MonitorCtl,
+        &mut MixerCtl,
+        &mut InputCtl,
+        &mut OutputCtl,
+    ) {
+        (
+            &mut self.sequence_number,
+            &mut self.req,
+            &mut self.reverb_ctl,
+            &mut self.monitor_ctl,
+            &mut self.mixer_ctl,
+            &mut self.input_ctl,
+            &mut self.output_ctl,
+        )
+    }
+
+    fn batch_mut(&mut self) -> &mut Option<CommandDspPreset> {
+        &mut self.batch
+    }
+
+    fn set_batched(&mut self, batched: bool) {
+        self.reverb_ctl.2 = batched;
+        self.monitor_ctl.2 = batched;
+        self.mixer_ctl.2 = batched;
+        self.input_ctl.2 = batched;
+        self.output_ctl.2 = batched;
+    }
+}
+
+impl CommandDspMessageCtlOperation<UltraliteMk3HybridProtocol> for UltraliteMk3Hybrid {
+    fn message_ctls_mut(&mut self) -> (&mut u8, &mut FwReq, &mut FwResp) {
+        (&mut self.sequence_number, &mut self.req, &mut self.resp)
+    }
+}
+
+impl MonitorSection<SndMotu> for UltraliteMk3Hybrid {
+    fn monitor_state(&self) -> &MonitorSectionState {
+        &self.monitor_ctl.4.state
+    }
+
+    fn write_monitor_state(&mut self, unit: &mut SndMotu, state: MonitorSectionState)
+        -> Result<(), Error>
+    {
+        self.monitor_ctl.write_monitor_section_state(
+            &mut self.sequence_number,
+            unit,
+            &mut self.req,
+            state,
+            TIMEOUT_MS
+        )
+    }
+}
+
+impl BatchedWrite<SndMotu> for UltraliteMk3Hybrid {
+    fn begin_batch(&mut self) {
+        self.begin_batched_write();
+    }
+
+    fn flush_batch(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.flush_batched_write(unit, TIMEOUT_MS)
+    }
+}
+
 impl<'a> CommandDspModel<'a> for UltraliteMk3Hybrid {
     fn prepare_message_handler<F>(&mut self, unit: &mut SndMotu, handler: F) -> Result<(), Error>
         where
@@ -429,4 +674,39 @@ impl<'a> CommandDspModel<'a> for UltraliteMk3Hybrid {
         )?;
         Ok(())
     }
+
+    fn ensure_messaging(&mut self, unit: &mut SndMotu) -> Result<bool, Error> {
+        self.recover_messaging(unit, TIMEOUT_MS)
+    }
+
+    fn build_preset(&self) -> Vec<u8> {
+        UltraliteMk3HybridProtocol::build_preset(&self.saved_preset())
+    }
+
+    fn load_preset(&mut self, unit: &mut SndMotu, raw: &[u8]) -> Result<usize, Error> {
+        self.parse_and_write_preset(unit, raw, TIMEOUT_MS)
+    }
+
+    fn panic(&mut self, unit: &mut SndMotu) -> Result<Vec<DspCmd>, Error> {
+        self.write_panic(unit, TIMEOUT_MS)
+    }
+
+    fn write_pending_main_volume(&mut self, unit: &mut SndMotu) -> Result<Vec<DspCmd>, Error> {
+        self.flush_pending_main_volume(unit, TIMEOUT_MS)
+    }
+
+    fn is_reverb_ramp_active(&self) -> bool {
+        self.reverb_ramp_active()
+    }
+
+    fn step_reverb_ramp(&mut self, unit: &mut SndMotu) -> Result<(), Error> {
+        self.write_reverb_ramp_step(unit, TIMEOUT_MS)
+    }
+
+    fn channel_ports(&self) -> (&'static [TargetPort], &'static [TargetPort]) {
+        (
+            UltraliteMk3HybridProtocol::INPUT_PORTS,
+            <UltraliteMk3HybridProtocol as CommandDspOutputOperation>::OUTPUT_PORTS,
+        )
+    }
 }
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2021 Takashi Sakamoto
 use std::sync::mpsc;
+use std::time::Duration;
 
 use nix::sys::signal::Signal;
 
@@ -12,7 +13,10 @@ use hinawa::{SndMotu, SndMotuExt, SndUnitExt};
 
 use alsactl::{CardExt, ElemId, ElemEventMask};
 
-use core::{card_cntr::*, dispatcher::*};
+use core::{card_cntr::*, dispatcher::*, event_queue::*, version::*};
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 use crate::{f828::*, f896::*};
 
@@ -21,51 +25,88 @@ pub type F896Runtime = Version1Runtime<F896>;
 
 pub struct Version1Runtime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
 {
     unit: SndMotu,
     model: T,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<Dispatcher>,
+    timer: Option<Dispatcher>,
     #[allow(dead_code)]
     version: u32,
     notified_elem_id_list: Vec<ElemId>,
+    measured_elem_id_list: Vec<ElemId>,
 }
 
 impl<T>  Drop for Version1Runtime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
 {
     fn drop(&mut self) {
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.timer.iter_mut()
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timer is finished at first, then the rest in reverse
+        // order of launch, thus the dispatcher for signal handler is finished before the
+        // dispatcher for node.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.dispatchers.drain(..).rev());
+        join_dispatchers(dispatchers);
     }
 }
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
     Notify(u32),
+    Timer,
+}
+
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => Some(elem_id),
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
 }
 
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
 const SYSTEM_DISPATCHER_NAME: &str = "system event dispatcher";
+const TIMER_DISPATCHER_NAME: &str = "interval timer dispatcher";
+
+const TIMER_INTERVAL: Duration = Duration::from_millis(500);
+
+impl<T> ConfigReloadOperation for Version1Runtime<T>
+where
+    T: CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+{
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            join_dispatchers(self.timer.take());
+            self.launch_interval_timer_dispatcher()?;
+        }
+        Ok(())
+    }
+}
 
 impl<T> Version1Runtime<T>
 where
-    T: CtlModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
+    T: CtlModel<SndMotu> + MeasureModel<SndMotu> + NotifyModel<SndMotu, u32> + Default,
 {
     pub fn new(unit: SndMotu, card_id: u32, version: u32) -> Result<Self, Error> {
         let card_cntr = CardCntr::new();
@@ -73,6 +114,7 @@ where
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(Self{
             unit,
@@ -81,8 +123,10 @@ where
             rx,
             tx,
             dispatchers: Default::default(),
+            timer: Default::default(),
             version,
             notified_elem_id_list: Default::default(),
+            measured_elem_id_list: Default::default(),
         })
     }
 
@@ -92,6 +136,17 @@ where
 
         self.model.load(&mut self.unit, &mut self.card_cntr)?;
         self.model.get_notified_elem_list(&mut self.notified_elem_id_list);
+        self.model.get_measure_elem_list(&mut self.measured_elem_id_list);
+
+        if self.measured_elem_id_list.len() > 0 {
+            self.launch_interval_timer_dispatcher()?;
+        }
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            motu_protocols::CRATE_VERSION,
+        )?;
 
         Ok(())
     }
@@ -105,8 +160,9 @@ where
 
             match ev {
                 Event::Shutdown | Event::Disconnected => break,
+                Event::Reload => self.reload(),
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Elem((elem_id, events)) => {
                     let _ = self.card_cntr.dispatch_elem_event(
@@ -124,6 +180,13 @@ where
                         &mut self.model,
                     );
                 }
+                Event::Timer => {
+                    let _ = self.card_cntr.measure_elems(
+                        &mut self.unit,
+                        &self.measured_elem_id_list,
+                        &mut self.model,
+                    );
+                }
             }
         }
         Ok(())
@@ -168,6 +231,11 @@ where
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -178,4 +246,19 @@ where
 
         Ok(())
     }
+
+    fn launch_interval_timer_dispatcher(&mut self) -> Result<(), Error> {
+        let name = TIMER_DISPATCHER_NAME.to_string();
+        let mut dispatcher = Dispatcher::run(name)?;
+
+        let tx = self.tx.clone();
+        dispatcher.attach_interval_handler(metering_interval(TIMER_INTERVAL), move || {
+            let _ = tx.send(Event::Timer);
+            source::Continue(true)
+        });
+
+        self.timer = Some(dispatcher);
+
+        Ok(())
+    }
 }
@@ -12,6 +12,8 @@ use core::elem_value_accessor::ElemValueAccessor;
 
 use motu_protocols::version_3::*;
 
+use crate::common_ctls::PHONE_ASSIGN_NAME;
+
 use super::*;
 
 fn clk_src_to_str(src: &V3ClkSrc) -> &'static str {
@@ -27,6 +29,8 @@ fn clk_src_to_str(src: &V3ClkSrc) -> &'static str {
 const RATE_NAME: &str = "sampling-rate";
 const SRC_NAME: &str = "clock-source";
 
+const IDENTIFY_LABEL: &str = "HELLO";
+
 pub trait V3ClkCtlOperation<T: V3ClkOperation> {
     fn load(&mut self, card_cntr: &mut CardCntr) -> Result<(), Error> {
         let labels: Vec<&str> = T::CLK_RATES
@@ -113,11 +117,45 @@ pub trait V3ClkCtlOperation<T: V3ClkOperation> {
             _ => Ok(false),
         }
     }
+
+    fn blink_clk_display(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        on: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        if T::HAS_LCD {
+            let label = if on { IDENTIFY_LABEL } else { "" };
+            T::update_clk_display(req, &mut unit.get_node(), label, timeout_ms)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn restore_clk_display(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        if T::HAS_LCD {
+            let mut node = unit.get_node();
+            let val = T::get_clk_src(req, &mut node, timeout_ms)?;
+            let label = clk_src_to_str(&T::CLK_SRCS[val].0);
+            T::update_clk_display(req, &mut node, &label, timeout_ms)
+        } else {
+            Ok(())
+        }
+    }
 }
 
 const MAIN_ASSIGN_NAME: &str = "main-assign";
 const RETURN_ASSIGN_NAME: &str = "return-assign";
 
+/// The pair of elements for link of phone bus to main output.
+pub const V3_PHONE_FOLLOW_MAIN_PAIRS: &[(&str, &str)] = &[(MAIN_ASSIGN_NAME, PHONE_ASSIGN_NAME)];
+
 #[derive(Default)]
 pub struct V3PortAssignState(usize, usize);
 
@@ -125,6 +163,25 @@ pub trait V3PortAssignCtlOperation<T: V3PortAssignOperation> {
     fn state(&self) -> &V3PortAssignState;
     fn state_mut(&mut self) -> &mut V3PortAssignState;
 
+    /// Whether the port is available for assignment in current configuration.
+    fn is_port_available(&self, _port: &TargetPort) -> bool {
+        true
+    }
+
+    fn check_port_available(&self, idx: usize, curr: usize) -> Result<(), Error> {
+        let &(port, _) = T::ASSIGN_PORTS.iter().nth(idx).ok_or_else(|| {
+            let msg = format!("Invalid index of port for assignment: {}", idx);
+            Error::new(FileError::Inval, &msg)
+        })?;
+        // The current assignment is kept even if unavailable.
+        if idx != curr && !self.is_port_available(&port) {
+            let msg = format!("Unavailable port for assignment: {}", target_port_to_str(&port));
+            Err(Error::new(FileError::Inval, &msg))
+        } else {
+            Ok(())
+        }
+    }
+
     fn load(
         &mut self,
         card_cntr: &mut CardCntr,
@@ -192,6 +249,7 @@ pub trait V3PortAssignCtlOperation<T: V3PortAssignOperation> {
         match elem_id.get_name().as_str() {
             MAIN_ASSIGN_NAME => {
                 ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    self.check_port_available(val as usize, self.state().0)?;
                     T::set_main_assign(req, &mut unit.get_node(), val as usize, timeout_ms)
                         .map(|_| self.state_mut().0 = val as usize)
                 })
@@ -199,6 +257,7 @@ pub trait V3PortAssignCtlOperation<T: V3PortAssignOperation> {
             }
             RETURN_ASSIGN_NAME => {
                 ElemValueAccessor::<u32>::get_val(elem_value, |val| {
+                    self.check_port_available(val as usize, self.state().1)?;
                     T::set_return_assign(req, &mut unit.get_node(), val as usize, timeout_ms)
                         .map(|_| self.state_mut().1 = val as usize)
                 })
@@ -220,7 +279,17 @@ fn opt_iface_mode_to_str(mode: &V3OptIfaceMode) -> &'static str {
 const OPT_IFACE_IN_MODE_NAME: &str = "optical-iface-in-mode";
 const OPT_IFACE_OUT_MODE_NAME: &str = "optical-iface-out-mode";
 
+/// The structure for state of optical interfaces, cached from registers.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct V3OptIfaceState {
+    pub input: [V3OptIfaceMode; 2],
+    pub output: [V3OptIfaceMode; 2],
+}
+
 pub trait V3OptIfaceCtlOperation<T: V3OptIfaceOperation> {
+    fn state(&self) -> &V3OptIfaceState;
+    fn state_mut(&mut self) -> &mut V3OptIfaceState;
+
     const MODES: [V3OptIfaceMode; 3] = [
         V3OptIfaceMode::Disabled,
         V3OptIfaceMode::Adat,
@@ -228,62 +297,77 @@ pub trait V3OptIfaceCtlOperation<T: V3OptIfaceOperation> {
     ];
     const TARGETS: [V3OptIfaceTarget; 2] = [V3OptIfaceTarget::A, V3OptIfaceTarget::B];
 
-    fn load(&self, card_cntr: &mut CardCntr) -> Result<(), Error> {
+    fn load(
+        &mut self,
+        card_cntr: &mut CardCntr,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        timeout_ms: u32,
+    ) -> Result<Vec<ElemId>, Error> {
+        self.cache(unit, req, timeout_ms)?;
+
+        let mut notified_elem_id_list = Vec::new();
+
         let labels: Vec<&str> = Self::MODES.iter()
             .map(|m| opt_iface_mode_to_str(m))
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, OPT_IFACE_IN_MODE_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, Self::TARGETS.len(), &labels, None, true)?;
+        card_cntr
+            .add_enum_elems(&elem_id, 1, Self::TARGETS.len(), &labels, None, true)
+            .map(|elem_id_list| notified_elem_id_list.extend_from_slice(&elem_id_list))?;
 
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, OPT_IFACE_OUT_MODE_NAME, 0);
-        let _ = card_cntr.add_enum_elems(&elem_id, 1, Self::TARGETS.len(), &labels, None, true)?;
+        card_cntr
+            .add_enum_elems(&elem_id, 1, Self::TARGETS.len(), &labels, None, true)
+            .map(|elem_id_list| notified_elem_id_list.extend_from_slice(&elem_id_list))?;
 
-        Ok(())
+        Ok(notified_elem_id_list)
     }
 
-    fn read(
-        &self,
+    fn cache(
+        &mut self,
         unit: &mut SndMotu,
         req: &mut FwReq,
-        elem_id: &ElemId,
-        elem_value: &mut ElemValue,
         timeout_ms: u32,
-    ) -> Result<bool, Error> {
+    ) -> Result<(), Error> {
+        let mut state = V3OptIfaceState::default();
+        Self::TARGETS
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, &target)| {
+                T::get_opt_input_iface_mode(req, &mut unit.get_node(), target, timeout_ms)
+                    .map(|mode| state.input[i] = mode)?;
+                T::get_opt_output_iface_mode(req, &mut unit.get_node(), target, timeout_ms)
+                    .map(|mode| state.output[i] = mode)
+            })?;
+        *self.state_mut() = state;
+        Ok(())
+    }
+
+    fn read(&self, elem_id: &ElemId, elem_value: &mut ElemValue) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             OPT_IFACE_IN_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_vals(elem_value, Self::TARGETS.len(), |idx| {
-                    T::get_opt_input_iface_mode(
-                        req,
-                        &mut unit.get_node(),
-                        Self::TARGETS[idx],
-                        timeout_ms
-                    )
-                        .map(|mode| {
-                            Self::MODES.iter().position(|m| m.eq(&mode)).unwrap() as u32
-                        })
-                })
-                .map(|_| true)
+                Self::read_modes(elem_value, &self.state().input);
+                Ok(true)
             }
             OPT_IFACE_OUT_MODE_NAME => {
-                ElemValueAccessor::<u32>::set_vals(elem_value, Self::TARGETS.len(), |idx| {
-                    T::get_opt_output_iface_mode(
-                        req,
-                        &mut unit.get_node(),
-                        Self::TARGETS[idx],
-                        timeout_ms
-                    )
-                        .map(|mode| {
-                            Self::MODES.iter().position(|m| m.eq(&mode)).unwrap() as u32
-                        })
-                })
-                .map(|_| true)
+                Self::read_modes(elem_value, &self.state().output);
+                Ok(true)
             }
             _ => Ok(false),
         }
     }
 
+    fn read_modes(elem_value: &mut ElemValue, modes: &[V3OptIfaceMode]) {
+        let vals: Vec<u32> = modes
+            .iter()
+            .map(|mode| Self::MODES.iter().position(|m| m.eq(mode)).unwrap() as u32)
+            .collect();
+        elem_value.set_enum(&vals);
+    }
+
     fn write(
-        &self,
+        &mut self,
         unit: &mut SndMotu,
         req: &mut FwReq,
         elem_id: &ElemId,
@@ -293,46 +377,57 @@ pub trait V3OptIfaceCtlOperation<T: V3OptIfaceOperation> {
     ) -> Result<bool, Error> {
         match elem_id.get_name().as_str() {
             OPT_IFACE_IN_MODE_NAME => {
-                unit.lock()?;
-                let res = ElemValueAccessor::<u32>::get_vals(new, old, Self::TARGETS.len(), |idx, val| {
-                    let &mode = Self::MODES.iter()
-                        .nth(val as usize)
-                        .ok_or_else(|| {
-                            let msg = format!("Invalid index for mode of opt interface: {}", val);
-                            Error::new(FileError::Inval, &msg)
-                        })?;
-                    T::set_opt_input_iface_mode(
-                        req,
-                        &mut unit.get_node(),
-                        Self::TARGETS[idx],
-                        mode,
-                        timeout_ms,
-                    )
-                });
-                let _ = unit.unlock();
-                res.and(Ok(true))
+                self.write_modes(unit, req, old, new, false, timeout_ms)
+                    .map(|_| true)
             }
             OPT_IFACE_OUT_MODE_NAME => {
-                unit.lock()?;
-                let res = ElemValueAccessor::<u32>::get_vals(new, old, Self::TARGETS.len(), |idx, val| {
-                    let &mode = Self::MODES.iter()
-                        .nth(val as usize)
-                        .ok_or_else(|| {
-                            let msg = format!("Invalid index for mode of opt interface: {}", val);
-                            Error::new(FileError::Inval, &msg)
-                        })?;
-                    T::set_opt_input_iface_mode(
-                        req,
-                        &mut unit.get_node(),
-                        Self::TARGETS[idx],
-                        mode,
-                        timeout_ms,
-                    )
-                });
-                let _ = unit.unlock();
-                res.and(Ok(true))
+                self.write_modes(unit, req, old, new, true, timeout_ms)
+                    .map(|_| true)
             }
             _ => Ok(false),
         }
     }
+
+    // The change of mode results in the change of channel count in isochronous packet, thus the
+    // unit is locked against starting packet streaming during the change. The lock fails when the
+    // packet streaming is already running. The registers are read again after the change.
+    fn write_modes(
+        &mut self,
+        unit: &mut SndMotu,
+        req: &mut FwReq,
+        old: &ElemValue,
+        new: &ElemValue,
+        is_output: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        unit.lock()?;
+        let res = ElemValueAccessor::<u32>::get_vals(new, old, Self::TARGETS.len(), |idx, val| {
+            let &mode = Self::MODES.iter()
+                .nth(val as usize)
+                .ok_or_else(|| {
+                    let msg = format!("Invalid index for mode of opt interface: {}", val);
+                    Error::new(FileError::Inval, &msg)
+                })?;
+            if is_output {
+                T::set_opt_output_iface_mode(
+                    req,
+                    &mut unit.get_node(),
+                    Self::TARGETS[idx],
+                    mode,
+                    timeout_ms,
+                )
+            } else {
+                T::set_opt_input_iface_mode(
+                    req,
+                    &mut unit.get_node(),
+                    Self::TARGETS[idx],
+                    mode,
+                    timeout_ms,
+                )
+            }
+        })
+        .and_then(|_| self.cache(unit, req, timeout_ms));
+        let _ = unit.unlock();
+        res
+    }
 }
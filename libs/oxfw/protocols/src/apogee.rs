@@ -24,7 +24,9 @@
 
 use glib::{Error, FileError};
 
-use hinawa::{FwFcp, FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwFcp, FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 use ta1394::{general::VendorDependent, *};
 
@@ -57,7 +59,8 @@ impl DuetFwInputMeterProtocol {
     ) -> Result<(), Error> {
         let mut frame = [0; Self::ANALOG_INPUT_SIZE];
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             METER_OFFSET_BASE + METER_INPUT_OFFSET,
@@ -102,7 +105,8 @@ impl DuetFwMixerMeterProtocol {
     ) -> Result<(), Error> {
         let mut frame = [0; Self::MIXER_IO_SIZE];
 
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadBlockRequest,
             METER_OFFSET_BASE + METER_MIXER_OFFSET,
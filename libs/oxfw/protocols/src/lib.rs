@@ -12,3 +12,6 @@ pub mod lacie;
 pub mod loud;
 pub mod oxford;
 pub mod tascam;
+
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
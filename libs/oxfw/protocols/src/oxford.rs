@@ -5,7 +5,9 @@
 
 use glib::Error;
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq, FwTcode};
+
+use fw_transaction::transaction_sync;
 
 #[derive(Default, Debug)]
 pub struct OxfordProtocol;
@@ -25,7 +27,8 @@ impl OxfordProtocol {
         timeout_ms: u32,
     ) -> Result<(), Error> {
         let mut quadlet = [0; 4];
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadQuadletRequest,
             CSR_REGISTER_BASE + FIRMWARE_ID_OFFSET,
@@ -43,7 +46,8 @@ impl OxfordProtocol {
         timeout_ms: u32,
     ) -> Result<(), Error> {
         let mut quadlet = [0; 4];
-        req.transaction_sync(
+        transaction_sync(
+            req,
             node,
             FwTcode::ReadQuadletRequest,
             CSR_REGISTER_BASE + HARDWARE_ID_OFFSET,
@@ -22,6 +22,11 @@ use alsactl::{CardExt, CardExtManual, ElemValueExtManual};
 use core::RuntimeOperation;
 use core::dispatcher;
 use core::card_cntr;
+use core::version::*;
+use core::event_queue::*;
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 use ieee1212_config_rom::ConfigRom;
 use ta1394::config_rom::Ta1394ConfigRom;
@@ -32,6 +37,7 @@ use std::convert::TryFrom;
 
 enum Event {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((alsactl::ElemId, alsactl::ElemEventMask)),
@@ -39,11 +45,26 @@ enum Event {
     StreamLock(bool),
 }
 
+impl CoalescedEvent for Event {
+    fn value_changed_elem_id(&self) -> Option<&alsactl::ElemId> {
+        match self {
+            Event::Elem((elem_id, events)) if *events == alsactl::ElemEventMask::VALUE => {
+                Some(elem_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, Event::Timer)
+    }
+}
+
 pub struct OxfwRuntime {
     unit: hinawa::SndUnit,
     model: OxfwModel,
     card_cntr: card_cntr::CardCntr,
-    rx: mpsc::Receiver<Event>,
+    rx: EventQueue<Event>,
     tx: mpsc::SyncSender<Event>,
     dispatchers: Vec<dispatcher::Dispatcher>,
     timer: Option<dispatcher::Dispatcher>,
@@ -96,6 +117,7 @@ impl<'a> RuntimeOperation<u32> for OxfwRuntime {
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(OxfwRuntime {
             unit,
@@ -120,6 +142,12 @@ impl<'a> RuntimeOperation<u32> for OxfwRuntime {
             let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         }
 
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            oxfw_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -132,9 +160,10 @@ impl<'a> RuntimeOperation<u32> for OxfwRuntime {
 
             match ev {
                 Event::Shutdown => break,
+                Event::Reload => self.reload(),
                 Event::Disconnected => break,
                 Event::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 Event::Elem((elem_id, events)) => {
                     if elem_id.get_name() != Self::TIMER_NAME {
@@ -165,6 +194,16 @@ impl<'a> RuntimeOperation<u32> for OxfwRuntime {
     }
 }
 
+impl ConfigReloadOperation for OxfwRuntime {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> OxfwRuntime {
     const NODE_DISPATCHER_NAME: &'a str = "node event dispatcher";
     const SYSTEM_DISPATCHER_NAME: &'a str = "system event dispatcher";
@@ -219,6 +258,11 @@ impl<'a> OxfwRuntime {
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(Event::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr.card.connect_handle_elem_event(move |_, elem_id, events| {
@@ -233,7 +277,7 @@ impl<'a> OxfwRuntime {
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = dispatcher::Dispatcher::run(Self::TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(Self::TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(Self::TIMER_INTERVAL), move || {
             let _ = tx.send(Event::Timer);
             source::Continue(true)
         });
@@ -11,6 +11,8 @@ use glib::{Error, error::ErrorDomain, Quark};
 
 use hinawa::{FwFcp, FwFcpExtManual};
 
+use fw_transaction::effective_timeout_ms;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum AvcSubunitType {
     Monitor,
@@ -330,6 +332,7 @@ pub trait Ta1394Avc : AsRef<FwFcp> {
         cmd.extend_from_slice(operands);
 
         let mut resp = vec![0;Self::FRAME_SIZE];
+        let timeout_ms = effective_timeout_ms(timeout_ms);
         let len = self.as_ref().avc_transaction(&cmd, &mut resp, timeout_ms)?;
         resp.truncate(len);
 
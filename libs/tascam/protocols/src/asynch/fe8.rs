@@ -58,6 +58,14 @@ impl MachineStateOperation for Fe8Protocol {
         MachineItem::Mute(5),
         MachineItem::Mute(6),
         MachineItem::Mute(7),
+        MachineItem::FaderTouch(0),
+        MachineItem::FaderTouch(1),
+        MachineItem::FaderTouch(2),
+        MachineItem::FaderTouch(3),
+        MachineItem::FaderTouch(4),
+        MachineItem::FaderTouch(5),
+        MachineItem::FaderTouch(6),
+        MachineItem::FaderTouch(7),
     ];
 
     const U16_ITEMS: &'static [MachineItem] = &[
@@ -96,7 +104,7 @@ impl SurfaceImageOperation<Fe8SurfaceState> for Fe8Protocol {
     }
 
     fn decode_surface_image(
-        state: &Fe8SurfaceState,
+        state: &mut Fe8SurfaceState,
         image: &[u32],
         index: u32,
         before: u32,
@@ -106,7 +114,7 @@ impl SurfaceImageOperation<Fe8SurfaceState> for Fe8Protocol {
 
         Self::decode_surface_image_common(
             &mut machine_values,
-            &state.common,
+            &mut state.common,
             image,
             index,
             before,
@@ -142,6 +150,10 @@ impl SurfaceImageOperation<Fe8SurfaceState> for Fe8Protocol {
     ) -> Result<(), Error> {
         clear_leds(&mut state.led_state, req, node, timeout_ms)
     }
+
+    fn invalidate_surface(state: &mut Fe8SurfaceState) {
+        state.led_state.invalidate();
+    }
 }
 
 impl SurfaceImageCommonOperation for Fe8Protocol {
@@ -7,10 +7,61 @@
 //! 1394 Trading Association. This module includes the parse of unit directory in the
 //! configuration ROM.
 
-use glib::{Error, FileError};
-
 use ieee1212_config_rom::{entry::*, *};
 
+/// The value of specifier ID for Tascam.
+pub const TASCAM_OUI: u32 = 0x00022e;
+
+/// The value of version for FW-1884.
+pub const FW1884_SW_VERSION: u32 = 0x800000;
+/// The value of version for FE-8.
+pub const FE8_SW_VERSION: u32 = 0x800001;
+/// The value of version for FW-1082.
+pub const FW1082_SW_VERSION: u32 = 0x800003;
+/// The value of version for FW-1804.
+pub const FW1804_SW_VERSION: u32 = 0x800004;
+
+const SUPPORTED_SW_VERSIONS: [u32; 4] = [
+    FW1884_SW_VERSION,
+    FE8_SW_VERSION,
+    FW1082_SW_VERSION,
+    FW1804_SW_VERSION,
+];
+
+/// The enumeration for error to identify the unit by configuration ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TascamConfigRomError {
+    /// The content of configuration ROM is not parsed.
+    UnreadableRom(ConfigRomParseError),
+    /// The specifier ID in unit directory is not for Tascam.
+    VendorMismatch { found: u32 },
+    /// The version in unit directory is not for supported models.
+    UnknownModel { model_id: u32 },
+    /// The entry is missing or has unexpected type.
+    MalformedEntry { key: KeyType },
+}
+
+impl std::fmt::Display for TascamConfigRomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnreadableRom(cause) => {
+                write!(f, "Malformed configuration ROM detected: {}", cause)
+            }
+            Self::VendorMismatch { found } => {
+                write!(f, "Unexpected specifier ID 0x{:06x}, not for Tascam", found)
+            }
+            Self::UnknownModel { model_id } => write!(f, "Unsupported version 0x{:06x}", model_id),
+            Self::MalformedEntry { key } => write!(f, "{:?} entry is missing or malformed", key),
+        }
+    }
+}
+
+impl From<ConfigRomParseError> for TascamConfigRomError {
+    fn from(cause: ConfigRomParseError) -> Self {
+        Self::UnreadableRom(cause)
+    }
+}
+
 /// The structure for data in unit directory of configuration ROM.
 #[derive(Default, Debug, Clone, Copy)]
 pub struct UnitData<'a> {
@@ -22,44 +73,68 @@ pub struct UnitData<'a> {
 
 /// The trait for parser of configuration ROM.
 pub trait TascamConfigRom<'a> {
-    fn get_unit_data(&'a self) -> Result<UnitData<'a>, Error>;
+    fn get_unit_data(&'a self) -> Result<UnitData<'a>, TascamConfigRomError>;
+
+    /// Check the data in unit directory and detect unsupported models.
+    fn identify_model(&'a self) -> Result<UnitData<'a>, TascamConfigRomError> {
+        let unit_data = self.get_unit_data()?;
+        if SUPPORTED_SW_VERSIONS.contains(&unit_data.version) {
+            Ok(unit_data)
+        } else {
+            Err(TascamConfigRomError::UnknownModel {
+                model_id: unit_data.version,
+            })
+        }
+    }
 }
 
 impl<'a> TascamConfigRom<'a> for ConfigRom<'a> {
-    fn get_unit_data(&'a self) -> Result<UnitData<'a>, Error> {
+    fn get_unit_data(&'a self) -> Result<UnitData<'a>, TascamConfigRomError> {
         let unit_directory = self
             .root
             .iter()
             .find_map(|entry| EntryDataAccess::<&[Entry]>::get(entry, KeyType::Unit))
-            .ok_or(Error::new(FileError::Nxio, "Unit directory is missing"))?;
+            .ok_or(TascamConfigRomError::MalformedEntry { key: KeyType::Unit })?;
 
         let &specifier_id = unit_directory
             .iter()
             .find_map(|entry| EntryDataAccess::<&u32>::get(entry, KeyType::SpecifierId))
-            .ok_or(Error::new(FileError::Nxio, "Specifier ID is missing"))?;
+            .ok_or(TascamConfigRomError::MalformedEntry {
+                key: KeyType::SpecifierId,
+            })?;
+        if specifier_id != TASCAM_OUI {
+            Err(TascamConfigRomError::VendorMismatch {
+                found: specifier_id,
+            })?;
+        }
 
         let &version = unit_directory
             .iter()
             .find_map(|entry| EntryDataAccess::<&u32>::get(entry, KeyType::Version))
-            .ok_or(Error::new(FileError::Nxio, "Version is missing"))?;
+            .ok_or(TascamConfigRomError::MalformedEntry {
+                key: KeyType::Version,
+            })?;
 
         let dependent_info_directory = unit_directory
             .iter()
             .find_map(|entry| EntryDataAccess::<&[Entry]>::get(entry, KeyType::DependentInfo))
-            .ok_or(Error::new(
-                FileError::Nxio,
-                "Dependent information directory is missing",
-            ))?;
+            .ok_or(TascamConfigRomError::MalformedEntry {
+                key: KeyType::DependentInfo,
+            })?;
 
         let vendor_name = dependent_info_directory
             .iter()
             .find_map(|entry| EntryDataAccess::<&str>::get(entry, KeyType::Descriptor))
-            .ok_or(Error::new(FileError::Nxio, "Vendor name is missing"))?;
+            .ok_or(TascamConfigRomError::MalformedEntry {
+                key: KeyType::Descriptor,
+            })?;
 
         let model_name = dependent_info_directory
             .iter()
             .find_map(|entry| EntryDataAccess::<&str>::get(entry, KeyType::BusDependentInfo))
-            .ok_or(Error::new(FileError::Nxio, "Model name is missing"))?;
+            .ok_or(TascamConfigRomError::MalformedEntry {
+                key: KeyType::BusDependentInfo,
+            })?;
 
         Ok(UnitData {
             specifier_id,
@@ -69,3 +144,116 @@ impl<'a> TascamConfigRom<'a> for ConfigRom<'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    const FW1884_CONFIG_ROM: [u8; 104] = [
+        0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0x00, 0xff, 0x50, 0x02, 0x00, 0x02, 0x2e,
+        0xff, 0x12, 0x34, 0x56, 0x78, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x02, 0x2e, 0x0c, 0x00,
+        0x83, 0xc0, 0xd1, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x12, 0x00, 0x02, 0x2e, 0x13,
+        0x80, 0x00, 0x00, 0xd4, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0x02,
+        0x82, 0x00, 0x00, 0x06, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x54, 0x41, 0x53, 0x43, 0x41, 0x4d, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x57, 0x2d, 0x31, 0x38, 0x38, 0x34, 0x00,
+    ];
+
+    const FW1082_CONFIG_ROM: [u8; 104] = [
+        0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0x00, 0xff, 0x50, 0x02, 0x00, 0x02, 0x2e,
+        0xff, 0x12, 0x34, 0x56, 0x78, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x02, 0x2e, 0x0c, 0x00,
+        0x83, 0xc0, 0xd1, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x12, 0x00, 0x02, 0x2e, 0x13,
+        0x80, 0x00, 0x03, 0xd4, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0x02,
+        0x82, 0x00, 0x00, 0x06, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x54, 0x41, 0x53, 0x43, 0x41, 0x4d, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x57, 0x2d, 0x31, 0x30, 0x38, 0x32, 0x00,
+    ];
+
+    const FE8_CONFIG_ROM: [u8; 100] = [
+        0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0x00, 0xff, 0x50, 0x02, 0x00, 0x02, 0x2e,
+        0xff, 0x12, 0x34, 0x56, 0x78, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x02, 0x2e, 0x0c, 0x00,
+        0x83, 0xc0, 0xd1, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x12, 0x00, 0x02, 0x2e, 0x13,
+        0x80, 0x00, 0x01, 0xd4, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0x02,
+        0x82, 0x00, 0x00, 0x06, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x54, 0x41, 0x53, 0x43, 0x41, 0x4d, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x45, 0x2d, 0x38,
+    ];
+
+    // The version in unit directory is not in the list of supported models.
+    const IF_FW_DM_CONFIG_ROM: [u8; 104] = [
+        0x04, 0x04, 0x00, 0x00, 0x31, 0x33, 0x39, 0x34, 0x00, 0xff, 0x50, 0x02, 0x00, 0x02, 0x2e,
+        0xff, 0x12, 0x34, 0x56, 0x78, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x02, 0x2e, 0x0c, 0x00,
+        0x83, 0xc0, 0xd1, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x12, 0x00, 0x02, 0x2e, 0x13,
+        0x80, 0x00, 0x02, 0xd4, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x81, 0x00, 0x00, 0x02,
+        0x82, 0x00, 0x00, 0x06, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x54, 0x41, 0x53, 0x43, 0x41, 0x4d, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x49, 0x46, 0x2d, 0x46, 0x57, 0x2f, 0x44, 0x4d,
+    ];
+
+    #[test]
+    fn test_supported_models() {
+        [
+            (&FW1884_CONFIG_ROM[..], FW1884_SW_VERSION, "FW-1884"),
+            (&FW1082_CONFIG_ROM[..], FW1082_SW_VERSION, "FW-1082"),
+            (&FE8_CONFIG_ROM[..], FE8_SW_VERSION, "FE-8"),
+        ]
+        .iter()
+        .for_each(|&(raw, version, model_name)| {
+            let config_rom = ConfigRom::try_from(raw).unwrap();
+            let unit_data = config_rom.identify_model().unwrap();
+            assert_eq!(unit_data.specifier_id, TASCAM_OUI);
+            assert_eq!(unit_data.version, version);
+            assert_eq!(unit_data.vendor_name, "TASCAM");
+            assert_eq!(unit_data.model_name, model_name);
+        });
+    }
+
+    #[test]
+    fn test_unsupported_model() {
+        let config_rom = ConfigRom::try_from(&IF_FW_DM_CONFIG_ROM[..]).unwrap();
+        let unit_data = config_rom.get_unit_data().unwrap();
+        assert_eq!(unit_data.model_name, "IF-FW/DM");
+        assert_eq!(
+            config_rom.identify_model().unwrap_err(),
+            TascamConfigRomError::UnknownModel { model_id: 0x800002 }
+        );
+    }
+
+    #[test]
+    fn test_vendor_mismatch() {
+        let mut raw = FW1884_CONFIG_ROM;
+        // The specifier ID in unit directory.
+        raw[41..44].copy_from_slice(&[0x00, 0x01, 0xf2]);
+        let config_rom = ConfigRom::try_from(&raw[..]).unwrap();
+        assert_eq!(
+            config_rom.identify_model().unwrap_err(),
+            TascamConfigRomError::VendorMismatch { found: 0x0001f2 }
+        );
+    }
+
+    #[test]
+    fn test_malformed_entry() {
+        let mut raw = FW1884_CONFIG_ROM;
+        // Replace key of version entry in unit directory with model.
+        raw[44] = 0x17;
+        let config_rom = ConfigRom::try_from(&raw[..]).unwrap();
+        assert_eq!(
+            config_rom.identify_model().unwrap_err(),
+            TascamConfigRomError::MalformedEntry {
+                key: KeyType::Version
+            }
+        );
+    }
+
+    #[test]
+    fn test_unreadable_rom() {
+        // The length of bus information block exceeds the data.
+        let mut raw = FW1884_CONFIG_ROM;
+        raw[0] = 0xff;
+        let err = ConfigRom::try_from(&raw[..])
+            .map_err(TascamConfigRomError::from)
+            .unwrap_err();
+        assert!(matches!(err, TascamConfigRomError::UnreadableRom(_)));
+    }
+}
@@ -70,6 +70,12 @@ pub struct IsochMeterState {
     pub monitor_meters: [i32; 2],
     pub analog_mixer_meters: [i32; 2],
     pub monitor_mode: MonitorMode,
+    /// The thresholds of input gain for signal detection and over-level detection, if known.
+    pub detection_thresholds: Option<(u16, u16)>,
+    /// Whether signal is detected in each analog input.
+    pub signal_detections: Vec<bool>,
+    /// Whether over-level is detected in each analog input.
+    pub over_level_detections: Vec<bool>,
 }
 
 /// The trait for meter operation.
@@ -86,6 +92,9 @@ pub trait IsochMeterOperation {
     const LEVEL_MAX: i32 = 0x7fffff00;
     const LEVEL_STEP: i32 = 0x100;
 
+    /// The number of analog inputs, placed at the beginning of inputs.
+    const ANALOG_INPUT_COUNT: usize = 8;
+
     fn create_meter_state() -> IsochMeterState {
         IsochMeterState {
             monitor: Default::default(),
@@ -101,6 +110,9 @@ pub trait IsochMeterOperation {
             monitor_meters: Default::default(),
             analog_mixer_meters: Default::default(),
             monitor_mode: Default::default(),
+            detection_thresholds: Default::default(),
+            signal_detections: vec![Default::default(); Self::ANALOG_INPUT_COUNT],
+            over_level_detections: vec![Default::default(); Self::ANALOG_INPUT_COUNT],
         }
     }
 
@@ -187,8 +199,37 @@ pub trait IsochMeterOperation {
             };
         }
 
+        Self::detect_analog_inputs(state);
+
         Ok(())
     }
+
+    /// Detect signal and over-level in analog inputs by comparison of the meter level with the
+    /// thresholds. Both of them are linear to the amplitude, thus the threshold between 1 and
+    /// 0x7fff is compared with the upper 16 bits of level. Nothing is detected without the
+    /// thresholds.
+    fn detect_analog_inputs(state: &mut IsochMeterState) {
+        let thresholds = state.detection_thresholds;
+        let levels = &state.inputs[..Self::ANALOG_INPUT_COUNT];
+        state
+            .signal_detections
+            .iter_mut()
+            .zip(state.over_level_detections.iter_mut())
+            .zip(levels)
+            .for_each(|((signal, over_level), &level)| {
+                let val = (level >> 16) as u16;
+                match thresholds {
+                    Some((signal_threshold, over_level_threshold)) => {
+                        *signal = val >= signal_threshold;
+                        *over_level = val >= over_level_threshold;
+                    }
+                    None => {
+                        *signal = false;
+                        *over_level = false;
+                    }
+                }
+            });
+    }
 }
 
 fn read_config_flag<T: Copy + Eq>(
@@ -713,7 +754,8 @@ pub trait IsochRackOperation {
 #[derive(Default, Debug)]
 struct SurfaceIsochState {
     shifted: bool,
-    shifted_items: Vec<bool>,
+    /// The layer in which each shifted item was pressed, till released.
+    pressed_layers: Vec<Option<usize>>,
     bank: u16,
 }
 
@@ -725,13 +767,13 @@ trait SurfaceImageIsochOperation {
 
     fn initialize_surface_isoch_state(state: &mut SurfaceIsochState) {
         state.shifted = false;
-        state.shifted_items = vec![false; Self::SHIFTED_ITEMS.len()];
+        state.pressed_layers = vec![None; Self::SHIFTED_ITEMS.len()];
         state.bank = 0;
     }
 
     fn decode_surface_image_isoch(
         machine_values: &mut Vec<(MachineItem, ItemValue)>,
-        state: &SurfaceIsochState,
+        state: &mut SurfaceIsochState,
         index: u32,
         before: u32,
         after: u32,
@@ -744,26 +786,21 @@ trait SurfaceImageIsochOperation {
             state.shifted
         };
 
-        if shifted != state.shifted {
-            let prev_idx = state.shifted as usize;
-            let curr_idx = shifted as usize;
-
-            Self::SHIFTED_ITEMS
-                .iter()
-                .zip(state.shifted_items.iter())
-                .filter(|(_, &s)| s)
-                .for_each(|((_, pairs), _)| {
-                    machine_values.push((pairs[prev_idx], ItemValue::Bool(false)));
-                    machine_values.push((pairs[curr_idx], ItemValue::Bool(true)));
-                });
-        }
-
+        // The release of item is reported for the layer in which the item was pressed, even if
+        // the shift item is operated in the meantime.
         Self::SHIFTED_ITEMS
             .iter()
-            .filter(|(bool_val, _)| detect_bool_action(bool_val, index, before, after))
-            .for_each(|(bool_val, pairs)| {
+            .zip(state.pressed_layers.iter_mut())
+            .filter(|((bool_val, _), _)| detect_bool_action(bool_val, index, before, after))
+            .for_each(|((bool_val, pairs), pressed_layer)| {
                 let value = detect_bool_value(bool_val, before);
-                machine_values.push((pairs[shifted as usize], ItemValue::Bool(value)));
+                let layer = if value {
+                    *pressed_layer = Some(shifted as usize);
+                    shifted as usize
+                } else {
+                    pressed_layer.take().unwrap_or(shifted as usize)
+                };
+                machine_values.push((pairs[layer], ItemValue::Bool(value)));
             });
 
         Self::BANK_CURSORS
@@ -822,3 +859,54 @@ trait SurfaceBankLedOperation {
             })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestProtocol;
+
+    impl IsochMeterOperation for TestProtocol {
+        const INPUT_COUNT: usize = 10;
+        const OUTPUT_COUNT: usize = 4;
+        const HAS_SOLO: bool = true;
+    }
+
+    #[test]
+    fn test_analog_input_detection() {
+        let mut state = TestProtocol::create_meter_state();
+        let mut image = vec![0u32; 64];
+        image[16] = 0x7fffff00;
+        image[17] = 0x10000000;
+        image[18] = 0x00ffff00;
+        // The digital input is out of detection.
+        image[40] = 0x7fffff00;
+
+        // Nothing is detected without the thresholds.
+        TestProtocol::parse_meter_state(&mut state, &image).unwrap();
+        assert_eq!(state.signal_detections, vec![false; 8]);
+        assert_eq!(state.over_level_detections, vec![false; 8]);
+
+        state.detection_thresholds = Some((0x0100, 0x7fff));
+        TestProtocol::parse_meter_state(&mut state, &image).unwrap();
+        assert_eq!(
+            state.signal_detections,
+            vec![true, true, false, false, false, false, false, false]
+        );
+        assert_eq!(
+            state.over_level_detections,
+            vec![true, false, false, false, false, false, false, false]
+        );
+
+        state.detection_thresholds = Some((0x00ff, 0x1000));
+        TestProtocol::parse_meter_state(&mut state, &image).unwrap();
+        assert_eq!(
+            state.signal_detections,
+            vec![true, true, true, false, false, false, false, false]
+        );
+        assert_eq!(
+            state.over_level_detections,
+            vec![true, true, false, false, false, false, false, false]
+        );
+    }
+}
@@ -102,6 +102,15 @@ impl MachineStateOperation for Fw1082Protocol {
         MachineItem::Recall,
         MachineItem::Panel,
         MachineItem::Shift,
+        MachineItem::FaderTouch(0),
+        MachineItem::FaderTouch(1),
+        MachineItem::FaderTouch(2),
+        MachineItem::FaderTouch(3),
+        MachineItem::FaderTouch(4),
+        MachineItem::FaderTouch(5),
+        MachineItem::FaderTouch(6),
+        MachineItem::FaderTouch(7),
+        MachineItem::FaderTouch(8),
     ];
 
     const U16_ITEMS: &'static [MachineItem] = &[
@@ -151,7 +160,7 @@ impl SurfaceImageOperation<Fw1082SurfaceState> for Fw1082Protocol {
     }
 
     fn decode_surface_image(
-        state: &Fw1082SurfaceState,
+        state: &mut Fw1082SurfaceState,
         image: &[u32],
         index: u32,
         before: u32,
@@ -161,14 +170,20 @@ impl SurfaceImageOperation<Fw1082SurfaceState> for Fw1082Protocol {
 
         Self::decode_surface_image_common(
             &mut machine_values,
-            &state.common,
+            &mut state.common,
             image,
             index,
             before,
             after,
         );
 
-        Self::decode_surface_image_isoch(&mut machine_values, &state.isoch, index, before, after);
+        Self::decode_surface_image_isoch(
+            &mut machine_values,
+            &mut state.isoch,
+            index,
+            before,
+            after,
+        );
 
         Self::decode_surface_image_specific(
             &mut machine_values,
@@ -270,6 +285,10 @@ impl SurfaceImageOperation<Fw1082SurfaceState> for Fw1082Protocol {
     ) -> Result<(), Error> {
         clear_leds(&mut state.led_state, req, node, timeout_ms)
     }
+
+    fn invalidate_surface(state: &mut Fw1082SurfaceState) {
+        state.led_state.invalidate();
+    }
 }
 
 impl SurfaceImageCommonOperation for Fw1082Protocol {
@@ -213,6 +213,15 @@ impl MachineStateOperation for Fw1884Protocol {
         MachineItem::Undo,
         MachineItem::Shift,
         MachineItem::Ctrl,
+        MachineItem::FaderTouch(0),
+        MachineItem::FaderTouch(1),
+        MachineItem::FaderTouch(2),
+        MachineItem::FaderTouch(3),
+        MachineItem::FaderTouch(4),
+        MachineItem::FaderTouch(5),
+        MachineItem::FaderTouch(6),
+        MachineItem::FaderTouch(7),
+        MachineItem::FaderTouch(8),
     ];
 
     const U16_ITEMS: &'static [MachineItem] = &[
@@ -241,6 +250,8 @@ impl MachineStateOperation for Fw1884Protocol {
 
     const HAS_TRANSPORT: bool = true;
     const HAS_BANK: bool = true;
+
+    const AUX_BUS_COUNT: usize = 8;
 }
 
 /// The structure for state of control surface in FW-1884.
@@ -258,7 +269,7 @@ impl SurfaceImageOperation<Fw1884SurfaceState> for Fw1884Protocol {
     }
 
     fn decode_surface_image(
-        state: &Fw1884SurfaceState,
+        state: &mut Fw1884SurfaceState,
         image: &[u32],
         index: u32,
         before: u32,
@@ -268,14 +279,20 @@ impl SurfaceImageOperation<Fw1884SurfaceState> for Fw1884Protocol {
 
         Self::decode_surface_image_common(
             &mut machine_values,
-            &state.common,
+            &mut state.common,
             image,
             index,
             before,
             after,
         );
 
-        Self::decode_surface_image_isoch(&mut machine_values, &state.isoch, index, before, after);
+        Self::decode_surface_image_isoch(
+            &mut machine_values,
+            &mut state.isoch,
+            index,
+            before,
+            after,
+        );
 
         machine_values
     }
@@ -311,6 +328,61 @@ impl SurfaceImageOperation<Fw1884SurfaceState> for Fw1884Protocol {
     ) -> Result<(), Error> {
         clear_leds(&mut state.led_state, req, node, timeout_ms)
     }
+
+    fn invalidate_surface(state: &mut Fw1884SurfaceState) {
+        state.led_state.invalidate();
+    }
+}
+
+impl Fw1884Protocol {
+    /// Save the state of REC LEDs, then use them temporarily to express the given states. The
+    /// change of REC items is deferred till restored.
+    pub fn overlay_rec_leds(
+        state: &mut Fw1884SurfaceState,
+        enables: &[bool],
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let positions: Vec<u16> = (0..enables.len())
+            .filter_map(|ch| Self::find_normal_led_pos(&MachineItem::Rec(ch)))
+            .collect();
+        push_leds(&mut state.led_state, &positions);
+
+        positions
+            .iter()
+            .zip(enables.iter())
+            .try_for_each(|(&pos, &enable)| {
+                operate_led_overlaid(&mut state.led_state, req, node, pos, enable, timeout_ms)
+            })
+    }
+
+    /// Operate REC LED for the channel during overlay.
+    pub fn operate_overlaid_rec_led(
+        state: &mut Fw1884SurfaceState,
+        ch: usize,
+        enable: bool,
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        match Self::find_normal_led_pos(&MachineItem::Rec(ch)) {
+            Some(pos) => {
+                operate_led_overlaid(&mut state.led_state, req, node, pos, enable, timeout_ms)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Restore the state of REC LEDs saved at overlay.
+    pub fn restore_rec_leds(
+        state: &mut Fw1884SurfaceState,
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        pop_leds(&mut state.led_state, req, node, timeout_ms)
+    }
 }
 
 impl SurfaceImageCommonOperation for Fw1884Protocol {
@@ -592,3 +664,69 @@ impl SurfaceNormalLedOperation for Fw1884Protocol {
 impl SurfaceBankLedOperation for Fw1884Protocol {
     const BANK_LEDS: [&'static [u16]; 4] = [&[127, 140], &[159, 172], &[191, 204], &[223, 236]];
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shifted_items() {
+        let mut state = Fw1884SurfaceState::default();
+        Fw1884Protocol::initialize_surface_state(&mut state);
+        let image = [0u32; 64];
+
+        let decode = |state: &mut Fw1884SurfaceState, index, before, after| {
+            let values = Fw1884Protocol::decode_surface_image(state, &image, index, before, after);
+            values.iter().for_each(|value| {
+                Fw1884Protocol::feedback_to_surface_isoch(&mut state.isoch, value)
+            });
+            values
+        };
+
+        // The item of Save/F1 button without shift.
+        assert_eq!(
+            decode(&mut state, 7, 0x02000000, 0),
+            vec![(MachineItem::Save, ItemValue::Bool(true))]
+        );
+        assert_eq!(
+            decode(&mut state, 7, 0, 0x02000000),
+            vec![(MachineItem::Save, ItemValue::Bool(false))]
+        );
+
+        // The item of Save/F1 button with shift.
+        assert_eq!(
+            decode(&mut state, 7, 0x80000000, 0),
+            vec![(MachineItem::Shift, ItemValue::Bool(true))]
+        );
+        assert_eq!(
+            decode(&mut state, 7, 0x02000000, 0),
+            vec![(MachineItem::Func(0), ItemValue::Bool(true))]
+        );
+
+        // The release is reported for the item pressed with shift even if shift is released
+        // before.
+        assert_eq!(
+            decode(&mut state, 7, 0, 0x80000000),
+            vec![(MachineItem::Shift, ItemValue::Bool(false))]
+        );
+        assert_eq!(
+            decode(&mut state, 7, 0, 0x02000000),
+            vec![(MachineItem::Func(0), ItemValue::Bool(false))]
+        );
+
+        // The item pressed without shift is released without shift even if shift is pressed
+        // before.
+        assert_eq!(
+            decode(&mut state, 8, 0x00000001, 0),
+            vec![(MachineItem::Revert, ItemValue::Bool(true))]
+        );
+        assert_eq!(
+            decode(&mut state, 7, 0x80000000, 0),
+            vec![(MachineItem::Shift, ItemValue::Bool(true))]
+        );
+        assert_eq!(
+            decode(&mut state, 8, 0, 0x00000001),
+            vec![(MachineItem::Revert, ItemValue::Bool(false))]
+        );
+    }
+}
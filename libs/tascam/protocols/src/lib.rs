@@ -68,7 +68,12 @@ pub mod config_rom;
 
 use glib::Error;
 
-use hinawa::{FwNode, FwReq, FwReqExtManual, FwTcode};
+use hinawa::{FwNode, FwReq};
+
+use fw_transaction::FwTransaction;
+
+/// The version of this crate, to be exposed by runtime.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const BASE_OFFSET: u64 = 0xffff00000000;
 const HW_INFO_REGISTER_OFFSET: u64 = 0x00;
@@ -84,14 +89,16 @@ fn read_quadlet(
     frames: &mut [u8],
     timeout_ms: u32,
 ) -> Result<(), Error> {
-    req.transaction_sync(
-        node,
-        FwTcode::ReadQuadletRequest,
-        BASE_OFFSET + offset,
-        4,
-        frames,
-        timeout_ms,
-    )
+    read_quadlet_by(&mut (&*req, &*node), offset, frames, timeout_ms)
+}
+
+fn read_quadlet_by<T: FwTransaction>(
+    tx: &mut T,
+    offset: u64,
+    frames: &mut [u8],
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    tx.read(BASE_OFFSET + offset, &mut frames[..4], timeout_ms)
 }
 
 fn write_quadlet(
@@ -101,14 +108,16 @@ fn write_quadlet(
     frames: &mut [u8],
     timeout_ms: u32,
 ) -> Result<(), Error> {
-    req.transaction_sync(
-        node,
-        FwTcode::WriteQuadletRequest,
-        BASE_OFFSET + offset,
-        4,
-        frames,
-        timeout_ms,
-    )
+    write_quadlet_by(&mut (&*req, node), offset, frames, timeout_ms)
+}
+
+fn write_quadlet_by<T: FwTransaction>(
+    tx: &mut T,
+    offset: u64,
+    frames: &mut [u8],
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    tx.write(BASE_OFFSET + offset, &mut frames[..4], timeout_ms)
 }
 
 /// The structure of hardware information.
@@ -158,6 +167,7 @@ pub enum MachineItem {
     Solo(usize),
     Mute(usize),
     Input(usize),
+    FaderTouch(usize),
     Func(usize),
     Pfl,
 
@@ -186,6 +196,7 @@ pub enum MachineItem {
     Flip,
     Pan, // has bool value in FW-1884, has u16 value in FW-1082.
     Aux(usize),
+    AuxSend(usize, usize), // bus, channel. The rotary during aux mode in FW-1884.
     EncoderMode, // FW-1082 only.
 
     // Equalizer section.
@@ -233,6 +244,16 @@ impl Default for MachineItem {
     }
 }
 
+impl MachineItem {
+    /// Whether the item is indicator driven by the device, instead of button operated by user.
+    ///
+    /// The detection of signal and over-level for analog inputs is derived from the meter instead,
+    /// in isoch::IsochMeterState.
+    pub fn is_device_indicator(&self) -> bool {
+        matches!(self, Self::Ol(_) | Self::Signal(_))
+    }
+}
+
 impl std::fmt::Display for MachineItem {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -241,10 +262,12 @@ impl std::fmt::Display for MachineItem {
             Self::Rec(ch) => write!(f, "rec {}", ch),
             Self::Signal(ch) => write!(f, "signal {}", ch),
             Self::Rotary(ch) => write!(f, "rotary {}", ch),
+            Self::AuxSend(bus, ch) => write!(f, "aux send {} {}", bus, ch),
             Self::Select(ch) => write!(f, "select {}", ch),
             Self::Solo(ch) => write!(f, "solo {}", ch),
             Self::Mute(ch) => write!(f, "mute {}", ch),
             Self::Input(ch) => write!(f, "input {}", ch),
+            Self::FaderTouch(ch) => write!(f, "fader touch {}", ch),
             Self::Func(ch) => write!(f, "func {}", ch),
             Self::Pfl => write!(f, "pfl"),
             Self::Read => write!(f, "read"),
@@ -315,6 +338,20 @@ pub struct MachineState {
     bank: u16,
     /// One of Rew, Fwd, Stop, Play, and Record.
     transport: MachineItem,
+    /// Whether any of solo items is enabled.
+    any_solo: bool,
+    /// Whether any of mute items is enabled.
+    any_mute: bool,
+    /// The aux bus selected for rotaries.
+    aux_bus: Option<usize>,
+    /// The value of aux send for each channel in each aux bus.
+    aux_sends: Vec<u16>,
+    /// Whether select items act as radio buttons for the selected channel.
+    select_follow: bool,
+    /// The selected channel. The index next to the last channel is for master.
+    selected_channel: usize,
+    /// Whether clear solo item clears all of solo items and indicates any of them enabled.
+    clear_solo: bool,
 }
 
 /// The event of state machine.
@@ -324,9 +361,197 @@ pub enum ItemValue {
     U16(u16),
 }
 
+/// The curve to scale u16 value of machine item.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ItemValueCurve {
+    /// y = x.
+    Linear,
+    /// y = log10(1 + 9x), rapid change at lower range.
+    Log,
+    /// y = x^2, rapid change at higher range, like audio taper.
+    Taper,
+}
+
+impl Default for ItemValueCurve {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// The descriptor to scale u16 value of machine item into the range of value for application,
+/// such as 7 bit value of MIDI control change, 14 bit value of pitch bend.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ItemValueScale {
+    /// The maximum of u16 value from the surface, e.g. 1023 for faders.
+    pub input_max: u16,
+    /// The minimum of value for application.
+    pub output_min: i32,
+    /// The maximum of value for application.
+    pub output_max: i32,
+    /// The curve between both ranges.
+    pub curve: ItemValueCurve,
+}
+
+impl ItemValueScale {
+    /// Scale the u16 value of machine item into the value for application.
+    pub fn to_output(&self, value: u16) -> i32 {
+        let x = (value.min(self.input_max) as f64) / (self.input_max.max(1) as f64);
+        let y = match self.curve {
+            ItemValueCurve::Linear => x,
+            ItemValueCurve::Log => (1.0 + 9.0 * x).log10(),
+            ItemValueCurve::Taper => x * x,
+        };
+        let range = (self.output_max - self.output_min) as f64;
+        self.output_min + (y * range).round() as i32
+    }
+
+    /// Scale the value from application into u16 value of machine item, as inverse of
+    /// `to_output()`.
+    pub fn to_input(&self, value: i32) -> u16 {
+        let range = (self.output_max - self.output_min) as f64;
+        let y = if range > 0.0 {
+            (((value - self.output_min) as f64) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let x = match self.curve {
+            ItemValueCurve::Linear => y,
+            ItemValueCurve::Log => (10f64.powf(y) - 1.0) / 9.0,
+            ItemValueCurve::Taper => y.sqrt(),
+        };
+        (x * self.input_max as f64).round() as u16
+    }
+}
+
+/// The device ID of MIDI Machine Control (MMC) to address all of devices.
+pub const MMC_DEVICE_ID_ALL_CALL: u8 = 0x7f;
+
+const MMC_STOP: u8 = 0x01;
+const MMC_PLAY: u8 = 0x02;
+const MMC_DEFERRED_PLAY: u8 = 0x03;
+const MMC_FAST_FORWARD: u8 = 0x04;
+const MMC_REWIND: u8 = 0x05;
+const MMC_RECORD_STROBE: u8 = 0x06;
+
+const MMC_TRANSPORT_CMDS: [(MachineItem, u8); 5] = [
+    (MachineItem::Stop, MMC_STOP),
+    (MachineItem::Play, MMC_PLAY),
+    (MachineItem::Fwd, MMC_FAST_FORWARD),
+    (MachineItem::Rew, MMC_REWIND),
+    (MachineItem::Record, MMC_RECORD_STROBE),
+];
+
+/// Build system exclusive message of MIDI Machine Control for the transport item.
+pub fn build_mmc_message(item: &MachineItem, device_id: u8) -> Option<Vec<u8>> {
+    MMC_TRANSPORT_CMDS
+        .iter()
+        .find(|(i, _)| item.eq(i))
+        .map(|&(_, cmd)| vec![0xf0, 0x7f, device_id & 0x7f, 0x06, cmd, 0xf7])
+}
+
+/// Parse system exclusive message of MIDI Machine Control into the transport item. The message
+/// addressed to the other device is ignored.
+pub fn parse_mmc_message(msg: &[u8], device_id: u8) -> Option<MachineItem> {
+    match msg {
+        [0xf0, 0x7f, id, 0x06, cmd, 0xf7]
+            if *id == device_id || *id == MMC_DEVICE_ID_ALL_CALL =>
+        {
+            // The deferred play is regarded as play.
+            let cmd = if *cmd == MMC_DEFERRED_PLAY {
+                MMC_PLAY
+            } else {
+                *cmd
+            };
+            MMC_TRANSPORT_CMDS
+                .iter()
+                .find(|(_, c)| cmd.eq(c))
+                .map(|&(item, _)| item)
+        }
+        _ => None,
+    }
+}
+
 const BANK_MIN: u16 = 0;
 const BANK_MAX: u16 = 3;
 
+// In clear solo mode, the state of clear solo item follows whether any of solo items is enabled,
+// so that LED of the button indicates it.
+fn update_clear_solo_item(
+    items: &[MachineItem],
+    state: &mut MachineState,
+    value: bool,
+    outputs: &mut Vec<(MachineItem, ItemValue)>,
+) {
+    items
+        .iter()
+        .zip(state.bool_items.iter_mut())
+        .filter(|(i, v)| MachineItem::ClrSolo.eq(i) && **v != value)
+        .for_each(|(i, v)| {
+            *v = value;
+            outputs.push((*i, ItemValue::Bool(*v)));
+        });
+}
+
+fn update_aggregate_items(
+    items: &[MachineItem],
+    state: &mut MachineState,
+    outputs: &mut Vec<(MachineItem, ItemValue)>,
+) {
+    let any_solo = items.iter().zip(state.bool_items.iter()).any(|(i, &v)| {
+        if let MachineItem::Solo(_) = i {
+            v
+        } else {
+            false
+        }
+    });
+    let any_mute = items.iter().zip(state.bool_items.iter()).any(|(i, &v)| {
+        if let MachineItem::Mute(_) = i {
+            v
+        } else {
+            false
+        }
+    });
+
+    if state.any_solo != any_solo {
+        state.any_solo = any_solo;
+        if state.clear_solo {
+            update_clear_solo_item(items, state, any_solo, outputs);
+        }
+    }
+
+    state.any_mute = any_mute;
+}
+
+// Just one of select items is enabled for the selected channel, while none of them is enabled for
+// master. The item enabled before is disabled at first.
+fn update_select_items(
+    items: &[MachineItem],
+    state: &mut MachineState,
+    outputs: &mut Vec<(MachineItem, ItemValue)>,
+) {
+    let selected_channel = state.selected_channel;
+    let mut enabled = Vec::new();
+
+    items
+        .iter()
+        .zip(state.bool_items.iter_mut())
+        .for_each(|(i, v)| {
+            if let MachineItem::Select(ch) = i {
+                let value = *ch == selected_channel;
+                if *v != value {
+                    *v = value;
+                    if value {
+                        enabled.push((*i, ItemValue::Bool(value)));
+                    } else {
+                        outputs.push((*i, ItemValue::Bool(value)));
+                    }
+                }
+            }
+        });
+
+    outputs.append(&mut enabled);
+}
+
 /// The trait for operation of state machine.
 pub trait MachineStateOperation {
     const BOOL_ITEMS: &'static [MachineItem];
@@ -355,11 +580,41 @@ pub trait MachineStateOperation {
         MachineItem::Low,
     ];
 
+    const SURFACE_MODE_ITEMS: [MachineItem; 2] = [MachineItem::Computer, MachineItem::Clock];
+
+    /// The number of aux buses whose send is operated by rotaries during aux mode.
+    const AUX_BUS_COUNT: usize = 0;
+    const AUX_SEND_CHANNEL_COUNT: usize = 8;
+
+    /// The number of channels selected by select items in follow mode. The index equals to the
+    /// number is for master, which has no select item.
+    const SELECT_CHANNEL_COUNT: usize = 8;
+
+    /// The list of aux send items, ordered by bus and channel.
+    fn aux_send_items() -> Vec<MachineItem> {
+        (0..Self::AUX_BUS_COUNT)
+            .flat_map(|bus| {
+                (0..Self::AUX_SEND_CHANNEL_COUNT).map(move |ch| MachineItem::AuxSend(bus, ch))
+            })
+            .collect()
+    }
+
     fn initialize_machine(state: &mut MachineState) {
         state.bool_items = vec![false; Self::BOOL_ITEMS.len()];
+        // The surface is in computer mode at first.
+        Self::BOOL_ITEMS
+            .iter()
+            .zip(state.bool_items.iter_mut())
+            .filter(|(i, _)| Self::SURFACE_MODE_ITEMS[0].eq(i))
+            .for_each(|(_, v)| *v = true);
         state.u16_items = vec![0; Self::U16_ITEMS.len()];
         state.bank = 0;
         state.transport = MachineItem::Stop;
+        state.aux_bus = None;
+        state.aux_sends = vec![0; Self::AUX_BUS_COUNT * Self::AUX_SEND_CHANNEL_COUNT];
+        state.select_follow = false;
+        state.selected_channel = 0;
+        state.clear_solo = false;
     }
 
     fn get_machine_current_values(state: &MachineState) -> Vec<(MachineItem, ItemValue)> {
@@ -375,6 +630,11 @@ pub trait MachineStateOperation {
             .zip(state.u16_items.iter())
             .for_each(|(&item, &value)| machine_values.push((item, ItemValue::U16(value))));
 
+        Self::aux_send_items()
+            .iter()
+            .zip(state.aux_sends.iter())
+            .for_each(|(&item, &value)| machine_values.push((item, ItemValue::U16(value))));
+
         if Self::HAS_BANK {
             machine_values.push((MachineItem::Bank, ItemValue::U16(state.bank)));
         }
@@ -388,22 +648,173 @@ pub trait MachineStateOperation {
         machine_values
     }
 
+    fn is_any_solo_active(state: &MachineState) -> bool {
+        state.any_solo
+    }
+
+    fn is_any_mute_active(state: &MachineState) -> bool {
+        state.any_mute
+    }
+
+    /// The aux bus selected for rotaries, if any.
+    fn selected_aux_bus(state: &MachineState) -> Option<usize> {
+        state.aux_bus
+    }
+
+    /// The values for the rotaries in the page of selected aux bus, or the plain rotaries.
+    fn aux_page_values(state: &MachineState) -> Vec<(MachineItem, ItemValue)> {
+        (0..Self::AUX_SEND_CHANNEL_COUNT)
+            .filter_map(|ch| match state.aux_bus {
+                Some(bus) => state
+                    .aux_sends
+                    .iter()
+                    .nth(bus * Self::AUX_SEND_CHANNEL_COUNT + ch)
+                    .map(|&value| (MachineItem::AuxSend(bus, ch), ItemValue::U16(value))),
+                None => Self::U16_ITEMS
+                    .iter()
+                    .zip(state.u16_items.iter())
+                    .find(|(i, _)| MachineItem::Rotary(ch).eq(i))
+                    .map(|(&item, &value)| (item, ItemValue::U16(value))),
+            })
+            .collect()
+    }
+
+    /// Whether select items act as radio buttons for the selected channel.
+    fn is_select_follow_mode(state: &MachineState) -> bool {
+        state.select_follow
+    }
+
+    /// The selected channel. The index equals to `SELECT_CHANNEL_COUNT` is for master.
+    fn selected_channel(state: &MachineState) -> usize {
+        state.selected_channel
+    }
+
+    /// Enable or disable follow mode of select items. At enabling, the select items are updated
+    /// according to the selected channel. At disabling, they are all disabled.
+    fn set_select_follow_mode(
+        state: &mut MachineState,
+        enable: bool,
+    ) -> Vec<(MachineItem, ItemValue)> {
+        let mut outputs = Vec::new();
+
+        state.select_follow = enable;
+        if enable {
+            update_select_items(Self::BOOL_ITEMS, state, &mut outputs);
+        } else {
+            Self::BOOL_ITEMS
+                .iter()
+                .zip(state.bool_items.iter_mut())
+                .filter(|(i, v)| matches!(i, MachineItem::Select(_)) && **v)
+                .for_each(|(i, v)| {
+                    *v = false;
+                    outputs.push((*i, ItemValue::Bool(false)));
+                });
+        }
+
+        outputs
+    }
+
+    /// Select the channel. The select items are updated just in follow mode. The channel out of
+    /// range is ignored.
+    fn select_channel(state: &mut MachineState, ch: usize) -> Vec<(MachineItem, ItemValue)> {
+        let mut outputs = Vec::new();
+
+        if ch <= Self::SELECT_CHANNEL_COUNT {
+            state.selected_channel = ch;
+            if state.select_follow {
+                update_select_items(Self::BOOL_ITEMS, state, &mut outputs);
+            }
+        }
+
+        outputs
+    }
+
+    /// Whether clear solo item clears all of solo items.
+    fn is_clear_solo_mode(state: &MachineState) -> bool {
+        state.clear_solo
+    }
+
+    /// Enable or disable clear solo mode. At enabling, clear solo item is updated according to
+    /// whether any of solo items is enabled. At disabling, it is disabled and operated as normal
+    /// item.
+    fn set_clear_solo_mode(
+        state: &mut MachineState,
+        enable: bool,
+    ) -> Vec<(MachineItem, ItemValue)> {
+        let mut outputs = Vec::new();
+
+        state.clear_solo = enable;
+        let value = enable && state.any_solo;
+        update_clear_solo_item(Self::BOOL_ITEMS, state, value, &mut outputs);
+
+        outputs
+    }
+
+    /// The index of equalizer band enabled in `EQ_BAND_ITEMS`, if any.
+    fn selected_eq_band(state: &MachineState) -> Option<usize> {
+        Self::BOOL_ITEMS
+            .iter()
+            .zip(state.bool_items.iter())
+            .filter(|(_, &v)| v)
+            .find_map(|(i, _)| Self::EQ_BAND_ITEMS.iter().position(|item| item.eq(i)))
+    }
+
     fn change_machine_value(
         state: &mut MachineState,
         input: &(MachineItem, ItemValue),
     ) -> Vec<(MachineItem, ItemValue)> {
         let mut outputs = Vec::new();
 
+        // The indicators driven by the device never flow as machine events.
+        if input.0.is_device_indicator() {
+            return outputs;
+        }
+
+        // In follow mode, any operation of select item selects the channel, thus the release of
+        // button and the push for the selected channel change nothing.
+        if let (MachineItem::Select(ch), ItemValue::Bool(_)) = input {
+            if state.select_follow && *ch < Self::SELECT_CHANNEL_COUNT {
+                return Self::select_channel(state, *ch);
+            }
+        }
+
         if let ItemValue::Bool(value) = input.1 {
-            // Normal items.
-            let _ = Self::BOOL_ITEMS
+            let is_surface_mode = Self::SURFACE_MODE_ITEMS
                 .iter()
-                .zip(state.bool_items.iter_mut())
-                .find(|(i, v)| input.0.eq(i) && !value.eq(v))
-                .map(|(_, v)| {
-                    *v = value;
-                    outputs.push((input.0, ItemValue::Bool(*v)));
-                });
+                .find(|i| input.0.eq(i))
+                .is_some();
+
+            let is_clear_solo = state.clear_solo && input.0 == MachineItem::ClrSolo;
+
+            // Normal items.
+            if !is_surface_mode && !is_clear_solo {
+                let _ = Self::BOOL_ITEMS
+                    .iter()
+                    .zip(state.bool_items.iter_mut())
+                    .find(|(i, v)| input.0.eq(i) && !value.eq(v))
+                    .map(|(_, v)| {
+                        *v = value;
+                        outputs.push((input.0, ItemValue::Bool(*v)));
+                    });
+            }
+
+            // One of surface mode items should be enabled. The release of button is ignored.
+            if is_surface_mode && value {
+                Self::BOOL_ITEMS
+                    .iter()
+                    .zip(state.bool_items.iter_mut())
+                    .filter(|(i, v)| {
+                        Self::SURFACE_MODE_ITEMS
+                            .iter()
+                            .find(|item| item.eq(i))
+                            .is_some()
+                            && input.0.eq(i) != **v
+                    })
+                    .for_each(|(i, v)| {
+                        *v = input.0.eq(i);
+                        outputs.push((*i, ItemValue::Bool(*v)));
+                    });
+            }
 
             // One of transport items should be enabled.
             if Self::HAS_TRANSPORT
@@ -419,10 +830,11 @@ pub trait MachineStateOperation {
                 }
             }
 
-            // None of, or one of equalizer band items should be enabled.
+            // None of, or one of equalizer band items should be enabled. The other band is
+            // disabled before the band is enabled.
             if Self::EQ_BAND_ITEMS.iter().find(|i| input.0.eq(i)).is_some() {
                 if value {
-                    Self::BOOL_ITEMS
+                    let disabled: Vec<(MachineItem, ItemValue)> = Self::BOOL_ITEMS
                         .iter()
                         .zip(state.bool_items.iter_mut())
                         .filter(|(i, v)| {
@@ -430,13 +842,71 @@ pub trait MachineStateOperation {
                                 && **v
                                 && Self::EQ_BAND_ITEMS.iter().find(|item| item.eq(i)).is_some()
                         })
-                        .for_each(|(i, v)| {
+                        .map(|(i, v)| {
                             *v = false;
-                            outputs.push((*i, ItemValue::Bool(*v)));
-                        });
+                            (*i, ItemValue::Bool(*v))
+                        })
+                        .collect();
+                    let pos = outputs
+                        .iter()
+                        .position(|(i, _)| input.0.eq(i))
+                        .unwrap_or(outputs.len());
+                    outputs.splice(pos..pos, disabled);
                 }
             }
+
+            // All of solo items are disabled by push of clear solo button.
+            if is_clear_solo && value {
+                Self::BOOL_ITEMS
+                    .iter()
+                    .zip(state.bool_items.iter_mut())
+                    .filter(|(i, v)| {
+                        if let MachineItem::Solo(_) = i {
+                            **v
+                        } else {
+                            false
+                        }
+                    })
+                    .for_each(|(i, v)| {
+                        *v = false;
+                        outputs.push((*i, ItemValue::Bool(*v)));
+                    });
+            }
+
+            // The rotaries operate send to the aux bus selected at last, till the push of button
+            // for the bus again. The release of button is ignored. At switching the page, the
+            // values for the rotaries in the page are repainted.
+            if let MachineItem::Aux(bus) = input.0 {
+                if bus < Self::AUX_BUS_COUNT && value {
+                    state.aux_bus = if state.aux_bus == Some(bus) {
+                        None
+                    } else {
+                        Some(bus)
+                    };
+                    outputs.append(&mut Self::aux_page_values(state));
+                }
+            }
+
+            update_aggregate_items(Self::BOOL_ITEMS, state, &mut outputs);
         } else if let ItemValue::U16(value) = input.1 {
+            let item = match (input.0, state.aux_bus) {
+                (MachineItem::Rotary(ch), Some(bus)) if ch < Self::AUX_SEND_CHANNEL_COUNT => {
+                    MachineItem::AuxSend(bus, ch)
+                }
+                (item, _) => item,
+            };
+
+            if let MachineItem::AuxSend(bus, ch) = item {
+                if bus < Self::AUX_BUS_COUNT && ch < Self::AUX_SEND_CHANNEL_COUNT {
+                    let v = &mut state.aux_sends[bus * Self::AUX_SEND_CHANNEL_COUNT + ch];
+                    if *v != value {
+                        *v = value;
+                        outputs.push((item, ItemValue::U16(value)));
+                    }
+                }
+                return outputs;
+            }
+
             let _ = Self::U16_ITEMS
                 .iter()
                 .zip(state.u16_items.iter_mut())
@@ -463,7 +933,7 @@ pub trait SurfaceImageOperation<T> {
     fn initialize_surface_state(state: &mut T);
 
     fn decode_surface_image(
-        state: &T,
+        state: &mut T,
         image: &[u32],
         index: u32,
         before: u32,
@@ -484,12 +954,38 @@ pub trait SurfaceImageOperation<T> {
         node: &mut FwNode,
         timeout_ms: u32,
     ) -> Result<(), Error>;
+
+    /// Invalidate the cache of LEDs, e.g. when the unit is power-cycled and all of LEDs are
+    /// turned off.
+    fn invalidate_surface(state: &mut T);
+
+    /// Turn on and off all of LEDs according to the current values of machine, e.g. after the
+    /// cache of LEDs is invalidated.
+    fn repaint_surface(
+        state: &mut T,
+        machine_state: &MachineState,
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<(), Error>
+    where
+        Self: MachineStateOperation,
+    {
+        Self::get_machine_current_values(machine_state)
+            .iter()
+            .try_for_each(|machine_value| {
+                Self::feedback_to_surface(state, machine_value, req, node, timeout_ms)
+            })
+    }
 }
 
 /// The structure for common state of surface.
 #[derive(Default, Debug)]
 struct SurfaceCommonState {
     stateful_items: Vec<bool>,
+    fader_touches: Vec<bool>,
+    /// The value of each fader emitted at last.
+    fader_values: Vec<u16>,
 }
 
 /// The structure of boolean value in surface image.
@@ -538,11 +1034,13 @@ trait SurfaceImageCommonOperation {
 
     fn initialize_surface_common_state(state: &mut SurfaceCommonState) {
         state.stateful_items = vec![Default::default(); Self::STATEFUL_ITEMS.len()];
+        state.fader_touches = vec![Default::default(); Self::FADERS.len()];
+        state.fader_values = vec![Default::default(); Self::FADERS.len()];
     }
 
     fn decode_surface_image_common(
         machine_values: &mut Vec<(MachineItem, ItemValue)>,
-        state: &SurfaceCommonState,
+        state: &mut SurfaceCommonState,
         image: &[u32],
         index: u32,
         before: u32,
@@ -572,12 +1070,33 @@ trait SurfaceImageCommonOperation {
                 machine_values.push((*item, ItemValue::U16(value)));
             });
 
+        // The value of fader is forwarded just while the fader is touched and at the release of
+        // touch, unless it is the same as the value emitted at last.
         Self::FADERS
             .iter()
-            .filter(|(bool_val, _, _)| detect_bool_action(bool_val, index, before, after))
-            .for_each(|(_, u16_val, item)| {
-                let value = detect_u16_value_in_image(u16_val, image);
-                machine_values.push((*item, ItemValue::U16(value)));
+            .zip(state.fader_touches.iter_mut())
+            .zip(state.fader_values.iter_mut())
+            .enumerate()
+            .for_each(|(i, (((bool_val, u16_val, item), touched), last_value))| {
+                let touch_changed = detect_bool_action(bool_val, index, before, after);
+                if touch_changed {
+                    *touched = detect_bool_value(bool_val, before);
+                    if *touched {
+                        machine_values.push((MachineItem::FaderTouch(i), ItemValue::Bool(true)));
+                    }
+                }
+
+                if touch_changed || (*touched && detect_u16_action(u16_val, index, before, after)) {
+                    let value = detect_u16_value_in_image(u16_val, image);
+                    if value != *last_value {
+                        *last_value = value;
+                        machine_values.push((*item, ItemValue::U16(value)));
+                    }
+                }
+
+                if touch_changed && !*touched {
+                    machine_values.push((MachineItem::FaderTouch(i), ItemValue::Bool(false)));
+                }
             });
     }
 
@@ -593,8 +1112,45 @@ trait SurfaceImageCommonOperation {
     }
 }
 
+/// The state of LEDs. The first is the positions of enabled LEDs. The second is the stack of
+/// LED states saved for temporary usage of the LEDs.
 #[derive(Default, Debug)]
-struct LedState(Vec<u16>);
+struct LedState(Vec<u16>, Vec<Vec<(u16, bool)>>);
+
+impl LedState {
+    fn cache(&mut self, pos: u16, enable: bool) {
+        if !enable {
+            self.0.retain(|&p| p != pos);
+        } else if self.0.iter().find(|&p| *p == pos).is_none() {
+            self.0.push(pos);
+        }
+    }
+
+    fn push(&mut self, positions: &[u16]) {
+        let frame = positions
+            .iter()
+            .map(|&pos| (pos, self.0.iter().find(|&p| *p == pos).is_some()))
+            .collect();
+        self.1.push(frame);
+    }
+
+    fn pop(&mut self) -> Option<Vec<(u16, bool)>> {
+        self.1.pop()
+    }
+
+    fn invalidate(&mut self) {
+        self.0.clear();
+        self.1.clear();
+    }
+
+    // The state of LED saved in the last frame, to be restored later.
+    fn saved_mut(&mut self, pos: u16) -> Option<&mut bool> {
+        self.1
+            .last_mut()
+            .and_then(|frame| frame.iter_mut().find(|(p, _)| *p == pos))
+            .map(|(_, enable)| enable)
+    }
+}
 
 fn operate_led(
     req: &mut FwReq,
@@ -602,11 +1158,20 @@ fn operate_led(
     pos: u16,
     enable: bool,
     timeout_ms: u32,
+) -> Result<(), Error> {
+    operate_led_by(&mut (&*req, &*node), pos, enable, timeout_ms)
+}
+
+fn operate_led_by<T: FwTransaction>(
+    tx: &mut T,
+    pos: u16,
+    enable: bool,
+    timeout_ms: u32,
 ) -> Result<(), Error> {
     let mut frame = [0; 4];
     frame[0..2].copy_from_slice(&(enable as u16).to_be_bytes());
     frame[2..4].copy_from_slice(&pos.to_be_bytes());
-    write_quadlet(req, node, LED_OFFSET, &mut frame, timeout_ms)
+    write_quadlet_by(tx, LED_OFFSET, &mut frame, timeout_ms)
 }
 
 fn operate_led_cached(
@@ -617,13 +1182,42 @@ fn operate_led_cached(
     enable: bool,
     timeout_ms: u32,
 ) -> Result<(), Error> {
-    operate_led(req, node, pos, enable, timeout_ms).map(|_| {
-        if !enable {
-            state.0.retain(|&p| p != pos);
-        } else if state.0.iter().find(|&p| *p == pos).is_none() {
-            state.0.push(pos);
-        }
-    })
+    // The LED is used temporarily. The change is applied when restored.
+    if let Some(saved) = state.saved_mut(pos) {
+        *saved = enable;
+        return Ok(());
+    }
+
+    operate_led_overlaid(state, req, node, pos, enable, timeout_ms)
+}
+
+fn operate_led_overlaid(
+    state: &mut LedState,
+    req: &mut FwReq,
+    node: &mut FwNode,
+    pos: u16,
+    enable: bool,
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    operate_led(req, node, pos, enable, timeout_ms).map(|_| state.cache(pos, enable))
+}
+
+fn push_leds(state: &mut LedState, positions: &[u16]) {
+    state.push(positions);
+}
+
+fn pop_leds(
+    state: &mut LedState,
+    req: &mut FwReq,
+    node: &mut FwNode,
+    timeout_ms: u32,
+) -> Result<(), Error> {
+    match state.pop() {
+        Some(frame) => frame.iter().try_for_each(|&(pos, enable)| {
+            operate_led_overlaid(state, req, node, pos, enable, timeout_ms)
+        }),
+        None => Ok(()),
+    }
 }
 
 fn clear_leds(
@@ -632,23 +1226,49 @@ fn clear_leds(
     node: &mut FwNode,
     timeout_ms: u32,
 ) -> Result<(), Error> {
+    state.1.clear();
     let cache = state.0.to_vec();
     cache
         .iter()
         .try_for_each(|&pos| operate_led_cached(state, req, node, pos, false, timeout_ms))
 }
 
+/// The state of FireWire LED operated persistently, to be restored after blinking.
+#[derive(Default, Debug)]
+pub struct FireWireLedState(bool);
+
 /// The trait for operation of FireWire LED.
 pub trait FireWireLedOperation {
     const POSITIONS: &'static [u16];
 
     fn operate_firewire_led(
+        state: &mut FireWireLedState,
         req: &mut FwReq,
         node: &mut FwNode,
         enable: bool,
         timeout_ms: u32,
     ) -> Result<(), Error> {
-        operate_led(req, node, Self::POSITIONS[0], enable, timeout_ms)
+        operate_led(req, node, Self::POSITIONS[0], enable, timeout_ms).map(|_| state.0 = enable)
+    }
+
+    /// Operate the LED temporarily without changing the state.
+    fn blink_firewire_led(
+        req: &mut FwReq,
+        node: &mut FwNode,
+        on: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        operate_led(req, node, Self::POSITIONS[0], on, timeout_ms)
+    }
+
+    /// Restore the LED according to the state.
+    fn restore_firewire_led(
+        state: &FireWireLedState,
+        req: &mut FwReq,
+        node: &mut FwNode,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        operate_led(req, node, Self::POSITIONS[0], state.0, timeout_ms)
     }
 }
 
@@ -669,3 +1289,755 @@ trait SurfaceNormalLedOperation {
             .map(|(_, positions)| positions[0])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fw_transaction::MockTransaction;
+
+    #[test]
+    fn test_operate_led() {
+        let mut tx = MockTransaction::default();
+
+        operate_led_by(&mut tx, 0x0123, true, 100).unwrap();
+        assert_eq!(
+            tx.get_quadlet_be(BASE_OFFSET + LED_OFFSET),
+            Some(0x00010123)
+        );
+
+        operate_led_by(&mut tx, 0x0123, false, 100).unwrap();
+        assert_eq!(
+            tx.get_quadlet_be(BASE_OFFSET + LED_OFFSET),
+            Some(0x00000123)
+        );
+    }
+
+    #[test]
+    fn test_item_value_scale() {
+        let mut scale = ItemValueScale {
+            input_max: 1000,
+            output_min: 0,
+            output_max: 100,
+            curve: ItemValueCurve::Linear,
+        };
+
+        [
+            (ItemValueCurve::Linear, 50),
+            (ItemValueCurve::Log, 74),
+            (ItemValueCurve::Taper, 25),
+        ]
+        .iter()
+        .for_each(|&(curve, midpoint)| {
+            scale.curve = curve;
+
+            assert_eq!(scale.to_output(0), 0);
+            assert_eq!(scale.to_output(500), midpoint);
+            assert_eq!(scale.to_output(1000), 100);
+            // Saturated.
+            assert_eq!(scale.to_output(1023), 100);
+
+            assert_eq!(scale.to_input(0), 0);
+            assert_eq!(scale.to_input(100), 1000);
+            // Saturated.
+            assert_eq!(scale.to_input(-1), 0);
+            assert_eq!(scale.to_input(101), 1000);
+        });
+
+        scale.curve = ItemValueCurve::Linear;
+        assert_eq!(scale.to_input(50), 500);
+        scale.curve = ItemValueCurve::Log;
+        assert_eq!(scale.to_input(74), 499);
+        scale.curve = ItemValueCurve::Taper;
+        assert_eq!(scale.to_input(25), 500);
+
+        // 10 bit value of fader to 14 bit value of pitch bend.
+        let scale = ItemValueScale {
+            input_max: 1023,
+            output_min: -8192,
+            output_max: 8191,
+            curve: ItemValueCurve::Linear,
+        };
+        assert_eq!(scale.to_output(0), -8192);
+        assert_eq!(scale.to_output(1023), 8191);
+        assert_eq!(scale.to_input(-8192), 0);
+        assert_eq!(scale.to_input(8191), 1023);
+    }
+
+    #[test]
+    fn test_led_state_stack() {
+        let mut state = LedState::default();
+        state.cache(10, true);
+        state.cache(11, false);
+        state.cache(12, true);
+
+        state.push(&[10, 11]);
+        assert_eq!(state.saved_mut(10), Some(&mut true));
+        assert_eq!(state.saved_mut(11), Some(&mut false));
+        assert_eq!(state.saved_mut(12), None);
+
+        // The LEDs are used temporarily.
+        state.cache(10, false);
+        state.cache(11, true);
+
+        // The nested frame.
+        state.push(&[11, 12]);
+        assert_eq!(state.saved_mut(10), None);
+        assert_eq!(state.saved_mut(11), Some(&mut true));
+        *state.saved_mut(12).unwrap() = false;
+
+        assert_eq!(state.pop(), Some(vec![(11, true), (12, false)]));
+        assert_eq!(state.saved_mut(10), Some(&mut true));
+
+        // The change during temporary usage is kept in the frame.
+        *state.saved_mut(11).unwrap() = true;
+        assert_eq!(state.pop(), Some(vec![(10, true), (11, true)]));
+        assert_eq!(state.pop(), None);
+        assert_eq!(state.saved_mut(10), None);
+    }
+
+    #[test]
+    fn test_led_state_invalidate() {
+        let mut state = LedState::default();
+        state.cache(10, true);
+        state.cache(11, true);
+        state.push(&[10]);
+
+        state.invalidate();
+        assert!(state.0.is_empty());
+        // The LED is not used temporarily anymore, thus the change is applied immediately.
+        assert_eq!(state.saved_mut(10), None);
+        assert_eq!(state.pop(), None);
+    }
+
+    #[test]
+    fn test_read_quadlet() {
+        let mut tx = MockTransaction::default();
+        tx.set_quadlet_be(BASE_OFFSET + HW_INFO_FPGA_OFFSET, 0x01020304);
+
+        let mut frames = [0; 4];
+        read_quadlet_by(&mut tx, HW_INFO_FPGA_OFFSET, &mut frames, 100).unwrap();
+        assert_eq!(frames, [0x01, 0x02, 0x03, 0x04]);
+
+        assert!(read_quadlet_by(&mut tx, HW_INFO_ARM_OFFSET, &mut frames, 100).is_err());
+    }
+
+    struct TestProtocol;
+
+    impl SurfaceImageCommonOperation for TestProtocol {
+        const STATEFUL_ITEMS: &'static [(SurfaceBoolValue, MachineItem)] = &[];
+        const STATELESS_ITEMS: &'static [(SurfaceBoolValue, MachineItem)] = &[];
+        const ROTARIES: &'static [(SurfaceU16Value, MachineItem)] = &[];
+        const FADERS: &'static [(SurfaceBoolValue, SurfaceU16Value, MachineItem)] = &[(
+            SurfaceBoolValue(1, 0x00000001),
+            SurfaceU16Value(0, 0x0000ffff, 0),
+            MachineItem::Input(0),
+        )];
+    }
+
+    fn decode_fader(
+        state: &mut SurfaceCommonState,
+        image: &mut [u32],
+        index: u32,
+        after: u32,
+    ) -> Vec<(MachineItem, ItemValue)> {
+        let before = image[index as usize];
+        image[index as usize] = after;
+        let mut machine_values = Vec::new();
+        TestProtocol::decode_surface_image_common(
+            &mut machine_values,
+            state,
+            image,
+            index,
+            before,
+            after,
+        );
+        machine_values
+    }
+
+    #[test]
+    fn test_fader_touch() {
+        let mut state = SurfaceCommonState::default();
+        TestProtocol::initialize_surface_common_state(&mut state);
+        let mut image = [0x00000000, 0x00000001];
+
+        // The noise of fader is not forwarded without touch.
+        let values = decode_fader(&mut state, &mut image, 0, 0x00000005);
+        assert!(values.is_empty());
+
+        // Touch begins.
+        let values = decode_fader(&mut state, &mut image, 1, 0x00000000);
+        assert_eq!(
+            values,
+            vec![
+                (MachineItem::FaderTouch(0), ItemValue::Bool(true)),
+                (MachineItem::Input(0), ItemValue::U16(0x0005)),
+            ]
+        );
+
+        let values = decode_fader(&mut state, &mut image, 0, 0x00000010);
+        assert_eq!(
+            values,
+            vec![(MachineItem::Input(0), ItemValue::U16(0x0010))]
+        );
+
+        // The change of the other bits in the same quadlet.
+        let values = decode_fader(&mut state, &mut image, 0, 0x00010010);
+        assert!(values.is_empty());
+
+        let values = decode_fader(&mut state, &mut image, 0, 0x00010011);
+        assert_eq!(
+            values,
+            vec![(MachineItem::Input(0), ItemValue::U16(0x0011))]
+        );
+
+        // Touch ends with the value already emitted.
+        let values = decode_fader(&mut state, &mut image, 1, 0x00000001);
+        assert_eq!(
+            values,
+            vec![(MachineItem::FaderTouch(0), ItemValue::Bool(false))]
+        );
+
+        let values = decode_fader(&mut state, &mut image, 0, 0x00000012);
+        assert!(values.is_empty());
+
+        // Touch begins and ends with the value changed without touch.
+        let values = decode_fader(&mut state, &mut image, 1, 0x00000000);
+        assert_eq!(
+            values,
+            vec![
+                (MachineItem::FaderTouch(0), ItemValue::Bool(true)),
+                (MachineItem::Input(0), ItemValue::U16(0x0012)),
+            ]
+        );
+        let values = decode_fader(&mut state, &mut image, 1, 0x00000001);
+        assert_eq!(
+            values,
+            vec![(MachineItem::FaderTouch(0), ItemValue::Bool(false))]
+        );
+    }
+
+    impl MachineStateOperation for TestProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[MachineItem::Computer, MachineItem::Clock];
+        const U16_ITEMS: &'static [MachineItem] = &[];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+    }
+
+    #[test]
+    fn test_surface_mode_items() {
+        let mut state = MachineState::default();
+        TestProtocol::initialize_machine(&mut state);
+        assert_eq!(
+            TestProtocol::get_machine_current_values(&state),
+            vec![
+                (MachineItem::Computer, ItemValue::Bool(true)),
+                (MachineItem::Clock, ItemValue::Bool(false)),
+            ]
+        );
+
+        let outputs = TestProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Clock, ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Computer, ItemValue::Bool(false)),
+                (MachineItem::Clock, ItemValue::Bool(true)),
+            ]
+        );
+
+        // The release of button is ignored.
+        let outputs = TestProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Clock, ItemValue::Bool(false)),
+        );
+        assert!(outputs.is_empty());
+
+        // Nothing changes when the mode is already selected.
+        let outputs = TestProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Clock, ItemValue::Bool(true)),
+        );
+        assert!(outputs.is_empty());
+
+        let outputs = TestProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Computer, ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Computer, ItemValue::Bool(true)),
+                (MachineItem::Clock, ItemValue::Bool(false)),
+            ]
+        );
+    }
+
+    struct SoloMuteProtocol;
+
+    impl MachineStateOperation for SoloMuteProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[
+            MachineItem::Solo(0),
+            MachineItem::Solo(1),
+            MachineItem::Mute(0),
+            MachineItem::Mute(1),
+            MachineItem::ClrSolo,
+        ];
+        const U16_ITEMS: &'static [MachineItem] = &[];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+    }
+
+    #[test]
+    fn test_any_solo_mute() {
+        let mut state = MachineState::default();
+        SoloMuteProtocol::initialize_machine(&mut state);
+        let outputs = SoloMuteProtocol::set_clear_solo_mode(&mut state, true);
+        assert!(outputs.is_empty());
+        assert!(!SoloMuteProtocol::is_any_solo_active(&state));
+        assert!(!SoloMuteProtocol::is_any_mute_active(&state));
+
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Solo(1), ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Solo(1), ItemValue::Bool(true)),
+                (MachineItem::ClrSolo, ItemValue::Bool(true)),
+            ]
+        );
+        assert!(SoloMuteProtocol::is_any_solo_active(&state));
+
+        // The indicator is kept as long as one of solo items is enabled.
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Solo(0), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Solo(0), ItemValue::Bool(true))]);
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Solo(1), ItemValue::Bool(false)),
+        );
+        assert_eq!(
+            outputs,
+            vec![(MachineItem::Solo(1), ItemValue::Bool(false))]
+        );
+        assert!(SoloMuteProtocol::is_any_solo_active(&state));
+
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Mute(0), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Mute(0), ItemValue::Bool(true))]);
+        assert!(SoloMuteProtocol::is_any_mute_active(&state));
+
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Mute(0), ItemValue::Bool(false)),
+        );
+        assert_eq!(
+            outputs,
+            vec![(MachineItem::Mute(0), ItemValue::Bool(false))]
+        );
+        assert!(!SoloMuteProtocol::is_any_mute_active(&state));
+    }
+
+    struct EqBandProtocol;
+
+    impl MachineStateOperation for EqBandProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[
+            MachineItem::High,
+            MachineItem::HighMid,
+            MachineItem::LowMid,
+            MachineItem::Low,
+        ];
+        const U16_ITEMS: &'static [MachineItem] = &[MachineItem::Gain];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+    }
+
+    #[test]
+    fn test_eq_band_exclusive() {
+        let mut state = MachineState::default();
+        EqBandProtocol::initialize_machine(&mut state);
+        assert_eq!(EqBandProtocol::selected_eq_band(&state), None);
+
+        let outputs = EqBandProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Low, ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Low, ItemValue::Bool(true))]);
+        assert_eq!(EqBandProtocol::selected_eq_band(&state), Some(3));
+
+        // The band enabled before is disabled at first.
+        let outputs = EqBandProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::High, ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Low, ItemValue::Bool(false)),
+                (MachineItem::High, ItemValue::Bool(true)),
+            ]
+        );
+        assert_eq!(EqBandProtocol::selected_eq_band(&state), Some(0));
+
+        let outputs = EqBandProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::High, ItemValue::Bool(false)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::High, ItemValue::Bool(false))]);
+        assert_eq!(EqBandProtocol::selected_eq_band(&state), None);
+    }
+
+    struct AuxSendProtocol;
+
+    impl MachineStateOperation for AuxSendProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[MachineItem::Aux(0), MachineItem::Aux(1)];
+        const U16_ITEMS: &'static [MachineItem] = &[MachineItem::Rotary(0), MachineItem::Rotary(1)];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+
+        const AUX_BUS_COUNT: usize = 2;
+        const AUX_SEND_CHANNEL_COUNT: usize = 2;
+    }
+
+    struct SelectProtocol;
+
+    impl MachineStateOperation for SelectProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[
+            MachineItem::Select(0),
+            MachineItem::Select(1),
+            MachineItem::Select(2),
+        ];
+        const U16_ITEMS: &'static [MachineItem] = &[];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+
+        const SELECT_CHANNEL_COUNT: usize = 3;
+    }
+
+    #[test]
+    fn test_select_follow() {
+        let mut state = MachineState::default();
+        SelectProtocol::initialize_machine(&mut state);
+        assert!(!SelectProtocol::is_select_follow_mode(&state));
+
+        // The select items are independent out of follow mode.
+        let outputs = SelectProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Select(1), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Select(1), ItemValue::Bool(true))]);
+        let outputs = SelectProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Select(2), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Select(2), ItemValue::Bool(true))]);
+
+        // The item for the selected channel is enabled only.
+        let outputs = SelectProtocol::set_select_follow_mode(&mut state, true);
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Select(1), ItemValue::Bool(false)),
+                (MachineItem::Select(2), ItemValue::Bool(false)),
+                (MachineItem::Select(0), ItemValue::Bool(true)),
+            ]
+        );
+        assert_eq!(SelectProtocol::selected_channel(&state), 0);
+
+        let outputs = SelectProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Select(2), ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Select(0), ItemValue::Bool(false)),
+                (MachineItem::Select(2), ItemValue::Bool(true)),
+            ]
+        );
+        assert_eq!(SelectProtocol::selected_channel(&state), 2);
+
+        // The push for the selected channel changes nothing.
+        let outputs = SelectProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Select(2), ItemValue::Bool(false)),
+        );
+        assert_eq!(outputs, vec![]);
+        assert_eq!(SelectProtocol::selected_channel(&state), 2);
+
+        // None of items is enabled for master.
+        let outputs = SelectProtocol::select_channel(&mut state, 3);
+        assert_eq!(outputs, vec![(MachineItem::Select(2), ItemValue::Bool(false))]);
+        assert_eq!(SelectProtocol::selected_channel(&state), 3);
+
+        let outputs = SelectProtocol::select_channel(&mut state, 4);
+        assert_eq!(outputs, vec![]);
+        assert_eq!(SelectProtocol::selected_channel(&state), 3);
+
+        let outputs = SelectProtocol::select_channel(&mut state, 1);
+        assert_eq!(outputs, vec![(MachineItem::Select(1), ItemValue::Bool(true))]);
+
+        // The items are disabled at disabling follow mode.
+        let outputs = SelectProtocol::set_select_follow_mode(&mut state, false);
+        assert_eq!(outputs, vec![(MachineItem::Select(1), ItemValue::Bool(false))]);
+        let outputs = SelectProtocol::set_select_follow_mode(&mut state, false);
+        assert_eq!(outputs, vec![]);
+        let outputs = SelectProtocol::select_channel(&mut state, 0);
+        assert_eq!(outputs, vec![]);
+        let outputs = SelectProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Select(1), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Select(1), ItemValue::Bool(true))]);
+    }
+
+    #[test]
+    fn test_aux_send_page() {
+        let mut state = MachineState::default();
+        AuxSendProtocol::initialize_machine(&mut state);
+        assert_eq!(AuxSendProtocol::selected_aux_bus(&state), None);
+        assert_eq!(AuxSendProtocol::get_machine_current_values(&state).len(), 8);
+
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Rotary(1), ItemValue::U16(10)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Rotary(1), ItemValue::U16(10))]);
+
+        // The rotary operates send to the selected aux bus.
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Aux(1), ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Aux(1), ItemValue::Bool(true)),
+                (MachineItem::AuxSend(1, 0), ItemValue::U16(0)),
+                (MachineItem::AuxSend(1, 1), ItemValue::U16(0)),
+            ]
+        );
+        assert_eq!(AuxSendProtocol::selected_aux_bus(&state), Some(1));
+
+        // The page is kept after the release of button.
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Aux(1), ItemValue::Bool(false)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Aux(1), ItemValue::Bool(false))]);
+        assert_eq!(AuxSendProtocol::selected_aux_bus(&state), Some(1));
+
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Rotary(1), ItemValue::U16(20)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::AuxSend(1, 1), ItemValue::U16(20))]);
+        assert!(AuxSendProtocol::get_machine_current_values(&state)
+            .contains(&(MachineItem::AuxSend(1, 1), ItemValue::U16(20))));
+        assert!(AuxSendProtocol::get_machine_current_values(&state)
+            .contains(&(MachineItem::Rotary(1), ItemValue::U16(10))));
+
+        // The send from application.
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::AuxSend(0, 0), ItemValue::U16(30)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::AuxSend(0, 0), ItemValue::U16(30))]);
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::AuxSend(0, 0), ItemValue::U16(30)),
+        );
+        assert_eq!(outputs, vec![]);
+
+        // The stored sends are repainted at switching the page.
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Aux(0), ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Aux(0), ItemValue::Bool(true)),
+                (MachineItem::AuxSend(0, 0), ItemValue::U16(30)),
+                (MachineItem::AuxSend(0, 1), ItemValue::U16(0)),
+            ]
+        );
+        assert_eq!(AuxSendProtocol::selected_aux_bus(&state), Some(0));
+
+        // Back to plain rotary at the push of button for the selected bus again.
+        let _ = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Aux(0), ItemValue::Bool(false)),
+        );
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Aux(0), ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Aux(0), ItemValue::Bool(true)),
+                (MachineItem::Rotary(0), ItemValue::U16(0)),
+                (MachineItem::Rotary(1), ItemValue::U16(10)),
+            ]
+        );
+        assert_eq!(AuxSendProtocol::selected_aux_bus(&state), None);
+        let outputs = AuxSendProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Rotary(1), ItemValue::U16(40)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Rotary(1), ItemValue::U16(40))]);
+    }
+
+    #[test]
+    fn test_clear_solo() {
+        let mut state = MachineState::default();
+        SoloMuteProtocol::initialize_machine(&mut state);
+        assert!(!SoloMuteProtocol::is_clear_solo_mode(&state));
+
+        // The item is operated as normal item unless clear solo mode.
+        let _ = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Solo(0), ItemValue::Bool(true)),
+        );
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::ClrSolo, ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::ClrSolo, ItemValue::Bool(true))]);
+        assert!(SoloMuteProtocol::is_any_solo_active(&state));
+
+        // At enabling the mode, the item indicates any of solo items enabled.
+        let _ = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::ClrSolo, ItemValue::Bool(false)),
+        );
+        let outputs = SoloMuteProtocol::set_clear_solo_mode(&mut state, true);
+        assert_eq!(outputs, vec![(MachineItem::ClrSolo, ItemValue::Bool(true))]);
+        assert!(SoloMuteProtocol::is_clear_solo_mode(&state));
+
+        // At disabling the mode, the item is disabled.
+        let outputs = SoloMuteProtocol::set_clear_solo_mode(&mut state, false);
+        assert_eq!(
+            outputs,
+            vec![(MachineItem::ClrSolo, ItemValue::Bool(false))]
+        );
+
+        SoloMuteProtocol::initialize_machine(&mut state);
+        let _ = SoloMuteProtocol::set_clear_solo_mode(&mut state, true);
+
+        // Nothing happens without any solo.
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::ClrSolo, ItemValue::Bool(true)),
+        );
+        assert!(outputs.is_empty());
+
+        [0, 1].iter().for_each(|&i| {
+            let _ = SoloMuteProtocol::change_machine_value(
+                &mut state,
+                &(MachineItem::Solo(i), ItemValue::Bool(true)),
+            );
+        });
+        let _ = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Mute(1), ItemValue::Bool(true)),
+        );
+
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::ClrSolo, ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Solo(0), ItemValue::Bool(false)),
+                (MachineItem::Solo(1), ItemValue::Bool(false)),
+                (MachineItem::ClrSolo, ItemValue::Bool(false)),
+            ]
+        );
+        assert!(!SoloMuteProtocol::is_any_solo_active(&state));
+        assert!(SoloMuteProtocol::is_any_mute_active(&state));
+
+        // The release of button is ignored.
+        let outputs = SoloMuteProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::ClrSolo, ItemValue::Bool(false)),
+        );
+        assert!(outputs.is_empty());
+    }
+
+    struct IndicatorProtocol;
+
+    impl MachineStateOperation for IndicatorProtocol {
+        const BOOL_ITEMS: &'static [MachineItem] = &[
+            MachineItem::Ol(0),
+            MachineItem::Signal(0),
+            MachineItem::Rec(0),
+        ];
+        const U16_ITEMS: &'static [MachineItem] = &[];
+        const HAS_TRANSPORT: bool = false;
+        const HAS_BANK: bool = false;
+    }
+
+    #[test]
+    fn test_device_indicator() {
+        let mut state = MachineState::default();
+        IndicatorProtocol::initialize_machine(&mut state);
+
+        [MachineItem::Ol(0), MachineItem::Signal(0)]
+            .iter()
+            .for_each(|&item| {
+                assert!(item.is_device_indicator());
+                let outputs = IndicatorProtocol::change_machine_value(
+                    &mut state,
+                    &(item, ItemValue::Bool(true)),
+                );
+                assert!(outputs.is_empty());
+            });
+
+        assert!(!MachineItem::Rec(0).is_device_indicator());
+        let outputs = IndicatorProtocol::change_machine_value(
+            &mut state,
+            &(MachineItem::Rec(0), ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Rec(0), ItemValue::Bool(true))]);
+    }
+
+    #[test]
+    fn test_mmc_message() {
+        MMC_TRANSPORT_CMDS.iter().for_each(|(item, _)| {
+            let msg = build_mmc_message(item, MMC_DEVICE_ID_ALL_CALL).unwrap();
+            assert_eq!(parse_mmc_message(&msg, 0x10), Some(*item));
+
+            let msg = build_mmc_message(item, 0x10).unwrap();
+            assert_eq!(parse_mmc_message(&msg, 0x10), Some(*item));
+            assert_eq!(parse_mmc_message(&msg, 0x11), None);
+        });
+
+        assert_eq!(
+            build_mmc_message(&MachineItem::Play, MMC_DEVICE_ID_ALL_CALL),
+            Some(vec![0xf0, 0x7f, 0x7f, 0x06, 0x02, 0xf7])
+        );
+        assert_eq!(build_mmc_message(&MachineItem::Bank, MMC_DEVICE_ID_ALL_CALL), None);
+
+        let msg = [0xf0, 0x7f, 0x7f, 0x06, 0x03, 0xf7];
+        assert_eq!(parse_mmc_message(&msg, 0x00), Some(MachineItem::Play));
+
+        // Record exit is not supported.
+        let msg = [0xf0, 0x7f, 0x7f, 0x06, 0x07, 0xf7];
+        assert_eq!(parse_mmc_message(&msg, 0x00), None);
+
+        // MIDI Machine Control response.
+        let msg = [0xf0, 0x7f, 0x7f, 0x07, 0x01, 0xf7];
+        assert_eq!(parse_mmc_message(&msg, 0x00), None);
+    }
+
+}
@@ -14,6 +14,9 @@ use hinawa::{FwNode, FwNodeExt, FwRcode, FwResp, FwRespExt, FwRespExtManual};
 use alsaseq::{UserClientExt, EventCntrExt, EventCntrExtManual, EventDataCtl, EventType};
 
 use core::dispatcher::*;
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
 use tascam_protocols::asynch::{fe8::*, *};
 
@@ -44,6 +47,10 @@ where
     T: MachineStateOperation + SurfaceImageOperation<U>,
 {
     fn drop(&mut self) {
+        if let Some(cascade) = &self.model.state().cascade {
+            cascade.unregister_route();
+        }
+
         let _ = self.model.enable_notification(&mut self.node, false);
         let _ = self.model.register_notification_address(&mut self.node, 0);
         let _ = self.model.finalize_surface(&mut self.node);
@@ -57,13 +64,15 @@ where
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads in reverse order of launch, thus the dispatcher for
+        // signal handler is finished at first, and the dispatcher for node at last.
+        join_dispatchers(self.dispatchers.drain(..).rev());
     }
 }
 
 enum AsyncUnitEvent {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Surface((u32, u32, u32)),
@@ -72,20 +81,43 @@ enum AsyncUnitEvent {
 
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
 
+impl<S, T, U> ConfigReloadOperation for AsynchRuntime<S, T, U>
+where
+    S: AsynchCtlOperation + SequencerCtlOperation<FwNode, T, U> + Default,
+    T: MachineStateOperation + SurfaceImageOperation<U>,
+{
+    fn apply_runtime_options(&mut self) {
+        self.model.state_mut().apply_value_scales(&current_config().value_scales);
+    }
+}
+
 impl<S, T, U> AsynchRuntime<S, T, U>
 where
     S: AsynchCtlOperation + SequencerCtlOperation<FwNode, T, U> + Default,
     T: MachineStateOperation + SurfaceImageOperation<U>,
 {
-    pub fn new(node: FwNode, name: String) -> Result<Self, Error> {
+    pub fn new(
+        node: FwNode,
+        name: String,
+        layer: Option<SequencerLayer>,
+        cascade: Option<SequencerCascade>,
+    ) -> Result<Self, Error> {
         let seq_cntr = SeqCntr::new(&name)?;
 
+        let mut model = S::default();
+        if let Some(layer) = layer {
+            model.state_mut().set_layer(layer);
+        }
+        if let Some(cascade) = cascade {
+            model.state_mut().set_cascade(cascade);
+        }
+
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
 
         Ok(Self{
             node,
-            model: Default::default(),
+            model,
             resp: Default::default(),
             seq_cntr,
             tx,
@@ -103,6 +135,14 @@ where
 
         self.seq_cntr.open_port()?;
 
+        // The events for the strips of the unit are delivered from the other units in cascade.
+        if let Some(cascade) = &self.model.state().cascade {
+            let tx = self.tx.clone();
+            cascade.register_route(Arc::new(move |data| {
+                tx.try_send(AsyncUnitEvent::SeqAppl(data)).is_ok()
+            }));
+        }
+
         self.model.initialize_sequencer(&mut self.node)?;
 
         let mut addr = self.resp.get_property_offset();
@@ -122,8 +162,9 @@ where
 
             match ev {
                 AsyncUnitEvent::Shutdown | AsyncUnitEvent::Disconnected => break,
+                AsyncUnitEvent::Reload => self.reload(),
                 AsyncUnitEvent::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
                 }
                 AsyncUnitEvent::Surface((index, before, after)) => {
                     // Handle error of mutex lock as unrecoverable one.
@@ -174,6 +215,11 @@ where
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(AsyncUnitEvent::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_seq(&self.seq_cntr.client)?;
         self.seq_cntr
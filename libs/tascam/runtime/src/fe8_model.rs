@@ -11,6 +11,7 @@ use crate::*;
 pub struct Fe8Model{
     req: FwReq,
     seq_state: SequencerState<Fe8SurfaceState>,
+    firewire_led: FireWireLedState,
 }
 
 const TIMEOUT_MS: u32 = 50;
@@ -29,7 +30,13 @@ impl SequencerCtlOperation<FwNode, Fe8Protocol, Fe8SurfaceState> for Fe8Model {
         node: &mut FwNode,
         _: &[(MachineItem, ItemValue)],
     ) -> Result<(), Error> {
-        Fe8Protocol::operate_firewire_led(&mut self.req, node, true, TIMEOUT_MS)?;
+        Fe8Protocol::operate_firewire_led(
+            &mut self.firewire_led,
+            &mut self.req,
+            node,
+            true,
+            TIMEOUT_MS,
+        )?;
         Ok(())
     }
 
@@ -40,10 +47,26 @@ impl SequencerCtlOperation<FwNode, Fe8Protocol, Fe8SurfaceState> for Fe8Model {
             node,
             TIMEOUT_MS,
         )?;
-        Fe8Protocol::operate_firewire_led(&mut self.req, node, false, TIMEOUT_MS)?;
+        Fe8Protocol::operate_firewire_led(
+            &mut self.firewire_led,
+            &mut self.req,
+            node,
+            false,
+            TIMEOUT_MS,
+        )?;
         Ok(())
     }
 
+    fn repaint_surface(&mut self, node: &mut FwNode) -> Result<(), Error> {
+        Fe8Protocol::repaint_surface(
+            &mut self.seq_state.surface_state,
+            &self.seq_state.machine_state,
+            &mut self.req,
+            node,
+            TIMEOUT_MS,
+        )
+    }
+
     fn feedback_to_surface(
         &mut self,
         node: &mut FwNode,
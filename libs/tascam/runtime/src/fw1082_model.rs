@@ -3,13 +3,13 @@
 use glib::Error;
 
 use hinawa::FwReq;
-use hinawa::{SndTscm, SndTscmExtManual};
+use hinawa::{SndTscm, SndTscmExtManual, SndUnitExt};
 
 use alsactl::{ElemId, ElemValue};
 
-use core::card_cntr::*;
+use core::{card_cntr::*, identify::*};
 
-use tascam_protocols::isoch::{fw1082::*, *};
+use tascam_protocols::{isoch::{fw1082::*, *}, *};
 
 use crate::{isoch_ctls::*, *};
 
@@ -19,7 +19,9 @@ pub struct Fw1082Model {
     meter_ctl: MeterCtl,
     common_ctl: CommonCtl,
     console_ctl: ConsoleCtl,
+    indicator_ctl: IndicatorCtl,
     seq_state: SequencerState<Fw1082SurfaceState>,
+    firewire_led: FireWireLedState,
 }
 
 const TIMEOUT_MS: u32 = 50;
@@ -51,6 +53,11 @@ struct CommonCtl;
 
 impl IsochCommonCtlOperation<Fw1082Protocol> for CommonCtl {}
 
+#[derive(Default)]
+struct IndicatorCtl(Vec<ElemId>);
+
+impl MachineIndicatorCtlOperation<Fw1082Protocol> for IndicatorCtl {}
+
 #[derive(Default)]
 struct ConsoleCtl(IsochConsoleState, Vec<ElemId>);
 
@@ -65,6 +72,9 @@ impl IsochConsoleCtlOperation<Fw1082Protocol> for ConsoleCtl {
 }
 
 impl SequencerCtlOperation<SndTscm, Fw1082Protocol, Fw1082SurfaceState> for Fw1082Model {
+    // The rotaries operate parameters of the enabled band in equalizer mode.
+    const EQ_NRPN: bool = true;
+
     fn state(&self) -> &SequencerState<Fw1082SurfaceState> {
         &self.seq_state
     }
@@ -93,6 +103,16 @@ impl SequencerCtlOperation<SndTscm, Fw1082Protocol, Fw1082SurfaceState> for Fw10
         )
     }
 
+    fn repaint_surface(&mut self, unit: &mut SndTscm) -> Result<(), Error> {
+        Fw1082Protocol::repaint_surface(
+            &mut self.seq_state.surface_state,
+            &self.seq_state.machine_state,
+            &mut self.req,
+            &mut unit.get_node(),
+            TIMEOUT_MS,
+        )
+    }
+
     fn feedback_to_surface(
         &mut self,
         unit: &mut SndTscm,
@@ -108,6 +128,21 @@ impl SequencerCtlOperation<SndTscm, Fw1082Protocol, Fw1082SurfaceState> for Fw10
     }
 }
 
+impl IdentifyModel<SndTscm> for Fw1082Model {
+    fn blink_indicator(&mut self, unit: &mut SndTscm, on: bool) -> Result<(), Error> {
+        Fw1082Protocol::blink_firewire_led(&mut self.req, &mut unit.get_node(), on, TIMEOUT_MS)
+    }
+
+    fn restore_indicator(&mut self, unit: &mut SndTscm) -> Result<(), Error> {
+        Fw1082Protocol::restore_firewire_led(
+            &self.firewire_led,
+            &mut self.req,
+            &mut unit.get_node(),
+            TIMEOUT_MS,
+        )
+    }
+}
+
 impl MeasureModel<SndTscm> for Fw1082Model {
     fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.meter_ctl.1);
@@ -137,12 +172,33 @@ impl MeasureModel<SndTscm> for Fw1082Model {
     }
 }
 
+impl NotifyModel<SndTscm, bool> for Fw1082Model {
+    fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.indicator_ctl.0);
+    }
+
+    fn parse_notification(&mut self, _: &mut SndTscm, _: &bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn read_notified_elem(
+        &mut self,
+        _: &SndTscm,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.indicator_ctl.read_indicators(&self.seq_state.machine_state, elem_id, elem_value)
+    }
+}
+
 impl CtlModel<SndTscm> for Fw1082Model {
     fn load(
         &mut self,
         unit: &mut SndTscm,
         card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
+        self.meter_ctl
+            .cache_detection_thresholds::<Fw1082Protocol>(unit, &mut self.req, TIMEOUT_MS)?;
         let image = unit.get_state()?;
         self.meter_ctl.load_state(card_cntr, image)
             .map(|mut elem_id_list| self.meter_ctl.1.append(&mut elem_id_list))?;
@@ -152,6 +208,9 @@ impl CtlModel<SndTscm> for Fw1082Model {
         self.console_ctl.load_params(card_cntr, image)
             .map(|mut elem_id_list| self.console_ctl.1.append(&mut elem_id_list))?;
 
+        self.indicator_ctl.load_indicators(card_cntr)
+            .map(|mut elem_id_list| self.indicator_ctl.0.append(&mut elem_id_list))?;
+
         Ok(())
     }
 
@@ -167,6 +226,8 @@ impl CtlModel<SndTscm> for Fw1082Model {
             Ok(true)
         } else if self.console_ctl.read_params(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.indicator_ctl.read_indicators(&self.seq_state.machine_state, elem_id, elem_value)? {
+            Ok(true)
         } else {
             Ok(false)
         }
@@ -180,6 +241,7 @@ impl CtlModel<SndTscm> for Fw1082Model {
         new: &ElemValue,
     ) -> Result<bool, Error> {
         if self.common_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.meter_ctl.observe_detection_threshold(elem_id, new);
             Ok(true)
         } else if self.console_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
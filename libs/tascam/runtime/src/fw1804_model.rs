@@ -3,13 +3,13 @@
 use glib::Error;
 
 use hinawa::FwReq;
-use hinawa::{SndTscm, SndTscmExtManual};
+use hinawa::{SndTscm, SndTscmExtManual, SndUnitExt};
 
 use alsactl::{ElemId, ElemValue};
 
-use core::card_cntr::*;
+use core::{card_cntr::*, identify::*};
 
-use tascam_protocols::isoch::{fw1804::*, *};
+use tascam_protocols::{isoch::{fw1804::*, *}, *};
 
 use super::isoch_ctls::*;
 
@@ -20,6 +20,7 @@ pub struct Fw1804Model {
     common_ctl: CommonCtl,
     optical_ctl: OpticalCtl,
     rack_ctl: RackCtl,
+    firewire_led: FireWireLedState,
 }
 
 const TIMEOUT_MS: u32 = 50;
@@ -81,6 +82,21 @@ impl IsochRackCtlOperation<Fw1804Protocol> for RackCtl {
     }
 }
 
+impl IdentifyModel<SndTscm> for Fw1804Model {
+    fn blink_indicator(&mut self, unit: &mut SndTscm, on: bool) -> Result<(), Error> {
+        Fw1804Protocol::blink_firewire_led(&mut self.req, &mut unit.get_node(), on, TIMEOUT_MS)
+    }
+
+    fn restore_indicator(&mut self, unit: &mut SndTscm) -> Result<(), Error> {
+        Fw1804Protocol::restore_firewire_led(
+            &self.firewire_led,
+            &mut self.req,
+            &mut unit.get_node(),
+            TIMEOUT_MS,
+        )
+    }
+}
+
 impl MeasureModel<SndTscm> for Fw1804Model {
     fn get_measure_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
         elem_id_list.extend_from_slice(&self.meter_ctl.1);
@@ -109,8 +125,11 @@ impl CtlModel<SndTscm> for Fw1804Model {
         unit: &mut SndTscm,
         card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
+        self.meter_ctl
+            .cache_detection_thresholds::<Fw1804Protocol>(unit, &mut self.req, TIMEOUT_MS)?;
         let image = unit.get_state()?;
-        self.meter_ctl.load_state(card_cntr, image)?;
+        self.meter_ctl.load_state(card_cntr, image)
+            .map(|mut elem_id_list| self.meter_ctl.1.append(&mut elem_id_list))?;
         self.common_ctl.load_params(card_cntr)?;
         self.optical_ctl.load_params(card_cntr)?;
         self.rack_ctl.load_params(card_cntr, unit, &mut self.req, TIMEOUT_MS)?;
@@ -144,6 +163,7 @@ impl CtlModel<SndTscm> for Fw1804Model {
         new: &ElemValue,
     ) -> Result<bool, Error> {
         if self.common_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.meter_ctl.observe_detection_threshold(elem_id, new);
             Ok(true)
         } else if self.optical_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
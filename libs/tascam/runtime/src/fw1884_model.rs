@@ -7,7 +7,7 @@ use hinawa::{SndTscm, SndTscmExtManual, SndUnitExt};
 
 use alsactl::{ElemId, ElemIfaceType, ElemValue, ElemValueExt, ElemValueExtManual};
 
-use core::card_cntr::*;
+use core::{card_cntr::*, identify::*};
 
 use tascam_protocols::{isoch::{fw1884::*, *}, *};
 
@@ -20,8 +20,11 @@ pub struct Fw1884Model {
     common_ctl: CommonCtl,
     optical_ctl: OpticalCtl,
     console_ctl: ConsoleCtl,
+    indicator_ctl: IndicatorCtl,
     specific_ctl: SpecificCtl,
     seq_state: SequencerState<Fw1884SurfaceState>,
+    aux_selection: AuxSelection,
+    firewire_led: FireWireLedState,
 }
 
 const TIMEOUT_MS: u32 = 50;
@@ -71,6 +74,11 @@ impl IsochOpticalCtlOperation<Fw1884Protocol> for OpticalCtl {
     ];
 }
 
+#[derive(Default)]
+struct IndicatorCtl(Vec<ElemId>);
+
+impl MachineIndicatorCtlOperation<Fw1884Protocol> for IndicatorCtl {}
+
 #[derive(Default)]
 struct ConsoleCtl(IsochConsoleState, Vec<ElemId>);
 
@@ -87,6 +95,18 @@ impl IsochConsoleCtlOperation<Fw1884Protocol> for ConsoleCtl {
 #[derive(Default)]
 struct SpecificCtl;
 
+/// The selection of channels for each aux, expressed by REC LEDs during aux mode.
+#[derive(Default)]
+struct AuxSelection {
+    mode: Option<usize>,
+    channels: [[bool; AuxSelection::CHANNEL_COUNT]; AuxSelection::AUX_COUNT],
+}
+
+impl AuxSelection {
+    const AUX_COUNT: usize = 8;
+    const CHANNEL_COUNT: usize = 8;
+}
+
 impl SequencerCtlOperation<SndTscm, Fw1884Protocol, Fw1884SurfaceState> for Fw1884Model {
     fn state(&self) -> &SequencerState<Fw1884SurfaceState> {
         &self.seq_state
@@ -116,14 +136,111 @@ impl SequencerCtlOperation<SndTscm, Fw1884Protocol, Fw1884SurfaceState> for Fw18
         )
     }
 
+    fn repaint_surface(&mut self, unit: &mut SndTscm) -> Result<(), Error> {
+        Fw1884Protocol::repaint_surface(
+            &mut self.seq_state.surface_state,
+            &self.seq_state.machine_state,
+            &mut self.req,
+            &mut unit.get_node(),
+            TIMEOUT_MS,
+        )
+    }
+
+    // In aux mode, REC buttons select channels for the aux instead of toggling recording.
+    fn consume_surface_event(
+        &mut self,
+        unit: &mut SndTscm,
+        event: &(MachineItem, ItemValue),
+    ) -> Result<bool, Error> {
+        match (self.aux_selection.mode, event) {
+            (Some(aux), &(MachineItem::Rec(ch), ItemValue::Bool(_)))
+                if ch < AuxSelection::CHANNEL_COUNT =>
+            {
+                let selected = &mut self.aux_selection.channels[aux][ch];
+                *selected = !*selected;
+                Fw1884Protocol::operate_overlaid_rec_led(
+                    &mut self.seq_state.surface_state,
+                    ch,
+                    *selected,
+                    &mut self.req,
+                    &mut unit.get_node(),
+                    TIMEOUT_MS,
+                )?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     fn feedback_to_surface(
         &mut self,
         unit: &mut SndTscm,
         event: &(MachineItem, ItemValue),
     ) -> Result<(), Error> {
-        Fw1884Protocol::feedback_to_surface(
-            &mut self.seq_state.surface_state,
-            event,
+        match event {
+            &(MachineItem::Aux(aux), ItemValue::Bool(true)) if aux < AuxSelection::AUX_COUNT => {
+                if self.aux_selection.mode.take().is_some() {
+                    Fw1884Protocol::restore_rec_leds(
+                        &mut self.seq_state.surface_state,
+                        &mut self.req,
+                        &mut unit.get_node(),
+                        TIMEOUT_MS,
+                    )?;
+                }
+                Fw1884Protocol::feedback_to_surface(
+                    &mut self.seq_state.surface_state,
+                    event,
+                    &mut self.req,
+                    &mut unit.get_node(),
+                    TIMEOUT_MS,
+                )?;
+                Fw1884Protocol::overlay_rec_leds(
+                    &mut self.seq_state.surface_state,
+                    &self.aux_selection.channels[aux],
+                    &mut self.req,
+                    &mut unit.get_node(),
+                    TIMEOUT_MS,
+                )?;
+                self.aux_selection.mode = Some(aux);
+                Ok(())
+            }
+            &(MachineItem::Aux(aux), ItemValue::Bool(false))
+                if self.aux_selection.mode == Some(aux) =>
+            {
+                self.aux_selection.mode = None;
+                Fw1884Protocol::restore_rec_leds(
+                    &mut self.seq_state.surface_state,
+                    &mut self.req,
+                    &mut unit.get_node(),
+                    TIMEOUT_MS,
+                )?;
+                Fw1884Protocol::feedback_to_surface(
+                    &mut self.seq_state.surface_state,
+                    event,
+                    &mut self.req,
+                    &mut unit.get_node(),
+                    TIMEOUT_MS,
+                )
+            }
+            _ => Fw1884Protocol::feedback_to_surface(
+                &mut self.seq_state.surface_state,
+                event,
+                &mut self.req,
+                &mut unit.get_node(),
+                TIMEOUT_MS,
+            ),
+        }
+    }
+}
+
+impl IdentifyModel<SndTscm> for Fw1884Model {
+    fn blink_indicator(&mut self, unit: &mut SndTscm, on: bool) -> Result<(), Error> {
+        Fw1884Protocol::blink_firewire_led(&mut self.req, &mut unit.get_node(), on, TIMEOUT_MS)
+    }
+
+    fn restore_indicator(&mut self, unit: &mut SndTscm) -> Result<(), Error> {
+        Fw1884Protocol::restore_firewire_led(
+            &self.firewire_led,
             &mut self.req,
             &mut unit.get_node(),
             TIMEOUT_MS,
@@ -160,12 +277,33 @@ impl MeasureModel<SndTscm> for Fw1884Model {
     }
 }
 
+impl NotifyModel<SndTscm, bool> for Fw1884Model {
+    fn get_notified_elem_list(&mut self, elem_id_list: &mut Vec<ElemId>) {
+        elem_id_list.extend_from_slice(&self.indicator_ctl.0);
+    }
+
+    fn parse_notification(&mut self, _: &mut SndTscm, _: &bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn read_notified_elem(
+        &mut self,
+        _: &SndTscm,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        self.indicator_ctl.read_indicators(&self.seq_state.machine_state, elem_id, elem_value)
+    }
+}
+
 impl CtlModel<SndTscm> for Fw1884Model {
     fn load(
         &mut self,
         unit: &mut SndTscm,
         card_cntr: &mut CardCntr,
     ) -> Result<(), Error> {
+        self.meter_ctl
+            .cache_detection_thresholds::<Fw1884Protocol>(unit, &mut self.req, TIMEOUT_MS)?;
         let image = unit.get_state()?;
         self.meter_ctl.load_state(card_cntr, image)
             .map(|mut elem_id_list| self.meter_ctl.1.append(&mut elem_id_list))?;
@@ -176,6 +314,9 @@ impl CtlModel<SndTscm> for Fw1884Model {
         self.console_ctl.load_params(card_cntr, image)
             .map(|mut elem_id_list| self.console_ctl.1.append(&mut elem_id_list))?;
 
+        self.indicator_ctl.load_indicators(card_cntr)
+            .map(|mut elem_id_list| self.indicator_ctl.0.append(&mut elem_id_list))?;
+
         self.specific_ctl.load_params(card_cntr)?;
 
         Ok(())
@@ -195,6 +336,8 @@ impl CtlModel<SndTscm> for Fw1884Model {
             Ok(true)
         } else if self.console_ctl.read_params(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
+        } else if self.indicator_ctl.read_indicators(&self.seq_state.machine_state, elem_id, elem_value)? {
+            Ok(true)
         } else if self.specific_ctl.read_params(unit, &mut self.req, elem_id, elem_value, TIMEOUT_MS)? {
             Ok(true)
         } else {
@@ -210,6 +353,7 @@ impl CtlModel<SndTscm> for Fw1884Model {
         new: &ElemValue,
     ) -> Result<bool, Error> {
         if self.common_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
+            self.meter_ctl.observe_detection_threshold(elem_id, new);
             Ok(true)
         } else if self.optical_ctl.write_params(unit, &mut self.req, elem_id, new, TIMEOUT_MS)? {
             Ok(true)
@@ -246,6 +390,7 @@ impl SpecificCtl {
             .collect();
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, MONITOR_ROTARY_ASSIGN_NAME, 0);
         let _ = card_cntr.add_enum_elems(&elem_id, 1, 1, &labels, None, false)?;
+
         Ok(())
     }
 
@@ -18,77 +18,175 @@ use alsaseq::{EventCntrExt, EventCntrExtManual, EventDataCtl, EventType, UserCli
 
 use core::dispatcher::*;
 use core::card_cntr::*;
+use core::identify::*;
+use core::version::*;
+use core::event_queue::*;
+use core::config::*;
+use core::log::*;
+use core::{log_info, log_warn};
 
 use tascam_protocols::{isoch::{fw1082::*, fw1884::*}};
 
-use crate::{fw1082_model::*, fw1884_model::*, seq_cntr::*, *};
+use crate::{fw1082_model::*, fw1884_model::*, isoch_ctls::clock_elem_id_list, seq_cntr::*, *};
 
 pub type Fw1884Runtime = IsochConsoleRuntime<Fw1884Model, Fw1884Protocol, Fw1884SurfaceState>;
 pub type Fw1082Runtime = IsochConsoleRuntime<Fw1082Model, Fw1082Protocol, Fw1082SurfaceState>;
 
 pub struct IsochConsoleRuntime<S, T, U>
 where
-    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
+    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + NotifyModel<SndTscm, bool> +
+        IdentifyModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
     T: MachineStateOperation + SurfaceImageOperation<U>,
 {
     unit: SndTscm,
     model: S,
     card_cntr: CardCntr,
     seq_cntr: SeqCntr,
-    rx: mpsc::Receiver<ConsoleUnitEvent>,
+    rx: EventQueue<ConsoleUnitEvent>,
     tx: mpsc::SyncSender<ConsoleUnitEvent>,
     dispatchers: Vec<Dispatcher>,
     timer: Option<Dispatcher>,
     measure_elems: Vec<ElemId>,
+    notified_elems: Vec<ElemId>,
+    identify_ctl: IdentifyCtl,
+    flush_timer: Option<Dispatcher>,
+    value_rate_limit: bool,
+    value_rate_window: Duration,
+    model_name: String,
+    generation: u32,
     _phantom0: PhantomData<T>,
     _phantom1: PhantomData<U>,
 }
 
 impl<S, T, U> Drop for IsochConsoleRuntime<S, T, U>
 where
-    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
+    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + NotifyModel<SndTscm, bool> +
+        IdentifyModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
     T: MachineStateOperation + SurfaceImageOperation<U>,
 {
     fn drop(&mut self) {
         let _ = self.model.finalize_sequencer(&mut self.unit);
-        self.dispatchers.clear();
+
+        // At first, stop event loop in all of dispatchers to avoid queueing new events.
+        self.timer.iter_mut()
+            .chain(self.identify_ctl.timer_mut())
+            .chain(self.flush_timer.iter_mut())
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
+
+        // Next, consume all events in queue to release blocked thread for sender.
+        for _ in self.rx.try_iter() {}
+
+        // Finally finish I/O threads. The timers and the dispatcher for signal handler are
+        // finished at first, then the dispatcher for node is finished at last.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.identify_ctl.take_timer())
+            .chain(self.flush_timer.take())
+            .chain(self.dispatchers.drain(..).rev());
+        join_dispatchers(dispatchers);
     }
 }
 
 enum ConsoleUnitEvent {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
     Interval,
+    Identify,
+    FlushValues,
+    DumpStats,
     SeqAppl(EventDataCtl),
+    SeqMmc(Vec<u8>),
     Surface((u32, u32, u32)),
 }
 
+impl CoalescedEvent for ConsoleUnitEvent {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            ConsoleUnitEvent::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => {
+                Some(elem_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, ConsoleUnitEvent::Interval)
+    }
+}
+
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
 const SYSTEM_DISPATCHER_NAME: &str = "system event dispatcher";
 const TIMER_DISPATCHER_NAME: &str = "interval timer dispatcher";
+const FLUSH_DISPATCHER_NAME: &str = "value flush timer dispatcher";
 
 const TIMER_NAME: &str = "metering";
 const TIMER_INTERVAL: Duration = Duration::from_millis(50);
 
+const MMC_EMISSION_NAME: &str = "mmc-emission";
+
+const PANIC_NAME: &str = "panic";
+
+const VALUE_RATE_LIMIT_NAME: &str = "value-rate-limit";
+const VALUE_RATE_WINDOW_NAME: &str = "value-rate-limit-window-ms";
+const VALUE_RATE_WINDOW_MIN: i32 = 1;
+const VALUE_RATE_WINDOW_MAX: i32 = 100;
+
+const SELECT_FOLLOW_MODE_NAME: &str = "select-follow-mode";
+const SELECTED_CHANNEL_NAME: &str = "selected-channel";
+
+const CLEAR_SOLO_MODE_NAME: &str = "clear-solo-mode";
+
+impl<S, T, U> ConfigReloadOperation for IsochConsoleRuntime<S, T, U>
+where
+    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + NotifyModel<SndTscm, bool> +
+        IdentifyModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
+    T: MachineStateOperation + SurfaceImageOperation<U>,
+{
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
+    }
+
+    fn apply_runtime_options(&mut self) {
+        self.model.state_mut().apply_value_scales(&current_config().value_scales);
+    }
+}
+
 impl<S, T, U> IsochConsoleRuntime<S, T, U>
 where
-    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
+    S:  CtlModel<SndTscm> + MeasureModel<SndTscm> + NotifyModel<SndTscm, bool> +
+        IdentifyModel<SndTscm> + SequencerCtlOperation<SndTscm, T, U> + Default,
     T: MachineStateOperation + SurfaceImageOperation<U>,
 {
-    pub fn new(unit: SndTscm, name: &str, sysnum: u32) -> Result<Self, Error> {
+    pub fn new(
+        unit: SndTscm,
+        name: &str,
+        sysnum: u32,
+        layer: Option<SequencerLayer>,
+    ) -> Result<Self, Error> {
         let card_cntr = CardCntr::new();
         card_cntr.card.open(sysnum, 0)?;
 
+        let mut model = S::default();
+        if let Some(layer) = layer {
+            model.state_mut().set_layer(layer);
+        }
+
         let seq_cntr = SeqCntr::new(name)?;
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(Self{
             unit,
-            model: Default::default(),
+            model,
             card_cntr,
             seq_cntr,
             tx,
@@ -96,6 +194,13 @@ where
             dispatchers: Default::default(),
             timer: Default::default(),
             measure_elems: Default::default(),
+            notified_elems: Default::default(),
+            identify_ctl: Default::default(),
+            flush_timer: Default::default(),
+            value_rate_limit: false,
+            value_rate_window: ValueRateLimiter::<SystemClock>::DEFAULT_WINDOW,
+            model_name: name.to_string(),
+            generation: 0,
             _phantom0: Default::default(),
             _phantom1: Default::default(),
         })
@@ -105,6 +210,8 @@ where
         self.launch_node_event_dispatcher()?;
         self.launch_system_event_dispatcher()?;
 
+        self.generation = self.unit.get_node().get_property_generation();
+
         self.seq_cntr.open_port()?;
         self.model.initialize_sequencer(&mut self.unit)?;
         self.model.load(&mut self.unit, &mut self.card_cntr)?;
@@ -112,6 +219,66 @@ where
         let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, TIMER_NAME, 0);
         let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         self.model.get_measure_elem_list(&mut self.measure_elems);
+        self.model.get_notified_elem_list(&mut self.notified_elems);
+
+        self.identify_ctl.load(&mut self.card_cntr)?;
+
+        if T::HAS_TRANSPORT {
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, MMC_EMISSION_NAME, 0);
+            let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+        }
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, PANIC_NAME, 0);
+        let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+        // The limiter is disabled by default.
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, VALUE_RATE_LIMIT_NAME, 0);
+        let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, VALUE_RATE_WINDOW_NAME, 0);
+        let _ = self.card_cntr.add_int_elems(
+            &elem_id,
+            1,
+            VALUE_RATE_WINDOW_MIN,
+            VALUE_RATE_WINDOW_MAX,
+            1,
+            1,
+            None,
+            true,
+        )?;
+        let mut elem_value = ElemValue::new();
+        elem_value.set_int(&[self.value_rate_window.as_millis() as i32]);
+        self.card_cntr.card.write_elem_value(&elem_id, &elem_value)?;
+
+        // The clear solo mode is disabled by default.
+        if T::BOOL_ITEMS.contains(&MachineItem::ClrSolo) {
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, CLEAR_SOLO_MODE_NAME, 0);
+            let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+        }
+
+        // The follow mode is disabled by default. The last index of channel is for master.
+        if T::BOOL_ITEMS.contains(&MachineItem::Select(0)) {
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SELECT_FOLLOW_MODE_NAME, 0);
+            let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
+
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SELECTED_CHANNEL_NAME, 0);
+            let _ = self.card_cntr.add_int_elems(
+                &elem_id,
+                1,
+                0,
+                T::SELECT_CHANNEL_COUNT as i32,
+                1,
+                1,
+                None,
+                true,
+            )?;
+        }
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            tascam_protocols::CRATE_VERSION,
+        )?;
 
         Ok(())
     }
@@ -125,12 +292,108 @@ where
 
             match ev {
                 ConsoleUnitEvent::Shutdown => break,
+                ConsoleUnitEvent::Reload => self.reload(),
                 ConsoleUnitEvent::Disconnected => break,
                 ConsoleUnitEvent::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
+
+                    // The configuration of clock can be changed by the other node.
+                    clock_elem_id_list().iter().for_each(|elem_id| {
+                        let _ = self.card_cntr.dispatch_elem_event(
+                            &mut self.unit,
+                            elem_id,
+                            &ElemEventMask::ADD,
+                            &mut self.model,
+                        );
+                    });
+
+                    // The LEDs are turned off by the unit when it is power-cycled and reconnected,
+                    // thus repaint them.
+                    let reconnected = is_reconnected(self.generation, generation);
+                    self.generation = generation;
+                    if reconnected {
+                        if let Err(e) = self.model.recover_surface(&mut self.unit) {
+                            log_warn!(LOG_RUNTIME, "Fail to recover control surface: {}", e);
+                        }
+                    }
                 }
                 ConsoleUnitEvent::Elem((elem_id, events)) => {
-                    if elem_id.get_name() != TIMER_NAME {
+                    let name = elem_id.get_name();
+                    if IdentifyCtl::is_identify_elem(&elem_id) {
+                        let _ = self.identify_ctl.handle_elem_event(
+                            &mut self.card_cntr,
+                            &elem_id,
+                            &self.tx,
+                            || ConsoleUnitEvent::Identify,
+                        );
+                    } else if name == MMC_EMISSION_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            self.model.state_mut().set_mmc_emission(vals[0]);
+                        }
+                    } else if name == VALUE_RATE_LIMIT_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            self.value_rate_limit = vals[0];
+                            self.update_value_rate_limit();
+                        }
+                    } else if name == VALUE_RATE_WINDOW_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [0];
+                            elem_value.get_int(&mut vals);
+                            let ms = vals[0].max(VALUE_RATE_WINDOW_MIN).min(VALUE_RATE_WINDOW_MAX);
+                            self.value_rate_window = Duration::from_millis(ms as u64);
+                            self.update_value_rate_limit();
+                        }
+                    } else if name == SELECT_FOLLOW_MODE_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            if let Err(e) = self.model.dispatch_select_follow_mode(&mut self.unit, vals[0]) {
+                                log_warn!(LOG_RUNTIME, "Fail to update select items: {}", e);
+                            }
+                            self.update_selected_channel();
+                        }
+                    } else if name == CLEAR_SOLO_MODE_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            if let Err(e) = self.model.dispatch_clear_solo_mode(&mut self.unit, vals[0]) {
+                                log_warn!(LOG_RUNTIME, "Fail to update clear solo item: {}", e);
+                            }
+                        }
+                    } else if name == SELECTED_CHANNEL_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [0];
+                            elem_value.get_int(&mut vals);
+                            let ch = vals[0].max(0).min(T::SELECT_CHANNEL_COUNT as i32) as usize;
+                            if let Err(e) = self.model.dispatch_selected_channel(&mut self.unit, ch) {
+                                log_warn!(LOG_RUNTIME, "Fail to update select items: {}", e);
+                            }
+                        }
+                    } else if name == PANIC_NAME {
+                        let mut elem_value = ElemValue::new();
+                        if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                            let mut vals = [false];
+                            elem_value.get_bool(&mut vals);
+                            if vals[0] {
+                                if let Err(e) = self.model.dispatch_appl_panic(&mut self.seq_cntr) {
+                                    log_warn!(LOG_RUNTIME, "Fail to release items: {}", e);
+                                }
+                                // Release the trigger for next operation.
+                                elem_value.set_bool(&[false]);
+                                let _ = self.card_cntr.card.write_elem_value(&elem_id, &elem_value);
+                            }
+                        }
+                    } else if name != TIMER_NAME {
                         let _ = self.card_cntr.dispatch_elem_event(
                             &mut self.unit,
                             &elem_id,
@@ -157,16 +420,49 @@ where
                         &mut self.model
                     );
                 }
+                ConsoleUnitEvent::Identify => {
+                    let _ = self.identify_ctl.blink(
+                        &mut self.card_cntr,
+                        &mut self.unit,
+                        &mut self.model,
+                    );
+                }
+                ConsoleUnitEvent::DumpStats => {
+                    log_info!(
+                        LOG_RUNTIME,
+                        "Statistics of surface for {}: {}",
+                        self.model_name,
+                        self.model.state().surface_stats()
+                    );
+                }
+                ConsoleUnitEvent::FlushValues => {
+                    let _ = self.model.flush_limited_values(&mut self.seq_cntr);
+                    if !self.model.state().has_pending_values() {
+                        self.stop_flush_timer();
+                    }
+                }
                 ConsoleUnitEvent::SeqAppl(data) => {
-                    let _ = self.model.dispatch_appl_event(
+                    let res = self.model.dispatch_appl_event(
                         &mut self.unit,
                         &mut self.seq_cntr,
                         &data,
                     );
+                    if let Ok(true) = res {
+                        self.update_selected_channel();
+                    }
+                    let _ = self.dispatch_machine_notification();
+                }
+                ConsoleUnitEvent::SeqMmc(msg) => {
+                    let _ = self.model.dispatch_appl_mmc(
+                        &mut self.unit,
+                        &mut self.seq_cntr,
+                        &msg,
+                    );
+                    let _ = self.dispatch_machine_notification();
                 }
                 ConsoleUnitEvent::Surface((index, before, after)) => {
                     let image = self.unit.get_state().map(|s| s.to_vec())?;
-                    let _ = self.model.dispatch_surface_event(
+                    let res = self.model.dispatch_surface_event(
                         &mut self.unit,
                         &mut self.seq_cntr,
                         &image,
@@ -174,6 +470,14 @@ where
                         before,
                         after,
                     );
+                    if self.model.state().has_pending_values() {
+                        let _ = self.start_flush_timer();
+                    }
+                    // The element is not read for events without select items, e.g. fader.
+                    if let Ok(true) = res {
+                        self.update_selected_channel();
+                    }
+                    let _ = self.dispatch_machine_notification();
                 }
             }
         }
@@ -221,6 +525,17 @@ where
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(ConsoleUnitEvent::Reload);
+        });
+
+        let tx = self.tx.clone();
+        dispatcher.attach_signal_handler(signal::Signal::SIGUSR1, move || {
+            let _ = tx.send(ConsoleUnitEvent::DumpStats);
+            source::Continue(true)
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr
@@ -234,15 +549,22 @@ where
         self.seq_cntr
             .client
             .connect_handle_event(move |_, ev_cntr| {
-                let _ = (0..ev_cntr.count_events())
-                    .filter(|&i| {
-                        // At present, controller event is handled.
-                        ev_cntr.get_event_type(i).unwrap_or(EventType::None) == EventType::Controller
-                    }).for_each(|i| {
-                        if let Ok(ctl_data) = ev_cntr.get_ctl_data(i) {
-                            let data = ConsoleUnitEvent::SeqAppl(ctl_data);
-                            let _ = tx.send(data);
+                // At present, controller event and system exclusive event for MIDI Machine Control
+                // are handled.
+                (0..ev_cntr.count_events())
+                    .for_each(|i| match ev_cntr.get_event_type(i).unwrap_or(EventType::None) {
+                        EventType::Controller => {
+                            if let Ok(ctl_data) = ev_cntr.get_ctl_data(i) {
+                                let data = ConsoleUnitEvent::SeqAppl(ctl_data);
+                                let _ = tx.send(data);
+                            }
+                        }
+                        EventType::Sysex => {
+                            if let Ok(msg) = ev_cntr.get_blob_data(i) {
+                                let _ = tx.send(ConsoleUnitEvent::SeqMmc(msg.to_vec()));
+                            }
                         }
+                        _ => (),
                     });
         });
 
@@ -251,10 +573,37 @@ where
         Ok(())
     }
 
+    // The state of machine is possibly changed by the event.
+    fn dispatch_machine_notification(&mut self) -> Result<(), Error> {
+        self.card_cntr.dispatch_notification(
+            &mut self.unit,
+            &true,
+            &self.notified_elems,
+            &mut self.model,
+        )
+    }
+
+    // The element is written just when the channel is selected by select items in follow mode, so
+    // that the application can be notified.
+    fn update_selected_channel(&mut self) {
+        if let Some(ch) = self.model.selected_channel() {
+            let elem_id = ElemId::new_by_name(ElemIfaceType::Card, 0, 0, SELECTED_CHANNEL_NAME, 0);
+            let mut elem_value = ElemValue::new();
+            if self.card_cntr.card.read_elem_value(&elem_id, &mut elem_value).is_ok() {
+                let mut vals = [0];
+                elem_value.get_int(&mut vals);
+                if vals[0] != ch as i32 {
+                    elem_value.set_int(&[ch as i32]);
+                    let _ = self.card_cntr.card.write_elem_value(&elem_id, &elem_value);
+                }
+            }
+        }
+    }
+
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = Dispatcher::run(TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(TIMER_INTERVAL), move || {
             let _ = tx.send(ConsoleUnitEvent::Interval);
             source::Continue(true)
         });
@@ -265,9 +614,63 @@ where
     }
 
     fn stop_interval_timer(&mut self) {
-        if let Some(dispatcher) = &self.timer {
-            drop(dispatcher);
-            self.timer = None;
+        join_dispatchers(self.timer.take());
+    }
+
+    // The pending values are emitted at once when the limiter is disabled.
+    fn update_value_rate_limit(&mut self) {
+        let window = if self.value_rate_limit {
+            Some(self.value_rate_window)
+        } else {
+            None
+        };
+        self.model.state_mut().set_value_rate_limit(window);
+        self.stop_flush_timer();
+        let _ = self.model.flush_limited_values(&mut self.seq_cntr);
+        if self.model.state().has_pending_values() {
+            let _ = self.start_flush_timer();
         }
     }
+
+    // The timer runs just while any value is pending.
+    fn start_flush_timer(&mut self) -> Result<(), Error> {
+        if self.flush_timer.is_none() {
+            let mut dispatcher = Dispatcher::run(FLUSH_DISPATCHER_NAME.to_string())?;
+            let tx = self.tx.clone();
+            dispatcher.attach_interval_handler(self.value_rate_window, move || {
+                let _ = tx.send(ConsoleUnitEvent::FlushValues);
+                source::Continue(true)
+            });
+
+            self.flush_timer = Some(dispatcher);
+        }
+
+        Ok(())
+    }
+
+    fn stop_flush_timer(&mut self) {
+        join_dispatchers(self.flush_timer.take());
+    }
+}
+
+// The event of bus reset is not delivered while the node is gone, thus the generation is skipped
+// when the node is power-cycled and reconnected. The generation is 8 bit in 1394 OHCI.
+fn is_reconnected(last: u32, generation: u32) -> bool {
+    (generation.wrapping_sub(last) & 0xff) > 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reconnection() {
+        // The bus reset by the other node.
+        assert!(!is_reconnected(3, 4));
+        assert!(!is_reconnected(0xff, 0x00));
+
+        // The node is gone at the bus reset with generation 4.
+        assert!(is_reconnected(3, 5));
+        assert!(is_reconnected(0xfe, 0x00));
+    }
 }
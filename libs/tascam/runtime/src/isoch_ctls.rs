@@ -13,7 +13,7 @@ use alsa_ctl_tlv_codec::items::DbInterval;
 use core::card_cntr::*;
 use core::elem_value_accessor::*;
 
-use tascam_protocols::isoch::*;
+use tascam_protocols::{isoch::*, MachineState, MachineStateOperation};
 
 const MONITOR_ROTARY_NAME: &str = "monitor-rotary";
 const SOLO_ROTARY_NAME: &str = "solo-rotary";
@@ -24,6 +24,8 @@ const DETECTED_CLK_RATE_NAME: &str = "detected-clock-rate";
 const MONITOR_METER_NAME: &str = "monitor-meters";
 const ANALOG_MIXER_METER_NAME: &str = "analog-mixer-meters";
 const MONITOR_MODE_NAME: &str = "monitor-mode";
+const SIGNAL_DETECTION_NAME: &str = "analog-input-signal-detection";
+const OVER_LEVEL_DETECTION_NAME: &str = "analog-input-over-level-detection";
 
 fn clk_src_to_str(src: &Option<ClkSrc>) -> &'static str {
     match src {
@@ -40,7 +42,7 @@ fn clk_rate_to_str(rate: &Option<ClkRate>) -> &'static str {
         Some(ClkRate::R44100) => "44100",
         Some(ClkRate::R48000) => "48000",
         Some(ClkRate::R88200) => "88200",
-        Some(ClkRate::R96000) => "i96000",
+        Some(ClkRate::R96000) => "96000",
         None => "N/A",
     }
 }
@@ -203,7 +205,19 @@ pub trait IsochMeterCtlOperation<T: IsochMeterOperation> {
             .add_enum_elems(&elem_id, 1, 1, &labels, None, false)
             .map(|mut elem_id_list| measured_elem_list.append(&mut elem_id_list))?;
 
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, SIGNAL_DETECTION_NAME, 0);
+        card_cntr
+            .add_bool_elems(&elem_id, 1, T::ANALOG_INPUT_COUNT, false)
+            .map(|mut elem_id_list| measured_elem_list.append(&mut elem_id_list))?;
+
+        let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, OVER_LEVEL_DETECTION_NAME, 0);
+        card_cntr
+            .add_bool_elems(&elem_id, 1, T::ANALOG_INPUT_COUNT, false)
+            .map(|mut elem_id_list| measured_elem_list.append(&mut elem_id_list))?;
+
+        let thresholds = self.meter().detection_thresholds;
         *self.meter_mut() = T::create_meter_state();
+        self.meter_mut().detection_thresholds = thresholds;
         self.parse_state(image)?;
 
         Ok(measured_elem_list)
@@ -273,9 +287,52 @@ pub trait IsochMeterCtlOperation<T: IsochMeterOperation> {
                 elem_value.set_enum(&[pos as u32]);
                 Ok(true)
             }
+            SIGNAL_DETECTION_NAME => {
+                elem_value.set_bool(&self.meter().signal_detections);
+                Ok(true)
+            }
+            OVER_LEVEL_DETECTION_NAME => {
+                elem_value.set_bool(&self.meter().over_level_detections);
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
+
+    /// Cache the thresholds of signal and over-level detection in the device, to derive the
+    /// detection of analog inputs from the meter.
+    fn cache_detection_thresholds<U: IsochCommonOperation>(
+        &mut self,
+        unit: &mut SndTscm,
+        req: &mut FwReq,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let mut node = unit.get_node();
+        let signal =
+            U::get_analog_input_threshold_for_signal_detection(req, &mut node, timeout_ms)?;
+        let over_level =
+            U::get_analog_input_threshold_for_over_level_detection(req, &mut node, timeout_ms)?;
+        self.meter_mut().detection_thresholds = Some((signal, over_level));
+        Ok(())
+    }
+
+    /// Follow the change of thresholds by the write operation to the elements for them.
+    fn observe_detection_threshold(&mut self, elem_id: &ElemId, elem_value: &ElemValue) {
+        if let Some((signal, over_level)) = &mut self.meter_mut().detection_thresholds {
+            let mut vals = [0];
+            match elem_id.get_name().as_str() {
+                SIGNAL_DETECTION_THRESHOLD_NAME => {
+                    elem_value.get_int(&mut vals);
+                    *signal = vals[0] as u16;
+                }
+                OVER_LEVEL_DETECTION_THRESHOLD_NAME => {
+                    elem_value.get_int(&mut vals);
+                    *over_level = vals[0] as u16;
+                }
+                _ => (),
+            }
+        }
+    }
 }
 
 const CLK_SRC_NAME: &str = "clock-source";
@@ -291,6 +348,15 @@ fn coaxial_output_source_to_str(src: &CoaxialOutputSource) -> &str {
     }
 }
 
+/// The elements for source and rate of sampling clock, to be read again after bus reset since the
+/// configuration can be changed by the other node or by power-cycle.
+pub fn clock_elem_id_list() -> Vec<ElemId> {
+    [CLK_SRC_NAME, CLK_RATE_NAME]
+        .iter()
+        .map(|name| ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, name, 0))
+        .collect()
+}
+
 pub trait IsochCommonCtlOperation<T: IsochCommonOperation> {
     const CLOCK_RATES: [ClkRate; 4] = [
         ClkRate::R44100,
@@ -426,7 +492,7 @@ pub trait IsochCommonCtlOperation<T: IsochCommonOperation> {
                 elem_value.set_enum(&[pos as u32]);
                 Ok(true)
             }
-            _ => Ok(true),
+            _ => Ok(false),
         }
     }
 
@@ -701,6 +767,45 @@ pub trait IsochConsoleCtlOperation<T: IsochConsoleOperation> {
     }
 }
 
+const ANY_SOLO_ACTIVE_NAME: &str = "any-solo-active";
+const ANY_MUTE_ACTIVE_NAME: &str = "any-mute-active";
+
+pub trait MachineIndicatorCtlOperation<T: MachineStateOperation> {
+    fn load_indicators(&mut self, card_cntr: &mut CardCntr) -> Result<Vec<ElemId>, Error> {
+        let mut notified_elem_list = Vec::new();
+
+        [ANY_SOLO_ACTIVE_NAME, ANY_MUTE_ACTIVE_NAME]
+            .iter()
+            .try_for_each(|name| {
+                let elem_id = ElemId::new_by_name(ElemIfaceType::Mixer, 0, 0, name, 0);
+                card_cntr
+                    .add_bool_elems(&elem_id, 1, 1, false)
+                    .map(|mut elem_id_list| notified_elem_list.append(&mut elem_id_list))
+            })?;
+
+        Ok(notified_elem_list)
+    }
+
+    fn read_indicators(
+        &self,
+        state: &MachineState,
+        elem_id: &ElemId,
+        elem_value: &mut ElemValue,
+    ) -> Result<bool, Error> {
+        match elem_id.get_name().as_str() {
+            ANY_SOLO_ACTIVE_NAME => {
+                elem_value.set_bool(&[T::is_any_solo_active(state)]);
+                Ok(true)
+            }
+            ANY_MUTE_ACTIVE_NAME => {
+                elem_value.set_bool(&[T::is_any_mute_active(state)]);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
 const INPUT_GAIN_NAME: &str = "input-gain";
 const INPUT_BALANCE_NAME: &str = "input-balance";
 const INPUT_MUTE_NAME: &str = "input-mute";
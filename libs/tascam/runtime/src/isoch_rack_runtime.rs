@@ -16,43 +16,72 @@ use alsactl::{ElemEventMask, ElemId, ElemIfaceType, ElemValue, ElemValueExtManua
 
 use core::dispatcher::*;
 use core::card_cntr::*;
+use core::identify::*;
+use core::version::*;
+use core::event_queue::*;
+use core::config::*;
+use core::log::*;
+use core::log_info;
 
-use crate::fw1804_model::*;
+use crate::{fw1804_model::*, isoch_ctls::clock_elem_id_list};
 
 pub type Fw1804Runtime = IsochRackRuntime<Fw1804Model>;
 
-pub struct IsochRackRuntime<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> {
+pub struct IsochRackRuntime<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + IdentifyModel<SndTscm> + Default> {
     unit: SndTscm,
     model: T,
     card_cntr: CardCntr,
-    rx: mpsc::Receiver<RackUnitEvent>,
+    rx: EventQueue<RackUnitEvent>,
     tx: mpsc::SyncSender<RackUnitEvent>,
     dispatchers: Vec<Dispatcher>,
     timer: Option<Dispatcher>,
     measure_elems: Vec<ElemId>,
+    identify_ctl: IdentifyCtl,
 }
 
-impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> Drop for IsochRackRuntime<T> {
+impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + IdentifyModel<SndTscm> + Default> Drop for IsochRackRuntime<T> {
     fn drop(&mut self) {
         // At first, stop event loop in all of dispatchers to avoid queueing new events.
-        for dispatcher in &mut self.dispatchers {
-            dispatcher.stop();
-        }
+        self.timer.iter_mut()
+            .chain(self.identify_ctl.timer_mut())
+            .chain(self.dispatchers.iter_mut())
+            .for_each(|dispatcher| dispatcher.stop());
 
         // Next, consume all events in queue to release blocked thread for sender.
         for _ in self.rx.try_iter() {}
 
-        // Finally Finish I/O threads.
-        self.dispatchers.clear();
+        // Finally finish I/O threads. The timers and the dispatcher for signal handler are
+        // finished at first, then the dispatcher for node is finished at last.
+        let dispatchers = self.timer.take().into_iter()
+            .chain(self.identify_ctl.take_timer())
+            .chain(self.dispatchers.drain(..).rev());
+        join_dispatchers(dispatchers);
     }
 }
 
 enum RackUnitEvent {
     Shutdown,
+    Reload,
     Disconnected,
     BusReset(u32),
     Elem((ElemId, ElemEventMask)),
     Timer,
+    Identify,
+}
+
+impl CoalescedEvent for RackUnitEvent {
+    fn value_changed_elem_id(&self) -> Option<&ElemId> {
+        match self {
+            RackUnitEvent::Elem((elem_id, events)) if *events == ElemEventMask::VALUE => {
+                Some(elem_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_interval_tick(&self) -> bool {
+        matches!(self, RackUnitEvent::Timer)
+    }
 }
 
 const NODE_DISPATCHER_NAME: &str = "node event dispatcher";
@@ -62,13 +91,24 @@ const TIMER_DISPATCHER_NAME: &str = "interval timer dispatcher";
 const TIMER_NAME: &str = "meter";
 const TIMER_INTERVAL: Duration = Duration::from_millis(50);
 
-impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T> {
+impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + IdentifyModel<SndTscm> + Default> ConfigReloadOperation for IsochRackRuntime<T> {
+    fn restart_metering_timer(&mut self) -> Result<(), Error> {
+        if self.timer.is_some() {
+            self.stop_interval_timer();
+            self.start_interval_timer()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + IdentifyModel<SndTscm> + Default> IsochRackRuntime<T> {
     pub fn new(unit: SndTscm, _: &str, sysnum: u32) -> Result<Self, Error> {
         let card_cntr = CardCntr::new();
         card_cntr.card.open(sysnum, 0)?;
 
         // Use uni-directional channel for communication to child threads.
         let (tx, rx) = mpsc::sync_channel(32);
+        let rx = EventQueue::new(rx);
 
         Ok(Self{
             unit,
@@ -79,6 +119,7 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
             dispatchers: Default::default(),
             timer: Default::default(),
             measure_elems: Default::default(),
+            identify_ctl: Default::default(),
         })
     }
 
@@ -92,6 +133,14 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
         let _ = self.card_cntr.add_bool_elems(&elem_id, 1, 1, true)?;
         self.model.get_measure_elem_list(&mut self.measure_elems);
 
+        self.identify_ctl.load(&mut self.card_cntr)?;
+
+        add_version_elems(
+            &mut self.card_cntr,
+            env!("CARGO_PKG_VERSION"),
+            tascam_protocols::CRATE_VERSION,
+        )?;
+
         Ok(())
     }
 
@@ -104,12 +153,31 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
 
             match ev {
                 RackUnitEvent::Shutdown => break,
+                RackUnitEvent::Reload => self.reload(),
                 RackUnitEvent::Disconnected => break,
                 RackUnitEvent::BusReset(generation) => {
-                    println!("IEEE 1394 bus is updated: {}", generation);
+                    log_info!(LOG_RUNTIME, "IEEE 1394 bus is updated: {}", generation);
+
+                    // The configuration of clock can be changed by the other node.
+                    clock_elem_id_list().iter().for_each(|elem_id| {
+                        let _ = self.card_cntr.dispatch_elem_event(
+                            &mut self.unit,
+                            elem_id,
+                            &ElemEventMask::ADD,
+                            &mut self.model,
+                        );
+                    });
                 }
                 RackUnitEvent::Elem((elem_id, events)) => {
-                    if elem_id.get_name() != TIMER_NAME {
+                    let name = elem_id.get_name();
+                    if IdentifyCtl::is_identify_elem(&elem_id) {
+                        let _ = self.identify_ctl.handle_elem_event(
+                            &mut self.card_cntr,
+                            &elem_id,
+                            &self.tx,
+                            || RackUnitEvent::Identify,
+                        );
+                    } else if name != TIMER_NAME {
                         let _ = self.card_cntr.dispatch_elem_event(
                             &mut self.unit,
                             &elem_id,
@@ -136,6 +204,13 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
                         &mut self.model
                     );
                 }
+                RackUnitEvent::Identify => {
+                    let _ = self.identify_ctl.blink(
+                        &mut self.card_cntr,
+                        &mut self.unit,
+                        &mut self.model,
+                    );
+                }
             }
         }
 
@@ -177,6 +252,11 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
             source::Continue(false)
         });
 
+        let tx = self.tx.clone();
+        attach_reload_handler(&mut dispatcher, move || {
+            let _ = tx.send(RackUnitEvent::Reload);
+        });
+
         let tx = self.tx.clone();
         dispatcher.attach_snd_card(&self.card_cntr.card, |_| {})?;
         self.card_cntr
@@ -193,7 +273,7 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
     fn start_interval_timer(&mut self) -> Result<(), Error> {
         let mut dispatcher = Dispatcher::run(TIMER_DISPATCHER_NAME.to_string())?;
         let tx = self.tx.clone();
-        dispatcher.attach_interval_handler(TIMER_INTERVAL, move || {
+        dispatcher.attach_interval_handler(metering_interval(TIMER_INTERVAL), move || {
             let _ = tx.send(RackUnitEvent::Timer);
             source::Continue(true)
         });
@@ -204,9 +284,6 @@ impl<T: CtlModel<SndTscm> + MeasureModel<SndTscm> + Default> IsochRackRuntime<T>
     }
 
     fn stop_interval_timer(&mut self) {
-        if let Some(dispatcher) = &self.timer {
-            drop(dispatcher);
-            self.timer = None;
-        }
+        join_dispatchers(self.timer.take());
     }
 }
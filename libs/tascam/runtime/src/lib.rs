@@ -13,6 +13,8 @@ mod isoch_ctls;
 
 mod seq_cntr;
 
+pub mod surface_stats;
+
 use glib::{Error, FileError};
 
 use hinawa::{FwNode, FwNodeExt, FwNodeExtManual};
@@ -20,35 +22,186 @@ use hinawa::{SndTscm, SndTscmExt, SndUnitExt};
 
 use alsaseq::EventDataCtl;
 
-use core::RuntimeOperation;
+use core::{burst::*, config_rom::ConfigRomError, RuntimeOperation};
+use core::channel_name::guid_from_config_rom;
+use core::config::{current_config, CascadeOrder, ValueCurve, ValueScale};
+use core::log::*;
+use core::log_warn;
 
 use ieee1212_config_rom::*;
 
 use tascam_protocols::{config_rom::*, *};
 
 use seq_cntr::*;
+use surface_stats::*;
 
 use isoch_console_runtime::*;
 use isoch_rack_runtime::*;
 use asynch_runtime::*;
 
 use std::convert::TryFrom;
+use std::sync::{atomic::{AtomicU16, Ordering}, mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The runtime to manage several units in the same process. Each unit is operated in dedicated
+/// thread, thus disconnection of the unit doesn't affect the others.
+pub struct TascamRuntime {
+    units: Vec<(String, u32)>,
+    threads: Vec<((String, u32), thread::JoinHandle<Result<(), Error>>)>,
+}
+
+impl RuntimeOperation<Vec<(String, u32)>> for TascamRuntime {
+    fn new(units: Vec<(String, u32)>) -> Result<Self, Error> {
+        if units.is_empty() {
+            Err(Error::new(FileError::Inval, "No unit is given"))?;
+        }
+
+        Ok(Self {
+            units,
+            threads: Default::default(),
+        })
+    }
+
+    fn listen(&mut self) -> Result<(), Error> {
+        // The layer of sequencer is shared just for several units.
+        let bank = Arc::new(AtomicU16::new(0));
+        let count = self.units.len();
+
+        // The units of fw subsystem (FE-8) are cascaded in the order of command line or GUID.
+        let cascade_units: Vec<u32> = self
+            .units
+            .iter()
+            .filter(|(subsystem, _)| subsystem == "fw")
+            .map(|&(_, sysnum)| sysnum)
+            .collect();
+        let cascade_count = cascade_units.len();
+        let cascade_indices = if cascade_count > 1 {
+            cascade_order(&cascade_units, current_config().cascade_order.unwrap_or_default())
+        } else {
+            Vec::new()
+        };
+        let router = CascadeRouter::new(cascade_count);
+        let mut cascade_pos = 0;
+
+        self.units.iter().enumerate().try_for_each(|(index, unit)| {
+            let layer = if count > 1 {
+                Some(SequencerLayer {
+                    bank: bank.clone(),
+                    index,
+                    count,
+                })
+            } else {
+                None
+            };
+
+            let cascade = if unit.0 == "fw" && cascade_count > 1 {
+                cascade_pos += 1;
+                let index = cascade_indices[cascade_pos - 1];
+                Some(SequencerCascade::new(index, cascade_count, router.clone()))
+            } else {
+                None
+            };
+
+            let (tx, rx) = mpsc::channel();
+            let args = unit.clone();
+            let handle = thread::spawn(move || {
+                let res = TascamUnitRuntime::new(args, layer, cascade)
+                    .and_then(|mut runtime| runtime.listen().map(|_| runtime));
+                match res {
+                    Ok(mut runtime) => {
+                        let _ = tx.send(Ok(()));
+                        runtime.run()
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e.clone()));
+                        Err(e)
+                    }
+                }
+            });
+            self.threads.push((unit.clone(), handle));
+
+            rx.recv().unwrap_or_else(|_| Err(aborted_thread_error()))
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        let mut first_err = None;
+
+        // The thread finishes when the unit is disconnected, while the others are kept.
+        self.threads.drain(..).for_each(|((subsystem, sysnum), handle)| {
+            let res = handle.join().unwrap_or_else(|_| Err(aborted_thread_error()));
+            if let Err(e) = res {
+                log_warn!(LOG_RUNTIME, "{} {}: {}", subsystem, sysnum, e);
+                if first_err.is_none() {
+                    first_err = Some(e);
+                }
+            }
+        });
+
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+// The index in cascade for each unit of fw subsystem given in command line.
+fn cascade_order(sysnums: &[u32], order: CascadeOrder) -> Vec<usize> {
+    let guids = match order {
+        CascadeOrder::CommandLine => None,
+        CascadeOrder::Guid => sysnums
+            .iter()
+            .map(|&sysnum| {
+                read_node_guid(sysnum).map_err(|e| {
+                    log_warn!(LOG_RUNTIME, "fw {}: GUID is not available: {}", sysnum, e);
+                })
+            })
+            .collect::<Result<Vec<u64>, ()>>()
+            .ok(),
+    };
+
+    match guids {
+        Some(guids) => cascade_indices_by_guid(&guids),
+        None => (0..sysnums.len()).collect(),
+    }
+}
+
+fn read_node_guid(sysnum: u32) -> Result<u64, Error> {
+    let node = FwNode::new();
+    let devnode = format!("/dev/fw{}", sysnum);
+    node.open(&devnode)?;
+    node.get_config_rom().and_then(|raw| {
+        guid_from_config_rom(&raw)
+            .ok_or_else(|| Error::new(FileError::Io, "Bus information block is truncated"))
+    })
+}
+
+// The unit with the smaller GUID precedes in cascade.
+fn cascade_indices_by_guid(guids: &[u64]) -> Vec<usize> {
+    guids
+        .iter()
+        .map(|guid| guids.iter().filter(|g| *g < guid).count())
+        .collect()
+}
 
-pub enum TascamRuntime {
+fn aborted_thread_error() -> Error {
+    Error::new(FileError::Failed, "Thread for the unit is aborted")
+}
+
+enum TascamUnitRuntime {
     Fw1884(Fw1884Runtime),
     Fw1082(Fw1082Runtime),
     Fw1804(Fw1804Runtime),
     Fe8(Fe8Runtime),
 }
 
-const TASCAM_OUI: u32 = 0x00022e;
-const FW1884_SW_VERSION: u32 = 0x800000;
-const FE8_SW_VERSION: u32 = 0x800001;
-const FW1082_SW_VERSION: u32 = 0x800003;
-const FW1804_SW_VERSION: u32 = 0x800004;
-
-impl RuntimeOperation<(String, u32)> for TascamRuntime {
-    fn new((subsystem, sysnum): (String, u32)) -> Result<Self, Error> {
+impl TascamUnitRuntime {
+    fn new(
+        (subsystem, sysnum): (String, u32),
+        layer: Option<SequencerLayer>,
+        cascade: Option<SequencerCascade>,
+    ) -> Result<Self, Error> {
         match subsystem.as_str() {
             "snd" => {
                 let unit = SndTscm::new();
@@ -58,21 +211,21 @@ impl RuntimeOperation<(String, u32)> for TascamRuntime {
                 let node = unit.get_node();
                 let data = node.get_config_rom()?;
                 let config_rom = ConfigRom::try_from(data)
-                    .map_err(|e| {
-                        let label = format!("Malformed configuration ROM detected: {}", e.to_string());
-                        Error::new(FileError::Nxio, &label)
-                    })?;
-                let unit_data = config_rom.get_unit_data()?;
+                    .map_err(|e| config_rom_error(&TascamConfigRomError::from(e)))?;
+                let unit_data = identify_unit(&config_rom)?;
                 match (unit_data.specifier_id, unit_data.version) {
                     (TASCAM_OUI, FW1884_SW_VERSION) => {
-                        let runtime = Fw1884Runtime::new(unit, unit_data.model_name, sysnum)?;
+                        let runtime =
+                            Fw1884Runtime::new(unit, unit_data.model_name, sysnum, layer)?;
                         Ok(Self::Fw1884(runtime))
                     }
                     (TASCAM_OUI, FW1082_SW_VERSION) => {
-                        let runtime = Fw1082Runtime::new(unit, unit_data.model_name, sysnum)?;
+                        let runtime =
+                            Fw1082Runtime::new(unit, unit_data.model_name, sysnum, layer)?;
                         Ok(Self::Fw1082(runtime))
                     }
                     (TASCAM_OUI, FW1804_SW_VERSION) => {
+                        // The unit has no channel strip, thus the layer is not used.
                         let runtime = Fw1804Runtime::new(unit, unit_data.model_name, sysnum)?;
                         Ok(Self::Fw1804(runtime))
                     }
@@ -86,15 +239,16 @@ impl RuntimeOperation<(String, u32)> for TascamRuntime {
 
                 let data = node.get_config_rom()?;
                 let config_rom = ConfigRom::try_from(data)
-                    .map_err(|e| {
-                        let label = format!("Malformed configuration ROM detected: {}", e.to_string());
-                        Error::new(FileError::Nxio, &label)
-                    })?;
-                let unit_data = config_rom.get_unit_data()?;
+                    .map_err(|e| config_rom_error(&TascamConfigRomError::from(e)))?;
+                let unit_data = identify_unit(&config_rom)?;
                 match (unit_data.specifier_id, unit_data.version) {
                     (TASCAM_OUI, FE8_SW_VERSION) => {
                         let name = unit_data.model_name.to_string();
-                        let runtime = Fe8Runtime::new(node, name)?;
+                        // The cascade is used instead of the layer for the strips.
+                        let runtime = match cascade {
+                            Some(cascade) => Fe8Runtime::new(node, name, None, Some(cascade))?,
+                            None => Fe8Runtime::new(node, name, layer, None)?,
+                        };
                         Ok(Self::Fe8(runtime))
                     }
                     _ => Err(Error::new(FileError::Noent, "Not supported")),
@@ -126,16 +280,483 @@ impl RuntimeOperation<(String, u32)> for TascamRuntime {
     }
 }
 
+fn config_rom_error(e: &TascamConfigRomError) -> Error {
+    let code = match e {
+        TascamConfigRomError::UnreadableRom(_) => ConfigRomError::UnreadableRom,
+        TascamConfigRomError::VendorMismatch { .. } => ConfigRomError::VendorMismatch,
+        TascamConfigRomError::UnknownModel { .. } => ConfigRomError::UnknownModel,
+        TascamConfigRomError::MalformedEntry { .. } => ConfigRomError::MalformedEntry,
+    };
+    Error::new(code, &e.to_string())
+}
+
+fn identify_unit<'a>(config_rom: &'a ConfigRom<'a>) -> Result<UnitData<'a>, Error> {
+    config_rom.identify_model().map_err(|e| match e {
+        TascamConfigRomError::UnknownModel { .. } => {
+            // The unit directory is already parsed, thus the names are available.
+            let msg = config_rom
+                .get_unit_data()
+                .map(|unit_data| {
+                    format!(
+                        "device is a {} {}, not supported",
+                        unit_data.vendor_name, unit_data.model_name
+                    )
+                })
+                .unwrap_or_else(|_| e.to_string());
+            Error::new(ConfigRomError::UnknownModel, &msg)
+        }
+        _ => config_rom_error(&e),
+    })
+}
+
+/// The layer of MIDI channel for channel strip items when several units are managed in the same
+/// instance. The channel is shifted according to the index of unit and the bank selected in any
+/// unit, thus the application can distinguish the strips of each unit.
+#[derive(Debug, Clone)]
+pub struct SequencerLayer {
+    bank: Arc<AtomicU16>,
+    index: usize,
+    count: usize,
+}
+
+impl SequencerLayer {
+    const CHANNEL_COUNT: usize = 16;
+
+    fn channel(&self) -> u8 {
+        let bank = self.bank.load(Ordering::SeqCst) as usize;
+        ((bank * self.count + self.index) % Self::CHANNEL_COUNT) as u8
+    }
+
+    fn update_bank(&self, bank: u16) {
+        self.bank.store(bank, Ordering::SeqCst);
+    }
+}
+
+type CascadeRoute = Arc<dyn Fn(EventDataCtl) -> bool + Send + Sync>;
+
+/// The router of events from the application to the units in cascade. Each unit receives the
+/// events for all of strips in cascade, thus the event for the strip of the other unit is routed
+/// to the unit.
+#[derive(Clone, Default)]
+pub struct CascadeRouter(Arc<Mutex<Vec<Option<CascadeRoute>>>>);
+
+impl CascadeRouter {
+    pub fn new(count: usize) -> Self {
+        Self(Arc::new(Mutex::new(vec![None; count])))
+    }
+
+    fn register(&self, index: usize, route: Option<CascadeRoute>) {
+        if let Ok(routes) = &mut self.0.lock() {
+            if let Some(r) = routes.iter_mut().nth(index) {
+                *r = route;
+            }
+        }
+    }
+
+    fn route(&self, index: usize, data: EventDataCtl) -> Result<(), Error> {
+        // The route is called without the lock, since it is shared by the threads of units.
+        let route = self
+            .0
+            .lock()
+            .map_err(|_| Error::new(FileError::Failed, "Unrecoverable error at mutex lock"))
+            .map(|routes| routes.iter().nth(index).cloned().flatten())?;
+
+        match route {
+            Some(route) if route(data) => Ok(()),
+            Some(_) => {
+                let msg = format!("The unit {} in cascade is busy", index);
+                Err(Error::new(FileError::Again, &msg))
+            }
+            None => {
+                let msg = format!("The unit {} in cascade is not available", index);
+                Err(Error::new(FileError::Nodev, &msg))
+            }
+        }
+    }
+}
+
+/// The cascade of several units of the same model side by side, like FE-8. The channel strips of
+/// each unit are mapped to the sequential bank of strips according to the order of unit, thus
+/// the strip 3 of the second FE-8 is expressed as strip 11 in the application side.
+#[derive(Clone)]
+pub struct SequencerCascade {
+    index: usize,
+    count: usize,
+    // The number of strips in the unit, detected at initialization of message map.
+    strip_count: usize,
+    router: CascadeRouter,
+}
+
+impl SequencerCascade {
+    pub fn new(index: usize, count: usize, router: CascadeRouter) -> Self {
+        Self {
+            index,
+            count,
+            strip_count: 0,
+            router,
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.index * self.strip_count
+    }
+
+    // The index of unit with the strip in application side.
+    fn unit_index(&self, ch: usize) -> Option<usize> {
+        if self.strip_count > 0 && ch < self.count * self.strip_count {
+            Some(ch / self.strip_count)
+        } else {
+            None
+        }
+    }
+
+    /// Register the route of events for the unit.
+    fn register_route(&self, route: CascadeRoute) {
+        self.router.register(self.index, Some(route));
+    }
+
+    /// Unregister the route of events for the unit.
+    fn unregister_route(&self) {
+        self.router.register(self.index, None);
+    }
+}
+
+fn strip_index(item: &MachineItem) -> Option<usize> {
+    match item {
+        MachineItem::Rec(ch)
+        | MachineItem::Select(ch)
+        | MachineItem::Solo(ch)
+        | MachineItem::Mute(ch)
+        | MachineItem::Input(ch)
+        | MachineItem::Rotary(ch)
+        | MachineItem::AuxSend(_, ch)
+        | MachineItem::FaderTouch(ch) => Some(*ch),
+        _ => None,
+    }
+}
+
+fn with_strip_index(item: &MachineItem, ch: usize) -> MachineItem {
+    match item {
+        MachineItem::Rec(_) => MachineItem::Rec(ch),
+        MachineItem::Select(_) => MachineItem::Select(ch),
+        MachineItem::Solo(_) => MachineItem::Solo(ch),
+        MachineItem::Mute(_) => MachineItem::Mute(ch),
+        MachineItem::Input(_) => MachineItem::Input(ch),
+        MachineItem::Rotary(_) => MachineItem::Rotary(ch),
+        MachineItem::AuxSend(bus, _) => MachineItem::AuxSend(*bus, ch),
+        MachineItem::FaderTouch(_) => MachineItem::FaderTouch(ch),
+        _ => *item,
+    }
+}
+
+fn is_strip_item(item: &MachineItem) -> bool {
+    strip_index(item).is_some()
+}
+
+// The message map for the cascade includes the strips of all units in the order of unit, while
+// the other items just once.
+fn build_cascade_map(map: &[MachineItem], cascade: &SequencerCascade) -> Vec<MachineItem> {
+    let mut cascade_map = Vec::new();
+    (0..cascade.count).for_each(|index| {
+        map.iter().for_each(|item| match strip_index(item) {
+            Some(ch) => cascade_map.push(with_strip_index(item, index * cascade.strip_count + ch)),
+            None => {
+                if index == 0 {
+                    cascade_map.push(*item);
+                }
+            }
+        });
+    });
+    cascade_map
+}
+
+/// The limiter of rate for value changes of u16 items, to coalesce the dense events such as the
+/// rotation of data wheel. The value within the window since the last emission is kept as pending,
+/// then the latest one is emitted when the window is expired. The boolean items always pass.
+#[derive(Debug)]
+pub struct ValueRateLimiter<C: Clock = SystemClock> {
+    clock: C,
+    window: Option<Duration>,
+    // The item, the time of last emission, and the pending value.
+    entries: Vec<(MachineItem, Instant, Option<u16>)>,
+}
+
+impl Default for ValueRateLimiter<SystemClock> {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl<C: Clock> ValueRateLimiter<C> {
+    pub const DEFAULT_WINDOW: Duration = Duration::from_millis(10);
+
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            window: None,
+            entries: Default::default(),
+        }
+    }
+
+    /// Configure the window. None disables the limiter.
+    pub fn set_window(&mut self, window: Option<Duration>) {
+        self.window = window;
+    }
+
+    pub fn window(&self) -> Option<Duration> {
+        self.window
+    }
+
+    /// Return true when the event should be emitted immediately. Otherwise the value is kept
+    /// as pending and superseded by the later one.
+    pub fn filter(&mut self, event: &(MachineItem, ItemValue)) -> bool {
+        let (window, val) = match (self.window, event.1) {
+            (Some(window), ItemValue::U16(val)) => (window, val),
+            _ => return true,
+        };
+
+        let now = self.clock.now();
+        match self.entries.iter_mut().find(|(item, _, _)| event.0.eq(item)) {
+            Some((_, last, pending)) => {
+                if now.duration_since(*last) >= window {
+                    *last = now;
+                    *pending = None;
+                    true
+                } else {
+                    *pending = Some(val);
+                    false
+                }
+            }
+            None => {
+                self.entries.push((event.0, now, None));
+                true
+            }
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.entries.iter().any(|(_, _, pending)| pending.is_some())
+    }
+
+    /// Take the pending values of which window is expired. All of pending values are taken when
+    /// the limiter is disabled.
+    pub fn flush(&mut self) -> Vec<(MachineItem, ItemValue)> {
+        let now = self.clock.now();
+        let window = self.window;
+        self.entries
+            .iter_mut()
+            .filter_map(|(item, last, pending)| match window {
+                Some(window) if now.duration_since(*last) < window => None,
+                _ => pending.take().map(|val| {
+                    *last = now;
+                    (*item, ItemValue::U16(val))
+                }),
+            })
+            .collect()
+    }
+}
+
 #[derive(Default)]
 pub struct SequencerState<U> {
     map: Vec<MachineItem>,
+    scales: Vec<(MachineItem, ItemValueScale)>,
     machine_state: MachineState,
     surface_state: U,
+    mmc_emission: bool,
+    layer: Option<SequencerLayer>,
+    cascade: Option<SequencerCascade>,
+    asserted: Vec<MachineItem>,
+    limiter: ValueRateLimiter,
+    stats: SurfaceStats,
+}
+
+impl<U> SequencerState<U> {
+    /// Configure the scale of u16 value between the machine item and application. The value is
+    /// not scaled for the item without the configuration.
+    pub fn set_value_scale(&mut self, item: MachineItem, scale: ItemValueScale) {
+        match self.scales.iter_mut().find(|(i, _)| item.eq(i)) {
+            Some((_, s)) => *s = scale,
+            None => self.scales.push((item, scale)),
+        }
+    }
+
+    /// Replace the scales of u16 value with the configured ones for controls in application side.
+    /// The control out of message map or for the strip of the other unit in cascade is ignored.
+    pub fn apply_value_scales(&mut self, scales: &[ValueScale]) {
+        self.scales.clear();
+        scales.iter().for_each(|scale| {
+            let item = self
+                .map
+                .iter()
+                .nth(scale.control as usize)
+                .and_then(|item| self.unit_item(item));
+            if let Some(item) = item {
+                let curve = match scale.curve {
+                    ValueCurve::Linear => ItemValueCurve::Linear,
+                    ValueCurve::Log => ItemValueCurve::Log,
+                    ValueCurve::Taper => ItemValueCurve::Taper,
+                };
+                let scale = ItemValueScale {
+                    input_max: scale.input_max,
+                    output_min: scale.output_min,
+                    output_max: scale.output_max,
+                    curve,
+                };
+                self.set_value_scale(item, scale);
+            }
+        });
+    }
+
+    /// Enable or disable emission of MIDI Machine Control message for transport items, in
+    /// addition to control change.
+    pub fn set_mmc_emission(&mut self, enable: bool) {
+        self.mmc_emission = enable;
+    }
+
+    /// Configure the layer of MIDI channel for channel strip items.
+    pub fn set_layer(&mut self, layer: SequencerLayer) {
+        self.layer = Some(layer);
+    }
+
+    /// Configure the cascade of units for channel strip items.
+    pub fn set_cascade(&mut self, cascade: SequencerCascade) {
+        self.cascade = Some(cascade);
+    }
+
+    /// Configure the window to coalesce value changes of u16 items operated in the surface. None
+    /// disables the limiter, thus all of changes are emitted to the application.
+    pub fn set_value_rate_limit(&mut self, window: Option<Duration>) {
+        self.limiter.set_window(window);
+    }
+
+    pub fn has_pending_values(&self) -> bool {
+        self.limiter.has_pending()
+    }
+
+    /// The statistics of surface image decoding.
+    pub fn surface_stats(&self) -> SurfaceStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    // Translate the item of unit to the one in application side.
+    fn appl_item(&self, item: &MachineItem) -> MachineItem {
+        match (&self.cascade, strip_index(item)) {
+            (Some(cascade), Some(ch)) => with_strip_index(item, cascade.offset() + ch),
+            _ => *item,
+        }
+    }
+
+    // Translate the item in application side to the one of unit. None when the strip belongs to
+    // the other unit in the cascade.
+    fn unit_item(&self, item: &MachineItem) -> Option<MachineItem> {
+        match (&self.cascade, strip_index(item)) {
+            (Some(cascade), Some(ch)) => {
+                let offset = cascade.offset();
+                if ch >= offset && ch < offset + cascade.strip_count {
+                    Some(with_strip_index(item, ch - offset))
+                } else {
+                    None
+                }
+            }
+            _ => Some(*item),
+        }
+    }
+
+    // Route the event from the application to the other unit in cascade when the strip belongs to
+    // the unit. None when the event is for the unit itself.
+    fn route_appl_event(&self, data: &EventDataCtl) -> Option<Result<(), Error>> {
+        let cascade = self.cascade.as_ref()?;
+        let index = self
+            .map
+            .iter()
+            .nth(data.get_param() as usize)
+            .and_then(|item| strip_index(item))
+            .and_then(|ch| cascade.unit_index(ch))
+            .filter(|&index| index != cascade.index)?;
+        Some(cascade.router.route(index, data.clone()))
+    }
+
+    fn appl_channel(&self, item: &MachineItem) -> u8 {
+        match &self.layer {
+            Some(layer) if is_strip_item(item) => layer.channel(),
+            _ => 0,
+        }
+    }
+
+    // Track the boolean items asserted in the application side.
+    fn track_appl_event(&mut self, event: &(MachineItem, ItemValue)) {
+        match event.1 {
+            ItemValue::Bool(true) => {
+                if !self.asserted.contains(&event.0) {
+                    self.asserted.push(event.0);
+                }
+            }
+            ItemValue::Bool(false) => self.asserted.retain(|item| !event.0.eq(item)),
+            ItemValue::U16(_) => (),
+        }
+    }
+
+    fn release_events(&self) -> Vec<(MachineItem, ItemValue)> {
+        self.asserted
+            .iter()
+            .map(|&item| (item, ItemValue::Bool(false)))
+            .collect()
+    }
+
+    fn find_value_scale(&self, item: &MachineItem) -> Option<&ItemValueScale> {
+        self.scales
+            .iter()
+            .find(|(i, _)| item.eq(i))
+            .map(|(_, scale)| scale)
+    }
 }
 
 const BOOL_TRUE: i32 = 0x7f;
 
+// The equalizer parameters are emitted as Non-Registered Parameter Number in the dedicated channel,
+// since the numbers of control change for data entry are used by the other items.
+const EQ_NRPN_CHANNEL: u8 = 15;
+const NRPN_MSB: u32 = 99;
+const NRPN_LSB: u32 = 98;
+const DATA_ENTRY_MSB: u32 = 6;
+const DATA_ENTRY_LSB: u32 = 38;
+const NRPN_DATA_MAX: i32 = 0x3fff;
+
+const EQ_PARAM_ITEMS: [MachineItem; 3] = [MachineItem::Gain, MachineItem::Freq, MachineItem::Q];
+
+/// Convert the event of equalizer parameter to the series of control change for NRPN, in which
+/// the MSB of parameter number is for the index of band and the LSB is for the index of parameter.
+/// None when the event is not for equalizer parameter. No control change when no band is enabled.
+fn convert_eq_param_event<T: MachineStateOperation, U>(
+    state: &SequencerState<U>,
+    event: &(MachineItem, ItemValue),
+) -> Option<Vec<(u32, i32)>> {
+    let param = EQ_PARAM_ITEMS.iter().position(|item| event.0.eq(item))?;
+    let value = match event.1 {
+        ItemValue::U16(val) => match state.find_value_scale(&event.0) {
+            Some(scale) => scale.to_output(val).max(0).min(NRPN_DATA_MAX),
+            None => (val >> 2) as i32,
+        },
+        ItemValue::Bool(_) => return None,
+    };
+
+    let events = match T::selected_eq_band(&state.machine_state) {
+        Some(band) => vec![
+            (NRPN_MSB, band as i32),
+            (NRPN_LSB, param as i32),
+            (DATA_ENTRY_MSB, value >> 7),
+            (DATA_ENTRY_LSB, value & 0x7f),
+        ],
+        None => Vec::new(),
+    };
+
+    Some(events)
+}
+
 pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperation<U>, U> {
+    /// Whether to emit the equalizer parameters operated by rotaries as NRPN for the enabled band.
+    const EQ_NRPN: bool = false;
+
     fn state(&self) -> &SequencerState<U>;
     fn state_mut(&mut self) -> &mut SequencerState<U>;
 
@@ -146,14 +767,27 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
     ) -> Result<(), Error>;
     fn finalize_surface(&mut self, node: &mut S) -> Result<(), Error>;
 
+    fn repaint_surface(&mut self, node: &mut S) -> Result<(), Error>;
+
     fn feedback_to_surface(
         &mut self,
         node: &mut S,
         event: &(MachineItem, ItemValue),
     ) -> Result<(), Error>;
 
+    /// Handle the event from surface in model-specific way instead of the state machine. When it
+    /// returns true, the event is consumed and not dispatched to the state machine.
+    fn consume_surface_event(
+        &mut self,
+        _: &mut S,
+        _: &(MachineItem, ItemValue),
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
     fn initialize_sequencer(&mut self, node: &mut S) -> Result<(), Error> {
         self.initialize_message_map();
+        self.state_mut().apply_value_scales(&current_config().value_scales);
         T::initialize_surface_state(&mut self.state_mut().surface_state);
         T::initialize_machine(&mut self.state_mut().machine_state);
         let machine_values = T::get_machine_current_values(&self.state().machine_state);
@@ -164,6 +798,18 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         self.finalize_surface(node)
     }
 
+    /// Recover the surface when the node is reconnected, e.g. after power-cycle. The cache of
+    /// LEDs is invalidated since the LEDs are turned off, then the surface is initialized again
+    /// and all of LEDs are repainted according to the current state of machine.
+    fn recover_surface(&mut self, node: &mut S) -> Result<(), Error> {
+        T::invalidate_surface(&mut self.state_mut().surface_state);
+        self.finalize_surface(node)?;
+        T::initialize_surface_state(&mut self.state_mut().surface_state);
+        // All of LEDs are repainted just once below, thus no machine value is given.
+        self.initialize_surface(node, &[])?;
+        self.repaint_surface(node)
+    }
+
     fn initialize_message_map(&mut self) {
         let map = &mut self.state_mut().map;
         T::BOOL_ITEMS
@@ -185,8 +831,23 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         if T::HAS_BANK {
             map.push(MachineItem::Bank);
         }
+
+        map.append(&mut T::aux_send_items());
+
+        let state = self.state_mut();
+        if let Some(cascade) = &mut state.cascade {
+            cascade.strip_count = state
+                .map
+                .iter()
+                .filter_map(|item| strip_index(item))
+                .max()
+                .map(|ch| ch + 1)
+                .unwrap_or_default();
+            state.map = build_cascade_map(&state.map, cascade);
+        }
     }
 
+    /// Dispatch the event of surface, then return whether any select item is changed.
     fn dispatch_surface_event(
         &mut self,
         unit: &mut S,
@@ -195,27 +856,90 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         index: u32,
         before: u32,
         after: u32,
-    ) -> Result<(), Error> {
-        let inputs =
-            T::decode_surface_image(&self.state().surface_state, image, index, before, after);
+    ) -> Result<bool, Error> {
+        let mut selected = false;
+        let inputs = self.decode_surface_event(image, index, before, after);
         inputs.iter().try_for_each(|input| {
+            if self.consume_surface_event(unit, input)? {
+                return Ok(());
+            }
             let outputs = self.dispatch_machine_event(input);
             outputs.iter().try_for_each(|output| {
-                self.feedback_to_appl(seq_cntr, output)?;
+                selected |= matches!(output.0, MachineItem::Select(_));
+                // The surface is always updated, while the application can be rate-limited.
+                if self.is_forwarded_to_appl(output) && self.state_mut().limiter.filter(output) {
+                    self.feedback_to_appl(seq_cntr, output)?;
+                }
+                self.state().stats.record_feedback();
                 self.feedback_to_surface(unit, output)
             })
         })
+        .map(|_| selected)
     }
 
+    fn decode_surface_event(
+        &mut self,
+        image: &[u32],
+        index: u32,
+        before: u32,
+        after: u32,
+    ) -> Vec<(MachineItem, ItemValue)> {
+        let state = self.state_mut();
+        let surface_state = &mut state.surface_state;
+        state.stats.measure_decode(|| {
+            T::decode_surface_image(surface_state, image, index, before, after)
+        })
+    }
+
+    /// Emit the latest values coalesced by the limiter, of which window is expired.
+    fn flush_limited_values(&mut self, seq_cntr: &mut SeqCntr) -> Result<(), Error> {
+        let events = self.state_mut().limiter.flush();
+        events
+            .iter()
+            .try_for_each(|event| self.feedback_to_appl(seq_cntr, event))
+    }
+
+    /// Dispatch the event of application, then return whether any select item is changed.
     fn dispatch_appl_event(
         &mut self,
         unit: &mut S,
         seq_cntr: &mut SeqCntr,
         data: &EventDataCtl,
-    ) -> Result<(), Error> {
+    ) -> Result<bool, Error> {
+        if let Some(res) = self.state().route_appl_event(data) {
+            return res.map(|_| false);
+        }
+
         let input = self.parse_appl_event(data)?;
         let outputs = self.dispatch_machine_event(&input);
         outputs.iter().try_for_each(|output| {
+            if !output.eq(&input) && self.is_forwarded_to_appl(output) {
+                self.feedback_to_appl(seq_cntr, output)?;
+            }
+            self.feedback_to_surface(unit, output)
+        })?;
+        Ok(outputs.iter().any(|output| matches!(output.0, MachineItem::Select(_))))
+    }
+
+    fn dispatch_appl_mmc(
+        &mut self,
+        unit: &mut S,
+        seq_cntr: &mut SeqCntr,
+        msg: &[u8],
+    ) -> Result<(), Error> {
+        if !T::HAS_TRANSPORT {
+            Err(Error::new(FileError::Nxio, "Transport items are not supported."))?;
+        }
+
+        let item = parse_mmc_message(msg, MMC_DEVICE_ID_ALL_CALL).ok_or_else(|| {
+            let msg = format!("Unsupported MIDI Machine Control message: {:02x?}", msg);
+            Error::new(FileError::Inval, &msg)
+        })?;
+
+        let input = (item, ItemValue::Bool(true));
+        let outputs = self.dispatch_machine_event(&input);
+        outputs.iter().try_for_each(|output| {
+            // The message is not echoed back to the application.
             if !output.eq(&input) {
                 self.feedback_to_appl(seq_cntr, output)?;
             }
@@ -223,14 +947,59 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         })
     }
 
-    fn parse_appl_event(&self, data: &EventDataCtl) -> Result<(MachineItem, ItemValue), Error> {
-        if data.get_channel() != 0 {
-            let msg = format!("Channel {} is not supported yet.", data.get_channel());
-            Err(Error::new(FileError::Inval, &msg))?;
+    /// Enable or disable follow mode of select items. The select items in the mode express the
+    /// selected channel, instead of being forwarded to the application.
+    fn dispatch_select_follow_mode(&mut self, unit: &mut S, enable: bool) -> Result<(), Error> {
+        let outputs = T::set_select_follow_mode(&mut self.state_mut().machine_state, enable);
+        outputs
+            .iter()
+            .try_for_each(|output| self.feedback_to_surface(unit, output))
+    }
+
+    /// Enable or disable clear solo mode. The clear solo item in the mode disables all of solo
+    /// items and expresses whether any of them is enabled.
+    fn dispatch_clear_solo_mode(&mut self, unit: &mut S, enable: bool) -> Result<(), Error> {
+        let outputs = T::set_clear_solo_mode(&mut self.state_mut().machine_state, enable);
+        outputs
+            .iter()
+            .try_for_each(|output| self.feedback_to_surface(unit, output))
+    }
+
+    /// Select the channel, then update the select items in follow mode.
+    fn dispatch_selected_channel(&mut self, unit: &mut S, ch: usize) -> Result<(), Error> {
+        let outputs = T::select_channel(&mut self.state_mut().machine_state, ch);
+        outputs
+            .iter()
+            .try_for_each(|output| self.feedback_to_surface(unit, output))
+    }
+
+    fn selected_channel(&self) -> Option<usize> {
+        let machine_state = &self.state().machine_state;
+        if T::is_select_follow_mode(machine_state) {
+            Some(T::selected_channel(machine_state))
+        } else {
+            None
         }
+    }
+
+    fn is_forwarded_to_appl(&self, event: &(MachineItem, ItemValue)) -> bool {
+        !(matches!(event.0, MachineItem::Select(_))
+            && T::is_select_follow_mode(&self.state().machine_state))
+    }
 
+    /// Release all of boolean items asserted in the application side, then emit All Notes Off
+    /// message. The state of machine is not changed.
+    fn dispatch_appl_panic(&mut self, seq_cntr: &mut SeqCntr) -> Result<(), Error> {
+        let events = self.state().release_events();
+        events
+            .iter()
+            .try_for_each(|event| self.feedback_to_appl(seq_cntr, event))?;
+        seq_cntr.schedule_all_notes_off()
+    }
+
+    fn parse_appl_event(&self, data: &EventDataCtl) -> Result<(MachineItem, ItemValue), Error> {
         let index = data.get_param();
-        let &machine_item = self
+        let machine_item = self
             .state()
             .map
             .iter()
@@ -238,8 +1007,24 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
             .ok_or_else(|| {
                 let msg = format!("Unsupported control number: {}", index);
                 Error::new(FileError::Inval, &msg)
+            })
+            .and_then(|item| {
+                self.state().unit_item(item).ok_or_else(|| {
+                    let msg = format!("{} belongs to the other unit in cascade.", item);
+                    Error::new(FileError::Inval, &msg)
+                })
             })?;
 
+        let channel = self.state().appl_channel(&machine_item);
+        if data.get_channel() != channel {
+            let msg = format!(
+                "Channel {} is not supported for {}.",
+                data.get_channel(),
+                machine_item
+            );
+            Err(Error::new(FileError::Inval, &msg))?;
+        }
+
         let value = data.get_value();
         let item_value = if T::BOOL_ITEMS.iter().find(|i| machine_item.eq(i)).is_some() {
             ItemValue::Bool(value == BOOL_TRUE)
@@ -249,8 +1034,14 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
             .is_some()
         {
             ItemValue::Bool(value == BOOL_TRUE)
-        } else if T::U16_ITEMS.iter().find(|i| machine_item.eq(i)).is_some() {
-            ItemValue::U16(value as u16)
+        } else if T::U16_ITEMS.iter().find(|i| machine_item.eq(i)).is_some()
+            || matches!(machine_item, MachineItem::AuxSend(_, _))
+        {
+            let val = match self.state().find_value_scale(&machine_item) {
+                Some(scale) => scale.to_input(value),
+                None => value as u16,
+            };
+            ItemValue::U16(val)
         } else if machine_item.eq(&MachineItem::Bank) {
             ItemValue::U16(value as u16)
         } else {
@@ -265,7 +1056,18 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         &mut self,
         input: &(MachineItem, ItemValue),
     ) -> Vec<(MachineItem, ItemValue)> {
-        T::change_machine_value(&mut self.state_mut().machine_state, input)
+        let outputs = T::change_machine_value(&mut self.state_mut().machine_state, input);
+
+        // The bank is shared by all of units in the same instance.
+        if let Some(layer) = &self.state().layer {
+            outputs.iter().for_each(|output| {
+                if let (MachineItem::Bank, ItemValue::U16(bank)) = output {
+                    layer.update_bank(*bank);
+                }
+            });
+        }
+
+        outputs
     }
 
     fn feedback_to_appl(
@@ -273,11 +1075,20 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
         cntr: &mut SeqCntr,
         event: &(MachineItem, ItemValue),
     ) -> Result<(), Error> {
+        if Self::EQ_NRPN {
+            if let Some(events) = convert_eq_param_event::<T, U>(self.state(), event) {
+                return events
+                    .iter()
+                    .try_for_each(|&(param, val)| cntr.schedule_event(EQ_NRPN_CHANNEL, param, val));
+            }
+        }
+
+        let appl_item = self.state().appl_item(&event.0);
         let index = self
             .state()
             .map
             .iter()
-            .position(|item| event.0.eq(item))
+            .position(|item| appl_item.eq(item))
             .ok_or_else(|| {
                 let msg = format!("Unsupported machine item: {}", event.0);
                 Error::new(FileError::Inval, &msg)
@@ -291,9 +1102,374 @@ pub trait SequencerCtlOperation<S, T: MachineStateOperation + SurfaceImageOperat
                     0
                 }
             }
-            ItemValue::U16(val) => val as i32,
+            ItemValue::U16(val) => match self.state().find_value_scale(&event.0) {
+                Some(scale) => scale.to_output(val),
+                None => val as i32,
+            },
+        };
+
+        let channel = self.state().appl_channel(&event.0);
+        cntr.schedule_event(channel, index as u32, value)?;
+        self.state_mut().track_appl_event(event);
+
+        if T::HAS_TRANSPORT && self.state().mmc_emission && event.1 == ItemValue::Bool(true) {
+            if let Some(msg) = build_mmc_message(&event.0, MMC_DEVICE_ID_ALL_CALL) {
+                cntr.schedule_sysex(&msg)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tascam_protocols::{asynch::fe8::*, isoch::fw1082::*};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct TestClock {
+        base: Instant,
+        elapsed: Rc<Cell<Duration>>,
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Instant {
+            self.base + self.elapsed.get()
+        }
+    }
+
+    fn advance(elapsed: &Rc<Cell<Duration>>, ms: u64) {
+        elapsed.set(elapsed.get() + Duration::from_millis(ms));
+    }
+
+    #[test]
+    fn test_value_rate_limiter() {
+        let elapsed = Rc::new(Cell::new(Duration::default()));
+        let mut limiter = ValueRateLimiter::new(TestClock {
+            base: Instant::now(),
+            elapsed: elapsed.clone(),
+        });
+
+        // Disabled.
+        (0..4).for_each(|i| assert!(limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(i)))));
+        assert!(!limiter.has_pending());
+
+        limiter.set_window(Some(ValueRateLimiter::<TestClock>::DEFAULT_WINDOW));
+
+        // The first change passes, then the later ones within the window are coalesced.
+        assert!(limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(10))));
+        advance(&elapsed, 2);
+        assert!(!limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(11))));
+        advance(&elapsed, 2);
+        assert!(!limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(12))));
+        assert!(limiter.has_pending());
+
+        // The boolean item and the other item are not affected.
+        assert!(limiter.filter(&(MachineItem::Play, ItemValue::Bool(true))));
+        assert!(limiter.filter(&(MachineItem::Play, ItemValue::Bool(false))));
+        assert!(limiter.filter(&(MachineItem::Rotary(1), ItemValue::U16(20))));
+
+        advance(&elapsed, 5);
+        assert!(limiter.flush().is_empty());
+
+        // The latest value is emitted when the window is expired.
+        advance(&elapsed, 1);
+        assert_eq!(limiter.flush(), vec![(MachineItem::Rotary(0), ItemValue::U16(12))]);
+        assert!(!limiter.has_pending());
+        assert!(limiter.flush().is_empty());
+
+        // The window starts again at the emission.
+        advance(&elapsed, 9);
+        assert!(!limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(13))));
+        advance(&elapsed, 1);
+        assert!(limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(14))));
+        assert!(!limiter.has_pending());
+
+        // The pending value is flushed at once when the limiter is disabled.
+        assert!(!limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(15))));
+        limiter.set_window(None);
+        assert_eq!(limiter.flush(), vec![(MachineItem::Rotary(0), ItemValue::U16(15))]);
+        assert!(limiter.filter(&(MachineItem::Rotary(0), ItemValue::U16(16))));
+    }
+
+    #[test]
+    fn test_release_events() {
+        let mut state = SequencerState::<()>::default();
+
+        state.track_appl_event(&(MachineItem::Play, ItemValue::Bool(true)));
+        state.track_appl_event(&(MachineItem::Mute(0), ItemValue::Bool(true)));
+        state.track_appl_event(&(MachineItem::Mute(0), ItemValue::Bool(true)));
+        state.track_appl_event(&(MachineItem::Solo(1), ItemValue::Bool(true)));
+        state.track_appl_event(&(MachineItem::Rotary(2), ItemValue::U16(0x7f)));
+        state.track_appl_event(&(MachineItem::Play, ItemValue::Bool(false)));
+
+        assert_eq!(
+            state.release_events(),
+            vec![
+                (MachineItem::Mute(0), ItemValue::Bool(false)),
+                (MachineItem::Solo(1), ItemValue::Bool(false)),
+            ]
+        );
+
+        state
+            .release_events()
+            .iter()
+            .for_each(|event| state.track_appl_event(event));
+        assert!(state.release_events().is_empty());
+    }
+
+    #[test]
+    fn test_eq_param_nrpn() {
+        let mut state = SequencerState::<()>::default();
+        Fw1082Protocol::initialize_machine(&mut state.machine_state);
+
+        // The rotary output is suppressed without band.
+        let event = (MachineItem::Gain, ItemValue::U16(0x100));
+        assert_eq!(convert_eq_param_event::<Fw1082Protocol, ()>(&state, &event), Some(vec![]));
+
+        let outputs = Fw1082Protocol::change_machine_value(
+            &mut state.machine_state,
+            &(MachineItem::Low, ItemValue::Bool(true)),
+        );
+        assert_eq!(outputs, vec![(MachineItem::Low, ItemValue::Bool(true))]);
+        assert_eq!(
+            convert_eq_param_event::<Fw1082Protocol, ()>(&state, &event),
+            Some(vec![(99, 3), (98, 0), (6, 0), (38, 0x40)])
+        );
+
+        // Enabling High while Low is active.
+        let outputs = Fw1082Protocol::change_machine_value(
+            &mut state.machine_state,
+            &(MachineItem::High, ItemValue::Bool(true)),
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                (MachineItem::Low, ItemValue::Bool(false)),
+                (MachineItem::High, ItemValue::Bool(true)),
+            ]
+        );
+        // The band enable is not converted.
+        assert_eq!(convert_eq_param_event::<Fw1082Protocol, ()>(&state, &outputs[1]), None);
+
+        let event = (MachineItem::Gain, ItemValue::U16(0xffff));
+        assert_eq!(
+            convert_eq_param_event::<Fw1082Protocol, ()>(&state, &event),
+            Some(vec![(99, 0), (98, 0), (6, 0x7f), (38, 0x7f)])
+        );
+        let event = (MachineItem::Q, ItemValue::U16(0x200));
+        assert_eq!(
+            convert_eq_param_event::<Fw1082Protocol, ()>(&state, &event),
+            Some(vec![(99, 0), (98, 2), (6, 1), (38, 0)])
+        );
+
+        // The other item is not converted.
+        let event = (MachineItem::Pan, ItemValue::U16(0x100));
+        assert_eq!(convert_eq_param_event::<Fw1082Protocol, ()>(&state, &event), None);
+    }
+
+    #[derive(Default)]
+    struct TestFe8(SequencerState<Fe8SurfaceState>);
+
+    impl SequencerCtlOperation<(), Fe8Protocol, Fe8SurfaceState> for TestFe8 {
+        fn state(&self) -> &SequencerState<Fe8SurfaceState> {
+            &self.0
+        }
+
+        fn state_mut(&mut self) -> &mut SequencerState<Fe8SurfaceState> {
+            &mut self.0
+        }
+
+        fn initialize_surface(
+            &mut self,
+            _: &mut (),
+            _: &[(MachineItem, ItemValue)],
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn finalize_surface(&mut self, _: &mut ()) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn repaint_surface(&mut self, _: &mut ()) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn feedback_to_surface(
+            &mut self,
+            _: &mut (),
+            _: &(MachineItem, ItemValue),
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_surface_stats() {
+        let mut unit = TestFe8::default();
+        unit.initialize_sequencer(&mut ()).unwrap();
+
+        // The canned sequence of images in which the rotary of first strip is turned.
+        const ROTARY_INDEX: usize = 20;
+        const IMAGE_COUNT: u32 = 10000;
+        let mut image = vec![0u32; 64];
+        let events = (0..IMAGE_COUNT).fold(0, |count, i| {
+            image[ROTARY_INDEX] = i + 1;
+            let inputs = unit.decode_surface_event(&image, ROTARY_INDEX as u32, i, i + 1);
+            count + inputs.len()
+        });
+        assert_eq!(events, IMAGE_COUNT as usize);
+
+        let stats = unit.state().surface_stats();
+        if cfg!(feature = "surface-stats") {
+            assert_eq!(stats.images, IMAGE_COUNT as u64);
+            assert_eq!(stats.events, IMAGE_COUNT as u64);
+            assert_eq!(stats.decode_times.iter().sum::<u64>(), IMAGE_COUNT as u64);
+        } else {
+            assert_eq!(stats, SurfaceStatsSnapshot::default());
+        }
+        assert_eq!(stats.feedbacks, 0);
+    }
+
+    #[test]
+    fn test_value_scales() {
+        let router = CascadeRouter::new(2);
+        let mut unit = TestFe8::default();
+        unit.state_mut().set_cascade(SequencerCascade::new(1, 2, router));
+        unit.initialize_message_map();
+
+        let map = unit.state().map.clone();
+        let pos0 = map.iter().position(|i| MachineItem::Rotary(3).eq(i)).unwrap();
+        let pos1 = map.iter().position(|i| MachineItem::Rotary(11).eq(i)).unwrap();
+        let scale = ValueScale {
+            control: 0,
+            input_max: 1023,
+            output_min: 0,
+            output_max: 127,
+            curve: ValueCurve::Log,
+        };
+        let scales = [
+            ValueScale {
+                control: pos0 as u32,
+                ..scale
+            },
+            ValueScale {
+                control: pos1 as u32,
+                ..scale
+            },
+            ValueScale {
+                control: map.len() as u32,
+                ..scale
+            },
+        ];
+        unit.state_mut().apply_value_scales(&scales);
+
+        // The control for the strip of the other unit and out of map are ignored.
+        assert_eq!(unit.state().scales.len(), 1);
+        let expected = ItemValueScale {
+            input_max: 1023,
+            output_min: 0,
+            output_max: 127,
+            curve: ItemValueCurve::Log,
         };
+        assert_eq!(unit.state().find_value_scale(&MachineItem::Rotary(3)), Some(&expected));
+
+        // The scales applied before are replaced.
+        unit.state_mut().apply_value_scales(&[]);
+        assert!(unit.state().find_value_scale(&MachineItem::Rotary(3)).is_none());
+    }
+
+    #[test]
+    fn test_cascade() {
+        let router = CascadeRouter::new(2);
+        let units: Vec<TestFe8> = (0..2)
+            .map(|index| {
+                let mut unit = TestFe8::default();
+                unit.state_mut()
+                    .set_cascade(SequencerCascade::new(index, 2, router.clone()));
+                unit.initialize_message_map();
+                unit
+            })
+            .collect();
+
+        // The map is shared by the units, and includes the strips of both units.
+        assert_eq!(units[0].state().map, units[1].state().map);
+        let map = &units[0].state().map;
+        assert_eq!(map.len(), 2 * (Fe8Protocol::BOOL_ITEMS.len() + Fe8Protocol::U16_ITEMS.len()));
+        assert_eq!(units[1].state().cascade.as_ref().unwrap().offset(), 8);
+
+        // The strip of the second unit is shifted.
+        let item = units[1].state().appl_item(&MachineItem::Select(3));
+        assert_eq!(item, MachineItem::Select(11));
+        let item = units[0].state().appl_item(&MachineItem::Select(3));
+        assert_eq!(item, MachineItem::Select(3));
+        let pos0 = map.iter().position(|i| MachineItem::Rotary(7).eq(i));
+        let pos1 = map.iter().position(|i| MachineItem::Rotary(15).eq(i));
+        assert!(pos0.is_some() && pos1.is_some() && pos0 != pos1);
+
+        // The feedback from the application is routed to the unit with the strip.
+        let item = MachineItem::Mute(11);
+        assert_eq!(units[0].state().unit_item(&item), None);
+        assert_eq!(units[1].state().unit_item(&item), Some(MachineItem::Mute(3)));
+        let item = MachineItem::Rec(7);
+        assert_eq!(units[0].state().unit_item(&item), Some(MachineItem::Rec(7)));
+        assert_eq!(units[1].state().unit_item(&item), None);
+
+        // The item out of strips is not changed.
+        assert_eq!(units[1].state().appl_item(&MachineItem::Play), MachineItem::Play);
+        assert_eq!(units[1].state().unit_item(&MachineItem::Play), Some(MachineItem::Play));
+
+        // The event from the application for the strip of the second unit is routed to it.
+        let routed = Arc::new(Mutex::new(Vec::new()));
+        let queue = routed.clone();
+        units[1]
+            .state()
+            .cascade
+            .as_ref()
+            .unwrap()
+            .register_route(Arc::new(move |data| {
+                queue.lock().unwrap().push(data);
+                true
+            }));
+
+        let ev_cntr = alsaseq::EventCntr::new(1).unwrap();
+        let mut data = ev_cntr.get_ctl_data(0).unwrap();
+        let pos = map.iter().position(|i| MachineItem::Mute(11).eq(i)).unwrap();
+        data.set_param(pos as u32);
+        data.set_value(BOOL_TRUE);
+        assert!(units[0].state().route_appl_event(&data).unwrap().is_ok());
+
+        let mut data = routed.lock().unwrap().pop().unwrap();
+        assert!(units[1].state().route_appl_event(&data).is_none());
+        assert_eq!(
+            units[1].parse_appl_event(&data).unwrap(),
+            (MachineItem::Mute(3), ItemValue::Bool(true))
+        );
+
+        // The event for the strip of the unit itself is not routed.
+        let pos = map.iter().position(|i| MachineItem::Mute(3).eq(i)).unwrap();
+        data.set_param(pos as u32);
+        assert!(units[0].state().route_appl_event(&data).is_none());
+        assert_eq!(
+            units[0].parse_appl_event(&data).unwrap(),
+            (MachineItem::Mute(3), ItemValue::Bool(true))
+        );
+
+        // The unit without route is not available.
+        units[1].state().cascade.as_ref().unwrap().unregister_route();
+        let pos = map.iter().position(|i| MachineItem::Mute(11).eq(i)).unwrap();
+        data.set_param(pos as u32);
+        let err = units[0].state().route_appl_event(&data).unwrap().unwrap_err();
+        assert_eq!(err.kind::<FileError>(), Some(FileError::Nodev));
+    }
 
-        cntr.schedule_event(index as u32, value)
+    #[test]
+    fn test_cascade_indices_by_guid() {
+        let guids = [0x00022e0000000203, 0x00022e0000000101, 0x00022e0000000102];
+        assert_eq!(cascade_indices_by_guid(&guids), vec![2, 0, 1]);
+        assert_eq!(cascade_order(&[4, 5, 6], CascadeOrder::CommandLine), vec![0, 1, 2]);
     }
 }
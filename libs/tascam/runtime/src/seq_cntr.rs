@@ -20,6 +20,9 @@ impl Drop for SeqCntr {
 impl SeqCntr {
     const SEQ_PORT_NAME: &'static str = "Control Surface";
 
+    const CHANNEL_COUNT: u8 = 16;
+    const ALL_NOTES_OFF: u32 = 123;
+
     pub fn new(name: &str) -> Result<Self, Error> {
         let client = alsaseq::UserClient::new();
         client.open(0)?;
@@ -58,9 +61,9 @@ impl SeqCntr {
         Ok(())
     }
 
-    pub fn schedule_event(&mut self, param: u32, val: i32) -> Result<(), Error> {
+    pub fn schedule_event(&mut self, channel: u8, param: u32, val: i32) -> Result<(), Error> {
         let mut data = self.ev_cntr.get_ctl_data(0)?;
-        data.set_channel(0);
+        data.set_channel(channel);
         data.set_param(param);
         data.set_value(val);
         self.ev_cntr.set_ctl_data(0, &data)?;
@@ -71,4 +74,22 @@ impl SeqCntr {
 
         self.client.schedule_event(&self.ev_cntr, 1)
     }
+
+    /// Emit All Notes Off message of channel mode to all of channels.
+    pub fn schedule_all_notes_off(&mut self) -> Result<(), Error> {
+        (0..Self::CHANNEL_COUNT).try_for_each(|channel| {
+            self.schedule_event(channel, Self::ALL_NOTES_OFF, 0)
+        })
+    }
+
+    pub fn schedule_sysex(&mut self, msg: &[u8]) -> Result<(), Error> {
+        let ev_cntr = alsaseq::EventCntr::new(1)?;
+        ev_cntr.set_event_type(0, alsaseq::EventType::Sysex)?;
+        ev_cntr.set_blob_data(0, msg)?;
+
+        // Multicast to subscribers and dispatch immediately.
+        ev_cntr.set_queue_id(0, alsaseq::SpecificAddress::Subscribers.to_glib() as u8)?;
+
+        self.client.schedule_event(&ev_cntr, 1)
+    }
 }
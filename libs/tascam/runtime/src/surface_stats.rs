@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2021 Takashi Sakamoto
+
+//! Statistics of surface image decoding, for performance tuning.
+//!
+//! The counters are plain atomics, thus the cost in the path of surface event is negligible. The
+//! collection is removed at compile time when `surface-stats` feature is disabled, then the
+//! counters are always zero.
+
+#[cfg(feature = "surface-stats")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// The upper bounds of buckets for histogram of decode time in microseconds. The last bucket is
+/// for the rest.
+pub const DECODE_TIME_BOUNDS_US: [u64; 5] = [10, 50, 100, 500, 1000];
+
+const DECODE_TIME_BUCKET_COUNT: usize = DECODE_TIME_BOUNDS_US.len() + 1;
+
+#[cfg(feature = "surface-stats")]
+fn decode_time_bucket(elapsed: Duration) -> usize {
+    let us = elapsed.as_micros() as u64;
+    DECODE_TIME_BOUNDS_US
+        .iter()
+        .position(|&bound| us < bound)
+        .unwrap_or(DECODE_TIME_BOUNDS_US.len())
+}
+
+/// The counters taken at once.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SurfaceStatsSnapshot {
+    /// The number of decoded images.
+    pub images: u64,
+    /// The number of events decoded from the images.
+    pub events: u64,
+    /// The number of feedbacks to the surface, each of which issues LED writes if required.
+    pub feedbacks: u64,
+    /// The histogram of decode time.
+    pub decode_times: [u64; DECODE_TIME_BUCKET_COUNT],
+}
+
+impl std::fmt::Display for SurfaceStatsSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let bounds: Vec<String> = DECODE_TIME_BOUNDS_US.iter().map(|b| b.to_string()).collect();
+        let times: Vec<String> = self.decode_times.iter().map(|t| t.to_string()).collect();
+        write!(
+            f,
+            "{{\"images\":{},\"events\":{},\"feedbacks\":{},\"decode-time-bounds-us\":[{}],\
+             \"decode-times\":[{}]}}",
+            self.images,
+            self.events,
+            self.feedbacks,
+            bounds.join(","),
+            times.join(",")
+        )
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct SurfaceStats {
+    #[cfg(feature = "surface-stats")]
+    images: AtomicU64,
+    #[cfg(feature = "surface-stats")]
+    events: AtomicU64,
+    #[cfg(feature = "surface-stats")]
+    feedbacks: AtomicU64,
+    #[cfg(feature = "surface-stats")]
+    decode_times: [AtomicU64; DECODE_TIME_BUCKET_COUNT],
+}
+
+#[cfg(feature = "surface-stats")]
+impl SurfaceStats {
+    /// Decode the image and count it.
+    pub fn measure_decode<T, F>(&self, decode: F) -> Vec<T>
+    where
+        F: FnOnce() -> Vec<T>,
+    {
+        let start = Instant::now();
+        let events = decode();
+        self.record_decode(start.elapsed(), events.len());
+        events
+    }
+
+    fn record_decode(&self, elapsed: Duration, events: usize) {
+        self.images.fetch_add(1, Ordering::Relaxed);
+        self.events.fetch_add(events as u64, Ordering::Relaxed);
+        self.decode_times[decode_time_bucket(elapsed)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_feedback(&self) {
+        self.feedbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> SurfaceStatsSnapshot {
+        let mut decode_times = [0; DECODE_TIME_BUCKET_COUNT];
+        decode_times
+            .iter_mut()
+            .zip(self.decode_times.iter())
+            .for_each(|(t, c)| *t = c.load(Ordering::Relaxed));
+        SurfaceStatsSnapshot {
+            images: self.images.load(Ordering::Relaxed),
+            events: self.events.load(Ordering::Relaxed),
+            feedbacks: self.feedbacks.load(Ordering::Relaxed),
+            decode_times,
+        }
+    }
+}
+
+#[cfg(not(feature = "surface-stats"))]
+impl SurfaceStats {
+    #[inline(always)]
+    pub fn measure_decode<T, F>(&self, decode: F) -> Vec<T>
+    where
+        F: FnOnce() -> Vec<T>,
+    {
+        decode()
+    }
+
+    #[inline(always)]
+    pub fn record_feedback(&self) {}
+
+    pub fn snapshot(&self) -> SurfaceStatsSnapshot {
+        SurfaceStatsSnapshot::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "surface-stats")]
+    #[test]
+    fn test_decode_time_bucket() {
+        assert_eq!(decode_time_bucket(Duration::from_micros(0)), 0);
+        assert_eq!(decode_time_bucket(Duration::from_micros(10)), 1);
+        assert_eq!(decode_time_bucket(Duration::from_micros(499)), 3);
+        assert_eq!(decode_time_bucket(Duration::from_millis(1)), 5);
+        assert_eq!(decode_time_bucket(Duration::from_secs(1)), 5);
+    }
+
+    #[test]
+    fn test_snapshot_text() {
+        let snapshot = SurfaceStatsSnapshot {
+            images: 3,
+            events: 2,
+            feedbacks: 1,
+            decode_times: [3, 0, 0, 0, 0, 0],
+        };
+        assert_eq!(
+            snapshot.to_string(),
+            "{\"images\":3,\"events\":2,\"feedbacks\":1,\
+             \"decode-time-bounds-us\":[10,50,100,500,1000],\"decode-times\":[3,0,0,0,0,0]}"
+        );
+    }
+}
@@ -5,12 +5,29 @@ use dice_runtime::DiceRuntime;
 
 struct DiceServiceCmd;
 
-impl<'a> ServiceCmd<'a, u32, DiceRuntime> for DiceServiceCmd {
+const NON_DESTRUCTIVE_OPT: &str = "--non-destructive";
+
+impl<'a> ServiceCmd<'a, (u32, bool), DiceRuntime> for DiceServiceCmd {
     const CMD_NAME: &'a str = "snd-dice-ctl-service";
     const ARGS: &'a [(&'a str, &'a str)] = &[("CARD_ID", "The numeric ID of sound card")];
+    const OPTS: &'a [(&'a str, &'a str)] = &[
+        (NON_DESTRUCTIVE_OPT, "Keep router entries of models with TCD2210/TCD2220 at service start"),
+    ];
+
+    fn parse_args(args: &[String]) -> Result<(u32, bool), String> {
+        let card_id = parse_arg_as_u32(&args[0])?;
+
+        let mut non_destructive = false;
+        args[1..].iter().try_for_each(|arg| {
+            if arg == NON_DESTRUCTIVE_OPT {
+                non_destructive = true;
+                Ok(())
+            } else {
+                Err(format!("Unknown option: {}", arg))
+            }
+        })?;
 
-    fn parse_args(args: &[String]) -> Result<u32, String> {
-        parse_arg_as_u32(&args[0])
+        Ok((card_id, non_destructive))
     }
 }
 
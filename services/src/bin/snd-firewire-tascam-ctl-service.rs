@@ -5,25 +5,62 @@ use tascam_runtime::TascamRuntime;
 
 struct TascamServiceCmd;
 
-impl<'a> ServiceCmd<'a, (String, u32), TascamRuntime> for TascamServiceCmd {
+const CARD_OPT: &str = "--card";
+
+fn parse_unit_args(subsystem: &str, sysnum: &str) -> Result<(String, u32), String> {
+    match subsystem {
+        "snd" | "fw" => Ok(subsystem.to_string()),
+        _ => {
+            let msg = format!("The subsystem should be one of 'snd' and 'fw': {}", subsystem);
+            Err(msg)
+        }
+    }
+    .and_then(|subsystem| {
+        parse_arg_as_u32(sysnum)
+            .map(|sysnum| (subsystem, sysnum))
+    })
+}
+
+impl<'a> ServiceCmd<'a, Vec<(String, u32)>, TascamRuntime> for TascamServiceCmd {
     const CMD_NAME: &'a str = "snd-firewire-tascam-ctl-service";
     const ARGS: &'a [(&'a str, &'a str)] = &[
         ("SUBSYSTEM", "The name of subsystem; 'snd' or 'fw'"),
         ("SYSNUM", "The numeric ID of sound card or fw character device"),
     ];
+    const OPTS: &'a [(&'a str, &'a str)] = &[
+        (
+            "--card SUBSYSTEM:SYSNUM",
+            "The additional unit managed in the same instance. Available several times",
+        ),
+    ];
 
-    fn parse_args(args: &[String]) -> Result<(String, u32), String> {
-        match args[0].as_str() {
-            "snd" | "fw" => Ok(args[0].clone()),
-            _ => {
-                let msg = format!("The first argument should be one of 'snd' and 'fw': {}", args[0]);
-                Err(msg)
+    fn parse_args(args: &[String]) -> Result<Vec<(String, u32)>, String> {
+        let mut units = vec![parse_unit_args(&args[0], &args[1])?];
+
+        let mut iter = args[2..].iter();
+        while let Some(arg) = iter.next() {
+            if arg != CARD_OPT {
+                Err(format!("Unknown option: {}", arg))?;
             }
+
+            let unit = iter
+                .next()
+                .ok_or_else(|| format!("The value is required for {} option", CARD_OPT))
+                .and_then(|val| {
+                    let mut parts = val.splitn(2, ':');
+                    match (parts.next(), parts.next()) {
+                        (Some(subsystem), Some(sysnum)) => parse_unit_args(subsystem, sysnum),
+                        _ => Err(format!("The value should be SUBSYSTEM:SYSNUM: {}", val)),
+                    }
+                })?;
+
+            if units.contains(&unit) {
+                Err(format!("The unit is given several times: {}:{}", unit.0, unit.1))?;
+            }
+            units.push(unit);
         }
-        .and_then(|subsystem| {
-            parse_arg_as_u32(&args[1])
-                .map(|sysnum| (subsystem, sysnum))
-        })
+
+        Ok(units)
     }
 }
 
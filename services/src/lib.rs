@@ -5,8 +5,9 @@ use hinawa::{FwNodeError, SndUnitError};
 use alsactl::CardError;
 use alsaseq::UserClientError;
 
-use core::RuntimeOperation;
+use core::{config::*, config_rom::ConfigRomError, log::*, RuntimeOperation};
 
+use std::path::PathBuf;
 use std::str::FromStr;
 
 pub fn parse_arg_as_u32(arg: &str) -> Result<u32, String> {
@@ -14,32 +15,88 @@ pub fn parse_arg_as_u32(arg: &str) -> Result<u32, String> {
         .map_err(|e| format!("The first argument should be numeric number: {}, {}", e, arg))
 }
 
+const LOG_LEVEL_OPT: &str = "--log-level";
+const CONFIG_OPT: &str = "--config";
+
+// The options are available for all of service programs.
+const COMMON_OPTS: &[(&str, &str)] = &[
+    (
+        "--log-level SPEC",
+        "The level of log, like 'warn,card=debug'. Available as environment variable as well",
+    ),
+    (
+        "--config PATH",
+        "The configuration file reloaded by SIGHUP. Available as environment variable as well",
+    ),
+];
+
+// The option for configuration file is taken from the arguments, then the file is loaded and
+// applied.
+fn setup_config(args: &mut Vec<String>) -> Result<(), String> {
+    if let Some(pos) = args.iter().position(|arg| arg == CONFIG_OPT) {
+        if pos + 1 >= args.len() {
+            Err(format!("The value is required for {} option", CONFIG_OPT))?;
+        }
+        let path = args.remove(pos + 1);
+        args.remove(pos);
+        set_config_path(Some(PathBuf::from(path)));
+    }
+
+    load_config().map(|config| config.into_iter().for_each(apply_config))
+}
+
+// The option for log level is taken from the arguments, then the filter of log is configured. The
+// option takes precedence over the configuration file, and the configuration file takes precedence
+// over the environment variable. The precedence is kept at reload of the configuration file.
+fn setup_log(args: &mut Vec<String>) -> Result<(), String> {
+    set_log_filter_from_env()
+        .map_err(|e| format!("Invalid value of {}: {}", LOG_LEVEL_ENV_NAME, e))?;
+    setup_config(args)?;
+
+    match args.iter().position(|arg| arg == LOG_LEVEL_OPT) {
+        Some(pos) => {
+            if pos + 1 >= args.len() {
+                Err(format!("The value is required for {} option", LOG_LEVEL_OPT))?;
+            }
+            let spec = args.remove(pos + 1);
+            args.remove(pos);
+            LogFilter::parse(&spec).map(set_cmdline_log_filter)
+        }
+        None => Ok(()),
+    }
+}
+
 pub trait ServiceCmd<'a, T, R> : Sized
     where R: RuntimeOperation<T>,
 {
     const CMD_NAME: &'a str;
     const ARGS: &'a [(&'a str, &'a str)];
+    const OPTS: &'a [(&'a str, &'a str)] = &[];
     fn parse_args(args: &[String]) -> Result<T, String>;
 
     fn print_help() {
         println!("
 Usage:
-  {}{}
+  {}{}{}
 
   where",
                  Self::CMD_NAME,
                  &Self::ARGS.iter().fold(String::new(), |label, entry| label + " " + entry.0),
+                 &Self::OPTS
+                    .iter()
+                    .chain(COMMON_OPTS.iter())
+                    .fold(String::new(), |label, entry| label + " [" + entry.0 + "]"),
                  );
 
-        Self::ARGS.iter().for_each(|entry| {
+        Self::ARGS.iter().chain(Self::OPTS.iter()).chain(COMMON_OPTS.iter()).for_each(|entry| {
             println!("    {}: {}", entry.0, entry.1);
         })
     }
 
     fn run() {
-        let args: Vec<String> = std::env::args().skip(1).collect();
-        let code =
-            (if args.len() < Self::ARGS.len() {
+        let mut args: Vec<String> = std::env::args().skip(1).collect();
+        let code = setup_log(&mut args)
+            .and_then(|_| if args.len() < Self::ARGS.len() {
                 let msg = if Self::ARGS.len() == 1 {
                     format!("1 argument is required at least")
                 } else {
@@ -78,6 +135,14 @@ Usage:
                             })
                         } else if e.is::<UserClientError>() {
                             ("ALSA Sequencer operation error", "")
+                        } else if let Some(error) = e.kind::<ConfigRomError>() {
+                            ("Configuration ROM error", match error {
+                                ConfigRomError::UnreadableRom => "Unreadable content",
+                                ConfigRomError::VendorMismatch => "Device from the other vendor",
+                                ConfigRomError::UnknownModel => "Unknown model",
+                                ConfigRomError::MalformedEntry => "Unexpected layout",
+                                _ => "",
+                            })
                         } else {
                             ("Unknown domain error", "")
                         };